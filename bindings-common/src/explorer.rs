@@ -0,0 +1,261 @@
+use anyhow::Result;
+use ethers::core::types::BlockNumber;
+use ethers::etherscan::account::{
+    ERC20TokenTransferEvent, ERC721TokenTransferEvent, NormalTransaction,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::timestamp;
+use crate::value::{normalize_value, token_value, TokenStandard, ValueKind, NATIVE_DECIMALS};
+
+/// Raw transaction details (the same as `RawTxDetail` of the C++ bindings)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RawTxDetail {
+    pub hash: String,
+    pub to_address: String,
+    pub from_address: String,
+    pub value: String,
+    pub raw_value: String,
+    pub formatted_value: String,
+    pub value_kind: ValueKind,
+    pub block_no: u64,
+    pub timestamp: String,
+    pub timestamp_epoch: u64,
+    pub timestamp_iso: String,
+    pub contract_address: String,
+    pub display_name: String,
+}
+
+/// Raw token transfer details (the same as `RawTokenTxDetail` of the C++ bindings)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct RawTokenTxDetail {
+    pub hash: String,
+    pub to_address: String,
+    pub from_address: String,
+    pub value: String,
+    pub raw_value: String,
+    pub formatted_value: String,
+    pub value_kind: ValueKind,
+    pub block_no: u64,
+    pub timestamp: String,
+    pub timestamp_epoch: u64,
+    pub timestamp_iso: String,
+    pub contract_address: String,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub token_decimal: String,
+    pub token_id: String,
+    pub display_name: String,
+}
+
+/// Token transfer from BlockScout API (Etherscan-compatible `tokentx`)
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawBlockScoutTransfer {
+    pub block_hash: String,
+    pub block_number: String,
+    pub confirmations: String,
+    pub contract_address: String,
+    pub cumulative_gas_used: String,
+    pub from: String,
+    pub gas: String,
+    pub gas_price: String,
+    pub gas_used: String,
+    pub hash: String,
+    pub input: String,
+    pub log_index: String,
+    pub nonce: String,
+    pub time_stamp: String,
+    pub to: String,
+    pub token_decimal: String,
+    /// only in NFT transfers
+    #[serde(default, rename = "tokenID")]
+    pub token_id: String,
+    /// "ERC-20", "ERC-721" or "ERC-1155" (only in the newer BlockScout releases)
+    #[serde(default)]
+    pub token_type: String,
+    pub token_name: String,
+    pub token_symbol: String,
+    pub transaction_index: String,
+    pub value: String,
+}
+
+/// the block number of an explorer result (0 if it's pending)
+fn block_no(block_number: &BlockNumber) -> u64 {
+    match block_number {
+        BlockNumber::Number(block_no) => block_no.0[0],
+        _ => 0,
+    }
+}
+
+impl TryFrom<&RawBlockScoutTransfer> for RawTokenTxDetail {
+    type Error = anyhow::Error;
+
+    fn try_from(tx: &RawBlockScoutTransfer) -> Result<Self, Self::Error> {
+        let block_no = tx.block_number.parse::<u64>()?;
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&tx.time_stamp);
+        let (raw_value, formatted_value, value_kind) = token_value(
+            &tx.value,
+            &tx.token_decimal,
+            TokenStandard::of(&tx.token_type, &tx.token_id),
+        );
+        Ok(Self {
+            hash: tx.hash.clone(),
+            to_address: tx.to.clone(),
+            from_address: tx.from.clone(),
+            value: tx.value.clone(),
+            raw_value,
+            formatted_value,
+            value_kind,
+            block_no,
+            timestamp: tx.time_stamp.clone(),
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address: tx.contract_address.clone(),
+            token_name: tx.token_name.clone(),
+            token_symbol: tx.token_symbol.clone(),
+            token_decimal: tx.token_decimal.clone(),
+            token_id: tx.token_id.clone(),
+            display_name: String::default(),
+        })
+    }
+}
+
+impl From<&NormalTransaction> for RawTxDetail {
+    fn from(tx: &NormalTransaction) -> Self {
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&tx.time_stamp);
+        let (raw_value, formatted_value) =
+            normalize_value(&tx.value.to_string(), Some(NATIVE_DECIMALS));
+        RawTxDetail {
+            hash: tx
+                .hash
+                .value()
+                .map(|x| format!("{x:?}"))
+                .unwrap_or_default(),
+            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
+            from_address: tx
+                .from
+                .value()
+                .map(|x| format!("{x:?}"))
+                .unwrap_or_default(),
+            value: tx.value.to_string(),
+            raw_value,
+            formatted_value,
+            value_kind: ValueKind::Native,
+            block_no: block_no(&tx.block_number),
+            timestamp: tx.time_stamp.clone(),
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address: format!("{:?}", tx.contract_address.unwrap_or_default()),
+            display_name: String::default(),
+        }
+    }
+}
+
+impl From<&ERC20TokenTransferEvent> for RawTokenTxDetail {
+    fn from(tx: &ERC20TokenTransferEvent) -> Self {
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&tx.time_stamp);
+        let (raw_value, formatted_value, value_kind) = token_value(
+            &tx.value.to_string(),
+            &tx.token_decimal,
+            TokenStandard::Erc20,
+        );
+        RawTokenTxDetail {
+            hash: format!("{:?}", tx.hash),
+            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
+            from_address: format!("{:?}", tx.from),
+            value: tx.value.to_string(),
+            raw_value,
+            formatted_value,
+            value_kind,
+            block_no: block_no(&tx.block_number),
+            timestamp: tx.time_stamp.clone(),
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address: format!("{:?}", tx.contract_address),
+            token_name: tx.token_name.clone(),
+            token_symbol: tx.token_symbol.clone(),
+            token_decimal: tx.token_decimal.clone(),
+            token_id: String::default(),
+            display_name: String::default(),
+        }
+    }
+}
+
+impl From<&ERC721TokenTransferEvent> for RawTokenTxDetail {
+    fn from(tx: &ERC721TokenTransferEvent) -> Self {
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&tx.time_stamp);
+        RawTokenTxDetail {
+            hash: format!("{:?}", tx.hash),
+            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
+            from_address: format!("{:?}", tx.from),
+            // a single token is transferred, its id is in `token_id`
+            value: "1".to_owned(),
+            raw_value: "1".to_owned(),
+            formatted_value: "1".to_owned(),
+            value_kind: ValueKind::NonFungible,
+            block_no: block_no(&tx.block_number),
+            timestamp: tx.time_stamp.clone(),
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address: format!("{:?}", tx.contract_address),
+            token_name: tx.token_name.clone(),
+            token_symbol: tx.token_symbol.clone(),
+            token_decimal: tx.token_decimal.clone(),
+            token_id: tx.token_id.clone(),
+            display_name: String::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_blockscout_transfer() {
+        let transfer: RawBlockScoutTransfer = serde_json::from_str(
+            r#"{
+              "value": "1500000",
+              "blockHash": "0x1456b7934898b7c735967e849effc4dc45e84ff32c6c2e130d572cb9589ca652",
+              "blockNumber": "2088372",
+              "confirmations": "537920",
+              "contractAddress": "0x715b4d660148c477e03358f8b0315ed4088fe89a",
+              "cumulativeGasUsed": "31760308",
+              "from": "0x9ad08de843158b0a4f8efdae6ea49caf77bbf13f",
+              "gas": "145930",
+              "gasPrice": "2021527882398",
+              "gasUsed": "97287",
+              "hash": "0x0890c4dce61da8713db5844fa0ae0aa73b74ea6ccfa91c90065ae80471cd908c",
+              "input": "0x",
+              "logIndex": "104",
+              "nonce": "13",
+              "timeStamp": "1646318156",
+              "to": "0x841a15d12aec9c6039fd132c2fbff112ed355700",
+              "tokenDecimal": "6",
+              "tokenName": "USD Coin",
+              "tokenSymbol": "USDC",
+              "transactionIndex": "11"
+            }"#,
+        )
+        .expect("parse");
+        let detail = RawTokenTxDetail::try_from(&transfer).unwrap();
+        assert_eq!(detail.block_no, 2088372);
+        assert_eq!(detail.formatted_value, "1.5");
+        assert_eq!(detail.value_kind, ValueKind::Fungible);
+        assert_eq!(detail.timestamp_epoch, 1646318156);
+        assert_eq!(detail.timestamp_iso, "2022-03-03T14:35:56Z");
+        assert!(detail.token_id.is_empty());
+
+        // 3 copies of the ERC-1155 token 3
+        let transfer = RawBlockScoutTransfer {
+            value: "3".to_owned(),
+            token_id: "3".to_owned(),
+            token_type: "ERC-1155".to_owned(),
+            ..transfer
+        };
+        let detail = RawTokenTxDetail::try_from(&transfer).unwrap();
+        assert_eq!(detail.raw_value, "3");
+        assert_eq!(detail.value_kind, ValueKind::NonFungible);
+    }
+}
@@ -0,0 +1,11 @@
+//! The conversions shared by the C++ bindings and the WebAssembly bindings,
+//! so that both of them return the same results
+
+/// the explorer (Cronoscan/Etherscan and BlockScout) results and their conversions
+pub mod explorer;
+/// the normalized timestamps of the explorers (unix and ISO-8601)
+pub mod timestamp;
+/// the fees of the unsigned transactions
+pub mod tx;
+/// the normalized values of the transactions and the token transfers
+pub mod value;
@@ -0,0 +1,172 @@
+/// the timestamps above it are in milliseconds (it's in the year 5138 in seconds)
+const MAX_SECONDS: u64 = 100_000_000_000;
+
+/// the civil date (year, month, day) of the days since 1970-01-01 (H. Hinnant's algorithm)
+fn civil_date(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// the days since 1970-01-01 of the civil date (H. Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// the unix timestamp of an ISO-8601 date and time
+/// ("YYYY-MM-DD[T ]HH:MM:SS[.fraction](Z|±HH:MM)"; UTC if there's no offset)
+fn parse_iso8601(timestamp: &str) -> Option<u64> {
+    let number = |s: &str| {
+        s.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| s.parse::<i64>().ok())
+            .flatten()
+    };
+    let (date, time) = timestamp.split_at_checked(10)?;
+    let mut date = date.split('-');
+    let (year, month, day) = (
+        number(date.next()?)?,
+        number(date.next()?)?,
+        number(date.next()?)?,
+    );
+    let time = time.strip_prefix(['T', 't', ' '])?;
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => time.split_at(i),
+        None => (time, ""),
+    };
+    let offset = match offset {
+        "" | "Z" | "z" => 0,
+        offset => {
+            let (sign, offset) = offset.split_at(1);
+            let (hours, minutes) = offset
+                .split_once(':')
+                .or_else(|| offset.split_at_checked(2))?;
+            let offset = number(hours)? * 3600 + number(minutes)? * 60;
+            if sign == "-" {
+                -offset
+            } else {
+                offset
+            }
+        }
+    };
+    let time = time.split('.').next()?;
+    let mut time = time.split(':');
+    let (hour, minute, second) = (
+        number(time.next()?)?,
+        number(time.next()?)?,
+        number(time.next()?)?,
+    );
+    if !(1..=12).contains(&month) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // the day is in the month (e.g. not February 30 or 29 in a common year)
+    let days = days_from_civil(year, month, day);
+    if day < 1 || civil_date(days) != (year, month, day) {
+        return None;
+    }
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok()
+}
+
+/// the unix timestamp (in seconds) of the explorer's timestamp: the seconds or
+/// the milliseconds in decimal or hexadecimal, or an ISO-8601 date and time
+pub fn parse_epoch(timestamp: &str) -> Option<u64> {
+    let timestamp = timestamp.trim();
+    let number = match timestamp.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => timestamp.parse::<u64>().ok(),
+    };
+    match number {
+        Some(millis) if millis >= MAX_SECONDS => Some(millis / 1000),
+        Some(seconds) => Some(seconds),
+        None => parse_iso8601(timestamp),
+    }
+}
+
+/// the ISO-8601 date and time in UTC ("YYYY-MM-DDTHH:MM:SSZ") of the unix timestamp
+pub fn to_iso8601(timestamp: u64) -> String {
+    let (year, month, day) = civil_date((timestamp / 86400) as i64);
+    let secs = timestamp % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// the unix timestamp and the ISO-8601 date and time of the explorer's timestamp
+/// (0 and an empty string if it's unknown)
+pub fn normalize(timestamp: &str) -> (u64, String) {
+    match parse_epoch(timestamp) {
+        Some(epoch) => (epoch, to_iso8601(epoch)),
+        None => (0, String::default()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_epoch() {
+        for timestamp in [
+            "1646318156",
+            " 1646318156 ",
+            "0x6220d24c",
+            "1646318156789",
+            "2022-03-03T14:35:56Z",
+            "2022-03-03T14:35:56.123456Z",
+            "2022-03-03 14:35:56",
+            "2022-03-03T15:35:56+01:00",
+            "2022-03-03T09:35:56-0500",
+        ] {
+            assert_eq!(parse_epoch(timestamp), Some(1646318156), "{timestamp}");
+        }
+        for timestamp in [
+            "",
+            "pending",
+            "2022-13-03T14:35:56Z",
+            "2022-02-30T14:35:56Z",
+            "2022-02-29T14:35:56Z",
+            "2022-04-31T14:35:56Z",
+            "2022-03-00T14:35:56Z",
+            "2022-03-32T14:35:56Z",
+            "1969-12-31T23:59:59Z",
+        ] {
+            assert_eq!(parse_epoch(timestamp), None, "{timestamp}");
+        }
+    }
+
+    #[test]
+    fn test_normalize_timestamp() {
+        assert_eq!(
+            normalize("1646318156"),
+            (1646318156, "2022-03-03T14:35:56Z".to_owned())
+        );
+        assert_eq!(to_iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(to_iso8601(951782400), "2000-02-29T00:00:00Z");
+        for days in [-1, 0, 11016, 19127, 2932896] {
+            let (year, month, day) = civil_date(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(normalize("pending"), (0, String::default()));
+        // the leap days
+        assert_eq!(parse_epoch("2000-02-29T00:00:00Z"), Some(951782400));
+        assert_eq!(parse_epoch("2024-02-29T00:00:00Z"), Some(1709164800));
+    }
+}
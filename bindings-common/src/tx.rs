@@ -0,0 +1,62 @@
+use anyhow::Result;
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::U256;
+
+/// the EIP-1559 fees (max fee per gas, max priority fee per gas) of the decimal strings,
+/// each of them falls back to `gas_price` if not set (empty)
+pub fn eip1559_fees(
+    gas_price: &str,
+    max_fee_per_gas: &str,
+    max_priority_fee_per_gas: &str,
+) -> Result<(Option<U256>, Option<U256>)> {
+    let fee = |value: &str| -> Result<Option<U256>> {
+        let value = if value.is_empty() { gas_price } else { value };
+        if value.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(U256::from_dec_str(value)?))
+        }
+    };
+    Ok((fee(max_fee_per_gas)?, fee(max_priority_fee_per_gas)?))
+}
+
+/// sets the fees of the transaction (the max fee is used as the gas price of legacy transactions)
+pub fn set_fees(
+    typedtx: &mut TypedTransaction,
+    (max_fee_per_gas, max_priority_fee_per_gas): (Option<U256>, Option<U256>),
+) {
+    match typedtx {
+        TypedTransaction::Eip1559(tx) => {
+            if max_fee_per_gas.is_some() {
+                tx.max_fee_per_gas = max_fee_per_gas;
+            }
+            if max_priority_fee_per_gas.is_some() {
+                tx.max_priority_fee_per_gas = max_priority_fee_per_gas;
+            }
+        }
+        _ => {
+            if let Some(gas_price) = max_fee_per_gas {
+                typedtx.set_gas_price(gas_price);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_eip1559_fees() {
+        assert_eq!(
+            eip1559_fees("5", "", "2").unwrap(),
+            (Some(5.into()), Some(2.into()))
+        );
+        assert_eq!(eip1559_fees("", "", "").unwrap(), (None, None));
+        assert!(eip1559_fees("", "0x1", "").is_err());
+
+        let mut legacy = TypedTransaction::Legacy(Default::default());
+        set_fees(&mut legacy, (Some(7.into()), Some(1.into())));
+        assert_eq!(legacy.gas_price(), Some(7.into()));
+    }
+}
@@ -0,0 +1,173 @@
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+/// the largest power of ten in a U256 is 10^77
+const MAX_DECIMALS: u32 = 77;
+/// the decimals of the native coin (e.g. CRO on Cronos)
+pub const NATIVE_DECIMALS: u32 = 18;
+
+/// What the value of a transaction or a token transfer is
+/// (the same as `ValueKind` of the C++ bindings)
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// the decimals of the value are unknown
+    #[default]
+    Unknown,
+    /// the amount of the native coin (in wei)
+    Native,
+    /// the amount of the ERC-20 tokens (in the base units of `token_decimal`)
+    Fungible,
+    /// the number of the NFTs (their id is in `token_id`)
+    NonFungible,
+}
+
+/// The token standard of a transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenStandard {
+    /// the decimals decide if the value is an amount of tokens
+    Unknown,
+    Erc20,
+    /// a single NFT (the explorers may return its id as the value)
+    Erc721,
+    /// the value is the number of the copies of the NFT
+    Erc1155,
+}
+
+impl TokenStandard {
+    /// the standard of the explorer's token type ("ERC-20", "ERC-721" or "ERC-1155");
+    /// without it, the transfers with a token id are ERC-721 ones (the explorers
+    /// not returning the type don't list the ERC-1155 transfers)
+    pub fn of(token_type: &str, token_id: &str) -> Self {
+        match token_type.trim().to_uppercase().replace('-', "").as_str() {
+            "ERC20" => Self::Erc20,
+            "ERC721" => Self::Erc721,
+            "ERC1155" => Self::Erc1155,
+            _ if !token_id.is_empty() => Self::Erc721,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// the amount of the base units (decimal or 0x-prefixed hexadecimal)
+fn parse_raw_amount(amount: &str) -> Option<U256> {
+    let amount = amount.trim();
+    match amount.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(amount).ok(),
+    }
+}
+
+/// the decimal digits of the base units as the tokens with all their fraction digits
+/// (without the trailing zeros)
+fn format_units(digits: &str, decimals: u32) -> String {
+    let digits = format!("{digits:0>width$}", width = decimals as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_owned()
+    } else {
+        format!("{integer}.{fraction}")
+    }
+}
+
+/// the value of a transaction or a transfer in the base units (in decimal) and in the tokens
+/// with all their fraction digits (the base units if the decimals are unknown or invalid;
+/// the value as is if it isn't a number)
+pub fn normalize_value(value: &str, decimals: Option<u32>) -> (String, String) {
+    let Some(raw_value) = parse_raw_amount(value).map(|value| value.to_string()) else {
+        return (value.to_owned(), value.to_owned());
+    };
+    let formatted_value = match decimals {
+        Some(decimals) if decimals <= MAX_DECIMALS => format_units(&raw_value, decimals),
+        _ => raw_value.clone(),
+    };
+    (raw_value, formatted_value)
+}
+
+/// the raw value, the formatted value and the kind of the value of a token transfer
+/// of the standard: the number of the NFTs, or the tokens if their decimals are known
+pub fn token_value(
+    value: &str,
+    token_decimal: &str,
+    standard: TokenStandard,
+) -> (String, String, ValueKind) {
+    let count = match standard {
+        TokenStandard::Erc721 => Some("1"),
+        TokenStandard::Erc1155 => Some(value),
+        TokenStandard::Erc20 | TokenStandard::Unknown => None,
+    };
+    if let Some(count) = count {
+        let (raw_value, formatted_value) = normalize_value(count, Some(0));
+        return (raw_value, formatted_value, ValueKind::NonFungible);
+    }
+    match token_decimal.trim().parse::<u32>() {
+        Ok(decimals) => {
+            let (raw_value, formatted_value) = normalize_value(value, Some(decimals));
+            (raw_value, formatted_value, ValueKind::Fungible)
+        }
+        Err(_) => {
+            let (raw_value, formatted_value) = normalize_value(value, None);
+            (raw_value, formatted_value, ValueKind::Unknown)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_value() {
+        assert_eq!(
+            normalize_value("0x1bc16d674ec80000", Some(NATIVE_DECIMALS)),
+            ("2000000000000000000".to_owned(), "2".to_owned())
+        );
+        assert_eq!(
+            normalize_value("1500000", Some(6)),
+            ("1500000".to_owned(), "1.5".to_owned())
+        );
+        assert_eq!(
+            normalize_value("1", Some(NATIVE_DECIMALS)).1,
+            "0.000000000000000001"
+        );
+        assert_eq!(
+            normalize_value("1234", None),
+            ("1234".to_owned(), "1234".to_owned())
+        );
+        assert_eq!(normalize_value("1234", Some(78)).1, "1234");
+        assert_eq!(
+            normalize_value("pending", Some(18)),
+            ("pending".to_owned(), "pending".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_token_value() {
+        assert_eq!(
+            token_value("1500000", "6", TokenStandard::of("ERC-20", "")),
+            ("1500000".to_owned(), "1.5".to_owned(), ValueKind::Fungible)
+        );
+        // the value of an ERC-721 transfer may be its token id
+        assert_eq!(
+            token_value("2101", "", TokenStandard::of("ERC-721", "2101")),
+            ("1".to_owned(), "1".to_owned(), ValueKind::NonFungible)
+        );
+        assert_eq!(
+            token_value("2101", "", TokenStandard::of("", "2101")),
+            ("1".to_owned(), "1".to_owned(), ValueKind::NonFungible)
+        );
+        // the copies of an ERC-1155 token (even as many as its id)
+        assert_eq!(
+            token_value("3", "", TokenStandard::of("ERC-1155", "2101")),
+            ("3".to_owned(), "3".to_owned(), ValueKind::NonFungible)
+        );
+        assert_eq!(
+            token_value("3", "", TokenStandard::of("ERC-1155", "3")),
+            ("3".to_owned(), "3".to_owned(), ValueKind::NonFungible)
+        );
+        assert_eq!(
+            token_value("0x10", "", TokenStandard::of("", "")),
+            ("16".to_owned(), "16".to_owned(), ValueKind::Unknown)
+        );
+    }
+}
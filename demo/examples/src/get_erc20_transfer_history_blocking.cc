@@ -21,11 +21,11 @@ int main(int argc, char *argv[]) {
     // Get a list of "CRC20 - Token Transfer Events" by Address
     // Returns up to a maximum of the last 10000 transactions only
     // https://cronoscan.com/tokentxns?a=0xa9b34a4b568e640d5e5d1e6e13101025e1262864
-    rust::Vec<RawTxDetail> erc20_txs = get_erc20_transfer_history_blocking(
+    rust::Vec<RawTokenTxDetail> erc20_txs = get_erc20_transfer_history_blocking(
         "0xa9b34a4b568e640d5e5d1e6e13101025e1262864", "",
         QueryOption::ByAddress, CRONOSCAN_API_KEY);
 
-    for (const RawTxDetail &tx : erc20_txs) {
+    for (const RawTokenTxDetail &tx : erc20_txs) {
         std::cout << "hash: " << tx.hash << " ";
         std::cout << "to: " << tx.to_address << " ";
         std::cout << "from: " << tx.from_address << " ";
@@ -46,7 +46,7 @@ int main(int argc, char *argv[]) {
         "0x2D03bECE6747ADC00E1a131BBA1469C15fD11e03",
         QueryOption::ByAddressAndContract, CRONOSCAN_API_KEY);
 
-    for (const RawTxDetail &tx : erc20_txs) {
+    for (const RawTokenTxDetail &tx : erc20_txs) {
         std::cout << "hash: " << tx.hash << " ";
         std::cout << "to: " << tx.to_address << " ";
         std::cout << "from: " << tx.from_address << " ";
@@ -21,14 +21,14 @@ int main(int argc, char *argv[]) {
     // Get a list of "ERC721 - Token Transfer Events" by Address
     // Returns up to a maximum of the last 10000 transactions only
     // https://cronoscan.com/tokentxns-nft?a=0x668f126b87936df4f9a98f18c44eb73868fffea0
-    rust::Vec<RawTxDetail> erc721_txs = get_erc721_transfer_history_blocking(
+    rust::Vec<RawTokenTxDetail> erc721_txs = get_erc721_transfer_history_blocking(
         "0x668f126b87936df4f9a98f18c44eb73868fffea0", "",
         QueryOption::ByAddress, CRONOSCAN_API_KEY);
-    for (const RawTxDetail &tx : erc721_txs) {
+    for (const RawTokenTxDetail &tx : erc721_txs) {
         std::cout << "hash: " << tx.hash << " ";
         std::cout << "to: " << tx.to_address << " ";
         std::cout << "from: " << tx.from_address << " ";
-        std::cout << "TokenID:" << tx.value << " ";
+        std::cout << "TokenID:" << tx.token_id << " ";
         std::cout << "block_no: " << tx.block_no << " ";
         std::cout << "timestamp: " << tx.timestamp << " ";
         std::cout << "contract: " << tx.contract_address << " " << std::endl;
@@ -44,11 +44,11 @@ int main(int argc, char *argv[]) {
         "0x668f126b87936df4f9a98f18c44eb73868fffea0",
         "0x562F021423D75A1636DB5bE1C4D99Bc005ccebFe",
         QueryOption::ByAddressAndContract, CRONOSCAN_API_KEY);
-    for (const RawTxDetail &tx : erc721_txs) {
+    for (const RawTokenTxDetail &tx : erc721_txs) {
         std::cout << "hash: " << tx.hash << " ";
         std::cout << "to: " << tx.to_address << " ";
         std::cout << "from: " << tx.from_address << " ";
-        std::cout << "TokenID:" << tx.value << " ";
+        std::cout << "TokenID:" << tx.token_id << " ";
         std::cout << "block_no: " << tx.block_no << " ";
         std::cout << "timestamp: " << tx.timestamp << " ";
         std::cout << "contract: " << tx.contract_address << " " << std::endl;
@@ -63,11 +63,11 @@ int main(int argc, char *argv[]) {
     erc721_txs = get_erc721_transfer_history_blocking(
         "", "0x18b73D1f9e2d97057deC3f8D6ea9e30FCADB54D7",
         QueryOption::ByContract, CRONOSCAN_API_KEY);
-    for (const RawTxDetail &tx : erc721_txs) {
+    for (const RawTokenTxDetail &tx : erc721_txs) {
         std::cout << "hash: " << tx.hash << " ";
         std::cout << "to: " << tx.to_address << " ";
         std::cout << "from: " << tx.from_address << " ";
-        std::cout << "TokenID:" << tx.value << " ";
+        std::cout << "TokenID:" << tx.token_id << " ";
         std::cout << "block_no: " << tx.block_no << " ";
         std::cout << "timestamp: " << tx.timestamp << " ";
         std::cout << "contract: " << tx.contract_address << " " << std::endl;
@@ -58,12 +58,12 @@ void test_blackscout_cronoscan() {
             cout << ptr->contract_address << " " << endl;
         }
 
-        Vec<RawTxDetail> erc20_txs = get_erc20_transfer_history_blocking(
+        Vec<RawTokenTxDetail> erc20_txs = get_erc20_transfer_history_blocking(
             "0xa9b34a4b568e640d5e5d1e6e13101025e1262864",
             "0x66e428c3f67a68878562e79A0234c1F83c208770",
             QueryOption::ByAddressAndContract, CRONOSCAN_API_KEY);
 
-        for (Vec<RawTxDetail>::iterator ptr = erc20_txs.begin();
+        for (Vec<RawTokenTxDetail>::iterator ptr = erc20_txs.begin();
              ptr < erc20_txs.end(); ptr++) {
             cout << ptr->hash << " ";
             cout << ptr->to_address << " ";
@@ -71,15 +71,16 @@ void test_blackscout_cronoscan() {
             cout << ptr->value << " ";
             cout << ptr->block_no << " ";
             cout << ptr->timestamp << " ";
-            cout << ptr->contract_address << " " << endl;
+            cout << ptr->contract_address << " ";
+            cout << ptr->token_symbol << " " << endl;
         }
 
-        Vec<RawTxDetail> erc721_txs = get_erc721_transfer_history_blocking(
+        Vec<RawTokenTxDetail> erc721_txs = get_erc721_transfer_history_blocking(
             "0x668f126b87936df4f9a98f18c44eb73868fffea0",
             "0xbd6b9a1A0477d64E99F660b7b7C205f4604E4Ff3",
             QueryOption::ByContract, CRONOSCAN_API_KEY);
 
-        for (Vec<RawTxDetail>::iterator ptr = erc721_txs.begin();
+        for (Vec<RawTokenTxDetail>::iterator ptr = erc721_txs.begin();
              ptr < erc721_txs.end(); ptr++) {
             cout << ptr->hash << " ";
             cout << ptr->to_address << " ";
@@ -106,11 +107,11 @@ void test_blackscout_cronoscan() {
         cout << ptr->token_type << endl;
     }
 
-    Vec<RawTxDetail> token_transfer_txs = get_token_transfers_blocking(
+    Vec<RawTokenTxDetail> token_transfer_txs = get_token_transfers_blocking(
         "https://cronos.org/explorer/testnet3/api",
         "0x841a15D12aEc9c6039FD132c2FbFF112eD355700", "",
         QueryOption::ByAddress);
-    for (Vec<RawTxDetail>::iterator ptr = token_transfer_txs.begin();
+    for (Vec<RawTokenTxDetail>::iterator ptr = token_transfer_txs.begin();
          ptr < token_transfer_txs.end(); ptr++) {
         cout << ptr->hash << " ";
         cout << ptr->to_address << " ";
@@ -171,6 +172,7 @@ class UserWalletConnectCallback : public WalletConnectCallback {
     void onDisconnected(const WalletConnectSessionInfo &sessioninfo) const;
     void onConnecting(const WalletConnectSessionInfo &sessioninfo) const;
     void onUpdated(const WalletConnectSessionInfo &sessioninfo) const;
+    void onError(rust::Str message) const;
 };
 void print_session(const WalletConnectSessionInfo &sessioninfo) {
     std::cout << "connected: " << sessioninfo.connected << std::endl;
@@ -209,6 +211,9 @@ void UserWalletConnectCallback::onUpdated(
     std::cout << "user c++ onUpdated" << std::endl;
     print_session(sessioninfo);
 }
+void UserWalletConnectCallback::onError(rust::Str message) const {
+    std::cout << "user c++ onError: " << message << std::endl;
+}
 
 void test_wallet_connect() {
     std::string mycronosrpc = getenv("CRONOSRPC");
@@ -258,16 +263,21 @@ void test_wallet_connect() {
             info.to = toaddress;
             info.common.gas_limit = "21000"; // gas limit
             info.common.gas_price = "10000"; // gas price
+            // optional EIP-1559 fees (gas price is used if empty)
+            info.common.max_fee_per_gas = "10000";
+            info.common.max_priority_fee_per_gas = "1000";
             info.value = "100000000000000";  // 0.0001 eth
             info.data = Vec<uint8_t>();
             info.common.nonce = mynonce;
             info.common.chainid = 1;
 
-            Vec<uint8_t> rawtx = client->sign_eip155_transaction_blocking(
+            SignedTxResult signedtx = client->sign_eip155_transaction_blocking(
                 info, result.addresses[0].address);
+            cout << "signed transaction_hash="
+                 << bytes_to_hex_string(signedtx.tx_hash).c_str() << endl;
 
             auto receipt = org::defi_wallet_core::broadcast_eth_signed_raw_tx(
-                rawtx, mycronosrpc, 3000);
+                signedtx.raw_tx, mycronosrpc, 3000);
             cout << "transaction_hash="
                  << bytes_to_hex_string(receipt.transaction_hash).c_str()
                  << endl;
@@ -303,11 +313,11 @@ void test_wallet_connect() {
             common.chainid = 1;
             common.web3api_url = mycronosrpc.c_str();
 
-            rust::Vec<uint8_t> rawtx = client->sign_contract_transaction(
+            SignedTxResult signedtx = client->sign_contract_transaction(
                 contract_action, common, result.addresses[0].address);
 
             auto receipt = org::defi_wallet_core::broadcast_eth_signed_raw_tx(
-                rawtx, mycronosrpc, 3000);
+                signedtx.raw_tx, mycronosrpc, 3000);
             cout << "transaction_hash="
                  << bytes_to_hex_string(receipt.transaction_hash).c_str()
                  << endl;
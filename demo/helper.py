@@ -17,6 +17,19 @@ SOURCES = [
     "../extra-cpp-bindings/src/pay.cc",
     "../extra-cpp-bindings/include/walletconnectcallback.h",
     "../extra-cpp-bindings/src/walletconnectcallback.cc",
+    "../extra-cpp-bindings/include/walletconnect2.h",
+    "../extra-cpp-bindings/include/addresswatcher.h",
+    "../extra-cpp-bindings/include/logscanner.h",
+    "../extra-cpp-bindings/include/rateservice.h",
+    "../extra-cpp-bindings/include/txtracker.h",
+    "../extra-cpp-bindings/include/externalsigner.h",
+    "../extra-cpp-bindings/include/mpc.h",
+    "../extra-cpp-bindings/include/snapshot.h",
+    "../extra-cpp-bindings/include/policy.h",
+    "../extra-cpp-bindings/include/netusage.h",
+    "../extra-cpp-bindings/include/runtime.h",
+    "../extra-cpp-bindings/include/play_sdk_c.h",
+    "../extra-cpp-bindings/include/play_sdk_unity.cs",
     "../defi-wallet-core-rs/bindings/cpp/src/nft.cc",
     "../defi-wallet-core-rs/bindings/cpp/include/nft.h",
     "../defi-wallet-core-rs/bindings/cpp/src/android.cc",
@@ -29,6 +42,17 @@ INITIAL_INCLUDES = [
     '#include "extra-cpp-bindings/src/lib.rs.h"',
     '#include "extra-cpp-bindings/include/pay.h"',
     '#include "extra-cpp-bindings/include/walletconnectcallback.h"',
+    '#include "extra-cpp-bindings/include/walletconnect2.h"',
+    '#include "extra-cpp-bindings/include/addresswatcher.h"',
+    '#include "extra-cpp-bindings/include/logscanner.h"',
+    '#include "extra-cpp-bindings/include/rateservice.h"',
+    '#include "extra-cpp-bindings/include/txtracker.h"',
+    '#include "extra-cpp-bindings/include/externalsigner.h"',
+    '#include "extra-cpp-bindings/include/mpc.h"',
+    '#include "extra-cpp-bindings/include/snapshot.h"',
+    '#include "extra-cpp-bindings/include/policy.h"',
+    '#include "extra-cpp-bindings/include/netusage.h"',
+    '#include "extra-cpp-bindings/include/runtime.h"',
     '#include "defi-wallet-core-cpp/src/lib.rs.h"',
     '#include "defi-wallet-core-cpp/src/uint.rs.h"',
     '#include "defi-wallet-core-cpp/include/nft.h"',
@@ -37,6 +61,17 @@ FINAL_INCLUDES = [
     '#include "lib.rs.h"',
     '#include "../../pay.h"',
     '#include "../../walletconnectcallback.h"',
+    '#include "../../walletconnect2.h"',
+    '#include "../../addresswatcher.h"',
+    '#include "../../logscanner.h"',
+    '#include "../../rateservice.h"',
+    '#include "../../txtracker.h"',
+    '#include "../../externalsigner.h"',
+    '#include "../../mpc.h"',
+    '#include "../../snapshot.h"',
+    '#include "../../policy.h"',
+    '#include "../../netusage.h"',
+    '#include "../../runtime.h"',
     '#include "lib.rs.h"',
     '#include "uint.rs.h"',
     '#include "../../nft.h"',
@@ -18,4 +18,15 @@ fn main() {
     println!("cargo:rerun-if-changed=include/pay.h");
     println!("cargo:rerun-if-changed=src/walletconnectcallback.cc");
     println!("cargo:rerun-if-changed=include/walletconnectcallback.h");
+    println!("cargo:rerun-if-changed=include/walletconnect2.h");
+    println!("cargo:rerun-if-changed=include/addresswatcher.h");
+    println!("cargo:rerun-if-changed=include/logscanner.h");
+    println!("cargo:rerun-if-changed=include/policy.h");
+    println!("cargo:rerun-if-changed=include/rateservice.h");
+    println!("cargo:rerun-if-changed=include/txtracker.h");
+    println!("cargo:rerun-if-changed=include/externalsigner.h");
+    println!("cargo:rerun-if-changed=include/mpc.h");
+    println!("cargo:rerun-if-changed=include/netusage.h");
+    println!("cargo:rerun-if-changed=include/runtime.h");
+    println!("cargo:rerun-if-changed=include/snapshot.h");
 }
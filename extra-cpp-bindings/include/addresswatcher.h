@@ -0,0 +1,28 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct RawTxDetail;
+struct RawTokenTxDetail;
+
+/// callback of AddressWatcher (called on the watcher's thread)
+class AddressWatcherCallback {
+  public:
+    virtual ~AddressWatcherCallback() {} // need virtual to prevent memory leak
+    // called once for each new transaction of a watched address
+    virtual void onNewTransaction(rust::Str address,
+                                  const RawTxDetail &tx) const = 0;
+    // called once for each new ERC20/ERC721 transfer of a watched address
+    virtual void onNewTokenTransfer(rust::Str address,
+                                    const RawTokenTxDetail &transfer) const = 0;
+    // called if a poll fails (the watcher keeps polling)
+    virtual void onError(rust::Str /* message */) const {}
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -0,0 +1,22 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+/// external signer of the digests (e.g. an HSM, a KMS or a remote signing service),
+/// used instead of the in-process keys (called on the signing thread)
+class ExternalSignerCallback {
+  public:
+    virtual ~ExternalSignerCallback() {} // need virtual to prevent memory leak
+    // signs the 32-byte digest as it is (no prefix or hashing) with the key of the address:
+    // 64 bytes (r || s) or 65 bytes (r || s || v); throws an exception if it fails
+    virtual rust::Vec<uint8_t>
+    signDigest(rust::Str address, rust::Slice<const uint8_t> digest) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -0,0 +1,21 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct DecodedEvent;
+
+/// callback of LogScanner (called on the thread calling scan_blocking)
+class LogScannerCallback {
+  public:
+    virtual ~LogScannerCallback() {} // need virtual to prevent memory leak
+    // called once for each scanned log (in the chain order)
+    virtual void onEvent(const DecodedEvent &event) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -0,0 +1,40 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct MpcMessage;
+struct MpcRoundResult;
+
+/// the local party of a threshold (MPC) signing protocol, e.g. the SDK of an MPC wallet
+/// vendor holding a share of the key (called on the signing thread)
+class MpcPartyCallback {
+  public:
+    virtual ~MpcPartyCallback() {} // need virtual to prevent memory leak
+    // starts signing the 32-byte digest in the session: the messages of the first round;
+    // throws an exception if it fails
+    virtual rust::Vec<MpcMessage>
+    start(rust::Str session_id, rust::Slice<const uint8_t> digest) const = 0;
+    // handles the messages of the other parties in a round: the messages of the next round,
+    // or the signature (64 or 65 bytes) when it's done; throws an exception if it fails
+    virtual MpcRoundResult
+    handle(rust::Str session_id, const rust::Vec<MpcMessage> &incoming) const = 0;
+};
+
+/// the opaque transport of the messages between the parties (e.g. the relay of the vendor)
+class MpcTransportCallback {
+  public:
+    virtual ~MpcTransportCallback() {} // need virtual to prevent memory leak
+    // sends the messages of the local party in a round and returns the ones of the other
+    // parties in that round (it blocks until they're received); throws an exception if it
+    // fails
+    virtual rust::Vec<MpcMessage>
+    exchange(rust::Str session_id, const rust::Vec<MpcMessage> &outgoing) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
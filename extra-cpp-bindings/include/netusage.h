@@ -0,0 +1,23 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct NetworkUsage;
+
+/// callback of the network budget (called on a thread of its own, so the
+/// requests and the WalletConnect connections aren't blocked by it)
+class NetworkBudgetCallback {
+  public:
+    virtual ~NetworkBudgetCallback() {} // need virtual to prevent memory leak
+    // called once when the network usage exceeds the budget
+    // (again after `reset_network_usage` or a new budget)
+    virtual void onBudgetExceeded(const NetworkUsage &usage) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
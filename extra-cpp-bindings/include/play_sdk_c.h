@@ -0,0 +1,148 @@
+#ifndef PLAY_SDK_C_H
+#define PLAY_SDK_C_H
+
+/* plain C interface of the SDK (src/capi.rs), for the integrations
+ * that cannot use the C++17 bindings.
+ *
+ * - every function returns a PlaySdkStatus; if it is not PLAY_SDK_OK,
+ *   the message can be read with play_sdk_last_error (on the same thread)
+ * - strings and byte buffers returned by the SDK must be released with
+ *   play_sdk_string_free and play_sdk_bytes_free
+ * - lists of records are returned as JSON arrays
+ * - addresses are 20 bytes, transaction hashes 32 bytes
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum PlaySdkStatus {
+    PLAY_SDK_OK = 0,
+    /* a required pointer was null or a string was not valid UTF-8 */
+    PLAY_SDK_INVALID_ARGUMENT = 1,
+    /* the call failed */
+    PLAY_SDK_ERROR = 2,
+    /* the call panicked */
+    PLAY_SDK_PANIC = 3
+} PlaySdkStatus;
+
+/* a byte buffer allocated by the SDK */
+typedef struct PlaySdkBytes {
+    uint8_t *data;
+    size_t len;
+} PlaySdkBytes;
+
+/* the common transaction fields (decimal strings; null is empty) */
+typedef struct PlaySdkTxCommon {
+    const char *gas_limit;
+    const char *gas_price;
+    const char *nonce;
+    uint64_t chain_id;
+    const char *web3api_url;
+    const char *max_fee_per_gas;
+    const char *max_priority_fee_per_gas;
+    /* the cap of the total fee of this transaction replacing the fee caps */
+    const char *max_total_fee;
+} PlaySdkTxCommon;
+
+/* query options of the transfer history */
+#define PLAY_SDK_BY_CONTRACT 0
+#define PLAY_SDK_BY_ADDRESS_AND_CONTRACT 1
+#define PLAY_SDK_BY_ADDRESS 2
+
+/* opaque WalletConnect client */
+typedef struct PlaySdkWalletConnect PlaySdkWalletConnect;
+
+/* the error message of the last failed call on the calling thread (NULL if none),
+ * valid until the next call on the same thread */
+const char *play_sdk_last_error(void);
+void play_sdk_string_free(char *s);
+void play_sdk_bytes_free(PlaySdkBytes bytes);
+
+/* the platform allocator (NULL if it fails) */
+typedef void *(*PlaySdkAllocFn)(size_t size, size_t align, void *user_data);
+typedef void (*PlaySdkFreeFn)(void *ptr, size_t size, size_t align, void *user_data);
+
+/* forwards the SDK's allocations to the platform allocator (the memory-stats feature);
+ * it must be called before any other call of the SDK */
+PlaySdkStatus play_sdk_set_allocator_hooks(PlaySdkAllocFn alloc, PlaySdkFreeFn free,
+                                           void *user_data);
+
+/* explorer queries (JSON arrays of RawTxDetail, RawTokenTxDetail or RawTokenResult) */
+PlaySdkStatus play_sdk_get_transaction_history(const char *address,
+                                               const char *api_key,
+                                               char **out_json);
+PlaySdkStatus play_sdk_get_erc20_transfer_history(const char *address,
+                                                  const char *contract_address,
+                                                  uint32_t option,
+                                                  const char *api_key,
+                                                  char **out_json);
+PlaySdkStatus play_sdk_get_erc721_transfer_history(const char *address,
+                                                   const char *contract_address,
+                                                   uint32_t option,
+                                                   const char *api_key,
+                                                   char **out_json);
+PlaySdkStatus play_sdk_get_tokens(const char *blockscout_base_url,
+                                  const char *account_address,
+                                  char **out_json);
+PlaySdkStatus play_sdk_get_token_transfers(const char *blockscout_base_url,
+                                           const char *address,
+                                           const char *contract_address,
+                                           uint32_t option, char **out_json);
+
+/* WalletConnect 1.0 */
+PlaySdkStatus play_sdk_walletconnect_new(const char *description,
+                                         const char *url,
+                                         const char *const *icon_urls,
+                                         size_t icon_urls_len,
+                                         const char *name, uint64_t chain_id,
+                                         PlaySdkWalletConnect **out_client);
+PlaySdkStatus play_sdk_walletconnect_restore(const char *session_info,
+                                             PlaySdkWalletConnect **out_client);
+void play_sdk_walletconnect_free(PlaySdkWalletConnect *client);
+PlaySdkStatus play_sdk_walletconnect_save(PlaySdkWalletConnect *client,
+                                          char **out_session_info);
+PlaySdkStatus
+play_sdk_walletconnect_connection_string(PlaySdkWalletConnect *client,
+                                         char **out_uri);
+/* out_addresses: concatenated 20-byte addresses */
+PlaySdkStatus play_sdk_walletconnect_ensure_session(
+    PlaySdkWalletConnect *client, PlaySdkBytes *out_addresses,
+    uint64_t *out_chain_id);
+/* out_signature: 65 bytes (r || s || v) */
+PlaySdkStatus play_sdk_walletconnect_sign_personal(PlaySdkWalletConnect *client,
+                                                   const char *message,
+                                                   const uint8_t *address,
+                                                   PlaySdkBytes *out_signature);
+/* transaction_request: JSON of Eip1559TransactionRequest */
+PlaySdkStatus play_sdk_walletconnect_sign_transaction(
+    PlaySdkWalletConnect *client, const char *transaction_request,
+    const uint8_t *address, PlaySdkBytes *out_raw_tx, uint8_t *out_tx_hash);
+PlaySdkStatus play_sdk_walletconnect_send_transaction(
+    PlaySdkWalletConnect *client, const char *transaction_request,
+    const uint8_t *address, uint8_t *out_tx_hash);
+/* contract_action: JSON of ContractAction */
+PlaySdkStatus play_sdk_walletconnect_sign_contract_transaction(
+    PlaySdkWalletConnect *client, const char *contract_action,
+    const PlaySdkTxCommon *common, const uint8_t *address,
+    PlaySdkBytes *out_raw_tx, uint8_t *out_tx_hash);
+PlaySdkStatus play_sdk_walletconnect_send_contract_transaction(
+    PlaySdkWalletConnect *client, const char *contract_action,
+    const PlaySdkTxCommon *common, const uint8_t *address,
+    uint8_t *out_tx_hash);
+
+/* WalletConnect 2.0 */
+/* out_json: JSON of MigrationOutcome (the 2.0 session info, or what is kept
+ * from the 1.0 session to pair the wallet again) */
+PlaySdkStatus play_sdk_walletconnect2_migrate_session(const char *session_json,
+                                                      uint64_t default_chain_id,
+                                                      char **out_json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PLAY_SDK_C_H */
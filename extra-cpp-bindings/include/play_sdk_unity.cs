@@ -0,0 +1,102 @@
+// P/Invoke declarations of the Unity exports (src/unity.rs),
+// available when the library is built with the `unity` feature:
+// cargo build --release -p play-cpp-sdk --features unity
+//
+// Strings are passed as UTF-8 bytes (Encoding.UTF8.GetBytes) with their length.
+// A call with a result writes its length to outLen; the result is then copied
+// with ReadResult into a buffer of that size, e.g.:
+//
+//     var address = Encoding.UTF8.GetBytes("0x...");
+//     var apiKey = Encoding.UTF8.GetBytes("...");
+//     if (PlaySdk.GetTransactionHistory(address, (UIntPtr)address.Length, apiKey, (UIntPtr)apiKey.Length, out var len) == PlaySdkStatus.Ok) {
+//         var json = new byte[(int)len];
+//         PlaySdk.ReadResult(json, len);
+//     }
+using System;
+using System.Runtime.InteropServices;
+
+namespace Com.Crypto.GameSdk
+{
+    public enum PlaySdkStatus : int
+    {
+        Ok = 0,
+        InvalidArgument = 1,
+        Error = 2,
+        Panic = 3,
+    }
+
+    public static class PlaySdk
+    {
+#if UNITY_IOS && !UNITY_EDITOR
+        const string Lib = "__Internal";
+#else
+        const string Lib = "play_cpp_sdk";
+#endif
+
+        [DllImport(Lib, EntryPoint = "play_unity_last_error")]
+        public static extern UIntPtr LastError(byte[] buffer, UIntPtr capacity);
+
+        [DllImport(Lib, EntryPoint = "play_unity_read_result")]
+        public static extern PlaySdkStatus ReadResult(byte[] buffer, UIntPtr capacity);
+
+        [DllImport(Lib, EntryPoint = "play_unity_get_transaction_history")]
+        public static extern PlaySdkStatus GetTransactionHistory(
+            byte[] address, UIntPtr addressLen, byte[] apiKey, UIntPtr apiKeyLen, out UIntPtr outLen);
+
+        [DllImport(Lib, EntryPoint = "play_unity_get_erc20_transfer_history")]
+        public static extern PlaySdkStatus GetErc20TransferHistory(
+            byte[] address, UIntPtr addressLen, byte[] contractAddress, UIntPtr contractAddressLen,
+            uint option, byte[] apiKey, UIntPtr apiKeyLen, out UIntPtr outLen);
+
+        [DllImport(Lib, EntryPoint = "play_unity_get_erc721_transfer_history")]
+        public static extern PlaySdkStatus GetErc721TransferHistory(
+            byte[] address, UIntPtr addressLen, byte[] contractAddress, UIntPtr contractAddressLen,
+            uint option, byte[] apiKey, UIntPtr apiKeyLen, out UIntPtr outLen);
+
+        [DllImport(Lib, EntryPoint = "play_unity_get_tokens")]
+        public static extern PlaySdkStatus GetTokens(
+            byte[] blockscoutBaseUrl, UIntPtr blockscoutBaseUrlLen,
+            byte[] accountAddress, UIntPtr accountAddressLen, out UIntPtr outLen);
+
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_new")]
+        public static extern PlaySdkStatus WalletConnectNew(
+            byte[] description, UIntPtr descriptionLen, byte[] url, UIntPtr urlLen,
+            byte[] iconUrl, UIntPtr iconUrlLen, byte[] name, UIntPtr nameLen,
+            ulong chainId, out IntPtr client);
+
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_restore")]
+        public static extern PlaySdkStatus WalletConnectRestore(
+            byte[] sessionInfo, UIntPtr sessionInfoLen, out IntPtr client);
+
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_free")]
+        public static extern void WalletConnectFree(IntPtr client);
+
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_save")]
+        public static extern PlaySdkStatus WalletConnectSave(IntPtr client, out UIntPtr outLen);
+
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_connection_string")]
+        public static extern PlaySdkStatus WalletConnectConnectionString(IntPtr client, out UIntPtr outLen);
+
+        // the result is the concatenated 20-byte addresses
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_ensure_session")]
+        public static extern PlaySdkStatus WalletConnectEnsureSession(
+            IntPtr client, out ulong chainId, out UIntPtr outLen);
+
+        // the result is the 65-byte signature (r || s || v)
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_sign_personal")]
+        public static extern PlaySdkStatus WalletConnectSignPersonal(
+            IntPtr client, byte[] message, UIntPtr messageLen, byte[] address, out UIntPtr outLen);
+
+        // txHash: 32 bytes, the result is the signed transaction
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_sign_transaction")]
+        public static extern PlaySdkStatus WalletConnectSignTransaction(
+            IntPtr client, byte[] transactionRequest, UIntPtr transactionRequestLen,
+            byte[] address, byte[] txHash, out UIntPtr outLen);
+
+        // txHash: 32 bytes
+        [DllImport(Lib, EntryPoint = "play_unity_walletconnect_send_transaction")]
+        public static extern PlaySdkStatus WalletConnectSendTransaction(
+            IntPtr client, byte[] transactionRequest, UIntPtr transactionRequestLen,
+            byte[] address, byte[] txHash);
+    }
+}
@@ -0,0 +1,22 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct PolicyViolation;
+
+/// callback of the transaction policy (called on the thread signing or sending
+/// the transaction)
+class PolicyCallback {
+  public:
+    virtual ~PolicyCallback() {} // need virtual to prevent memory leak
+    // called once for each transaction blocked by the policy
+    virtual void onPolicyBlocked(const PolicyViolation &violation) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -0,0 +1,23 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct ExchangeRate;
+
+/// callback of RateService (called on the service's thread)
+class RateServiceCallback {
+  public:
+    virtual ~RateServiceCallback() {} // need virtual to prevent memory leak
+    // called once for each new or changed rate of a pair
+    virtual void onRateUpdated(const ExchangeRate &rate) const = 0;
+    // called if a refresh fails (the service keeps refreshing)
+    virtual void onError(rust::Str /* message */) const {}
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
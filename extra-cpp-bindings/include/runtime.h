@@ -0,0 +1,20 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+/// callback of the SDK's runtimes (called on the new thread)
+class RuntimeThreadCallback {
+  public:
+    virtual ~RuntimeThreadCallback() {} // need virtual to prevent memory leak
+    // called once on each new thread of the runtimes, before it runs any task
+    // (e.g. to set its priority or its core affinity with the platform's API)
+    virtual void onThreadStart(rust::Str thread_name) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -0,0 +1,21 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+/// progress callback of snapshot_holders_blocking (called on the thread calling it)
+class SnapshotProgressCallback {
+  public:
+    virtual ~SnapshotProgressCallback() {} // need virtual to prevent memory leak
+    // called after each scanned block range: the transfers are replayed up to
+    // scanned_block (inclusive) of the snapshot at block_number
+    virtual void onProgress(uint64_t scanned_block, uint64_t block_number,
+                            uint64_t holders) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
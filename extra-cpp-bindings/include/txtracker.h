@@ -0,0 +1,32 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct TrackedTxReceipt;
+
+/// callback of TxTracker (called on the tracker's thread)
+class TxTrackerCallback {
+  public:
+    virtual ~TxTrackerCallback() {} // need virtual to prevent memory leak
+    // called when the confirmations of a mined transaction change
+    // (fewer than the required ones)
+    virtual void onConfirmations(const TrackedTxReceipt & /* receipt */) const {}
+    // called once when a transaction has the required confirmations
+    // (it's not tracked anymore)
+    virtual void onConfirmed(const TrackedTxReceipt &receipt) const = 0;
+    // called when the block of a mined transaction was replaced by a reorg:
+    // the transaction is pending again (it may be mined in another block or dropped)
+    virtual void onReorged(rust::Str tx_hash, uint64_t block_number,
+                           rust::Str block_hash) const = 0;
+    // called if a poll fails, e.g. "0x...: <error>" for the transaction whose
+    // receipt failed (the tracker keeps polling the others)
+    virtual void onError(rust::Str /* message */) const {}
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
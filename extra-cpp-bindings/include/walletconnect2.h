@@ -0,0 +1,25 @@
+#pragma once
+
+#include "rust/cxx.h"
+#include <memory>
+namespace com {
+namespace crypto {
+namespace game_sdk {
+
+struct WalletConnect2Namespaces;
+
+/// callback previewing the session of WalletConnect 2.0 (called on the thread
+/// of the client's runtime, before `ensure_session_blocking` returns)
+class WalletConnect2SessionProposalCallback {
+  public:
+    virtual ~WalletConnect2SessionProposalCallback() {
+    } // need virtual to prevent memory leak
+    // returns false to reject the session (then `ensure_session_blocking`
+    // fails)
+    virtual bool
+    onSessionProposal(const WalletConnect2Namespaces &namespaces) const = 0;
+};
+
+} // namespace game_sdk
+} // namespace crypto
+} // namespace com
@@ -57,6 +57,8 @@ class WalletConnectCallback {
     onConnecting(const WalletConnectSessionInfo &sessioninfo) const = 0;
     virtual void
     onUpdated(const WalletConnectSessionInfo &sessioninfo) const = 0;
+    // called if the callback task ends with an error (it is not running anymore)
+    virtual void onError(rust::Str /* message */) const {}
 };
 
 std::unique_ptr<WalletConnectCallback> new_walletconnect_callback();
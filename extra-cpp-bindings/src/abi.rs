@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use ethers::abi::{Abi, Event, RawLog, Token};
+use ethers::types::{Bytes, H256, I256};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Decodes the event logs of the contract ABI
+#[derive(Debug, Default)]
+pub(crate) struct EventDecoder {
+    /// the non-anonymous events by their signature (topic 0)
+    events: HashMap<H256, Vec<Event>>,
+}
+
+impl EventDecoder {
+    /// parses the ABI JSON (an empty string for no events)
+    pub(crate) fn new(abi_json: &str) -> Result<Self> {
+        if abi_json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let abi: Abi = serde_json::from_str(abi_json)?;
+        let mut events: HashMap<H256, Vec<Event>> = HashMap::new();
+        for event in abi.events().filter(|event| !event.anonymous) {
+            events
+                .entry(event.signature())
+                .or_default()
+                .push(event.clone());
+        }
+        Ok(Self { events })
+    }
+
+    /// the signatures (topic 0) of the events
+    pub(crate) fn signatures(&self) -> Vec<H256> {
+        self.events.keys().copied().collect()
+    }
+
+    /// the event name and its parameters (a JSON object) if the log matches an event
+    /// (events with the same signature differ in their indexed parameters)
+    pub(crate) fn decode(&self, topics: &[H256], data: &[u8]) -> Option<(String, Value)> {
+        let candidates = self.events.get(topics.first()?)?;
+        candidates.iter().find_map(|event| {
+            let log = event
+                .parse_log(RawLog {
+                    topics: topics.to_vec(),
+                    data: data.to_vec(),
+                })
+                .ok()?;
+            let params: Map<String, Value> = log
+                .params
+                .into_iter()
+                .map(|param| (param.name, token_to_json(param.value)))
+                .collect();
+            Some((event.name.clone(), Value::Object(params)))
+        })
+    }
+}
+
+/// The raw log (e.g. an item of the `eth_getLogs` result; the other fields are ignored)
+#[derive(Deserialize)]
+struct RawEventLog {
+    topics: Vec<H256>,
+    #[serde(default)]
+    data: Bytes,
+}
+
+/// decodes the log JSON with the events of the ABI JSON,
+/// returns the JSON object with the event `name` and the decoded `params`
+pub(crate) fn decode_event(abi_json: &str, log_json: &str) -> Result<String> {
+    let decoder = EventDecoder::new(abi_json)?;
+    let log: RawEventLog = serde_json::from_str(log_json)?;
+    let (name, params) = decoder
+        .decode(&log.topics, &log.data)
+        .ok_or_else(|| anyhow!("no matching event in the ABI"))?;
+    Ok(json!({ "name": name, "params": params }).to_string())
+}
+
+/// the JSON of the ABI value: integers as decimal strings,
+/// addresses and bytes as 0x-prefixed hexadecimal strings, arrays and tuples as arrays
+pub(crate) fn token_to_json(token: Token) -> Value {
+    match token {
+        Token::Address(address) => json!(format!("{address:?}")),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            json!(format!("0x{}", hex::encode(bytes)))
+        }
+        Token::Int(value) => json!(I256::from_raw(value).to_string()),
+        Token::Uint(value) => json!(value.to_string()),
+        Token::Bool(value) => json!(value),
+        Token::String(value) => json!(value),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.into_iter().map(token_to_json).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ERC20_TRANSFER_ABI: &str = r#"[{
+        "anonymous": false,
+        "inputs": [
+            {"indexed": true, "name": "from", "type": "address"},
+            {"indexed": true, "name": "to", "type": "address"},
+            {"indexed": false, "name": "value", "type": "uint256"}
+        ],
+        "name": "Transfer",
+        "type": "event"
+    }]"#;
+
+    #[test]
+    fn test_decode_transfer() {
+        let decoder = EventDecoder::new(ERC20_TRANSFER_ABI).unwrap();
+        let signature: H256 = ethers::utils::keccak256("Transfer(address,address,uint256)").into();
+        assert_eq!(decoder.signatures(), vec![signature]);
+        let topics = [
+            signature,
+            H256::from(ethers::types::Address::repeat_byte(1)),
+            H256::from(ethers::types::Address::repeat_byte(2)),
+        ];
+        let data = ethers::abi::encode(&[Token::Uint(1000.into())]);
+        let (name, params) = decoder.decode(&topics, &data).unwrap();
+        assert_eq!(name, "Transfer");
+        assert_eq!(
+            params,
+            json!({
+                "from": "0x0101010101010101010101010101010101010101",
+                "to": "0x0202020202020202020202020202020202020202",
+                "value": "1000"
+            })
+        );
+        // not an event of the ABI
+        assert!(decoder.decode(&[H256::zero()], &data).is_none());
+        // the indexed parameters are missing
+        assert!(decoder.decode(&topics[..1], &data).is_none());
+        assert!(EventDecoder::new("")
+            .unwrap()
+            .decode(&topics, &data)
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let log = r#"{
+            "address": "0x66e428c3f67a68878562e79a0234c1f83c208770",
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                "0x0000000000000000000000000101010101010101010101010101010101010101",
+                "0x0000000000000000000000000202020202020202020202020202020202020202"
+            ],
+            "data": "0x00000000000000000000000000000000000000000000000000000000000003e8",
+            "blockNumber": "0x1"
+        }"#;
+        let decoded: Value =
+            serde_json::from_str(&decode_event(ERC20_TRANSFER_ABI, log).unwrap()).unwrap();
+        assert_eq!(
+            decoded,
+            json!({
+                "name": "Transfer",
+                "params": {
+                    "from": "0x0101010101010101010101010101010101010101",
+                    "to": "0x0202020202020202020202020202020202020202",
+                    "value": "1000"
+                }
+            })
+        );
+        assert!(decode_event(ERC20_TRANSFER_ABI, r#"{"topics": [], "data": "0x"}"#).is_err());
+        assert!(decode_event("not an abi", log).is_err());
+    }
+
+    #[test]
+    fn test_token_to_json() {
+        assert_eq!(
+            token_to_json(Token::Int(I256::from(-5).into_raw())),
+            json!("-5")
+        );
+        assert_eq!(
+            token_to_json(Token::Array(vec![
+                Token::Bool(true),
+                Token::Bytes(vec![0xab]),
+                Token::String("sword".into())
+            ])),
+            json!([true, "0xab", "sword"])
+        );
+    }
+}
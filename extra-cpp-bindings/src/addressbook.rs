@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+
+use crate::ffi::{AddressBookEntry, RawTokenTxDetail, RawTxDetail};
+
+impl AddressBookEntry {
+    /// whether the entry is of the address on the chain (the entries without a chain
+    /// are of all the chains; hexadecimal addresses are case-insensitive)
+    fn matches(&self, chain: &str, address: &str) -> bool {
+        (self.chain.is_empty() || self.chain == chain) && self.address.eq_ignore_ascii_case(address)
+    }
+}
+
+/// Labels of the addresses (name, chain, notes and tags), persisted as a JSON array
+/// of the entries (the same as the exported JSON)
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    entries: Vec<AddressBookEntry>,
+    /// where the entries are persisted (none if empty)
+    path: Option<PathBuf>,
+}
+
+impl AddressBook {
+    pub(crate) fn open(path: String) -> Result<Self> {
+        let path = (!path.is_empty()).then(|| PathBuf::from(path));
+        let entries = match &path {
+            Some(path) if path.exists() => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            _ => vec![],
+        };
+        Ok(Self { entries, path })
+    }
+
+    /// writes the entries (via a temporary file, so that they're never partially written)
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_string(&self.entries)?)?;
+            std::fs::rename(tmp, path)?;
+        }
+        Ok(())
+    }
+
+    /// adds the entry or replaces the one of the same chain and address
+    fn upsert(&mut self, entry: AddressBookEntry) -> Result<()> {
+        if entry.address.is_empty() || entry.name.is_empty() {
+            return Err(anyhow!(
+                "the address book entry needs an address and a name"
+            ));
+        }
+        match self.entries.iter_mut().find(|existing| {
+            existing.chain == entry.chain && existing.address.eq_ignore_ascii_case(&entry.address)
+        }) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        Ok(())
+    }
+
+    /// adds the entry (or replaces the one of the same chain and address)
+    pub fn add_entry(&mut self, entry: AddressBookEntry) -> Result<()> {
+        self.upsert(entry)?;
+        self.save()
+    }
+
+    /// removes the entry of the chain and address, returns false if there was none
+    pub fn remove_entry(&mut self, chain: String, address: String) -> Result<bool> {
+        let count = self.entries.len();
+        self.entries.retain(|entry| {
+            !(entry.chain == chain && entry.address.eq_ignore_ascii_case(&address))
+        });
+        if self.entries.len() == count {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// the entry of the address on the chain (the one of the chain is preferred
+    /// to the one of all the chains)
+    fn find(&self, chain: &str, address: &str) -> Option<&AddressBookEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.matches(chain, address))
+            .max_by_key(|entry| !entry.chain.is_empty())
+    }
+
+    /// the name of the address on the chain (empty if it's not in the address book)
+    pub fn find_name(&self, chain: String, address: String) -> String {
+        self.find(&chain, &address)
+            .map(|entry| entry.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// all the entries (in the order they were added)
+    pub fn entries(&self) -> Vec<AddressBookEntry> {
+        self.entries.clone()
+    }
+
+    /// the entries with the tag
+    pub fn entries_with_tag(&self, tag: String) -> Vec<AddressBookEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.tags.contains(&tag))
+            .cloned()
+            .collect()
+    }
+
+    /// the JSON array of the entries
+    pub fn export_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.entries)?)
+    }
+
+    /// adds the entries of the JSON array (they replace the ones of the same chain and address),
+    /// returns the number of the imported entries
+    pub fn import_json(&mut self, json: String) -> Result<u32> {
+        let imported: Vec<AddressBookEntry> = serde_json::from_str(&json)?;
+        let count = imported.len() as u32;
+        for entry in imported {
+            self.upsert(entry)?;
+        }
+        self.save()?;
+        Ok(count)
+    }
+
+    /// the name of the counterparty of the address (or of the receiver if the address
+    /// is neither the sender nor the receiver)
+    fn counterparty_name(&self, chain: &str, address: &str, from: &str, to: &str) -> String {
+        let counterparty = if from.eq_ignore_ascii_case(address) {
+            to
+        } else if to.eq_ignore_ascii_case(address) {
+            from
+        } else {
+            to
+        };
+        self.find(chain, counterparty)
+            .map(|entry| entry.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// sets the `display_name` of the transactions of the address on the chain
+    pub fn label_transactions(
+        &self,
+        chain: String,
+        address: String,
+        mut txs: Vec<RawTxDetail>,
+    ) -> Vec<RawTxDetail> {
+        for tx in txs.iter_mut() {
+            tx.display_name =
+                self.counterparty_name(&chain, &address, &tx.from_address, &tx.to_address);
+        }
+        txs
+    }
+
+    /// sets the `display_name` of the token transfers of the address on the chain
+    pub fn label_token_transfers(
+        &self,
+        chain: String,
+        address: String,
+        mut transfers: Vec<RawTokenTxDetail>,
+    ) -> Vec<RawTokenTxDetail> {
+        for transfer in transfers.iter_mut() {
+            transfer.display_name = self.counterparty_name(
+                &chain,
+                &address,
+                &transfer.from_address,
+                &transfer.to_address,
+            );
+        }
+        transfers
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(name: &str, address: &str, chain: &str) -> AddressBookEntry {
+        AddressBookEntry {
+            name: name.into(),
+            address: address.into(),
+            chain: chain.into(),
+            notes: String::new(),
+            tags: vec!["friends".into()],
+        }
+    }
+
+    #[test]
+    fn test_address_book() {
+        let path = std::env::temp_dir().join(format!("addressbook-{}.json", uuid::Uuid::new_v4()));
+        let mut book = AddressBook::open(path.to_string_lossy().into_owned()).unwrap();
+        let alice = "0x841a15d12aec9c6039fd132c2fbff112ed355700";
+        book.add_entry(entry("Alice", alice, "")).unwrap();
+        book.add_entry(entry("Alice (Cronos)", alice, "eip155:25"))
+            .unwrap();
+        assert_eq!(
+            book.find_name("eip155:25".into(), alice.to_uppercase().replace("0X", "0x")),
+            "Alice (Cronos)"
+        );
+        assert_eq!(book.find_name("eip155:338".into(), alice.into()), "Alice");
+        assert!(book.add_entry(entry("", alice, "")).is_err());
+
+        let me = "0x0101010101010101010101010101010101010101";
+        let txs = book.label_transactions(
+            "eip155:338".into(),
+            me.into(),
+            vec![
+                RawTxDetail {
+                    to_address: me.into(),
+                    from_address: alice.into(),
+                    ..Default::default()
+                },
+                RawTxDetail {
+                    to_address: alice.into(),
+                    from_address: me.into(),
+                    ..Default::default()
+                },
+            ],
+        );
+        // the counterparty of the incoming and the outgoing transactions
+        assert_eq!(txs[0].display_name, "Alice");
+        assert_eq!(txs[1].display_name, "Alice");
+
+        // persisted and exported
+        let reopened = AddressBook::open(path.to_string_lossy().into_owned()).unwrap();
+        assert_eq!(reopened.entries(), book.entries());
+        let mut imported = AddressBook::default();
+        assert_eq!(
+            imported.import_json(book.export_json().unwrap()).unwrap(),
+            2
+        );
+        assert_eq!(imported.entries_with_tag("friends".into()).len(), 2);
+
+        assert!(book.remove_entry("".into(), alice.into()).unwrap());
+        assert!(!book.remove_entry("".into(), alice.into()).unwrap());
+        assert_eq!(book.find_name("eip155:338".into(), alice.into()), "");
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use ethers::abi::{Abi, Token};
+use ethers::types::{Address, H256, U256};
+use ethers::utils::keccak256;
+use serde::Serialize;
+
+use crate::ffi::{AirdropClaim, AirdropRecipient, WalletConnectTxEip155};
+use crate::{parse_address, rpc};
+
+/// the methods of the MerkleDistributor contracts (Uniswap-style)
+const DISTRIBUTOR_METHODS: &[&str] = &[
+    "function claim(uint256 index, address account, uint256 amount, bytes32[] merkleProof)",
+    "function isClaimed(uint256 index) view returns (bool)",
+];
+
+fn distributor_abi() -> Result<Abi> {
+    Ok(ethers::abi::parse_abi(DISTRIBUTOR_METHODS)?)
+}
+
+/// the leaf of a claim: `keccak256(abi.encodePacked(index, account, amount))`
+fn leaf(index: u64, account: Address, amount: U256) -> H256 {
+    let mut packed = [0u8; 84];
+    U256::from(index).to_big_endian(&mut packed[..32]);
+    packed[32..52].copy_from_slice(account.as_bytes());
+    amount.to_big_endian(&mut packed[52..]);
+    H256(keccak256(packed))
+}
+
+/// the parent of the two nodes (they're sorted, so the proofs don't need the positions)
+fn parent(a: H256, b: H256) -> H256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    H256(keccak256([first.as_bytes(), second.as_bytes()].concat()))
+}
+
+/// the root of the tree of the leaf and its proof
+fn proof_root(leaf: H256, proof: &[H256]) -> H256 {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| parent(node, *sibling))
+}
+
+fn parse_proof(proof: &[String]) -> Result<Vec<H256>> {
+    proof
+        .iter()
+        .map(|node| {
+            node.parse()
+                .map_err(|_| anyhow!("invalid proof node: {node}"))
+        })
+        .collect()
+}
+
+/// A recipient of the airdrop (its index is its position in the tree)
+struct Entry {
+    account: Address,
+    amount: U256,
+}
+
+/// The Merkle tree of the claims of an airdrop (the recipients and their amounts),
+/// compatible with the Uniswap-style MerkleDistributor contracts: the leaves are sorted,
+/// the pairs are hashed sorted and the last node of an odd layer is promoted
+pub struct AirdropTree {
+    /// by index (the order of the recipients)
+    entries: Vec<Entry>,
+    /// the sorted leaves, then each layer up to the root
+    layers: Vec<Vec<H256>>,
+}
+
+impl AirdropTree {
+    pub(crate) fn new(recipients: Vec<AirdropRecipient>) -> Result<Self> {
+        if recipients.is_empty() {
+            return Err(anyhow!("no recipients"));
+        }
+        let mut seen = HashSet::new();
+        let mut entries = Vec::with_capacity(recipients.len());
+        for recipient in &recipients {
+            let account = parse_address(&recipient.address)?;
+            if !seen.insert(account) {
+                return Err(anyhow!("duplicate recipient {account:?}"));
+            }
+            let amount = U256::from_dec_str(&recipient.amount)
+                .map_err(|_| anyhow!("invalid amount: {}", recipient.amount))?;
+            entries.push(Entry { account, amount });
+        }
+        let mut leaves: Vec<H256> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| leaf(index as u64, entry.account, entry.amount))
+            .collect();
+        leaves.sort();
+        let mut layers = vec![leaves];
+        while layers.last().is_some_and(|layer| layer.len() > 1) {
+            let next = layers
+                .last()
+                .expect("a layer")
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => parent(*a, *b),
+                    [a] => *a,
+                    _ => unreachable!("chunks of 2"),
+                })
+                .collect();
+            layers.push(next);
+        }
+        Ok(Self { entries, layers })
+    }
+
+    fn proof(&self, leaf: H256) -> Vec<H256> {
+        let mut position = self.layers[0]
+            .binary_search(&leaf)
+            .expect("the leaf of an entry");
+        let mut proof = vec![];
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                proof.push(*sibling);
+            }
+            position /= 2;
+        }
+        proof
+    }
+
+    fn claim_of(&self, index: usize) -> AirdropClaim {
+        let entry = &self.entries[index];
+        let proof = self.proof(leaf(index as u64, entry.account, entry.amount));
+        AirdropClaim {
+            index: index as u64,
+            address: format!("{:?}", entry.account),
+            amount: entry.amount.to_string(),
+            proof: proof.iter().map(|node| format!("{node:?}")).collect(),
+        }
+    }
+
+    /// the Merkle root (hexadecimal, 0x-prefixed) of the distributor contract
+    pub fn root(&self) -> String {
+        format!("{:?}", self.layers.last().expect("a root")[0])
+    }
+
+    /// the claim of the address
+    pub fn claim(&self, address: String) -> Result<AirdropClaim> {
+        let account = parse_address(&address)?;
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.account == account)
+            .ok_or_else(|| anyhow!("{account:?} is not a recipient of the airdrop"))?;
+        Ok(self.claim_of(index))
+    }
+
+    /// the claims of all the recipients (by index)
+    pub fn claims(&self) -> Vec<AirdropClaim> {
+        (0..self.entries.len())
+            .map(|index| self.claim_of(index))
+            .collect()
+    }
+
+    /// the JSON of the root, the total and the claims by address
+    /// (the format of the Uniswap `parse-balance-map` script, with decimal amounts)
+    pub fn export_json(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct JsonClaim {
+            index: u64,
+            amount: String,
+            proof: Vec<String>,
+        }
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct JsonTree {
+            merkle_root: String,
+            token_total: String,
+            claims: std::collections::BTreeMap<String, JsonClaim>,
+        }
+        let total = self
+            .entries
+            .iter()
+            .try_fold(U256::zero(), |total, entry| total.checked_add(entry.amount))
+            .ok_or_else(|| anyhow!("the total of the airdrop overflows"))?;
+        let claims = self
+            .claims()
+            .into_iter()
+            .map(|claim| {
+                (
+                    claim.address,
+                    JsonClaim {
+                        index: claim.index,
+                        amount: claim.amount,
+                        proof: claim.proof,
+                    },
+                )
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&JsonTree {
+            merkle_root: self.root(),
+            token_total: total.to_string(),
+            claims,
+        })?)
+    }
+}
+
+/// whether the claim (its index, address, amount and proof) is in the tree of the root
+pub fn verify_airdrop_claim(root: String, claim: &AirdropClaim) -> Result<bool> {
+    let root: H256 = root
+        .parse()
+        .map_err(|_| anyhow!("invalid Merkle root: {root}"))?;
+    let amount = U256::from_dec_str(&claim.amount)?;
+    let leaf = leaf(claim.index, parse_address(&claim.address)?, amount);
+    Ok(proof_root(leaf, &parse_proof(&claim.proof)?) == root)
+}
+
+/// the transaction claiming the airdrop from the distributor contract (`claim`);
+/// anyone can send it, the tokens go to the address of the claim
+pub fn build_airdrop_claim_transaction(
+    chain_id: u64,
+    distributor: String,
+    claim: &AirdropClaim,
+) -> Result<WalletConnectTxEip155> {
+    let proof = parse_proof(&claim.proof)?
+        .into_iter()
+        .map(|node| Token::FixedBytes(node.as_bytes().to_vec()))
+        .collect();
+    let args = [
+        Token::Uint(claim.index.into()),
+        Token::Address(parse_address(&claim.address)?),
+        Token::Uint(U256::from_dec_str(&claim.amount)?),
+        Token::Array(proof),
+    ];
+    let mut tx = WalletConnectTxEip155 {
+        to: format!("{:?}", parse_address(&distributor)?),
+        value: "0".to_owned(),
+        data: distributor_abi()?.function("claim")?.encode_input(&args)?,
+        common: Default::default(),
+    };
+    tx.common.chainid = chain_id;
+    Ok(tx)
+}
+
+/// whether the claim of the index was made on the distributor contract
+pub async fn is_airdrop_claimed(rpc_url: &str, distributor: &str, index: u64) -> Result<bool> {
+    let provider = rpc::provider(rpc_url)?;
+    let abi = distributor_abi()?;
+    let output = crate::swap::call(
+        &provider,
+        parse_address(distributor)?,
+        &abi,
+        "isClaimed",
+        &[Token::Uint(index.into())],
+    )
+    .await?;
+    output
+        .first()
+        .and_then(|claimed| claimed.clone().into_bool())
+        .ok_or_else(|| anyhow!("unexpected output of isClaimed"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn recipients(count: u8) -> Vec<AirdropRecipient> {
+        (1..=count)
+            .map(|i| AirdropRecipient {
+                address: format!("{:?}", Address::repeat_byte(i)),
+                amount: (u64::from(i) * 100).to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_airdrop_tree_proofs() {
+        // a single leaf is the root
+        let tree = AirdropTree::new(recipients(1)).unwrap();
+        let claim = tree.claims().remove(0);
+        assert!(claim.proof.is_empty());
+        let expected = leaf(0, Address::repeat_byte(1), 100.into());
+        assert_eq!(tree.root(), format!("{expected:?}"));
+
+        let tree = AirdropTree::new(recipients(2)).unwrap();
+        let root = parent(
+            leaf(0, Address::repeat_byte(1), 100.into()),
+            leaf(1, Address::repeat_byte(2), 200.into()),
+        );
+        assert_eq!(tree.root(), format!("{root:?}"));
+
+        // odd layers
+        for count in [3, 5, 7, 8] {
+            let tree = AirdropTree::new(recipients(count)).unwrap();
+            let claims = tree.claims();
+            assert_eq!(claims.len(), count as usize);
+            for claim in &claims {
+                assert!(verify_airdrop_claim(tree.root(), claim).unwrap());
+                let mut tampered = claim.clone();
+                tampered.amount = "1000000".to_owned();
+                assert!(!verify_airdrop_claim(tree.root(), &tampered).unwrap());
+            }
+        }
+
+        let tree = AirdropTree::new(recipients(3)).unwrap();
+        let claim = tree
+            .claim(format!("{:?}", Address::repeat_byte(2)))
+            .unwrap();
+        assert_eq!((claim.index, claim.amount.as_str()), (1, "200"));
+        assert!(tree
+            .claim(format!("{:?}", Address::repeat_byte(9)))
+            .is_err());
+        let json: serde_json::Value = serde_json::from_str(&tree.export_json().unwrap()).unwrap();
+        assert_eq!(json["merkleRoot"], tree.root());
+        assert_eq!(json["tokenTotal"], "600");
+        assert_eq!(json["claims"][&claim.address]["index"], 1);
+
+        let mut duplicate = recipients(2);
+        duplicate[1].address = duplicate[0].address.clone();
+        assert!(AirdropTree::new(duplicate).is_err());
+        assert!(AirdropTree::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_airdrop_claim_transaction() {
+        let tree = AirdropTree::new(recipients(3)).unwrap();
+        let claim = tree.claims().remove(2);
+        let distributor = format!("{:?}", Address::repeat_byte(0xd1));
+        let tx = build_airdrop_claim_transaction(25, distributor.clone(), &claim).unwrap();
+        assert_eq!(tx.to, distributor);
+        assert_eq!(tx.common.chainid, 25);
+        // claim(uint256,address,uint256,bytes32[])
+        assert_eq!(tx.data[..4], hex::decode("2e7ba6ef").unwrap());
+        let decoded = distributor_abi()
+            .unwrap()
+            .function("claim")
+            .unwrap()
+            .decode_input(&tx.data[4..])
+            .unwrap();
+        assert_eq!(decoded[0], Token::Uint(2.into()));
+        assert_eq!(decoded[2], Token::Uint(300.into()));
+        assert_eq!(
+            decoded[3].clone().into_array().unwrap().len(),
+            claim.proof.len()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Result};
+use ethers::types::U256;
+
+use crate::ffi::{AmountFormat, RoundingMode};
+
+/// the largest power of ten in a U256 is 10^77
+const MAX_EXPONENT: u32 = 77;
+
+/// the amount of the base units (decimal or 0x-prefixed hexadecimal)
+fn parse_raw_amount(amount: &str) -> Result<U256> {
+    let amount = amount.trim();
+    match amount.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).ok(),
+        None => U256::from_dec_str(amount).ok(),
+    }
+    .ok_or_else(|| anyhow!("invalid amount: {amount}"))
+}
+
+/// the value rounded to a multiple of 10^exponent
+fn round_to(value: U256, exponent: u32, rounding: RoundingMode) -> Result<U256> {
+    if exponent == 0 {
+        return Ok(value);
+    }
+    let unit = U256::exp10(exponent as usize);
+    let (quotient, remainder) = value.div_mod(unit);
+    let up = match rounding {
+        RoundingMode::Floor => false,
+        RoundingMode::Ceil => !remainder.is_zero(),
+        // half up (doubling the remainder overflows with the largest units)
+        RoundingMode::Round => remainder >= unit - remainder,
+        _ => return Err(anyhow!("invalid rounding mode")),
+    };
+    let quotient = if up { quotient + 1 } else { quotient };
+    quotient
+        .checked_mul(unit)
+        .ok_or_else(|| anyhow!("the rounded amount overflows"))
+}
+
+/// the digits grouped by three with the separator
+fn group_thousands(digits: &str, separator: &str) -> String {
+    if separator.is_empty() {
+        return digits.to_owned();
+    }
+    let mut grouped = String::with_capacity(digits.len() * 2);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// formats the amount of the base units (e.g. a balance or a transfer value returned
+/// by the SDK, decimal or 0x-prefixed hexadecimal) as a decimal number of the tokens
+pub fn format_amount(amount: String, format: &AmountFormat) -> Result<String> {
+    if format.decimals > MAX_EXPONENT {
+        return Err(anyhow!("too many decimals: {}", format.decimals));
+    }
+    let value = parse_raw_amount(&amount)?;
+    let decimals = format.decimals as i64;
+    // the number of the kept fraction digits (negative if the integer digits are rounded)
+    let kept = if format.significant_digits > 0 {
+        // the exponent of the leading digit of the amount of the tokens
+        let leading = if value.is_zero() {
+            0
+        } else {
+            value.to_string().len() as i64 - 1 - decimals
+        };
+        format.significant_digits as i64 - 1 - leading
+    } else {
+        (format.max_fraction_digits as i64).min(decimals)
+    };
+    let exponent = (decimals - kept).clamp(0, MAX_EXPONENT as i64) as u32;
+    let rounded = round_to(value, exponent, format.rounding)?;
+
+    let digits = format!(
+        "{:0>width$}",
+        rounded.to_string(),
+        width = format.decimals as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - format.decimals as usize);
+    let mut fraction = &fraction[..kept.clamp(0, decimals) as usize];
+    if format.trim_zeros {
+        fraction = fraction.trim_end_matches('0');
+    }
+    let integer = group_thousands(integer, &format.thousands_separator);
+    if fraction.is_empty() {
+        Ok(integer)
+    } else {
+        Ok(format!("{integer}{}{fraction}", format.decimal_mark))
+    }
+}
+
+/// the format with the thousands separator and the decimal mark of the locale
+/// (e.g. "en-US", "de", "fr_FR"; English for the unknown locales), rounded to
+/// 4 fraction digits without the trailing zeros
+pub fn amount_format_for_locale(locale: String, decimals: u32) -> AmountFormat {
+    let language = locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let region = locale
+        .split(['-', '_'])
+        .nth(1)
+        .unwrap_or_default()
+        .to_uppercase();
+    let (thousands_separator, decimal_mark) = match (language.as_str(), region.as_str()) {
+        ("de" | "it" | "fr", "CH") => ("\u{2019}", "."),
+        ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "vi", _) => (".", ","),
+        ("fr" | "ru" | "pl" | "cs" | "sk" | "uk" | "sv" | "fi" | "nb" | "no", _) => ("\u{a0}", ","),
+        _ => (",", "."),
+    };
+    AmountFormat {
+        decimals,
+        max_fraction_digits: 4,
+        significant_digits: 0,
+        rounding: RoundingMode::Round,
+        trim_zeros: true,
+        thousands_separator: thousands_separator.to_owned(),
+        decimal_mark: decimal_mark.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(
+        decimals: u32,
+        fraction: u32,
+        significant: u32,
+        rounding: RoundingMode,
+    ) -> AmountFormat {
+        AmountFormat {
+            decimals,
+            max_fraction_digits: fraction,
+            significant_digits: significant,
+            rounding,
+            trim_zeros: false,
+            thousands_separator: ",".into(),
+            decimal_mark: ".".into(),
+        }
+    }
+
+    #[test]
+    fn test_format_fixed_digits() {
+        let amount = "1234567890000000000000".to_owned();
+        let fixed = |fraction, rounding| {
+            format_amount(amount.clone(), &format(18, fraction, 0, rounding)).unwrap()
+        };
+        assert_eq!(fixed(2, RoundingMode::Round), "1,234.57");
+        assert_eq!(fixed(2, RoundingMode::Floor), "1,234.56");
+        assert_eq!(fixed(0, RoundingMode::Ceil), "1,235");
+        assert_eq!(fixed(6, RoundingMode::Floor), "1,234.567890");
+        assert_eq!(
+            format_amount("999999".into(), &format(6, 2, 0, RoundingMode::Round)).unwrap(),
+            "1.00"
+        );
+        assert_eq!(
+            format_amount("0x0".into(), &format(18, 2, 0, RoundingMode::Round)).unwrap(),
+            "0.00"
+        );
+        // the amounts of the tokens without decimals
+        assert_eq!(
+            format_amount("1500".into(), &format(0, 2, 0, RoundingMode::Round)).unwrap(),
+            "1,500"
+        );
+        assert!(format_amount("12.5".into(), &format(0, 2, 0, RoundingMode::Round)).is_err());
+        assert!(
+            format_amount(U256::MAX.to_string(), &format(0, 0, 1, RoundingMode::Ceil)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_round_to_max_exponent() {
+        let nine = U256::exp10(76) * 9;
+        assert_eq!(
+            round_to(nine, MAX_EXPONENT, RoundingMode::Round).unwrap(),
+            U256::exp10(77)
+        );
+        assert_eq!(
+            round_to(U256::exp10(76) * 4, MAX_EXPONENT, RoundingMode::Round).unwrap(),
+            U256::zero()
+        );
+        assert!(round_to(U256::MAX, MAX_EXPONENT, RoundingMode::Ceil).is_err());
+    }
+
+    #[test]
+    fn test_format_significant_digits() {
+        let significant = |amount: &str, digits| {
+            format_amount(amount.into(), &format(8, 0, digits, RoundingMode::Round)).unwrap()
+        };
+        assert_eq!(significant("123456", 3), "0.00123");
+        assert_eq!(significant("123456789012", 3), "1,230");
+        assert_eq!(significant("123456789012", 6), "1,234.57");
+        assert_eq!(significant("0", 3), "0.00");
+    }
+
+    #[test]
+    fn test_locale_format() {
+        let mut german = amount_format_for_locale("de-DE".into(), 18);
+        assert_eq!(
+            format_amount("1234500000000000000000".into(), &german).unwrap(),
+            "1.234,5"
+        );
+        german.trim_zeros = false;
+        assert_eq!(
+            format_amount("1234500000000000000000".into(), &german).unwrap(),
+            "1.234,5000"
+        );
+        let swiss = amount_format_for_locale("de_CH".into(), 2);
+        assert_eq!(
+            format_amount("123456789".into(), &swiss).unwrap(),
+            "1\u{2019}234\u{2019}567.89"
+        );
+        let english = amount_format_for_locale("".into(), 8);
+        assert_eq!(english.decimal_mark, ".");
+        assert_eq!(english.thousands_separator, ",");
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+use crate::cosmos::{get_all_pages, Page, Pagination};
+use crate::ffi::{Asset, AssetAccount, AssetKind, CosmosCoin, RawTokenResult};
+use crate::{chainstate, get_tokens, http, parse_address, rpc};
+
+/// the decimals of the native EVM coins
+const NATIVE_DECIMALS: u32 = 18;
+
+/// the symbol of the native coin of the EVM chain (empty if it's not known)
+fn native_symbol(chain_id: u64) -> &'static str {
+    match chain_id {
+        25 => "CRO",
+        338 => "TCRO",
+        1 => "ETH",
+        _ => "",
+    }
+}
+
+/// the symbol and the decimals of the known Cosmos denoms
+pub(crate) fn cosmos_denom_info(denom: &str) -> Option<(&'static str, u32)> {
+    match denom {
+        "basecro" => Some(("CRO", 8)),
+        "basetcro" => Some(("TCRO", 8)),
+        _ => None,
+    }
+}
+
+impl Asset {
+    fn from_token(chain: &str, token: RawTokenResult) -> Self {
+        let kind = match token.token_type.as_str() {
+            "ERC-721" => AssetKind::Erc721,
+            "ERC-1155" => AssetKind::Erc1155,
+            _ => AssetKind::Erc20,
+        };
+        Self {
+            chain: chain.to_owned(),
+            kind,
+            denom: token.contract_address,
+            token_id: token.id,
+            decimals: token.decimals.parse().unwrap_or_default(),
+            symbol: token.symbol,
+            name: token.name,
+            balance: token.balance,
+        }
+    }
+
+    fn from_cosmos_coin(chain: &str, coin: CosmosCoin) -> Self {
+        // the unknown denoms (e.g. IBC ones) are in their base units
+        let (symbol, decimals) = cosmos_denom_info(&coin.denom).unwrap_or_default();
+        Self {
+            chain: chain.to_owned(),
+            kind: AssetKind::CosmosCoin,
+            symbol: symbol.to_owned(),
+            name: symbol.to_owned(),
+            token_id: String::new(),
+            decimals,
+            denom: coin.denom,
+            balance: coin.amount,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct BalancesPage {
+    balances: Vec<CosmosCoin>,
+    pagination: Option<Pagination>,
+}
+
+impl Page for BalancesPage {
+    type Item = CosmosCoin;
+
+    fn pagination(&self) -> Option<&Pagination> {
+        self.pagination.as_ref()
+    }
+
+    fn into_items(self) -> Vec<CosmosCoin> {
+        self.balances
+    }
+}
+
+#[derive(Deserialize)]
+struct NodeInfo {
+    network: String,
+}
+
+#[derive(Deserialize)]
+struct NodeInfoResponse {
+    default_node_info: NodeInfo,
+}
+
+/// the native coin and the tokens of the EVM account
+async fn get_evm_assets(account: &AssetAccount) -> Result<Vec<Asset>> {
+    if account.rpc_url.is_empty() {
+        return Err(anyhow!("the JSON-RPC url of the EVM account is required"));
+    }
+    let provider = rpc::provider(&account.rpc_url)?;
+    let address = parse_address(&account.evm_address)?;
+    let chain_id = chainstate::chain_id(&provider).await?;
+    let balance = chainstate::balance(&provider, address).await?;
+    let chain = format!("eip155:{chain_id}");
+    let symbol = native_symbol(chain_id);
+    let mut assets = vec![Asset {
+        chain: chain.clone(),
+        kind: AssetKind::Native,
+        denom: String::new(),
+        token_id: String::new(),
+        decimals: NATIVE_DECIMALS,
+        symbol: symbol.to_owned(),
+        name: symbol.to_owned(),
+        balance: balance.to_string(),
+    }];
+    if !account.blockscout_base_url.is_empty() {
+        let tokens = get_tokens(&account.blockscout_base_url, &account.evm_address).await?;
+        assets.extend(
+            tokens
+                .into_iter()
+                .map(|token| Asset::from_token(&chain, token)),
+        );
+    }
+    Ok(assets)
+}
+
+/// the bank balances of the Cosmos account (all the pages)
+async fn get_cosmos_assets(account: &AssetAccount) -> Result<Vec<Asset>> {
+    if account.lcd_url.is_empty() {
+        return Err(anyhow!("the LCD url of the Cosmos account is required"));
+    }
+    let lcd_url = account.lcd_url.trim_end_matches('/');
+    let node_info: NodeInfoResponse = http::get_json_async(&format!(
+        "{lcd_url}/cosmos/base/tendermint/v1beta1/node_info"
+    ))
+    .await?;
+    let chain = format!("cosmos:{}", node_info.default_node_info.network);
+    let url = format!(
+        "{lcd_url}/cosmos/bank/v1beta1/balances/{}",
+        account.cosmos_address
+    );
+    // the pages are queried with the blocking client
+    let coins = tokio::task::spawn_blocking(move || get_all_pages::<BalancesPage>(&url)).await??;
+    Ok(coins
+        .into_iter()
+        .map(|coin| Asset::from_cosmos_coin(&chain, coin))
+        .collect())
+}
+
+/// the assets of the account: the native EVM coin, the BlockScout tokens
+/// and the Cosmos bank balances (the sources with an empty address are skipped)
+pub(crate) async fn get_all_assets(account: &AssetAccount) -> Result<Vec<Asset>> {
+    let mut assets = vec![];
+    if !account.evm_address.is_empty() {
+        assets.extend(get_evm_assets(account).await?);
+    }
+    if !account.cosmos_address.is_empty() {
+        assets.extend(get_cosmos_assets(account).await?);
+    }
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_asset_conversions() {
+        let token: RawTokenResult = serde_json::from_str(
+            r#"{
+                "balance": "5",
+                "contractAddress": "0x93d0c9a35c43f6bc999416a06aadf21e68b29eba",
+                "decimals": "",
+                "id": "1",
+                "name": "Sword",
+                "symbol": "SWD",
+                "type": "ERC-1155"
+            }"#,
+        )
+        .unwrap();
+        let asset = Asset::from_token("eip155:338", token);
+        assert_eq!(asset.kind, AssetKind::Erc1155);
+        assert_eq!(asset.decimals, 0);
+        assert_eq!(asset.token_id, "1");
+        assert_eq!(asset.denom, "0x93d0c9a35c43f6bc999416a06aadf21e68b29eba");
+
+        let asset = Asset::from_cosmos_coin(
+            "cosmos:crypto-org-chain-mainnet-1",
+            CosmosCoin {
+                denom: "basecro".into(),
+                amount: "100000000".into(),
+            },
+        );
+        assert_eq!(asset.kind, AssetKind::CosmosCoin);
+        assert_eq!((asset.symbol.as_str(), asset.decimals), ("CRO", 8));
+        assert_eq!(asset.balance, "100000000");
+        let asset = Asset::from_cosmos_coin(
+            "cosmos:crypto-org-chain-mainnet-1",
+            CosmosCoin {
+                denom: "ibc/6B5A664BF0AF4F71B2F0BAA33141E2F1321242FBD5D19762F541EC971ACB0865"
+                    .into(),
+                amount: "7".into(),
+            },
+        );
+        assert_eq!((asset.symbol.as_str(), asset.decimals), ("", 0));
+    }
+}
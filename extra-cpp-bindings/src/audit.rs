@@ -0,0 +1,106 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+
+use crate::ffi::AuditEntry;
+
+/// The signing requests of a WalletConnect session: kept in memory
+/// and appended to the JSON Lines file (if it's set)
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: Mutex<Vec<AuditEntry>>,
+    path: Mutex<Option<PathBuf>>,
+}
+
+impl AuditLog {
+    /// appends the entries recorded from now on to the file (none if empty)
+    pub(crate) fn set_path(&self, path: String) {
+        *self.path.lock().expect("audit log path lock") =
+            (!path.is_empty()).then(|| PathBuf::from(path));
+    }
+
+    /// records the request (the hash of what the wallet was asked to sign) and its result
+    /// (e.g. the transaction hash or the signature), or its error
+    pub(crate) fn record(&self, method: &str, params_hash: &[u8], result: Result<String, String>) {
+        let entry = AuditEntry {
+            method: method.to_owned(),
+            params_hash: format!("0x{}", hex::encode(params_hash)),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            success: result.is_ok(),
+            result: result.unwrap_or_else(|e| e),
+        };
+        if let Some(path) = self.path.lock().expect("audit log path lock").as_ref() {
+            // the audit log is best-effort: a failed write doesn't fail the request
+            let _ = append_line(path, &entry);
+        }
+        self.entries.lock().expect("audit log lock").push(entry);
+    }
+
+    /// the recorded entries (oldest first)
+    pub(crate) fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().expect("audit log lock").clone()
+    }
+
+    /// the JSON array of the recorded entries
+    pub(crate) fn export_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(
+            &*self.entries.lock().expect("audit log lock"),
+        )?)
+    }
+
+    /// forgets the entries in memory (the file is kept)
+    pub(crate) fn clear(&self) {
+        self.entries.lock().expect("audit log lock").clear();
+    }
+}
+
+fn append_line(path: &PathBuf, entry: &AuditEntry) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_audit_log() {
+        let path = std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let log = AuditLog::default();
+        log.record("personal_sign", &[0xab; 32], Ok("0x01".to_owned()));
+        log.set_path(path.to_string_lossy().into_owned());
+        log.record(
+            "eth_sendTransaction",
+            &[0xcd; 32],
+            Err("user rejected".to_owned()),
+        );
+        let entries = log.entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert_eq!(entries[1].result, "user rejected");
+        assert_eq!(entries[1].params_hash, format!("0x{}", "cd".repeat(32)));
+
+        let exported: Vec<AuditEntry> = serde_json::from_str(&log.export_json().unwrap()).unwrap();
+        assert_eq!(exported, entries);
+        // only the entries recorded after the path was set are in the file
+        let lines = std::fs::read_to_string(&path).unwrap();
+        let persisted: Vec<AuditEntry> = lines
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(persisted, entries[1..]);
+        log.clear();
+        assert!(log.entries().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::types::{Address, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::ffi::{AuthChallenge, AuthToken};
+use crate::hashing::{hmac_sha256, verify_hmac_sha256};
+
+/// how long a challenge can be signed if its TTL is 0
+const DEFAULT_CHALLENGE_TTL_SECS: u64 = 300;
+/// how long a session token is valid if its TTL is 0
+const DEFAULT_TOKEN_TTL_SECS: u64 = 86400;
+/// the fewest bytes of the secret of the tokens
+const MIN_SECRET_LEN: usize = 32;
+/// the most challenges waiting for their signatures (the expired ones are dropped first)
+const MAX_PENDING_CHALLENGES: usize = 100_000;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn base64url(data: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+/// the message of the challenge (signed with `personal_sign`)
+fn challenge_message(
+    domain: &str,
+    address: &str,
+    nonce: &str,
+    issued_at: u64,
+    expires_at: u64,
+) -> String {
+    format!(
+        "{domain} wants you to sign in with your account:\n{address}\n\n\
+         Nonce: {nonce}\nIssued At: {issued_at}\nExpiration Time: {expires_at}"
+    )
+}
+
+/// the value of the line of the challenge message starting with the label (e.g. "Nonce: ")
+fn challenge_field<'a>(message: &'a str, label: &str) -> Option<&'a str> {
+    message.lines().find_map(|line| line.strip_prefix(label))
+}
+
+/// checks the challenge message before it's signed by the address: it must be of the expected
+/// domain and the address, and not expired (so that a client doesn't sign whatever a server asks,
+/// e.g. the challenge of another domain relayed by a phishing site)
+pub(crate) fn check_challenge(
+    message: &str,
+    domain: &str,
+    address: Address,
+    now: u64,
+) -> Result<()> {
+    let invalid = || anyhow!("not an authentication challenge");
+    let mut lines = message.lines();
+    let challenger = lines
+        .next()
+        .and_then(|line| line.strip_suffix(" wants you to sign in with your account:"))
+        .ok_or_else(invalid)?;
+    if challenger != domain {
+        return Err(anyhow!("the challenge is not of {domain}"));
+    }
+    let challenged = lines.next().ok_or_else(invalid)?;
+    if !challenged.eq_ignore_ascii_case(&format!("{address:?}")) {
+        return Err(anyhow!("the challenge is not of {address:?}"));
+    }
+    let expires_at: u64 = challenge_field(message, "Expiration Time: ")
+        .ok_or_else(invalid)?
+        .parse()?;
+    if now >= expires_at {
+        return Err(anyhow!("the challenge expired"));
+    }
+    Ok(())
+}
+
+/// The claims of a session token (a JWT)
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// the address (hexadecimal, 0x-prefixed)
+    sub: String,
+    /// the domain of the server
+    iss: String,
+    iat: u64,
+    exp: u64,
+    /// the nonce of the signed challenge
+    jti: String,
+}
+
+/// a challenge waiting for its signature
+struct PendingChallenge {
+    message: String,
+    address: Address,
+    expires_at: u64,
+}
+
+/// The server half of the sign-in with a wallet: it issues the challenges, verifies their
+/// signatures (once each) and mints the session tokens (JWT, HS256) of the addresses
+pub struct AuthServer {
+    secret: Vec<u8>,
+    domain: String,
+    challenge_ttl_secs: u64,
+    token_ttl_secs: u64,
+    /// by nonce
+    pending: Mutex<HashMap<String, PendingChallenge>>,
+}
+
+impl AuthServer {
+    pub(crate) fn new(
+        secret: Vec<u8>,
+        domain: String,
+        challenge_ttl_secs: u64,
+        token_ttl_secs: u64,
+    ) -> Result<Self> {
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(anyhow!(
+                "the secret must be at least {MIN_SECRET_LEN} bytes"
+            ));
+        }
+        if domain.is_empty() || domain.contains('\n') {
+            return Err(anyhow!("invalid domain"));
+        }
+        let ttl = |ttl, default| if ttl == 0 { default } else { ttl };
+        Ok(Self {
+            secret,
+            domain,
+            challenge_ttl_secs: ttl(challenge_ttl_secs, DEFAULT_CHALLENGE_TTL_SECS),
+            token_ttl_secs: ttl(token_ttl_secs, DEFAULT_TOKEN_TTL_SECS),
+            pending: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn issue_challenge_at(&self, address: &str, now: u64) -> Result<AuthChallenge> {
+        let address = crate::parse_address(address)?;
+        let nonce = hex::encode(thread_rng().gen::<[u8; 16]>());
+        let expires_at = now
+            .checked_add(self.challenge_ttl_secs)
+            .ok_or_else(|| anyhow!("the challenge TTL is too long"))?;
+        let message = challenge_message(
+            &self.domain,
+            &format!("{address:?}"),
+            &nonce,
+            now,
+            expires_at,
+        );
+        let mut pending = self.pending.lock().expect("auth lock");
+        pending.retain(|_, challenge| challenge.expires_at > now);
+        if pending.len() >= MAX_PENDING_CHALLENGES {
+            return Err(anyhow!("too many pending challenges"));
+        }
+        pending.insert(
+            nonce.clone(),
+            PendingChallenge {
+                message: message.clone(),
+                address,
+                expires_at,
+            },
+        );
+        Ok(AuthChallenge {
+            message,
+            nonce,
+            expires_at,
+        })
+    }
+
+    /// issues a challenge to be signed by the address
+    pub fn issue_challenge(&self, address: String) -> Result<AuthChallenge> {
+        self.issue_challenge_at(&address, now())
+    }
+
+    fn verify_response_at(&self, message: &str, signature: &[u8], now: u64) -> Result<AuthToken> {
+        let nonce = challenge_field(message, "Nonce: ")
+            .ok_or_else(|| anyhow!("not an authentication challenge"))?;
+        let unknown = || anyhow!("unknown or already used challenge");
+        let address = {
+            let pending = self.pending.lock().expect("auth lock");
+            let challenge = pending.get(nonce).ok_or_else(unknown)?;
+            if challenge.message != message {
+                return Err(anyhow!("the challenge message was modified"));
+            }
+            if now >= challenge.expires_at {
+                return Err(anyhow!("the challenge expired"));
+            }
+            challenge.address
+        };
+        // the challenge is consumed only by its valid signature (so that anyone knowing
+        // the nonce can't void the challenge of the address with an invalid one)
+        let recovered = Signature::try_from(signature)?.recover(message)?;
+        if recovered != address {
+            return Err(anyhow!("the signature is not of {address:?}"));
+        }
+        self.pending
+            .lock()
+            .expect("auth lock")
+            .remove(nonce)
+            .ok_or_else(unknown)?;
+        let claims = Claims {
+            sub: format!("{address:?}"),
+            iss: self.domain.clone(),
+            iat: now,
+            exp: now
+                .checked_add(self.token_ttl_secs)
+                .ok_or_else(|| anyhow!("the token TTL is too long"))?,
+            jti: nonce.to_owned(),
+        };
+        let header = base64url(br#"{"alg":"HS256","typ":"JWT"}"#);
+        let payload = base64url(&serde_json::to_vec(&claims)?);
+        let signing_input = format!("{header}.{payload}");
+        let mac = hmac_sha256(self.secret.clone(), signing_input.clone().into_bytes());
+        Ok(AuthToken {
+            token: format!("{signing_input}.{}", base64url(&mac)),
+            address: claims.sub,
+            expires_at: claims.exp,
+        })
+    }
+
+    /// verifies the signature (65 bytes, `personal_sign`) of the challenge message
+    /// and mints the session token of its address
+    pub fn verify_response(&self, message: String, signature: Vec<u8>) -> Result<AuthToken> {
+        self.verify_response_at(&message, &signature, now())
+    }
+
+    fn verify_token_at(&self, token: &str, now: u64) -> Result<AuthToken> {
+        let invalid = || anyhow!("invalid session token");
+        let (signing_input, mac) = token.rsplit_once('.').ok_or_else(invalid)?;
+        let mac = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(mac)
+            .map_err(|_| invalid())?;
+        if !verify_hmac_sha256(&self.secret, signing_input.as_bytes(), &mac) {
+            return Err(invalid());
+        }
+        let (_, payload) = signing_input.split_once('.').ok_or_else(invalid)?;
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| invalid())?;
+        let claims: Claims = serde_json::from_slice(&payload)?;
+        if claims.iss != self.domain {
+            return Err(invalid());
+        }
+        if now >= claims.exp {
+            return Err(anyhow!("the session token expired"));
+        }
+        Ok(AuthToken {
+            token: token.to_owned(),
+            address: claims.sub,
+            expires_at: claims.exp,
+        })
+    }
+
+    /// verifies the session token (its signature, domain and expiry)
+    pub fn verify_token(&self, token: String) -> Result<AuthToken> {
+        self.verify_token_at(&token, now())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::signers::LocalWallet;
+    use ethers::utils::hash_message;
+
+    #[test]
+    fn test_auth_challenge_response() {
+        assert!(AuthServer::new(vec![1; 16], "game.example".into(), 0, 0).is_err());
+        let server = AuthServer::new(vec![7; 32], "game.example".into(), 60, 3600).unwrap();
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let address = ethers::signers::Signer::address(&wallet);
+        let now = 1_700_000_000;
+
+        let challenge = server
+            .issue_challenge_at(&format!("{address:?}"), now)
+            .unwrap();
+        assert_eq!(challenge.expires_at, now + 60);
+        // the client checks the challenge before signing it
+        check_challenge(&challenge.message, "game.example", address, now).unwrap();
+        assert!(check_challenge(&challenge.message, "other.example", address, now).is_err());
+        assert!(check_challenge(&challenge.message, "example", address, now).is_err());
+        assert!(check_challenge(&challenge.message, "game.example", Address::zero(), now).is_err());
+        assert!(check_challenge(&challenge.message, "game.example", address, now + 60).is_err());
+        let signature = wallet
+            .sign_hash(hash_message(&challenge.message))
+            .unwrap()
+            .to_vec();
+
+        // an invalid signature doesn't consume the challenge
+        let other = LocalWallet::from_bytes(&[1u8; 32]).unwrap();
+        let forged = other
+            .sign_hash(hash_message(&challenge.message))
+            .unwrap()
+            .to_vec();
+        assert!(server
+            .verify_response_at(&challenge.message, &forged, now + 5)
+            .is_err());
+        let token = server
+            .verify_response_at(&challenge.message, &signature, now + 5)
+            .unwrap();
+        assert_eq!(token.address, format!("{address:?}"));
+        assert_eq!(token.expires_at, now + 5 + 3600);
+        // the challenge can't be replayed
+        assert!(server
+            .verify_response_at(&challenge.message, &signature, now + 5)
+            .is_err());
+
+        let verified = server.verify_token_at(&token.token, now + 10).unwrap();
+        assert_eq!(verified.address, token.address);
+        assert!(server
+            .verify_token_at(&token.token, now + 5 + 3600)
+            .is_err());
+        let other = AuthServer::new(vec![8; 32], "game.example".into(), 0, 0).unwrap();
+        assert!(other.verify_token_at(&token.token, now + 10).is_err());
+        let mut tampered = token.token.clone();
+        tampered.insert(tampered.find('.').unwrap() + 1, 'e');
+        assert!(server.verify_token_at(&tampered, now + 10).is_err());
+
+        // an expired or modified challenge is rejected
+        let challenge = server
+            .issue_challenge_at(&format!("{address:?}"), now)
+            .unwrap();
+        let signature = wallet
+            .sign_hash(hash_message(&challenge.message))
+            .unwrap()
+            .to_vec();
+        assert!(server
+            .verify_response_at(&challenge.message, &signature, now + 60)
+            .is_err());
+        let challenge = server
+            .issue_challenge_at(&format!("{address:?}"), now)
+            .unwrap();
+        let modified = challenge.message.replace("game.example", "evil.example");
+        let signature = wallet.sign_hash(hash_message(&modified)).unwrap().to_vec();
+        assert!(server
+            .verify_response_at(&modified, &signature, now)
+            .is_err());
+
+        // the expiries don't overflow
+        let server = AuthServer::new(vec![7; 32], "game.example".into(), u64::MAX, 0).unwrap();
+        assert!(server
+            .issue_challenge_at(&format!("{address:?}"), now)
+            .is_err());
+        let server = AuthServer::new(vec![7; 32], "game.example".into(), 60, u64::MAX).unwrap();
+        let challenge = server
+            .issue_challenge_at(&format!("{address:?}"), now)
+            .unwrap();
+        let signature = wallet
+            .sign_hash(hash_message(&challenge.message))
+            .unwrap()
+            .to_vec();
+        assert!(server
+            .verify_response_at(&challenge.message, &signature, now)
+            .is_err());
+    }
+}
@@ -0,0 +1,771 @@
+//! plain C interface (see `include/play_sdk_c.h`) for the integrations
+//! that cannot use the C++17 bindings, e.g. older toolchains or other languages.
+//!
+//! conventions:
+//! - every function returns a `PlaySdkStatus`; if it is not `Ok`, the message
+//!   can be read with `play_sdk_last_error` (on the same thread)
+//! - the results are written to the out-parameters; strings and byte buffers
+//!   allocated by the SDK must be released with `play_sdk_string_free`
+//!   and `play_sdk_bytes_free`
+//! - lists of records (history, tokens...) are returned as JSON arrays
+//! - the WalletConnect client is an opaque handle released with `play_sdk_walletconnect_free`
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::ffi::{QueryOption, WalletConnectTxCommon};
+use crate::memory;
+use crate::walletconnect::WalletconnectClient;
+
+thread_local! {
+    /// the error message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// The result of a C API call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaySdkStatus {
+    Ok = 0,
+    /// a required pointer was null or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// the call failed (see `play_sdk_last_error`)
+    Error = 2,
+    /// the call panicked (see `play_sdk_last_error`)
+    Panic = 3,
+}
+
+/// A byte buffer allocated by the SDK (released with `play_sdk_bytes_free`)
+#[repr(C)]
+pub struct PlaySdkBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl From<Vec<u8>> for PlaySdkBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        PlaySdkBytes { data, len }
+    }
+}
+
+/// The common transaction fields (the same as `WalletConnectTxCommon`;
+/// null strings are treated as empty)
+#[repr(C)]
+pub struct PlaySdkTxCommon {
+    pub gas_limit: *const c_char,
+    pub gas_price: *const c_char,
+    pub nonce: *const c_char,
+    pub chain_id: u64,
+    pub web3api_url: *const c_char,
+    pub max_fee_per_gas: *const c_char,
+    pub max_priority_fee_per_gas: *const c_char,
+    pub max_total_fee: *const c_char,
+}
+
+/// an invalid pointer or string passed by the caller
+#[derive(Debug, thiserror::Error)]
+#[error("invalid argument: {0}")]
+pub(crate) struct InvalidArgument(pub &'static str);
+
+pub(crate) fn set_last_error(message: String) {
+    // the messages do not contain NUL, but just in case
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// runs the call, catches the panics and records the error message
+pub(crate) fn run(f: impl FnOnce() -> Result<()>) -> PlaySdkStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => {
+            LAST_ERROR.with(|e| *e.borrow_mut() = None);
+            PlaySdkStatus::Ok
+        }
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            if e.is::<InvalidArgument>() {
+                PlaySdkStatus::InvalidArgument
+            } else {
+                PlaySdkStatus::Error
+            }
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_owned());
+            set_last_error(format!("panic: {message}"));
+            PlaySdkStatus::Panic
+        }
+    }
+}
+
+/// a required C string argument
+unsafe fn arg_str<'a>(ptr: *const c_char, name: &'static str) -> Result<&'a str> {
+    if ptr.is_null() {
+        return Err(InvalidArgument(name).into());
+    }
+    Ok(CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| InvalidArgument(name))?)
+}
+
+/// an optional C string argument (null is empty)
+unsafe fn opt_str(ptr: *const c_char, name: &'static str) -> Result<String> {
+    if ptr.is_null() {
+        Ok(String::default())
+    } else {
+        Ok(arg_str(ptr, name)?.to_owned())
+    }
+}
+
+pub(crate) unsafe fn arg_address(ptr: *const u8) -> Result<[u8; 20]> {
+    if ptr.is_null() {
+        return Err(InvalidArgument("address").into());
+    }
+    let mut address = [0u8; 20];
+    address.copy_from_slice(std::slice::from_raw_parts(ptr, 20));
+    Ok(address)
+}
+
+pub(crate) unsafe fn arg_client<'a>(
+    client: *mut WalletconnectClient,
+) -> Result<&'a mut WalletconnectClient> {
+    client
+        .as_mut()
+        .ok_or_else(|| InvalidArgument("client").into())
+}
+
+pub(crate) fn query_option(option: u32) -> Result<QueryOption> {
+    match option {
+        0 => Ok(QueryOption::ByContract),
+        1 => Ok(QueryOption::ByAddressAndContract),
+        2 => Ok(QueryOption::ByAddress),
+        _ => Err(InvalidArgument("option").into()),
+    }
+}
+
+/// writes the value to the out-parameter (checked before the call is made)
+pub(crate) unsafe fn write_out<T>(out: *mut T, value: T) {
+    ptr::write(out, value);
+}
+
+pub(crate) fn check_out<T>(out: *mut T, name: &'static str) -> Result<()> {
+    if out.is_null() {
+        return Err(InvalidArgument(name).into());
+    }
+    Ok(())
+}
+
+fn to_c_string(s: String) -> Result<*mut c_char> {
+    Ok(CString::new(s)
+        .map_err(|_| anyhow!("the result contains a NUL character"))?
+        .into_raw())
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<*mut c_char> {
+    to_c_string(serde_json::to_string(value)?)
+}
+
+pub(crate) unsafe fn write_tx_hash(out_tx_hash: *mut u8, tx_hash: &[u8]) -> Result<()> {
+    if tx_hash.len() != 32 {
+        return Err(anyhow!(
+            "unexpected transaction hash length: {}",
+            tx_hash.len()
+        ));
+    }
+    ptr::copy_nonoverlapping(tx_hash.as_ptr(), out_tx_hash, 32);
+    Ok(())
+}
+
+unsafe fn tx_common(common: *const PlaySdkTxCommon) -> Result<WalletConnectTxCommon> {
+    let common = common.as_ref().ok_or(InvalidArgument("common"))?;
+    Ok(WalletConnectTxCommon {
+        gas_limit: opt_str(common.gas_limit, "gas_limit")?,
+        gas_price: opt_str(common.gas_price, "gas_price")?,
+        nonce: opt_str(common.nonce, "nonce")?,
+        chainid: common.chain_id,
+        web3api_url: opt_str(common.web3api_url, "web3api_url")?,
+        max_fee_per_gas: opt_str(common.max_fee_per_gas, "max_fee_per_gas")?,
+        max_priority_fee_per_gas: opt_str(
+            common.max_priority_fee_per_gas,
+            "max_priority_fee_per_gas",
+        )?,
+        max_total_fee: opt_str(common.max_total_fee, "max_total_fee")?,
+    })
+}
+
+/// the error message of the last failed call on the calling thread (empty if none)
+#[cfg(feature = "unity")]
+pub(crate) fn last_error() -> Vec<u8> {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map(|message| message.as_bytes().to_vec())
+            .unwrap_or_default()
+    })
+}
+
+/// the error message of the last failed call on the calling thread (null if none);
+/// it is valid until the next call on the same thread
+#[no_mangle]
+pub extern "C" fn play_sdk_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map(|message| message.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// releases a string returned by the SDK
+///
+/// # Safety
+/// `s` must be null or a string returned by the SDK that was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// releases a byte buffer returned by the SDK
+///
+/// # Safety
+/// `bytes` must be a buffer returned by the SDK that was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_bytes_free(bytes: PlaySdkBytes) {
+    if !bytes.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            bytes.data, bytes.len,
+        )));
+    }
+}
+
+/// forwards the SDK's allocations to the platform allocator (the `memory-stats` feature);
+/// it must be called before any other call of the SDK
+///
+/// # Safety
+/// the functions must allocate and free the memory with the given size and alignment
+/// (null if it fails), and `user_data` must be valid while the SDK is used
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_set_allocator_hooks(
+    alloc: Option<memory::AllocFn>,
+    free: Option<memory::FreeFn>,
+    user_data: *mut c_void,
+) -> PlaySdkStatus {
+    run(|| {
+        let alloc = alloc.ok_or(InvalidArgument("alloc"))?;
+        let free = free.ok_or(InvalidArgument("free"))?;
+        memory::set_allocator_hooks(alloc, free, user_data)
+    })
+}
+
+/// the transactions of the address as a JSON array of `RawTxDetail`
+/// (see `get_transaction_history_blocking`)
+///
+/// # Safety
+/// the strings must be valid NUL-terminated strings, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_get_transaction_history(
+    address: *const c_char,
+    api_key: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let result = crate::get_transaction_history_blocking(
+            arg_str(address, "address")?.to_owned(),
+            opt_str(api_key, "api_key")?,
+        )?;
+        write_out(out_json, to_json(&result)?);
+        Ok(())
+    })
+}
+
+/// the ERC20 transfers as a JSON array of `RawTokenTxDetail`
+/// (option: 0 = by contract, 1 = by address and contract, 2 = by address;
+/// see `get_erc20_transfer_history_blocking`)
+///
+/// # Safety
+/// the strings must be null or valid NUL-terminated strings, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_get_erc20_transfer_history(
+    address: *const c_char,
+    contract_address: *const c_char,
+    option: u32,
+    api_key: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let result = crate::get_erc20_transfer_history_blocking(
+            opt_str(address, "address")?,
+            opt_str(contract_address, "contract_address")?,
+            query_option(option)?,
+            opt_str(api_key, "api_key")?,
+        )?;
+        write_out(out_json, to_json(&result)?);
+        Ok(())
+    })
+}
+
+/// the ERC721 transfers as a JSON array of `RawTokenTxDetail`
+/// (option: 0 = by contract, 1 = by address and contract, 2 = by address;
+/// see `get_erc721_transfer_history_blocking`)
+///
+/// # Safety
+/// the strings must be null or valid NUL-terminated strings, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_get_erc721_transfer_history(
+    address: *const c_char,
+    contract_address: *const c_char,
+    option: u32,
+    api_key: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let result = crate::get_erc721_transfer_history_blocking(
+            opt_str(address, "address")?,
+            opt_str(contract_address, "contract_address")?,
+            query_option(option)?,
+            opt_str(api_key, "api_key")?,
+        )?;
+        write_out(out_json, to_json(&result)?);
+        Ok(())
+    })
+}
+
+/// the owned tokens as a JSON array of `RawTokenResult` (see `get_tokens_blocking`)
+///
+/// # Safety
+/// the strings must be valid NUL-terminated strings, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_get_tokens(
+    blockscout_base_url: *const c_char,
+    account_address: *const c_char,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let result = crate::get_tokens_blocking(
+            arg_str(blockscout_base_url, "blockscout_base_url")?.to_owned(),
+            arg_str(account_address, "account_address")?.to_owned(),
+        )?;
+        write_out(out_json, to_json(&result)?);
+        Ok(())
+    })
+}
+
+/// the token transfers as a JSON array of `RawTokenTxDetail`
+/// (option: 1 = by address and contract, 2 = by address; see `get_token_transfers_blocking`)
+///
+/// # Safety
+/// the strings must be null or valid NUL-terminated strings, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_get_token_transfers(
+    blockscout_base_url: *const c_char,
+    address: *const c_char,
+    contract_address: *const c_char,
+    option: u32,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let result = crate::get_token_transfers_blocking(
+            arg_str(blockscout_base_url, "blockscout_base_url")?.to_owned(),
+            opt_str(address, "address")?,
+            opt_str(contract_address, "contract_address")?,
+            query_option(option)?,
+        )?;
+        write_out(out_json, to_json(&result)?);
+        Ok(())
+    })
+}
+
+/// creates a WalletConnect client (see `walletconnect_new_client`)
+///
+/// # Safety
+/// the strings must be valid NUL-terminated strings, `icon_urls` an array
+/// of `icon_urls_len` strings (or null if 0), `out_client` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_new(
+    description: *const c_char,
+    url: *const c_char,
+    icon_urls: *const *const c_char,
+    icon_urls_len: usize,
+    name: *const c_char,
+    chain_id: u64,
+    out_client: *mut *mut WalletconnectClient,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_client, "out_client")?;
+        let icon_urls = if icon_urls_len == 0 {
+            vec![]
+        } else {
+            if icon_urls.is_null() {
+                return Err(InvalidArgument("icon_urls").into());
+            }
+            std::slice::from_raw_parts(icon_urls, icon_urls_len)
+                .iter()
+                .map(|icon_url| arg_str(*icon_url, "icon_urls").map(str::to_owned))
+                .collect::<Result<Vec<_>>>()?
+        };
+        let client = crate::walletconnect_new_client(
+            arg_str(description, "description")?.to_owned(),
+            arg_str(url, "url")?.to_owned(),
+            icon_urls,
+            arg_str(name, "name")?.to_owned(),
+            chain_id,
+        )?;
+        write_out(out_client, Box::into_raw(client));
+        Ok(())
+    })
+}
+
+/// restores a WalletConnect client from the string of `play_sdk_walletconnect_save`
+///
+/// # Safety
+/// `session_info` must be a valid NUL-terminated string, `out_client` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_restore(
+    session_info: *const c_char,
+    out_client: *mut *mut WalletconnectClient,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_client, "out_client")?;
+        let client =
+            crate::walletconnect_restore_client(arg_str(session_info, "session_info")?.to_owned())?;
+        write_out(out_client, Box::into_raw(client));
+        Ok(())
+    })
+}
+
+/// releases a WalletConnect client (the session can be restored later if it was saved)
+///
+/// # Safety
+/// `client` must be null or a client returned by the SDK that was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_free(client: *mut WalletconnectClient) {
+    if !client.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(client))));
+    }
+}
+
+/// writes the session to a string, which can be stored and restored later
+///
+/// # Safety
+/// `client` must be a valid client, `out_session_info` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_save(
+    client: *mut WalletconnectClient,
+    out_session_info: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_session_info, "out_session_info")?;
+        let session_info = arg_client(client)?.save_client()?;
+        write_out(out_session_info, to_c_string(session_info)?);
+        Ok(())
+    })
+}
+
+/// the connection string to be displayed as a QR code
+///
+/// # Safety
+/// `client` must be a valid client, `out_uri` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_connection_string(
+    client: *mut WalletconnectClient,
+    out_uri: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_uri, "out_uri")?;
+        let uri = arg_client(client)?.get_connection_string()?;
+        write_out(out_uri, to_c_string(uri)?);
+        Ok(())
+    })
+}
+
+/// creates or restores the session (blocks until the wallet approves it);
+/// the addresses are returned as concatenated 20-byte addresses
+///
+/// # Safety
+/// `client` must be a valid client, the out-parameters valid pointers
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_ensure_session(
+    client: *mut WalletconnectClient,
+    out_addresses: *mut PlaySdkBytes,
+    out_chain_id: *mut u64,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_addresses, "out_addresses")?;
+        check_out(out_chain_id, "out_chain_id")?;
+        let result = arg_client(client)?.ensure_session_blocking()?;
+        let addresses: Vec<u8> = result
+            .addresses
+            .iter()
+            .flat_map(|address| address.address)
+            .collect();
+        write_out(out_addresses, addresses.into());
+        write_out(out_chain_id, result.chain_id);
+        Ok(())
+    })
+}
+
+/// signs the message (`personal_sign`), the signature is 65 bytes (r || s || v)
+///
+/// # Safety
+/// `client` must be a valid client, `message` a valid NUL-terminated string,
+/// `address` 20 bytes, `out_signature` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_sign_personal(
+    client: *mut WalletconnectClient,
+    message: *const c_char,
+    address: *const u8,
+    out_signature: *mut PlaySdkBytes,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_signature, "out_signature")?;
+        let signature = arg_client(client)?.sign_personal_blocking(
+            arg_str(message, "message")?.to_owned(),
+            arg_address(address)?,
+        )?;
+        write_out(out_signature, signature.into());
+        Ok(())
+    })
+}
+
+/// signs the transaction (a JSON string of `Eip1559TransactionRequest`)
+/// and returns the signed transaction bytes and its 32-byte hash
+///
+/// # Safety
+/// `client` must be a valid client, `transaction_request` a valid NUL-terminated string,
+/// `address` 20 bytes, `out_raw_tx` a valid pointer, `out_tx_hash` 32 writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_sign_transaction(
+    client: *mut WalletconnectClient,
+    transaction_request: *const c_char,
+    address: *const u8,
+    out_raw_tx: *mut PlaySdkBytes,
+    out_tx_hash: *mut u8,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_raw_tx, "out_raw_tx")?;
+        check_out(out_tx_hash, "out_tx_hash")?;
+        let result = arg_client(client)?.sign_transaction(
+            arg_str(transaction_request, "transaction_request")?.to_owned(),
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &result.tx_hash)?;
+        write_out(out_raw_tx, result.raw_tx.into());
+        Ok(())
+    })
+}
+
+/// sends the transaction (a JSON string of `Eip1559TransactionRequest`)
+/// via the wallet and returns its 32-byte hash
+///
+/// # Safety
+/// `client` must be a valid client, `transaction_request` a valid NUL-terminated string,
+/// `address` 20 bytes, `out_tx_hash` 32 writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_send_transaction(
+    client: *mut WalletconnectClient,
+    transaction_request: *const c_char,
+    address: *const u8,
+    out_tx_hash: *mut u8,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_tx_hash, "out_tx_hash")?;
+        let tx_hash = arg_client(client)?.send_transaction(
+            arg_str(transaction_request, "transaction_request")?.to_owned(),
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &tx_hash)
+    })
+}
+
+/// signs the contract transaction (a JSON string of `ContractAction`)
+/// and returns the signed transaction bytes and its 32-byte hash
+///
+/// # Safety
+/// `client` must be a valid client, `contract_action` a valid NUL-terminated string,
+/// `common` a valid pointer, `address` 20 bytes, `out_raw_tx` a valid pointer,
+/// `out_tx_hash` 32 writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_sign_contract_transaction(
+    client: *mut WalletconnectClient,
+    contract_action: *const c_char,
+    common: *const PlaySdkTxCommon,
+    address: *const u8,
+    out_raw_tx: *mut PlaySdkBytes,
+    out_tx_hash: *mut u8,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_raw_tx, "out_raw_tx")?;
+        check_out(out_tx_hash, "out_tx_hash")?;
+        let result = arg_client(client)?.sign_contract_transaction(
+            arg_str(contract_action, "contract_action")?.to_owned(),
+            &tx_common(common)?,
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &result.tx_hash)?;
+        write_out(out_raw_tx, result.raw_tx.into());
+        Ok(())
+    })
+}
+
+/// sends the contract transaction (a JSON string of `ContractAction`)
+/// via the wallet and returns its 32-byte hash
+///
+/// # Safety
+/// `client` must be a valid client, `contract_action` a valid NUL-terminated string,
+/// `common` a valid pointer, `address` 20 bytes, `out_tx_hash` 32 writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect_send_contract_transaction(
+    client: *mut WalletconnectClient,
+    contract_action: *const c_char,
+    common: *const PlaySdkTxCommon,
+    address: *const u8,
+    out_tx_hash: *mut u8,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_tx_hash, "out_tx_hash")?;
+        let tx_hash = arg_client(client)?.send_contract_transaction(
+            arg_str(contract_action, "contract_action")?.to_owned(),
+            &tx_common(common)?,
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &tx_hash)
+    })
+}
+
+/// detects the version of the saved WalletConnect session and migrates it
+/// (see `walletconnect2_migrate_session`); the result is the JSON of `MigrationOutcome`
+///
+/// # Safety
+/// `session_json` must be a valid NUL-terminated string, `out_json` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_sdk_walletconnect2_migrate_session(
+    session_json: *const c_char,
+    default_chain_id: u64,
+    out_json: *mut *mut c_char,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_json, "out_json")?;
+        let outcome = crate::walletconnect2_migrate_session(
+            arg_str(session_json, "session_json")?.to_owned(),
+            default_chain_id,
+        )?;
+        write_out(out_json, to_json(&outcome)?);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn last_error() -> String {
+        let message = play_sdk_last_error();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_capi_errors() {
+        let mut json = ptr::null_mut();
+        let status =
+            unsafe { play_sdk_get_transaction_history(ptr::null(), ptr::null(), &mut json) };
+        assert_eq!(status, PlaySdkStatus::InvalidArgument);
+        assert_eq!(last_error(), "invalid argument: address");
+
+        let address = CString::new("0xnot-an-address").unwrap();
+        let status =
+            unsafe { play_sdk_get_transaction_history(address.as_ptr(), ptr::null(), &mut json) };
+        assert_eq!(status, PlaySdkStatus::Error);
+        assert!(last_error().starts_with("Invalid address"));
+        assert!(json.is_null());
+
+        let status = run(|| panic!("boom"));
+        assert_eq!(status, PlaySdkStatus::Panic);
+        assert_eq!(last_error(), "panic: boom");
+
+        assert_eq!(run(|| Ok(())), PlaySdkStatus::Ok);
+        assert!(play_sdk_last_error().is_null());
+    }
+
+    #[test]
+    fn test_capi_tx_common() {
+        let gas_limit = CString::new("21000").unwrap();
+        let max_total_fee = CString::new("1000000000000000").unwrap();
+        let common = PlaySdkTxCommon {
+            gas_limit: gas_limit.as_ptr(),
+            gas_price: ptr::null(),
+            nonce: ptr::null(),
+            chain_id: 25,
+            web3api_url: ptr::null(),
+            max_fee_per_gas: ptr::null(),
+            max_priority_fee_per_gas: ptr::null(),
+            max_total_fee: max_total_fee.as_ptr(),
+        };
+        let common = unsafe { tx_common(&common) }.unwrap();
+        assert_eq!(common.gas_limit, "21000");
+        assert_eq!(common.max_total_fee, "1000000000000000");
+        assert!(common.gas_price.is_empty());
+    }
+
+    #[test]
+    fn test_capi_migrate_session() {
+        let session = defi_wallet_connect::v2::SessionInfo::new(
+            "wss://relay.walletconnect.com".parse().unwrap(),
+            "project".to_owned(),
+            defi_wallet_connect::v2::RequiredNamespaces::new(vec![], vec![], vec![]),
+            serde_json::from_str(r#"{"description":"","url":"","icons":[],"name":"Game"}"#)
+                .unwrap(),
+        );
+        let session = CString::new(serde_json::to_string(&session).unwrap()).unwrap();
+        let mut json = ptr::null_mut();
+        let status =
+            unsafe { play_sdk_walletconnect2_migrate_session(session.as_ptr(), 25, &mut json) };
+        assert_eq!(status, PlaySdkStatus::Ok);
+        let outcome: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(json) }.to_str().unwrap()).unwrap();
+        unsafe { play_sdk_string_free(json) };
+        assert_eq!(outcome["current"], true);
+        assert!(outcome["session_info"]
+            .as_str()
+            .unwrap()
+            .contains("relayServer"));
+
+        let session = CString::new("{}").unwrap();
+        let status =
+            unsafe { play_sdk_walletconnect2_migrate_session(session.as_ptr(), 25, &mut json) };
+        assert_eq!(status, PlaySdkStatus::Error);
+    }
+
+    #[test]
+    fn test_capi_buffers() {
+        let bytes = PlaySdkBytes::from(vec![1, 2, 3]);
+        assert_eq!(
+            unsafe { std::slice::from_raw_parts(bytes.data, bytes.len) },
+            [1, 2, 3]
+        );
+        unsafe { play_sdk_bytes_free(bytes) };
+        unsafe { play_sdk_bytes_free(PlaySdkBytes::from(vec![])) };
+
+        let json = to_json(&vec!["a"]).expect("json");
+        assert_eq!(unsafe { CStr::from_ptr(json) }.to_str(), Ok(r#"["a"]"#));
+        unsafe { play_sdk_string_free(json) };
+        assert!(query_option(3).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use ethers::providers::Middleware;
+use ethers::types::{Address, U256};
+
+use crate::ffi::ChainStateCacheStats;
+use crate::rpc::RpcProvider;
+
+/// The state of an account at the latest block (the pending transactions aren't included)
+#[derive(Debug, Clone)]
+struct AccountState {
+    nonce: Option<U256>,
+    balance: Option<U256>,
+    has_code: Option<bool>,
+    cached_at: Instant,
+}
+
+impl AccountState {
+    fn new(now: Instant) -> Self {
+        Self {
+            nonce: None,
+            balance: None,
+            has_code: None,
+            cached_at: now,
+        }
+    }
+}
+
+/// The account states by (chain id, address), shared by the transaction building,
+/// the policy checks and the UI queries; the entries of a chain are dropped
+/// when a newer block of it is observed
+struct ChainStateCache {
+    /// the chain ids of the JSON-RPC endpoints
+    chain_ids: BTreeMap<String, u64>,
+    /// the latest observed block by chain id
+    blocks: BTreeMap<u64, u64>,
+    accounts: BTreeMap<(u64, Address), AccountState>,
+    /// the invalidations by account, so that a fetch started before one isn't cached
+    generations: BTreeMap<(u64, Address), u64>,
+    /// bumped when the entries are dropped at once (a new block, `clear`)
+    epoch: u64,
+    /// zero (the default) disables the cache
+    max_age: Duration,
+    hits: u64,
+    misses: u64,
+}
+
+impl ChainStateCache {
+    const fn new() -> Self {
+        Self {
+            chain_ids: BTreeMap::new(),
+            blocks: BTreeMap::new(),
+            accounts: BTreeMap::new(),
+            generations: BTreeMap::new(),
+            epoch: 0,
+            max_age: Duration::ZERO,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get<T>(
+        &mut self,
+        key: (u64, Address),
+        now: Instant,
+        field: impl Fn(&AccountState) -> Option<T>,
+    ) -> Option<T> {
+        let value = self
+            .accounts
+            .get(&key)
+            .filter(|state| now.duration_since(state.cached_at) < self.max_age)
+            .and_then(field);
+        if value.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        value
+    }
+
+    /// the generation of the entry when its fetch starts
+    fn generation(&self, key: (u64, Address)) -> (u64, u64) {
+        let invalidations = self.generations.get(&key).copied().unwrap_or_default();
+        (self.epoch, invalidations)
+    }
+
+    /// caches the fetched field unless the entry was invalidated since `generation`
+    fn put(
+        &mut self,
+        key: (u64, Address),
+        now: Instant,
+        generation: (u64, u64),
+        update: impl FnOnce(&mut AccountState),
+    ) {
+        if self.max_age.is_zero() || self.generation(key) != generation {
+            return;
+        }
+        let state = self
+            .accounts
+            .entry(key)
+            .or_insert_with(|| AccountState::new(now));
+        if now.duration_since(state.cached_at) >= self.max_age {
+            *state = AccountState::new(now);
+        }
+        update(state);
+    }
+
+    /// drops the entries of the chain if the block is newer than the observed ones
+    fn observe_block(&mut self, chain_id: u64, block: u64) {
+        let latest = self.blocks.entry(chain_id).or_default();
+        if block > *latest {
+            *latest = block;
+            self.accounts.retain(|(chain, _), _| *chain != chain_id);
+            self.epoch += 1;
+        }
+    }
+
+    fn invalidate(&mut self, key: (u64, Address)) {
+        self.accounts.remove(&key);
+        *self.generations.entry(key).or_default() += 1;
+    }
+
+    fn stats(&self) -> ChainStateCacheStats {
+        ChainStateCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.accounts.len() as u64,
+        }
+    }
+}
+
+static CACHE: Mutex<ChainStateCache> = Mutex::new(ChainStateCache::new());
+
+fn cache() -> std::sync::MutexGuard<'static, ChainStateCache> {
+    CACHE.lock().expect("chain state cache lock")
+}
+
+fn endpoint(provider: &RpcProvider) -> String {
+    provider.as_ref().url().to_string()
+}
+
+/// the chain id of the endpoint (requested once)
+pub(crate) async fn chain_id(provider: &RpcProvider) -> Result<u64> {
+    let endpoint = endpoint(provider);
+    if let Some(chain_id) = cache().chain_ids.get(&endpoint) {
+        return Ok(*chain_id);
+    }
+    let chain_id = provider.get_chainid().await?.as_u64();
+    cache().chain_ids.insert(endpoint, chain_id);
+    Ok(chain_id)
+}
+
+/// the cached field of the account or the fetched one
+async fn cached<T: Clone, F: Future<Output = Result<T>>>(
+    provider: &RpcProvider,
+    address: Address,
+    field: impl Fn(&AccountState) -> Option<T>,
+    set: impl FnOnce(&mut AccountState, T),
+    fetch: F,
+) -> Result<T> {
+    let key = (chain_id(provider).await?, address);
+    let generation = {
+        let mut cache = cache();
+        if let Some(value) = cache.get(key, Instant::now(), field) {
+            return Ok(value);
+        }
+        cache.generation(key)
+    };
+    let value = fetch.await?;
+    let cached = value.clone();
+    cache().put(key, Instant::now(), generation, |state| set(state, cached));
+    Ok(value)
+}
+
+/// the number of the mined transactions sent from the address
+pub(crate) async fn nonce(provider: &RpcProvider, address: Address) -> Result<U256> {
+    cached(
+        provider,
+        address,
+        |state| state.nonce,
+        |state, nonce| state.nonce = Some(nonce),
+        async { Ok(provider.get_transaction_count(address, None).await?) },
+    )
+    .await
+}
+
+/// the balance of the native coin of the address (in wei)
+pub(crate) async fn balance(provider: &RpcProvider, address: Address) -> Result<U256> {
+    cached(
+        provider,
+        address,
+        |state| state.balance,
+        |state, balance| state.balance = Some(balance),
+        async { Ok(provider.get_balance(address, None).await?) },
+    )
+    .await
+}
+
+/// whether there is a contract code at the address
+pub(crate) async fn has_code(provider: &RpcProvider, address: Address) -> Result<bool> {
+    cached(
+        provider,
+        address,
+        |state| state.has_code,
+        |state, has_code| state.has_code = Some(has_code),
+        async { Ok(!provider.get_code(address, None).await?.is_empty()) },
+    )
+    .await
+}
+
+/// drops the entries of the endpoint's chain if the block is newer
+/// (nothing is dropped if its chain id wasn't requested yet, as nothing was cached)
+pub(crate) fn observe_block(provider: &RpcProvider, block: u64) {
+    let mut cache = cache();
+    if let Some(chain_id) = cache.chain_ids.get(&endpoint(provider)).copied() {
+        cache.observe_block(chain_id, block);
+    }
+}
+
+/// drops the entry of the account (e.g. after it signed or sent a transaction),
+/// including the values being fetched
+pub(crate) fn invalidate(chain_id: u64, address: Address) {
+    cache().invalidate((chain_id, address));
+}
+
+/// sets the max age of the entries (zero, the default, disables the cache)
+pub(crate) fn set_max_age(max_age: Duration) {
+    let mut cache = cache();
+    cache.max_age = max_age;
+    if max_age.is_zero() {
+        cache.accounts.clear();
+    }
+}
+
+/// drops the entries and resets the statistics
+pub(crate) fn clear() {
+    let mut cache = cache();
+    cache.accounts.clear();
+    cache.blocks.clear();
+    cache.epoch += 1;
+    cache.hits = 0;
+    cache.misses = 0;
+}
+
+pub(crate) fn stats() -> ChainStateCacheStats {
+    cache().stats()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chain_state_cache() {
+        let mut cache = ChainStateCache::new();
+        let now = Instant::now();
+        let cronos = (25, Address::repeat_byte(1));
+        let testnet = (338, Address::repeat_byte(1));
+        let nonce = |state: &AccountState| state.nonce;
+        // disabled by default
+        cache.put(cronos, now, cache.generation(cronos), |state| {
+            state.nonce = Some(7.into())
+        });
+        assert_eq!(cache.get(cronos, now, nonce), None);
+        let max_age = Duration::from_secs(5);
+        cache.max_age = max_age;
+        cache.put(cronos, now, cache.generation(cronos), |state| {
+            state.nonce = Some(7.into())
+        });
+        cache.put(testnet, now, cache.generation(testnet), |state| {
+            state.balance = Some(1.into())
+        });
+        assert_eq!(cache.get(cronos, now, nonce), Some(7.into()));
+        // the fields are cached separately
+        assert_eq!(cache.get(cronos, now, |state| state.balance), None);
+
+        // a new block drops the entries of its chain only
+        cache.observe_block(25, 100);
+        assert_eq!(cache.get(cronos, now, nonce), None);
+        assert_eq!(
+            cache.get(testnet, now, |state| state.balance),
+            Some(1.into())
+        );
+        cache.put(cronos, now, cache.generation(cronos), |state| {
+            state.nonce = Some(8.into())
+        });
+        cache.observe_block(25, 100);
+        assert_eq!(cache.get(cronos, now, nonce), Some(8.into()));
+
+        // the fetches started before an invalidation or a new block aren't cached
+        let generation = cache.generation(cronos);
+        cache.invalidate(cronos);
+        cache.put(cronos, now, generation, |state| {
+            state.nonce = Some(8.into())
+        });
+        assert_eq!(cache.get(cronos, now, nonce), None);
+        let generation = cache.generation(cronos);
+        cache.observe_block(25, 101);
+        cache.put(cronos, now, generation, |state| {
+            state.nonce = Some(8.into())
+        });
+        assert_eq!(cache.get(cronos, now, nonce), None);
+        cache.put(cronos, now, cache.generation(cronos), |state| {
+            state.nonce = Some(9.into())
+        });
+
+        // the old entries are refreshed
+        let later = now + max_age;
+        assert_eq!(cache.get(cronos, later, nonce), None);
+        cache.put(cronos, later, cache.generation(cronos), |state| {
+            state.has_code = Some(false)
+        });
+        assert_eq!(cache.get(cronos, later, nonce), None);
+        assert_eq!(
+            cache.stats(),
+            ChainStateCacheStats {
+                hits: 3,
+                misses: 7,
+                entries: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use ethers::utils::rlp::RlpStream;
+
+/// the hexadecimal encoding of the data (lowercase; 0x-prefixed if `prefix`)
+pub fn hex_encode(data: Vec<u8>, prefix: bool) -> String {
+    if prefix {
+        format!("0x{}", hex::encode(data))
+    } else {
+        hex::encode(data)
+    }
+}
+
+/// decodes the hexadecimal string (with or without the 0x prefix; case-insensitive)
+pub fn hex_decode(hex: String) -> Result<Vec<u8>> {
+    let hex = hex.trim();
+    let digits = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+    hex::decode(digits).map_err(|e| anyhow!("invalid hexadecimal string: {e}"))
+}
+
+/// the (standard, padded) base64 encoding of the data
+pub fn base64_encode(data: Vec<u8>) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+/// decodes the (standard, padded) base64 string
+pub fn base64_decode(encoded: String) -> Result<Vec<u8>> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| anyhow!("invalid base64 string: {e}"))
+}
+
+/// the RLP encoding of the list of strings (as their UTF-8 bytes)
+pub fn rlp_encode_strings(items: Vec<String>) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(items.len());
+    for item in items.iter() {
+        stream.append(&item.as_bytes());
+    }
+    stream.out().to_vec()
+}
+
+/// the RLP encoding of the list of integers
+pub fn rlp_encode_u64s(items: Vec<u64>) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(items.len());
+    for item in items.iter() {
+        stream.append(item);
+    }
+    stream.out().to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex_encode(vec![0xde, 0xad], true), "0xdead");
+        assert_eq!(hex_encode(vec![0xde, 0xad], false), "dead");
+        assert_eq!(hex_decode("0xDEAD".into()).unwrap(), vec![0xde, 0xad]);
+        assert_eq!(hex_decode(" dead\n".into()).unwrap(), vec![0xde, 0xad]);
+        assert!(hex_decode("0x".into()).unwrap().is_empty());
+        assert!(hex_decode("0xabc".into()).is_err());
+        assert!(hex_decode("0xzz".into()).is_err());
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_encode(b"play".to_vec()), "cGxheQ==");
+        assert_eq!(base64_decode("cGxheQ==".into()).unwrap(), b"play");
+        assert!(base64_decode("cGxheQ=".into()).is_err());
+    }
+
+    #[test]
+    fn test_rlp() {
+        // https://ethereum.org/en/developers/docs/data-structures-and-encoding/rlp/#examples
+        assert_eq!(
+            rlp_encode_strings(vec!["cat".into(), "dog".into()]),
+            b"\xc8\x83cat\x83dog"
+        );
+        assert_eq!(rlp_encode_strings(vec![]), vec![0xc0]);
+        assert_eq!(
+            rlp_encode_u64s(vec![0, 15, 1024]),
+            vec![0xc5, 0x80, 0x0f, 0x82, 0x04, 0x00]
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::ffi::{CosmosAccountState, CosmosFeeEstimate, CosmosTransport};
+use crate::grpcweb::{proto_bytes, proto_fields, proto_string, proto_uint, ProtoWriter};
+use crate::http;
+
+/// the ABCI code of the transactions rejected for the wrong sequence
+/// (`ErrWrongSequence` of the Cosmos SDK)
+const WRONG_SEQUENCE_CODE: u32 = 32;
+
+/// the gas prices set via `set_cosmos_gas_price` by the chain ID
+static GAS_PRICES: RwLock<BTreeMap<String, (String, f64)>> = RwLock::new(BTreeMap::new());
+
+/// sets the gas price (in the denom) of the chain used in the fee estimation
+pub(crate) fn set_gas_price(chain_id: String, denom: String, price: f64) -> Result<()> {
+    if !price.is_finite() || price < 0.0 || denom.is_empty() {
+        return Err(anyhow!("invalid gas price {price}{denom}"));
+    }
+    GAS_PRICES
+        .write()
+        .expect("gas prices lock")
+        .insert(chain_id, (denom, price));
+    Ok(())
+}
+
+/// the gas price of the chain (the set one or the minimum one of the known chains)
+fn gas_price(chain_id: &str) -> Option<(String, f64)> {
+    if let Some(price) = GAS_PRICES.read().expect("gas prices lock").get(chain_id) {
+        return Some(price.clone());
+    }
+    match chain_id {
+        "crypto-org-chain-mainnet-1" => Some(("basecro".to_owned(), 0.025)),
+        id if id.starts_with("testnet-croeseid-") => Some(("basetcro".to_owned(), 0.025)),
+        _ => None,
+    }
+}
+
+/// the gas limit (the simulated gas with the adjustment) and its fee at the gas price
+fn fee_estimate(
+    gas_used: u64,
+    gas_adjustment: f64,
+    (denom, price): (String, f64),
+) -> CosmosFeeEstimate {
+    let gas_limit = (gas_used as f64 * gas_adjustment.max(1.0)).ceil() as u64;
+    CosmosFeeEstimate {
+        gas_limit,
+        fee_amount: (gas_limit as f64 * price).ceil() as u64,
+        fee_denom: denom,
+    }
+}
+
+/// the account number and sequence of the LCD (REST) `account` object;
+/// the vesting and module accounts nest the base account
+fn account_state(account: &Value) -> Option<CosmosAccountState> {
+    let number = |key: &str| account.get(key)?.as_str()?.parse::<u64>().ok();
+    match (number("account_number"), number("sequence")) {
+        (Some(account_number), Some(sequence)) => Some(CosmosAccountState {
+            account_number,
+            sequence,
+        }),
+        _ => account
+            .as_object()?
+            .values()
+            .filter(|value| value.is_object())
+            .find_map(account_state),
+    }
+}
+
+/// the account number and sequence of the protobuf `Any` account of `Query/Account`
+/// (the vesting and module accounts nest the base account in their first field)
+fn proto_account_state(any: &[u8]) -> Result<CosmosAccountState> {
+    let fields = proto_fields(any)?;
+    let type_url = proto_string(&fields, 1);
+    let mut account = proto_fields(proto_bytes(&fields, 2))?;
+    let depth = match type_url.as_str() {
+        "/cosmos.auth.v1beta1.BaseAccount" => 0,
+        "/cosmos.auth.v1beta1.ModuleAccount" => 1,
+        url if url.starts_with("/cosmos.vesting.") => 2,
+        url => return Err(anyhow!("unsupported account type {url}")),
+    };
+    for _ in 0..depth {
+        account = proto_fields(proto_bytes(&account, 1))?;
+    }
+    Ok(CosmosAccountState {
+        account_number: proto_uint(&account, 3),
+        sequence: proto_uint(&account, 4),
+    })
+}
+
+/// the pagination of the LCD list responses
+#[derive(Deserialize)]
+pub(crate) struct Pagination {
+    pub(crate) next_key: Option<String>,
+}
+
+/// A page of an LCD list response
+pub(crate) trait Page: DeserializeOwned {
+    type Item;
+    fn pagination(&self) -> Option<&Pagination>;
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// the url of the LCD list page after the key (the first page if there is none)
+pub(crate) fn page_url(url: &str, next_key: Option<&str>) -> Result<String> {
+    match next_key {
+        Some(key) => {
+            let mut page_url = url::Url::parse(url)?;
+            page_url
+                .query_pairs_mut()
+                .append_pair("pagination.key", key);
+            Ok(page_url.to_string())
+        }
+        None => Ok(url.to_owned()),
+    }
+}
+
+/// queries all the pages of the LCD list
+pub(crate) fn get_all_pages<P: Page>(url: &str) -> Result<Vec<P::Item>> {
+    let mut items = vec![];
+    let mut next_key: Option<String> = None;
+    loop {
+        let page: P = http::get_json(&page_url(url, next_key.as_deref())?)?;
+        next_key = page
+            .pagination()
+            .and_then(|pagination| pagination.next_key.clone())
+            .filter(|key| !key.is_empty());
+        items.extend(page.into_items());
+        if next_key.is_none() {
+            return Ok(items);
+        }
+    }
+}
+
+/// The result of Tendermint `broadcast_tx_sync`
+#[derive(Deserialize)]
+struct BroadcastResult {
+    code: u32,
+    #[serde(default)]
+    log: String,
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct BroadcastResponse {
+    result: Option<BroadcastResult>,
+    error: Option<Value>,
+}
+
+/// Keeps the account numbers and sequences of Cosmos accounts (queried from an LCD endpoint),
+/// so that the transactions can be signed without querying them each time
+/// (the sequence is increased after each successful broadcast)
+pub struct CosmosAccountSync {
+    /// the LCD or gRPC-web url (depending on the transport)
+    query_url: String,
+    transport: CosmosTransport,
+    accounts: HashMap<String, CosmosAccountState>,
+}
+
+impl CosmosAccountSync {
+    pub(crate) fn new(query_url: &str, transport: CosmosTransport) -> Result<Self> {
+        url::Url::parse(query_url)?;
+        if !matches!(transport, CosmosTransport::Rest | CosmosTransport::GrpcWeb) {
+            return Err(anyhow!("unknown transport"));
+        }
+        Ok(Self {
+            query_url: query_url.trim_end_matches('/').to_owned(),
+            transport,
+            accounts: HashMap::new(),
+        })
+    }
+
+    /// the account state (queried if it isn't cached)
+    pub fn account_state_blocking(&mut self, address: String) -> Result<CosmosAccountState> {
+        match self.accounts.get(&address) {
+            Some(state) => Ok(*state),
+            None => self.refresh_blocking(address),
+        }
+    }
+
+    /// queries the account state (e.g. after the account was used elsewhere)
+    pub fn refresh_blocking(&mut self, address: String) -> Result<CosmosAccountState> {
+        let state = if self.transport == CosmosTransport::GrpcWeb {
+            let request = ProtoWriter::default().string(1, &address).finish();
+            let response = crate::grpcweb::unary_blocking(
+                &self.query_url,
+                "cosmos.auth.v1beta1.Query/Account",
+                &request,
+            )?;
+            proto_account_state(proto_bytes(&proto_fields(&response)?, 1))?
+        } else {
+            let url = format!("{}/cosmos/auth/v1beta1/accounts/{address}", self.query_url);
+            let response: Value = http::get_json(&url)?;
+            response
+                .get("account")
+                .and_then(account_state)
+                .ok_or_else(|| anyhow!("no account {address}: {response}"))?
+        };
+        self.accounts.insert(address, state);
+        Ok(state)
+    }
+
+    /// forgets the cached account state
+    pub fn invalidate(&mut self, address: String) {
+        self.accounts.remove(&address);
+    }
+
+    /// simulates the signed transaction (e.g. signed with a placeholder fee),
+    /// returns the used gas
+    pub fn simulate_blocking(&self, signed_tx: Vec<u8>) -> Result<u64> {
+        if self.transport == CosmosTransport::GrpcWeb {
+            let request = ProtoWriter::default().bytes(2, &signed_tx).finish();
+            let response = crate::grpcweb::unary_blocking(
+                &self.query_url,
+                "cosmos.tx.v1beta1.Service/Simulate",
+                &request,
+            )?;
+            let gas_info = proto_fields(proto_bytes(&proto_fields(&response)?, 1))?;
+            Ok(proto_uint(&gas_info, 2))
+        } else {
+            use base64::Engine;
+            let client = http::blocking_client()?;
+            let request = client
+                .post(format!("{}/cosmos/tx/v1beta1/simulate", self.query_url))
+                .json(&json!({
+                    "tx_bytes": base64::engine::general_purpose::STANDARD.encode(signed_tx),
+                }));
+            let response: Value = http::send_json(&client, request)?;
+            response["gas_info"]["gas_used"]
+                .as_str()
+                .and_then(|gas| gas.parse().ok())
+                .ok_or_else(|| anyhow!("simulation failed: {response}"))
+        }
+    }
+
+    /// estimates the gas limit and the fee of the signed transaction on the chain:
+    /// the simulated gas is multiplied by the adjustment (at least 1)
+    /// and the fee is at the gas price of the chain
+    pub fn estimate_fee_blocking(
+        &self,
+        chain_id: String,
+        signed_tx: Vec<u8>,
+        gas_adjustment: f64,
+    ) -> Result<CosmosFeeEstimate> {
+        let price = gas_price(&chain_id).ok_or_else(|| anyhow!("no gas price of {chain_id}"))?;
+        let gas_used = self.simulate_blocking(signed_tx)?;
+        Ok(fee_estimate(gas_used, gas_adjustment, price))
+    }
+
+    /// the state after the broadcast of the transaction signed by the account
+    fn on_broadcast(&mut self, address: String, result: &BroadcastResult) {
+        if result.code == 0 {
+            if let Some(state) = self.accounts.get_mut(&address) {
+                state.sequence += 1;
+            }
+        } else if result.code == WRONG_SEQUENCE_CODE {
+            self.accounts.remove(&address);
+        }
+    }
+
+    /// broadcasts via the Tendermint RPC (`broadcast_tx_sync`)
+    fn broadcast_tendermint(
+        tendermint_rpc_url: &str,
+        signed_tx: Vec<u8>,
+    ) -> Result<BroadcastResult> {
+        use base64::Engine;
+        let client = http::blocking_client()?;
+        let request = client.post(tendermint_rpc_url).json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "broadcast_tx_sync",
+            "params": { "tx": base64::engine::general_purpose::STANDARD.encode(signed_tx) },
+        }));
+        let response: BroadcastResponse = http::send_json(&client, request)?;
+        match (response.result, response.error) {
+            (Some(result), _) => Ok(result),
+            (None, error) => Err(anyhow!("broadcast failed: {}", error.unwrap_or_default())),
+        }
+    }
+
+    /// broadcasts via the gRPC-web `Service/BroadcastTx` (in the sync mode)
+    fn broadcast_grpc_web(grpc_web_url: &str, signed_tx: Vec<u8>) -> Result<BroadcastResult> {
+        /// `BROADCAST_MODE_SYNC`
+        const SYNC_MODE: u64 = 2;
+        let request = ProtoWriter::default()
+            .bytes(1, &signed_tx)
+            .uint(2, SYNC_MODE)
+            .finish();
+        let response = crate::grpcweb::unary_blocking(
+            grpc_web_url,
+            "cosmos.tx.v1beta1.Service/BroadcastTx",
+            &request,
+        )?;
+        let tx_response = proto_fields(proto_bytes(&proto_fields(&response)?, 1))?;
+        Ok(BroadcastResult {
+            code: proto_uint(&tx_response, 4) as u32,
+            log: proto_string(&tx_response, 6),
+            hash: proto_string(&tx_response, 2),
+        })
+    }
+
+    /// broadcasts the signed transaction of the account via the Tendermint RPC
+    /// (`broadcast_tx_sync`) or the gRPC-web url (depending on the transport),
+    /// returns the transaction hash
+    pub fn broadcast_blocking(
+        &mut self,
+        broadcast_url: String,
+        address: String,
+        signed_tx: Vec<u8>,
+    ) -> Result<String> {
+        let result = if self.transport == CosmosTransport::GrpcWeb {
+            Self::broadcast_grpc_web(&broadcast_url, signed_tx)?
+        } else {
+            Self::broadcast_tendermint(&broadcast_url, signed_tx)?
+        };
+        self.on_broadcast(address, &result);
+        if result.code != 0 {
+            return Err(anyhow!(
+                "transaction rejected (code {}): {}",
+                result.code,
+                result.log
+            ));
+        }
+        Ok(result.hash)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_account_state() {
+        let base: Value = serde_json::from_str(
+            r#"{"account": {
+                "@type": "/cosmos.auth.v1beta1.BaseAccount",
+                "address": "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy",
+                "pub_key": null,
+                "account_number": "12",
+                "sequence": "5"
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            account_state(&base["account"]),
+            Some(CosmosAccountState {
+                account_number: 12,
+                sequence: 5
+            })
+        );
+        let vesting: Value = serde_json::from_str(
+            r#"{"account": {
+                "@type": "/cosmos.vesting.v1beta1.DelayedVestingAccount",
+                "base_vesting_account": {
+                    "base_account": {
+                        "address": "cro1apdh4yc2lnpephevc6lmpvkyv6s5cjh652n6e4",
+                        "account_number": "7",
+                        "sequence": "0"
+                    },
+                    "original_vesting": [],
+                    "end_time": "1700000000"
+                }
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            account_state(&vesting["account"]),
+            Some(CosmosAccountState {
+                account_number: 7,
+                sequence: 0
+            })
+        );
+        assert_eq!(account_state(&json!({"code": 5})), None);
+    }
+
+    #[test]
+    fn test_sequence_after_broadcast() {
+        let address = "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy".to_owned();
+        let mut sync =
+            CosmosAccountSync::new("http://127.0.0.1:1317/", CosmosTransport::Rest).unwrap();
+        assert_eq!(sync.query_url, "http://127.0.0.1:1317");
+        sync.accounts.insert(
+            address.clone(),
+            CosmosAccountState {
+                account_number: 1,
+                sequence: 5,
+            },
+        );
+        let result = |code| BroadcastResult {
+            code,
+            log: String::default(),
+            hash: String::default(),
+        };
+        sync.on_broadcast(address.clone(), &result(0));
+        assert_eq!(
+            sync.account_state_blocking(address.clone())
+                .unwrap()
+                .sequence,
+            6
+        );
+        // other errors (e.g. insufficient fee) don't use the sequence
+        sync.on_broadcast(address.clone(), &result(13));
+        assert_eq!(sync.accounts[&address].sequence, 6);
+        // queried again after the sequence mismatch
+        sync.on_broadcast(address.clone(), &result(WRONG_SEQUENCE_CODE));
+        assert!(sync.accounts.is_empty());
+
+        assert!(CosmosAccountSync::new("not a url", CosmosTransport::Rest).is_err());
+    }
+
+    #[test]
+    fn test_fee_estimate() {
+        assert_eq!(
+            gas_price("crypto-org-chain-mainnet-1"),
+            Some(("basecro".to_owned(), 0.025))
+        );
+        assert_eq!(gas_price("test-fee-chain"), None);
+        set_gas_price("test-fee-chain".into(), "basetcro".into(), 5000.0).unwrap();
+        assert!(set_gas_price("test-fee-chain".into(), "basetcro".into(), -1.0).is_err());
+        let estimate = fee_estimate(80001, 1.5, gas_price("test-fee-chain").unwrap());
+        assert_eq!(
+            estimate,
+            CosmosFeeEstimate {
+                gas_limit: 120002,
+                fee_amount: 600010000,
+                fee_denom: "basetcro".to_owned(),
+            }
+        );
+        // the adjustment doesn't reduce the simulated gas
+        let estimate = fee_estimate(100001, 0.5, ("basecro".to_owned(), 0.025));
+        assert_eq!(estimate.gas_limit, 100001);
+        assert_eq!(estimate.fee_amount, 2501);
+    }
+
+    #[test]
+    fn test_proto_account_state() {
+        let base_account = ProtoWriter::default()
+            .string(1, "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy")
+            .uint(3, 12)
+            .uint(4, 5)
+            .finish();
+        let any = ProtoWriter::default()
+            .string(1, "/cosmos.auth.v1beta1.BaseAccount")
+            .bytes(2, &base_account)
+            .finish();
+        let expected = CosmosAccountState {
+            account_number: 12,
+            sequence: 5,
+        };
+        assert_eq!(proto_account_state(&any).unwrap(), expected);
+
+        let base_vesting_account = ProtoWriter::default().bytes(1, &base_account).finish();
+        let vesting_account = ProtoWriter::default()
+            .bytes(1, &base_vesting_account)
+            .uint(2, 1700000000)
+            .finish();
+        let any = ProtoWriter::default()
+            .string(1, "/cosmos.vesting.v1beta1.DelayedVestingAccount")
+            .bytes(2, &vesting_account)
+            .finish();
+        assert_eq!(proto_account_state(&any).unwrap(), expected);
+
+        let any = ProtoWriter::default()
+            .string(1, "/ethermint.types.v1.EthAccount")
+            .finish();
+        assert!(proto_account_state(&any).is_err());
+    }
+}
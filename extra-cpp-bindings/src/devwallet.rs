@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Result};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::signers::coins_bip39::English;
+use ethers::signers::{LocalWallet, MnemonicBuilder, Signer};
+use ethers::types::H256;
+use ethers::utils::hash_message;
+
+use crate::ffi::{SignedTxResult, WalletConnectTxEip155};
+use crate::signer;
+use crate::walletconnect::{eip1559_request, signed_tx_result};
+
+/// the mnemonic of the default Hardhat and Anvil accounts
+pub(crate) const DEFAULT_MNEMONIC: &str =
+    "test test test test test test test test test test test junk";
+/// the derivation path of the accounts (BIP-44, Ethereum)
+const DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+/// the most accounts derived by a dev wallet
+const MAX_ACCOUNTS: u32 = 1000;
+
+/// Deterministic accounts derived from a mnemonic that sign locally,
+/// for the automated tests without WalletConnect or real wallets
+/// (only with the `dev-wallet` feature: the keys are not secret)
+pub struct DevWallet {
+    wallets: Vec<LocalWallet>,
+}
+
+impl DevWallet {
+    /// derives the first `count` accounts of the mnemonic (the Hardhat/Anvil one if empty)
+    pub(crate) fn new(mnemonic: &str, count: u32, chain_id: u64) -> Result<Self> {
+        if count == 0 || count > MAX_ACCOUNTS {
+            return Err(anyhow!("the account count must be 1 to {MAX_ACCOUNTS}"));
+        }
+        let mnemonic = if mnemonic.is_empty() {
+            DEFAULT_MNEMONIC
+        } else {
+            mnemonic
+        };
+        let wallets = (0..count)
+            .map(|index| {
+                Ok(MnemonicBuilder::<English>::default()
+                    .phrase(mnemonic)
+                    .derivation_path(&format!("{DERIVATION_PATH}/{index}"))?
+                    .build()?
+                    .with_chain_id(chain_id))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { wallets })
+    }
+
+    fn wallet(&self, index: u32) -> Result<&LocalWallet> {
+        self.wallets
+            .get(index as usize)
+            .ok_or_else(|| anyhow!("no dev account {index}"))
+    }
+
+    fn signer(&self, index: u32) -> Result<&dyn signer::Signer> {
+        Ok(self.wallet(index)?)
+    }
+
+    /// the addresses of the accounts (hexadecimal, 0x-prefixed)
+    pub fn addresses(&self) -> Vec<String> {
+        self.wallets
+            .iter()
+            .map(|wallet| format!("{:?}", wallet.address()))
+            .collect()
+    }
+
+    /// the private key (32 bytes) of the account
+    pub fn private_key(&self, index: u32) -> Result<Vec<u8>> {
+        Ok(self.wallet(index)?.signer().to_bytes().to_vec())
+    }
+
+    /// signs the message with the EIP-191 prefix (like `personal_sign`): 65 bytes (r, s, v)
+    pub fn sign_personal_message(&self, index: u32, message: Vec<u8>) -> Result<Vec<u8>> {
+        crate::policy::check_message(true)?;
+        Ok(self
+            .signer(index)?
+            .sign_digest(hash_message(message))?
+            .to_vec())
+    }
+
+    /// signs the 32-byte digest as it is (like `eth_sign`): 65 bytes (r, s, v)
+    pub fn sign_hash(&self, index: u32, hash: Vec<u8>) -> Result<Vec<u8>> {
+        if hash.len() != 32 {
+            return Err(anyhow!("the hash must be 32 bytes"));
+        }
+        crate::policy::check_message(false)?;
+        Ok(self
+            .signer(index)?
+            .sign_digest(H256::from_slice(&hash))?
+            .to_vec())
+    }
+
+    /// signs the EIP-1559 transaction (on the chain of the wallet if the transaction
+    /// doesn't set it)
+    pub fn sign_eip155_transaction(
+        &self,
+        index: u32,
+        tx: &WalletConnectTxEip155,
+    ) -> Result<SignedTxResult> {
+        let wallet = self.wallet(index)?;
+        let mut request = eip1559_request(tx)?;
+        request.from = Some(wallet.address());
+        let chain_id = if tx.common.chainid != 0 {
+            crate::environment::chain_id(tx.common.chainid)?
+        } else {
+            wallet.chain_id()
+        };
+        request.chain_id = Some(chain_id.into());
+        let typedtx = TypedTransaction::Eip1559(request);
+        let fee_cap = crate::feecap::fee_cap_override(&tx.common)?;
+        let reservation = signer::check_transaction_blocking(&typedtx, fee_cap)?;
+        let sig = signer::sign_transaction(self.signer(index)?, &typedtx)?;
+        reservation.commit();
+        Ok(signed_tx_result(&typedtx, &sig))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::GameSdkError;
+    use crate::ffi::{TransactionPolicy, WalletConnectTxCommon};
+    use ethers::types::Signature;
+
+    #[test]
+    fn test_dev_wallet() {
+        let wallet = DevWallet::new("", 3, 338).unwrap();
+        let addresses = wallet.addresses();
+        // the Hardhat/Anvil default accounts
+        assert_eq!(addresses[0], "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+        assert_eq!(addresses[1], "0x70997970c51812dc3a010c7d01b50e0d17dc79c8");
+        assert_eq!(
+            hex::encode(wallet.private_key(0).unwrap()),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        assert!(wallet.private_key(3).is_err());
+        assert!(DevWallet::new("", 0, 338).is_err());
+
+        let signature = wallet.sign_personal_message(1, b"hello".to_vec()).unwrap();
+        let signature = Signature::try_from(signature.as_slice()).unwrap();
+        assert_eq!(
+            format!("{:?}", signature.recover("hello").unwrap()),
+            addresses[1]
+        );
+
+        let tx = WalletConnectTxEip155 {
+            to: addresses[2].clone(),
+            value: "1000".into(),
+            ..Default::default()
+        };
+        let signed = wallet.sign_eip155_transaction(0, &tx).unwrap();
+        assert_eq!(signed.tx_hash.len(), 32);
+        // a type 2 transaction
+        assert_eq!(signed.raw_tx[0], 2);
+
+        // checked like the WalletConnect transactions
+        let expensive = WalletConnectTxEip155 {
+            common: WalletConnectTxCommon {
+                gas_limit: "21000".into(),
+                max_fee_per_gas: "1000".into(),
+                max_total_fee: "21000".into(),
+                ..Default::default()
+            },
+            ..tx
+        };
+        let error = wallet.sign_eip155_transaction(0, &expensive).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::FeeTooHigh(_))
+        ));
+
+        // the messages are checked like the WalletConnect ones: the raw digests
+        // (like `eth_sign`) are blocked by any policy
+        assert_eq!(wallet.sign_hash(0, vec![1; 32]).unwrap().len(), 65);
+        crate::policy::set_policy(&TransactionPolicy::default()).unwrap();
+        let digest = wallet.sign_hash(0, vec![1; 32]);
+        let personal = wallet.sign_personal_message(0, b"hello".to_vec());
+        crate::policy::clear_policy();
+        assert!(matches!(
+            digest.unwrap_err().downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::PolicyViolation(_))
+        ));
+        assert!(personal.is_ok());
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::TxHash;
+use ethers::utils::keccak256;
+use ethers::utils::rlp::Rlp;
+
+use crate::ffi::DryRunResult;
+use crate::rpc;
+
+/// the number of the recorded dry runs kept (the oldest are dropped)
+const MAX_RESULTS: usize = 100;
+
+/// The dry-run mode and the transactions that would have been sent
+struct DryRun {
+    enabled: bool,
+    /// the JSON-RPC url of the simulations (none if it's empty)
+    rpc_url: String,
+    results: Vec<DryRunResult>,
+}
+
+static DRY_RUN: Mutex<DryRun> = Mutex::new(DryRun {
+    enabled: false,
+    rpc_url: String::new(),
+    results: vec![],
+});
+
+pub(crate) fn set_dry_run(enabled: bool, rpc_url: String) {
+    let mut dry_run = DRY_RUN.lock().expect("dry run lock");
+    dry_run.enabled = enabled;
+    dry_run.rpc_url = rpc_url;
+}
+
+pub(crate) fn is_enabled() -> bool {
+    DRY_RUN.lock().expect("dry run lock").enabled
+}
+
+/// simulates the transaction (via `eth_estimateGas` from its sender, so the reverts fail),
+/// returns the gas estimate (empty without the JSON-RPC url)
+pub(crate) async fn simulate(tx: &TypedTransaction) -> Result<String> {
+    let rpc_url = DRY_RUN.lock().expect("dry run lock").rpc_url.clone();
+    if rpc_url.is_empty() {
+        return Ok(String::new());
+    }
+    let gas = rpc::provider(&rpc_url)?
+        .estimate_gas(tx, None)
+        .await
+        .map_err(|e| anyhow!("dry run: the simulation failed: {e}"))?;
+    Ok(gas.to_string())
+}
+
+/// records the signed raw transaction (as signed, e.g. by the wallet) of the transaction
+/// that would have been sent, returns its hash
+pub(crate) fn record(tx: &TypedTransaction, raw_tx: &[u8], gas_estimate: String) -> TxHash {
+    let tx_hash = TxHash::from(keccak256(raw_tx));
+    let result = DryRunResult {
+        tx_hash: format!("{tx_hash:?}"),
+        raw_tx: raw_tx.to_vec(),
+        from: tx
+            .from()
+            .map(|from| format!("{from:?}"))
+            .unwrap_or_default(),
+        to: tx.to_addr().map(|to| format!("{to:?}")).unwrap_or_default(),
+        value: tx.value().copied().unwrap_or_default().to_string(),
+        gas_estimate,
+    };
+    let mut dry_run = DRY_RUN.lock().expect("dry run lock");
+    if dry_run.results.len() == MAX_RESULTS {
+        dry_run.results.remove(0);
+    }
+    dry_run.results.push(result);
+    tx_hash
+}
+
+/// the dry run of the broadcast of the signed raw transaction: it's decoded, simulated
+/// and recorded, returns its hash
+pub(crate) async fn dry_run_raw_transaction(raw_tx: &[u8]) -> Result<String> {
+    let (mut tx, sig) = TypedTransaction::decode_signed(&Rlp::new(raw_tx))
+        .map_err(|e| anyhow!("invalid raw transaction: {e}"))?;
+    tx.set_from(sig.recover(tx.sighash())?);
+    let gas_estimate = simulate(&tx).await?;
+    Ok(format!("{:?}", record(&tx, raw_tx, gas_estimate)))
+}
+
+/// the transactions that would have been sent, the oldest first
+pub(crate) fn results() -> Vec<DryRunResult> {
+    DRY_RUN.lock().expect("dry run lock").results.clone()
+}
+
+pub(crate) fn clear_results() {
+    DRY_RUN.lock().expect("dry run lock").results.clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{Eip1559TransactionRequest, U256};
+
+    #[test]
+    fn test_dry_run_raw_transaction() {
+        let wallet: LocalWallet =
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+                .parse()
+                .unwrap();
+        let tx = TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .to(wallet.address())
+                .value(1000)
+                .nonce(0)
+                .gas(21000)
+                .max_fee_per_gas(1)
+                .max_priority_fee_per_gas(1)
+                .chain_id(338),
+        );
+        let sig = wallet.sign_transaction_sync(&tx).unwrap();
+        let raw_tx = tx.rlp_signed(&sig);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // without the JSON-RPC url, it isn't simulated
+        let tx_hash = rt.block_on(dry_run_raw_transaction(&raw_tx)).unwrap();
+        assert_eq!(tx_hash, format!("{:?}", tx.hash(&sig)));
+        let result = results().pop().unwrap();
+        assert_eq!(result.tx_hash, tx_hash);
+        assert_eq!(result.raw_tx, raw_tx.to_vec());
+        assert_eq!(result.from, format!("{:?}", wallet.address()));
+        assert_eq!(result.to, result.from);
+        assert_eq!(result.value, U256::from(1000).to_string());
+        assert_eq!(result.gas_estimate, "");
+
+        assert!(rt.block_on(dry_run_raw_transaction(&[1, 2, 3])).is_err());
+    }
+}
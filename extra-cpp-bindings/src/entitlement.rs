@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+use ethers::abi::{Abi, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, BlockNumber, Eip1559TransactionRequest, U256};
+
+use crate::ffi::{AssetRequirement, EntitlementVerdict, RequirementKind};
+use crate::{parse_address, rpc};
+
+/// the methods of the ERC-20, ERC-721 and ERC-1155 contracts checked by the requirements
+const ENTITLEMENT_METHODS: &[&str] = &[
+    "function balanceOf(address owner) view returns (uint256)",
+    "function ownerOf(uint256 tokenId) view returns (address)",
+    "function balanceOf(address account, uint256 id) view returns (uint256)",
+];
+
+fn entitlement_abi() -> Result<Abi> {
+    Ok(ethers::abi::parse_abi(ENTITLEMENT_METHODS)?)
+}
+
+fn parse_u256(value: &str, name: &str) -> Result<U256> {
+    U256::from_dec_str(value).map_err(|_| anyhow!("invalid {name}: {value}"))
+}
+
+/// the minimum amount of the requirement (1 if it's empty)
+fn min_amount(requirement: &AssetRequirement) -> Result<U256> {
+    if requirement.min_amount.is_empty() {
+        Ok(U256::one())
+    } else {
+        parse_u256(&requirement.min_amount, "minimum amount")
+    }
+}
+
+/// the call checking the requirement: the signature of the method and its arguments
+fn requirement_call(
+    requirement: &AssetRequirement,
+    owner: Address,
+) -> Result<(&'static str, Vec<Token>)> {
+    let token_id = || parse_u256(&requirement.token_id, "token id");
+    match requirement.kind {
+        RequirementKind::Erc20MinBalance | RequirementKind::Erc721AnyToken => {
+            Ok(("balanceOf(address)", vec![Token::Address(owner)]))
+        }
+        RequirementKind::Erc721Token => Ok(("ownerOf(uint256)", vec![Token::Uint(token_id()?)])),
+        RequirementKind::Erc1155Amount => Ok((
+            "balanceOf(address,uint256)",
+            vec![Token::Address(owner), Token::Uint(token_id()?)],
+        )),
+        _ => Err(anyhow!("unknown requirement kind")),
+    }
+}
+
+/// whether the output of the call satisfies the requirement, and the observed value
+/// (the balance, or the owner of the ERC-721 token)
+fn evaluate(
+    requirement: &AssetRequirement,
+    owner: Address,
+    output: &[Token],
+) -> Result<(bool, String)> {
+    let output = output
+        .first()
+        .ok_or_else(|| anyhow!("no output of the call"))?;
+    if requirement.kind == RequirementKind::Erc721Token {
+        let token_owner = output
+            .clone()
+            .into_address()
+            .ok_or_else(|| anyhow!("unexpected output: {output}"))?;
+        return Ok((token_owner == owner, format!("{token_owner:?}")));
+    }
+    let balance = output
+        .clone()
+        .into_uint()
+        .ok_or_else(|| anyhow!("unexpected output: {output}"))?;
+    Ok((balance >= min_amount(requirement)?, balance.to_string()))
+}
+
+/// checks one requirement at the block, the error is in the verdict
+async fn check(
+    provider: &rpc::RpcProvider,
+    abi: &Abi,
+    owner: Address,
+    requirement: &AssetRequirement,
+    block: BlockId,
+    verdict: &mut EntitlementVerdict,
+) -> Result<()> {
+    let contract = parse_address(&requirement.contract_address)?;
+    let (signature, args) = requirement_call(requirement, owner)?;
+    let function = abi
+        .functions()
+        .find(|function| function.signature().starts_with(signature))
+        .ok_or_else(|| anyhow!("no method {signature}"))?;
+    let data = function.encode_input(&args)?;
+    verdict.contract_address = format!("{contract:?}");
+    verdict.call_data = format!("0x{}", hex::encode(&data));
+    let tx = TypedTransaction::Eip1559(Eip1559TransactionRequest::new().to(contract).data(data));
+    let output = provider.call(&tx, Some(block)).await?;
+    let (satisfied, observed) = evaluate(requirement, owner, &function.decode_output(&output)?)?;
+    verdict.satisfied = satisfied;
+    verdict.observed = observed;
+    Ok(())
+}
+
+/// checks whether the address satisfies each requirement; all the calls are made
+/// at the same (latest) block, which is in the verdicts with the calls as their proof
+pub async fn check_entitlements(
+    rpc_url: &str,
+    address: &str,
+    requirements: &[AssetRequirement],
+) -> Result<Vec<EntitlementVerdict>> {
+    let owner = parse_address(address)?;
+    let abi = entitlement_abi()?;
+    let provider = rpc::provider(rpc_url)?;
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| anyhow!("no latest block"))?;
+    let block_number = block
+        .number
+        .ok_or_else(|| anyhow!("the latest block is pending"))?;
+    let block_hash = block.hash.unwrap_or_default();
+    let mut verdicts = Vec::with_capacity(requirements.len());
+    for requirement in requirements {
+        let mut verdict = EntitlementVerdict {
+            satisfied: false,
+            contract_address: requirement.contract_address.clone(),
+            observed: String::new(),
+            block_number: block_number.as_u64(),
+            block_hash: format!("{block_hash:?}"),
+            call_data: String::new(),
+            error: String::new(),
+        };
+        if let Err(e) = check(
+            &provider,
+            &abi,
+            owner,
+            requirement,
+            BlockId::Number(block_number.into()),
+            &mut verdict,
+        )
+        .await
+        {
+            // e.g. `ownerOf` reverts if the token doesn't exist
+            verdict.satisfied = false;
+            verdict.error = e.to_string();
+        }
+        verdicts.push(verdict);
+    }
+    Ok(verdicts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn requirement(kind: RequirementKind, token_id: &str, min_amount: &str) -> AssetRequirement {
+        AssetRequirement {
+            kind,
+            contract_address: "0x5C7F8A570d578ED84E63fdFA7b1eE72dEae1AE23".to_owned(),
+            token_id: token_id.to_owned(),
+            min_amount: min_amount.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_entitlement_requirements() {
+        let owner = Address::repeat_byte(1);
+        let balance = |value: u64| vec![Token::Uint(value.into())];
+
+        let erc20 = requirement(RequirementKind::Erc20MinBalance, "", "1000");
+        let (signature, args) = requirement_call(&erc20, owner).unwrap();
+        assert_eq!(signature, "balanceOf(address)");
+        assert_eq!(args, vec![Token::Address(owner)]);
+        assert_eq!(
+            evaluate(&erc20, owner, &balance(1000)).unwrap(),
+            (true, "1000".to_owned())
+        );
+        assert!(!evaluate(&erc20, owner, &balance(999)).unwrap().0);
+
+        // any token of the collection
+        let collection = requirement(RequirementKind::Erc721AnyToken, "", "");
+        assert!(evaluate(&collection, owner, &balance(1)).unwrap().0);
+        assert!(!evaluate(&collection, owner, &balance(0)).unwrap().0);
+
+        let token = requirement(RequirementKind::Erc721Token, "42", "");
+        let (signature, args) = requirement_call(&token, owner).unwrap();
+        assert_eq!(signature, "ownerOf(uint256)");
+        assert_eq!(args, vec![Token::Uint(42.into())]);
+        let (satisfied, observed) = evaluate(&token, owner, &[Token::Address(owner)]).unwrap();
+        assert!(satisfied);
+        assert_eq!(observed, format!("{owner:?}"));
+        let other = [Token::Address(Address::repeat_byte(2))];
+        assert!(!evaluate(&token, owner, &other).unwrap().0);
+        assert!(evaluate(&token, owner, &balance(1)).is_err());
+
+        let erc1155 = requirement(RequirementKind::Erc1155Amount, "7", "5");
+        let (signature, args) = requirement_call(&erc1155, owner).unwrap();
+        assert_eq!(signature, "balanceOf(address,uint256)");
+        assert_eq!(args, vec![Token::Address(owner), Token::Uint(7.into())]);
+        assert!(evaluate(&erc1155, owner, &balance(5)).unwrap().0);
+        assert!(!evaluate(&erc1155, owner, &balance(4)).unwrap().0);
+
+        // the signatures match the methods of the ABI
+        let abi = entitlement_abi().unwrap();
+        for signature in [
+            "balanceOf(address)",
+            "ownerOf(uint256)",
+            "balanceOf(address,uint256)",
+        ] {
+            assert!(abi
+                .functions()
+                .any(|function| function.signature().starts_with(signature)));
+        }
+
+        assert!(
+            requirement_call(&requirement(RequirementKind::Erc721Token, "x", ""), owner).is_err()
+        );
+        assert!(evaluate(
+            &requirement(RequirementKind::Erc20MinBalance, "", "-1"),
+            owner,
+            &balance(1)
+        )
+        .is_err());
+    }
+}
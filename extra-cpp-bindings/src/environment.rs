@@ -0,0 +1,266 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+
+use crate::ffi::{SdkEnvironment, SdkEnvironmentConfig};
+
+/// the WalletConnect 1.0 bridge server of the wallets
+const DEFAULT_RELAY_URL: &str = "https://l.bridge.walletconnect.org";
+/// the faucet of the Cronos testnet
+const TESTNET_FAUCET_URL: &str = "https://cronos.org/faucet/api";
+/// the chain id of Anvil and Hardhat
+pub(crate) const LOCAL_CHAIN_ID: u64 = 31337;
+
+/// the environment of the SDK (none if it's not set: the per-call parameters
+/// and the Cronos mainnet explorer are used)
+static ENVIRONMENT: Mutex<Option<SdkEnvironmentConfig>> = Mutex::new(None);
+
+/// the defaults of the environment
+fn preset(environment: SdkEnvironment) -> Result<SdkEnvironmentConfig> {
+    let config = match environment {
+        SdkEnvironment::Mainnet => SdkEnvironmentConfig {
+            chain_id: 25,
+            rpc_url: "https://evm.cronos.org".to_owned(),
+            explorer_api_url: "https://api.cronoscan.com/api".to_owned(),
+            blockscout_url: "https://cronos.org/explorer/api".to_owned(),
+            relay_url: DEFAULT_RELAY_URL.to_owned(),
+            // no faucet on the mainnet (the testnet one is refused, see `check_url`)
+            faucet_url: String::new(),
+        },
+        SdkEnvironment::Testnet => SdkEnvironmentConfig {
+            chain_id: 338,
+            rpc_url: "https://evm-t3.cronos.org".to_owned(),
+            explorer_api_url: "https://api-testnet.cronoscan.com/api".to_owned(),
+            blockscout_url: "https://cronos.org/explorer/testnet3/api".to_owned(),
+            relay_url: DEFAULT_RELAY_URL.to_owned(),
+            faucet_url: TESTNET_FAUCET_URL.to_owned(),
+        },
+        // e.g. `start_local_node`
+        SdkEnvironment::Local => SdkEnvironmentConfig {
+            chain_id: LOCAL_CHAIN_ID,
+            rpc_url: "http://127.0.0.1:8545".to_owned(),
+            relay_url: DEFAULT_RELAY_URL.to_owned(),
+            ..Default::default()
+        },
+        _ => return Err(anyhow!("unknown SDK environment")),
+    };
+    Ok(config)
+}
+
+pub(crate) fn set_environment(environment: SdkEnvironment) -> Result<()> {
+    set_config(&preset(environment)?)
+}
+
+/// the config with a chain id and valid urls (the empty ones are unset)
+fn validate(config: &SdkEnvironmentConfig) -> Result<()> {
+    if config.chain_id == 0 {
+        return Err(anyhow!("the environment needs a chain id"));
+    }
+    for url in [
+        &config.rpc_url,
+        &config.explorer_api_url,
+        &config.blockscout_url,
+        &config.relay_url,
+        &config.faucet_url,
+    ] {
+        if !url.is_empty() {
+            url::Url::parse(url).map_err(|e| anyhow!("invalid url {url}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn set_config(config: &SdkEnvironmentConfig) -> Result<()> {
+    validate(config)?;
+    *ENVIRONMENT.lock().expect("environment lock") = Some(config.clone());
+    Ok(())
+}
+
+pub(crate) fn clear_environment() {
+    *ENVIRONMENT.lock().expect("environment lock") = None;
+}
+
+/// the config of the environment (none if it's not set)
+pub(crate) fn config() -> Option<SdkEnvironmentConfig> {
+    ENVIRONMENT.lock().expect("environment lock").clone()
+}
+
+fn resolve_chain_id(config: Option<&SdkEnvironmentConfig>, chain_id: u64) -> Result<u64> {
+    match config {
+        Some(config) if chain_id == 0 => Ok(config.chain_id),
+        Some(config) if chain_id != config.chain_id => Err(anyhow!(
+            "the chain id {chain_id} is not the one of the SDK environment ({})",
+            config.chain_id
+        )),
+        _ => Ok(chain_id),
+    }
+}
+
+/// the chain id of the environment if the given one is 0; the other chains
+/// than the environment's are refused
+pub(crate) fn chain_id(chain_id: u64) -> Result<u64> {
+    resolve_chain_id(config().as_ref(), chain_id)
+}
+
+/// the base url of the Cronoscan API of the environment (none if it's not set)
+pub(crate) fn explorer_api_url() -> Result<Option<url::Url>> {
+    match config() {
+        Some(config) if config.explorer_api_url.is_empty() => {
+            Err(anyhow!("no explorer in the SDK environment"))
+        }
+        Some(config) => Ok(Some(config.explorer_api_url.parse()?)),
+        None => Ok(None),
+    }
+}
+
+/// the WalletConnect 1.0 bridge server of the environment (none for the default one)
+pub(crate) fn relay_url() -> Result<Option<url::Url>> {
+    match config() {
+        Some(config) if !config.relay_url.is_empty() => Ok(Some(config.relay_url.parse()?)),
+        _ => Ok(None),
+    }
+}
+
+/// whether the urls are of the same endpoint (regardless of the trailing `/`)
+fn same_url(a: &url::Url, b: &str) -> bool {
+    url::Url::parse(b).is_ok_and(|b| {
+        a.scheme() == b.scheme()
+            && a.host_str() == b.host_str()
+            && a.port_or_known_default() == b.port_or_known_default()
+            && a.path().trim_end_matches('/') == b.path().trim_end_matches('/')
+    })
+}
+
+/// checks the per-call url against the environment: the urls of the presets of the other
+/// chains are refused (e.g. the testnet faucet or JSON-RPC url in the mainnet environment)
+fn check_url(config: Option<&SdkEnvironmentConfig>, url: &str) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    let parsed = url::Url::parse(url).map_err(|e| anyhow!("invalid url {url}: {e}"))?;
+    for environment in [SdkEnvironment::Mainnet, SdkEnvironment::Testnet] {
+        let preset = preset(environment)?;
+        if preset.chain_id == config.chain_id {
+            continue;
+        }
+        let urls = [
+            &preset.rpc_url,
+            &preset.explorer_api_url,
+            &preset.blockscout_url,
+            &preset.faucet_url,
+        ];
+        if urls.iter().any(|preset_url| same_url(&parsed, preset_url)) {
+            return Err(anyhow!(
+                "the url {url} is of the chain {}, not the one of the SDK environment ({})",
+                preset.chain_id,
+                config.chain_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// the per-call url (checked against the environment), or the one of the environment
+/// if it's empty
+fn resolve_url(
+    config: Option<&SdkEnvironmentConfig>,
+    url: &str,
+    configured: fn(&SdkEnvironmentConfig) -> &String,
+    name: &str,
+) -> Result<String> {
+    if !url.is_empty() {
+        check_url(config, url)?;
+        return Ok(url.to_owned());
+    }
+    config
+        .map(|config| configured(config).clone())
+        .filter(|url| !url.is_empty())
+        .ok_or_else(|| anyhow!("no {name} url"))
+}
+
+/// the given faucet url, or the one of the environment if it's empty
+pub(crate) fn faucet_url(faucet_url: &str) -> Result<String> {
+    resolve_url(
+        config().as_ref(),
+        faucet_url,
+        |config| &config.faucet_url,
+        "faucet",
+    )
+}
+
+/// the given JSON-RPC url, or the one of the environment if it's empty
+pub(crate) fn rpc_url(rpc_url: &str) -> Result<String> {
+    resolve_url(
+        config().as_ref(),
+        rpc_url,
+        |config| &config.rpc_url,
+        "JSON-RPC",
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sdk_environment() {
+        let testnet = preset(SdkEnvironment::Testnet).unwrap();
+        assert_eq!(testnet.chain_id, 338);
+        validate(&testnet).unwrap();
+        assert!(!testnet.faucet_url.is_empty());
+        let mainnet = preset(SdkEnvironment::Mainnet).unwrap();
+        assert_eq!(mainnet.chain_id, 25);
+        validate(&mainnet).unwrap();
+        let local = preset(SdkEnvironment::Local).unwrap();
+        assert_eq!(local.chain_id, 31337);
+        assert!(local.explorer_api_url.is_empty());
+
+        assert!(validate(&SdkEnvironmentConfig::default()).is_err());
+        assert!(validate(&SdkEnvironmentConfig {
+            rpc_url: "not a url".to_owned(),
+            ..testnet.clone()
+        })
+        .is_err());
+
+        // the chain ids of the other networks are refused
+        assert_eq!(resolve_chain_id(Some(&testnet), 0).unwrap(), 338);
+        assert_eq!(resolve_chain_id(Some(&testnet), 338).unwrap(), 338);
+        assert!(resolve_chain_id(Some(&testnet), 25).is_err());
+        assert_eq!(resolve_chain_id(None, 25).unwrap(), 25);
+
+        let faucet: fn(&SdkEnvironmentConfig) -> &String = |config| &config.faucet_url;
+        let config = SdkEnvironmentConfig {
+            faucet_url: "http://127.0.0.1:1/faucet".to_owned(),
+            ..testnet.clone()
+        };
+        assert_eq!(
+            resolve_url(Some(&config), "", faucet, "faucet").unwrap(),
+            "http://127.0.0.1:1/faucet"
+        );
+        assert_eq!(
+            resolve_url(Some(&config), "http://x", faucet, "faucet").unwrap(),
+            "http://x"
+        );
+        assert!(resolve_url(None, "", faucet, "faucet").is_err());
+        assert!(resolve_url(Some(&mainnet), "", faucet, "faucet").is_err());
+
+        // the per-call urls of the other chains' presets are refused
+        let rpc: fn(&SdkEnvironmentConfig) -> &String = |config| &config.rpc_url;
+        assert!(resolve_url(Some(&mainnet), &testnet.faucet_url, faucet, "faucet").is_err());
+        assert!(resolve_url(Some(&mainnet), "https://evm-t3.cronos.org/", rpc, "rpc").is_err());
+        assert!(resolve_url(Some(&testnet), "https://evm.cronos.org", rpc, "rpc").is_err());
+        assert_eq!(
+            resolve_url(Some(&testnet), "https://evm-t3.cronos.org", rpc, "rpc").unwrap(),
+            "https://evm-t3.cronos.org"
+        );
+        assert_eq!(
+            resolve_url(Some(&testnet), "", rpc, "rpc").unwrap(),
+            testnet.rpc_url
+        );
+        assert!(resolve_url(Some(&testnet), "not a url", rpc, "rpc").is_err());
+        assert_eq!(
+            resolve_url(None, "https://evm-t3.cronos.org", rpc, "rpc").unwrap(),
+            "https://evm-t3.cronos.org"
+        );
+    }
+}
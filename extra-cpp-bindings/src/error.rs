@@ -1,15 +1,97 @@
 use super::pay::CryptoPayErrorObject;
+use crate::messages::Message;
 
+/// The SDK errors: their texts are rendered from the message catalog
+/// (see `GameSdkError::message`)
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum GameSdkError {
-    #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
-    #[error(transparent)]
     Serde(#[from] serde_json::Error),
-    #[error("Crypto Pay Error: {0:?}")]
     CryptoPayError(CryptoPayErrorObject),
-    #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("Invalid wallet id")]
     InvalidWalletId,
+    InvalidAddress(String),
+    InvalidHttpHeader(String),
+    MissingFixture(String),
+    ScreeningDenied {
+        address: String,
+        reason: String,
+    },
+    /// the message of the violated rule of the policy
+    PolicyViolation(Message),
+    InvalidWebhookSignature(String),
+    NotSignable(String),
+    UnsupportedMethod(String),
+    /// the message of the exceeded cap
+    FeeTooHigh(Message),
+    /// the hash (or CID) the media can't be verified against
+    UnsupportedMediaHash(String),
+}
+
+impl GameSdkError {
+    /// the message of the catalog of the error (its text is the error's text)
+    pub(crate) fn message(&self) -> Message {
+        match self {
+            Self::Reqwest(e) => Message::new("error.http", &[("message", e.to_string())]),
+            Self::Serde(e) => Message::new("error.json", &[("message", e.to_string())]),
+            Self::CryptoPayError(e) => {
+                Message::new("error.crypto_pay", &[("error", format!("{e:?}"))])
+            }
+            Self::Io(e) => Message::new("error.io", &[("message", e.to_string())]),
+            Self::InvalidWalletId => Message::new("error.invalid_wallet_id", &[]),
+            Self::InvalidAddress(address) => {
+                Message::new("error.invalid_address", &[("address", address.clone())])
+            }
+            Self::InvalidHttpHeader(header) => {
+                Message::new("error.invalid_http_header", &[("header", header.clone())])
+            }
+            Self::MissingFixture(path) => {
+                Message::new("error.missing_fixture", &[("path", path.clone())])
+            }
+            Self::ScreeningDenied { address, reason } => Message::new(
+                "error.screening_denied",
+                &[("address", address.clone()), ("reason", reason.clone())],
+            ),
+            Self::PolicyViolation(rule) => {
+                Message::new("error.policy_violation", &[("reason", rule.text())])
+            }
+            Self::InvalidWebhookSignature(reason) => Message::new(
+                "error.invalid_webhook_signature",
+                &[("reason", reason.clone())],
+            ),
+            Self::NotSignable(address) => {
+                Message::new("error.not_signable", &[("address", address.clone())])
+            }
+            Self::UnsupportedMethod(method) => {
+                Message::new("error.unsupported_method", &[("method", method.clone())])
+            }
+            Self::FeeTooHigh(cap) => Message::new("error.fee_too_high", &[("reason", cap.text())]),
+            Self::UnsupportedMediaHash(reason) => Message::new(
+                "error.unsupported_media_hash",
+                &[("reason", reason.clone())],
+            ),
+        }
+    }
+
+    /// the message for the localized error: the violated rule of the policy
+    /// or the exceeded cap of the fees, otherwise `message`
+    pub(crate) fn localized_message(&self) -> Message {
+        match self {
+            Self::PolicyViolation(message) | Self::FeeTooHigh(message) => message.clone(),
+            _ => self.message(),
+        }
+    }
+}
+
+impl std::fmt::Display for GameSdkError {
+    /// the text followed by the tag of the localized message, which `localize_error`
+    /// reads from the texts received by C++
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.message().text(),
+            self.localized_message().tag()
+        )
+    }
 }
@@ -0,0 +1,321 @@
+use std::future::Future;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use bindings_common::timestamp;
+use bindings_common::value::{normalize_value, NATIVE_DECIMALS};
+use ethers::etherscan::account::{Sort, TxListParams};
+use serde::Deserialize;
+
+use crate::ffi::{
+    ExplorerFailoverPolicy, ExplorerSource, QueryOption, RawTokenTxDetail, RawTxDetail, ValueKind,
+};
+use crate::{
+    blockscout_list_params, get_erc20_transfer_history, get_erc721_transfer_history,
+    get_transaction_history, http, normalize_address, token_transfers_url, RawBlockScoutTransfer,
+    RawResponse,
+};
+
+/// the failover policy set via `set_explorer_failover_policy` (disabled if none)
+static POLICY: RwLock<Option<Policy>> = RwLock::new(None);
+
+#[derive(Clone, Debug, Default)]
+struct Policy {
+    blockscout_base_url: String,
+    cronoscan_api_key: String,
+}
+
+/// replaces the failover policy
+pub(crate) fn set_policy(policy: &ExplorerFailoverPolicy) {
+    *POLICY.write().expect("failover policy lock") = if policy.enabled {
+        Some(Policy {
+            blockscout_base_url: policy.blockscout_base_url.clone(),
+            cronoscan_api_key: policy.cronoscan_api_key.clone(),
+        })
+    } else {
+        None
+    };
+}
+
+fn policy() -> Option<Policy> {
+    POLICY.read().expect("failover policy lock").clone()
+}
+
+impl std::fmt::Display for ExplorerSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ExplorerSource::Cronoscan => write!(f, "Cronoscan"),
+            ExplorerSource::BlockScout => write!(f, "BlockScout"),
+            _ => write!(f, "unknown explorer"),
+        }
+    }
+}
+
+/// Normal transaction from BlockScout API (Etherscan-compatible `txlist`)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawBlockScoutTx {
+    block_number: String,
+    /// only in contract creations
+    #[serde(default)]
+    contract_address: String,
+    from: String,
+    hash: String,
+    time_stamp: String,
+    /// empty in contract creations
+    #[serde(default)]
+    to: String,
+    value: String,
+}
+
+/// the address in the same form as in the Cronoscan results
+/// (if it's not a valid address, it's returned as is)
+fn same_address(address: &str) -> String {
+    normalize_address(address).unwrap_or_else(|_| address.to_owned())
+}
+
+impl TryFrom<&RawBlockScoutTx> for RawTxDetail {
+    type Error = anyhow::Error;
+
+    fn try_from(tx: &RawBlockScoutTx) -> Result<Self, Self::Error> {
+        let contract_address = if tx.contract_address.is_empty() {
+            format!("{:?}", ethers::types::Address::zero())
+        } else {
+            same_address(&tx.contract_address)
+        };
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&tx.time_stamp);
+        let (raw_value, formatted_value) = normalize_value(&tx.value, Some(NATIVE_DECIMALS));
+        Ok(Self {
+            hash: tx.hash.to_lowercase(),
+            to_address: same_address(&tx.to),
+            from_address: same_address(&tx.from),
+            value: tx.value.clone(),
+            raw_value,
+            formatted_value,
+            value_kind: ValueKind::Native,
+            block_no: tx.block_number.parse::<u64>()?,
+            timestamp: tx.time_stamp.clone(),
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address,
+            display_name: String::default(),
+        })
+    }
+}
+
+/// queries the primary explorer and, if it fails (e.g. it's rate-limited or down),
+/// the fallback one; returns the explorer that produced the result
+async fn with_failover<T, P, F>(
+    primary: (ExplorerSource, P),
+    fallback: Option<(ExplorerSource, F)>,
+) -> Result<(ExplorerSource, T)>
+where
+    P: Future<Output = Result<T>>,
+    F: Future<Output = Result<T>>,
+{
+    let (primary_source, primary) = primary;
+    let primary_error = match primary.await {
+        Ok(result) => return Ok((primary_source, result)),
+        Err(e) => e,
+    };
+    match fallback {
+        Some((fallback_source, fallback)) => match fallback.await {
+            Ok(result) => Ok((fallback_source, result)),
+            Err(e) => Err(anyhow!(
+                "{primary_source}: {primary_error}; {fallback_source}: {e}"
+            )),
+        },
+        None => Err(primary_error),
+    }
+}
+
+async fn get_blockscout_transaction_history(
+    blockscout_base_url: &str,
+    address: &str,
+    params: TxListParams,
+) -> Result<Vec<RawTxDetail>> {
+    let address = normalize_address(address)?;
+    let blockscout_url = format!(
+        "{blockscout_base_url}?module=account&action=txlist&address={address}{}",
+        blockscout_list_params(params)
+    );
+    let resp: RawResponse<RawBlockScoutTx> = http::get_json_async(&blockscout_url).await?;
+    resp.result.iter().map(TryInto::try_into).collect()
+}
+
+async fn get_blockscout_token_transfers(
+    blockscout_base_url: &str,
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+    params: Option<TxListParams>,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let blockscout_url = token_transfers_url(
+        blockscout_base_url,
+        address,
+        contract_address,
+        option,
+        params,
+    )?;
+    let resp: RawResponse<RawBlockScoutTransfer> = http::get_json_async(&blockscout_url).await?;
+    Ok(resp.result.iter().flat_map(TryInto::try_into).collect())
+}
+
+/// the ERC20 and ERC721 transfers from Cronoscan (ordered by blocks)
+async fn get_cronoscan_token_transfers(
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+    params: TxListParams,
+    api_key: String,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let mut transfers =
+        get_erc20_transfer_history(address, contract_address, option, params, api_key.clone())
+            .await?;
+    transfers.extend(
+        get_erc721_transfer_history(address, contract_address, option, params, api_key).await?,
+    );
+    transfers.sort_by_key(|transfer| transfer.block_no);
+    if matches!(params.sort, Sort::Desc) {
+        transfers.reverse();
+    }
+    Ok(transfers)
+}
+
+/// the transactions from Cronoscan or, if it fails, BlockScout
+pub(crate) async fn transaction_history(
+    address: &str,
+    params: TxListParams,
+    api_key: String,
+) -> Result<(ExplorerSource, Vec<RawTxDetail>)> {
+    let fallback = policy()
+        .filter(|policy| !policy.blockscout_base_url.is_empty())
+        .map(|policy| {
+            let address = address.to_owned();
+            (ExplorerSource::BlockScout, async move {
+                get_blockscout_transaction_history(&policy.blockscout_base_url, &address, params)
+                    .await
+            })
+        });
+    with_failover(
+        (
+            ExplorerSource::Cronoscan,
+            get_transaction_history(address, params, api_key),
+        ),
+        fallback,
+    )
+    .await
+}
+
+/// the token transfers from BlockScout or, if it fails, Cronoscan
+pub(crate) async fn token_transfers(
+    blockscout_base_url: &str,
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+    params: Option<TxListParams>,
+) -> Result<(ExplorerSource, Vec<RawTokenTxDetail>)> {
+    let fallback = policy()
+        .filter(|policy| !policy.cronoscan_api_key.is_empty())
+        .map(|policy| {
+            (
+                ExplorerSource::Cronoscan,
+                get_cronoscan_token_transfers(
+                    address,
+                    contract_address,
+                    option,
+                    params.unwrap_or_default(),
+                    policy.cronoscan_api_key,
+                ),
+            )
+        });
+    with_failover(
+        (
+            ExplorerSource::BlockScout,
+            get_blockscout_token_transfers(
+                blockscout_base_url,
+                address,
+                contract_address,
+                option,
+                params,
+            ),
+        ),
+        fallback,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_with_failover() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let ok = |value: u8| async move { anyhow::Ok(value) };
+        let failed = |message: &'static str| async move { Err::<u8, _>(anyhow!(message)) };
+
+        let result = rt.block_on(with_failover(
+            (ExplorerSource::Cronoscan, ok(1)),
+            Some((ExplorerSource::BlockScout, ok(2))),
+        ));
+        assert_eq!(result.unwrap(), (ExplorerSource::Cronoscan, 1));
+        let result = rt.block_on(with_failover(
+            (ExplorerSource::Cronoscan, failed("Max rate limit reached")),
+            Some((ExplorerSource::BlockScout, ok(2))),
+        ));
+        assert_eq!(result.unwrap(), (ExplorerSource::BlockScout, 2));
+        let result = rt.block_on(with_failover(
+            (ExplorerSource::Cronoscan, failed("Max rate limit reached")),
+            Some((ExplorerSource::BlockScout, failed("down"))),
+        ));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cronoscan: Max rate limit reached; BlockScout: down"
+        );
+        let result = rt.block_on(with_failover(
+            (ExplorerSource::Cronoscan, failed("down")),
+            None::<(ExplorerSource, std::future::Ready<Result<u8>>)>,
+        ));
+        assert_eq!(result.unwrap_err().to_string(), "down");
+    }
+
+    #[test]
+    fn test_blockscout_transaction() {
+        let resp: RawResponse<RawBlockScoutTx> = serde_json::from_str(
+            r#"{"message":"OK","status":"1","result":[{
+                "blockHash": "0x1456b7934898b7c735967e849effc4dc45e84ff32c6c2e130d572cb9589ca652",
+                "blockNumber": "2088372",
+                "confirmations": "537920",
+                "contractAddress": "",
+                "from": "0x9AD08DE843158b0a4f8efdae6ea49caf77bbf13f",
+                "hash": "0x0890c4dce61da8713db5844fa0ae0aa73b74ea6ccfa91c90065ae80471cd908c",
+                "input": "0x",
+                "isError": "0",
+                "timeStamp": "1646318156",
+                "to": "0x841a15d12aec9c6039fd132c2fbff112ed355700",
+                "value": "1000"
+            }]}"#,
+        )
+        .expect("parse");
+        let tx = RawTxDetail::try_from(&resp.result[0]).expect("convert");
+        assert_eq!(
+            tx,
+            RawTxDetail {
+                hash: "0x0890c4dce61da8713db5844fa0ae0aa73b74ea6ccfa91c90065ae80471cd908c".into(),
+                to_address: "0x841a15d12aec9c6039fd132c2fbff112ed355700".into(),
+                from_address: "0x9ad08de843158b0a4f8efdae6ea49caf77bbf13f".into(),
+                value: "1000".into(),
+                raw_value: "1000".into(),
+                formatted_value: "0.000000000000001".into(),
+                value_kind: ValueKind::Native,
+                block_no: 2088372,
+                timestamp: "1646318156".into(),
+                timestamp_epoch: 1646318156,
+                timestamp_iso: "2022-03-03T14:35:56Z".into(),
+                contract_address: "0x0000000000000000000000000000000000000000".into(),
+                display_name: String::default(),
+            }
+        );
+    }
+}
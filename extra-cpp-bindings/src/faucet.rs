@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use reqwest::header::RETRY_AFTER;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::ffi::FaucetResponse;
+use crate::nftstats::json_text;
+use crate::{http, netusage, parse_address};
+
+/// the wait if a rate-limited response doesn't tell it
+const DEFAULT_RETRY_AFTER_SECS: u64 = 60;
+
+/// the first non-empty field of the JSON response
+fn first_text(response: &Value, pointers: &[&str]) -> String {
+    pointers
+        .iter()
+        .map(|pointer| json_text(response, pointer))
+        .find(|text| !text.is_empty())
+        .unwrap_or_default()
+}
+
+/// the faucet response of the status, the `Retry-After` header (in seconds) and the body
+fn faucet_response(
+    status: StatusCode,
+    retry_after: Option<&str>,
+    body: &str,
+) -> Result<FaucetResponse> {
+    let response: Value = serde_json::from_str(body).unwrap_or_else(|_| json!({ "message": body }));
+    let message = first_text(&response, &["/message", "/msg", "/error", "/error/message"]);
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after_secs = retry_after
+            .and_then(|secs| secs.trim().parse().ok())
+            .or_else(|| {
+                first_text(&response, &["/retryAfter", "/retry_after"])
+                    .parse()
+                    .ok()
+            })
+            .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+        return Ok(FaucetResponse {
+            funded: false,
+            message,
+            retry_after_secs,
+            ..Default::default()
+        });
+    }
+    if !status.is_success() {
+        return Err(anyhow!("the faucet failed ({status}): {message}"));
+    }
+    Ok(FaucetResponse {
+        funded: true,
+        tx_hash: first_text(
+            &response,
+            &[
+                "/txHash",
+                "/tx_hash",
+                "/transactionHash",
+                "/hash",
+                "/data/txHash",
+            ],
+        ),
+        amount: first_text(&response, &["/amount", "/data/amount"]),
+        message,
+        retry_after_secs: 0,
+    })
+}
+
+/// requests the test coins to the address from the faucet (a JSON POST of the address
+/// and the captcha token, if any); the rate-limited requests are not funded and tell
+/// when to retry
+pub(crate) fn request_testnet_funds(
+    faucet_url: &str,
+    address: &str,
+    captcha_token: &str,
+) -> Result<FaucetResponse> {
+    let address = format!("{:?}", parse_address(address)?);
+    let mut body = json!({ "address": address });
+    if !captcha_token.is_empty() {
+        body["captcha"] = Value::String(captcha_token.to_owned());
+    }
+    let client = http::blocking_client()?;
+    let response = http::send(&client, client.post(faucet_url).json(&body))?;
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let text = response.text()?;
+    netusage::record_received(text.len() as u64);
+    faucet_response(status, retry_after.as_deref(), &text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_faucet_response() {
+        let funded = faucet_response(
+            StatusCode::OK,
+            None,
+            r#"{"txHash": "0xabc", "amount": "10000000000000000000"}"#,
+        )
+        .unwrap();
+        assert!(funded.funded);
+        assert_eq!(funded.tx_hash, "0xabc");
+
+        let limited = faucet_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some("120"),
+            r#"{"message": "too many requests"}"#,
+        )
+        .unwrap();
+        assert!(!limited.funded);
+        assert_eq!(limited.retry_after_secs, 120);
+        assert_eq!(limited.message, "too many requests");
+        let limited = faucet_response(StatusCode::TOO_MANY_REQUESTS, None, "slow down").unwrap();
+        assert_eq!(limited.retry_after_secs, DEFAULT_RETRY_AFTER_SECS);
+        assert_eq!(limited.message, "slow down");
+
+        let failed = faucet_response(
+            StatusCode::BAD_REQUEST,
+            None,
+            r#"{"error": "invalid captcha"}"#,
+        );
+        assert!(failed.unwrap_err().to_string().contains("invalid captcha"));
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::Mutex;
+
+use anyhow::Result;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+
+use crate::error::GameSdkError;
+use crate::ffi::{FeeCaps, LocalizedMessage, WalletConnectTxCommon, WalletConnectTxEip155};
+use crate::messages::Message;
+
+/// The parsed fee caps
+#[derive(Debug)]
+struct Caps {
+    max_fee_per_gas: Option<U256>,
+    max_total_fee: Option<U256>,
+    warn_fee_per_gas: Option<U256>,
+}
+
+static FEE_CAPS: Mutex<Caps> = Mutex::new(Caps {
+    max_fee_per_gas: None,
+    max_total_fee: None,
+    warn_fee_per_gas: None,
+});
+
+fn cap(value: &str) -> Result<Option<U256>> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(U256::from_dec_str(value)?))
+    }
+}
+
+pub(crate) fn set_fee_caps(caps: &FeeCaps) -> Result<()> {
+    let caps = Caps {
+        max_fee_per_gas: cap(&caps.max_fee_per_gas)?,
+        max_total_fee: cap(&caps.max_total_fee)?,
+        warn_fee_per_gas: cap(&caps.warn_fee_per_gas)?,
+    };
+    *FEE_CAPS.lock().expect("fee caps lock") = caps;
+    Ok(())
+}
+
+pub(crate) fn clear_fee_caps() {
+    let mut caps = FEE_CAPS.lock().expect("fee caps lock");
+    caps.max_fee_per_gas = None;
+    caps.max_total_fee = None;
+    caps.warn_fee_per_gas = None;
+}
+
+/// the cap of the total fee of the transaction overriding the configured caps
+/// (none if `max_total_fee` is empty)
+pub(crate) fn fee_cap_override(common: &WalletConnectTxCommon) -> Result<Option<U256>> {
+    cap(&common.max_total_fee)
+}
+
+/// the max fee per gas of the transaction (the gas price of the legacy ones)
+fn fee_per_gas(tx: &TypedTransaction) -> Option<U256> {
+    match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas,
+        _ => tx.gas_price(),
+    }
+}
+
+/// the warnings of the fees of the transaction, or the `FeeTooHigh` error if they're
+/// over the caps; the override replaces the caps by a cap of the total fee
+fn evaluate(
+    caps: &Caps,
+    tx: &TypedTransaction,
+    fee_cap: Option<U256>,
+) -> Result<Vec<Message>, GameSdkError> {
+    let (max_fee_per_gas, max_total_fee) = match fee_cap {
+        Some(fee_cap) => (None, Some(fee_cap)),
+        None => (caps.max_fee_per_gas, caps.max_total_fee),
+    };
+    let mut warnings = vec![];
+    let Some(fee) = fee_per_gas(tx) else {
+        if max_fee_per_gas.is_some() || max_total_fee.is_some() {
+            warnings.push(Message::new("fee.fee_unset", &[]));
+        }
+        return Ok(warnings);
+    };
+    if let Some(max) = max_fee_per_gas {
+        if fee > max {
+            return Err(GameSdkError::FeeTooHigh(Message::new(
+                "fee.max_fee_per_gas",
+                &[("fee", fee.to_string()), ("max", max.to_string())],
+            )));
+        }
+    }
+    match (max_total_fee, tx.gas()) {
+        (Some(max), Some(gas)) => {
+            let total = gas.saturating_mul(fee);
+            if total > max {
+                return Err(GameSdkError::FeeTooHigh(Message::new(
+                    "fee.max_total_fee",
+                    &[("fee", total.to_string()), ("max", max.to_string())],
+                )));
+            }
+        }
+        (Some(_), None) => warnings.push(Message::new("fee.gas_limit_unset", &[])),
+        _ => {}
+    }
+    if let Some(threshold) = caps.warn_fee_per_gas {
+        if fee > threshold {
+            warnings.push(Message::new(
+                "fee.over_threshold",
+                &[
+                    ("fee", fee.to_string()),
+                    ("threshold", threshold.to_string()),
+                ],
+            ));
+        }
+    }
+    if let TypedTransaction::Eip1559(eip1559) = tx {
+        if let Some(priority) = eip1559.max_priority_fee_per_gas.filter(|p| *p > fee) {
+            warnings.push(Message::new(
+                "fee.priority_over_max",
+                &[("priority", priority.to_string()), ("fee", fee.to_string())],
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+/// checks the fees before the transaction is signed or sent: the ones over the caps
+/// (or over the override) fail with the `FeeTooHigh` error
+pub(crate) fn check_transaction(tx: &TypedTransaction, fee_cap: Option<U256>) -> Result<()> {
+    let caps = FEE_CAPS.lock().expect("fee caps lock");
+    evaluate(&caps, tx, fee_cap)?;
+    Ok(())
+}
+
+/// the warnings of the fees of the transaction (e.g. a priority fee over the max fee),
+/// or the `FeeTooHigh` error if it would be refused by the caps
+pub fn check_transaction_fees(tx: &WalletConnectTxEip155) -> Result<Vec<LocalizedMessage>> {
+    let fee_cap = fee_cap_override(&tx.common)?;
+    let typedtx = TypedTransaction::Eip1559(crate::walletconnect::eip1559_request(tx)?);
+    let caps = FEE_CAPS.lock().expect("fee caps lock");
+    let warnings = evaluate(&caps, &typedtx, fee_cap)?;
+    Ok(warnings.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest};
+
+    fn caps(max_fee_per_gas: u64, max_total_fee: u64) -> Caps {
+        Caps {
+            max_fee_per_gas: Some(max_fee_per_gas.into()),
+            max_total_fee: Some(max_total_fee.into()),
+            warn_fee_per_gas: None,
+        }
+    }
+
+    fn tx(gas: Option<u64>, max_fee_per_gas: u64, priority: u64) -> TypedTransaction {
+        let mut tx = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(priority);
+        tx.gas = gas.map(Into::into);
+        TypedTransaction::Eip1559(tx)
+    }
+
+    fn ids(warnings: Vec<Message>) -> Vec<&'static str> {
+        warnings.into_iter().map(|warning| warning.id).collect()
+    }
+
+    #[test]
+    fn test_fee_caps() {
+        let caps = caps(100, 21_000 * 50);
+        assert!(evaluate(&caps, &tx(Some(21_000), 50, 1), None)
+            .unwrap()
+            .is_empty());
+        let error = evaluate(&caps, &tx(Some(21_000), 101, 1), None).unwrap_err();
+        assert_eq!(
+            error.message().text(),
+            "Fee too high: the max fee per gas 101 is over the cap 100"
+        );
+        let error = evaluate(&caps, &tx(Some(21_000), 51, 1), None).unwrap_err();
+        assert!(matches!(error, GameSdkError::FeeTooHigh(_)));
+        // the legacy gas price
+        let legacy = TypedTransaction::Legacy(TransactionRequest::new().gas_price(101));
+        assert!(evaluate(&caps, &legacy, None).is_err());
+
+        // the override replaces the caps
+        assert!(evaluate(
+            &caps,
+            &tx(Some(21_000), 200, 1),
+            Some((21_000 * 200).into())
+        )
+        .is_ok());
+        assert!(evaluate(&caps, &tx(Some(21_000), 50, 1), Some(1.into())).is_err());
+
+        // the unchecked fees and the sanity warnings
+        assert_eq!(
+            ids(evaluate(&caps, &tx(None, 50, 1), None).unwrap()),
+            vec!["fee.gas_limit_unset"]
+        );
+        let unset = TypedTransaction::Eip1559(Eip1559TransactionRequest::new());
+        assert_eq!(
+            ids(evaluate(&caps, &unset, None).unwrap()),
+            vec!["fee.fee_unset"]
+        );
+        let caps = Caps {
+            max_fee_per_gas: None,
+            max_total_fee: None,
+            warn_fee_per_gas: Some(10.into()),
+        };
+        assert_eq!(
+            ids(evaluate(&caps, &tx(Some(21_000), 50, 60), None).unwrap()),
+            vec!["fee.over_threshold", "fee.priority_over_max"]
+        );
+    }
+}
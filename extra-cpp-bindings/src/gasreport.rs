@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use ethers::abi::Abi;
+use ethers::etherscan::account::{NormalTransaction, TxListParams};
+use ethers::types::{Address, U256};
+
+use crate::ffi::MethodGasUsage;
+use crate::preview::{find_function, STANDARD_METHODS};
+use crate::{get_explorer_result, parse_address, tx_list_params};
+
+/// the gas usage of the calls of a method of a contract
+#[derive(Default)]
+struct Usage {
+    count: u64,
+    failed_count: u64,
+    gas_used: U256,
+    fee: U256,
+}
+
+/// the signature of the method of the selector, e.g. "transfer(address,uint256)",
+/// from the ABIs or the name reported by the explorer (empty if unknown)
+fn method_name(abis: &[Abi], selector: &[u8], tx: &NormalTransaction) -> String {
+    abis.iter()
+        .find_map(|abi| find_function(abi, selector))
+        .map(|function| {
+            let inputs: Vec<String> = function
+                .inputs
+                .iter()
+                .map(|input| input.kind.to_string())
+                .collect();
+            format!("{}({})", function.name, inputs.join(","))
+        })
+        .or_else(|| tx.function_name.clone().filter(|name| !name.is_empty()))
+        .unwrap_or_default()
+}
+
+/// aggregates the transactions sent by the address by their contract and method,
+/// the most expensive first
+fn aggregate(
+    address: Address,
+    transactions: &[NormalTransaction],
+    abi_json: &str,
+) -> Result<Vec<MethodGasUsage>> {
+    let mut abis = vec![ethers::abi::parse_abi(STANDARD_METHODS)?];
+    if !abi_json.trim().is_empty() {
+        abis.insert(0, serde_json::from_str(abi_json)?);
+    }
+    let mut usages: HashMap<(Option<Address>, Vec<u8>), (String, Usage)> = HashMap::new();
+    // the gas is paid by the sender
+    for tx in transactions
+        .iter()
+        .filter(|tx| tx.from.value() == Some(&address))
+    {
+        let selector = tx.input.get(..4).unwrap_or_default().to_vec();
+        let (_, usage) = usages
+            .entry((tx.to, selector.clone()))
+            .or_insert_with(|| (method_name(&abis, &selector, tx), Usage::default()));
+        usage.count += 1;
+        if tx.is_error == "1" {
+            usage.failed_count += 1;
+        }
+        usage.gas_used = usage.gas_used.saturating_add(tx.gas_used);
+        let fee = tx.gas_used.saturating_mul(tx.gas_price.unwrap_or_default());
+        usage.fee = usage.fee.saturating_add(fee);
+    }
+    let mut report: Vec<MethodGasUsage> = usages
+        .into_iter()
+        .map(|((to, selector), (method, usage))| MethodGasUsage {
+            contract_address: to.map(|to| format!("{to:?}")).unwrap_or_default(),
+            selector: if selector.is_empty() {
+                String::new()
+            } else {
+                format!("0x{}", hex::encode(&selector))
+            },
+            method,
+            count: usage.count,
+            failed_count: usage.failed_count,
+            total_gas_used: usage.gas_used.to_string(),
+            average_gas_used: (usage.gas_used / usage.count).to_string(),
+            total_fee: usage.fee.to_string(),
+        })
+        .collect();
+    report.sort_by(|a, b| {
+        let fee = |usage: &MethodGasUsage| U256::from_dec_str(&usage.total_fee).unwrap_or_default();
+        fee(b)
+            .cmp(&fee(a))
+            .then_with(|| a.contract_address.cmp(&b.contract_address))
+            .then_with(|| a.selector.cmp(&b.selector))
+    });
+    Ok(report)
+}
+
+/// the gas usage of the transactions sent by the address (in the block range, page
+/// and order of the params) by contract and method
+pub(crate) async fn gas_report(
+    address: &str,
+    params: TxListParams,
+    abi_json: &str,
+    api_key: String,
+) -> Result<Vec<MethodGasUsage>> {
+    let address = parse_address(address)?;
+    let mut params = tx_list_params(params);
+    params.insert("address", format!("{address:?}"));
+    let transactions: Vec<NormalTransaction> =
+        get_explorer_result("txlist", params, api_key).await?;
+    aggregate(address, &transactions, abi_json)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const SENDER: &str = "0x841a15d12aec9c6039fd132c2fbff112ed355700";
+    const GAME: &str = "0x5c7f8a570d578ed84e63fdfa7b1ee72deae1ae23";
+
+    fn tx(from: &str, to: &str, input: &str, gas_used: u64, is_error: &str) -> NormalTransaction {
+        serde_json::from_value(json!({
+            "blockNumber": "100",
+            "timeStamp": "1700000000",
+            "hash": format!("0x{:064x}", gas_used),
+            "nonce": "1",
+            "blockHash": format!("0x{:064x}", 1),
+            "transactionIndex": "0",
+            "from": from,
+            "to": to,
+            "value": "0",
+            "gas": "100000",
+            "gasPrice": "5000000000000",
+            "isError": is_error,
+            "txreceipt_status": "1",
+            "input": input,
+            "contractAddress": "",
+            "cumulativeGasUsed": "100000",
+            "gasUsed": gas_used.to_string(),
+            "confirmations": "10",
+            "methodId": "",
+            "functionName": ""
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_gas_report() {
+        let abi = r#"[{"type":"function","name":"play","inputs":[{"name":"round","type":"uint256"}],"outputs":[{"name":"","type":"bool"}],"stateMutability":"nonpayable"}]"#;
+        let play = ethers::abi::parse_abi(&["function play(uint256 round)"]).unwrap();
+        let play_input = format!(
+            "0x{}",
+            hex::encode(
+                play.function("play")
+                    .unwrap()
+                    .encode_input(&[ethers::abi::Token::Uint(1.into())])
+                    .unwrap()
+            )
+        );
+        // transfer(address,uint256)
+        let transfer_input = format!("0xa9059cbb{}", "00".repeat(64));
+        let transactions = [
+            tx(SENDER, GAME, &play_input, 50_000, "0"),
+            tx(SENDER, GAME, &play_input, 70_000, "1"),
+            tx(SENDER, GAME, &transfer_input, 30_000, "0"),
+            tx(SENDER, SENDER, "0x", 21_000, "0"),
+            // received, paid by the other sender
+            tx(GAME, SENDER, &play_input, 90_000, "0"),
+        ];
+        let report = aggregate(parse_address(SENDER).unwrap(), &transactions, abi).unwrap();
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[0].contract_address, GAME);
+        assert_eq!(report[0].method, "play(uint256)");
+        assert_eq!((report[0].count, report[0].failed_count), (2, 1));
+        assert_eq!(report[0].total_gas_used, "120000");
+        assert_eq!(report[0].average_gas_used, "60000");
+        assert_eq!(
+            report[0].total_fee,
+            (120_000u64 * 5_000_000_000_000).to_string()
+        );
+        assert_eq!(report[1].method, "transfer(address,uint256)");
+        assert_eq!(report[1].selector, "0xa9059cbb");
+        // the native transfer
+        assert_eq!(
+            (report[2].selector.as_str(), report[2].method.as_str()),
+            ("", "")
+        );
+        assert_eq!(report[2].total_gas_used, "21000");
+
+        // without the ABI, the method is unknown
+        let report = aggregate(parse_address(SENDER).unwrap(), &transactions, "").unwrap();
+        assert_eq!(report[0].method, "");
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::{anyhow, Result};
+
+use crate::{http, netusage};
+
+/// the flag of the trailers frame (the others are the message frames)
+const TRAILERS_FLAG: u8 = 0x80;
+
+/// the message in a gRPC-web frame (uncompressed)
+fn encode_frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(message.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// the `grpc-status` and `grpc-message` of the trailers (or of the headers
+/// in the trailers-only responses); the status is missing if it's not in them
+fn check_status(status: Option<&str>, message: Option<&str>) -> Result<()> {
+    match status.map(str::trim) {
+        Some("0") | None => Ok(()),
+        Some(status) => Err(anyhow!(
+            "gRPC status {status}: {}",
+            message.unwrap_or_default().trim()
+        )),
+    }
+}
+
+/// the message of the gRPC-web response body (it fails with the non-OK status of the trailers)
+fn decode_response(mut body: &[u8]) -> Result<Vec<u8>> {
+    let mut message = None;
+    while !body.is_empty() {
+        if body.len() < 5 {
+            return Err(anyhow!("truncated gRPC-web frame"));
+        }
+        let flag = body[0];
+        let length = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        // the end of the frame (it overflows on the 32-bit targets if the length is too large)
+        let end = length
+            .checked_add(5)
+            .ok_or_else(|| anyhow!("truncated gRPC-web frame"))?;
+        let data = body
+            .get(5..end)
+            .ok_or_else(|| anyhow!("truncated gRPC-web frame"))?;
+        if flag & TRAILERS_FLAG != 0 {
+            let trailers = String::from_utf8_lossy(data);
+            let trailer = |name: &str| {
+                trailers.split("\r\n").find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    key.trim().eq_ignore_ascii_case(name).then_some(value)
+                })
+            };
+            check_status(trailer("grpc-status"), trailer("grpc-message"))?;
+        } else {
+            message.get_or_insert_with(Vec::new).extend_from_slice(data);
+        }
+        body = &body[end..];
+    }
+    message.ok_or_else(|| anyhow!("no message in the gRPC-web response"))
+}
+
+/// calls the unary method (e.g. "cosmos.auth.v1beta1.Query/Account") of the gRPC-web endpoint
+/// with the protobuf-encoded request, returns the protobuf-encoded response
+pub(crate) fn unary_blocking(base_url: &str, method: &str, request: &[u8]) -> Result<Vec<u8>> {
+    let client = http::blocking_client()?;
+    let request = client
+        .post(format!("{}/{method}", base_url.trim_end_matches('/')))
+        .header("content-type", "application/grpc-web+proto")
+        .header("accept", "application/grpc-web+proto")
+        .header("x-grpc-web", "1")
+        .body(encode_frame(request));
+    let response = http::send(&client, request)?;
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned)
+    };
+    check_status(
+        header("grpc-status").as_deref(),
+        header("grpc-message").as_deref(),
+    )?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("gRPC-web HTTP status {status}"));
+    }
+    let body = response.bytes()?;
+    netusage::record_received(body.len() as u64);
+    decode_response(&body)
+}
+
+/// A field value of a protobuf message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProtoValue<'a> {
+    Varint(u64),
+    /// the length-delimited values (bytes, strings and messages)
+    Bytes(&'a [u8]),
+}
+
+/// Minimal protobuf message encoding (only the varint and length-delimited fields)
+#[derive(Debug, Default)]
+pub(crate) struct ProtoWriter(Vec<u8>);
+
+impl ProtoWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    pub(crate) fn uint(mut self, field: u32, value: u64) -> Self {
+        self.varint(u64::from(field) << 3);
+        self.varint(value);
+        self
+    }
+
+    pub(crate) fn bytes(mut self, field: u32, value: &[u8]) -> Self {
+        self.varint(u64::from(field) << 3 | 2);
+        self.varint(value.len() as u64);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    pub(crate) fn string(self, field: u32, value: &str) -> Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+fn read_varint(buf: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = buf
+            .split_first()
+            .ok_or_else(|| anyhow!("truncated protobuf varint"))?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("invalid protobuf varint"))
+}
+
+/// the fields of the protobuf message (the 32-bit and 64-bit ones are skipped)
+pub(crate) fn proto_fields(mut buf: &[u8]) -> Result<Vec<(u32, ProtoValue<'_>)>> {
+    let mut fields = vec![];
+    while !buf.is_empty() {
+        let key = read_varint(&mut buf)?;
+        let field = (key >> 3) as u32;
+        match key & 7 {
+            0 => fields.push((field, ProtoValue::Varint(read_varint(&mut buf)?))),
+            1 | 5 => {
+                let size = if key & 7 == 1 { 8 } else { 4 };
+                buf = buf
+                    .get(size..)
+                    .ok_or_else(|| anyhow!("truncated protobuf field"))?;
+            }
+            2 => {
+                let length = read_varint(&mut buf)? as usize;
+                let value = buf
+                    .get(..length)
+                    .ok_or_else(|| anyhow!("truncated protobuf field"))?;
+                fields.push((field, ProtoValue::Bytes(value)));
+                buf = &buf[length..];
+            }
+            wire_type => return Err(anyhow!("unsupported protobuf wire type {wire_type}")),
+        }
+    }
+    Ok(fields)
+}
+
+/// the last value of the varint field (0 if it's missing, as in protobuf)
+pub(crate) fn proto_uint(fields: &[(u32, ProtoValue<'_>)], field: u32) -> u64 {
+    fields
+        .iter()
+        .rev()
+        .find_map(|(number, value)| match value {
+            ProtoValue::Varint(value) if *number == field => Some(*value),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// the last value of the length-delimited field (empty if it's missing)
+pub(crate) fn proto_bytes<'a>(fields: &[(u32, ProtoValue<'a>)], field: u32) -> &'a [u8] {
+    fields
+        .iter()
+        .rev()
+        .find_map(|(number, value)| match value {
+            ProtoValue::Bytes(value) if *number == field => Some(*value),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// the last value of the string field (empty if it's missing)
+pub(crate) fn proto_string(fields: &[(u32, ProtoValue<'_>)], field: u32) -> String {
+    String::from_utf8_lossy(proto_bytes(fields, field)).into_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proto_fields() {
+        let message = ProtoWriter::default()
+            .string(1, "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy")
+            .uint(3, 300)
+            .bytes(4, &[0xff; 200])
+            .finish();
+        // the length of 200 bytes is a 2-byte varint
+        assert_eq!(&message[44..50], &[0x18, 0xac, 0x02, 0x22, 0xc8, 0x01]);
+        let fields = proto_fields(&message).unwrap();
+        assert_eq!(
+            proto_string(&fields, 1),
+            "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy"
+        );
+        assert_eq!(proto_uint(&fields, 3), 300);
+        assert_eq!(proto_bytes(&fields, 4), &[0xff; 200]);
+        assert_eq!(proto_uint(&fields, 2), 0);
+        assert!(proto_fields(&message[..message.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let message = ProtoWriter::default().uint(1, 5).finish();
+        let mut body = encode_frame(&message);
+        let trailers = b"grpc-status:0\r\ngrpc-message:\r\n";
+        body.push(TRAILERS_FLAG);
+        body.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+        body.extend_from_slice(trailers);
+        assert_eq!(decode_response(&body).unwrap(), message);
+
+        let mut failed = vec![TRAILERS_FLAG];
+        let trailers = b"grpc-status: 5\r\ngrpc-message: account not found\r\n";
+        failed.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+        failed.extend_from_slice(trailers);
+        assert_eq!(
+            decode_response(&failed).unwrap_err().to_string(),
+            "gRPC status 5: account not found"
+        );
+        assert!(decode_response(&body[..3]).is_err());
+        assert!(decode_response(&[0, 0xff, 0xff, 0xff, 0xff]).is_err());
+    }
+}
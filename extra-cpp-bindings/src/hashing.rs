@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use ethers::core::k256::ecdsa::VerifyingKey;
+use hmac::{Hmac, Mac};
+use sha2::Digest;
+
+/// the Keccak-256 hash of the data
+pub fn keccak256(data: Vec<u8>) -> Vec<u8> {
+    ethers::utils::keccak256(data).to_vec()
+}
+
+/// the SHA-256 hash of the data
+pub fn sha256(data: Vec<u8>) -> Vec<u8> {
+    sha2::Sha256::digest(data).to_vec()
+}
+
+/// the HMAC-SHA256 of the message with the key (RFC 2104)
+pub fn hmac_sha256(key: Vec<u8>, message: Vec<u8>) -> Vec<u8> {
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_from_slice(&key).expect("HMAC accepts the keys of any size");
+    mac.update(&message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// whether the tag is the HMAC-SHA256 of the message with the key (compared in a constant time)
+pub(crate) fn verify_hmac_sha256(key: &[u8], message: &[u8], tag: &[u8]) -> bool {
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC accepts the keys of any size");
+    mac.update(message);
+    mac.verify_slice(tag).is_ok()
+}
+
+/// the EIP-191 hash of the message
+/// ("\x19Ethereum Signed Message:\n" + message length + message; the same as `personal_sign`)
+pub fn hash_eip191_message(message: Vec<u8>) -> Vec<u8> {
+    ethers::utils::hash_message(message).as_bytes().to_vec()
+}
+
+/// the address (hexadecimal, 0x-prefixed) of the secp256k1 public key:
+/// uncompressed (65 bytes with the 0x04 prefix or 64 bytes without it) or compressed (33 bytes)
+pub fn address_from_public_key(public_key: Vec<u8>) -> Result<String> {
+    let sec1 = if public_key.len() == 64 {
+        [&[0x04], &public_key[..]].concat()
+    } else {
+        public_key
+    };
+    let key = VerifyingKey::from_sec1_bytes(&sec1).map_err(|_| anyhow!("invalid public key"))?;
+    Ok(format!("{:?}", ethers::utils::public_key_to_address(&key)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_hashes() {
+        assert_eq!(
+            keccak256(vec![]),
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert_eq!(
+            sha256(b"abc".to_vec()),
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        // RFC 4231 test case 2
+        assert_eq!(
+            hmac_sha256(b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec()),
+            hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert!(verify_hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        ));
+        assert!(!verify_hmac_sha256(
+            b"Jefe",
+            b"what do ya want for nothing?",
+            &hex!("5bdcc146bf60754e6a042426089575c7")
+        ));
+        assert_eq!(
+            hash_eip191_message(b"Hello World".to_vec()),
+            hex!("a1de988600a42c4b4ab089b619297c17d53cffae5d5120d82d8a92d0bb3b78f2")
+        );
+    }
+
+    #[test]
+    fn test_address_from_public_key() {
+        // the public key of the private key 1 (the generator point)
+        let uncompressed = hex!(
+            "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+            "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+        );
+        let address = "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf";
+        assert_eq!(
+            address_from_public_key(uncompressed.to_vec()).unwrap(),
+            address
+        );
+        assert_eq!(
+            address_from_public_key(uncompressed[1..].to_vec()).unwrap(),
+            address
+        );
+        assert!(address_from_public_key(vec![0x04; 65]).is_err());
+        assert!(address_from_public_key(vec![]).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::Result;
+use ethers::etherscan::account::{NormalTransaction, Sort, TxListParams};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::ffi::RawTxDetail;
+use crate::{get_explorer_result, parse_address, tx_list_params};
+
+/// the last block number accepted by the explorer API
+const END_BLOCK: u64 = 99999999;
+/// the page size used when all the transactions since a block are fetched
+const PAGE_SIZE: u64 = 1000;
+
+/// the saved position of a cursor
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+struct SavedCursor {
+    address: Address,
+    start_block: u64,
+    skip: usize,
+}
+
+/// Iterates over the transactions of an address (oldest first) in pages,
+/// so that large histories do not need to be loaded in memory at once
+pub struct HistoryCursor {
+    address: Address,
+    api_key: String,
+    /// the block the next page starts from
+    start_block: u64,
+    /// how many transactions in `start_block` were already returned
+    skip: usize,
+    done: bool,
+    rt: tokio::runtime::Runtime,
+}
+
+impl HistoryCursor {
+    pub(crate) fn new(address: &str, api_key: String) -> Result<Self> {
+        Self::since(address, 0, api_key)
+    }
+
+    /// a cursor over the transactions from `start_block`
+    pub(crate) fn since(address: &str, start_block: u64, api_key: String) -> Result<Self> {
+        Ok(Self {
+            address: parse_address(address)?,
+            api_key,
+            start_block,
+            skip: 0,
+            done: false,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// restores a cursor saved by `save`: it continues after the last returned transaction,
+    /// so only the new ones are fetched
+    pub(crate) fn restore(saved: &str, api_key: String) -> Result<Self> {
+        let saved: SavedCursor = serde_json::from_str(saved)?;
+        Ok(Self {
+            address: saved.address,
+            api_key,
+            start_block: saved.start_block,
+            skip: saved.skip,
+            done: false,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// saves the position of the cursor (e.g. to be written to a file)
+    pub fn save(&self) -> Result<String> {
+        Ok(serde_json::to_string(&SavedCursor {
+            address: self.address,
+            start_block: self.start_block,
+            skip: self.skip,
+        })?)
+    }
+
+    /// returns all the remaining transactions
+    pub(crate) fn collect_all(&mut self) -> Result<Vec<RawTxDetail>> {
+        let mut transactions = vec![];
+        loop {
+            let page = self.next_page(PAGE_SIZE)?;
+            if page.is_empty() {
+                return Ok(transactions);
+            }
+            transactions.extend(page);
+        }
+    }
+
+    /// returns up to `n` next transactions (an empty result means the end of the history)
+    pub fn next_page(&mut self, n: u64) -> Result<Vec<RawTxDetail>> {
+        if self.done || n == 0 {
+            return Ok(vec![]);
+        }
+        // the already returned transactions of `start_block` are fetched again and skipped
+        let offset = n + self.skip as u64;
+        let mut params = tx_list_params(TxListParams::new(
+            self.start_block,
+            END_BLOCK,
+            1,
+            offset,
+            Sort::Asc,
+        ));
+        params.insert("address", format!("{:?}", self.address));
+        let api_key = self.api_key.clone();
+        let transactions: Vec<NormalTransaction> = self
+            .rt
+            .block_on(async move { get_explorer_result("txlist", params, api_key).await })?;
+        let fetched = transactions.iter().map(|tx| tx.into()).collect();
+        Ok(self.advance(fetched, offset as usize))
+    }
+
+    /// updates the position from the fetched transactions and returns the new ones
+    fn advance(&mut self, fetched: Vec<RawTxDetail>, requested: usize) -> Vec<RawTxDetail> {
+        let already_returned = self.skip;
+        if fetched.len() < requested {
+            self.done = true;
+        }
+        if let Some(last_block) = fetched.last().map(|tx| tx.block_no) {
+            let in_last_block = fetched
+                .iter()
+                .filter(|tx| tx.block_no == last_block)
+                .count();
+            self.start_block = last_block;
+            self.skip = in_last_block;
+        }
+        fetched.into_iter().skip(already_returned).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// the cursor only looks at the blocks and the hashes
+    fn tx(hash: &str, block_no: u64) -> RawTxDetail {
+        RawTxDetail {
+            hash: hash.to_owned(),
+            block_no,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_advance() {
+        let mut cursor =
+            HistoryCursor::new("0x841a15D12aEc9c6039FD132c2FbFF112eD355700", "".into())
+                .expect("cursor");
+        // first page of 2: the block 11 may have more transactions
+        let page = cursor.advance(vec![tx("a", 10), tx("b", 11)], 2);
+        assert_eq!(page, vec![tx("a", 10), tx("b", 11)]);
+        assert_eq!(
+            (cursor.start_block, cursor.skip, cursor.done),
+            (11, 1, false)
+        );
+        // next page of 2 (3 requested from block 11, the first one skipped)
+        let page = cursor.advance(vec![tx("b", 11), tx("c", 11), tx("d", 12)], 3);
+        assert_eq!(page, vec![tx("c", 11), tx("d", 12)]);
+        assert_eq!(
+            (cursor.start_block, cursor.skip, cursor.done),
+            (12, 1, false)
+        );
+        // the last page
+        let page = cursor.advance(vec![tx("d", 12), tx("e", 12)], 3);
+        assert_eq!(page, vec![tx("e", 12)]);
+        assert_eq!(
+            (cursor.start_block, cursor.skip, cursor.done),
+            (12, 2, true)
+        );
+        assert!(cursor.next_page(2).expect("no more").is_empty());
+
+        // the restored cursor continues after the returned transactions
+        let saved = cursor.save().expect("save");
+        let mut restored = HistoryCursor::restore(&saved, "".into()).expect("restore");
+        assert_eq!(
+            (
+                restored.address,
+                restored.start_block,
+                restored.skip,
+                restored.done
+            ),
+            (cursor.address, 12, 2, false)
+        );
+        let page = restored.advance(vec![tx("d", 12), tx("e", 12), tx("f", 13)], 4);
+        assert_eq!(page, vec![tx("f", 13)]);
+        assert!(HistoryCursor::restore("{}", "".into()).is_err());
+    }
+}
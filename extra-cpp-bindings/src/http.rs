@@ -0,0 +1,498 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::GameSdkError;
+use crate::ffi::{EndpointHealth, HttpMode};
+use crate::{memory, netusage};
+use defi_wallet_connect::{dns, tls};
+
+/// environment variable selecting the mode (`live`, `record` or `replay`)
+const HTTP_MODE_ENV: &str = "PLAY_CPP_SDK_HTTP_MODE";
+/// environment variable with the directory where fixtures are stored
+const HTTP_FIXTURES_ENV: &str = "PLAY_CPP_SDK_HTTP_FIXTURES";
+const DEFAULT_FIXTURES_DIR: &str = "fixtures";
+
+/// settings explicitly set via `set_http_mode`; if none, the environment variables are used
+static SETTINGS: RwLock<Option<Settings>> = RwLock::new(None);
+/// headers added to every request (set via `set_default_headers`)
+static DEFAULT_HEADERS: RwLock<Option<HeaderMap>> = RwLock::new(None);
+/// the health of the endpoints of the SDK's requests, by host
+/// (the replayed requests aren't included)
+static HEALTH: Mutex<BTreeMap<String, EndpointHealth>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone)]
+pub(crate) struct Settings {
+    pub mode: HttpMode,
+    pub fixtures_dir: PathBuf,
+}
+
+impl Settings {
+    fn from_env() -> Self {
+        let mode = match std::env::var(HTTP_MODE_ENV)
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "record" => HttpMode::Record,
+            "replay" => HttpMode::Replay,
+            _ => HttpMode::Live,
+        };
+        let fixtures_dir = std::env::var(HTTP_FIXTURES_ENV)
+            .unwrap_or_else(|_| DEFAULT_FIXTURES_DIR.to_owned())
+            .into();
+        Self { mode, fixtures_dir }
+    }
+
+    fn fixture_path(&self, method: &str, url: &str, body: &[u8]) -> PathBuf {
+        self.fixtures_dir
+            .join(format!("{}.json", fixture_key(method, url, body)))
+    }
+}
+
+/// A recorded response
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    /// request method
+    method: String,
+    /// request url without the api key
+    url: String,
+    /// response status code
+    status: u16,
+    /// response body
+    body: String,
+}
+
+/// overrides the settings from the environment variables
+pub(crate) fn set_http_mode(mode: HttpMode, fixtures_dir: String) -> anyhow::Result<()> {
+    let fixtures_dir = if fixtures_dir.is_empty() {
+        Settings::from_env().fixtures_dir
+    } else {
+        PathBuf::from(fixtures_dir)
+    };
+    if mode == HttpMode::Record {
+        std::fs::create_dir_all(&fixtures_dir)?;
+    }
+    *SETTINGS.write().expect("http settings lock") = Some(Settings { mode, fixtures_dir });
+    Ok(())
+}
+
+pub(crate) fn settings() -> Settings {
+    SETTINGS
+        .read()
+        .expect("http settings lock")
+        .clone()
+        .unwrap_or_else(Settings::from_env)
+}
+
+/// replaces the headers added to every request
+/// (e.g. `User-Agent` or authorization headers required by some gateways)
+pub(crate) fn set_default_headers(
+    headers: impl IntoIterator<Item = (String, String)>,
+) -> Result<(), GameSdkError> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| GameSdkError::InvalidHttpHeader(name.clone()))?;
+        let header_value =
+            HeaderValue::from_str(&value).map_err(|_| GameSdkError::InvalidHttpHeader(name))?;
+        map.append(header_name, header_value);
+    }
+    *DEFAULT_HEADERS.write().expect("http headers lock") = Some(map);
+    Ok(())
+}
+
+fn default_headers() -> HeaderMap {
+    DEFAULT_HEADERS
+        .read()
+        .expect("http headers lock")
+        .clone()
+        .unwrap_or_default()
+}
+
+/// the blocking client used for all the SDK's HTTP requests
+pub(crate) fn blocking_client() -> Result<reqwest::blocking::Client, GameSdkError> {
+    let mut builder = reqwest::blocking::Client::builder().default_headers(default_headers());
+    // custom root certificates or pinning if set via `set_tls_options`
+    if let Some(config) = tls::tls_config() {
+        builder = builder.use_preconfigured_tls((*config).clone());
+    }
+    for (host, addrs) in overridden_hosts() {
+        builder = builder.resolve_to_addrs(&host, &addrs);
+    }
+    Ok(builder.build()?)
+}
+
+/// the async client used for all the SDK's HTTP requests
+pub(crate) fn async_client() -> Result<reqwest::Client, GameSdkError> {
+    let mut builder = reqwest::Client::builder().default_headers(default_headers());
+    if let Some(config) = tls::tls_config() {
+        builder = builder.use_preconfigured_tls((*config).clone());
+    }
+    for (host, addrs) in overridden_hosts() {
+        builder = builder.resolve_to_addrs(&host, &addrs);
+    }
+    Ok(builder.build()?)
+}
+
+/// the host overrides set via `set_host_overrides`
+/// (the port is ignored by reqwest, the one from the url is used)
+fn overridden_hosts() -> Vec<(String, Vec<SocketAddr>)> {
+    dns::host_overrides()
+        .into_iter()
+        .map(|(host, ips)| {
+            let addrs = ips.into_iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+            (host, addrs)
+        })
+        .collect()
+}
+
+/// records the outcome of a request to the host: its status code, or the error
+fn record_health(
+    health: &mut BTreeMap<String, EndpointHealth>,
+    host: &str,
+    latency: Duration,
+    outcome: Result<u16, String>,
+    now: u64,
+) {
+    let endpoint = health
+        .entry(host.to_owned())
+        .or_insert_with(|| EndpointHealth {
+            host: host.to_owned(),
+            ..Default::default()
+        });
+    endpoint.request_count += 1;
+    endpoint.last_latency_ms = latency.as_millis() as u64;
+    match outcome {
+        Ok(status) if status < 400 => {
+            endpoint.last_status = status;
+            endpoint.last_error.clear();
+            endpoint.last_success_at = now;
+        }
+        Ok(status) => {
+            endpoint.failure_count += 1;
+            endpoint.last_status = status;
+            endpoint.last_error = format!("HTTP {status}");
+        }
+        Err(error) => {
+            endpoint.failure_count += 1;
+            endpoint.last_status = 0;
+            endpoint.last_error = error;
+        }
+    }
+}
+
+pub(crate) fn record_request(url: &url::Url, started: Instant, outcome: Result<u16, String>) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    record_health(
+        &mut HEALTH.lock().expect("http health lock"),
+        url.host_str().unwrap_or_default(),
+        started.elapsed(),
+        outcome,
+        now,
+    );
+}
+
+/// the health of the endpoints of the SDK's HTTP requests (the explorer APIs, the faucets,
+/// the JSON-RPC nodes...), sorted by host
+pub(crate) fn endpoint_health() -> Vec<EndpointHealth> {
+    HEALTH
+        .lock()
+        .expect("http health lock")
+        .values()
+        .cloned()
+        .collect()
+}
+
+/// the bytes of the url and the body of the request (the headers aren't counted)
+fn request_size(url: &url::Url, body: Option<&[u8]>) -> u64 {
+    (url.as_str().len() + body.map(<[u8]>::len).unwrap_or_default()) as u64
+}
+
+/// executes the request, returns the status code and the body of the response;
+/// its outcome is recorded in the health of the endpoint and its size in the network usage
+fn execute(
+    client: &reqwest::blocking::Client,
+    request: reqwest::blocking::Request,
+) -> Result<(u16, String), GameSdkError> {
+    let _scope = memory::scope(memory::HTTP);
+    let url = request.url().clone();
+    let sent = request_size(&url, request.body().and_then(|b| b.as_bytes()));
+    let started = Instant::now();
+    let response = client
+        .execute(request)
+        .and_then(|response| Ok((response.status().as_u16(), response.text()?)));
+    let outcome = match &response {
+        Ok((status, _)) => Ok(*status),
+        Err(e) => Err(e.to_string()),
+    };
+    record_request(&url, started, outcome);
+    let received = response
+        .as_ref()
+        .map(|(_, body)| body.len())
+        .unwrap_or_default();
+    netusage::record_http(sent, received as u64);
+    Ok(response?)
+}
+
+/// sends the request whose response is read by the caller (e.g. with its headers, or
+/// within a size limit): its outcome is recorded in the health of the endpoint and its
+/// size in the network usage, the caller records the body it reads via `netusage::record_received`
+pub(crate) fn send(
+    client: &reqwest::blocking::Client,
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<reqwest::blocking::Response, reqwest::Error> {
+    let _scope = memory::scope(memory::HTTP);
+    let request = request.build()?;
+    let url = request.url().clone();
+    let sent = request_size(&url, request.body().and_then(|b| b.as_bytes()));
+    let started = Instant::now();
+    let response = client.execute(request);
+    let outcome = match &response {
+        Ok(response) => Ok(response.status().as_u16()),
+        Err(e) => Err(e.to_string()),
+    };
+    record_request(&url, started, outcome);
+    netusage::record_http(sent, 0);
+    response
+}
+
+/// executes the request and records it (for use inside of a tokio runtime)
+pub(crate) async fn execute_async(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+) -> Result<(u16, String), reqwest::Error> {
+    let url = request.url().clone();
+    let sent = request_size(&url, request.body().and_then(|b| b.as_bytes()));
+    let started = Instant::now();
+    let response = match client.execute(request).await {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            response.text().await.map(|body| (status, body))
+        }
+        Err(e) => Err(e),
+    };
+    let outcome = match &response {
+        Ok((status, _)) => Ok(*status),
+        Err(e) => Err(e.to_string()),
+    };
+    record_request(&url, started, outcome);
+    let received = response
+        .as_ref()
+        .map(|(_, body)| body.len())
+        .unwrap_or_default();
+    netusage::record_http(sent, received as u64);
+    response
+}
+
+/// GET the url and deserialize the JSON response
+pub(crate) fn get_json<T: DeserializeOwned>(url: &str) -> Result<T, GameSdkError> {
+    let client = blocking_client()?;
+    send_json(&client, client.get(url))
+}
+
+/// send the request and deserialize the JSON response
+pub(crate) fn send_json<T: DeserializeOwned>(
+    client: &reqwest::blocking::Client,
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<T, GameSdkError> {
+    let _scope = memory::scope(memory::HTTP);
+    let request = request.build()?;
+    let settings = settings();
+    let fixture_path = settings.fixture_path(
+        request.method().as_str(),
+        request.url().as_str(),
+        request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .unwrap_or_default(),
+    );
+    let body = match settings.mode {
+        HttpMode::Replay => load_fixture(&fixture_path)?,
+        HttpMode::Record => {
+            let method = request.method().to_string();
+            let url = request.url().clone();
+            let (status, body) = execute(client, request)?;
+            save_fixture(&fixture_path, method, &url, status, &body)?;
+            body
+        }
+        _ => execute(client, request)?.1,
+    };
+    Ok(serde_json::from_str(&body)?)
+}
+
+/// GET the url and deserialize the JSON response (for use inside of a tokio runtime)
+pub(crate) async fn get_json_async<T: DeserializeOwned>(url: &str) -> Result<T, GameSdkError> {
+    let request = async move {
+        let client = async_client()?;
+        let request = client.get(url).build()?;
+        let settings = settings();
+        let fixture_path =
+            settings.fixture_path(request.method().as_str(), request.url().as_str(), &[]);
+        let body = match settings.mode {
+            HttpMode::Replay => load_fixture(&fixture_path)?,
+            HttpMode::Record => {
+                let url = request.url().clone();
+                let (status, body) = execute_async(&client, request).await?;
+                save_fixture(&fixture_path, "GET".to_owned(), &url, status, &body)?;
+                body
+            }
+            _ => execute_async(&client, request).await?.1,
+        };
+        Ok(serde_json::from_str(&body)?)
+    };
+    memory::scoped(memory::HTTP, request).await
+}
+
+/// the fixture name: hash of the method, url (without api keys) and body
+fn fixture_key(method: &str, url: &str, body: &[u8]) -> String {
+    let url = redact_url(url);
+    let mut preimage = Vec::with_capacity(method.len() + url.len() + body.len() + 2);
+    preimage.extend_from_slice(method.as_bytes());
+    preimage.push(b' ');
+    preimage.extend_from_slice(url.as_bytes());
+    preimage.push(b'\n');
+    preimage.extend_from_slice(body);
+    hex::encode(ethers::utils::keccak256(preimage))
+}
+
+/// removes the api key from the url (and sorts the query parameters),
+/// so fixtures can be shared and replayed without it
+fn redact_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) => {
+            let mut pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .filter(|(k, _)| !k.eq_ignore_ascii_case("apikey"))
+                .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                .collect();
+            pairs.sort();
+            if pairs.is_empty() {
+                parsed.set_query(None);
+            } else {
+                parsed.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+            parsed.to_string()
+        }
+        Err(_) => url.to_owned(),
+    }
+}
+
+fn load_fixture(path: &Path) -> Result<String, GameSdkError> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|_| GameSdkError::MissingFixture(path.display().to_string()))?;
+    let fixture: Fixture = serde_json::from_str(&data)?;
+    Ok(fixture.body)
+}
+
+fn save_fixture(
+    path: &Path,
+    method: String,
+    url: &url::Url,
+    status: u16,
+    body: &str,
+) -> Result<(), GameSdkError> {
+    let fixture = Fixture {
+        method,
+        url: redact_url(url.as_str()),
+        status,
+        body: body.to_owned(),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&fixture)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fixture_key_ignores_api_key() {
+        assert_eq!(
+            fixture_key(
+                "GET",
+                "https://api.cronoscan.com/api?module=account&apikey=SECRET&action=txlist",
+                &[]
+            ),
+            fixture_key(
+                "GET",
+                "https://api.cronoscan.com/api?action=txlist&module=account",
+                &[]
+            )
+        );
+        assert_ne!(
+            fixture_key("GET", "https://api.cronoscan.com/api?module=account", &[]),
+            fixture_key("POST", "https://api.cronoscan.com/api?module=account", &[])
+        );
+    }
+
+    #[test]
+    fn test_invalid_default_headers() {
+        assert!(matches!(
+            set_default_headers(vec![("Bad Header".to_owned(), "value".to_owned())]),
+            Err(GameSdkError::InvalidHttpHeader(_))
+        ));
+        assert!(matches!(
+            set_default_headers(vec![("X-Api-Key".to_owned(), "line\nbreak".to_owned())]),
+            Err(GameSdkError::InvalidHttpHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_endpoint_health() {
+        let mut health = BTreeMap::new();
+        let host = "api.cronoscan.com";
+        record_health(&mut health, host, Duration::from_millis(120), Ok(200), 10);
+        record_health(&mut health, host, Duration::from_millis(80), Ok(503), 20);
+        let endpoint = &health[host];
+        assert_eq!(endpoint.request_count, 2);
+        assert_eq!(endpoint.failure_count, 1);
+        assert_eq!(endpoint.last_status, 503);
+        assert_eq!(endpoint.last_latency_ms, 80);
+        assert_eq!(endpoint.last_error, "HTTP 503");
+        assert_eq!(endpoint.last_success_at, 10);
+
+        record_health(
+            &mut health,
+            host,
+            Duration::ZERO,
+            Err("timeout".to_owned()),
+            30,
+        );
+        assert_eq!(health[host].last_status, 0);
+        assert_eq!(health[host].last_error, "timeout");
+        record_health(&mut health, host, Duration::ZERO, Ok(200), 40);
+        assert_eq!(health[host].failure_count, 2);
+        assert!(health[host].last_error.is_empty());
+        assert_eq!(health[host].last_success_at, 40);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let settings = Settings {
+            mode: HttpMode::Replay,
+            fixtures_dir: std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()),
+        };
+        let url = "https://cronos.org/explorer/testnet3/api?module=account&action=tokenlist";
+        let path = settings.fixture_path("GET", url, &[]);
+        assert!(matches!(
+            load_fixture(&path),
+            Err(GameSdkError::MissingFixture(_))
+        ));
+        let body = r#"{"message":"OK","result":[],"status":"1"}"#;
+        save_fixture(&path, "GET".into(), &url.parse().unwrap(), 200, body).expect("save");
+        assert_eq!(load_fixture(&path).expect("load"), body);
+        std::fs::remove_dir_all(&settings.fixtures_dir).expect("cleanup");
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use ethers::types::TxHash;
+use serde::{Deserialize, Serialize};
+
+/// the send of an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct IdempotentSend {
+    /// the hash of the sent transaction (empty while it's being sent)
+    tx_hash: String,
+    /// the decimal nonce of the transaction (empty if the wallet chose it)
+    nonce: String,
+    /// unix timestamp of the send
+    created_at: u64,
+    /// whether the send failed after the transaction may have been broadcast
+    #[serde(default)]
+    outcome_unknown: bool,
+}
+
+/// The error of a send after which the transaction may have been broadcast (e.g. a timeout
+/// or a disconnection once the wallet got the request), unlike the definite rejections
+/// (by the checks of the SDK, the user or the node): its idempotency key is kept
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(crate) struct MaybeSent(pub(crate) String);
+
+/// whether the wallet's error is a definite rejection of the transaction by the user
+/// or the node (so it wasn't broadcast)
+pub(crate) fn is_rejection(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("4001")
+        || [
+            "rejected",
+            "denied",
+            "cancelled",
+            "canceled",
+            "insufficient funds",
+            "nonce too low",
+            "underpriced",
+            "intrinsic gas too low",
+            "exceeds block gas limit",
+            "execution reverted",
+        ]
+        .iter()
+        .any(|reason| message.contains(reason))
+}
+
+/// The sends by idempotency key, so that a send repeated with the same key (e.g. after
+/// a crash of the game) returns the first transaction hash instead of broadcasting again;
+/// persisted as a JSON object (if the path is set)
+#[derive(Debug, Default)]
+pub struct IdempotencyStore {
+    sends: Mutex<BTreeMap<String, IdempotentSend>>,
+    path: Mutex<Option<PathBuf>>,
+}
+
+impl IdempotencyStore {
+    /// persists the sends to the file (none if empty), loading the ones already in it
+    pub(crate) fn set_path(&self, path: String) -> Result<()> {
+        let path = (!path.is_empty()).then(|| PathBuf::from(path));
+        let mut sends = self.sends.lock().expect("idempotency lock");
+        if let Some(path) = path.as_ref().filter(|path| path.exists()) {
+            let saved: BTreeMap<String, IdempotentSend> =
+                serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            sends.extend(saved);
+        }
+        *self.path.lock().expect("idempotency path lock") = path;
+        save(self, &sends)
+    }
+
+    /// starts the send of the key: the hash of its first transaction if it was already sent,
+    /// or `None` if the transaction should be sent now (then `finish` must be called)
+    pub(crate) fn begin(&self, key: &str, nonce: &str) -> Result<Option<TxHash>> {
+        if key.is_empty() {
+            return Err(anyhow!("empty idempotency key"));
+        }
+        let mut sends = self.sends.lock().expect("idempotency lock");
+        if let Some(send) = sends.get(key) {
+            if send.outcome_unknown {
+                return Err(anyhow!(
+                    "the send of the idempotency key {key} failed, but its transaction may have \
+                     been broadcast: check the transactions of the nonce {:?} before forgetting \
+                     the key",
+                    send.nonce
+                ));
+            }
+            if send.tx_hash.is_empty() {
+                // e.g. the game crashed during the send: the transaction may have been broadcast
+                return Err(anyhow!(
+                    "the send of the idempotency key {key} didn't finish: \
+                     check the transactions of the nonce {:?} before forgetting the key",
+                    send.nonce
+                ));
+            }
+            return Ok(Some(send.tx_hash.parse()?));
+        }
+        sends.insert(
+            key.to_owned(),
+            IdempotentSend {
+                tx_hash: String::new(),
+                nonce: nonce.to_owned(),
+                created_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+                outcome_unknown: false,
+            },
+        );
+        save(self, &sends)?;
+        Ok(None)
+    }
+
+    /// records the result of the send of the key: a rejected send can be repeated,
+    /// but the key of a send that may have been broadcast (`MaybeSent`) is kept
+    /// until it's forgotten
+    pub(crate) fn finish(&self, key: &str, result: &Result<TxHash>) -> Result<()> {
+        let mut sends = self.sends.lock().expect("idempotency lock");
+        match result {
+            Ok(tx_hash) => {
+                if let Some(send) = sends.get_mut(key) {
+                    send.tx_hash = format!("{tx_hash:?}");
+                }
+            }
+            Err(e) if e.is::<MaybeSent>() => {
+                if let Some(send) = sends.get_mut(key) {
+                    send.outcome_unknown = true;
+                }
+            }
+            Err(_) => {
+                sends.remove(key);
+            }
+        }
+        save(self, &sends)
+    }
+
+    /// forgets the key, so that a send with it broadcasts again
+    pub(crate) fn forget(&self, key: &str) -> Result<()> {
+        let mut sends = self.sends.lock().expect("idempotency lock");
+        sends.remove(key);
+        save(self, &sends)
+    }
+}
+
+/// writes the sends (via a temporary file, so that they're never partially written)
+fn save(store: &IdempotencyStore, sends: &BTreeMap<String, IdempotentSend>) -> Result<()> {
+    if let Some(path) = store.path.lock().expect("idempotency path lock").as_ref() {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string(sends)?)?;
+        std::fs::rename(tmp, path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_idempotency_store() {
+        let path = std::env::temp_dir().join(format!("sends-{}.json", uuid::Uuid::new_v4()));
+        let store = IdempotencyStore::default();
+        store.set_path(path.to_string_lossy().into()).unwrap();
+        assert!(store.begin("", "").is_err());
+
+        assert_eq!(store.begin("reward-1", "7").unwrap(), None);
+        let tx_hash = TxHash::repeat_byte(0xab);
+        store.finish("reward-1", &Ok(tx_hash)).unwrap();
+        assert_eq!(store.begin("reward-1", "7").unwrap(), Some(tx_hash));
+
+        // a failed send can be repeated
+        assert_eq!(store.begin("reward-2", "").unwrap(), None);
+        store.finish("reward-2", &Err(anyhow!("rejected"))).unwrap();
+        assert_eq!(store.begin("reward-2", "").unwrap(), None);
+
+        // a send that may have been broadcast is not repeated
+        assert_eq!(store.begin("reward-3", "8").unwrap(), None);
+        store
+            .finish("reward-3", &Err(MaybeSent("timeout".into()).into()))
+            .unwrap();
+        assert!(store
+            .begin("reward-3", "8")
+            .unwrap_err()
+            .to_string()
+            .contains("may have been broadcast"));
+        assert!(is_rejection("User rejected the transaction"));
+        assert!(is_rejection("insufficient funds for gas * price + value"));
+        assert!(!is_rejection("Request timed out"));
+
+        // after a restart: the unfinished send is not repeated
+        let restarted = IdempotencyStore::default();
+        restarted.set_path(path.to_string_lossy().into()).unwrap();
+        assert_eq!(restarted.begin("reward-1", "").unwrap(), Some(tx_hash));
+        assert!(restarted.begin("reward-2", "").is_err());
+        restarted.forget("reward-2").unwrap();
+        assert_eq!(restarted.begin("reward-2", "").unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+
+use crate::ffi::NftImageCacheConfig;
+use crate::hashing::sha256;
+use crate::{http, ipfs, media};
+
+const DEFAULT_CACHE_DIR: &str = "play-cpp-sdk-nft-images";
+const DEFAULT_MAX_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+const DEFAULT_MIN_REQUEST_INTERVAL_MS: u64 = 100;
+const DEFAULT_GATEWAYS: &[&str] = &["https://ipfs.io/ipfs/", "https://dweb.link/ipfs/"];
+
+/// the configuration set via `set_nft_image_cache` (the defaults if none)
+static CONFIG: RwLock<Option<NftImageCacheConfig>> = RwLock::new(None);
+/// the time of the last request, for the rate limit
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+/// the gateway tried first by the next IPFS fetch
+static NEXT_GATEWAY: AtomicUsize = AtomicUsize::new(0);
+
+/// replaces the configuration (the empty or 0 fields are the defaults)
+pub(crate) fn set_config(config: &NftImageCacheConfig) -> Result<()> {
+    if !config.cache_dir.is_empty() {
+        fs::create_dir_all(&config.cache_dir)?;
+    }
+    *CONFIG.write().expect("nft image cache lock") = Some(config.clone());
+    Ok(())
+}
+
+fn config() -> NftImageCacheConfig {
+    let mut config = CONFIG
+        .read()
+        .expect("nft image cache lock")
+        .clone()
+        .unwrap_or_default();
+    if config.cache_dir.is_empty() {
+        config.cache_dir = std::env::temp_dir()
+            .join(DEFAULT_CACHE_DIR)
+            .to_string_lossy()
+            .into_owned();
+    }
+    if config.max_cache_bytes == 0 {
+        config.max_cache_bytes = DEFAULT_MAX_CACHE_BYTES;
+    }
+    if config.gateways.is_empty() {
+        config.gateways = DEFAULT_GATEWAYS.iter().map(|g| g.to_string()).collect();
+    }
+    if config.min_request_interval_ms == 0 {
+        config.min_request_interval_ms = DEFAULT_MIN_REQUEST_INTERVAL_MS;
+    }
+    config
+}
+
+/// the urls to try: the `ipfs://` uris on each gateway (starting from `first`),
+/// the other urls as they are
+fn candidate_urls(url_or_ipfs: &str, gateways: &[String], first: usize) -> Vec<String> {
+    if !url_or_ipfs.starts_with("ipfs://") || gateways.is_empty() {
+        return vec![url_or_ipfs.to_owned()];
+    }
+    (0..gateways.len())
+        .map(|i| {
+            let gateway = &gateways[(first + i) % gateways.len()];
+            ipfs::ipfs_uri_to_gateway_url(url_or_ipfs.to_owned(), gateway.clone())
+        })
+        .collect()
+}
+
+/// waits until the minimum interval since the last request has passed
+fn throttle(interval: Duration) {
+    let mut last = LAST_REQUEST.lock().expect("nft image rate limit lock");
+    if let Some(elapsed) = last.map(|last| last.elapsed()) {
+        if elapsed < interval {
+            std::thread::sleep(interval - elapsed);
+        }
+    }
+    *last = Some(Instant::now());
+}
+
+/// The cached image of a url: `<key>.bin` and its ETag in `<key>.etag`
+struct CacheEntry {
+    data_path: PathBuf,
+    etag_path: PathBuf,
+}
+
+impl CacheEntry {
+    fn new(dir: &Path, url_or_ipfs: &str) -> Self {
+        let key = hex::encode(sha256(url_or_ipfs.as_bytes().to_vec()));
+        Self {
+            data_path: dir.join(format!("{key}.bin")),
+            etag_path: dir.join(format!("{key}.etag")),
+        }
+    }
+
+    /// the cached data (marked as recently used)
+    fn read(&self) -> Option<Vec<u8>> {
+        let data = fs::read(&self.data_path).ok()?;
+        // the modification time is the last use of the LRU eviction
+        if let Ok(file) = fs::File::options().append(true).open(&self.data_path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(data)
+    }
+
+    fn etag(&self) -> Option<String> {
+        fs::read_to_string(&self.etag_path).ok()
+    }
+
+    fn write(&self, data: &[u8], etag: Option<&str>) -> Result<()> {
+        let tmp_path = self.data_path.with_extension("tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, &self.data_path)?;
+        match etag {
+            Some(etag) => fs::write(&self.etag_path, etag)?,
+            None => {
+                let _ = fs::remove_file(&self.etag_path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// removes the least recently used images until the cache is within the maximum size
+fn evict(dir: &Path, max_cache_bytes: u64) -> Result<()> {
+    let mut entries = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "bin") {
+            let metadata = fs::metadata(&path)?;
+            entries.push((metadata.modified()?, metadata.len(), path));
+        }
+    }
+    entries.sort();
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    for (_, len, path) in entries {
+        if total <= max_cache_bytes {
+            break;
+        }
+        fs::remove_file(&path)?;
+        let _ = fs::remove_file(path.with_extension("etag"));
+        total -= len;
+    }
+    Ok(())
+}
+
+fn check_size(data: Vec<u8>, max_bytes: u64) -> Result<Vec<u8>> {
+    if max_bytes > 0 && data.len() as u64 > max_bytes {
+        return Err(anyhow!("the image is over {max_bytes} bytes"));
+    }
+    Ok(data)
+}
+
+/// fetches the image of the url or the `ipfs://` uri (up to `max_bytes`, 0 for no limit):
+/// the IPFS images are immutable, so they're served from the cache; the other cached ones
+/// are revalidated with their ETag. The requests are rate-limited, the IPFS gateways
+/// are rotated and tried in turn, and the cached image is returned if all of them fail
+pub(crate) fn fetch_nft_image(url_or_ipfs: &str, max_bytes: u64) -> Result<Vec<u8>> {
+    let config = config();
+    let dir = Path::new(&config.cache_dir);
+    fs::create_dir_all(dir)?;
+    let entry = CacheEntry::new(dir, url_or_ipfs);
+    let cached = entry.read();
+    let immutable = url_or_ipfs.starts_with("ipfs://");
+    if let (Some(data), true) = (&cached, immutable) {
+        return check_size(data.clone(), max_bytes);
+    }
+    let etag = cached.as_ref().and_then(|_| entry.etag());
+    let client = http::blocking_client()?;
+    let first = NEXT_GATEWAY.fetch_add(1, Ordering::Relaxed);
+    let mut last_error = anyhow!("no url to fetch the image {url_or_ipfs}");
+    for url in candidate_urls(url_or_ipfs, &config.gateways, first) {
+        throttle(Duration::from_millis(config.min_request_interval_ms));
+        let mut request = client.get(&url);
+        if let Some(etag) = &etag {
+            request = request.header(IF_NONE_MATCH, etag.as_str());
+        }
+        let response = match http::send(&client, request).and_then(|r| r.error_for_status()) {
+            Ok(response) => response,
+            Err(e) => {
+                last_error = e.into();
+                continue;
+            }
+        };
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(data) = cached {
+                return check_size(data, max_bytes);
+            }
+        }
+        let new_etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        let data = media::read_limited(response, max_bytes)?;
+        entry.write(&data, new_etag.as_deref())?;
+        evict(dir, config.max_cache_bytes)?;
+        return Ok(data);
+    }
+    match cached {
+        Some(data) => check_size(data, max_bytes),
+        None => Err(last_error),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_candidate_urls() {
+        let gateways = vec!["https://a/ipfs".to_owned(), "https://b/ipfs/".to_owned()];
+        assert_eq!(
+            candidate_urls("ipfs://bafy1/1.png", &gateways, 3),
+            vec!["https://b/ipfs/bafy1/1.png", "https://a/ipfs/bafy1/1.png"]
+        );
+        assert_eq!(
+            candidate_urls("https://example.com/1.png", &gateways, 0),
+            vec!["https://example.com/1.png"]
+        );
+    }
+
+    #[test]
+    fn test_image_cache() {
+        let dir = std::env::temp_dir().join(format!("nft-images-{}", uuid::Uuid::new_v4()));
+        set_config(&NftImageCacheConfig {
+            cache_dir: dir.to_string_lossy().into_owned(),
+            max_cache_bytes: 10,
+            gateways: vec![],
+            min_request_interval_ms: 0,
+        })
+        .unwrap();
+        let old = CacheEntry::new(&dir, "ipfs://bafyold");
+        old.write(b"123456", Some("\"v1\"")).unwrap();
+        fs::File::options()
+            .append(true)
+            .open(&old.data_path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let new = CacheEntry::new(&dir, "ipfs://bafynew");
+        new.write(b"abcdef", None).unwrap();
+        assert_eq!(fetch_nft_image("ipfs://bafynew", 0).unwrap(), b"abcdef");
+        assert!(fetch_nft_image("ipfs://bafynew", 5).is_err());
+
+        evict(&dir, 10).unwrap();
+        assert!(!old.data_path.exists() && !old.etag_path.exists());
+        assert_eq!(new.read().unwrap(), b"abcdef");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
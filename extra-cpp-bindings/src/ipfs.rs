@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use serde_json::{json, Value};
+
+use crate::error::GameSdkError;
+use crate::ffi::{IpfsConfig, IpfsService, IpfsUpload};
+use crate::hashing::sha256;
+use crate::http;
+
+const PINATA_URL: &str = "https://api.pinata.cloud";
+const WEB3_STORAGE_URL: &str = "https://api.web3.storage";
+const KUBO_URL: &str = "http://127.0.0.1:5001";
+const DEFAULT_GATEWAY_URL: &str = "https://ipfs.io/ipfs/";
+
+/// the url of the CID (with the optional path, e.g. "1.json" in a directory) on the gateway
+/// (the public ipfs.io gateway if it's empty)
+pub fn ipfs_gateway_url(cid: String, gateway_url: String) -> String {
+    let gateway = if gateway_url.is_empty() {
+        DEFAULT_GATEWAY_URL
+    } else {
+        gateway_url.as_str()
+    };
+    format!(
+        "{}/{}",
+        gateway.trim_end_matches('/'),
+        cid.trim_start_matches('/')
+    )
+}
+
+/// the gateway url of the `ipfs://` uri (e.g. the `image` of an NFT metadata);
+/// the other urls are returned as they are
+pub fn ipfs_uri_to_gateway_url(uri: String, gateway_url: String) -> String {
+    match uri.strip_prefix("ipfs://") {
+        Some(cid) => ipfs_gateway_url(cid.trim_start_matches("ipfs/").to_owned(), gateway_url),
+        None => uri,
+    }
+}
+
+/// the multipart/form-data body of the file; the boundary is derived from the data,
+/// so that the recorded requests are replayable
+fn multipart_body(file_name: &str, data: &[u8]) -> (String, Vec<u8>) {
+    let boundary = format!("play-cpp-sdk-{}", hex::encode(&sha256(data.to_vec())[..16]));
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        file_name.replace('"', "")
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    (boundary, body)
+}
+
+/// the CID in the response of the service
+fn response_cid(service: IpfsService, response: &Value) -> Result<String> {
+    let field = match service {
+        IpfsService::Pinata => "IpfsHash",
+        IpfsService::Web3Storage => "cid",
+        IpfsService::Kubo => "Hash",
+        _ => return Err(anyhow!("unknown IPFS service")),
+    };
+    response
+        .get(field)
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| anyhow!("no CID in the IPFS response: {response}"))
+}
+
+/// The content uploaded to the IPFS service
+#[derive(Clone, Copy)]
+enum Content<'a> {
+    Json(&'a Value),
+    File(&'a [u8]),
+}
+
+/// uploads (and pins) the content named `name` to the service
+fn upload(config: &IpfsConfig, name: &str, content: Content) -> Result<IpfsUpload> {
+    let api_url = |default: &str| {
+        if config.api_url.is_empty() {
+            default.to_owned()
+        } else {
+            config.api_url.trim_end_matches('/').to_owned()
+        }
+    };
+    let client = http::blocking_client()?;
+    let json_bytes;
+    let data = match content {
+        Content::Json(json) => {
+            json_bytes = serde_json::to_vec(json)?;
+            json_bytes.as_slice()
+        }
+        Content::File(data) => data,
+    };
+    let mut request = match (config.service, content) {
+        (IpfsService::Pinata, Content::Json(json)) => client
+            .post(format!("{}/pinning/pinJSONToIPFS", api_url(PINATA_URL)))
+            .json(&json!({ "pinataContent": json, "pinataMetadata": { "name": name } })),
+        (IpfsService::Pinata, Content::File(_)) => {
+            let (boundary, body) = multipart_body(name, data);
+            client
+                .post(format!("{}/pinning/pinFileToIPFS", api_url(PINATA_URL)))
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(body)
+        }
+        (IpfsService::Web3Storage, _) => client
+            .post(format!("{}/upload", api_url(WEB3_STORAGE_URL)))
+            .header("X-NAME", name)
+            .body(data.to_vec()),
+        (IpfsService::Kubo, _) => {
+            let (boundary, body) = multipart_body(name, data);
+            client
+                .post(format!("{}/api/v0/add?pin=true", api_url(KUBO_URL)))
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(body)
+        }
+        _ => return Err(anyhow!("unknown IPFS service")),
+    };
+    for header in config.auth_headers.iter() {
+        let name = HeaderName::from_bytes(header.name.as_bytes())
+            .map_err(|_| GameSdkError::InvalidHttpHeader(header.name.clone()))?;
+        let value = HeaderValue::from_str(&header.value)
+            .map_err(|_| GameSdkError::InvalidHttpHeader(header.name.clone()))?;
+        request = request.header(name, value);
+    }
+    let response: Value = http::send_json(&client, request)?;
+    let cid = response_cid(config.service, &response)?;
+    Ok(IpfsUpload {
+        uri: format!("ipfs://{cid}"),
+        gateway_url: ipfs_gateway_url(cid.clone(), config.gateway_url.clone()),
+        cid,
+    })
+}
+
+/// uploads the JSON (e.g. an NFT metadata) named `name`
+pub(crate) fn upload_json(config: &IpfsConfig, json: &str, name: &str) -> Result<IpfsUpload> {
+    let json: Value = serde_json::from_str(json)?;
+    upload(config, name, Content::Json(&json))
+}
+
+/// uploads the file (named by its file name)
+pub(crate) fn upload_file(config: &IpfsConfig, path: &str) -> Result<IpfsUpload> {
+    let data = std::fs::read(path)?;
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    upload(config, &name, Content::File(&data))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gateway_urls() {
+        assert_eq!(
+            ipfs_gateway_url("bafy1".into(), "".into()),
+            "https://ipfs.io/ipfs/bafy1"
+        );
+        assert_eq!(
+            ipfs_uri_to_gateway_url(
+                "ipfs://ipfs/bafy1/1.json".into(),
+                "https://gateway.pinata.cloud/ipfs".into()
+            ),
+            "https://gateway.pinata.cloud/ipfs/bafy1/1.json"
+        );
+        assert_eq!(
+            ipfs_uri_to_gateway_url("https://example.com/1.png".into(), "".into()),
+            "https://example.com/1.png"
+        );
+    }
+
+    #[test]
+    fn test_upload_requests() {
+        let (boundary, body) = multipart_body("sword.json", b"{}");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(&format!("--{boundary}\r\n")));
+        assert!(body.contains("filename=\"sword.json\""));
+        assert!(body.ends_with(&format!("\r\n\r\n{{}}\r\n--{boundary}--\r\n")));
+
+        let response = json!({"IpfsHash": "Qm1", "PinSize": 2});
+        assert_eq!(response_cid(IpfsService::Pinata, &response).unwrap(), "Qm1");
+        assert_eq!(
+            response_cid(IpfsService::Web3Storage, &json!({"cid": "bafy1"})).unwrap(),
+            "bafy1"
+        );
+        assert!(response_cid(IpfsService::Kubo, &response).is_err());
+    }
+}
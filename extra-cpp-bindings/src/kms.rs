@@ -0,0 +1,508 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use ethers::types::{Address, Signature, H256};
+use serde_json::{json, Value};
+
+use crate::ffi::{KmsConfig, KmsProvider};
+use crate::hashing::{address_from_public_key, hmac_sha256, sha256};
+use crate::http;
+use crate::nftstats::json_text;
+use crate::signer::{normalize_signature, Signer};
+
+const GCP_KMS_URL: &str = "https://cloudkms.googleapis.com/v1";
+/// the access tokens of the service account of the GCE/GKE/Cloud Run instance
+const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// how long before its expiry a cached access token is refreshed
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+fn base64_encode(data: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
+fn base64_decode(text: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::STANDARD.decode(text.trim())?)
+}
+
+/// the tag, the content and the rest of the first DER element
+fn der_element(der: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let invalid = || anyhow!("invalid DER");
+    let (&tag, rest) = der.split_first().ok_or_else(invalid)?;
+    let (&first, rest) = rest.split_first().ok_or_else(invalid)?;
+    let (len, rest) = match first {
+        len if len < 0x80 => (len as usize, rest),
+        0x81 | 0x82 => {
+            let size = (first & 0x7f) as usize;
+            let len = rest
+                .get(..size)
+                .ok_or_else(invalid)?
+                .iter()
+                .fold(0, |len, byte| (len << 8) | *byte as usize);
+            (len, &rest[size..])
+        }
+        _ => return Err(invalid()),
+    };
+    let content = rest.get(..len).ok_or_else(invalid)?;
+    Ok((tag, content, &rest[len..]))
+}
+
+/// the 64 bytes (r || s) of a DER ECDSA signature (SEQUENCE of the INTEGERs r and s)
+fn der_signature_rs(der: &[u8]) -> Result<Vec<u8>> {
+    let (tag, sequence, _) = der_element(der)?;
+    if tag != 0x30 {
+        return Err(anyhow!("the DER signature is not a sequence"));
+    }
+    let mut rs = Vec::with_capacity(64);
+    let mut rest = sequence;
+    for _ in 0..2 {
+        let (tag, integer, next) = der_element(rest)?;
+        let integer: Vec<u8> = integer.iter().copied().skip_while(|b| *b == 0).collect();
+        if tag != 0x02 || integer.len() > 32 {
+            return Err(anyhow!("invalid DER signature integer"));
+        }
+        rs.resize(rs.len() + 32 - integer.len(), 0);
+        rs.extend(integer);
+        rest = next;
+    }
+    Ok(rs)
+}
+
+/// the address of the DER SubjectPublicKeyInfo of a secp256k1 key
+/// (its uncompressed point is at the end of the BIT STRING)
+fn spki_address(der: &[u8]) -> Result<Address> {
+    if der.len() < 65 || der[der.len() - 65] != 0x04 {
+        return Err(anyhow!("not an uncompressed secp256k1 public key"));
+    }
+    Ok(address_from_public_key(der[der.len() - 65..].to_vec())?.parse()?)
+}
+
+/// the DER of the PEM (the base64 lines between the BEGIN and END ones)
+fn pem_der(pem: &str) -> Result<Vec<u8>> {
+    let base64: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    base64_decode(&base64)
+}
+
+/// the date (YYYYMMDD) and the time (YYYYMMDD'T'HHMMSS'Z') of the unix timestamp in UTC
+fn amz_date(timestamp: u64) -> (String, String) {
+    // the civil date of the days since 1970-01-01 (H. Hinnant's algorithm)
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let secs = timestamp % 86400;
+    let date = format!("{year:04}{month:02}{day:02}");
+    let time = format!(
+        "{date}T{:02}{:02}{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    (date, time)
+}
+
+/// The AWS credentials of the requests
+struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    /// the token of the temporary credentials (empty if none)
+    session_token: String,
+}
+
+/// the canonical URI of the (percent-encoded) path: its segments are encoded again,
+/// as in the requests to the services other than S3
+fn canonical_uri(path: &str) -> String {
+    let uri = path
+        .split('/')
+        .map(|segment| {
+            segment
+                .bytes()
+                .map(|byte| match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                        (byte as char).to_string()
+                    }
+                    _ => format!("%{byte:02X}"),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    if uri.starts_with('/') {
+        uri
+    } else {
+        format!("/{uri}")
+    }
+}
+
+/// the `Authorization` header of the AWS Signature Version 4 of the request to the path
+/// (the headers must include `host` and `x-amz-date`)
+#[allow(clippy::too_many_arguments)]
+fn sigv4_authorization(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    amz_time: &str,
+) -> String {
+    let date = &amz_time[..8];
+    let mut headers: Vec<(String, &str)> = headers
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.trim()))
+        .collect();
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{method}\n{}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        canonical_uri(path),
+        hex::encode(sha256(body.to_vec()))
+    );
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_time}\n{scope}\n{}",
+        hex::encode(sha256(canonical_request.into_bytes()))
+    );
+    let key = [date, region, service, "aws4_request"].iter().fold(
+        format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+        |key, part| hmac_sha256(key, part.as_bytes().to_vec()),
+    );
+    let signature = hex::encode(hmac_sha256(key, string_to_sign.into_bytes()));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, \
+         Signature={signature}",
+        credentials.access_key_id
+    )
+}
+
+/// the KMS API of a secp256k1 key
+trait KmsApi: Send + Sync {
+    /// the DER SubjectPublicKeyInfo of the key
+    fn public_key_der(&self) -> Result<Vec<u8>>;
+    /// the DER ECDSA signature of the digest (as it is)
+    fn sign_der(&self, digest: H256) -> Result<Vec<u8>>;
+}
+
+/// AWS KMS (an `ECC_SECG_P256K1` key)
+struct AwsKms {
+    key_id: String,
+    region: String,
+    url: String,
+    credentials: AwsCredentials,
+}
+
+impl AwsKms {
+    fn new(config: &KmsConfig) -> Result<Self> {
+        // the credentials of the environment if not set
+        let env = |name: &str| std::env::var(name).unwrap_or_default();
+        let credentials = if config.access_key_id.is_empty() {
+            AwsCredentials {
+                access_key_id: env("AWS_ACCESS_KEY_ID"),
+                secret_access_key: env("AWS_SECRET_ACCESS_KEY"),
+                session_token: env("AWS_SESSION_TOKEN"),
+            }
+        } else {
+            AwsCredentials {
+                access_key_id: config.access_key_id.clone(),
+                secret_access_key: config.secret_access_key.clone(),
+                session_token: config.session_token.clone(),
+            }
+        };
+        if credentials.access_key_id.is_empty() || credentials.secret_access_key.is_empty() {
+            return Err(anyhow!("no AWS credentials"));
+        }
+        let region = if config.region.is_empty() {
+            env("AWS_REGION")
+        } else {
+            config.region.clone()
+        };
+        if region.is_empty() {
+            return Err(anyhow!("no AWS region"));
+        }
+        let url = if config.endpoint.is_empty() {
+            format!("https://kms.{region}.amazonaws.com/")
+        } else {
+            config.endpoint.clone()
+        };
+        Ok(Self {
+            key_id: config.key_id.clone(),
+            region,
+            url,
+            credentials,
+        })
+    }
+
+    /// calls the action (e.g. "Sign") of the KMS JSON API
+    fn call(&self, action: &str, body: Value) -> Result<Value> {
+        let url = url::Url::parse(&self.url)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            _ => return Err(anyhow!("invalid AWS KMS endpoint: {url}")),
+        };
+        let body = serde_json::to_vec(&body)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let (_, amz_time) = amz_date(timestamp);
+        let target = format!("TrentService.{action}");
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1"),
+            ("host", host.as_str()),
+            ("x-amz-date", amz_time.as_str()),
+            ("x-amz-target", target.as_str()),
+        ];
+        if !self.credentials.session_token.is_empty() {
+            headers.push(("x-amz-security-token", &self.credentials.session_token));
+        }
+        let authorization = sigv4_authorization(
+            &self.credentials,
+            &self.region,
+            "kms",
+            "POST",
+            url.path(),
+            &headers,
+            &body,
+            &amz_time,
+        );
+        let client = http::blocking_client()?;
+        let mut request = client
+            .post(url)
+            .header("authorization", authorization)
+            .body(body);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.header(*name, *value);
+        }
+        let response: Value = http::send_json(&client, request)?;
+        if response.get("__type").is_some() {
+            return Err(anyhow!("AWS KMS {action} failed: {response}"));
+        }
+        Ok(response)
+    }
+}
+
+impl KmsApi for AwsKms {
+    fn public_key_der(&self) -> Result<Vec<u8>> {
+        let response = self.call("GetPublicKey", json!({ "KeyId": self.key_id }))?;
+        base64_decode(&json_text(&response, "/PublicKey"))
+    }
+
+    fn sign_der(&self, digest: H256) -> Result<Vec<u8>> {
+        let response = self.call(
+            "Sign",
+            json!({
+                "KeyId": self.key_id,
+                "Message": base64_encode(digest.as_bytes()),
+                "MessageType": "DIGEST",
+                "SigningAlgorithm": "ECDSA_SHA_256",
+            }),
+        )?;
+        base64_decode(&json_text(&response, "/Signature"))
+    }
+}
+
+/// Google Cloud KMS (an `EC_SIGN_SECP256K1_SHA256` key version)
+struct GcpKms {
+    /// projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*
+    key_version: String,
+    url: String,
+    /// the access token set in the config, or the one of the metadata server and its expiry
+    access_token: Mutex<(String, Option<Instant>)>,
+}
+
+impl GcpKms {
+    fn new(config: &KmsConfig) -> Self {
+        Self {
+            key_version: config.key_id.trim_start_matches('/').to_owned(),
+            url: if config.endpoint.is_empty() {
+                GCP_KMS_URL.to_owned()
+            } else {
+                config.endpoint.trim_end_matches('/').to_owned()
+            },
+            access_token: Mutex::new((config.access_token.clone(), None)),
+        }
+    }
+
+    /// the access token: the one of the config, or the cached one of the metadata server
+    fn access_token(&self) -> Result<String> {
+        let mut token = self.access_token.lock().expect("gcp token lock");
+        match &*token {
+            (access_token, None) if !access_token.is_empty() => return Ok(access_token.clone()),
+            (access_token, Some(expiry)) if Instant::now() + TOKEN_REFRESH_MARGIN < *expiry => {
+                return Ok(access_token.clone())
+            }
+            _ => {}
+        }
+        let client = http::blocking_client()?;
+        let response: Value = http::send_json(
+            &client,
+            client
+                .get(GCP_METADATA_TOKEN_URL)
+                .header("Metadata-Flavor", "Google"),
+        )?;
+        let access_token = json_text(&response, "/access_token");
+        if access_token.is_empty() {
+            return Err(anyhow!(
+                "no access token from the metadata server: {response}"
+            ));
+        }
+        let expires_in = response["expires_in"].as_u64().unwrap_or_default();
+        *token = (
+            access_token.clone(),
+            Some(Instant::now() + Duration::from_secs(expires_in)),
+        );
+        Ok(access_token)
+    }
+
+    fn send(&self, request: reqwest::blocking::RequestBuilder) -> Result<Value> {
+        let client = http::blocking_client()?;
+        let response: Value = http::send_json(&client, request.bearer_auth(self.access_token()?))?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("Google Cloud KMS failed: {error}"));
+        }
+        Ok(response)
+    }
+}
+
+impl KmsApi for GcpKms {
+    fn public_key_der(&self) -> Result<Vec<u8>> {
+        let client = http::blocking_client()?;
+        let url = format!("{}/{}/publicKey", self.url, self.key_version);
+        let response = self.send(client.get(url))?;
+        pem_der(&json_text(&response, "/pem"))
+    }
+
+    fn sign_der(&self, digest: H256) -> Result<Vec<u8>> {
+        let client = http::blocking_client()?;
+        let url = format!("{}/{}:asymmetricSign", self.url, self.key_version);
+        let body = json!({ "digest": { "sha256": base64_encode(digest.as_bytes()) } });
+        let response = self.send(client.post(url).json(&body))?;
+        base64_decode(&json_text(&response, "/signature"))
+    }
+}
+
+/// A signer whose secp256k1 key is in a cloud KMS (only with the `kms` feature):
+/// the private key never leaves the KMS, the address is derived once from its public key
+pub(crate) struct KmsSigner {
+    api: Box<dyn KmsApi>,
+    address: Address,
+}
+
+impl KmsSigner {
+    pub(crate) fn new(config: &KmsConfig) -> Result<Self> {
+        if config.key_id.is_empty() {
+            return Err(anyhow!("no KMS key id"));
+        }
+        let api: Box<dyn KmsApi> = match config.provider {
+            KmsProvider::Aws => Box::new(AwsKms::new(config)?),
+            KmsProvider::Gcp => Box::new(GcpKms::new(config)),
+            _ => return Err(anyhow!("unknown KMS provider")),
+        };
+        let address = spki_address(&api.public_key_der()?)?;
+        Ok(Self { api, address })
+    }
+}
+
+impl Signer for KmsSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let rs = der_signature_rs(&self.api.sign_der(digest)?)?;
+        normalize_signature(&rs, digest, self.address)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_kms_encodings() {
+        // 2015-08-30T12:36:00Z
+        assert_eq!(
+            amz_date(1440938160),
+            ("20150830".to_owned(), "20150830T123600Z".to_owned())
+        );
+        assert_eq!(amz_date(951782400).0, "20000229");
+
+        // the AWS Signature Version 4 test suite (get-vanilla)
+        let credentials = AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: String::new(),
+        };
+        let authorization = sigv4_authorization(
+            &credentials,
+            "us-east-1",
+            "service",
+            "GET",
+            "/",
+            &[
+                ("Host", "example.amazonaws.com"),
+                ("X-Amz-Date", "20150830T123600Z"),
+            ],
+            b"",
+            "20150830T123600Z",
+        );
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+
+        assert_eq!(canonical_uri(""), "/");
+        assert_eq!(canonical_uri("/kms/eu-west-1"), "/kms/eu-west-1");
+        assert_eq!(canonical_uri("/a%20b/c"), "/a%2520b/c");
+
+        // r with a leading zero byte (positive) and a short s
+        let der = hex::decode(format!("3026022100{}0201{}", "ff".repeat(32), "07")).unwrap();
+        let rs = der_signature_rs(&der).unwrap();
+        assert_eq!(rs[..32], [0xff; 32]);
+        assert_eq!(rs[32..63], [0; 31]);
+        assert_eq!(rs[63], 7);
+        assert!(der_signature_rs(&der[..10]).is_err());
+
+        // the SubjectPublicKeyInfo of the Hardhat/Anvil account 0
+        let spki = hex::decode(
+            "3056301006072a8648ce3d020106052b8104000a034200\
+             048318535b54105d4a7aae60c08fc45f9687181b4fdfc625bd1a753fa7397fed75\
+             3547f11ca8696646f2f3acb08e31016afac23e630c5d11f59f61fef57b0d2aa5",
+        )
+        .unwrap();
+        assert_eq!(
+            spki_address(&spki).unwrap(),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+                .parse::<Address>()
+                .unwrap()
+        );
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64_encode(&spki)
+        );
+        assert_eq!(pem_der(&pem).unwrap(), spki);
+    }
+}
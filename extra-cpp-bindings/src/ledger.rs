@@ -0,0 +1,468 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::signers::to_eip155_v;
+use ethers::types::Address;
+
+use crate::ffi::{LedgerDevice, SignedTxResult, WalletConnectTxEip155};
+use crate::signer::normalize_signature;
+use crate::walletconnect::{eip1559_request, signed_tx_result};
+
+/// the size of the HID reports
+const HID_PACKET_SIZE: usize = 64;
+/// the channel of the HID packets
+const HID_CHANNEL: u16 = 0x0101;
+/// the tag of the APDU packets
+const HID_TAG_APDU: u8 = 0x05;
+
+/// the class of the instructions of the Ethereum app
+const CLA: u8 = 0xe0;
+const INS_GET_ADDRESS: u8 = 0x02;
+const INS_SIGN_TRANSACTION: u8 = 0x04;
+/// the most bytes of the data of an APDU
+const MAX_APDU_DATA: usize = 255;
+/// the first account of the Ethereum app (Ledger Live)
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// the HID reports of a Ledger device
+trait HidIo: Send {
+    fn write_packet(&self, packet: &[u8; HID_PACKET_SIZE]) -> Result<()>;
+    /// the next packet (it waits while the user confirms on the device)
+    fn read_packet(&self) -> Result<Vec<u8>>;
+}
+
+/// the HID packets (64 bytes) of the APDU
+fn hid_packets(apdu: &[u8]) -> Vec<[u8; HID_PACKET_SIZE]> {
+    // the APDU is prefixed by its length in the first packet
+    let mut data = (apdu.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(apdu);
+    data.chunks(HID_PACKET_SIZE - 5)
+        .enumerate()
+        .map(|(sequence, chunk)| {
+            let mut packet = [0u8; HID_PACKET_SIZE];
+            packet[..2].copy_from_slice(&HID_CHANNEL.to_be_bytes());
+            packet[2] = HID_TAG_APDU;
+            packet[3..5].copy_from_slice(&(sequence as u16).to_be_bytes());
+            packet[5..5 + chunk.len()].copy_from_slice(chunk);
+            packet
+        })
+        .collect()
+}
+
+/// reassembles the response APDU from the HID packets read one by one
+fn hid_response(mut read_packet: impl FnMut() -> Result<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut response = vec![];
+    let mut len = None;
+    for sequence in 0u16.. {
+        let packet = read_packet()?;
+        if packet.len() < 5
+            || packet[..2] != HID_CHANNEL.to_be_bytes()
+            || packet[2] != HID_TAG_APDU
+            || packet[3..5] != sequence.to_be_bytes()
+        {
+            return Err(anyhow!("invalid Ledger HID packet"));
+        }
+        let mut chunk = &packet[5..];
+        if sequence == 0 {
+            if chunk.len() < 2 {
+                return Err(anyhow!("invalid Ledger HID packet"));
+            }
+            len = Some(u16::from_be_bytes([chunk[0], chunk[1]]) as usize);
+            chunk = &chunk[2..];
+        }
+        let len = len.unwrap_or_default();
+        let take = chunk.len().min(len - response.len());
+        response.extend_from_slice(&chunk[..take]);
+        if response.len() == len {
+            break;
+        }
+    }
+    Ok(response)
+}
+
+/// the data of the response APDU if its status word is success
+fn response_data(mut response: Vec<u8>) -> Result<Vec<u8>> {
+    if response.len() < 2 {
+        return Err(anyhow!("the Ledger response has no status word"));
+    }
+    let status = response.split_off(response.len() - 2);
+    match u16::from_be_bytes([status[0], status[1]]) {
+        0x9000 => Ok(response),
+        0x6985 => Err(anyhow!("the request was rejected on the Ledger")),
+        0x5515 => Err(anyhow!("the Ledger is locked")),
+        0x6d00 | 0x6e00 | 0x6e01 => Err(anyhow!("the Ethereum app is not open on the Ledger")),
+        0x6a80 => Err(anyhow!(
+            "the Ledger rejected the data (blind signing may be disabled in the Ethereum app)"
+        )),
+        status => Err(anyhow!("the Ledger failed with the status {status:#06x}")),
+    }
+}
+
+/// the indexes of the BIP-32 derivation path, e.g. "m/44'/60'/0'/0/0"
+/// (the Ledger Live one if empty)
+fn parse_derivation_path(path: &str) -> Result<Vec<u32>> {
+    let path = if path.is_empty() {
+        DEFAULT_DERIVATION_PATH
+    } else {
+        path
+    };
+    let indexes = path
+        .trim_start_matches("m/")
+        .split('/')
+        .map(|index| {
+            let (index, hardened) = match index.strip_suffix('\'') {
+                Some(index) => (index, 0x8000_0000),
+                None => (index, 0),
+            };
+            match index.parse::<u32>() {
+                Ok(index) if index < 0x8000_0000 => Ok(index | hardened),
+                _ => Err(anyhow!("invalid derivation path: {path}")),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if indexes.is_empty() || indexes.len() > 10 {
+        return Err(anyhow!("invalid derivation path: {path}"));
+    }
+    Ok(indexes)
+}
+
+/// the encoding of the derivation path in the APDUs (its length and the big-endian indexes)
+fn encode_path(path: &[u32]) -> Vec<u8> {
+    let mut data = vec![path.len() as u8];
+    for index in path {
+        data.extend_from_slice(&index.to_be_bytes());
+    }
+    data
+}
+
+fn apdu(ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    let mut apdu = vec![CLA, ins, p1, p2, data.len() as u8];
+    apdu.extend_from_slice(data);
+    apdu
+}
+
+/// the address of the response of GET_ADDRESS (the public key, then the hexadecimal address)
+fn parse_address_response(data: &[u8]) -> Result<Address> {
+    let invalid = || anyhow!("invalid Ledger address response");
+    let public_key_len = *data.first().ok_or_else(invalid)? as usize;
+    let address_len = *data.get(1 + public_key_len).ok_or_else(invalid)? as usize;
+    let address = data
+        .get(2 + public_key_len..2 + public_key_len + address_len)
+        .ok_or_else(invalid)?;
+    Ok(std::str::from_utf8(address)?.parse()?)
+}
+
+/// A Ledger hardware wallet with the Ethereum app (only with the `ledger` feature):
+/// the keys never leave the device and every signature is confirmed on it
+pub struct LedgerWallet {
+    device: Mutex<Box<dyn HidIo>>,
+    chain_id: u64,
+}
+
+impl LedgerWallet {
+    /// opens the device of the HID path (the first Ledger if empty)
+    pub(crate) fn open(device_path: &str, chain_id: u64) -> Result<Self> {
+        Ok(Self::with_device(hid::open(device_path)?, chain_id))
+    }
+
+    fn with_device(device: Box<dyn HidIo>, chain_id: u64) -> Self {
+        Self {
+            device: Mutex::new(device),
+            chain_id,
+        }
+    }
+
+    /// sends the command APDU and returns the data of the response
+    fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>> {
+        let device = self.device.lock().expect("ledger lock");
+        for packet in hid_packets(apdu) {
+            device.write_packet(&packet)?;
+        }
+        response_data(hid_response(|| device.read_packet())?)
+    }
+
+    fn address(&self, path: &[u32], verify_on_device: bool) -> Result<Address> {
+        let data = self.exchange(&apdu(
+            INS_GET_ADDRESS,
+            u8::from(verify_on_device),
+            0,
+            &encode_path(path),
+        ))?;
+        parse_address_response(&data)
+    }
+
+    /// the address of the derivation path (hexadecimal, 0x-prefixed); if `verify_on_device`,
+    /// it's displayed on the device and returned once the user confirms it
+    pub fn get_address(&self, derivation_path: String, verify_on_device: bool) -> Result<String> {
+        let path = parse_derivation_path(&derivation_path)?;
+        Ok(format!("{:?}", self.address(&path, verify_on_device)?))
+    }
+
+    /// signs the EIP-1559 transaction with the account of the derivation path
+    /// (on the chain of the wallet if the transaction doesn't set it),
+    /// once the user confirms it on the device
+    pub fn sign_eip155_transaction(
+        &self,
+        derivation_path: String,
+        tx: &WalletConnectTxEip155,
+    ) -> Result<SignedTxResult> {
+        let path = parse_derivation_path(&derivation_path)?;
+        let address = self.address(&path, false)?;
+        let mut request = eip1559_request(tx)?;
+        request.from = Some(address);
+        let chain_id = if tx.common.chainid != 0 {
+            tx.common.chainid
+        } else {
+            self.chain_id
+        };
+        request.chain_id = Some(chain_id.into());
+        let typedtx = TypedTransaction::Eip1559(request);
+        let fee_cap = crate::feecap::fee_cap_override(&tx.common)?;
+        let reservation = crate::signer::check_transaction_blocking(&typedtx, fee_cap)?;
+
+        // the path then the unsigned transaction (0x02 || rlp), in chunks of 255 bytes
+        let mut data = encode_path(&path);
+        data.extend_from_slice(&typedtx.rlp());
+        let mut response = vec![];
+        for (i, chunk) in data.chunks(MAX_APDU_DATA).enumerate() {
+            let p1 = if i == 0 { 0x00 } else { 0x80 };
+            response = self.exchange(&apdu(INS_SIGN_TRANSACTION, p1, 0, chunk))?;
+        }
+        // v, r, s: v is recovered, as the app truncates it for the large chain ids
+        if response.len() != 65 {
+            return Err(anyhow!("invalid Ledger signature response"));
+        }
+        let digest = typedtx.sighash();
+        let mut sig = normalize_signature(&response[1..], digest, address)?;
+        sig.v = to_eip155_v((sig.v - 27) as u8, chain_id);
+        reservation.commit();
+        Ok(signed_tx_result(&typedtx, &sig))
+    }
+}
+
+/// the Ledger devices connected over USB
+pub(crate) fn list_devices() -> Result<Vec<LedgerDevice>> {
+    hid::list_devices()
+}
+
+#[cfg(feature = "ledger")]
+mod hid {
+    use std::ffi::CString;
+
+    use anyhow::{anyhow, Result};
+    use hidapi::{DeviceInfo, HidApi, HidDevice};
+
+    use super::{HidIo, HID_PACKET_SIZE};
+    use crate::ffi::LedgerDevice;
+
+    /// the USB vendor id of Ledger
+    const LEDGER_VENDOR_ID: u16 = 0x2c97;
+    /// the HID usage page of the APDU interface (the interface 0 where it's not reported)
+    const LEDGER_USAGE_PAGE: u16 = 0xffa0;
+    /// how long to wait for a packet (e.g. while the user confirms on the device)
+    const READ_TIMEOUT_MS: i32 = 120_000;
+
+    fn is_ledger(device: &DeviceInfo) -> bool {
+        device.vendor_id() == LEDGER_VENDOR_ID
+            && (device.usage_page() == LEDGER_USAGE_PAGE || device.interface_number() == 0)
+    }
+
+    pub(super) fn list_devices() -> Result<Vec<LedgerDevice>> {
+        let api = HidApi::new()?;
+        Ok(api
+            .device_list()
+            .filter(|device| is_ledger(device))
+            .map(|device| LedgerDevice {
+                path: device.path().to_string_lossy().into_owned(),
+                product: device.product_string().unwrap_or_default().to_owned(),
+                serial_number: device.serial_number().unwrap_or_default().to_owned(),
+            })
+            .collect())
+    }
+
+    pub(super) fn open(device_path: &str) -> Result<Box<dyn HidIo>> {
+        let api = HidApi::new()?;
+        let device = if device_path.is_empty() {
+            api.device_list()
+                .find(|device| is_ledger(device))
+                .ok_or_else(|| anyhow!("no Ledger connected"))?
+                .open_device(&api)?
+        } else {
+            api.open_path(&CString::new(device_path)?)?
+        };
+        Ok(Box::new(device))
+    }
+
+    impl HidIo for HidDevice {
+        fn write_packet(&self, packet: &[u8; HID_PACKET_SIZE]) -> Result<()> {
+            // prefixed by the report id (0)
+            let mut report = vec![0u8];
+            report.extend_from_slice(packet);
+            self.write(&report)?;
+            Ok(())
+        }
+
+        fn read_packet(&self) -> Result<Vec<u8>> {
+            let mut packet = vec![0u8; HID_PACKET_SIZE];
+            let len = self.read_timeout(&mut packet, READ_TIMEOUT_MS)?;
+            if len == 0 {
+                return Err(anyhow!("the Ledger didn't respond"));
+            }
+            packet.truncate(len);
+            Ok(packet)
+        }
+    }
+}
+
+/// without USB HID: the Ledger wallets fail
+#[cfg(not(feature = "ledger"))]
+mod hid {
+    use anyhow::{anyhow, Result};
+
+    use super::HidIo;
+    use crate::ffi::LedgerDevice;
+
+    pub(super) fn list_devices() -> Result<Vec<LedgerDevice>> {
+        Err(anyhow!("the Ledger wallets need the `ledger` feature"))
+    }
+
+    pub(super) fn open(_device_path: &str) -> Result<Box<dyn HidIo>> {
+        Err(anyhow!("the Ledger wallets need the `ledger` feature"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::keccak256;
+
+    /// the Ethereum app emulated with the in-process key of every path
+    struct EmulatedApp {
+        wallet: LocalWallet,
+        /// the packets of the command APDU being written
+        command: Mutex<Vec<[u8; HID_PACKET_SIZE]>>,
+        /// the packets of the response being read
+        response: Mutex<Vec<Vec<u8>>>,
+        /// the data of the transaction being signed
+        tx: Mutex<Vec<u8>>,
+    }
+
+    impl EmulatedApp {
+        fn handle(&self, apdu: &[u8]) -> Vec<u8> {
+            assert_eq!(apdu[0], CLA);
+            assert_eq!(apdu[4] as usize, apdu.len() - 5);
+            let data = &apdu[5..];
+            let mut response = match apdu[1] {
+                INS_GET_ADDRESS => {
+                    let public_key = self.wallet.signer().verifying_key().to_encoded_point(false);
+                    let address = hex::encode(self.wallet.address());
+                    let mut response = vec![65];
+                    response.extend_from_slice(public_key.as_bytes());
+                    response.push(40);
+                    response.extend_from_slice(address.as_bytes());
+                    response
+                }
+                INS_SIGN_TRANSACTION => {
+                    let mut tx = self.tx.lock().unwrap();
+                    if apdu[2] == 0 {
+                        // skips the path
+                        *tx = data[1 + 4 * data[0] as usize..].to_vec();
+                    } else {
+                        tx.extend_from_slice(data);
+                    }
+                    let sig = self.wallet.sign_hash(keccak256(&*tx).into()).unwrap();
+                    let mut response = vec![(sig.v - 27) as u8];
+                    response.extend_from_slice(&sig.to_vec()[..64]);
+                    response
+                }
+                _ => vec![],
+            };
+            response.extend_from_slice(&[0x90, 0x00]);
+            response
+        }
+    }
+
+    impl HidIo for EmulatedApp {
+        fn write_packet(&self, packet: &[u8; HID_PACKET_SIZE]) -> Result<()> {
+            let mut command = self.command.lock().unwrap();
+            command.push(*packet);
+            let mut packets = command.iter();
+            let len = u16::from_be_bytes([command[0][5], command[0][6]]) as usize;
+            if (len + 2).div_ceil(HID_PACKET_SIZE - 5) == command.len() {
+                let apdu = hid_response(|| Ok(packets.next().unwrap().to_vec()))?;
+                command.clear();
+                let mut response = hid_packets(&self.handle(&apdu));
+                response.reverse();
+                *self.response.lock().unwrap() = response.iter().map(|p| p.to_vec()).collect();
+            }
+            Ok(())
+        }
+
+        fn read_packet(&self) -> Result<Vec<u8>> {
+            self.response
+                .lock()
+                .unwrap()
+                .pop()
+                .ok_or_else(|| anyhow!("no response"))
+        }
+    }
+
+    #[cfg(not(feature = "ledger"))]
+    #[test]
+    fn test_ledger_without_feature() {
+        assert!(list_devices().is_err());
+        assert!(LedgerWallet::open("", 25).is_err());
+    }
+
+    #[test]
+    fn test_ledger_emulated_app() {
+        assert_eq!(
+            parse_derivation_path("").unwrap(),
+            vec![0x8000_002c, 0x8000_003c, 0x8000_0000, 0, 0]
+        );
+        assert!(parse_derivation_path("m/44'/x").is_err());
+        assert!(response_data(vec![0x69, 0x85]).is_err());
+
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let ledger = LedgerWallet::with_device(
+            Box::new(EmulatedApp {
+                wallet,
+                command: Mutex::new(vec![]),
+                response: Mutex::new(vec![]),
+                tx: Mutex::new(vec![]),
+            }),
+            338,
+        );
+        assert_eq!(
+            ledger.get_address(String::new(), true).unwrap(),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        // the data makes the transaction longer than an APDU
+        let tx = WalletConnectTxEip155 {
+            to: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8".into(),
+            value: "1000".into(),
+            data: vec![7; 400],
+            common: crate::ffi::WalletConnectTxCommon {
+                gas_limit: "21000".into(),
+                gas_price: "5000000000".into(),
+                nonce: "1".into(),
+                ..Default::default()
+            },
+        };
+        let signed = ledger.sign_eip155_transaction(String::new(), &tx).unwrap();
+        let (decoded, sig) =
+            TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(&signed.raw_tx)).unwrap();
+        assert_eq!(decoded.chain_id(), Some(338.into()));
+        assert_eq!(
+            sig.recover(decoded.sighash()).unwrap(),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+                .parse::<Address>()
+                .unwrap()
+        );
+    }
+}
@@ -1,28 +1,200 @@
+/// ABI decoding of event logs
+mod abi;
+/// persistent address book (labels of the addresses)
+mod addressbook;
+/// Merkle airdrops (the claims of the MerkleDistributor contracts)
+mod airdrop;
+/// decimal formatting of the token amounts
+mod amount;
+/// unified asset model of the EVM and Cosmos accounts
+mod asset;
+/// audit log of the signing requests
+mod audit;
+/// sign-in with a wallet: challenges and session tokens
+mod auth;
+/// plain C interface
+mod capi;
+/// the cache of the account states (nonces, balances, code) by chain and address
+mod chainstate;
+/// hexadecimal, base64 and RLP encodings
+mod codec;
+/// Cosmos (crypto.org chain) account state and broadcasting
+mod cosmos;
+/// deterministic dev-mode wallets signing locally (the `dev-wallet` feature)
+#[cfg(feature = "dev-wallet")]
+mod devwallet;
+/// dry runs of the sends (simulated and signed, not broadcast)
+mod dryrun;
+/// token-gated entitlement checks
+mod entitlement;
+/// the network environment of the SDK (mainnet, testnet or local)
+mod environment;
 mod error;
+/// failover between the explorers (Cronoscan and BlockScout)
+mod failover;
+/// testnet faucet client
+mod faucet;
+/// caps of the transaction fees
+mod feecap;
+/// gas usage reports by contract method
+mod gasreport;
+/// gRPC-web transport and minimal protobuf encoding
+mod grpcweb;
+/// hashing utilities (Keccak-256, SHA-256, EIP-191, addresses)
+mod hashing;
+/// paginated transaction history
+mod history;
+/// shared HTTP client with the record/replay support
+mod http;
+/// idempotency keys of the sent transactions
+mod idempotency;
+/// rate-limited fetcher of the NFT images with a disk cache
+mod imagecache;
+/// IPFS uploads via the pinning services (Pinata, web3.storage, Kubo)
+mod ipfs;
+/// signers backed by the cloud KMSes (the `kms` feature)
+#[cfg(feature = "kms")]
+mod kms;
+/// Ledger hardware wallets over USB HID (the `ledger` feature)
+mod ledger;
+/// local database of the synced transaction history
+mod localdb;
+/// local Anvil nodes for the integration tests (the `test-utils` feature)
+#[cfg(feature = "test-utils")]
+mod localnode;
+/// checkpointed scanner of contract event logs
+mod logscanner;
+/// verified downloads of the NFT media (hash and CID checks, size and content type limits)
+mod media;
+/// the memory allocated by the SDK and the platform allocator hooks (the `memory-stats` feature)
+mod memory;
+/// catalog of the messages with stable ids (for the localization)
+mod messages;
+/// NFT mints with the metadata uploaded to IPFS
+mod mint;
+/// threshold (MPC) signing run round by round over an opaque transport
+mod mpc;
+/// the network usage of the SDK and its budget
+mod netusage;
+/// NFT collection stats of the marketplaces
+mod nftstats;
+/// fiat on-ramp quotes and redirect urls (Transak, MoonPay)
+mod onramp;
 /// Crypto.com Pay basic support
 mod pay;
+/// transaction policy (spending limits, allowed contracts and methods)
+mod policy;
+/// human-readable transaction previews
+mod preview;
+/// player profiles linking the verified addresses of a player
+mod profile;
+/// the filters of the transaction history and the token transfers
+mod queryfilter;
+/// exchange rates refreshed at an interval
+mod rates;
+/// JSON-RPC queries of the account state
+mod rpc;
+/// the options of the SDK's tokio runtimes
+mod runtime;
+/// denylist and remote screening of the transaction destinations
+mod screening;
+/// signature encodings (rsv, EIP-2098)
+mod signature;
+/// signers of the digests: the in-process keys or the external ones (e.g. HSMs)
+mod signer;
+/// snapshots of the token holders at a block
+mod snapshot;
+/// crypto.org chain staking queries
+mod staking;
+/// token swaps and liquidity positions of the UniswapV2-style DEXes (VVS Finance, MM Finance)
+mod swap;
+/// the validated JSON of the transaction requests
+mod txjson;
+/// durable queue of the transactions of the custodial wallets
+mod txqueue;
+/// confirmations and reorgs of the sent transactions
+mod txtracker;
+/// blittable exports for Unity (C# P/Invoke)
+#[cfg(feature = "unity")]
+mod unity;
 /// Wallect Connect registry of wallets/apps support
 mod wallectconnectregistry;
 mod walletconnect;
+/// WalletConnect 2.0 client
+mod walletconnect2;
+/// polling watcher of address activity
+mod watcher;
+/// read-only wallets of an address (no signing)
+mod watchwallet;
+/// wrapping of the native coin (WCRO)
+mod wrapped;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::Result;
 
-use ethers::core::types::{BlockNumber, Chain};
+use addressbook::AddressBook;
+use airdrop::{build_airdrop_claim_transaction, verify_airdrop_claim, AirdropTree};
+use amount::{amount_format_for_locale, format_amount};
+use auth::AuthServer;
+use bindings_common::explorer::RawBlockScoutTransfer;
+use codec::{
+    base64_decode, base64_encode, hex_decode, hex_encode, rlp_encode_strings, rlp_encode_u64s,
+};
+use cosmos::CosmosAccountSync;
+use defi_wallet_connect::{dns, tls};
+#[cfg(feature = "dev-wallet")]
+use devwallet::DevWallet;
+use error::GameSdkError;
+use ethers::core::types::{Address, Chain};
 use ethers::etherscan::{
     account::{
-        ERC20TokenTransferEvent, ERC721TokenTransferEvent, NormalTransaction, TokenQueryOption,
+        ERC20TokenTransferEvent, ERC721TokenTransferEvent, NormalTransaction, Sort,
+        TokenQueryOption, TxListParams,
     },
     Client,
 };
+use feecap::check_transaction_fees;
 use ffi::{
-    CryptoComPaymentResponse, ImageUrl, Platform, QueryOption, RawTokenResult, RawTxDetail,
-    TokenHolderDetail, WalletEntry,
+    AddressTokens, CosmosTransport, CryptoComPaymentResponse, ImageUrl, Platform, QueryOption,
+    RawTokenResult, RawTokenTxDetail, RawTxDetail, TokenHolderDetail, ValueKind, WalletEntry,
 };
+use hashing::{address_from_public_key, hash_eip191_message, hmac_sha256, keccak256, sha256};
+use history::HistoryCursor;
+use ipfs::{ipfs_gateway_url, ipfs_uri_to_gateway_url};
+#[cfg(feature = "kms")]
+use kms::KmsSigner;
+use ledger::LedgerWallet;
+use localdb::LocalHistory;
+#[cfg(feature = "test-utils")]
+use localnode::LocalNode;
+use logscanner::LogScanner;
+use media::verify_media_hash;
+use mpc::MpcSigner;
+use profile::PlayerProfile;
 use qrcodegen::QrCode;
 use qrcodegen::QrCodeEcc;
+use rates::RateService;
 use serde::{Deserialize, Serialize};
+use signature::{
+    signature_from_eip2098, signature_from_rsv, signature_to_eip2098, signature_to_rsv,
+};
+use signer::ExternalSigner;
+use swap::{build_swap_transaction, dex_router_address, swap_amount_out_min};
+use tokio::sync::Semaphore;
+use txjson::{transaction_json_schema, validate_transaction_json};
+use txqueue::TxQueue;
+use txtracker::TxTracker;
+pub use walletconnect::ContractAction;
 use walletconnect::WalletconnectClient;
+use walletconnect2::{
+    walletconnect2_client_new, walletconnect2_migrate_session, walletconnect2_restore_client,
+    Walletconnect2Client,
+};
+use watcher::AddressWatcher;
+use watchwallet::WatchWallet;
+use wrapped::{build_unwrap_transaction, build_wrap_transaction, wcro_address};
 
 #[cxx::bridge(namespace = "com::crypto::game_sdk")]
 mod ffi {
@@ -53,6 +225,111 @@ mod ffi {
         fn onDisconnected(&self, sessioninfo: &WalletConnectSessionInfo);
         fn onConnecting(&self, sessioninfo: &WalletConnectSessionInfo);
         fn onUpdated(&self, sessioninfo: &WalletConnectSessionInfo);
+        fn onError(&self, message: &str);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/walletconnect2.h");
+
+        type WalletConnect2SessionProposalCallback;
+
+        fn onSessionProposal(&self, namespaces: &WalletConnect2Namespaces) -> bool;
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/addresswatcher.h");
+
+        type AddressWatcherCallback;
+
+        fn onNewTransaction(&self, address: &str, tx: &RawTxDetail);
+        fn onNewTokenTransfer(&self, address: &str, transfer: &RawTokenTxDetail);
+        fn onError(&self, message: &str);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/logscanner.h");
+
+        type LogScannerCallback;
+
+        fn onEvent(&self, event: &DecodedEvent);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/snapshot.h");
+
+        type SnapshotProgressCallback;
+
+        fn onProgress(&self, scanned_block: u64, block_number: u64, holders: u64);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/rateservice.h");
+
+        type RateServiceCallback;
+
+        fn onRateUpdated(&self, rate: &ExchangeRate);
+        fn onError(&self, message: &str);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/externalsigner.h");
+
+        type ExternalSignerCallback;
+
+        fn signDigest(&self, address: &str, digest: &[u8]) -> Result<Vec<u8>>;
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/mpc.h");
+
+        type MpcPartyCallback;
+
+        fn start(&self, session_id: &str, digest: &[u8]) -> Result<Vec<MpcMessage>>;
+        fn handle(&self, session_id: &str, incoming: &Vec<MpcMessage>) -> Result<MpcRoundResult>;
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/mpc.h");
+
+        type MpcTransportCallback;
+
+        fn exchange(&self, session_id: &str, outgoing: &Vec<MpcMessage>)
+            -> Result<Vec<MpcMessage>>;
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/txtracker.h");
+
+        type TxTrackerCallback;
+
+        fn onConfirmations(&self, receipt: &TrackedTxReceipt);
+        fn onConfirmed(&self, receipt: &TrackedTxReceipt);
+        fn onReorged(&self, tx_hash: &str, block_number: u64, block_hash: &str);
+        fn onError(&self, message: &str);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/policy.h");
+
+        type PolicyCallback;
+
+        fn onPolicyBlocked(&self, violation: &PolicyViolation);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/netusage.h");
+
+        type NetworkBudgetCallback;
+
+        fn onBudgetExceeded(&self, usage: &NetworkUsage);
+    }
+
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/runtime.h");
+
+        type RuntimeThreadCallback;
+
+        fn onThreadStart(&self, thread_name: &str);
     }
 
     unsafe extern "C++" {
@@ -122,6 +399,13 @@ mod ffi {
         pub nonce: String,       // decimal string
         pub chainid: u64,        // integer u64
         pub web3api_url: String, // string
+        /// decimal string, EIP-1559 max fee per gas (gas_price is used if empty)
+        pub max_fee_per_gas: String,
+        /// decimal string, EIP-1559 max priority fee per gas (gas_price is used if empty)
+        pub max_priority_fee_per_gas: String,
+        /// decimal string, the cap of the total fee (gas limit times max fee per gas)
+        /// of this transaction replacing the fee caps (they apply if empty)
+        pub max_total_fee: String,
     }
 
     /// wallet connect cronos(eth) eip155-tx signing info
@@ -134,6 +418,71 @@ mod ffi {
         pub common: WalletConnectTxCommon,
     }
 
+    /// ERC20 approval (`approve`)
+    #[derive(Debug, Default)]
+    pub struct Erc20ApprovalAction {
+        pub contract_address: String, // hexstring, "0x..."
+        pub approved_address: String, // hexstring, "0x..."
+        pub amount: String,           // decimal string, in base units
+    }
+
+    /// ERC20 transfer (`transfer`, or `transferFrom` if from_address is not empty)
+    #[derive(Debug, Default)]
+    pub struct Erc20TransferAction {
+        pub contract_address: String, // hexstring, "0x..."
+        pub from_address: String,     // hexstring, "0x..." (can be empty)
+        pub to_address: String,       // hexstring, "0x..."
+        pub amount: String,           // decimal string, in base units
+    }
+
+    /// ERC721 transfer (`safeTransferFrom` if safe, otherwise `transferFrom`)
+    #[derive(Debug, Default)]
+    pub struct Erc721TransferAction {
+        pub contract_address: String, // hexstring, "0x..."
+        pub from_address: String,     // hexstring, "0x..."
+        pub to_address: String,       // hexstring, "0x..."
+        pub token_id: String,         // decimal string
+        pub safe: bool,
+        pub additional_data: Vec<u8>, // data passed to the receiver (only if safe)
+    }
+
+    /// ERC1155 transfer (`safeTransferFrom`)
+    #[derive(Debug, Default)]
+    pub struct Erc1155TransferAction {
+        pub contract_address: String, // hexstring, "0x..."
+        pub from_address: String,     // hexstring, "0x..."
+        pub to_address: String,       // hexstring, "0x..."
+        pub token_id: String,         // decimal string
+        pub amount: String,           // decimal string
+        pub additional_data: Vec<u8>, // data passed to the receiver
+    }
+
+    /// signed transaction
+    #[derive(Debug, Default)]
+    pub struct SignedTxResult {
+        pub raw_tx: Vec<u8>,  // rlp-encoded signed transaction, to be broadcast
+        pub tx_hash: Vec<u8>, // transaction hash, 32 bytes
+        pub signature: SignatureParts,
+    }
+
+    /// signature components
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    pub struct SignatureParts {
+        pub r: Vec<u8>, // 32 bytes
+        pub s: Vec<u8>, // 32 bytes
+        pub v: u64,     // 27/28, 0/1 or EIP-155 (chain_id * 2 + 35/36)
+    }
+
+    /// the information for the wallet's prompt of the next signing/sending request
+    /// (empty fields are not sent; the wallets that do not support it ignore it)
+    #[derive(Debug, Default)]
+    pub struct WalletConnectRequestMetadata {
+        pub name: String,
+        pub description: String,
+        pub icon_url: String,
+        pub message: String, // explanation of the request
+    }
+
     /// cronos address info
     pub struct WalletConnectAddress {
         pub address: [u8; 20], // address, as bytes, 20 bytes
@@ -145,6 +494,70 @@ mod ffi {
         pub chain_id: u64,
     }
 
+    /// the namespaces settled by the wallet (WalletConnect 2.0)
+    pub struct WalletConnect2Namespaces {
+        /// the EVM accounts with their chain ids (e.g. "eip155:25:0x...")
+        pub accounts: Vec<String>,
+        pub chain_ids: Vec<u64>,
+        pub methods: Vec<String>,
+        pub events: Vec<String>,
+        /// the Cosmos accounts with their chain ids (e.g. "cosmos:crypto-org-chain-mainnet-1:cro1...")
+        pub cosmos_accounts: Vec<String>,
+    }
+
+    /// what's kept from the WalletConnect 1.0 session to pair the wallet again
+    #[derive(Serialize, Debug, Default)]
+    pub struct RePairing {
+        /// if the wallet had approved the 1.0 session
+        pub was_connected: bool,
+        /// the accounts of the 1.0 session (empty if it wasn't approved)
+        pub accounts: Vec<String>,
+        /// the chain id of the 1.0 session (or the default one if it's unknown)
+        pub chain_id: u64,
+        /// the name of the wallet (empty if unknown), e.g. to tell the player which wallet to open
+        pub wallet_name: String,
+        /// json of the client metadata of the 1.0 session (for `walletconnect2_client_new`)
+        pub client_meta: String,
+        /// json of the namespaces required on the chain (for `walletconnect2_client_new`)
+        pub required_namespaces: String,
+    }
+
+    /// the saved WalletConnect session after the migration
+    #[derive(Serialize, Debug)]
+    pub struct MigrationOutcome {
+        /// a WalletConnect 2.0 session: `session_info` is restored as is
+        /// (`walletconnect2_restore_client`), otherwise the wallet has to pair again
+        pub current: bool,
+        /// the session info of the 2.0 session (empty if the wallet has to pair again)
+        pub session_info: String,
+        /// what's kept from the 1.0 session (empty if it's a 2.0 session)
+        pub re_pairing: RePairing,
+    }
+
+    /// the signature of the Cosmos signing requests (WalletConnect 2.0)
+    pub struct WalletConnect2CosmosSignature {
+        /// e.g. "tendermint/PubKeySecp256k1"
+        pub pub_key_type: String,
+        pub pub_key: Vec<u8>,
+        pub signature: Vec<u8>,
+        /// json of the sign doc signed by the wallet (it may differ from the request, e.g. in the fee)
+        pub signed_doc: String,
+    }
+
+    /// the event of a Crypto.com Pay webhook callback
+    #[derive(Debug)]
+    pub struct CryptoPayWebhookEvent {
+        pub id: String,
+        /// e.g. "payment.created", "payment.captured" or "payment.refund_transferred"
+        pub event_type: String,
+        /// unix timestamp
+        pub created: u64,
+        /// the payment of the payment events (empty for the refund events)
+        pub payment: CryptoComPaymentResponse,
+        /// the JSON of the event object (e.g. the full payment or refund object)
+        pub object_json: String,
+    }
+
     /// the subset of payment object from https://pay-docs.crypto.com
     #[derive(Debug)]
     pub struct CryptoComPaymentResponse {
@@ -168,8 +581,21 @@ mod ffi {
         pub status: String,
     }
 
+    /// What the value of a transaction or a token transfer is
+    #[derive(Serialize, Deserialize, Debug)]
+    pub enum ValueKind {
+        /// the decimals of the value are unknown
+        Unknown,
+        /// the amount of the native coin (in wei)
+        Native,
+        /// the amount of the ERC-20 tokens (in the base units of `token_decimal`)
+        Fungible,
+        /// the number of the NFTs (their id is in `token_id`)
+        NonFungible,
+    }
+
     /// Raw transaction details (extracted from Cronoscan/Etherscan or BlockScout API)
-    #[derive(Debug, PartialEq, Eq)]
+    #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
     pub struct RawTxDetail {
         /// Transaction hash
         pub hash: String,
@@ -179,12 +605,79 @@ mod ffi {
         pub from_address: String,
         /// the value sent in decimal (in base tokens)
         pub value: String,
+        /// the value in the base units in decimal (the number of the NFTs for ERC-721)
+        #[serde(default)]
+        pub raw_value: String,
+        /// the value in the tokens with their decimals, e.g. "1.5"
+        /// (the raw value if the decimals are unknown)
+        #[serde(default)]
+        pub formatted_value: String,
+        #[serde(default)]
+        pub value_kind: ValueKind,
         /// block number when it happened
         pub block_no: u64,
         /// the time it happened
         pub timestamp: String,
+        /// the time it happened in seconds since the Unix epoch (0 if it's unknown)
+        #[serde(default)]
+        pub timestamp_epoch: u64,
+        /// the time it happened in ISO-8601 in UTC, e.g. "2022-03-03T14:35:56Z"
+        /// (empty if it's unknown)
+        #[serde(default)]
+        pub timestamp_iso: String,
         /// the address of the contract (if no contract, it's an empty string)
         pub contract_address: String,
+        /// the address book name of the counterparty (empty if it's not labelled;
+        /// see `AddressBook::label_transactions`)
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        pub display_name: String,
+    }
+
+    /// Raw token transfer details (extracted from Cronoscan/Etherscan or BlockScout API)
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+    pub struct RawTokenTxDetail {
+        /// Transaction hash
+        pub hash: String,
+        /// the hexadecimal address of the receiver
+        pub to_address: String,
+        /// the hexadecimal address of the sender
+        pub from_address: String,
+        /// the value sent in decimal (in base tokens; 1 for ERC-721)
+        pub value: String,
+        /// the value in the base units in decimal (the number of the NFTs for ERC-721)
+        #[serde(default)]
+        pub raw_value: String,
+        /// the value in the tokens with their decimals, e.g. "1.5"
+        /// (the raw value if the decimals are unknown)
+        #[serde(default)]
+        pub formatted_value: String,
+        #[serde(default)]
+        pub value_kind: ValueKind,
+        /// block number when it happened
+        pub block_no: u64,
+        /// the time it happened
+        pub timestamp: String,
+        /// the time it happened in seconds since the Unix epoch (0 if it's unknown)
+        #[serde(default)]
+        pub timestamp_epoch: u64,
+        /// the time it happened in ISO-8601 in UTC, e.g. "2022-03-03T14:35:56Z"
+        /// (empty if it's unknown)
+        #[serde(default)]
+        pub timestamp_iso: String,
+        /// the address of the token contract
+        pub contract_address: String,
+        /// the human-readable name of the token
+        pub token_name: String,
+        /// the ticker for the token
+        pub token_symbol: String,
+        /// the number of decimal places (empty for NFTs)
+        pub token_decimal: String,
+        /// the token id (empty for ERC-20)
+        pub token_id: String,
+        /// the address book name of the counterparty (empty if it's not labelled;
+        /// see `AddressBook::label_token_transfers`)
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        pub display_name: String,
     }
 
     /// Token ownership result detail from BlockScout API
@@ -209,6 +702,33 @@ mod ffi {
         pub token_type: String,
     }
 
+    /// Filter of the local history queries
+    pub struct LocalHistoryFilter {
+        /// the address (empty for all the registered addresses)
+        pub address: String,
+        /// the token contract address (empty for all; only used for token transfers)
+        pub contract_address: String,
+        /// the first block
+        pub from_block: u64,
+        /// the last block (0 for no limit)
+        pub to_block: u64,
+        /// the maximum number of results (0 for no limit)
+        pub limit: u64,
+        /// whether the results are ordered from the newest block
+        pub newest_first: bool,
+    }
+
+    /// Owned tokens of one of the addresses in a bulk query
+    #[derive(Debug)]
+    pub struct AddressTokens {
+        /// the queried address
+        pub address: String,
+        /// the owned tokens (empty if the query failed)
+        pub tokens: Vec<RawTokenResult>,
+        /// the error message if the query failed, empty otherwise
+        pub error: String,
+    }
+
     /// Token holder detail from BlockScout API
     ///
     /// tokenid is not supported yet.
@@ -220,135 +740,1379 @@ mod ffi {
         pub value: String,
     }
 
-    pub enum QueryOption {
-        ByContract,
-        ByAddressAndContract,
-        ByAddress,
+    /// The transport of the Cosmos queries and broadcasts
+    #[derive(Debug)]
+    pub enum CosmosTransport {
+        /// the LCD (REST) queries and the Tendermint RPC broadcasts
+        Rest,
+        /// gRPC-web (e.g. for the platforms without raw gRPC)
+        GrpcWeb,
     }
 
-    extern "Rust" {
-        /// filter wallets by platform
-        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
-        pub fn filter_wallets(
-            cached: bool,
-            registry_local_path: String,
-            platform: Platform,
-        ) -> Result<Vec<WalletEntry>>;
-        /// get all possible wallets
-        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
-        pub fn get_all_wallets(
-            cached: bool,
-            registry_local_path: String,
-        ) -> Result<Vec<WalletEntry>>;
-        /// check wallet by `id` for supported `platform` listing or not
-        /// Check wallet id at https://explorer.walletconnect.com/
-        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
-        pub fn check_wallet(
-            cached: bool,
-            registry_local_path: String,
-            id: String,
-            platform: Platform,
-        ) -> Result<bool>;
-        /// get a wallet by `id`
-        /// Check wallet id at https://explorer.walletconnect.com/
-        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
-        pub fn get_wallet(
-            cached: bool,
-            registry_local_path: String,
-            id: String,
-        ) -> Result<WalletEntry>;
-        pub fn generate_qrcode(qrcodestring: String) -> Result<WalletQrcode>;
-        /// WallnetConnect API
-        type WalletconnectClient;
-        /// restore walletconnect-session from string
-        pub fn walletconnect_restore_client(
-            session_info: String,
-        ) -> Result<Box<WalletconnectClient>>;
-        /// create walletconnect-session
-        /// the chain id (if 0, retrived and decided by wallet, if > 0, decided by the client)
-        pub fn walletconnect_new_client(
-            description: String,
-            url: String,
-            icon_urls: Vec<String>,
-            name: String,
-            chain_id: u64,
-        ) -> Result<Box<WalletconnectClient>>;
+    /// The Cosmos account state needed for signing its transactions
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CosmosAccountState {
+        pub account_number: u64,
+        /// the sequence of the next transaction
+        pub sequence: u64,
+    }
 
-        /// setup callback
-        pub fn setup_callback_blocking(
-            self: &mut WalletconnectClient,
-            usercallback: UniquePtr<WalletConnectCallback>,
-        ) -> Result<()>;
-        /// create or restore a session
-        /// once session is created, it will be reused
-        pub fn ensure_session_blocking(
-            self: &mut WalletconnectClient,
-        ) -> Result<WalletConnectEnsureSessionResult>;
-        /// get connection string for qrcode
-        pub fn get_connection_string(self: &mut WalletconnectClient) -> Result<String>;
-        /// write session-info to string, which can be written to file
-        pub fn save_client(self: &mut WalletconnectClient) -> Result<String>;
-        /// print qrcode in termal, for debugging
-        pub fn print_uri(self: &mut WalletconnectClient) -> Result<String>;
-        /// sign message
-        pub fn sign_personal_blocking(
-            self: &mut WalletconnectClient,
-            message: String,
-            address: [u8; 20],
-        ) -> Result<Vec<u8>>;
+    /// The estimated gas limit and fee of a Cosmos transaction
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosFeeEstimate {
+        pub gas_limit: u64,
+        pub fee_amount: u64,
+        pub fee_denom: String,
+    }
 
-        /// build cronos(eth) eip155 transaction
-        /// Supported Wallets: Trust Wallet, Crypto.com Desktop Defi Wallet
-        pub fn sign_eip155_transaction_blocking(
-            self: &mut WalletconnectClient,
-            info: &WalletConnectTxEip155,
-            address: [u8; 20],
-        ) -> Result<Vec<u8>>;
+    /// A Cosmos coin (the amount is a decimal string, e.g. "1000" or "12.5" in the rewards)
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosCoin {
+        pub denom: String,
+        pub amount: String,
+    }
 
-        /// send cronos(eth) eip155 transaction
-        /// Supported Wallets: Trust Wallet, MetaMask and Crypto.com Mobile Defi Wallet
-        pub fn send_eip155_transaction_blocking(
-            self: &mut WalletconnectClient,
-            info: &WalletConnectTxEip155,
-            address: [u8; 20],
-        ) -> Result<Vec<u8>>;
+    /// A delegation to a validator
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosDelegation {
+        pub validator_address: String,
+        /// the delegator shares (a decimal string)
+        pub shares: String,
+        /// the current balance of the delegation
+        pub balance: CosmosCoin,
+    }
 
-        /// eip1559_transaction_request: json string of Eip1559TransactionRequest
-        /// return signed transaction bytes
-        pub fn sign_transaction(
-            self: &mut WalletconnectClient,
-            eip1559_transaction_request: String,
-            address: [u8; 20],
-        ) -> Result<Vec<u8>>;
+    /// An undelegation being completed (the balance is in the staking denom)
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosUnbondingEntry {
+        pub validator_address: String,
+        pub creation_height: u64,
+        /// RFC 3339 time, e.g. "2023-06-01T00:00:00Z"
+        pub completion_time: String,
+        pub initial_balance: String,
+        /// the balance to be returned (it may be slashed)
+        pub balance: String,
+    }
 
-        /// eip1559_transaction_request: json string of Eip1559TransactionRequest
-        /// return transaction hash bytes
-        pub fn send_transaction(
-            self: &mut WalletconnectClient,
-            eip1559_transaction_request: String,
-            address: [u8; 20],
-        ) -> Result<Vec<u8>>;
+    /// The pending rewards of a delegation
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosReward {
+        pub validator_address: String,
+        pub reward: Vec<CosmosCoin>,
+    }
 
-        /// sign a contract transaction
-        /// contract_action is a json string of `ContractAction` type, for example:
-        /// for example, transfer Erc20 token
-        /// {
-        ///     "ContractTransfer": {
-        ///         "Erc20Transfer": {
-        ///             "contract_address": "0xxxxx",
-        ///             "to_address": "0xxxxx",
-        ///             "amount": "1000000000000000000"
-        ///         }
-        ///     }
-        /// }
-        /// return signed transaction bytes
-        pub fn sign_contract_transaction(
-            self: &mut WalletconnectClient,
-            contract_action: String,
-            common: &WalletConnectTxCommon,
+    /// The pending rewards of a delegator
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CosmosRewards {
+        /// by the validator
+        pub rewards: Vec<CosmosReward>,
+        pub total: Vec<CosmosCoin>,
+    }
+
+    /// A labelled address of the address book
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct AddressBookEntry {
+        pub name: String,
+        pub address: String,
+        /// the CAIP-2 chain ID, e.g. "eip155:25" (empty for all the chains)
+        #[serde(default)]
+        pub chain: String,
+        #[serde(default)]
+        pub notes: String,
+        #[serde(default)]
+        pub tags: Vec<String>,
+    }
+
+    /// The risk of an address found by the screening
+    #[derive(Debug)]
+    pub enum RiskLevel {
+        Clean,
+        /// e.g. flagged by the screening endpoint, or it couldn't be screened
+        Suspicious,
+        /// the transactions to it are rejected
+        Denied,
+    }
+
+    /// The screening result of an address
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ScreeningVerdict {
+        pub address: String,
+        pub risk: RiskLevel,
+        /// why it's suspicious or denied (may be empty)
+        pub reason: String,
+        /// "denylist" or "endpoint"
+        pub source: String,
+    }
+
+    /// The policy evaluated before any signer signs or sends a transaction
+    #[derive(Debug, Clone, Default)]
+    pub struct TransactionPolicy {
+        /// the max value (in the base units of the native coin) of a transaction
+        /// (empty for no limit)
+        pub max_value_per_tx: String,
+        /// the max value of the transactions signed or sent in a day (UTC; empty for no limit)
+        pub max_value_per_day: String,
+        /// the contracts that can be called (empty for all of them); the plain transfers
+        /// are restricted if their recipient has a contract code, or if it can't be checked
+        /// (the SDK environment has no JSON-RPC url)
+        pub allowed_contracts: Vec<String>,
+        /// the methods that can be called: the 0x-prefixed selectors or the signatures,
+        /// e.g. "transfer(address,uint256)" (empty for all of them)
+        pub allowed_methods: Vec<String>,
+        /// blocks the message signing (`personal_sign`) too
+        /// (the raw digests of `eth_sign` are blocked by any policy)
+        pub block_message_signing: bool,
+    }
+
+    /// The rule of the transaction policy
+    #[derive(Debug)]
+    pub enum PolicyRule {
+        MaxValuePerTx,
+        MaxValuePerDay,
+        ContractNotAllowed,
+        MethodNotAllowed,
+        MessageSigningNotAllowed,
+    }
+
+    /// A transaction blocked by the policy
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct PolicyViolation {
+        pub rule: PolicyRule,
+        pub message: String,
+        /// the destination of the transaction (empty for the contract creations)
+        pub to: String,
+        /// the value of the transaction (in the base units of the native coin)
+        pub value: String,
+    }
+
+    /// The caps of the fees of the WalletConnect transactions, against fee spikes
+    /// or injected fees (decimal strings in wei, empty for no cap)
+    #[derive(Debug, Clone, Default)]
+    pub struct FeeCaps {
+        /// the max fee per gas (the gas price of the legacy transactions)
+        pub max_fee_per_gas: String,
+        /// the max total fee: the gas limit times the max fee per gas
+        pub max_total_fee: String,
+        /// the max fee per gas over which `check_transaction_fees` warns
+        pub warn_fee_per_gas: String,
+    }
+
+    /// A transaction that would have been sent in the dry-run mode
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct DryRunResult {
+        /// the hash of the signed transaction (0x-prefixed)
+        pub tx_hash: String,
+        /// the signed raw transaction
+        pub raw_tx: Vec<u8>,
+        pub from: String,
+        /// empty for the contract creations
+        pub to: String,
+        /// in wei
+        pub value: String,
+        /// the simulated gas (empty if it wasn't simulated)
+        pub gas_estimate: String,
+    }
+
+    /// The network environment of the SDK
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SdkEnvironment {
+        /// Cronos mainnet (chain id 25)
+        Mainnet,
+        /// Cronos testnet (chain id 338)
+        Testnet,
+        /// a local node, e.g. Anvil (chain id 31337)
+        Local,
+    }
+
+    /// The defaults of the SDK environment (the empty urls are unset)
+    #[derive(Debug, Clone, Default)]
+    pub struct SdkEnvironmentConfig {
+        /// the chain id of the WalletConnect sessions and transactions
+        pub chain_id: u64,
+        /// the JSON-RPC url, e.g. for the `rpc_url` parameters
+        pub rpc_url: String,
+        /// the base url of the Cronoscan-compatible API of the explorer queries
+        pub explorer_api_url: String,
+        /// the BlockScout API url, e.g. for the `blockscout_base_url` parameters
+        pub blockscout_url: String,
+        /// the WalletConnect 1.0 bridge server of the new sessions
+        pub relay_url: String,
+        /// the faucet of `request_testnet_funds_blocking` if its url is empty
+        pub faucet_url: String,
+    }
+
+    /// The health of an endpoint of the SDK's HTTP requests
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct EndpointHealth {
+        pub host: String,
+        pub request_count: u64,
+        /// the errors and the status codes >= 400
+        pub failure_count: u64,
+        /// the status code of the last request (0 if it failed without a response)
+        pub last_status: u16,
+        pub last_latency_ms: u64,
+        /// the error of the last request (empty if it succeeded)
+        pub last_error: String,
+        /// when the last successful request was made (unix timestamp in seconds, 0 if none)
+        pub last_success_at: u64,
+    }
+
+    /// The state of the connections of the SDK
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ConnectionStatus {
+        /// whether the websocket of the WalletConnect 1.0 bridge server
+        /// (or of the 2.0 relay) is open
+        pub bridge_connected: bool,
+        /// the round-trip time of the last answered websocket ping (0 if none was answered)
+        pub last_ping_rtt_ms: u64,
+        /// the number of the reconnections to the bridge server (or the relay)
+        pub reconnect_count: u64,
+        /// the health of the endpoints of the HTTP requests (including the JSON-RPC ones)
+        pub endpoints: Vec<EndpointHealth>,
+    }
+
+    /// The network usage of a subsystem of the SDK
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct SubsystemUsage {
+        /// "http" (the SDK's HTTP requests), "walletconnect" (the bridge server messages)
+        /// or "walletconnect2" (the relay messages)
+        pub subsystem: String,
+        /// the requests (the messages sent to the bridge server or the relay)
+        pub requests: u64,
+        pub bytes_sent: u64,
+        pub bytes_received: u64,
+    }
+
+    /// The network usage of the SDK since the start or `reset_network_usage`
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct NetworkUsage {
+        pub requests: u64,
+        pub bytes_sent: u64,
+        pub bytes_received: u64,
+        pub subsystems: Vec<SubsystemUsage>,
+    }
+
+    /// The budget of the network usage (0 for no limit)
+    #[derive(Debug, Clone, Default)]
+    pub struct NetworkBudget {
+        /// the bytes sent and received
+        pub max_bytes: u64,
+        pub max_requests: u64,
+    }
+
+    /// The options of the SDK's runtimes (the background network threads)
+    #[derive(Debug, Clone, Default)]
+    pub struct RuntimeConfig {
+        /// the worker threads of each runtime (0 for the number of the cores)
+        pub worker_threads: u32,
+        /// the threads of the blocking tasks of each runtime (0 for the tokio default)
+        pub max_blocking_threads: u32,
+        /// the names of the threads are the prefix and a number
+        /// (empty for "tokio-runtime-worker")
+        pub thread_name_prefix: String,
+    }
+
+    /// The memory allocated by a subsystem of the SDK
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct SubsystemMemory {
+        /// "http" (the SDK's HTTP requests), "jsonrpc" (the JSON-RPC requests),
+        /// "walletconnect" or "walletconnect2" (the threads of the clients' runtimes),
+        /// or "other" (the rest of the SDK)
+        pub subsystem: String,
+        /// the allocated bytes since the start (the freed ones included)
+        pub allocated_bytes: u64,
+        pub allocations: u64,
+    }
+
+    /// The memory allocated by the SDK
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct MemoryUsage {
+        /// the bytes allocated and not freed yet
+        pub current_bytes: u64,
+        pub peak_bytes: u64,
+        pub allocations: u64,
+        pub deallocations: u64,
+        pub subsystems: Vec<SubsystemMemory>,
+    }
+
+    /// The statistics of the cache of the account states
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ChainStateCacheStats {
+        pub hits: u64,
+        pub misses: u64,
+        /// the cached accounts
+        pub entries: u64,
+    }
+
+    /// A signing request recorded in the audit log
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    pub struct AuditEntry {
+        /// the JSON-RPC method, e.g. "personal_sign" or "eth_sendTransaction"
+        pub method: String,
+        /// the 0x-prefixed hash of what the wallet was asked to sign
+        /// (the EIP-191 message hash, the digest or the transaction sighash)
+        pub params_hash: String,
+        /// Unix time in seconds
+        pub timestamp: u64,
+        pub success: bool,
+        /// the transaction hash or the signature (0x-prefixed), or the error message
+        pub result: String,
+    }
+
+    /// The description of a transaction for the confirmation dialogs
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TransactionDescription {
+        /// "native_transfer", "contract_creation", "transfer", "approve", "approve_for_all",
+        /// "revoke_approval_for_all", "mint" or "contract_call"
+        pub action: String,
+        /// the English summary, e.g. "Approve 1000 of 0x.. to 0x.." (the amounts are
+        /// in the base units)
+        pub summary: String,
+        /// the signature of the decoded method (empty if it wasn't decoded)
+        pub method: String,
+        /// the JSON object of the parameters for the localized summaries: `to`, `value`
+        /// and the decoded `contract` and `args` (by their names)
+        pub params_json: String,
+        /// the id of the summary in the message catalog, e.g. "preview.approve"
+        /// (the decoded calls sending a value are "preview.with_value": its `summary_id`
+        /// parameter is the id of the call's message, rendered with the same parameters)
+        pub message_id: String,
+        /// the JSON object of the parameters of the message
+        pub message_params_json: String,
+    }
+
+    /// A message of the catalog: its stable id and parameters for the localized text
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+    pub struct LocalizedMessage {
+        /// e.g. "error.invalid_address" or "preview.transfer"
+        pub id: String,
+        /// the JSON object of the parameters (strings) by their names in the templates
+        pub params_json: String,
+        /// the English text
+        pub text: String,
+    }
+
+    /// The IPFS pinning services
+    #[derive(Debug)]
+    pub enum IpfsService {
+        /// the Pinata API (`pinJSONToIPFS` and `pinFileToIPFS`)
+        Pinata,
+        /// the web3.storage API (`/upload`)
+        Web3Storage,
+        /// the Kubo (go-ipfs) RPC API of an IPFS node (`/api/v0/add`)
+        Kubo,
+    }
+
+    /// The IPFS pinning service of the uploads
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct IpfsConfig {
+        pub service: IpfsService,
+        /// the API url (the default one of the service if empty,
+        /// e.g. "http://127.0.0.1:5001" for Kubo)
+        pub api_url: String,
+        /// the authentication headers of the service, e.g. "Authorization: Bearer <JWT>"
+        /// or the Pinata `pinata_api_key` and `pinata_secret_api_key`
+        pub auth_headers: Vec<HttpHeader>,
+        /// the gateway of the returned urls ("https://ipfs.io/ipfs/" if empty)
+        pub gateway_url: String,
+    }
+
+    /// The uploaded content
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct IpfsUpload {
+        pub cid: String,
+        /// "ipfs://<cid>", e.g. for the `tokenURI` of an NFT
+        pub uri: String,
+        /// the url of the content on the gateway
+        pub gateway_url: String,
+    }
+
+    /// The NFT minted by `mint_nft_with_metadata_blocking`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NftMintRequest {
+        /// the NFT contract
+        pub contract: String,
+        /// the receiver of the NFT
+        pub to: String,
+        /// the image file uploaded first (none if empty)
+        pub image_path: String,
+        /// the metadata JSON object (e.g. `name`, `description` and `attributes`);
+        /// its `image` is set to the uri of the uploaded image
+        pub metadata_json: String,
+        /// the mint function, e.g. "mint(address,uint256,uint256,bytes)"
+        /// ("safeMint(address,string)" if empty)
+        pub mint_function: String,
+        /// the arguments of the mint function (decimal integers, 0x-prefixed addresses
+        /// and bytes, etc.) with the placeholders "{to}", "{token_uri}", "{token_id}"
+        /// and "{amount}" (["{to}", "{token_uri}"] if empty)
+        pub args: Vec<String>,
+        pub token_id: String,
+        pub amount: String,
+    }
+
+    /// The uploads and the mint transaction of an NFT
+    #[derive(Debug)]
+    pub struct NftMint {
+        /// the uploaded image (empty if there was none)
+        pub image: IpfsUpload,
+        /// the uploaded metadata JSON (its uri is the token uri)
+        pub metadata: IpfsUpload,
+        /// the mint transaction to the NFT contract, signed or sent with
+        /// `sign_eip155_transaction_blocking` or `send_eip155_transaction_blocking`
+        pub transaction: WalletConnectTxEip155,
+    }
+
+    /// The disk cache and the rate limit of `fetch_nft_image` (the empty or 0 fields
+    /// are the defaults)
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct NftImageCacheConfig {
+        /// the cache directory ("play-cpp-sdk-nft-images" in the temporary directory)
+        pub cache_dir: String,
+        /// the maximum size of the cache; the least recently used images are evicted (64 MiB)
+        pub max_cache_bytes: u64,
+        /// the IPFS gateways tried in turn (ipfs.io and dweb.link)
+        pub gateways: Vec<String>,
+        /// the minimum interval between the requests in milliseconds (100)
+        pub min_request_interval_ms: u64,
+    }
+
+    /// The limits of the downloaded NFT media
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct MediaPolicy {
+        /// the maximum size in bytes (0 for no limit)
+        pub max_size: u64,
+        /// the allowed content type prefixes, e.g. "image/" or "video/mp4" (any if empty)
+        pub allowed_content_types: Vec<String>,
+        /// the IPFS gateway of the `ipfs://` urls (the public ipfs.io gateway if empty)
+        pub gateway_url: String,
+    }
+
+    /// The downloaded NFT media matching its expected hash
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct VerifiedMedia {
+        pub data: Vec<u8>,
+        pub content_type: String,
+        /// the hexadecimal SHA-256 digest of the data
+        pub sha256: String,
+    }
+
+    /// The local Anvil node of `spawn_local_node` (the empty or 0 fields are the defaults)
+    #[cfg(feature = "test-utils")]
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct LocalNodeConfig {
+        /// the path of the Anvil binary ("anvil" in the PATH)
+        pub anvil_path: String,
+        /// the port (a free one)
+        pub port: u16,
+        /// the chain id (31337)
+        pub chain_id: u64,
+        /// the number of the prefunded accounts (10)
+        pub accounts: u32,
+        /// the mnemonic of the accounts (the Anvil/Hardhat "test test ... junk" one)
+        pub mnemonic: String,
+        /// the interval of the mined blocks in seconds (a block per transaction)
+        pub block_time_secs: u64,
+        /// how long to wait for the node to listen in milliseconds (10 seconds)
+        pub startup_timeout_ms: u64,
+    }
+
+    /// A message of a round of a threshold (MPC) signing session
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct MpcMessage {
+        /// the index of the sending party
+        pub from: u32,
+        /// the index of the receiving party (the sending one for a broadcast)
+        pub to: u32,
+        /// the round (from 1)
+        pub round: u32,
+        /// the opaque payload of the protocol
+        pub payload: Vec<u8>,
+    }
+
+    /// The result of a round of the local MPC party
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct MpcRoundResult {
+        /// the messages of the next round (empty when it's done)
+        pub messages: Vec<MpcMessage>,
+        /// the signature when it's done: 64 bytes (r || s) or 65 bytes (r || s || v)
+        pub signature: Vec<u8>,
+    }
+
+    /// A Ledger connected over USB
+    #[derive(Debug, Default, Clone)]
+    pub struct LedgerDevice {
+        /// the HID path of the device (to open it)
+        pub path: String,
+        /// e.g. "Nano X"
+        pub product: String,
+        pub serial_number: String,
+    }
+
+    /// The cloud KMS providers
+    #[cfg(feature = "kms")]
+    #[derive(Debug)]
+    pub enum KmsProvider {
+        /// AWS KMS (an `ECC_SECG_P256K1` key)
+        Aws,
+        /// Google Cloud KMS (an `EC_SIGN_SECP256K1_SHA256` key version)
+        Gcp,
+    }
+
+    /// The key of a KMS signer
+    #[cfg(feature = "kms")]
+    #[derive(Debug, Clone)]
+    pub struct KmsConfig {
+        pub provider: KmsProvider,
+        /// the AWS key id or ARN, or the Google Cloud key version name
+        /// (projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*)
+        pub key_id: String,
+        /// the AWS region (AWS_REGION if empty)
+        pub region: String,
+        /// the AWS credentials (AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN
+        /// if empty)
+        pub access_key_id: String,
+        pub secret_access_key: String,
+        pub session_token: String,
+        /// the Google Cloud access token (the one of the metadata server if empty,
+        /// i.e. of the service account of the instance)
+        pub access_token: String,
+        /// the url of the KMS API (the public one if empty)
+        pub endpoint: String,
+    }
+
+    /// The lifecycle of a job of `TxQueue`
+    #[derive(Debug)]
+    pub enum TxJobStatus {
+        /// waiting to be signed
+        Queued,
+        /// signed with its nonce and persisted, not yet broadcast
+        Signed,
+        /// broadcast, not yet mined
+        Sent,
+        /// mined successfully
+        Confirmed,
+        /// rejected by the node, reverted or replaced (see `error`)
+        Failed,
+        Cancelled,
+    }
+
+    /// A transaction of `TxQueue`
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct TxJob {
+        pub id: u64,
+        pub status: TxJobStatus,
+        /// hexstring, "0x..." (empty for the contract creations)
+        pub to: String,
+        /// decimal string, in wei units
+        pub value: String,
+        pub data: Vec<u8>,
+        /// decimal string (empty until it's signed)
+        pub nonce: String,
+        /// hexstring (empty until it's signed)
+        pub tx_hash: String,
+        /// why it failed
+        pub error: String,
+        /// unix timestamps
+        pub created_at: u64,
+        pub updated_at: u64,
+    }
+
+    /// The receipt of a transaction tracked by `TxTracker`
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct TrackedTxReceipt {
+        pub tx_hash: String,
+        pub block_number: u64,
+        pub block_hash: String,
+        /// whether the transaction succeeded (false if reverted)
+        pub success: bool,
+        /// decimal gas used
+        pub gas_used: String,
+        /// the blocks since the block of the transaction (1 in that block)
+        pub confirmations: u64,
+    }
+
+    /// The exchange rate of a pair
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct ExchangeRate {
+        /// e.g. "CRO/USD"
+        pub pair: String,
+        pub base: String,
+        pub quote: String,
+        /// the decimal price of the base in the quote
+        pub rate: String,
+        /// unix timestamp of the refresh
+        pub updated_at: u64,
+    }
+
+    /// The response of a testnet faucet
+    #[derive(Debug, Clone, PartialEq, Eq, Default)]
+    pub struct FaucetResponse {
+        /// whether the coins were sent (false if rate-limited)
+        pub funded: bool,
+        /// the hash of the funding transaction (empty if the faucet doesn't tell it)
+        pub tx_hash: String,
+        /// the amount sent in the base units (empty if the faucet doesn't tell it)
+        pub amount: String,
+        /// the message of the faucet, if any
+        pub message: String,
+        /// the seconds to wait before retrying a rate-limited request (0 if funded)
+        pub retry_after_secs: u64,
+    }
+
+    /// The fiat on-ramp providers
+    #[derive(Debug)]
+    pub enum OnRampProviderKind {
+        Transak,
+        MoonPay,
+    }
+
+    /// A fiat on-ramp provider of the quotes
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OnRampProvider {
+        pub kind: OnRampProviderKind,
+        /// the API (partner) key of the provider
+        pub api_key: String,
+        /// the API url (the default one of the provider if empty, e.g. for the staging)
+        pub api_url: String,
+        /// the widget url of the redirect urls (the default one of the provider if empty)
+        pub widget_url: String,
+    }
+
+    /// A quote of buying crypto with fiat
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OnRampQuote {
+        pub provider: OnRampProviderKind,
+        pub fiat_currency: String,
+        pub crypto_currency: String,
+        /// the decimal amount of the fiat currency paid
+        pub fiat_amount: String,
+        /// the decimal amount of the crypto received
+        pub crypto_amount: String,
+        /// the decimal amount of the fiat currency of the fees
+        pub fee_amount: String,
+        /// the price of the crypto in the fiat currency
+        pub price: String,
+        /// the country code of the quote (empty if unknown)
+        pub region: String,
+        /// the url of the provider's widget buying the quote (see `onramp_redirect_url`
+        /// to prefill the wallet address)
+        pub redirect_url: String,
+    }
+
+    /// The marketplaces of the NFT collection stats
+    #[derive(Debug)]
+    pub enum NftMarketplace {
+        /// the Crypto.com NFT GraphQL API (the collection id)
+        CryptoComNft,
+        /// the Minted REST API (the collection contract address)
+        Minted,
+        /// the url with the `{collection}` placeholder returning the JSON
+        /// of `NftCollectionStats`
+        Custom,
+    }
+
+    /// The marketplace API of the NFT collection stats
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NftStatsSource {
+        pub marketplace: NftMarketplace,
+        /// the API url (the default one of the marketplace if empty; required for `Custom`)
+        pub url: String,
+    }
+
+    /// A sale of an NFT of a collection
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct NftSale {
+        pub token_id: String,
+        /// the decimal amount of the currency of the collection stats
+        pub price: String,
+        /// Unix time in seconds
+        pub timestamp: u64,
+        pub tx_hash: String,
+    }
+
+    /// The stats of an NFT collection on a marketplace (the missing ones are empty or 0),
+    /// e.g. for the in-game item pricing hints
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+    #[serde(default)]
+    pub struct NftCollectionStats {
+        pub collection: String,
+        /// the lowest listing price (a decimal amount of the currency)
+        pub floor_price: String,
+        /// e.g. "CRO" or "USD"
+        pub currency: String,
+        pub volume_24h: String,
+        pub total_volume: String,
+        pub sales_24h: u64,
+        pub owners: u64,
+        pub items: u64,
+        /// the latest sales (newest first)
+        pub recent_sales: Vec<NftSale>,
+    }
+
+    /// The UniswapV2-style DEX routers on Cronos mainnet
+    #[derive(Debug)]
+    pub enum DexRouter {
+        VvsFinance,
+        MmFinance,
+    }
+
+    /// The quote of a swap via a UniswapV2-style router
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SwapQuote {
+        /// the chain id of the router (set as the chain id of the swap transaction)
+        pub chain_id: u64,
+        /// the token addresses of the route (the first is sold and the last is bought)
+        pub path: Vec<String>,
+        /// the amounts (in the base units) of the tokens of the path
+        pub amounts: Vec<String>,
+        pub amount_in: String,
+        pub amount_out: String,
+        /// the price impact of the swap in basis points (including the LP fees)
+        pub price_impact_bps: u32,
+    }
+
+    /// The liquidity position of an account in a UniswapV2-style pair
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct LiquidityPosition {
+        /// the address of the pair (the LP token contract)
+        pub pair: String,
+        pub token0: String,
+        pub token1: String,
+        /// the LP tokens of the account
+        pub lp_balance: String,
+        pub lp_total_supply: String,
+        /// the reserves of the pair (in the base units of the tokens)
+        pub reserve0: String,
+        pub reserve1: String,
+        /// the underlying amounts of the LP tokens of the account (in the base units)
+        pub amount0: String,
+        pub amount1: String,
+        /// the share of the pool in percent, e.g. "12.5" (up to 8 fraction digits)
+        pub share_percent: String,
+    }
+
+    /// The options of the swap transaction of a quote
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct SwapOptions {
+        /// the address of the router (see `dex_router_address`)
+        pub router: String,
+        /// the receiver of the bought tokens
+        pub recipient: String,
+        /// the tolerated slippage in basis points, e.g. 50 for 0.5%
+        pub slippage_bps: u32,
+        /// the seconds from now until the swap expires
+        pub deadline_secs: u64,
+        /// the native coin is sold (the first token of the path is its wrapped token, e.g. WCRO)
+        pub native_in: bool,
+        /// the native coin is bought (the last token of the path is its wrapped token)
+        pub native_out: bool,
+    }
+
+    /// The kind of an entitlement requirement
+    #[derive(Debug)]
+    pub enum RequirementKind {
+        /// an ERC-20 balance of at least `min_amount`
+        Erc20MinBalance,
+        /// the ERC-721 token `token_id`
+        Erc721Token,
+        /// any token of the ERC-721 collection
+        Erc721AnyToken,
+        /// at least `min_amount` of the ERC-1155 token `token_id`
+        Erc1155Amount,
+    }
+
+    /// An asset the address must hold (e.g. to unlock token-gated content)
+    #[derive(Debug, Clone)]
+    pub struct AssetRequirement {
+        pub kind: RequirementKind,
+        /// the address of the token contract
+        pub contract_address: String,
+        /// the token id (decimal) of `Erc721Token` and `Erc1155Amount`
+        pub token_id: String,
+        /// the minimum amount (decimal, in the base units) of `Erc20MinBalance`
+        /// and `Erc1155Amount`, 1 if empty
+        pub min_amount: String,
+    }
+
+    /// The verdict of an entitlement requirement with its proof: the call made
+    /// at the block and what it returned
+    #[derive(Debug, Clone)]
+    pub struct EntitlementVerdict {
+        pub satisfied: bool,
+        pub contract_address: String,
+        /// the balance (decimal), or the owner of the `Erc721Token` token
+        pub observed: String,
+        /// the block of the calls (the same for all the requirements)
+        pub block_number: u64,
+        pub block_hash: String,
+        /// the data of the `eth_call` (hexadecimal, 0x-prefixed)
+        pub call_data: String,
+        /// the error message if the check failed (then it's not satisfied), empty otherwise
+        pub error: String,
+    }
+
+    /// A recipient of an airdrop
+    #[derive(Debug, Clone)]
+    pub struct AirdropRecipient {
+        pub address: String,
+        /// decimal, in the base units of the token
+        pub amount: String,
+    }
+
+    /// The claim of a recipient of an airdrop with its Merkle proof
+    #[derive(Debug, Clone)]
+    pub struct AirdropClaim {
+        /// the index of the recipient (in the order of the recipients of the tree)
+        pub index: u64,
+        pub address: String,
+        /// decimal, in the base units of the token
+        pub amount: String,
+        /// the sibling nodes from the leaf to the root (hexadecimal, 0x-prefixed)
+        pub proof: Vec<String>,
+    }
+
+    /// A holder of a token contract in a snapshot
+    #[derive(Debug, Clone)]
+    pub struct SnapshotHolder {
+        pub address: String,
+        /// decimal, in the base units (the number of the tokens of an ERC-721 collection,
+        /// the total of all the ids of an ERC-1155 contract)
+        pub balance: String,
+        /// the ids (decimal) of the owned ERC-721 tokens, empty for the other contracts
+        pub token_ids: Vec<String>,
+    }
+
+    /// How the dropped digits of an amount are rounded
+    #[derive(Debug)]
+    pub enum RoundingMode {
+        /// towards zero
+        Floor,
+        /// to the nearest (half up)
+        Round,
+        /// away from zero
+        Ceil,
+    }
+
+    /// The formatting of the token amounts (see `amount_format_for_locale`)
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct AmountFormat {
+        /// the decimals of the token (e.g. 18 for CRO on Cronos)
+        pub decimals: u32,
+        /// the fraction digits (if `significant_digits` is 0)
+        pub max_fraction_digits: u32,
+        /// the significant digits (0 for a fixed number of the fraction digits)
+        pub significant_digits: u32,
+        pub rounding: RoundingMode,
+        /// whether the trailing zeros of the fraction are removed
+        pub trim_zeros: bool,
+        /// e.g. "," in English (empty for none)
+        pub thousands_separator: String,
+        /// e.g. "." in English
+        pub decimal_mark: String,
+    }
+
+    /// The kind of an asset
+    #[derive(Debug)]
+    pub enum AssetKind {
+        /// the native coin of the EVM chain (e.g. CRO on Cronos)
+        Native,
+        Erc20,
+        Erc721,
+        Erc1155,
+        /// a Cosmos bank balance
+        CosmosCoin,
+    }
+
+    /// An asset of the EVM or Cosmos account, so that the wallet UI has one model of both
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Asset {
+        /// the CAIP-2 chain ID, e.g. "eip155:25" or "cosmos:crypto-org-chain-mainnet-1"
+        pub chain: String,
+        pub kind: AssetKind,
+        /// the token contract address or the Cosmos denom (empty for the native EVM coin)
+        pub denom: String,
+        /// the token id (only for the ERC-721 and ERC-1155 tokens)
+        pub token_id: String,
+        /// the number of decimal places (0 if it's not known)
+        pub decimals: u32,
+        /// the ticker (empty if it's not known)
+        pub symbol: String,
+        pub name: String,
+        /// the balance in the base units
+        pub balance: String,
+    }
+
+    /// The addresses and endpoints of an account whose assets are queried
+    /// (the EVM or Cosmos part is skipped if its address is empty)
+    #[derive(Debug, Clone, Default)]
+    pub struct AssetAccount {
+        /// hexadecimal
+        pub evm_address: String,
+        /// the JSON-RPC url (required for the EVM address)
+        pub rpc_url: String,
+        /// the BlockScout REST API base url of the tokens (skipped if empty)
+        pub blockscout_base_url: String,
+        /// bech32, e.g. "cro1..."
+        pub cosmos_address: String,
+        /// the LCD (REST) url (required for the Cosmos address)
+        pub lcd_url: String,
+    }
+
+    /// A sign-in challenge issued by an `AuthServer`
+    #[derive(Debug, Clone, Default)]
+    pub struct AuthChallenge {
+        /// the message to be signed by the address (`personal_sign`)
+        pub message: String,
+        pub nonce: String,
+        /// unix timestamp after which it can't be verified
+        pub expires_at: u64,
+    }
+
+    /// A session token minted by an `AuthServer`
+    #[derive(Debug, Clone, Default)]
+    pub struct AuthToken {
+        /// the JWT (HS256) of the address
+        pub token: String,
+        /// the signed-in address (hexadecimal, 0x-prefixed)
+        pub address: String,
+        /// unix timestamp
+        pub expires_at: u64,
+    }
+
+    /// The chain of an address of a player profile
+    #[derive(Debug)]
+    pub enum ProfileAddressKind {
+        /// hexadecimal
+        Evm,
+        /// bech32, e.g. "cro1..."
+        Cosmos,
+    }
+
+    /// An address of a player profile
+    #[derive(Debug, Clone)]
+    pub struct ProfileAddress {
+        pub address: String,
+        pub kind: ProfileAddressKind,
+        /// whether the challenge of the address was signed
+        pub verified: bool,
+    }
+
+    /// The endpoints of the holdings of a player profile (the addresses of a chain
+    /// are skipped if its url is empty)
+    #[derive(Debug, Clone, Default)]
+    pub struct ProfileEndpoints {
+        /// the JSON-RPC url of the EVM addresses
+        pub rpc_url: String,
+        /// the BlockScout REST API base url of their tokens (skipped if empty)
+        pub blockscout_base_url: String,
+        /// the LCD (REST) url of the Cosmos addresses
+        pub lcd_url: String,
+    }
+
+    /// An asset of an address of a player profile
+    #[derive(Debug, Clone)]
+    pub struct ProfileAsset {
+        pub address: String,
+        pub asset: Asset,
+    }
+
+    /// Event log scanned by LogScanner
+    pub struct DecodedEvent {
+        /// the contract address
+        pub address: String,
+        pub block_no: u64,
+        pub tx_hash: String,
+        /// the index of the log in the block
+        pub log_index: u64,
+        /// the raw topics (0x-prefixed hexadecimal)
+        pub topics: Vec<String>,
+        /// the raw data (0x-prefixed hexadecimal)
+        pub data: String,
+        /// the event name (empty if the log doesn't match any event of the ABI)
+        pub name: String,
+        /// the decoded parameters as a JSON object ("null" if the log wasn't decoded)
+        pub params_json: String,
+    }
+
+    /// Failover between Cronoscan and BlockScout
+    pub struct ExplorerFailoverPolicy {
+        /// whether the other explorer is queried when one fails (e.g. rate-limited or down)
+        pub enabled: bool,
+        /// the BlockScout REST API base url used when Cronoscan fails (empty for no failover)
+        pub blockscout_base_url: String,
+        /// the Cronoscan API key used when BlockScout fails (empty for no failover)
+        pub cronoscan_api_key: String,
+    }
+
+    /// The explorer that produced a result
+    #[derive(Debug)]
+    pub enum ExplorerSource {
+        Cronoscan,
+        BlockScout,
+    }
+
+    /// The gas usage of the transactions of an address calling a method of a contract
+    #[derive(Debug, Clone)]
+    pub struct MethodGasUsage {
+        /// the called contract (empty for the contract creations)
+        pub contract_address: String,
+        /// the method selector (0x-prefixed 4 bytes), empty for the native transfers
+        pub selector: String,
+        /// the method signature, e.g. "transfer(address,uint256)" (empty if unknown)
+        pub method: String,
+        pub count: u64,
+        /// the failed transactions (their gas is paid too)
+        pub failed_count: u64,
+        /// decimal
+        pub total_gas_used: String,
+        /// decimal
+        pub average_gas_used: String,
+        /// decimal, in wei
+        pub total_fee: String,
+    }
+
+    /// Transactions with the explorer that produced them
+    pub struct TransactionHistoryResult {
+        pub source: ExplorerSource,
+        pub transactions: Vec<RawTxDetail>,
+    }
+
+    /// Token transfers with the explorer that produced them
+    pub struct TokenTransfersResult {
+        pub source: ExplorerSource,
+        pub transfers: Vec<RawTokenTxDetail>,
+    }
+
+    pub enum QueryOption {
+        ByContract,
+        ByAddressAndContract,
+        ByAddress,
+    }
+
+    /// Order of the listed transactions or transfers (by block)
+    pub enum SortOrder {
+        Ascending,
+        Descending,
+    }
+
+    /// Direction of the transactions or transfers relative to the queried address
+    pub enum TransferDirection {
+        Any,
+        /// received by the address
+        Incoming,
+        /// sent by the address
+        Outgoing,
+    }
+
+    /// Filter of the listed transactions or transfers (the unset fields don't filter);
+    /// the explorer filters by the contract if there's only one,
+    /// the other fields are applied to its results (so a page may have fewer records)
+    pub struct QueryFilter {
+        pub direction: TransferDirection,
+        /// the minimum value in base units (decimal; empty for no minimum)
+        pub min_value: String,
+        /// the token contracts of the transfers or the receivers of the transactions
+        /// (hexadecimal; empty for all)
+        pub contract_addresses: Vec<String>,
+        /// the first time (unix seconds; 0 for no limit)
+        pub from_timestamp: u64,
+        /// the last time (unix seconds; 0 for no limit)
+        pub to_timestamp: u64,
+        /// only the first record of each transaction hash is kept
+        pub dedup_by_hash: bool,
+    }
+
+    /// Range, pagination and order of the listed transactions or transfers
+    pub struct TxListOptions {
+        /// the first block (inclusive)
+        pub start_block: u64,
+        /// the last block (inclusive); 0 for the latest block
+        pub end_block: u64,
+        /// the page number (starting from 1; 0 for no pagination)
+        pub page: u64,
+        /// the maximum number of records in a page (0 for the explorer's default)
+        pub offset: u64,
+        pub sort: SortOrder,
+    }
+
+    /// HTTP header added to the SDK's requests
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct HttpHeader {
+        /// header name, e.g. "User-Agent"
+        pub name: String,
+        /// header value
+        pub value: String,
+    }
+
+    /// static DNS entry
+    pub struct HostOverride {
+        /// host name, e.g. "api.cronoscan.com"
+        pub host: String,
+        /// IPv4 or IPv6 address
+        pub ip_address: String,
+    }
+
+    /// How the HTTP requests (explorer, registry, Crypto.com Pay) are handled
+    pub enum HttpMode {
+        /// send the requests
+        Live,
+        /// send the requests and store the responses in the fixtures directory
+        Record,
+        /// do not send the requests, but return the responses from the fixtures directory
+        Replay,
+    }
+
+    extern "Rust" {
+        /// filter wallets by platform
+        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
+        pub fn filter_wallets(
+            cached: bool,
+            registry_local_path: String,
+            platform: Platform,
+        ) -> Result<Vec<WalletEntry>>;
+        /// get all possible wallets
+        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
+        pub fn get_all_wallets(
+            cached: bool,
+            registry_local_path: String,
+        ) -> Result<Vec<WalletEntry>>;
+        /// check wallet by `id` for supported `platform` listing or not
+        /// Check wallet id at https://explorer.walletconnect.com/
+        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
+        pub fn check_wallet(
+            cached: bool,
+            registry_local_path: String,
+            id: String,
+            platform: Platform,
+        ) -> Result<bool>;
+        /// get a wallet by `id`
+        /// Check wallet id at https://explorer.walletconnect.com/
+        /// (`registry_local_path` can be empty string if it is not needed to store the `cached` registry result)
+        pub fn get_wallet(
+            cached: bool,
+            registry_local_path: String,
+            id: String,
+        ) -> Result<WalletEntry>;
+        pub fn generate_qrcode(qrcodestring: String) -> Result<WalletQrcode>;
+        /// WallnetConnect API
+        type WalletconnectClient;
+        /// restore walletconnect-session from string
+        pub fn walletconnect_restore_client(
+            session_info: String,
+        ) -> Result<Box<WalletconnectClient>>;
+        /// create walletconnect-session
+        /// the chain id (if 0, retrived and decided by wallet, if > 0, decided by the client)
+        pub fn walletconnect_new_client(
+            description: String,
+            url: String,
+            icon_urls: Vec<String>,
+            name: String,
+            chain_id: u64,
+        ) -> Result<Box<WalletconnectClient>>;
+
+        /// setup callback
+        /// (it replaces the previous one; `onError` is called if the callback task fails)
+        pub fn setup_callback_blocking(
+            self: &mut WalletconnectClient,
+            usercallback: UniquePtr<WalletConnectCallback>,
+        ) -> Result<()>;
+        /// setup callback in the manual pump mode: the session events are queued
+        /// and delivered on the caller's thread (e.g. the game loop) by `poll_events`
+        /// (it replaces the previous callback)
+        pub fn setup_polled_callback_blocking(
+            self: &mut WalletconnectClient,
+            usercallback: UniquePtr<WalletConnectCallback>,
+        ) -> Result<()>;
+        /// deliver up to max_events queued session events to the polled callback,
+        /// returns the number of delivered events
+        pub fn poll_events(self: &mut WalletconnectClient, max_events: u32) -> Result<u32>;
+        /// stop the callback task
+        pub fn stop_callback(self: &mut WalletconnectClient);
+        /// whether the callback task is running
+        pub fn is_callback_running(self: &WalletconnectClient) -> bool;
+        /// to be called when the app goes to the background (Android/iOS suspend its sockets):
+        /// the keepalives are paused
+        pub fn notify_app_background(self: &mut WalletconnectClient) -> Result<()>;
+        /// to be called when the app returns to the foreground: it reconnects
+        /// to the bridge server, receives the messages queued in the meantime
+        /// and resumes the keepalives (the callback receives the current session as `Updated`)
+        pub fn notify_app_foreground(self: &mut WalletconnectClient) -> Result<()>;
+        /// the state of the bridge server connection (WalletConnect 1.0 only)
+        /// and the health of the HTTP endpoints, e.g. for the diagnostics screens
+        pub fn get_connection_status(self: &mut WalletconnectClient) -> Result<ConnectionStatus>;
+        /// enables (with an empty log) or disables the capture of the decrypted messages
+        /// exchanged with the wallet via the bridge server, with the secrets redacted
+        pub fn set_replay_capture(self: &mut WalletconnectClient, enabled: bool) -> Result<()>;
+        /// the captured messages as JSON Lines, e.g. to be attached to the bug reports
+        /// and replayed in the tests (`defi_wallet_connect::replay`)
+        pub fn export_replay_log(self: &mut WalletconnectClient) -> Result<String>;
+        /// create or restore a session
+        /// once session is created, it will be reused
+        pub fn ensure_session_blocking(
+            self: &mut WalletconnectClient,
+        ) -> Result<WalletConnectEnsureSessionResult>;
+        /// get connection string for qrcode
+        pub fn get_connection_string(self: &mut WalletconnectClient) -> Result<String>;
+        /// write session-info to string, which can be written to file
+        pub fn save_client(self: &mut WalletconnectClient) -> Result<String>;
+        /// session-info without the key and with the topics hashed (as JSON), e.g. to be
+        /// attached to the bug reports (it can't be restored)
+        pub fn get_session_info_redacted(self: &mut WalletconnectClient) -> Result<String>;
+        /// print qrcode in termal, for debugging
+        pub fn print_uri(self: &mut WalletconnectClient) -> Result<String>;
+        /// attach the metadata to the next request that the wallet asks the user to approve
+        /// (WalletConnect 1.0 only; all fields empty clears it)
+        pub fn set_request_metadata(
+            self: &mut WalletconnectClient,
+            metadata: &WalletConnectRequestMetadata,
+        ) -> Result<()>;
+        /// the signing requests of the session (their hashes, times and results)
+        /// as a JSON array of `AuditEntry`
+        pub fn export_audit_log(self: &WalletconnectClient) -> Result<String>;
+        /// the signing requests of the session (oldest first)
+        pub fn audit_entries(self: &WalletconnectClient) -> Vec<AuditEntry>;
+        /// appends the signing requests recorded from now on to the JSON Lines file
+        /// (none if the path is empty), so that they're kept after the session
+        pub fn set_audit_log_path(self: &mut WalletconnectClient, path: String);
+        /// forgets the recorded signing requests (the file is kept)
+        pub fn clear_audit_log(self: &mut WalletconnectClient);
+        /// persists the sends by idempotency key to the JSON file (none if the path is empty),
+        /// loading the ones already in it, so that the keys are kept after a crash or a restart
+        pub fn set_idempotency_store_path(
+            self: &mut WalletconnectClient,
+            path: String,
+        ) -> Result<()>;
+        /// forgets the idempotency key, so that a send with it broadcasts again
+        /// (e.g. after checking that its unfinished send wasn't broadcast)
+        pub fn forget_idempotency_key(
+            self: &mut WalletconnectClient,
+            idempotency_key: String,
+        ) -> Result<()>;
+        /// sign message
+        pub fn sign_personal_blocking(
+            self: &mut WalletconnectClient,
+            message: String,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+        /// sign the challenge of an `AuthServer` (like `sign_personal_blocking`), after checking
+        /// that it's of the expected domain (of the server) and the address, and not expired
+        pub fn sign_auth_challenge_blocking(
+            self: &mut WalletconnectClient,
+            challenge: String,
+            domain: String,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+        /// sign arbitrary bytes (e.g. a binary challenge)
+        /// if eip191_prefix is true, the wallet signs the message with the EIP-191 prefix
+        /// (`personal_sign`), otherwise the message must be a 32-byte digest
+        /// signed as it is (`eth_sign`; not supported by all wallets)
+        pub fn sign_personal_bytes_blocking(
+            self: &mut WalletconnectClient,
+            message: Vec<u8>,
+            eip191_prefix: bool,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// build cronos(eth) eip155 transaction
+        /// Supported Wallets: Trust Wallet, Crypto.com Desktop Defi Wallet
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_eip155_transaction_blocking(
+            self: &mut WalletconnectClient,
+            info: &WalletConnectTxEip155,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// signs the eip155 transaction via the wallet (`eth_signTransaction`) without
+        /// broadcasting it, returns the signed raw transaction for the game to broadcast
+        /// (e.g. with `broadcast_raw_tx_blocking`); it fails with "Unsupported by the wallet"
+        /// if the wallet doesn't support the method (then send it with
+        /// `send_eip155_transaction_blocking`)
+        pub fn wallet_sign_transaction_blocking(
+            self: &mut WalletconnectClient,
+            info: &WalletConnectTxEip155,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// send cronos(eth) eip155 transaction
+        /// Supported Wallets: Trust Wallet, MetaMask and Crypto.com Mobile Defi Wallet
+        pub fn send_eip155_transaction_blocking(
+            self: &mut WalletconnectClient,
+            info: &WalletConnectTxEip155,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// `send_eip155_transaction_blocking` once per idempotency key (e.g. a reward id):
+        /// a send repeated with the key returns the hash of the first transaction instead
+        /// of broadcasting again; it fails if the first send didn't finish (e.g. a crash)
+        /// or failed after its transaction may have been broadcast (e.g. a timeout), until
+        /// the key is forgotten; the sends rejected by the user or the node can be repeated
+        pub fn send_eip155_transaction_with_key_blocking(
+            self: &mut WalletconnectClient,
+            info: &WalletConnectTxEip155,
+            address: [u8; 20],
+            idempotency_key: String,
+        ) -> Result<Vec<u8>>;
+
+        /// eip1559_transaction_request: json string of Eip1559TransactionRequest
+        /// (see `transaction_json_schema`)
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_transaction(
+            self: &mut WalletconnectClient,
+            eip1559_transaction_request: String,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// eip1559_transaction_request: json string of Eip1559TransactionRequest
+        /// (see `transaction_json_schema`)
+        /// return transaction hash bytes
+        pub fn send_transaction(
+            self: &mut WalletconnectClient,
+            eip1559_transaction_request: String,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// `send_transaction` once per idempotency key
+        pub fn send_transaction_with_key(
+            self: &mut WalletconnectClient,
+            eip1559_transaction_request: String,
             address: [u8; 20],
+            idempotency_key: String,
         ) -> Result<Vec<u8>>;
 
+        /// sign a contract transaction
+        /// contract_action is a json string of `ContractAction` type, for example:
+        /// for example, transfer Erc20 token
+        /// {
+        ///     "ContractTransfer": {
+        ///         "Erc20Transfer": {
+        ///             "contract_address": "0xxxxx",
+        ///             "to_address": "0xxxxx",
+        ///             "amount": "1000000000000000000"
+        ///         }
+        ///     }
+        /// }
+        /// or transfer a batch of Erc1155 tokens
+        /// {
+        ///     "ContractBatchTransfer": {
+        ///         "Erc1155": {
+        ///             "contract_address": "0xxxxx",
+        ///             "from_address": "0xxxxx",
+        ///             "to_address": "0xxxxx",
+        ///             "token_ids": ["1", "2"],
+        ///             "amounts": ["10", "20"],
+        ///             "additional_data": []
+        ///         }
+        ///     }
+        /// }
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_contract_transaction(
+            self: &mut WalletconnectClient,
+            contract_action: String,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
         // send a contract transaction
         /// contract_action is a json string of `ContractAction` type
         /// for example, transfer Erc20 token
@@ -361,6 +2125,19 @@ mod ffi {
         ///         }
         ///     }
         /// }
+        /// or transfer a batch of Erc1155 tokens
+        /// {
+        ///     "ContractBatchTransfer": {
+        ///         "Erc1155": {
+        ///             "contract_address": "0xxxxx",
+        ///             "from_address": "0xxxxx",
+        ///             "to_address": "0xxxxx",
+        ///             "token_ids": ["1", "2"],
+        ///             "amounts": ["10", "20"],
+        ///             "additional_data": []
+        ///         }
+        ///     }
+        /// }
         // return transaction hash bytes
         pub fn send_contract_transaction(
             self: &mut WalletconnectClient,
@@ -369,12 +2146,1063 @@ mod ffi {
             address: [u8; 20],
         ) -> Result<Vec<u8>>;
 
-        /// returns the transactions of a given address.
-        /// The API key can be obtained from https://cronoscan.com
-        pub fn get_transaction_history_blocking(
+        /// sign an Erc20 approval (same as `sign_contract_transaction` without json)
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_erc20_approval(
+            self: &mut WalletconnectClient,
+            action: &Erc20ApprovalAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// send an Erc20 approval (same as `send_contract_transaction` without json)
+        /// return transaction hash bytes
+        pub fn send_erc20_approval(
+            self: &mut WalletconnectClient,
+            action: &Erc20ApprovalAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// sign an Erc20 transfer (same as `sign_contract_transaction` without json)
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_erc20_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc20TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// send an Erc20 transfer (same as `send_contract_transaction` without json)
+        /// return transaction hash bytes
+        pub fn send_erc20_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc20TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// sign an Erc721 transfer (same as `sign_contract_transaction` without json)
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_erc721_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc721TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// send an Erc721 transfer (same as `send_contract_transaction` without json)
+        /// return transaction hash bytes
+        pub fn send_erc721_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc721TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// sign an Erc1155 transfer (same as `sign_contract_transaction` without json)
+        /// return the signed transaction bytes, its hash and signature
+        pub fn sign_erc1155_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc1155TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<SignedTxResult>;
+
+        /// send an Erc1155 transfer (same as `send_contract_transaction` without json)
+        /// return transaction hash bytes
+        pub fn send_erc1155_transfer(
+            self: &mut WalletconnectClient,
+            action: &Erc1155TransferAction,
+            common: &WalletConnectTxCommon,
+            address: [u8; 20],
+        ) -> Result<Vec<u8>>;
+
+        /// WalletConnect 2.0 API
+        type Walletconnect2Client;
+        /// create the WalletConnect 2.0 client
+        /// relay_server: e.g. "wss://relay.walletconnect.com" (without the trailing `/`)
+        /// required_namespaces: json of the namespaces (methods, chains, events)
+        /// required by the client
+        /// client_meta: json of the client metadata (description, url, icons, name)
+        pub fn walletconnect2_client_new(
+            relay_server: String,
+            project_id: String,
+            required_namespaces: String,
+            client_meta: String,
+        ) -> Result<Box<Walletconnect2Client>>;
+        /// restore the WalletConnect 2.0 client from the session info string
+        pub fn walletconnect2_restore_client(
+            session_info: String,
+        ) -> Result<Box<Walletconnect2Client>>;
+        /// detects the version of the saved session (e.g. in the player saves) and migrates it:
+        /// the 2.0 sessions are kept, the wallet has to pair again for the 1.0 sessions
+        /// (on their chain, or on default_chain_id if it's unknown)
+        pub fn walletconnect2_migrate_session(
+            session_json: String,
+            default_chain_id: u64,
+        ) -> Result<MigrationOutcome>;
+        /// create or restore a session, returns the namespaces settled by the wallet
+        pub fn ensure_session_blocking(
+            self: &mut Walletconnect2Client,
+        ) -> Result<WalletConnect2Namespaces>;
+        /// get connection string for qrcode
+        pub fn get_connection_string(self: &mut Walletconnect2Client) -> String;
+        /// write session-info to string, which can be written to file
+        pub fn save_client(self: &mut Walletconnect2Client) -> Result<String>;
+        /// session-info without the keys and with the topics hashed (as JSON), e.g. to be
+        /// attached to the bug reports (it can't be restored)
+        pub fn get_session_info_redacted(self: &mut Walletconnect2Client) -> Result<String>;
+        /// sign the protobuf-encoded Cosmos transaction (`cosmos_signDirect`) with the account
+        /// of the signer address on the chain (e.g. "crypto-org-chain-mainnet-1";
+        /// the Cosmos namespace has to be required)
+        pub fn cosmos_sign_direct_blocking(
+            self: &mut Walletconnect2Client,
+            chain_id: String,
+            signer_address: String,
+            account_number: u64,
+            auth_info_bytes: Vec<u8>,
+            body_bytes: Vec<u8>,
+        ) -> Result<WalletConnect2CosmosSignature>;
+        /// sign the Amino JSON `StdSignDoc` (`cosmos_signAmino`) with the account
+        /// of the signer address on the chain
+        pub fn cosmos_sign_amino_blocking(
+            self: &mut Walletconnect2Client,
+            chain_id: String,
+            signer_address: String,
+            sign_doc: String,
+        ) -> Result<WalletConnect2CosmosSignature>;
+        /// set the callback previewing the namespaces settled by the wallet before
+        /// the session is established: if it returns false, the session is rejected
+        /// and `ensure_session_blocking` fails (a null callback removes it)
+        pub fn set_session_proposal_callback(
+            self: &mut Walletconnect2Client,
+            usercallback: UniquePtr<WalletConnect2SessionProposalCallback>,
+        );
+        /// the state of the relay connection and the health of the HTTP endpoints
+        pub fn get_connection_status(self: &mut Walletconnect2Client) -> ConnectionStatus;
+        /// enables (with an empty log) or disables the capture of the decrypted messages
+        /// exchanged with the wallet via the relay, with the secrets redacted
+        pub fn set_replay_capture(self: &mut Walletconnect2Client, enabled: bool);
+        /// the captured messages as JSON Lines (the topics are hashed)
+        pub fn export_replay_log(self: &mut Walletconnect2Client) -> Result<String>;
+
+        /// splits a 65-byte signature (r || s || v), e.g. from `sign_personal_blocking`
+        pub fn signature_from_rsv(signature: Vec<u8>) -> Result<SignatureParts>;
+        /// joins the signature parts into 65 bytes (r || s || v)
+        pub fn signature_to_rsv(signature: &SignatureParts) -> Result<Vec<u8>>;
+        /// encodes the signature parts into a 64-byte EIP-2098 compact signature
+        pub fn signature_to_eip2098(signature: &SignatureParts) -> Result<Vec<u8>>;
+        /// decodes a 64-byte EIP-2098 compact signature
+        pub fn signature_from_eip2098(compact: Vec<u8>) -> Result<SignatureParts>;
+        /// returns the Keccak-256 hash (32 bytes) of the data
+        pub fn keccak256(data: Vec<u8>) -> Vec<u8>;
+        /// returns the SHA-256 hash (32 bytes) of the data
+        pub fn sha256(data: Vec<u8>) -> Vec<u8>;
+        /// returns the HMAC-SHA256 (32 bytes) of the message with the key
+        pub fn hmac_sha256(key: Vec<u8>, message: Vec<u8>) -> Vec<u8>;
+        /// returns the EIP-191 hash (32 bytes) of the message, i.e. the hash signed
+        /// by `personal_sign` (e.g. `sign_personal_bytes_blocking` with the prefix)
+        pub fn hash_eip191_message(message: Vec<u8>) -> Vec<u8>;
+        /// returns the address (hexadecimal, 0x-prefixed) of the secp256k1 public key:
+        /// uncompressed (65 bytes with the 0x04 prefix or 64 bytes without it) or compressed (33 bytes)
+        pub fn address_from_public_key(public_key: Vec<u8>) -> Result<String>;
+        /// returns the hexadecimal encoding of the data (lowercase; 0x-prefixed if `prefix`)
+        pub fn hex_encode(data: Vec<u8>, prefix: bool) -> String;
+        /// decodes the hexadecimal string (with or without the 0x prefix; case-insensitive)
+        pub fn hex_decode(hex: String) -> Result<Vec<u8>>;
+        /// returns the (standard, padded) base64 encoding of the data
+        pub fn base64_encode(data: Vec<u8>) -> String;
+        /// decodes the (standard, padded) base64 string
+        pub fn base64_decode(encoded: String) -> Result<Vec<u8>>;
+        /// returns the RLP encoding of the list of strings (as their UTF-8 bytes)
+        pub fn rlp_encode_strings(items: Vec<String>) -> Vec<u8>;
+        /// returns the RLP encoding of the list of integers
+        pub fn rlp_encode_u64s(items: Vec<u64>) -> Vec<u8>;
+        /// formats the amount of the base units (e.g. a balance or a transfer value,
+        /// decimal or 0x-prefixed hexadecimal) as a decimal number of the tokens,
+        /// e.g. "1234567890000000000000" with 18 decimals as "1,234.57"
+        pub fn format_amount(amount: String, format: &AmountFormat) -> Result<String>;
+        /// returns the format with the thousands separator and the decimal mark of the locale
+        /// (e.g. "en-US", "de-DE" or "fr_FR"), rounded to 4 fraction digits without
+        /// the trailing zeros
+        pub fn amount_format_for_locale(locale: String, decimals: u32) -> AmountFormat;
+        /// set the record/replay mode of all HTTP requests
+        /// (by default, it is taken from the `PLAY_CPP_SDK_HTTP_MODE` environment variable:
+        /// `live`, `record` or `replay`)
+        /// fixtures_dir can be empty to use the `PLAY_CPP_SDK_HTTP_FIXTURES` environment variable
+        /// (or `fixtures` if not set)
+        pub fn set_http_mode(mode: HttpMode, fixtures_dir: String) -> Result<()>;
+
+        /// set the headers (e.g. User-Agent or authorization headers)
+        /// added to all the explorer, registry and Crypto.com Pay requests
+        /// (it replaces the previously set headers)
+        pub fn set_default_headers(headers: Vec<HttpHeader>) -> Result<()>;
+
+        /// set the TLS options of the HTTP requests and the WalletConnect 1.0 bridge connection
+        /// root_certificates_pem: additional trusted root certificates in PEM (can be empty)
+        /// spki_sha256_pins: base64-encoded SHA-256 hashes of the allowed SubjectPublicKeyInfo
+        /// (optionally prefixed with "sha256/"); if empty, no pinning is done
+        /// NOTE: the WalletConnect 2.0 relay connection uses its own TLS configuration
+        pub fn set_tls_options(
+            root_certificates_pem: String,
+            spki_sha256_pins: Vec<String>,
+        ) -> Result<()>;
+
+        /// set the static DNS entries used instead of the system resolver
+        /// by the HTTP requests and the WalletConnect 1.0 bridge connection
+        /// (a host can be repeated to give several addresses; it replaces the previous overrides)
+        pub fn set_host_overrides(overrides: Vec<HostOverride>) -> Result<()>;
+
+        /// set the failover policy of the explorer queries: if enabled,
+        /// `get_transaction_history_blocking` falls back to BlockScout when Cronoscan fails,
+        /// and `get_token_transfers_blocking` falls back to Cronoscan when BlockScout fails
+        pub fn set_explorer_failover_policy(policy: &ExplorerFailoverPolicy) -> Result<()>;
+
+        /// returns the transactions of a given address.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_transaction_history_blocking(
+            address: String,
+            api_key: String,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// returns the transactions of a given address
+        /// and the explorer that produced them (see `set_explorer_failover_policy`).
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_transaction_history_with_source_blocking(
+            address: String,
+            api_key: String,
+        ) -> Result<TransactionHistoryResult>;
+        /// returns the transactions of a given address in the block range, page and order
+        /// of the options.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_transaction_history_with_options_blocking(
+            address: String,
+            options: &TxListOptions,
+            api_key: String,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// the same as `get_transaction_history_with_options_blocking`,
+        /// but only the transactions matching the filter are returned
+        pub fn get_transaction_history_filtered_blocking(
+            address: String,
+            options: &TxListOptions,
+            filter: &QueryFilter,
+            api_key: String,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// returns the gas usage of the transactions sent by the address (in the block range,
+        /// page and order of the options) by contract and method selector, the most expensive
+        /// first; the methods are named with the ABI JSON (optional, it can be empty),
+        /// the standard token methods or the explorer.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_gas_report_blocking(
+            address: String,
+            options: &TxListOptions,
+            abi_json: String,
+            api_key: String,
+        ) -> Result<Vec<MethodGasUsage>>;
+        /// Paginated transaction history
+        type HistoryCursor;
+        /// returns a cursor over the transactions of a given address (oldest first),
+        /// so that large histories can be iterated in bounded memory.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn open_history_cursor(address: String, api_key: String) -> Result<Box<HistoryCursor>>;
+        /// returns up to `n` next transactions (an empty result means the end of the history)
+        pub fn next_page(self: &mut HistoryCursor, n: u64) -> Result<Vec<RawTxDetail>>;
+        /// saves the position of the cursor (e.g. to be written to a file)
+        pub fn save(self: &HistoryCursor) -> Result<String>;
+        /// restores a cursor saved by `save`: it continues after the last returned
+        /// transaction, so that repeated syncs only fetch the new transactions
+        pub fn restore_history_cursor(saved: String, api_key: String)
+            -> Result<Box<HistoryCursor>>;
+        /// returns the transactions of a given address from `start_block` (inclusive),
+        /// so that the already seen blocks are not fetched again.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_transaction_history_since_blocking(
+            address: String,
+            start_block: u64,
+            api_key: String,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// Local database of the synced transaction history
+        type LocalHistory;
+        /// opens (or creates) the local database in the given directory;
+        /// the transactions and token transfers of the registered addresses
+        /// are synced from Cronoscan by `sync`, and then they can be queried offline.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn open_local_history(path: String, api_key: String) -> Result<Box<LocalHistory>>;
+        /// registers the address to be synced
+        pub fn register_address(self: &mut LocalHistory, address: String) -> Result<()>;
+        /// unregisters the address and removes its stored records
+        pub fn unregister_address(self: &mut LocalHistory, address: String) -> Result<()>;
+        /// returns the registered addresses
+        pub fn registered_addresses(self: &LocalHistory) -> Result<Vec<String>>;
+        /// syncs the new records of the registered addresses (since the last sync),
+        /// returns the number of the newly stored records
+        pub fn sync(self: &mut LocalHistory) -> Result<u64>;
+        /// removes the stored records and syncs the registered addresses from the beginning,
+        /// returns the number of the stored records
+        pub fn resync(self: &mut LocalHistory) -> Result<u64>;
+        /// returns the stored transactions matching the filter
+        pub fn query_local_history(
+            self: &LocalHistory,
+            filter: &LocalHistoryFilter,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// returns the stored ERC20 and ERC721 transfers matching the filter
+        pub fn query_local_token_transfers(
+            self: &LocalHistory,
+            filter: &LocalHistoryFilter,
+        ) -> Result<Vec<RawTokenTxDetail>>;
+
+        /// Deterministic accounts signing locally, for the automated tests
+        #[cfg(feature = "dev-wallet")]
+        type DevWallet;
+        /// derives `count` deterministic accounts from the mnemonic (the Hardhat/Anvil
+        /// "test test ... junk" one if empty) on the chain, e.g. for the C++ integration tests
+        /// without WalletConnect (only if the SDK is built with the `dev-wallet` feature)
+        #[cfg(feature = "dev-wallet")]
+        pub fn new_dev_wallet(
+            mnemonic: String,
+            count: u32,
+            chain_id: u64,
+        ) -> Result<Box<DevWallet>>;
+        /// returns the addresses of the accounts
+        #[cfg(feature = "dev-wallet")]
+        pub fn addresses(self: &DevWallet) -> Vec<String>;
+        /// returns the private key (32 bytes) of the account, e.g. to fund it on a local node
+        #[cfg(feature = "dev-wallet")]
+        pub fn private_key(self: &DevWallet, index: u32) -> Result<Vec<u8>>;
+        /// signs the message with the EIP-191 prefix (like `personal_sign`): 65 bytes;
+        /// the policy is checked like the WalletConnect message signing'
+        #[cfg(feature = "dev-wallet")]
+        pub fn sign_personal_message(
+            self: &DevWallet,
+            index: u32,
+            message: Vec<u8>,
+        ) -> Result<Vec<u8>>;
+        /// signs the 32-byte hash as it is: 65 bytes (refused by any policy, like `eth_sign`)
+        #[cfg(feature = "dev-wallet")]
+        pub fn sign_hash(self: &DevWallet, index: u32, hash: Vec<u8>) -> Result<Vec<u8>>;
+        /// signs the EIP-1559 transaction with the account (on the chain of the wallet
+        /// if `common.chainid` is 0); the fee caps, the policy and the screening
+        /// are checked like the WalletConnect transactions'
+        #[cfg(feature = "dev-wallet")]
+        pub fn sign_eip155_transaction(
+            self: &DevWallet,
+            index: u32,
+            tx: &WalletConnectTxEip155,
+        ) -> Result<SignedTxResult>;
+
+        /// Ledger hardware wallet with the Ethereum app open
+        type LedgerWallet;
+        /// returns the Ledgers connected over USB
+        /// (it fails if the SDK is built without the `ledger` feature)
+        pub fn list_ledger_devices() -> Result<Vec<LedgerDevice>>;
+        /// opens the Ledger of the HID path (the first connected one if empty) to sign
+        /// on the chain (it fails if the SDK is built without the `ledger` feature)
+        pub fn open_ledger_wallet(device_path: String, chain_id: u64) -> Result<Box<LedgerWallet>>;
+        /// returns the address of the derivation path (m/44'/60'/0'/0/0 if empty);
+        /// if `verify_on_device`, it's displayed on the Ledger and returned once the user
+        /// confirms it (it blocks until then)
+        pub fn get_address(
+            self: &LedgerWallet,
+            derivation_path: String,
+            verify_on_device: bool,
+        ) -> Result<String>;
+        /// signs the EIP-1559 transaction with the account of the derivation path
+        /// (m/44'/60'/0'/0/0 if empty) once the user confirms it on the Ledger
+        /// (on the chain of the wallet if `common.chainid` is 0); the fee caps, the policy
+        /// and the screening are checked like the WalletConnect transactions'
+        pub fn sign_eip155_transaction(
+            self: &LedgerWallet,
+            derivation_path: String,
+            tx: &WalletConnectTxEip155,
+        ) -> Result<SignedTxResult>;
+
+        /// Read-only wallet of an address (e.g. for the spectators or the customer support)
+        type WatchWallet;
+        /// creates the watch-only wallet of the address: its history, balances and tokens
+        /// can be queried, but every signing fails with a "Not signable" error
+        pub fn new_watch_wallet(address: String) -> Result<Box<WatchWallet>>;
+        /// returns the address of the wallet
+        pub fn address(self: &WatchWallet) -> String;
+        /// returns the transactions of the address (see `get_transaction_history_blocking`)
+        pub fn get_transaction_history_blocking(
+            self: &WatchWallet,
+            api_key: String,
+        ) -> Result<Vec<RawTxDetail>>;
+        /// returns the token transfers of the address (see `get_token_transfers_blocking`)
+        pub fn get_token_transfers_blocking(
+            self: &WatchWallet,
+            blockscout_base_url: String,
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// returns the tokens owned by the address (see `get_tokens_blocking`)
+        pub fn get_tokens_blocking(
+            self: &WatchWallet,
+            blockscout_base_url: String,
+        ) -> Result<Vec<RawTokenResult>>;
+        /// returns the balance of the native coin (decimal, in wei) via the JSON-RPC url
+        pub fn get_balance_blocking(self: &WatchWallet, rpc_url: String) -> Result<String>;
+        /// creates a watcher of the activity of the address (see `new_address_watcher`)
+        pub fn watch_activity(
+            self: &WatchWallet,
+            api_key: String,
+            interval_ms: u64,
+        ) -> Result<Box<AddressWatcher>>;
+        /// always fails: the wallet can't sign
+        pub fn sign_personal_message(self: &WatchWallet, message: Vec<u8>) -> Result<Vec<u8>>;
+        /// always fails: the wallet can't sign
+        pub fn sign_hash(self: &WatchWallet, hash: Vec<u8>) -> Result<Vec<u8>>;
+        /// always fails: the wallet can't sign
+        pub fn sign_eip155_transaction(
+            self: &WatchWallet,
+            tx: &WalletConnectTxEip155,
+        ) -> Result<SignedTxResult>;
+
+        /// Local Anvil node for the end-to-end transaction tests
+        #[cfg(feature = "test-utils")]
+        type LocalNode;
+        /// spawns a local Anvil node and waits until it's listening (it's killed when the node
+        /// is destroyed; only if the SDK is built with the `test-utils` feature)
+        #[cfg(feature = "test-utils")]
+        pub fn spawn_local_node(config: &LocalNodeConfig) -> Result<Box<LocalNode>>;
+        /// returns the JSON-RPC url of the node
+        #[cfg(feature = "test-utils")]
+        pub fn endpoint(self: &LocalNode) -> String;
+        #[cfg(feature = "test-utils")]
+        pub fn port(self: &LocalNode) -> u16;
+        #[cfg(feature = "test-utils")]
+        pub fn chain_id(self: &LocalNode) -> u64;
+        /// returns the addresses of the prefunded accounts
+        #[cfg(feature = "test-utils")]
+        pub fn addresses(self: &LocalNode) -> Vec<String>;
+        /// returns the private keys (hexadecimal, 0x-prefixed) of the prefunded accounts
+        #[cfg(feature = "test-utils")]
+        pub fn private_keys(self: &LocalNode) -> Vec<String>;
+        /// kills the node
+        #[cfg(feature = "test-utils")]
+        pub fn stop(self: &mut LocalNode);
+        /// whether the node is running
+        #[cfg(feature = "test-utils")]
+        pub fn is_running(self: &mut LocalNode) -> bool;
+
+        /// Polling watcher of address activity
+        type AddressWatcher;
+        /// creates a watcher polling Cronoscan every `interval_ms` (at least 1 second)
+        /// for the transactions and token transfers of the addresses.
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn new_address_watcher(
+            addresses: Vec<String>,
+            api_key: String,
+            interval_ms: u64,
+        ) -> Result<Box<AddressWatcher>>;
+        /// starts polling: the callback is called once for each new transaction
+        /// or token transfer (the activity before the start is not reported;
+        /// it replaces the previous callback)
+        pub fn start(
+            self: &mut AddressWatcher,
+            callback: UniquePtr<AddressWatcherCallback>,
+        ) -> Result<()>;
+        /// stops polling
+        pub fn stop(self: &mut AddressWatcher);
+        /// whether the watcher is polling
+        pub fn is_running(self: &AddressWatcher) -> bool;
+
+        /// Cache of the exchange rates refreshed at an interval
+        type RateService;
+        /// creates a service refreshing the rates of the pairs (e.g. "CRO/USD") from the CoinGecko
+        /// API url (the public one if empty) every `interval_ms` (at least 5 seconds)
+        pub fn new_rate_service(
+            pairs: Vec<String>,
+            interval_ms: u64,
+            api_url: String,
+        ) -> Result<Box<RateService>>;
+        /// starts refreshing: the callback is called once for each new or changed rate
+        /// (it replaces the previous callback)
+        pub fn start(
+            self: &mut RateService,
+            callback: UniquePtr<RateServiceCallback>,
+        ) -> Result<()>;
+        /// stops refreshing (the cached rates are kept)
+        pub fn stop(self: &mut RateService);
+        /// whether the service is refreshing
+        pub fn is_running(self: &RateService) -> bool;
+        /// returns the cached rate of the pair (without any request, e.g. for the HUD)
+        pub fn get_rate(self: &RateService, pair: String) -> Result<ExchangeRate>;
+        /// returns the cached rates
+        pub fn get_rates(self: &RateService) -> Vec<ExchangeRate>;
+
+        /// Durable queue of the transactions of a custodial wallet
+        type TxQueue;
+        /// opens the queue persisted in the directory, whose transactions are signed with
+        /// the private key (32 bytes) on the chain and sent via the JSON-RPC url (the ones
+        /// of the SDK environment if 0 or empty; the other chains are refused)
+        pub fn open_tx_queue(
+            path: String,
+            rpc_url: String,
+            private_key: Vec<u8>,
+            chain_id: u64,
+        ) -> Result<Box<TxQueue>>;
+        /// opens the queue whose transactions are signed by the external signer
+        /// (e.g. an HSM or a remote signing service) of the address, instead of an in-process key
+        pub fn open_tx_queue_with_external_signer(
+            path: String,
+            rpc_url: String,
+            address: String,
+            chain_id: u64,
+            signer: UniquePtr<ExternalSignerCallback>,
+        ) -> Result<Box<TxQueue>>;
+        /// opens the queue whose transactions are signed by the cloud KMS key
+        /// (only if the SDK is built with the `kms` feature)
+        #[cfg(feature = "kms")]
+        pub fn open_tx_queue_with_kms(
+            path: String,
+            rpc_url: String,
+            kms: &KmsConfig,
+            chain_id: u64,
+        ) -> Result<Box<TxQueue>>;
+        /// returns the address of the cloud KMS key (e.g. to fund it before opening the queue)
+        #[cfg(feature = "kms")]
+        pub fn get_kms_address(kms: &KmsConfig) -> Result<String>;
+        /// opens the queue whose transactions are signed by the threshold (MPC) key
+        /// of the address: the SDK runs the rounds of the local party and exchanges
+        /// their messages with the other parties via the transport
+        pub fn open_tx_queue_with_mpc(
+            path: String,
+            rpc_url: String,
+            address: String,
+            chain_id: u64,
+            party: UniquePtr<MpcPartyCallback>,
+            transport: UniquePtr<MpcTransportCallback>,
+        ) -> Result<Box<TxQueue>>;
+        /// returns the address of the wallet
+        pub fn address(self: &TxQueue) -> String;
+        /// enqueues the transaction and returns the id of its job (the nonce is set by the queue;
+        /// the gas limit and the fees are estimated when it's signed if empty); it's checked
+        /// like the WalletConnect transactions (the fee caps or `common.max_total_fee`,
+        /// the policy and the screening) before it's signed
+        pub fn enqueue(self: &TxQueue, tx: &WalletConnectTxEip155) -> Result<u64>;
+        /// returns the jobs (in the enqueue order)
+        pub fn list_jobs(self: &TxQueue) -> Result<Vec<TxJob>>;
+        pub fn get_job(self: &TxQueue, id: u64) -> Result<TxJob>;
+        /// cancels the job if it's not signed yet
+        pub fn cancel(self: &TxQueue, id: u64) -> Result<()>;
+        /// removes the confirmed, failed and cancelled jobs and returns how many
+        pub fn prune(self: &TxQueue) -> Result<u32>;
+        /// advances the jobs and returns how many changed: the sent ones are checked (and
+        /// broadcast again while their nonce is not mined), the signed ones are broadcast (again, after a restart) and the queued ones are
+        /// signed and broadcast in order; call it periodically. The error of a job is recorded in it
+        /// and the other jobs are advanced: the queued job fails if it's refused (e.g. by the
+        /// checks or the signer), the others are retried by the next call (e.g. the node is
+        /// unreachable), and no job is signed after a signed one that's not broadcast.
+        /// In the dry-run mode, the signed and queued jobs are recorded instead (unchanged)
+        pub fn process_blocking(self: &mut TxQueue) -> Result<u32>;
+
+        /// Tracker of the confirmations and the reorgs of the sent transactions
+        type TxTracker;
+        /// creates a tracker polling the receipts via the JSON-RPC url (the one of the SDK
+        /// environment if empty) every `interval_ms` (at least 1 second) until the
+        /// transactions have `confirmations` (at least 1)
+        pub fn new_tx_tracker(
+            rpc_url: String,
+            confirmations: u64,
+            interval_ms: u64,
+        ) -> Result<Box<TxTracker>>;
+        /// tracks the transaction (its hash) until it's confirmed
+        pub fn track(self: &TxTracker, tx_hash: String) -> Result<()>;
+        /// stops tracking the transaction
+        pub fn untrack(self: &TxTracker, tx_hash: String);
+        /// returns the hashes of the tracked (not yet confirmed) transactions
+        pub fn tracked_transactions(self: &TxTracker) -> Vec<String>;
+        /// starts polling: the callback is called when the confirmations change, when the
+        /// transactions are confirmed and when their blocks are replaced by reorgs
+        /// (it replaces the previous callback)
+        pub fn start(self: &mut TxTracker, callback: UniquePtr<TxTrackerCallback>) -> Result<()>;
+        /// stops polling (the transactions stay tracked)
+        pub fn stop(self: &mut TxTracker);
+        /// whether the tracker is polling
+        pub fn is_running(self: &TxTracker) -> bool;
+
+        /// Checkpointed scanner of contract event logs
+        type LogScanner;
+        /// creates a scanner of the event logs of the contracts (all contracts if empty)
+        /// via the JSON-RPC url (e.g. https://evm.cronos.org); only the events of the ABI JSON
+        /// are scanned and decoded (all events if empty).
+        /// If the checkpoint file exists, the scanning resumes from it (instead of `start_block`),
+        /// and it's updated after each scanned block range (it can be empty for no file)
+        pub fn new_log_scanner(
+            rpc_url: String,
+            abi_json: String,
+            contract_addresses: Vec<String>,
+            start_block: u64,
+            checkpoint_path: String,
+        ) -> Result<Box<LogScanner>>;
+        /// scans the logs up to the latest block and calls the callback for each of them,
+        /// returns the number of the scanned logs (the block ranges rejected by the provider,
+        /// e.g. for too many results, are split)
+        pub fn scan_blocking(self: &mut LogScanner, callback: &LogScannerCallback) -> Result<u64>;
+        /// the next block to be scanned
+        pub fn next_block(self: &LogScanner) -> u64;
+        /// reconstructs the holders of the token contract (ERC-20, ERC-721 or ERC-1155)
+        /// at the block by replaying its transfer logs via the JSON-RPC url from `from_block`
+        /// (e.g. the block of its deployment, 0 if unknown), the largest balance first;
+        /// the callback is called after each scanned block range
+        pub fn snapshot_holders_blocking(
+            rpc_url: String,
+            contract: String,
+            from_block: u64,
+            block_number: u64,
+            callback: &SnapshotProgressCallback,
+        ) -> Result<Vec<SnapshotHolder>>;
+        /// describes the transaction (the JSON of an EIP-1559 transaction request,
+        /// as in `sign_transaction`) before the wallet is prompted: its calldata is decoded
+        /// with the ABI JSON (optional, it can be empty) or as a standard token method
+        /// (ERC-20/721/1155 transfers, approvals and mints)
+        pub fn describe_transaction(
+            tx_json: String,
+            abi_json: String,
+        ) -> Result<TransactionDescription>;
+        /// decodes the log JSON (with `topics` and `data`, e.g. an item of the `eth_getLogs`
+        /// result) with the events of the ABI JSON, returns the JSON object with the event
+        /// `name` and the decoded `params` (integers as decimal strings,
+        /// addresses and bytes as 0x-prefixed hexadecimal strings)
+        pub fn decode_event(abi_json: String, log_json: String) -> Result<String>;
+        /// the JSON Schema of the transaction JSON of `sign_transaction`, `send_transaction`
+        /// and `describe_transaction` (e.g. for the C++ codegen)
+        pub fn transaction_json_schema() -> String;
+        /// validates the transaction JSON (see `transaction_json_schema`), returns its
+        /// canonical form; the error names the invalid field
+        pub fn validate_transaction_json(json: String) -> Result<String>;
+        /// the message catalog: the JSON object of the English templates by the message ids
+        /// (the `{name}` placeholders are the parameters of the messages)
+        pub fn get_message_catalog() -> String;
+        /// the message of the catalog of the error message of an SDK function, read from
+        /// the tag at the end of the SDK errors, e.g. `[error.invalid_address {"address":"0x12"}]`
+        /// ("error.unknown" with the `message` parameter if it has none)
+        pub fn localize_error(error: String) -> LocalizedMessage;
+        /// fills the placeholders of the (translated) template with the parameters JSON,
+        /// e.g. the `params_json` of a localized message
+        pub fn render_message(message_template: String, params_json: String) -> Result<String>;
+
+        /// sets the policy evaluated before any signer signs or sends a transaction
+        /// (and before the messages are signed): the blocked ones fail with a policy violation
+        /// error; the value of a pending transaction counts towards the daily limit
+        pub fn set_transaction_policy(policy: &TransactionPolicy) -> Result<()>;
+        /// removes the transaction policy (all the transactions are allowed)
+        pub fn clear_transaction_policy();
+        /// sets the callback of the transactions blocked by the policy (none if it's null)
+        pub fn set_policy_callback(callback: UniquePtr<PolicyCallback>);
+        /// the value (in the base units of the native coin) of the transactions signed
+        /// or sent today, counted in `max_value_per_day`
+        pub fn policy_spent_today() -> String;
+
+        /// sets the caps of the fees of the WalletConnect transactions: the ones over them
+        /// fail with a fee too high error (unless `max_total_fee` of the transaction is set)
+        pub fn set_fee_caps(caps: &FeeCaps) -> Result<()>;
+        /// removes the fee caps
+        pub fn clear_fee_caps();
+
+        /// sets the dry-run mode (e.g. for the QA builds): the WalletConnect sends
+        /// and `broadcast_raw_tx_blocking` are simulated (via `eth_estimateGas` of the
+        /// JSON-RPC url, unless it's empty) and signed, but they're recorded instead of being
+        /// broadcast, and they return the hash of the transaction that would have been sent
+        /// (the wallets must support `eth_signTransaction`)
+        pub fn set_dry_run(enabled: bool, rpc_url: String);
+        /// the transactions that would have been sent in the dry-run mode (the last 100)
+        pub fn dry_run_results() -> Vec<DryRunResult>;
+        /// removes the recorded dry runs
+        pub fn clear_dry_run_results();
+
+        /// sets the network environment of the SDK in one call: the explorer queries
+        /// use its explorer, the new WalletConnect sessions its bridge server and chain id,
+        /// the WalletConnect transactions of the other chains are refused,
+        /// and its urls are in `sdk_environment_config` (for the per-call url parameters)
+        pub fn set_sdk_environment(environment: SdkEnvironment) -> Result<()>;
+        /// sets a custom network environment of the SDK (see `set_sdk_environment`)
+        pub fn set_sdk_environment_config(config: &SdkEnvironmentConfig) -> Result<()>;
+        /// removes the network environment (the per-call parameters
+        /// and the Cronos mainnet explorer are used)
+        pub fn clear_sdk_environment();
+        /// the config of the network environment (the default one, i.e. no chain id,
+        /// if it's not set)
+        pub fn sdk_environment_config() -> SdkEnvironmentConfig;
+        /// the health of the endpoints of the SDK's HTTP requests (the explorer APIs,
+        /// the faucets, the JSON-RPC nodes...) by host
+        pub fn get_http_endpoint_health() -> Vec<EndpointHealth>;
+        /// the network usage of the SDK by subsystem: the bytes of the urls and the bodies
+        /// of its HTTP requests (the JSON-RPC ones included), of the WalletConnect 1.0
+        /// bridge server messages and of the WalletConnect 2.0 relay messages
+        /// (the headers aren't included)
+        pub fn get_network_usage() -> NetworkUsage;
+        /// resets the network usage (the budget can be exceeded again)
+        pub fn reset_network_usage();
+        /// sets the budget of the network usage: the callback (none if it's null)
+        /// is called once when the usage exceeds it; the requests aren't blocked
+        pub fn set_network_budget(
+            budget: &NetworkBudget,
+            callback: UniquePtr<NetworkBudgetCallback>,
+        );
+        /// removes the budget of the network usage
+        pub fn clear_network_budget();
+
+        /// sets the options of the runtimes created from now on (to be called first,
+        /// before the clients and the other calls create them); the callback (none if it's
+        /// null) is called on each new thread, e.g. to set its priority or its core affinity
+        pub fn set_runtime_config(
+            config: &RuntimeConfig,
+            callback: UniquePtr<RuntimeThreadCallback>,
+        );
+        /// removes the options of the runtimes (the tokio defaults are used)
+        pub fn clear_runtime_config();
+        /// the memory allocated by the SDK, e.g. for the accounting of the console
+        /// certifications (it fails if the SDK is built without the `memory-stats` feature;
+        /// the platform allocator can be plugged in with `play_sdk_set_allocator_hooks`)
+        pub fn get_memory_usage() -> Result<MemoryUsage>;
+        /// sets how long the nonces, the balances and the code presence of the accounts
+        /// are cached (0, the default, disables the cache; e.g. 5000 is about a block
+        /// on Cronos); they're refreshed earlier when a newer block is observed (e.g. by
+        /// the trackers) or the account signs or sends a transaction via the SDK
+        pub fn set_chain_state_cache_max_age(max_age_ms: u64);
+        /// drops the cached account states and resets the statistics
+        pub fn clear_chain_state_cache();
+        /// the hits and the misses of the cache of the account states
+        pub fn get_chain_state_cache_stats() -> ChainStateCacheStats;
+        /// the warnings of the fees of the transaction (e.g. a priority fee over the max fee
+        /// or an unset gas limit), or the fee too high error if it's over the caps
+        pub fn check_transaction_fees(tx: &WalletConnectTxEip155) -> Result<Vec<LocalizedMessage>>;
+
+        /// replaces the local denylist with the JSON array of the addresses
+        /// (strings or `{"address": "0x..", "reason": ".."}` objects),
+        /// returns the number of the denied addresses. The transactions to the denied
+        /// addresses (or transferring tokens to them) are rejected before they're signed or sent
+        pub fn load_denylist(json: String) -> Result<u32>;
+        /// the same as `load_denylist`, but with the JSON array downloaded from the url
+        pub fn update_denylist_blocking(url: String) -> Result<u32>;
+        /// adds the address (or the contract) to the local denylist
+        pub fn add_denied_address(address: String, reason: String);
+        /// removes the address from the local denylist, returns false if it wasn't on it
+        pub fn remove_denied_address(address: String) -> bool;
+        /// sets the remote screening endpoint (empty to disable it): it's queried with
+        /// the `address` query parameter and returns `{"risk": "clean" | "suspicious" | "denied",
+        /// "reason": ".."}`
+        pub fn set_screening_endpoint(url: String);
+        /// screens the address against the local denylist and the remote endpoint
+        /// (a failed remote screening is `Suspicious`)
+        pub fn screen_address_blocking(address: String) -> Result<ScreeningVerdict>;
+
+        /// Persistent labels of the addresses
+        type AddressBook;
+        /// opens the address book persisted in the JSON file
+        /// (it's created on the first change; in memory only if the path is empty)
+        pub fn open_address_book(path: String) -> Result<Box<AddressBook>>;
+        /// adds the entry (or replaces the one of the same chain and address)
+        pub fn add_entry(self: &mut AddressBook, entry: AddressBookEntry) -> Result<()>;
+        /// removes the entry of the chain and address, returns false if there was none
+        pub fn remove_entry(self: &mut AddressBook, chain: String, address: String)
+            -> Result<bool>;
+        /// the name of the address on the chain (empty if it's not in the address book;
+        /// the entries of the chain are preferred to the ones of all the chains)
+        pub fn find_name(self: &AddressBook, chain: String, address: String) -> String;
+        /// all the entries (in the order they were added)
+        pub fn entries(self: &AddressBook) -> Vec<AddressBookEntry>;
+        /// the entries with the tag
+        pub fn entries_with_tag(self: &AddressBook, tag: String) -> Vec<AddressBookEntry>;
+        /// the JSON array of the entries
+        pub fn export_json(self: &AddressBook) -> Result<String>;
+        /// adds the entries of the JSON array (they replace the ones of the same chain
+        /// and address), returns the number of the imported entries
+        pub fn import_json(self: &mut AddressBook, json: String) -> Result<u32>;
+        /// sets the `display_name` of the transactions of the address on the chain
+        /// to the name of their counterparty
+        pub fn label_transactions(
+            self: &AddressBook,
+            chain: String,
+            address: String,
+            txs: Vec<RawTxDetail>,
+        ) -> Vec<RawTxDetail>;
+        /// sets the `display_name` of the token transfers of the address on the chain
+        /// to the name of their counterparty
+        pub fn label_token_transfers(
+            self: &AddressBook,
+            chain: String,
+            address: String,
+            transfers: Vec<RawTokenTxDetail>,
+        ) -> Vec<RawTokenTxDetail>;
+
+        /// Cached account numbers and sequences of Cosmos accounts
+        type CosmosAccountSync;
+        /// creates the cache of the Cosmos account states queried from the LCD (REST) url
+        /// (e.g. https://rest.mainnet.crypto.org)
+        pub fn new_cosmos_account_sync(lcd_url: String) -> Result<Box<CosmosAccountSync>>;
+        /// the same as `new_cosmos_account_sync`, but with the transport of the url
+        /// (the LCD url for `Rest`, the gRPC-web url for `GrpcWeb`)
+        pub fn new_cosmos_account_sync_with_transport(
+            query_url: String,
+            transport: CosmosTransport,
+        ) -> Result<Box<CosmosAccountSync>>;
+        /// returns the account number and the sequence of the next transaction
+        /// (queried if it isn't cached)
+        pub fn account_state_blocking(
+            self: &mut CosmosAccountSync,
+            address: String,
+        ) -> Result<CosmosAccountState>;
+        /// queries the account state again (e.g. after the account was used elsewhere)
+        pub fn refresh_blocking(
+            self: &mut CosmosAccountSync,
+            address: String,
+        ) -> Result<CosmosAccountState>;
+        /// forgets the cached account state
+        pub fn invalidate(self: &mut CosmosAccountSync, address: String);
+        /// simulates the signed transaction (e.g. signed with a placeholder fee)
+        /// via the LCD or gRPC-web url, returns the used gas
+        pub fn simulate_blocking(self: &CosmosAccountSync, signed_tx: Vec<u8>) -> Result<u64>;
+        /// estimates the gas limit and the fee of the signed transaction on the chain
+        /// (e.g. to sign it again with them): the simulated gas is multiplied by the adjustment
+        /// (e.g. 1.3) and the fee is at the gas price of the chain (see `set_cosmos_gas_price`)
+        pub fn estimate_fee_blocking(
+            self: &CosmosAccountSync,
+            chain_id: String,
+            signed_tx: Vec<u8>,
+            gas_adjustment: f64,
+        ) -> Result<CosmosFeeEstimate>;
+        /// sets the gas price (in the denom, e.g. 0.025 basecro) of the chain used in the fee
+        /// estimation (the crypto.org chain mainnet and the croeseid testnets have defaults)
+        pub fn set_cosmos_gas_price(chain_id: String, denom: String, price: f64) -> Result<()>;
+
+        /// returns the delegations of the delegator address queried from the LCD (REST) url
+        /// (e.g. https://rest.mainnet.crypto.org)
+        pub fn get_delegations_blocking(
+            lcd_url: String,
             address: String,
+        ) -> Result<Vec<CosmosDelegation>>;
+        /// returns the undelegations of the delegator address being completed
+        /// (one entry for each undelegation)
+        pub fn get_unbonding_blocking(
+            lcd_url: String,
+            address: String,
+        ) -> Result<Vec<CosmosUnbondingEntry>>;
+        /// returns the pending staking rewards of the delegator address (by the validator)
+        pub fn get_rewards_blocking(lcd_url: String, address: String) -> Result<CosmosRewards>;
+        /// returns the assets of the account: the native EVM coin and its balance,
+        /// the BlockScout tokens and the Cosmos bank balances
+        pub fn get_all_assets_blocking(account: &AssetAccount) -> Result<Vec<Asset>>;
+        /// Server half of the sign-in with a wallet
+        type AuthServer;
+        /// creates the server of the domain (e.g. "game.example.com") minting the session
+        /// tokens signed with the secret (at least 32 bytes): the challenges are valid for
+        /// `challenge_ttl_secs` (5 minutes if 0) and the tokens for `token_ttl_secs`
+        /// (1 day if 0)
+        pub fn new_auth_server(
+            secret: Vec<u8>,
+            domain: String,
+            challenge_ttl_secs: u64,
+            token_ttl_secs: u64,
+        ) -> Result<Box<AuthServer>>;
+        /// issues a challenge to be signed by the address
+        /// (e.g. via `sign_auth_challenge_blocking` on the client)
+        pub fn issue_challenge(self: &AuthServer, address: String) -> Result<AuthChallenge>;
+        /// verifies the signature (65 bytes) of the challenge message and mints the session
+        /// token of its address (a challenge can be verified once)
+        pub fn verify_response(
+            self: &AuthServer,
+            message: String,
+            signature: Vec<u8>,
+        ) -> Result<AuthToken>;
+        /// verifies the session token (its signature, domain and expiry)
+        pub fn verify_token(self: &AuthServer, token: String) -> Result<AuthToken>;
+
+        /// Addresses (EVM and Cosmos) of a player, each proven by a signed challenge
+        type PlayerProfile;
+        /// opens the profile persisted at the path (in memory only if empty)
+        pub fn open_player_profile(profile_id: String, path: String) -> Result<Box<PlayerProfile>>;
+        pub fn profile_id(self: &PlayerProfile) -> String;
+        /// adds the address (unverified) and returns the challenge message to be signed
+        /// with its key: `personal_sign` for an EVM address, ADR-036 (`signArbitrary`)
+        /// for a Cosmos address
+        pub fn add_address(self: &mut PlayerProfile, address: String) -> Result<String>;
+        /// verifies the signature of the challenge of the address: 65 bytes for an EVM
+        /// address; 64 bytes (r || s) and the compressed public key (33 bytes)
+        /// for a Cosmos address (empty for an EVM one)
+        pub fn verify_address(
+            self: &mut PlayerProfile,
+            address: String,
+            signature: Vec<u8>,
+            public_key: Vec<u8>,
+        ) -> Result<()>;
+        /// removes the address, returns false if it wasn't in the profile
+        pub fn remove_address(self: &mut PlayerProfile, address: String) -> Result<bool>;
+        /// returns the addresses of the profile (verified or not)
+        pub fn addresses(self: &PlayerProfile) -> Vec<ProfileAddress>;
+        /// returns the assets of all the verified addresses (see `get_all_assets_blocking`)
+        pub fn get_holdings_blocking(
+            self: &PlayerProfile,
+            endpoints: &ProfileEndpoints,
+        ) -> Result<Vec<ProfileAsset>>;
+        /// returns the transactions of all the verified addresses (newest first): the EVM
+        /// ones via Cronoscan, the Cosmos ones via the LCD url (skipped if it's empty).
+        /// The API key can be obtained from https://cronoscan.com
+        pub fn get_history_blocking(
+            self: &PlayerProfile,
             api_key: String,
+            lcd_url: String,
         ) -> Result<Vec<RawTxDetail>>;
+        /// uploads (and pins) the JSON, e.g. an NFT metadata, named `name` to the IPFS service
+        pub fn ipfs_upload_json_blocking(
+            config: &IpfsConfig,
+            json: String,
+            name: String,
+        ) -> Result<IpfsUpload>;
+        /// uploads (and pins) the file, e.g. an NFT image, to the IPFS service
+        pub fn ipfs_upload_file_blocking(config: &IpfsConfig, path: String) -> Result<IpfsUpload>;
+        /// uploads the image and the metadata JSON (referencing the image) to IPFS
+        /// and builds the mint transaction of the NFT with the metadata uri
+        pub fn mint_nft_with_metadata_blocking(
+            config: &IpfsConfig,
+            request: &NftMintRequest,
+        ) -> Result<NftMint>;
+        /// returns the url of the CID (with an optional path, e.g. "<cid>/1.json")
+        /// on the gateway (the public ipfs.io gateway if it's empty)
+        pub fn ipfs_gateway_url(cid: String, gateway_url: String) -> String;
+        /// returns the gateway url of the `ipfs://` uri (the other urls are returned as they are)
+        pub fn ipfs_uri_to_gateway_url(uri: String, gateway_url: String) -> String;
+        /// downloads the NFT media (e.g. the `image` of the metadata) within the limits
+        /// of the policy and verifies it against the expected hash: the hexadecimal
+        /// SHA-256 digest ("sha256:" or 0x-prefixed too) or the IPFS CID (the CID
+        /// of the `ipfs://` url if empty)
+        pub fn download_verified_media_blocking(
+            url: String,
+            expected_hash: String,
+            policy: &MediaPolicy,
+        ) -> Result<VerifiedMedia>;
+        /// set the disk cache and the rate limit of `fetch_nft_image`
+        pub fn set_nft_image_cache(config: &NftImageCacheConfig) -> Result<()>;
+        /// returns the bytes of the NFT image of the url or the `ipfs://` uri (up to `max_bytes`,
+        /// 0 for no limit), e.g. for the texture loading: the images are cached on disk
+        /// (the `ipfs://` ones are immutable, the others are revalidated with their ETag),
+        /// the requests are rate-limited and the IPFS gateways are rotated
+        pub fn fetch_nft_image(url_or_ipfs: String, max_bytes: u64) -> Result<Vec<u8>>;
+        /// verifies the data against the expected SHA-256 digest or IPFS CID (of a raw
+        /// block or a single-block UnixFS file, i.e. up to 256 KiB); the CIDs of the chunked
+        /// files fail as an unsupported media hash (not as a mismatch)
+        pub fn verify_media_hash(data: Vec<u8>, expected_hash: String) -> Result<()>;
+        /// requests the test coins to the address from the faucet url (e.g. of the Cronos
+        /// testnet) with the captcha token (passed through if not empty); a rate-limited
+        /// request is not funded and tells when to retry
+        /// (the faucet of the SDK environment if `faucet_url` is empty)
+        pub fn request_testnet_funds_blocking(
+            faucet_url: String,
+            address: String,
+            captcha_token: String,
+        ) -> Result<FaucetResponse>;
+        /// set the fiat on-ramp providers of `get_onramp_quote_blocking`
+        pub fn set_onramp_providers(providers: Vec<OnRampProvider>) -> Result<()>;
+        /// returns the quotes of buying the crypto (e.g. "CRO") with the amount of the fiat
+        /// currency (e.g. "USD", "100") in the region (the ISO 3166-1 alpha-2 country code,
+        /// or empty) from the on-ramp providers, the most crypto first (the providers not
+        /// selling in the region are skipped)
+        pub fn get_onramp_quote_blocking(
+            fiat: String,
+            crypto: String,
+            amount: String,
+            region: String,
+        ) -> Result<Vec<OnRampQuote>>;
+        /// returns the url of the provider's widget buying the quote to the wallet address
+        pub fn onramp_redirect_url(quote: &OnRampQuote, wallet_address: String) -> Result<String>;
+        /// returns the stats of the NFT collection (its id or contract address, depending
+        /// on the marketplace): floor price, volume and recent sales
+        pub fn get_nft_collection_stats_blocking(
+            source: &NftStatsSource,
+            collection: String,
+        ) -> Result<NftCollectionStats>;
+        /// returns the router address of the DEX on Cronos mainnet
+        pub fn dex_router_address(router: DexRouter) -> Result<String>;
+        /// quotes the swap of `amount_in` (in the base units of the first token of the path)
+        /// with `getAmountsOut` of the router via the JSON-RPC url; the price impact
+        /// is computed from the reserves of the pairs of the path
+        pub fn get_swap_quote_blocking(
+            rpc_url: String,
+            router: String,
+            path: Vec<String>,
+            amount_in: String,
+        ) -> Result<SwapQuote>;
+        /// returns the liquidity position of the account in the UniswapV2-style pair
+        /// (the LP token contract) via the JSON-RPC url: the underlying amounts
+        /// of its LP tokens (from the reserves) and its share of the pool
+        pub fn get_liquidity_position_blocking(
+            rpc_url: String,
+            pair: String,
+            account: String,
+        ) -> Result<LiquidityPosition>;
+        /// returns the minimum output of the swap with the tolerated slippage (in basis points)
+        pub fn swap_amount_out_min(amount_out: String, slippage_bps: u32) -> Result<String>;
+        /// builds the swap transaction of the quote (`swapExactTokensForTokens`,
+        /// `swapExactETHForTokens` or `swapExactTokensForETH`) with the minimum output
+        /// of the slippage and the deadline; it's signed or sent with
+        /// `sign_eip155_transaction_blocking` or `send_eip155_transaction_blocking`
+        /// (after the sold token is approved to the router with `send_erc20_approval`)
+        pub fn build_swap_transaction(
+            quote: &SwapQuote,
+            options: &SwapOptions,
+        ) -> Result<WalletConnectTxEip155>;
+        /// checks whether the address holds the assets of the requirements
+        /// via the JSON-RPC url: a verdict per requirement, in the same order
+        pub fn check_entitlements_blocking(
+            rpc_url: String,
+            address: String,
+            requirements: Vec<AssetRequirement>,
+        ) -> Result<Vec<EntitlementVerdict>>;
+        /// returns the address of the canonical WCRO contract of the chain (Cronos mainnet)
+        pub fn wcro_address(chain_id: u64) -> Result<String>;
+        /// builds the transaction wrapping the amount of CRO (in the base units) into WCRO:
+        /// `deposit` with the amount as the value
+        pub fn build_wrap_transaction(
+            chain_id: u64,
+            amount: String,
+        ) -> Result<WalletConnectTxEip155>;
+        /// builds the transaction unwrapping the amount of WCRO (in the base units) into CRO:
+        /// `withdraw` without a value
+        pub fn build_unwrap_transaction(
+            chain_id: u64,
+            amount: String,
+        ) -> Result<WalletConnectTxEip155>;
+        /// broadcasts the transaction signed by the account via the Tendermint RPC url
+        /// (`broadcast_tx_sync`; or the gRPC-web url of the `GrpcWeb` transport),
+        /// returns the transaction hash; the cached sequence is increased
+        /// if it's accepted (or forgotten if it's rejected for the wrong sequence)
+        pub fn broadcast_blocking(
+            self: &mut CosmosAccountSync,
+            broadcast_url: String,
+            address: String,
+            signed_tx: Vec<u8>,
+        ) -> Result<String>;
+
+        /// The Merkle tree of the claims of an airdrop (MerkleDistributor-compatible)
+        type AirdropTree;
+        /// builds the tree of the recipients (their indices are in the order of the vector)
+        pub fn new_airdrop_tree(recipients: Vec<AirdropRecipient>) -> Result<Box<AirdropTree>>;
+        /// the Merkle root (hexadecimal, 0x-prefixed) to deploy the distributor contract with
+        pub fn root(self: &AirdropTree) -> String;
+        /// the claim of the address (it fails if it's not a recipient)
+        pub fn claim(self: &AirdropTree, address: String) -> Result<AirdropClaim>;
+        /// the claims of all the recipients (by index)
+        pub fn claims(self: &AirdropTree) -> Vec<AirdropClaim>;
+        /// the JSON of the root, the total and the claims by address (to be published
+        /// for the recipients, like the output of the Uniswap `parse-balance-map` script)
+        pub fn export_json(self: &AirdropTree) -> Result<String>;
+        /// whether the claim (its index, address, amount and proof) is in the tree of the root
+        pub fn verify_airdrop_claim(root: String, claim: &AirdropClaim) -> Result<bool>;
+        /// builds the transaction claiming the airdrop from the distributor contract (`claim`);
+        /// anyone can send it, the tokens go to the address of the claim
+        pub fn build_airdrop_claim_transaction(
+            chain_id: u64,
+            distributor: String,
+            claim: &AirdropClaim,
+        ) -> Result<WalletConnectTxEip155>;
+        /// whether the claim of the index was made on the distributor contract
+        /// via the JSON-RPC url (`isClaimed`)
+        pub fn is_airdrop_claimed_blocking(
+            rpc_url: String,
+            distributor: String,
+            index: u64,
+        ) -> Result<bool>;
+
         /// returns the ERC20 transfers of a given address of a given contract.
         /// (address can be empty if option is ByContract)
         /// default option is by address
@@ -384,7 +3212,24 @@ mod ffi {
             contract_address: String,
             option: QueryOption,
             api_key: String,
-        ) -> Result<Vec<RawTxDetail>>;
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// the same as `get_erc20_transfer_history_blocking`,
+        /// but in the block range, page and order of the options
+        pub fn get_erc20_transfer_history_with_options_blocking(
+            address: String,
+            contract_address: String,
+            option: QueryOption,
+            options: &TxListOptions,
+            api_key: String,
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// returns the ERC20 transfers of the address in the block range, page and order
+        /// of the options matching the filter
+        pub fn get_erc20_transfer_history_filtered_blocking(
+            address: String,
+            options: &TxListOptions,
+            filter: &QueryFilter,
+            api_key: String,
+        ) -> Result<Vec<RawTokenTxDetail>>;
         /// returns the ERC721 transfers of a given address of a given contract.
         /// (address can be empty if option is ByContract)
         /// default option is by address
@@ -394,7 +3239,16 @@ mod ffi {
             contract_address: String,
             option: QueryOption,
             api_key: String,
-        ) -> Result<Vec<RawTxDetail>>;
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// the same as `get_erc721_transfer_history_blocking`,
+        /// but in the block range, page and order of the options
+        pub fn get_erc721_transfer_history_with_options_blocking(
+            address: String,
+            contract_address: String,
+            option: QueryOption,
+            options: &TxListOptions,
+            api_key: String,
+        ) -> Result<Vec<RawTokenTxDetail>>;
         /// given the BlockScout REST API base url and the account address (hexadecimal),
         /// it will return the list of all owned tokens
         /// (ref: https://cronos.org/explorer/testnet3/api-docs)
@@ -402,6 +3256,15 @@ mod ffi {
             blockscout_base_url: String,
             account_address: String,
         ) -> Result<Vec<RawTokenResult>>;
+        /// given the BlockScout REST API base url and the account addresses (hexadecimal),
+        /// it will return the list of owned tokens for each address (in the same order).
+        /// At most `concurrency` requests are sent at the same time.
+        /// A failed query does not fail the others: its error is in `AddressTokens::error`.
+        pub fn get_tokens_bulk_blocking(
+            blockscout_base_url: String,
+            addresses: Vec<String>,
+            concurrency: u32,
+        ) -> Result<Vec<AddressTokens>>;
         /// given the BlockScout REST API base url and the account address (hexadecimal; required)
         /// and optional contract address (hexadecimal; optional -- it can be empty if the option is ByAddress),
         /// it will return all the token transfers (ERC20, ERC721... in the newer BlockScout
@@ -413,7 +3276,40 @@ mod ffi {
             address: String,
             contract_address: String,
             option: QueryOption,
-        ) -> Result<Vec<RawTxDetail>>;
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// the same as `get_token_transfers_blocking`, but it also returns
+        /// the explorer that produced the transfers (see `set_explorer_failover_policy`)
+        pub fn get_token_transfers_with_source_blocking(
+            blockscout_base_url: String,
+            address: String,
+            contract_address: String,
+            option: QueryOption,
+        ) -> Result<TokenTransfersResult>;
+        /// the same as `get_token_transfers_blocking`,
+        /// but in the block range, page and order of the options
+        pub fn get_token_transfers_with_options_blocking(
+            blockscout_base_url: String,
+            address: String,
+            contract_address: String,
+            option: QueryOption,
+            options: &TxListOptions,
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// returns the token transfers of the address in the block range, page and order
+        /// of the options matching the filter (see `get_token_transfers_blocking`)
+        pub fn get_token_transfers_filtered_blocking(
+            blockscout_base_url: String,
+            address: String,
+            options: &TxListOptions,
+            filter: &QueryFilter,
+        ) -> Result<Vec<RawTokenTxDetail>>;
+        /// the same as `get_token_transfers_blocking` (`ByAddressAndContract`) for each
+        /// of the contracts, queried in parallel (a few at the same time); the transfers
+        /// are merged in the block order (the duplicated contracts are queried once)
+        pub fn get_token_transfers_multi_blocking(
+            blockscout_base_url: String,
+            address: String,
+            contract_addresses: Vec<String>,
+        ) -> Result<Vec<RawTokenTxDetail>>;
         /// given the BlockScout REST API base url and the contract address (hexadecimal),
         ///
         /// page: A nonnegative integer that represents the page number to be used for
@@ -429,12 +3325,34 @@ mod ffi {
         ///
         /// ::TIPS:: Use another functions to get more token/owner details, e.g.
         /// `get_tokens_blocking` to get owned tokens by account_address
+        pub fn get_token_holders_blocking(
+            blockscout_base_url: String,
+            contract_address: String,
+            page: u64,
+            offset: u64,
+        ) -> Result<Vec<TokenHolderDetail>>;
+        /// the same as `get_token_holders_blocking`
+        /// (deprecated: kept for compatibility, use `get_token_holders_blocking`)
         pub fn get_token_holders(
             blockscout_base_url: String,
             contract_address: String,
             page: u64,
             offset: u64,
         ) -> Result<Vec<TokenHolderDetail>>;
+        /// given the JSON-RPC url (e.g. https://evm.cronos.org) and the address (hexadecimal),
+        /// it returns whether there is a contract at the address (via `eth_getCode`),
+        /// i.e. it's not an externally owned account (cached, see `set_chain_state_cache_max_age`)
+        pub fn is_contract_blocking(rpc_url: String, address: String) -> Result<bool>;
+        /// given the JSON-RPC url (e.g. https://evm.cronos.org) and the address (hexadecimal),
+        /// it returns the number of transactions sent from the address
+        /// (via `eth_getTransactionCount`; 0 for a fresh account; cached,
+        /// see `set_chain_state_cache_max_age`)
+        pub fn get_tx_count_blocking(rpc_url: String, address: String) -> Result<u64>;
+        /// given the JSON-RPC url (e.g. https://evm.cronos.org), it broadcasts the signed
+        /// raw transaction (e.g. of `wallet_sign_transaction_blocking`)
+        /// via `eth_sendRawTransaction`, returns its hash (hexadecimal, 0x-prefixed);
+        /// in the dry-run mode, it's only simulated and recorded (see `set_dry_run`)
+        pub fn broadcast_raw_tx_blocking(rpc_url: String, raw_tx: Vec<u8>) -> Result<String>;
         /// it creates the payment object
         /// https://pay-docs.crypto.com/#api-reference-resources-payments-create-a-payment
         /// This API can be called using either your Secret Key or Publishable Key.
@@ -452,32 +3370,739 @@ mod ffi {
             secret_or_publishable_api_key: String,
             payment_id: String,
         ) -> Result<CryptoComPaymentResponse>;
+        /// it verifies the `Pay-Signature` header of the webhook callback (the raw request body
+        /// as the payload) with the webhook signature secret and parses the event
+        /// https://pay-docs.crypto.com/#api-reference-webhooks
+        /// The timestamp of the signature must be within `tolerance_secs` (5 minutes if 0).
+        pub fn verify_crypto_pay_webhook(
+            payload: String,
+            signature_header: String,
+            webhook_secret: String,
+            tolerance_secs: u64,
+        ) -> Result<CryptoPayWebhookEvent>;
+        /// it parses the payload of the webhook callback (without verifying it)
+        pub fn parse_crypto_pay_webhook_event(payload: String) -> Result<CryptoPayWebhookEvent>;
+    }
+
+    // C++ types and signatures exposed to Rust.
+    unsafe extern "C++" {
+        include!("extra-cpp-bindings/include/pay.h");
+
+        type OptionalArguments;
+        fn get_description(&self) -> &str;
+        fn get_metadata(&self) -> &str;
+        fn get_order_id(&self) -> &str;
+        fn get_return_url(&self) -> &str;
+        fn get_cancel_url(&self) -> &str;
+        fn get_sub_merchant_id(&self) -> &str;
+        fn get_onchain_allowed(&self) -> bool;
+        fn get_expired_at(&self) -> u64;
     }
+}
+
+/// the token transfers of the address in the range, page and order of the options
+/// that match the filter (the explorer filters by the contract if there's only one,
+/// and by the blocks of the time range)
+pub fn get_token_transfers_filtered_blocking(
+    blockscout_base_url: String,
+    address: String,
+    options: &ffi::TxListOptions,
+    filter: &ffi::QueryFilter,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let filter = queryfilter::Filter::new(&address, filter)?;
+    let explorer = queryfilter::Explorer::BlockScout(&blockscout_base_url);
+    let Some(options) = runtime::new_runtime()?.block_on(filter.block_range(options, explorer))
+    else {
+        return Ok(vec![]);
+    };
+    let (contract_address, option) = match filter.single_contract() {
+        Some(contract_address) => (contract_address, QueryOption::ByAddressAndContract),
+        None => (String::default(), QueryOption::ByAddress),
+    };
+    let transfers = get_token_transfers_with_options_blocking(
+        blockscout_base_url,
+        address,
+        contract_address,
+        option,
+        &options,
+    )?;
+    Ok(filter.apply(transfers))
+}
+
+/// returns the transactions of a given address.
+/// The API key can be obtained from https://cronoscan.com
+pub fn get_transaction_history_blocking(
+    address: String,
+    api_key: String,
+) -> Result<Vec<RawTxDetail>> {
+    Ok(get_transaction_history_with_source_blocking(address, api_key)?.transactions)
+}
+
+/// returns the transactions of a given address
+/// and the explorer that produced them (see `set_explorer_failover_policy`).
+/// The API key can be obtained from https://cronoscan.com
+pub fn get_transaction_history_with_source_blocking(
+    address: String,
+    api_key: String,
+) -> Result<ffi::TransactionHistoryResult> {
+    let rt = runtime::new_runtime()?;
+    let (source, transactions) = rt.block_on(async move {
+        failover::transaction_history(&address, TxListParams::default(), api_key).await
+    })?;
+    Ok(ffi::TransactionHistoryResult {
+        source,
+        transactions,
+    })
+}
+
+/// returns the transactions of a given address in the block range, page and order
+/// of the options.
+/// The API key can be obtained from https://cronoscan.com
+pub fn get_transaction_history_with_options_blocking(
+    address: String,
+    options: &ffi::TxListOptions,
+    api_key: String,
+) -> Result<Vec<RawTxDetail>> {
+    let params = options.into();
+    let rt = runtime::new_runtime()?;
+    let (_, transactions) =
+        rt.block_on(async move { failover::transaction_history(&address, params, api_key).await })?;
+    Ok(transactions)
+}
+
+/// the transactions of the address in the range, page and order of the options
+/// that match the filter (the explorer filters by the blocks of the time range)
+pub fn get_transaction_history_filtered_blocking(
+    address: String,
+    options: &ffi::TxListOptions,
+    filter: &ffi::QueryFilter,
+    api_key: String,
+) -> Result<Vec<RawTxDetail>> {
+    let filter = queryfilter::Filter::new(&address, filter)?;
+    let explorer = queryfilter::Explorer::Cronoscan(&api_key);
+    let Some(options) = runtime::new_runtime()?.block_on(filter.block_range(options, explorer))
+    else {
+        return Ok(vec![]);
+    };
+    let transactions = get_transaction_history_with_options_blocking(address, &options, api_key)?;
+    Ok(filter.apply(transactions))
+}
+
+/// returns the gas usage of the transactions sent by the address by contract and method.
+/// The API key can be obtained from https://cronoscan.com
+pub fn get_gas_report_blocking(
+    address: String,
+    options: &ffi::TxListOptions,
+    abi_json: String,
+    api_key: String,
+) -> Result<Vec<ffi::MethodGasUsage>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(gasreport::gas_report(
+        &address,
+        options.into(),
+        &abi_json,
+        api_key,
+    ))
+}
+
+/// set the failover policy of the explorer queries
+pub fn set_explorer_failover_policy(policy: &ffi::ExplorerFailoverPolicy) -> Result<()> {
+    failover::set_policy(policy);
+    Ok(())
+}
+
+/// returns a cursor over the transactions of a given address (oldest first),
+/// so that large histories can be iterated in bounded memory.
+/// The API key can be obtained from https://cronoscan.com
+pub fn open_history_cursor(address: String, api_key: String) -> Result<Box<HistoryCursor>> {
+    Ok(Box::new(HistoryCursor::new(&address, api_key)?))
+}
+
+/// spawns a local Anvil node for the integration tests
+#[cfg(feature = "test-utils")]
+pub fn spawn_local_node(config: &ffi::LocalNodeConfig) -> Result<Box<LocalNode>> {
+    Ok(Box::new(LocalNode::spawn(config)?))
+}
+
+/// derives the deterministic dev accounts from the mnemonic
+#[cfg(feature = "dev-wallet")]
+pub fn new_dev_wallet(mnemonic: String, count: u32, chain_id: u64) -> Result<Box<DevWallet>> {
+    let chain_id = environment::chain_id(chain_id)?;
+    Ok(Box::new(DevWallet::new(&mnemonic, count, chain_id)?))
+}
+
+/// creates the watch-only wallet of the address
+pub fn new_watch_wallet(address: String) -> Result<Box<WatchWallet>> {
+    Ok(Box::new(WatchWallet::new(&address)?))
+}
+
+/// returns the Ledgers connected over USB
+pub fn list_ledger_devices() -> Result<Vec<ffi::LedgerDevice>> {
+    ledger::list_devices()
+}
+
+/// opens the Ledger of the HID path (the first connected one if empty)
+pub fn open_ledger_wallet(device_path: String, chain_id: u64) -> Result<Box<LedgerWallet>> {
+    Ok(Box::new(LedgerWallet::open(&device_path, chain_id)?))
+}
+
+/// creates a watcher polling Cronoscan every `interval_ms` (at least 1 second)
+/// for the transactions and token transfers of the addresses.
+/// The API key can be obtained from https://cronoscan.com
+pub fn new_address_watcher(
+    addresses: Vec<String>,
+    api_key: String,
+    interval_ms: u64,
+) -> Result<Box<AddressWatcher>> {
+    Ok(Box::new(AddressWatcher::new(
+        &addresses,
+        api_key,
+        interval_ms,
+    )?))
+}
+
+/// creates a service refreshing the exchange rates of the pairs every `interval_ms`
+pub fn new_rate_service(
+    pairs: Vec<String>,
+    interval_ms: u64,
+    api_url: String,
+) -> Result<Box<RateService>> {
+    Ok(Box::new(RateService::new(&pairs, interval_ms, api_url)?))
+}
+
+/// opens the durable transaction queue of the custodial wallet
+pub fn open_tx_queue(
+    path: String,
+    rpc_url: String,
+    private_key: Vec<u8>,
+    chain_id: u64,
+) -> Result<Box<TxQueue>> {
+    let wallet = signer::local_wallet(&private_key)?;
+    Ok(Box::new(TxQueue::open(
+        path,
+        &rpc_url,
+        Box::new(wallet),
+        chain_id,
+    )?))
+}
+
+/// opens the durable transaction queue signed by the external signer
+pub fn open_tx_queue_with_external_signer(
+    path: String,
+    rpc_url: String,
+    address: String,
+    chain_id: u64,
+    signer: cxx::UniquePtr<ffi::ExternalSignerCallback>,
+) -> Result<Box<TxQueue>> {
+    let signer = ExternalSigner::new(&address, signer)?;
+    Ok(Box::new(TxQueue::open(
+        path,
+        &rpc_url,
+        Box::new(signer),
+        chain_id,
+    )?))
+}
+
+/// opens the durable transaction queue signed by the cloud KMS key
+#[cfg(feature = "kms")]
+pub fn open_tx_queue_with_kms(
+    path: String,
+    rpc_url: String,
+    kms: &ffi::KmsConfig,
+    chain_id: u64,
+) -> Result<Box<TxQueue>> {
+    let signer = KmsSigner::new(kms)?;
+    Ok(Box::new(TxQueue::open(
+        path,
+        &rpc_url,
+        Box::new(signer),
+        chain_id,
+    )?))
+}
+
+/// returns the address of the cloud KMS key (e.g. to fund it)
+#[cfg(feature = "kms")]
+pub fn get_kms_address(kms: &ffi::KmsConfig) -> Result<String> {
+    Ok(format!(
+        "{:?}",
+        signer::Signer::address(&KmsSigner::new(kms)?)
+    ))
+}
+
+/// opens the durable transaction queue signed by the threshold (MPC) key
+pub fn open_tx_queue_with_mpc(
+    path: String,
+    rpc_url: String,
+    address: String,
+    chain_id: u64,
+    party: cxx::UniquePtr<ffi::MpcPartyCallback>,
+    transport: cxx::UniquePtr<ffi::MpcTransportCallback>,
+) -> Result<Box<TxQueue>> {
+    let signer = MpcSigner::new(&address, party, transport)?;
+    Ok(Box::new(TxQueue::open(
+        path,
+        &rpc_url,
+        Box::new(signer),
+        chain_id,
+    )?))
+}
+
+/// creates a tracker of the confirmations and the reorgs of the transactions
+/// via the JSON-RPC url
+pub fn new_tx_tracker(
+    rpc_url: String,
+    confirmations: u64,
+    interval_ms: u64,
+) -> Result<Box<TxTracker>> {
+    Ok(Box::new(TxTracker::new(
+        &environment::rpc_url(&rpc_url)?,
+        confirmations,
+        interval_ms,
+    )?))
+}
+
+/// creates a scanner of the event logs of the contracts via the JSON-RPC url
+pub fn new_log_scanner(
+    rpc_url: String,
+    abi_json: String,
+    contract_addresses: Vec<String>,
+    start_block: u64,
+    checkpoint_path: String,
+) -> Result<Box<LogScanner>> {
+    Ok(Box::new(LogScanner::new(
+        &rpc_url,
+        &abi_json,
+        &contract_addresses,
+        start_block,
+        checkpoint_path,
+    )?))
+}
+
+/// describes the transaction request JSON (decoded with the optional ABI JSON)
+pub fn describe_transaction(
+    tx_json: String,
+    abi_json: String,
+) -> Result<ffi::TransactionDescription> {
+    preview::describe_transaction(&tx_json, &abi_json)
+}
+
+/// decodes the log JSON with the events of the ABI JSON
+pub fn decode_event(abi_json: String, log_json: String) -> Result<String> {
+    abi::decode_event(&abi_json, &log_json)
+}
+
+/// the JSON object of the English templates by the message ids
+pub fn get_message_catalog() -> String {
+    messages::catalog_json()
+}
+
+/// the message of the catalog of the error message
+pub fn localize_error(error: String) -> ffi::LocalizedMessage {
+    messages::localize_error(&error)
+}
+
+/// fills the placeholders of the template with the parameters JSON
+pub fn render_message(message_template: String, params_json: String) -> Result<String> {
+    messages::render_message(&message_template, &params_json)
+}
+
+/// sets the policy evaluated before any signer signs or sends a transaction
+pub fn set_transaction_policy(policy: &ffi::TransactionPolicy) -> Result<()> {
+    policy::set_policy(policy)
+}
+
+/// removes the transaction policy
+pub fn clear_transaction_policy() {
+    policy::clear_policy()
+}
+
+/// sets the callback of the transactions blocked by the policy
+pub fn set_policy_callback(callback: cxx::UniquePtr<ffi::PolicyCallback>) {
+    policy::set_callback(callback)
+}
+
+/// the value of the transactions signed or sent today
+pub fn policy_spent_today() -> String {
+    policy::spent_today()
+}
+
+/// sets the caps of the fees of the WalletConnect transactions
+pub fn set_fee_caps(caps: &ffi::FeeCaps) -> Result<()> {
+    feecap::set_fee_caps(caps)
+}
+
+/// removes the fee caps
+pub fn clear_fee_caps() {
+    feecap::clear_fee_caps()
+}
+
+/// sets the dry-run mode of the sends
+pub fn set_dry_run(enabled: bool, rpc_url: String) {
+    dryrun::set_dry_run(enabled, rpc_url)
+}
+
+/// the transactions that would have been sent in the dry-run mode
+pub fn dry_run_results() -> Vec<ffi::DryRunResult> {
+    dryrun::results()
+}
+
+/// removes the recorded dry runs
+pub fn clear_dry_run_results() {
+    dryrun::clear_results()
+}
+
+/// sets the network environment of the SDK
+pub fn set_sdk_environment(environment: ffi::SdkEnvironment) -> Result<()> {
+    environment::set_environment(environment)
+}
+
+/// sets a custom network environment of the SDK
+pub fn set_sdk_environment_config(config: &ffi::SdkEnvironmentConfig) -> Result<()> {
+    environment::set_config(config)
+}
+
+/// removes the network environment of the SDK
+pub fn clear_sdk_environment() {
+    environment::clear_environment()
+}
+
+/// the config of the network environment of the SDK
+pub fn sdk_environment_config() -> ffi::SdkEnvironmentConfig {
+    environment::config().unwrap_or_default()
+}
+
+/// the health of the endpoints of the SDK's HTTP requests
+pub fn get_http_endpoint_health() -> Vec<ffi::EndpointHealth> {
+    http::endpoint_health()
+}
+
+/// the network usage of the SDK by subsystem
+pub fn get_network_usage() -> ffi::NetworkUsage {
+    netusage::usage()
+}
+
+/// resets the network usage of the SDK
+pub fn reset_network_usage() {
+    netusage::reset_usage()
+}
+
+/// sets the budget of the network usage
+pub fn set_network_budget(
+    budget: &ffi::NetworkBudget,
+    callback: cxx::UniquePtr<ffi::NetworkBudgetCallback>,
+) {
+    netusage::set_budget(budget, callback)
+}
+
+/// removes the budget of the network usage
+pub fn clear_network_budget() {
+    netusage::clear_budget()
+}
+
+/// sets the options of the SDK's runtimes
+pub fn set_runtime_config(
+    config: &ffi::RuntimeConfig,
+    callback: cxx::UniquePtr<ffi::RuntimeThreadCallback>,
+) {
+    runtime::set_config(config, callback)
+}
+
+/// removes the options of the SDK's runtimes
+pub fn clear_runtime_config() {
+    runtime::clear_config()
+}
+
+/// the memory allocated by the SDK
+pub fn get_memory_usage() -> Result<ffi::MemoryUsage> {
+    memory::usage()
+}
+
+/// sets the max age of the cached account states
+pub fn set_chain_state_cache_max_age(max_age_ms: u64) {
+    chainstate::set_max_age(std::time::Duration::from_millis(max_age_ms))
+}
+
+/// drops the cached account states
+pub fn clear_chain_state_cache() {
+    chainstate::clear()
+}
+
+/// the statistics of the cache of the account states
+pub fn get_chain_state_cache_stats() -> ffi::ChainStateCacheStats {
+    chainstate::stats()
+}
+
+/// replaces the local denylist with the JSON array of the addresses
+pub fn load_denylist(json: String) -> Result<u32> {
+    screening::load_denylist(&json)
+}
+
+/// replaces the local denylist with the JSON array downloaded from the url
+pub fn update_denylist_blocking(url: String) -> Result<u32> {
+    screening::update_denylist(&url)
+}
+
+/// adds the address to the local denylist
+pub fn add_denied_address(address: String, reason: String) {
+    screening::add_denied_address(&address, reason)
+}
+
+/// removes the address from the local denylist
+pub fn remove_denied_address(address: String) -> bool {
+    screening::remove_denied_address(&address)
+}
+
+/// sets the remote screening endpoint (empty to disable it)
+pub fn set_screening_endpoint(url: String) {
+    screening::set_screening_endpoint(url)
+}
+
+/// screens the address against the local denylist and the remote endpoint
+pub fn screen_address_blocking(address: String) -> Result<ffi::ScreeningVerdict> {
+    let rt = runtime::new_runtime()?;
+    Ok(rt.block_on(screening::screen_address(&address)))
+}
+
+/// opens the address book persisted in the JSON file (in memory only if the path is empty)
+pub fn open_address_book(path: String) -> Result<Box<AddressBook>> {
+    Ok(Box::new(AddressBook::open(path)?))
+}
+
+/// creates the cache of the Cosmos account states queried from the LCD url
+pub fn new_cosmos_account_sync(lcd_url: String) -> Result<Box<CosmosAccountSync>> {
+    new_cosmos_account_sync_with_transport(lcd_url, CosmosTransport::Rest)
+}
+
+/// sets the gas price of the chain used in the Cosmos fee estimation
+pub fn set_cosmos_gas_price(chain_id: String, denom: String, price: f64) -> Result<()> {
+    cosmos::set_gas_price(chain_id, denom, price)
+}
+
+/// returns the delegations of the delegator address
+pub fn get_delegations_blocking(
+    lcd_url: String,
+    address: String,
+) -> Result<Vec<ffi::CosmosDelegation>> {
+    staking::get_delegations(&lcd_url, &address)
+}
+
+/// returns the undelegations of the delegator address being completed
+pub fn get_unbonding_blocking(
+    lcd_url: String,
+    address: String,
+) -> Result<Vec<ffi::CosmosUnbondingEntry>> {
+    staking::get_unbonding(&lcd_url, &address)
+}
+
+/// returns the pending staking rewards of the delegator address
+pub fn get_rewards_blocking(lcd_url: String, address: String) -> Result<ffi::CosmosRewards> {
+    staking::get_rewards(&lcd_url, &address)
+}
+
+/// returns the assets of the account (the EVM and Cosmos ones)
+pub fn get_all_assets_blocking(account: &ffi::AssetAccount) -> Result<Vec<ffi::Asset>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(asset::get_all_assets(account))
+}
+
+/// creates the server half of the sign-in with a wallet
+pub fn new_auth_server(
+    secret: Vec<u8>,
+    domain: String,
+    challenge_ttl_secs: u64,
+    token_ttl_secs: u64,
+) -> Result<Box<AuthServer>> {
+    Ok(Box::new(AuthServer::new(
+        secret,
+        domain,
+        challenge_ttl_secs,
+        token_ttl_secs,
+    )?))
+}
+
+/// opens the player profile persisted at the path
+pub fn open_player_profile(profile_id: String, path: String) -> Result<Box<PlayerProfile>> {
+    Ok(Box::new(PlayerProfile::open(profile_id, path)?))
+}
+
+/// uploads the JSON to the IPFS service
+pub fn ipfs_upload_json_blocking(
+    config: &ffi::IpfsConfig,
+    json: String,
+    name: String,
+) -> Result<ffi::IpfsUpload> {
+    ipfs::upload_json(config, &json, &name)
+}
+
+/// uploads the file to the IPFS service
+pub fn ipfs_upload_file_blocking(
+    config: &ffi::IpfsConfig,
+    path: String,
+) -> Result<ffi::IpfsUpload> {
+    ipfs::upload_file(config, &path)
+}
+
+/// uploads the NFT metadata to IPFS and builds the mint transaction
+pub fn mint_nft_with_metadata_blocking(
+    config: &ffi::IpfsConfig,
+    request: &ffi::NftMintRequest,
+) -> Result<ffi::NftMint> {
+    mint::mint_nft_with_metadata(config, request)
+}
+
+/// downloads the NFT media and verifies it against the expected hash
+pub fn download_verified_media_blocking(
+    url: String,
+    expected_hash: String,
+    policy: &ffi::MediaPolicy,
+) -> Result<ffi::VerifiedMedia> {
+    media::download_verified_media(&url, &expected_hash, policy)
+}
+
+/// set the disk cache and the rate limit of the NFT images
+pub fn set_nft_image_cache(config: &ffi::NftImageCacheConfig) -> Result<()> {
+    imagecache::set_config(config)
+}
 
-    // C++ types and signatures exposed to Rust.
-    unsafe extern "C++" {
-        include!("extra-cpp-bindings/include/pay.h");
+/// returns the bytes of the NFT image (cached on disk)
+pub fn fetch_nft_image(url_or_ipfs: String, max_bytes: u64) -> Result<Vec<u8>> {
+    imagecache::fetch_nft_image(&url_or_ipfs, max_bytes)
+}
 
-        type OptionalArguments;
-        fn get_description(&self) -> &str;
-        fn get_metadata(&self) -> &str;
-        fn get_order_id(&self) -> &str;
-        fn get_return_url(&self) -> &str;
-        fn get_cancel_url(&self) -> &str;
-        fn get_sub_merchant_id(&self) -> &str;
-        fn get_onchain_allowed(&self) -> bool;
-        fn get_expired_at(&self) -> u64;
-    }
+/// requests the test coins to the address from the faucet
+pub fn request_testnet_funds_blocking(
+    faucet_url: String,
+    address: String,
+    captcha_token: String,
+) -> Result<ffi::FaucetResponse> {
+    let faucet_url = environment::faucet_url(&faucet_url)?;
+    faucet::request_testnet_funds(&faucet_url, &address, &captcha_token)
 }
 
-/// returns the transactions of a given address.
+/// set the fiat on-ramp providers
+pub fn set_onramp_providers(providers: Vec<ffi::OnRampProvider>) -> Result<()> {
+    onramp::set_providers(providers)
+}
+
+/// returns the on-ramp quotes of the providers, the most crypto first
+pub fn get_onramp_quote_blocking(
+    fiat: String,
+    crypto: String,
+    amount: String,
+    region: String,
+) -> Result<Vec<ffi::OnRampQuote>> {
+    onramp::get_onramp_quotes(&fiat, &crypto, &amount, &region)
+}
+
+/// returns the redirect url of the on-ramp quote
+pub fn onramp_redirect_url(quote: &ffi::OnRampQuote, wallet_address: String) -> Result<String> {
+    onramp::onramp_redirect_url(quote, &wallet_address)
+}
+
+/// returns the stats of the NFT collection on the marketplace
+pub fn get_nft_collection_stats_blocking(
+    source: &ffi::NftStatsSource,
+    collection: String,
+) -> Result<ffi::NftCollectionStats> {
+    nftstats::get_collection_stats(source, &collection)
+}
+
+/// quotes the swap via the router
+pub fn get_swap_quote_blocking(
+    rpc_url: String,
+    router: String,
+    path: Vec<String>,
+    amount_in: String,
+) -> Result<ffi::SwapQuote> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(swap::get_swap_quote(&rpc_url, &router, &path, &amount_in))
+}
+
+/// returns the liquidity position of the account in the pair
+pub fn get_liquidity_position_blocking(
+    rpc_url: String,
+    pair: String,
+    account: String,
+) -> Result<ffi::LiquidityPosition> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(swap::get_liquidity_position(&rpc_url, &pair, &account))
+}
+
+/// checks whether the address holds the assets of the requirements
+pub fn check_entitlements_blocking(
+    rpc_url: String,
+    address: String,
+    requirements: Vec<ffi::AssetRequirement>,
+) -> Result<Vec<ffi::EntitlementVerdict>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(entitlement::check_entitlements(
+        &rpc_url,
+        &address,
+        &requirements,
+    ))
+}
+
+/// builds the Merkle tree of the claims of the airdrop recipients
+pub fn new_airdrop_tree(recipients: Vec<ffi::AirdropRecipient>) -> Result<Box<AirdropTree>> {
+    Ok(Box::new(AirdropTree::new(recipients)?))
+}
+
+/// whether the claim of the index was made on the distributor contract
+pub fn is_airdrop_claimed_blocking(
+    rpc_url: String,
+    distributor: String,
+    index: u64,
+) -> Result<bool> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(airdrop::is_airdrop_claimed(&rpc_url, &distributor, index))
+}
+
+/// reconstructs the holders of the token contract at the block from its transfer logs
+pub fn snapshot_holders_blocking(
+    rpc_url: String,
+    contract: String,
+    from_block: u64,
+    block_number: u64,
+    callback: &ffi::SnapshotProgressCallback,
+) -> Result<Vec<ffi::SnapshotHolder>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(snapshot::snapshot_holders(
+        &rpc_url,
+        &contract,
+        from_block,
+        block_number,
+        |scanned_block, holders| callback.onProgress(scanned_block, block_number, holders),
+    ))
+}
+
+/// creates the cache of the Cosmos account states queried with the transport
+pub fn new_cosmos_account_sync_with_transport(
+    query_url: String,
+    transport: CosmosTransport,
+) -> Result<Box<CosmosAccountSync>> {
+    Ok(Box::new(CosmosAccountSync::new(&query_url, transport)?))
+}
+
+/// restores a cursor saved by `HistoryCursor::save`
+pub fn restore_history_cursor(saved: String, api_key: String) -> Result<Box<HistoryCursor>> {
+    Ok(Box::new(HistoryCursor::restore(&saved, api_key)?))
+}
+
+/// returns the transactions of a given address from `start_block` (inclusive).
 /// The API key can be obtained from https://cronoscan.com
-pub fn get_transaction_history_blocking(
+pub fn get_transaction_history_since_blocking(
     address: String,
+    start_block: u64,
     api_key: String,
 ) -> Result<Vec<RawTxDetail>> {
-    let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(async move { get_transaction_history(&address, api_key).await })
+    HistoryCursor::since(&address, start_block, api_key)?.collect_all()
+}
+
+/// opens (or creates) the local database of the synced transaction history
+/// in the given directory.
+/// The API key can be obtained from https://cronoscan.com
+pub fn open_local_history(path: String, api_key: String) -> Result<Box<LocalHistory>> {
+    Ok(Box::new(LocalHistory::open(path, api_key)?))
 }
 
 /// returns the ERC20 transfers of a given address of a given contract.
@@ -489,13 +4114,65 @@ pub fn get_erc20_transfer_history_blocking(
     contract_address: String,
     option: QueryOption,
     api_key: String,
-) -> Result<Vec<RawTxDetail>> {
-    let rt = tokio::runtime::Runtime::new()?;
+) -> Result<Vec<RawTokenTxDetail>> {
+    let rt = runtime::new_runtime()?;
     rt.block_on(async move {
-        get_erc20_transfer_history(&address, &contract_address, option, api_key).await
+        get_erc20_transfer_history(
+            &address,
+            &contract_address,
+            option,
+            TxListParams::default(),
+            api_key,
+        )
+        .await
+    })
+}
+
+/// the same as `get_erc20_transfer_history_blocking`,
+/// but in the block range, page and order of the options
+pub fn get_erc20_transfer_history_with_options_blocking(
+    address: String,
+    contract_address: String,
+    option: QueryOption,
+    options: &ffi::TxListOptions,
+    api_key: String,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let params = options.into();
+    let rt = runtime::new_runtime()?;
+    rt.block_on(async move {
+        get_erc20_transfer_history(&address, &contract_address, option, params, api_key).await
     })
 }
 
+/// the ERC20 transfers of the address in the range, page and order of the options
+/// that match the filter (the explorer filters by the contract if there's only one,
+/// and by the blocks of the time range)
+pub fn get_erc20_transfer_history_filtered_blocking(
+    address: String,
+    options: &ffi::TxListOptions,
+    filter: &ffi::QueryFilter,
+    api_key: String,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let filter = queryfilter::Filter::new(&address, filter)?;
+    let explorer = queryfilter::Explorer::Cronoscan(&api_key);
+    let Some(options) = runtime::new_runtime()?.block_on(filter.block_range(options, explorer))
+    else {
+        return Ok(vec![]);
+    };
+    let (contract_address, option) = match filter.single_contract() {
+        Some(contract_address) => (contract_address, QueryOption::ByAddressAndContract),
+        None => (String::default(), QueryOption::ByAddress),
+    };
+    let transfers = get_erc20_transfer_history_with_options_blocking(
+        address,
+        contract_address,
+        option,
+        &options,
+        api_key,
+    )?;
+    Ok(filter.apply(transfers))
+}
+
 /// returns the ERC721 transfers of a given address of a given contract.
 /// (address can be empty if option is ByContract)
 /// default option is by address
@@ -505,10 +4182,33 @@ pub fn get_erc721_transfer_history_blocking(
     contract_address: String,
     option: QueryOption,
     api_key: String,
-) -> Result<Vec<RawTxDetail>> {
-    let rt = tokio::runtime::Runtime::new()?;
+) -> Result<Vec<RawTokenTxDetail>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(async move {
+        get_erc721_transfer_history(
+            &address,
+            &contract_address,
+            option,
+            TxListParams::default(),
+            api_key,
+        )
+        .await
+    })
+}
+
+/// the same as `get_erc721_transfer_history_blocking`,
+/// but in the block range, page and order of the options
+pub fn get_erc721_transfer_history_with_options_blocking(
+    address: String,
+    contract_address: String,
+    option: QueryOption,
+    options: &ffi::TxListOptions,
+    api_key: String,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let params = options.into();
+    let rt = runtime::new_runtime()?;
     rt.block_on(async move {
-        get_erc721_transfer_history(&address, &contract_address, option, api_key).await
+        get_erc721_transfer_history(&address, &contract_address, option, params, api_key).await
     })
 }
 
@@ -519,9 +4219,64 @@ pub fn get_tokens_blocking(
     blockscout_base_url: String,
     account_address: String,
 ) -> Result<Vec<RawTokenResult>> {
+    let account_address = normalize_address(&account_address)?;
+    let blockscout_url =
+        format!("{blockscout_base_url}?module=account&action=tokenlist&address={account_address}");
+    let resp: RawResponse<RawTokenResult> = http::get_json(&blockscout_url)?;
+    Ok(resp.result)
+}
+
+/// given the BlockScout REST API base url and the account addresses (hexadecimal),
+/// it will return the list of owned tokens for each address (in the same order).
+/// At most `concurrency` requests are sent at the same time.
+/// A failed query does not fail the others: its error is in `AddressTokens::error`.
+pub fn get_tokens_bulk_blocking(
+    blockscout_base_url: String,
+    addresses: Vec<String>,
+    concurrency: u32,
+) -> Result<Vec<AddressTokens>> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(async move {
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1) as usize));
+        let blockscout_base_url = Arc::new(blockscout_base_url);
+        let handles: Vec<_> = addresses
+            .into_iter()
+            .map(|address| {
+                let semaphore = semaphore.clone();
+                let blockscout_base_url = blockscout_base_url.clone();
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    match get_tokens(&blockscout_base_url, &address).await {
+                        Ok(tokens) => AddressTokens {
+                            address,
+                            tokens,
+                            error: String::default(),
+                        },
+                        Err(e) => AddressTokens {
+                            address,
+                            tokens: vec![],
+                            error: e.to_string(),
+                        },
+                    }
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(handle.await?);
+        }
+        Ok(results)
+    })
+}
+
+async fn get_tokens(
+    blockscout_base_url: &str,
+    account_address: &str,
+) -> Result<Vec<RawTokenResult>> {
+    let account_address = normalize_address(account_address)?;
     let blockscout_url =
         format!("{blockscout_base_url}?module=account&action=tokenlist&address={account_address}");
-    let resp = reqwest::blocking::get(blockscout_url)?.json::<RawResponse<RawTokenResult>>()?;
+    let resp: RawResponse<RawTokenResult> = http::get_json_async(&blockscout_url).await?;
     Ok(resp.result)
 }
 
@@ -536,12 +4291,135 @@ pub fn get_token_transfers_blocking(
     address: String,
     contract_address: String,
     option: QueryOption,
-) -> Result<Vec<RawTxDetail>> {
-    let blockscout_url = match option {
+) -> Result<Vec<RawTokenTxDetail>> {
+    Ok(get_token_transfers_with_source_blocking(
+        blockscout_base_url,
+        address,
+        contract_address,
+        option,
+    )?
+    .transfers)
+}
+
+/// the same as `get_token_transfers_blocking`, but it also returns
+/// the explorer that produced the transfers (see `set_explorer_failover_policy`)
+pub fn get_token_transfers_with_source_blocking(
+    blockscout_base_url: String,
+    address: String,
+    contract_address: String,
+    option: QueryOption,
+) -> Result<ffi::TokenTransfersResult> {
+    let rt = runtime::new_runtime()?;
+    let (source, transfers) = rt.block_on(async move {
+        failover::token_transfers(
+            &blockscout_base_url,
+            &address,
+            &contract_address,
+            option,
+            None,
+        )
+        .await
+    })?;
+    Ok(ffi::TokenTransfersResult { source, transfers })
+}
+
+/// the same as `get_token_transfers_blocking`,
+/// but in the block range, page and order of the options
+pub fn get_token_transfers_with_options_blocking(
+    blockscout_base_url: String,
+    address: String,
+    contract_address: String,
+    option: QueryOption,
+    options: &ffi::TxListOptions,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let params = options.into();
+    let rt = runtime::new_runtime()?;
+    let (_, transfers) = rt.block_on(async move {
+        failover::token_transfers(
+            &blockscout_base_url,
+            &address,
+            &contract_address,
+            option,
+            Some(params),
+        )
+        .await
+    })?;
+    Ok(transfers)
+}
+
+/// the token transfers of the contracts queried at the same time
+const TRANSFERS_CONCURRENCY: usize = 4;
+
+/// the token transfers of the address for each of the contracts, in the block order
+pub fn get_token_transfers_multi_blocking(
+    blockscout_base_url: String,
+    address: String,
+    contract_addresses: Vec<String>,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let mut contracts: Vec<String> = vec![];
+    for contract_address in contract_addresses {
+        let contract_address = normalize_address(&contract_address)?;
+        if !contracts.contains(&contract_address) {
+            contracts.push(contract_address);
+        }
+    }
+    let rt = runtime::new_runtime()?;
+    rt.block_on(async move {
+        let semaphore = Arc::new(Semaphore::new(TRANSFERS_CONCURRENCY));
+        let blockscout_base_url = Arc::new(blockscout_base_url);
+        let address = Arc::new(address);
+        let handles: Vec<_> = contracts
+            .into_iter()
+            .map(|contract_address| {
+                let semaphore = semaphore.clone();
+                let blockscout_base_url = blockscout_base_url.clone();
+                let address = address.clone();
+                tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    failover::token_transfers(
+                        &blockscout_base_url,
+                        &address,
+                        &contract_address,
+                        QueryOption::ByAddressAndContract,
+                        None,
+                    )
+                    .await
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let (_, transfers) = handle.await??;
+            results.push(transfers);
+        }
+        Ok(merge_transfers(results))
+    })
+}
+
+/// the transfers of the contracts in the block order
+/// (the order of the contracts is kept in the same block)
+fn merge_transfers(results: Vec<Vec<RawTokenTxDetail>>) -> Vec<RawTokenTxDetail> {
+    let mut transfers: Vec<RawTokenTxDetail> = results.into_iter().flatten().collect();
+    transfers.sort_by_key(|transfer| transfer.block_no);
+    transfers
+}
+
+/// the BlockScout url of the token transfers (QueryOption::ByContract is not supported)
+pub(crate) fn token_transfers_url(
+    blockscout_base_url: &str,
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+    params: Option<TxListParams>,
+) -> Result<String> {
+    let url = match option {
         QueryOption::ByAddress => {
+            let address = normalize_address(address)?;
             format!("{blockscout_base_url}?module=account&action=tokentx&address={address}")
         }
         QueryOption::ByAddressAndContract => {
+            let address = normalize_address(address)?;
+            let contract_address = normalize_address(contract_address)?;
             format!(
                 "{blockscout_base_url}?module=account&action=tokentx&address={address}&contractaddress={contract_address}"
             )
@@ -550,10 +4428,23 @@ pub fn get_token_transfers_blocking(
             anyhow::bail!("unsupported option")
         }
     };
-    let resp =
-        reqwest::blocking::get(blockscout_url)?.json::<RawResponse<RawBlockScoutTransfer>>()?;
+    Ok(match params {
+        Some(params) => format!("{url}{}", blockscout_list_params(params)),
+        None => url,
+    })
+}
 
-    Ok(resp.result.iter().flat_map(TryInto::try_into).collect())
+/// the BlockScout query string of the block range, page and order
+/// (appended to the other parameters)
+pub(crate) fn blockscout_list_params(params: TxListParams) -> String {
+    let mut query = format!(
+        "&startblock={}&endblock={}&sort={}",
+        params.start_block, params.end_block, params.sort
+    );
+    if params.page != 0 {
+        query.push_str(&format!("&page={}&offset={}", params.page, params.offset));
+    }
+    query
 }
 
 /// given the BlockScout REST API base url and the contract address (hexadecimal),
@@ -571,18 +4462,102 @@ pub fn get_token_transfers_blocking(
 ///
 /// ::TIPS:: Use another functions to get more token/owner details, e.g.
 /// `get_tokens_blocking` to get owned tokens by account_address
-pub fn get_token_holders<S: AsRef<str> + std::fmt::Display>(
+pub fn get_token_holders_blocking<S: AsRef<str> + std::fmt::Display>(
     blockscout_base_url: S,
     contract_address: S,
     page: u64,
     offset: u64,
 ) -> Result<Vec<TokenHolderDetail>> {
+    let contract_address = normalize_address(contract_address.as_ref())?;
     let blockscout_url =
         format!("{blockscout_base_url}?module=token&action=getTokenHolders&contractaddress={contract_address}&page={page}&offset={offset}");
-    let resp = reqwest::blocking::get(blockscout_url)?.json::<RawResponse<TokenHolderDetail>>()?;
+    let resp: RawResponse<TokenHolderDetail> = http::get_json(&blockscout_url)?;
     Ok(resp.result)
 }
 
+/// the same as `get_token_holders_blocking`
+/// (deprecated: kept for compatibility, use `get_token_holders_blocking`)
+pub fn get_token_holders<S: AsRef<str> + std::fmt::Display>(
+    blockscout_base_url: S,
+    contract_address: S,
+    page: u64,
+    offset: u64,
+) -> Result<Vec<TokenHolderDetail>> {
+    get_token_holders_blocking(blockscout_base_url, contract_address, page, offset)
+}
+
+/// returns whether there is a contract at the address (via `eth_getCode`)
+pub fn is_contract_blocking(rpc_url: String, address: String) -> Result<bool> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(rpc::is_contract(&rpc_url, &address))
+}
+
+/// returns the number of transactions sent from the address (via `eth_getTransactionCount`)
+pub fn get_tx_count_blocking(rpc_url: String, address: String) -> Result<u64> {
+    let rt = runtime::new_runtime()?;
+    rt.block_on(rpc::get_tx_count(&rpc_url, &address))
+}
+
+/// broadcasts the signed raw transaction (via `eth_sendRawTransaction`), returns its hash
+pub fn broadcast_raw_tx_blocking(rpc_url: String, raw_tx: Vec<u8>) -> Result<String> {
+    let rt = runtime::new_runtime()?;
+    if dryrun::is_enabled() {
+        return rt.block_on(dryrun::dry_run_raw_transaction(&raw_tx));
+    }
+    rt.block_on(rpc::send_raw_transaction(&rpc_url, raw_tx))
+}
+
+/// set the record/replay mode of all HTTP requests
+/// (by default, it is taken from the `PLAY_CPP_SDK_HTTP_MODE` environment variable:
+/// `live`, `record` or `replay`)
+/// fixtures_dir can be empty to use the `PLAY_CPP_SDK_HTTP_FIXTURES` environment variable
+/// (or `fixtures` if not set)
+pub fn set_http_mode(mode: ffi::HttpMode, fixtures_dir: String) -> Result<()> {
+    http::set_http_mode(mode, fixtures_dir)
+}
+
+/// set the headers (e.g. User-Agent or authorization headers)
+/// added to all the explorer, registry and Crypto.com Pay requests
+/// (it replaces the previously set headers)
+pub fn set_default_headers(headers: Vec<ffi::HttpHeader>) -> Result<()> {
+    Ok(http::set_default_headers(
+        headers.into_iter().map(|h| (h.name, h.value)),
+    )?)
+}
+
+/// set the TLS options of the HTTP requests and the WalletConnect 1.0 bridge connection
+/// root_certificates_pem: additional trusted root certificates in PEM (can be empty)
+/// spki_sha256_pins: base64-encoded SHA-256 hashes of the allowed SubjectPublicKeyInfo
+/// (optionally prefixed with "sha256/"); if empty, no pinning is done
+pub fn set_tls_options(root_certificates_pem: String, spki_sha256_pins: Vec<String>) -> Result<()> {
+    let mut options = tls::TlsOptions::default();
+    options
+        .add_root_certificates_pem(&root_certificates_pem)
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+    for pin in spki_sha256_pins.iter() {
+        options
+            .add_spki_pin(pin)
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+    }
+    tls::set_tls_options(Some(options)).map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+/// set the static DNS entries used instead of the system resolver
+/// by the HTTP requests and the WalletConnect 1.0 bridge connection
+/// (a host can be repeated to give several addresses; it replaces the previous overrides)
+pub fn set_host_overrides(overrides: Vec<ffi::HostOverride>) -> Result<()> {
+    let mut hosts: BTreeMap<String, Vec<std::net::IpAddr>> = BTreeMap::new();
+    for entry in overrides {
+        let ip = entry
+            .ip_address
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid IP address: {}", entry.ip_address))?;
+        hosts.entry(entry.host).or_default().push(ip);
+    }
+    dns::set_host_overrides(hosts);
+    Ok(())
+}
+
 /// it creates the payment object
 /// https://pay-docs.crypto.com/#api-reference-resources-payments-create-a-payment
 /// This API can be called using either your Secret Key or Publishable Key.
@@ -612,6 +4587,32 @@ pub fn get_payment(
     Ok(pay::get_payment(&secret_or_publishable_api_key, &payment_id)?.into())
 }
 
+/// it verifies the signature of the webhook callback and parses the event
+/// https://pay-docs.crypto.com/#api-reference-webhooks
+pub fn verify_crypto_pay_webhook(
+    payload: String,
+    signature_header: String,
+    webhook_secret: String,
+    tolerance_secs: u64,
+) -> Result<ffi::CryptoPayWebhookEvent> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    pay::verify_webhook_signature(
+        &payload,
+        &signature_header,
+        &webhook_secret,
+        tolerance_secs,
+        now,
+    )?;
+    Ok(pay::parse_webhook_event(&payload)?)
+}
+
+/// it parses the payload of the webhook callback
+pub fn parse_crypto_pay_webhook_event(payload: String) -> Result<ffi::CryptoPayWebhookEvent> {
+    Ok(pay::parse_webhook_event(&payload)?)
+}
+
 impl From<pay::CryptoPayObject> for CryptoComPaymentResponse {
     fn from(obj: pay::CryptoPayObject) -> Self {
         Self {
@@ -627,169 +4628,261 @@ impl From<pay::CryptoPayObject> for CryptoComPaymentResponse {
 }
 
 #[derive(Serialize, Deserialize)]
-struct RawResponse<R> {
+pub(crate) struct RawResponse<R> {
     message: String,
     result: Vec<R>,
     status: String,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RawBlockScoutTransfer {
-    block_hash: String,
-    block_number: String,
-    confirmations: String,
-    contract_address: String,
-    cumulative_gas_used: String,
-    from: String,
-    gas: String,
-    gas_price: String,
-    gas_used: String,
-    hash: String,
-    input: String,
-    log_index: String,
-    nonce: String,
-    time_stamp: String,
-    to: String,
-    token_decimal: String,
-    token_name: String,
-    token_symbol: String,
-    transaction_index: String,
-    value: String,
-}
-
-impl TryFrom<&RawBlockScoutTransfer> for RawTxDetail {
+/// Etherscan-compatible API response (on errors, `result` is a string)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExplorerResponse<R> {
+    Success(RawResponse<R>),
+    Error {
+        message: String,
+        result: Option<String>,
+    },
+}
+
+impl Default for ValueKind {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+impl From<bindings_common::value::ValueKind> for ValueKind {
+    fn from(kind: bindings_common::value::ValueKind) -> Self {
+        match kind {
+            bindings_common::value::ValueKind::Unknown => Self::Unknown,
+            bindings_common::value::ValueKind::Native => Self::Native,
+            bindings_common::value::ValueKind::Fungible => Self::Fungible,
+            bindings_common::value::ValueKind::NonFungible => Self::NonFungible,
+        }
+    }
+}
+
+impl From<bindings_common::explorer::RawTxDetail> for RawTxDetail {
+    fn from(tx: bindings_common::explorer::RawTxDetail) -> Self {
+        Self {
+            hash: tx.hash,
+            to_address: tx.to_address,
+            from_address: tx.from_address,
+            value: tx.value,
+            raw_value: tx.raw_value,
+            formatted_value: tx.formatted_value,
+            value_kind: tx.value_kind.into(),
+            block_no: tx.block_no,
+            timestamp: tx.timestamp,
+            timestamp_epoch: tx.timestamp_epoch,
+            timestamp_iso: tx.timestamp_iso,
+            contract_address: tx.contract_address,
+            display_name: tx.display_name,
+        }
+    }
+}
+
+impl From<bindings_common::explorer::RawTokenTxDetail> for RawTokenTxDetail {
+    fn from(tx: bindings_common::explorer::RawTokenTxDetail) -> Self {
+        Self {
+            hash: tx.hash,
+            to_address: tx.to_address,
+            from_address: tx.from_address,
+            value: tx.value,
+            raw_value: tx.raw_value,
+            formatted_value: tx.formatted_value,
+            value_kind: tx.value_kind.into(),
+            block_no: tx.block_no,
+            timestamp: tx.timestamp,
+            timestamp_epoch: tx.timestamp_epoch,
+            timestamp_iso: tx.timestamp_iso,
+            contract_address: tx.contract_address,
+            token_name: tx.token_name,
+            token_symbol: tx.token_symbol,
+            token_decimal: tx.token_decimal,
+            token_id: tx.token_id,
+            display_name: tx.display_name,
+        }
+    }
+}
+
+impl TryFrom<&RawBlockScoutTransfer> for RawTokenTxDetail {
     type Error = anyhow::Error;
 
     fn try_from(tx: &RawBlockScoutTransfer) -> Result<Self, Self::Error> {
-        let block_no = tx.block_number.parse::<u64>()?;
-        Ok(Self {
-            hash: tx.hash.clone(),
-            to_address: tx.to.clone(),
-            from_address: tx.from.clone(),
-            value: tx.value.clone(),
-            block_no,
-            timestamp: tx.time_stamp.clone(),
-            contract_address: tx.contract_address.clone(),
-        })
+        Ok(bindings_common::explorer::RawTokenTxDetail::try_from(tx)?.into())
     }
 }
 
 impl From<&NormalTransaction> for RawTxDetail {
     fn from(tx: &NormalTransaction) -> Self {
-        let block_no: u64 = match tx.block_number {
-            BlockNumber::Number(block_no) => block_no.0[0],
-            _ => 0,
-        };
-        RawTxDetail {
-            hash: tx
-                .hash
-                .value()
-                .map(|x| format!("{x:?}"))
-                .unwrap_or_default(),
-            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
-            from_address: tx
-                .from
-                .value()
-                .map(|x| format!("{x:?}"))
-                .unwrap_or_default(),
-            value: tx.value.to_string(),
-            block_no,
-            timestamp: tx.time_stamp.clone(),
-            contract_address: format!("{:?}", tx.contract_address.unwrap_or_default()),
-        }
+        bindings_common::explorer::RawTxDetail::from(tx).into()
     }
 }
 
-impl From<&ERC20TokenTransferEvent> for RawTxDetail {
+impl From<&ERC20TokenTransferEvent> for RawTokenTxDetail {
     fn from(tx: &ERC20TokenTransferEvent) -> Self {
-        let block_no: u64 = match tx.block_number {
-            BlockNumber::Number(block_no) => block_no.0[0],
-            _ => 0,
-        };
-        RawTxDetail {
-            hash: format!("{:?}", tx.hash),
-            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
-            from_address: format!("{:?}", tx.from),
-            value: tx.value.to_string(),
-            block_no,
-            timestamp: tx.time_stamp.clone(),
-            contract_address: format!("{:?}", tx.contract_address),
-        }
+        bindings_common::explorer::RawTokenTxDetail::from(tx).into()
     }
 }
 
-impl From<&ERC721TokenTransferEvent> for RawTxDetail {
+impl From<&ERC721TokenTransferEvent> for RawTokenTxDetail {
     fn from(tx: &ERC721TokenTransferEvent) -> Self {
-        let block_no: u64 = match tx.block_number {
-            BlockNumber::Number(block_no) => block_no.0[0],
-            _ => 0,
-        };
-        RawTxDetail {
-            hash: format!("{:?}", tx.hash),
-            to_address: tx.to.map(|x| format!("{x:?}")).unwrap_or_default(),
-            from_address: format!("{:?}", tx.from),
-            value: tx.token_id.to_string(),
-            block_no,
-            timestamp: tx.time_stamp.clone(),
-            contract_address: format!("{:?}", tx.contract_address),
+        bindings_common::explorer::RawTokenTxDetail::from(tx).into()
+    }
+}
+
+/// the base url of the Cronoscan API (of the SDK environment)
+pub(crate) fn explorer_url(api_key: &str) -> Result<url::Url> {
+    Ok(match environment::explorer_api_url()? {
+        Some(url) => url,
+        None => Client::new(Chain::Cronos, api_key)?
+            .etherscan_api_url()
+            .clone(),
+    })
+}
+
+/// queries the account module of the Cronoscan API (of the SDK environment)
+pub(crate) async fn get_explorer_result<R: serde::de::DeserializeOwned>(
+    action: &str,
+    params: BTreeMap<&str, String>,
+    api_key: String,
+) -> Result<Vec<R>> {
+    let mut url = explorer_url(&api_key)?;
+    url.query_pairs_mut()
+        .append_pair("module", "account")
+        .append_pair("action", action)
+        .extend_pairs(params)
+        .append_pair("apikey", &api_key);
+    match http::get_json_async::<ExplorerResponse<R>>(url.as_str()).await? {
+        ExplorerResponse::Success(resp) => Ok(resp.result),
+        ExplorerResponse::Error { message, result } => {
+            anyhow::bail!("{message}: {}", result.unwrap_or_default())
         }
     }
 }
 
-async fn get_transaction_history(address: &str, api_key: String) -> Result<Vec<RawTxDetail>> {
-    let client = Client::new(Chain::Cronos, api_key)?;
-    let transactions = client.get_transactions(&address.parse()?, None).await?;
-    Ok(transactions.iter().map(|tx| tx.into()).collect())
+pub(crate) fn tx_list_params(params: TxListParams) -> BTreeMap<&'static str, String> {
+    HashMap::from(params).into_iter().collect()
+}
+
+impl From<&ffi::TxListOptions> for TxListParams {
+    /// the unset (zero) end block and offset are the explorer's defaults
+    fn from(options: &ffi::TxListOptions) -> Self {
+        let defaults = TxListParams::default();
+        TxListParams::new(
+            options.start_block,
+            if options.end_block == 0 {
+                defaults.end_block
+            } else {
+                options.end_block
+            },
+            options.page,
+            if options.offset == 0 {
+                defaults.offset
+            } else {
+                options.offset
+            },
+            match options.sort {
+                ffi::SortOrder::Descending => Sort::Desc,
+                _ => Sort::Asc,
+            },
+        )
+    }
 }
 
-async fn get_erc20_transfer_history(
+/// validates the addresses required by the query option
+fn token_query_option(
     address: &str,
     contract_address: &str,
     option: QueryOption,
+) -> Result<TokenQueryOption, GameSdkError> {
+    Ok(match option {
+        QueryOption::ByContract => TokenQueryOption::ByContract(parse_address(contract_address)?),
+        QueryOption::ByAddressAndContract => TokenQueryOption::ByAddressAndContract(
+            parse_address(address)?,
+            parse_address(contract_address)?,
+        ),
+        _ => TokenQueryOption::ByAddress(parse_address(address)?),
+    })
+}
+
+/// parses a hexadecimal address given to the SDK (with or without the 0x prefix),
+/// so that invalid inputs fail before any request is sent
+pub(crate) fn parse_address(address: &str) -> Result<Address, GameSdkError> {
+    address
+        .trim()
+        .parse()
+        .map_err(|_| GameSdkError::InvalidAddress(address.to_owned()))
+}
+
+/// returns the normalized (0x-prefixed, lowercase) form of a hexadecimal address
+pub(crate) fn normalize_address(address: &str) -> Result<String, GameSdkError> {
+    Ok(format!("{:?}", parse_address(address)?))
+}
+
+fn token_query_params(
+    option: TokenQueryOption,
+    params: TxListParams,
+) -> BTreeMap<&'static str, String> {
+    option.into_params(params).into_iter().collect()
+}
+
+pub(crate) async fn get_transaction_history(
+    address: &str,
+    params: TxListParams,
     api_key: String,
 ) -> Result<Vec<RawTxDetail>> {
-    let client = Client::new(Chain::Cronos, api_key)?;
-    let token_query = match option {
-        QueryOption::ByContract => TokenQueryOption::ByContract(contract_address.parse()?),
-        QueryOption::ByAddressAndContract => {
-            TokenQueryOption::ByAddressAndContract(address.parse()?, contract_address.parse()?)
-        }
-        _ => TokenQueryOption::ByAddress(address.parse()?),
-    };
-    let transactions = client
-        .get_erc20_token_transfer_events(token_query, None)
-        .await?;
+    let mut params = tx_list_params(params);
+    params.insert("address", normalize_address(address)?);
+    let transactions: Vec<NormalTransaction> =
+        get_explorer_result("txlist", params, api_key).await?;
     Ok(transactions.iter().map(|tx| tx.into()).collect())
 }
 
-async fn get_erc721_transfer_history(
+pub(crate) async fn get_erc20_transfer_history(
     address: &str,
     contract_address: &str,
     option: QueryOption,
+    params: TxListParams,
     api_key: String,
-) -> Result<Vec<RawTxDetail>> {
-    let client = Client::new(Chain::Cronos, api_key)?;
-    let token_query = match option {
-        QueryOption::ByContract => TokenQueryOption::ByContract(contract_address.parse()?),
-        QueryOption::ByAddressAndContract => {
-            TokenQueryOption::ByAddressAndContract(address.parse()?, contract_address.parse()?)
-        }
-        _ => TokenQueryOption::ByAddress(address.parse()?),
-    };
-    let transactions = client
-        .get_erc721_token_transfer_events(token_query, None)
-        .await?;
+) -> Result<Vec<RawTokenTxDetail>> {
+    let token_query = token_query_option(address, contract_address, option)?;
+    let transactions: Vec<ERC20TokenTransferEvent> =
+        get_explorer_result("tokentx", token_query_params(token_query, params), api_key).await?;
+    Ok(transactions.iter().map(|tx| tx.into()).collect())
+}
+
+pub(crate) async fn get_erc721_transfer_history(
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+    params: TxListParams,
+    api_key: String,
+) -> Result<Vec<RawTokenTxDetail>> {
+    let token_query = token_query_option(address, contract_address, option)?;
+    let transactions: Vec<ERC721TokenTransferEvent> = get_explorer_result(
+        "tokennfttx",
+        token_query_params(token_query, params),
+        api_key,
+    )
+    .await?;
     Ok(transactions.iter().map(|tx| tx.into()).collect())
 }
 
 fn walletconnect_restore_client(session_info: String) -> Result<Box<WalletconnectClient>> {
-    let mut rt = tokio::runtime::Runtime::new()?;
+    // the threads of the client's runtime count their allocations for it
+    let _scope = memory::scope(memory::WALLETCONNECT);
+    let mut rt = runtime::new_runtime()?;
     let client = walletconnect::walletconnect_restore_client(&mut rt, session_info)?;
 
     Ok(Box::new(WalletconnectClient {
         client: Some(client),
         rt,
+        callback_task: None,
+        polled_callback: None,
+        audit_log: Default::default(),
+        idempotency: Default::default(),
     }))
 }
 
@@ -800,7 +4893,8 @@ fn walletconnect_new_client(
     name: String,
     chain_id: u64,
 ) -> Result<Box<WalletconnectClient>> {
-    let mut rt = tokio::runtime::Runtime::new()?;
+    let _scope = memory::scope(memory::WALLETCONNECT);
+    let mut rt = runtime::new_runtime()?;
     let client = walletconnect::walletconnect_new_client(
         &mut rt,
         description,
@@ -813,10 +4907,34 @@ fn walletconnect_new_client(
     Ok(Box::new(WalletconnectClient {
         client: Some(client),
         rt,
+        callback_task: None,
+        polled_callback: None,
+        audit_log: Default::default(),
+        idempotency: Default::default(),
     }))
 }
 unsafe impl Send for ffi::WalletConnectCallback {}
 unsafe impl Sync for ffi::WalletConnectCallback {}
+unsafe impl Send for ffi::WalletConnect2SessionProposalCallback {}
+unsafe impl Sync for ffi::WalletConnect2SessionProposalCallback {}
+unsafe impl Send for ffi::AddressWatcherCallback {}
+unsafe impl Sync for ffi::AddressWatcherCallback {}
+unsafe impl Send for ffi::PolicyCallback {}
+unsafe impl Sync for ffi::PolicyCallback {}
+unsafe impl Send for ffi::NetworkBudgetCallback {}
+unsafe impl Sync for ffi::NetworkBudgetCallback {}
+unsafe impl Send for ffi::RuntimeThreadCallback {}
+unsafe impl Sync for ffi::RuntimeThreadCallback {}
+unsafe impl Send for ffi::RateServiceCallback {}
+unsafe impl Sync for ffi::RateServiceCallback {}
+unsafe impl Send for ffi::TxTrackerCallback {}
+unsafe impl Sync for ffi::TxTrackerCallback {}
+unsafe impl Send for ffi::ExternalSignerCallback {}
+unsafe impl Sync for ffi::ExternalSignerCallback {}
+unsafe impl Send for ffi::MpcPartyCallback {}
+unsafe impl Sync for ffi::MpcPartyCallback {}
+unsafe impl Send for ffi::MpcTransportCallback {}
+unsafe impl Sync for ffi::MpcTransportCallback {}
 
 fn check_wallet(
     cached: bool,
@@ -979,6 +5097,82 @@ mod test {
         panic!("test_get_tokens failed");
     }
 
+    #[test]
+    fn test_tx_list_options() {
+        let params = TxListParams::from(&ffi::TxListOptions {
+            start_block: 100,
+            end_block: 0,
+            page: 0,
+            offset: 0,
+            sort: ffi::SortOrder::Ascending,
+        });
+        let defaults = TxListParams::default();
+        assert_eq!(params.start_block, 100);
+        assert_eq!(params.end_block, defaults.end_block);
+        assert_eq!(params.offset, defaults.offset);
+        assert_eq!(
+            blockscout_list_params(params),
+            "&startblock=100&endblock=99999999&sort=asc"
+        );
+
+        let params = TxListParams::from(&ffi::TxListOptions {
+            start_block: 100,
+            end_block: 200,
+            page: 2,
+            offset: 50,
+            sort: ffi::SortOrder::Descending,
+        });
+        let query = tx_list_params(params);
+        assert_eq!(query["endBlock"], "200");
+        assert_eq!(query["page"], "2");
+        assert_eq!(query["offset"], "50");
+        assert_eq!(query["sort"], "desc");
+        assert_eq!(
+            blockscout_list_params(params),
+            "&startblock=100&endblock=200&sort=desc&page=2&offset=50"
+        );
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        assert_eq!(
+            normalize_address("841a15D12aEc9c6039FD132c2FbFF112eD355700").expect("address"),
+            "0x841a15d12aec9c6039fd132c2fbff112ed355700"
+        );
+        assert!(matches!(
+            parse_address("0x841a15D12aEc9c"),
+            Err(GameSdkError::InvalidAddress(_))
+        ));
+        // fails before sending the request to the (unreachable) url
+        let err = get_tokens_blocking("http://127.0.0.1:1/api".into(), "not an address".into())
+            .expect_err("invalid address");
+        assert!(matches!(
+            err.downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::InvalidAddress(_))
+        ));
+    }
+
+    #[test]
+    #[ignore]
+    pub fn test_get_tokens_bulk() {
+        let addresses = vec![
+            "0x652d53227d7013f3FbBeA542443Dc2eeF05719De".to_owned(),
+            "not an address".to_owned(),
+        ];
+        let actual = get_tokens_bulk_blocking(
+            "https://blockscout.com/xdai/mainnet/api".into(),
+            addresses.clone(),
+            2,
+        )
+        .expect("bulk query");
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].address, addresses[0]);
+        assert!(actual[0].error.is_empty());
+        assert!(!actual[0].tokens.is_empty());
+        assert_eq!(actual[1].address, addresses[1]);
+        assert!(!actual[1].error.is_empty());
+    }
+
     #[test]
     pub fn test_get_token_transactions() {
         let expected: Vec<RawBlockScoutTransfer> = serde_json::from_str(
@@ -1030,7 +5224,7 @@ mod test {
           ]"#,
         )
         .expect("parse");
-        let expected: Vec<RawTxDetail> = expected.iter().flat_map(TryInto::try_into).collect();
+        let expected: Vec<RawTokenTxDetail> = expected.iter().flat_map(TryInto::try_into).collect();
         let actual = get_token_transfers_blocking(
             "https://cronos.org/explorer/testnet3/api".to_string(),
             "0x841a15D12aEc9c6039FD132c2FbFF112eD355700".to_string(),
@@ -1041,6 +5235,121 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_merge_transfers() {
+        // the transfers of the contracts a and b
+        let transfer = |hash: &str, block_no| RawTokenTxDetail {
+            hash: hash.to_owned(),
+            block_no,
+            contract_address: hash[..1].to_owned(),
+            ..Default::default()
+        };
+        let merged = merge_transfers(vec![
+            vec![transfer("a1", 3), transfer("a2", 10)],
+            vec![],
+            vec![transfer("b1", 1), transfer("b2", 3)],
+        ]);
+        let hashes: Vec<&str> = merged.iter().map(|t| t.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["b1", "a1", "b2", "a2"]);
+        // the contract a before b in the block 3
+        let blocks: Vec<(u64, &str)> = merged
+            .iter()
+            .map(|t| (t.block_no, t.contract_address.as_str()))
+            .collect();
+        assert_eq!(blocks, vec![(1, "b"), (3, "a"), (3, "b"), (10, "a")]);
+        assert!(get_token_transfers_multi_blocking(
+            "http://127.0.0.1:1".to_owned(),
+            "0x841a15d12aec9c6039fd132c2fbff112ed355700".to_owned(),
+            vec!["not an address".to_owned()],
+        )
+        .is_err());
+    }
+
+    #[test]
+    pub fn test_erc20_transfer_token_details() {
+        let events: Vec<ERC20TokenTransferEvent> = serde_json::from_str(
+            r#"[{
+                "blockNumber": "2088372",
+                "timeStamp": "1646318156",
+                "hash": "0x0890c4dce61da8713db5844fa0ae0aa73b74ea6ccfa91c90065ae80471cd908c",
+                "nonce": "13",
+                "blockHash": "0x1456b7934898b7c735967e849effc4dc45e84ff32c6c2e130d572cb9589ca652",
+                "from": "0x9ad08de843158b0a4f8efdae6ea49caf77bbf13f",
+                "contractAddress": "0x715b4d660148c477e03358f8b0315ed4088fe89a",
+                "to": "0x841a15d12aec9c6039fd132c2fbff112ed355700",
+                "value": "200000000000000000000",
+                "tokenName": "DAI",
+                "tokenSymbol": "DAI",
+                "tokenDecimal": "18",
+                "transactionIndex": "37",
+                "gas": "145930",
+                "gasPrice": "2021527882398",
+                "gasUsed": "97287",
+                "cumulativeGasUsed": "31760308",
+                "input": "deprecated",
+                "confirmations": "537920"
+            }]"#,
+        )
+        .expect("parse");
+        let actual: Vec<RawTokenTxDetail> = events.iter().map(Into::into).collect();
+        assert_eq!(
+            actual,
+            vec![RawTokenTxDetail {
+                hash: "0x0890c4dce61da8713db5844fa0ae0aa73b74ea6ccfa91c90065ae80471cd908c"
+                    .to_owned(),
+                to_address: "0x841a15d12aec9c6039fd132c2fbff112ed355700".to_owned(),
+                from_address: "0x9ad08de843158b0a4f8efdae6ea49caf77bbf13f".to_owned(),
+                value: "200000000000000000000".to_owned(),
+                raw_value: "200000000000000000000".to_owned(),
+                formatted_value: "200".to_owned(),
+                value_kind: ValueKind::Fungible,
+                block_no: 2088372,
+                timestamp: "1646318156".to_owned(),
+                timestamp_epoch: 1646318156,
+                timestamp_iso: "2022-03-03T14:35:56Z".to_owned(),
+                contract_address: "0x715b4d660148c477e03358f8b0315ed4088fe89a".to_owned(),
+                token_name: "DAI".to_owned(),
+                token_symbol: "DAI".to_owned(),
+                token_decimal: "18".to_owned(),
+                token_id: "".to_owned(),
+                display_name: "".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_erc721_transfer_token_id() {
+        let events: Vec<ERC721TokenTransferEvent> = serde_json::from_str(
+            r#"[{
+                "blockNumber": "4708120",
+                "timeStamp": "1662700813",
+                "hash": "0x7b3d1a5e0b25ef5d9a61ac79bd87a4e2b4b0d2a0e8dbcd1d1c6f0f0e7f38b3a1",
+                "nonce": "42",
+                "blockHash": "0x5c1e53b7a1f6f0f1a4c6e3b4c1d9a7f5e2b3c4d5e6f708192a3b4c5d6e7f8091",
+                "from": "0x0000000000000000000000000000000000000000",
+                "contractAddress": "0x562f021423d75a1636db5be1c4d99bc005ccebfe",
+                "to": "0x668f126b87936df4f9a98f18c44eb73868fffea0",
+                "tokenID": "2101",
+                "tokenName": "Cronos Chimp Club",
+                "tokenSymbol": "CCC",
+                "tokenDecimal": "0",
+                "transactionIndex": "3",
+                "gas": "300000",
+                "gasPrice": "5000000000000",
+                "gasUsed": "155000",
+                "cumulativeGasUsed": "455000",
+                "input": "deprecated",
+                "confirmations": "100"
+            }]"#,
+        )
+        .expect("parse");
+        let actual: RawTokenTxDetail = (&events[0]).into();
+        assert_eq!(actual.value, "1");
+        assert_eq!(actual.token_id, "2101");
+        assert_eq!(actual.value_kind, ValueKind::NonFungible);
+        assert_eq!(actual.token_symbol, "CCC");
+    }
+
     #[test]
     pub fn test_generate_qrcode() {
         let qrcode = generate_qrcode("play-cpp-sdk".to_string()).expect("get qrcode");
@@ -1102,7 +5411,7 @@ mod test {
           ]"#,
         )
         .expect("parse");
-        let actual = get_token_holders(
+        let actual = get_token_holders_blocking(
             "https://blockscout.com/xdai/mainnet/api",
             "0xed1efc6efceaab9f6d609fec89c9e675bf1efb0a",
             1,
@@ -0,0 +1,604 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use bindings_common::timestamp;
+use bindings_common::value::{normalize_value, token_value, TokenStandard, NATIVE_DECIMALS};
+use ethers::etherscan::account::{
+    ERC20TokenTransferEvent, ERC721TokenTransferEvent, NormalTransaction, Sort, TokenQueryOption,
+    TxListParams,
+};
+use ethers::types::Address;
+use ethers::utils::keccak256;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::ffi::{LocalHistoryFilter, RawTokenTxDetail, RawTxDetail, ValueKind};
+use crate::{get_explorer_result, parse_address, tx_list_params};
+
+/// the last block number accepted by the explorer API
+const END_BLOCK: u64 = 99999999;
+/// how many records are requested at once
+const PAGE_SIZE: u64 = 1000;
+
+/// the registered addresses and their sync state
+const ADDRESSES_TREE: &str = "addresses";
+/// the normal transactions
+const TRANSACTIONS_TREE: &str = "transactions";
+/// the ERC20 and ERC721 transfers
+const TOKEN_TRANSFERS_TREE: &str = "token_transfers";
+
+/// the last synced block of each kind of records (they are synced from it again,
+/// as the block may not have been complete)
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+struct SyncState {
+    transactions: u64,
+    erc20_transfers: u64,
+    erc721_transfers: u64,
+}
+
+/// the stored records
+trait Record: Serialize + DeserializeOwned {
+    fn block_no(&self) -> u64;
+    /// what identifies the record in its block (so that it's the same when fetched again)
+    fn id(&self) -> String;
+    /// sets the normalized fields of the records stored before they were added
+    fn normalize(&mut self);
+}
+
+impl Record for RawTxDetail {
+    fn block_no(&self) -> u64 {
+        self.block_no
+    }
+
+    fn id(&self) -> String {
+        self.hash.to_lowercase()
+    }
+
+    fn normalize(&mut self) {
+        if self.timestamp_iso.is_empty() {
+            (self.timestamp_epoch, self.timestamp_iso) = timestamp::normalize(&self.timestamp);
+        }
+        if self.raw_value.is_empty() {
+            (self.raw_value, self.formatted_value) =
+                normalize_value(&self.value, Some(NATIVE_DECIMALS));
+            self.value_kind = ValueKind::Native;
+        }
+    }
+}
+
+impl Record for RawTokenTxDetail {
+    fn block_no(&self) -> u64 {
+        self.block_no
+    }
+
+    /// the NFTs are identified by their token id; the explorers don't return the log index
+    /// of the ERC20 transfers, so they are identified by their transfer
+    fn id(&self) -> String {
+        let hash = self.hash.to_lowercase();
+        let contract = self.contract_address.to_lowercase();
+        if self.token_id.is_empty() {
+            let (from, to) = (
+                self.from_address.to_lowercase(),
+                self.to_address.to_lowercase(),
+            );
+            format!("{hash}/{contract}/{from}/{to}/{}", self.value)
+        } else {
+            format!("{hash}/{contract}/{}", self.token_id)
+        }
+    }
+
+    fn normalize(&mut self) {
+        if self.timestamp_iso.is_empty() {
+            (self.timestamp_epoch, self.timestamp_iso) = timestamp::normalize(&self.timestamp);
+        }
+        if self.raw_value.is_empty() {
+            let (raw_value, formatted_value, value_kind) = token_value(
+                &self.value,
+                &self.token_decimal,
+                TokenStandard::of("", &self.token_id),
+            );
+            (self.raw_value, self.formatted_value) = (raw_value, formatted_value);
+            self.value_kind = value_kind.into();
+        }
+    }
+}
+
+/// the length of the address and block number prefix of the record keys
+const PREFIX_LEN: usize = 20 + 8;
+
+/// address || block number (big-endian, so that the records are ordered by blocks)
+fn block_prefix(address: &Address, block_no: u64) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(PREFIX_LEN + 32);
+    prefix.extend_from_slice(address.as_bytes());
+    prefix.extend_from_slice(&block_no.to_be_bytes());
+    prefix
+}
+
+/// the record key: the block prefix || hash of the record id
+/// (so that the records fetched again are not duplicated)
+fn record_key<D: Record>(address: &Address, record: &D) -> Vec<u8> {
+    let mut key = block_prefix(address, record.block_no());
+    key.extend_from_slice(&keccak256(record.id()));
+    key
+}
+
+/// the explorer query parameters of a page of the records from `start_block`
+fn page_params(
+    action: &str,
+    address: Address,
+    start_block: u64,
+    page: u64,
+) -> BTreeMap<&'static str, String> {
+    let params = TxListParams::new(start_block, END_BLOCK, page, PAGE_SIZE, Sort::Asc);
+    if action == "txlist" {
+        let mut params = tx_list_params(params);
+        params.insert("address", format!("{address:?}"));
+        params
+    } else {
+        TokenQueryOption::ByAddress(address)
+            .into_params(params)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// the start block and the page number of the records after a full page ending at `last_block`:
+/// the records from its last block (which may not be complete), or the next page
+/// if the whole page was from one block (so that the blocks with more records than a page
+/// aren't skipped)
+fn next_page(start_block: u64, page: u64, last_block: u64) -> (u64, u64) {
+    if last_block == start_block {
+        (start_block, page + 1)
+    } else {
+        (last_block, 1)
+    }
+}
+
+/// Local index of the transaction history of the registered addresses:
+/// the records are synced incrementally from the explorer and stored in an embedded database,
+/// so that they can be queried instantly and offline
+pub struct LocalHistory {
+    db: sled::Db,
+    api_key: String,
+    rt: tokio::runtime::Runtime,
+}
+
+impl LocalHistory {
+    pub(crate) fn open(path: impl AsRef<Path>, api_key: String) -> Result<Self> {
+        Self::with_db(sled::open(path)?, api_key)
+    }
+
+    fn with_db(db: sled::Db, api_key: String) -> Result<Self> {
+        Ok(Self {
+            db,
+            api_key,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// registers the address to be synced (it's not synced until `sync` is called)
+    pub fn register_address(&mut self, address: String) -> Result<()> {
+        let address = parse_address(&address)?;
+        let addresses = self.db.open_tree(ADDRESSES_TREE)?;
+        if !addresses.contains_key(address.as_bytes())? {
+            addresses.insert(
+                address.as_bytes(),
+                serde_json::to_vec(&SyncState::default())?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// unregisters the address and removes its stored records
+    pub fn unregister_address(&mut self, address: String) -> Result<()> {
+        let address = parse_address(&address)?;
+        self.db
+            .open_tree(ADDRESSES_TREE)?
+            .remove(address.as_bytes())?;
+        self.clear_records(&address)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// returns the registered addresses
+    pub fn registered_addresses(&self) -> Result<Vec<String>> {
+        Ok(self
+            .addresses()?
+            .iter()
+            .map(|address| format!("{address:?}"))
+            .collect())
+    }
+
+    /// syncs the new records of all the registered addresses,
+    /// returns the number of the newly stored records
+    pub fn sync(&mut self) -> Result<u64> {
+        let mut stored = 0;
+        for address in self.addresses()? {
+            stored += self.sync_address(&address)?;
+        }
+        self.db.flush()?;
+        Ok(stored)
+    }
+
+    /// removes all the stored records and syncs the registered addresses from the beginning,
+    /// returns the number of the stored records
+    pub fn resync(&mut self) -> Result<u64> {
+        let addresses = self.db.open_tree(ADDRESSES_TREE)?;
+        for address in self.addresses()? {
+            self.clear_records(&address)?;
+            addresses.insert(
+                address.as_bytes(),
+                serde_json::to_vec(&SyncState::default())?,
+            )?;
+        }
+        self.sync()
+    }
+
+    /// returns the stored transactions matching the filter
+    /// (the contract address is not used)
+    pub fn query_local_history(&self, filter: &LocalHistoryFilter) -> Result<Vec<RawTxDetail>> {
+        self.query(TRANSACTIONS_TREE, filter, |_| true)
+    }
+
+    /// returns the stored token transfers matching the filter
+    pub fn query_local_token_transfers(
+        &self,
+        filter: &LocalHistoryFilter,
+    ) -> Result<Vec<RawTokenTxDetail>> {
+        let contract_address = if filter.contract_address.is_empty() {
+            None
+        } else {
+            Some(parse_address(&filter.contract_address)?)
+        };
+        self.query(
+            TOKEN_TRANSFERS_TREE,
+            filter,
+            |transfer: &RawTokenTxDetail| {
+                contract_address
+                    .map(|contract| {
+                        parse_address(&transfer.contract_address).ok() == Some(contract)
+                    })
+                    .unwrap_or(true)
+            },
+        )
+    }
+
+    fn addresses(&self) -> Result<Vec<Address>> {
+        self.db
+            .open_tree(ADDRESSES_TREE)?
+            .iter()
+            .keys()
+            .map(|key| Ok(Address::from_slice(&key?)))
+            .collect()
+    }
+
+    fn sync_state(&self, address: &Address) -> Result<SyncState> {
+        match self.db.open_tree(ADDRESSES_TREE)?.get(address.as_bytes())? {
+            Some(state) => Ok(serde_json::from_slice(&state)?),
+            None => Ok(SyncState::default()),
+        }
+    }
+
+    fn sync_address(&self, address: &Address) -> Result<u64> {
+        let mut state = self.sync_state(address)?;
+        let (mut stored, last_block) = self.sync_records::<NormalTransaction, RawTxDetail>(
+            "txlist",
+            TRANSACTIONS_TREE,
+            address,
+            state.transactions,
+        )?;
+        state.transactions = last_block;
+        let (new, last_block) = self.sync_records::<ERC20TokenTransferEvent, RawTokenTxDetail>(
+            "tokentx",
+            TOKEN_TRANSFERS_TREE,
+            address,
+            state.erc20_transfers,
+        )?;
+        stored += new;
+        state.erc20_transfers = last_block;
+        let (new, last_block) = self.sync_records::<ERC721TokenTransferEvent, RawTokenTxDetail>(
+            "tokennfttx",
+            TOKEN_TRANSFERS_TREE,
+            address,
+            state.erc721_transfers,
+        )?;
+        stored += new;
+        state.erc721_transfers = last_block;
+        self.db
+            .open_tree(ADDRESSES_TREE)?
+            .insert(address.as_bytes(), serde_json::to_vec(&state)?)?;
+        Ok(stored)
+    }
+
+    /// fetches the records from `start_block` page by page,
+    /// returns the number of the newly stored records and the last synced block
+    fn sync_records<E, D>(
+        &self,
+        action: &str,
+        tree: &str,
+        address: &Address,
+        start_block: u64,
+    ) -> Result<(u64, u64)>
+    where
+        E: DeserializeOwned,
+        D: Record + for<'a> From<&'a E>,
+    {
+        let mut stored = 0;
+        let mut start_block = start_block;
+        let mut page = 1;
+        let mut last_block = start_block;
+        loop {
+            let params = page_params(action, *address, start_block, page);
+            let events: Vec<E> =
+                self.rt
+                    .block_on(get_explorer_result(action, params, self.api_key.clone()))?;
+            let records: Vec<D> = events.iter().map(D::from).collect();
+            stored += self.store(tree, address, &records)?;
+            if let Some(record) = records.last() {
+                last_block = record.block_no();
+            }
+            if (records.len() as u64) < PAGE_SIZE {
+                return Ok((stored, last_block));
+            }
+            (start_block, page) = next_page(start_block, page, last_block);
+        }
+    }
+
+    /// stores the records, returns the number of the new ones
+    fn store<D: Record>(&self, tree: &str, address: &Address, records: &[D]) -> Result<u64> {
+        let tree = self.db.open_tree(tree)?;
+        let mut stored = 0;
+        for record in records {
+            let value = serde_json::to_vec(record)?;
+            if tree.insert(record_key(address, record), value)?.is_none() {
+                stored += 1;
+            }
+        }
+        Ok(stored)
+    }
+
+    fn clear_records(&self, address: &Address) -> Result<()> {
+        for tree in [TRANSACTIONS_TREE, TOKEN_TRANSFERS_TREE] {
+            let tree = self.db.open_tree(tree)?;
+            for key in tree.scan_prefix(address.as_bytes()).keys() {
+                tree.remove(key?)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn query<D: Record>(
+        &self,
+        tree: &str,
+        filter: &LocalHistoryFilter,
+        matches: impl Fn(&D) -> bool,
+    ) -> Result<Vec<D>> {
+        let addresses = if filter.address.is_empty() {
+            self.addresses()?
+        } else {
+            vec![parse_address(&filter.address)?]
+        };
+        let tree = self.db.open_tree(tree)?;
+        // ordered by blocks; the records of several addresses (e.g. a transfer between them)
+        // are only returned once
+        let mut found = BTreeMap::new();
+        for address in addresses {
+            let start = block_prefix(&address, filter.from_block);
+            let range = match filter.to_block {
+                0 | u64::MAX => tree.range(start..),
+                to_block => tree.range(start..block_prefix(&address, to_block + 1)),
+            };
+            for item in range {
+                let (key, value) = item?;
+                if !key.starts_with(address.as_bytes()) {
+                    break;
+                }
+                let mut record: D = serde_json::from_slice(&value)?;
+                record.normalize();
+                if matches(&record) {
+                    found.insert((record.block_no(), key[PREFIX_LEN..].to_vec()), record);
+                }
+            }
+        }
+        let records = found.into_values();
+        let limit = match filter.limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        Ok(if filter.newest_first {
+            records.rev().take(limit).collect()
+        } else {
+            records.take(limit).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// a normalized transaction of 1 CRO (the records are stored as they are)
+    fn tx(hash: &str, block_no: u64) -> RawTxDetail {
+        RawTxDetail {
+            hash: hash.to_owned(),
+            value: "1000000000000000000".to_owned(),
+            raw_value: "1000000000000000000".to_owned(),
+            formatted_value: "1".to_owned(),
+            value_kind: ValueKind::Native,
+            block_no,
+            timestamp: "1646318156".to_owned(),
+            timestamp_epoch: 1646318156,
+            timestamp_iso: "2022-03-03T14:35:56Z".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// a normalized transfer of an NFT of the contract
+    fn transfer(hash: &str, block_no: u64, contract_address: &str) -> RawTokenTxDetail {
+        RawTokenTxDetail {
+            hash: hash.to_owned(),
+            value: "1".to_owned(),
+            raw_value: "1".to_owned(),
+            formatted_value: "1".to_owned(),
+            value_kind: ValueKind::NonFungible,
+            block_no,
+            contract_address: contract_address.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    const ADDRESS: &str = "0x841a15D12aEc9c6039FD132c2FbFF112eD355700";
+    const OTHER: &str = "0x0000000000000000000000000000000000000001";
+    const CONTRACT: &str = "0x0000000000000000000000000000000000000002";
+
+    fn filter(address: &str) -> LocalHistoryFilter {
+        LocalHistoryFilter {
+            address: address.to_owned(),
+            contract_address: String::default(),
+            from_block: 0,
+            to_block: 0,
+            limit: 0,
+            newest_first: false,
+        }
+    }
+
+    #[test]
+    fn test_local_history_query() {
+        let db = sled::Config::new().temporary(true).open().expect("db");
+        let mut history = LocalHistory::with_db(db, "".into()).expect("history");
+        history.register_address(ADDRESS.into()).unwrap();
+        history.register_address(OTHER.into()).unwrap();
+        assert_eq!(history.registered_addresses().unwrap().len(), 2);
+
+        let address = parse_address(ADDRESS).unwrap();
+        let other = parse_address(OTHER).unwrap();
+        let stored = history
+            .store(TRANSACTIONS_TREE, &address, &[tx("b", 12), tx("a", 10)])
+            .unwrap();
+        assert_eq!(stored, 2);
+        // fetched again: not duplicated
+        let stored = history
+            .store(TRANSACTIONS_TREE, &address, &[tx("b", 12), tx("c", 300)])
+            .unwrap();
+        assert_eq!(stored, 1);
+        // a transaction between the registered addresses
+        history
+            .store(TRANSACTIONS_TREE, &other, &[tx("a", 10)])
+            .unwrap();
+
+        let all = history.query_local_history(&filter("")).unwrap();
+        assert_eq!(all, vec![tx("a", 10), tx("b", 12), tx("c", 300)]);
+        let mut range = filter(ADDRESS);
+        range.from_block = 11;
+        range.to_block = 12;
+        assert_eq!(
+            history.query_local_history(&range).unwrap(),
+            vec![tx("b", 12)]
+        );
+        let mut latest = filter(ADDRESS);
+        latest.newest_first = true;
+        latest.limit = 2;
+        assert_eq!(
+            history.query_local_history(&latest).unwrap(),
+            vec![tx("c", 300), tx("b", 12)]
+        );
+
+        history
+            .store(
+                TOKEN_TRANSFERS_TREE,
+                &address,
+                &[transfer("d", 20, CONTRACT), transfer("e", 21, OTHER)],
+            )
+            .unwrap();
+        let mut by_contract = filter(ADDRESS);
+        by_contract.contract_address = CONTRACT.into();
+        assert_eq!(
+            history.query_local_token_transfers(&by_contract).unwrap(),
+            vec![transfer("d", 20, CONTRACT)]
+        );
+
+        history.unregister_address(ADDRESS.into()).unwrap();
+        assert_eq!(history.registered_addresses().unwrap().len(), 1);
+        assert!(history
+            .query_local_token_transfers(&filter(ADDRESS))
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            history.query_local_history(&filter("")).unwrap(),
+            vec![tx("a", 10)]
+        );
+    }
+
+    #[test]
+    fn test_local_history_records() {
+        let db = sled::Config::new().temporary(true).open().expect("db");
+        let history = LocalHistory::with_db(db, "".into()).expect("history");
+        let address = parse_address(ADDRESS).unwrap();
+
+        // stored before the normalized fields were added
+        let old = || RawTxDetail {
+            value: "1500000000000000000".to_owned(),
+            raw_value: String::default(),
+            formatted_value: String::default(),
+            value_kind: ValueKind::Unknown,
+            timestamp_epoch: 0,
+            timestamp_iso: String::default(),
+            ..tx("a", 10)
+        };
+        history
+            .store(TRANSACTIONS_TREE, &address, &[old()])
+            .unwrap();
+        let mut labelled = tx("A", 10);
+        labelled.display_name = "friend".to_owned();
+        // the same transaction (the other fields aren't part of its key)
+        assert_eq!(
+            history
+                .store(TRANSACTIONS_TREE, &address, &[labelled])
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            history.query_local_history(&filter(ADDRESS)).unwrap()[0].display_name,
+            "friend"
+        );
+        history.clear_records(&address).unwrap();
+        history
+            .store(TRANSACTIONS_TREE, &address, &[old()])
+            .unwrap();
+        let stored = history.query_local_history(&filter(ADDRESS)).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].formatted_value, "1.5");
+        assert_eq!(stored[0].value_kind, ValueKind::Native);
+        assert_eq!(stored[0].timestamp_iso, "2022-03-03T14:35:56Z");
+
+        // two NFTs transferred in one transaction
+        let mut first = transfer("d", 20, CONTRACT);
+        first.token_id = "1".to_owned();
+        let mut second = transfer("d", 20, CONTRACT);
+        second.token_id = "2".to_owned();
+        assert_eq!(
+            history
+                .store(TOKEN_TRANSFERS_TREE, &address, &[first, second])
+                .unwrap(),
+            2
+        );
+        let stored = history
+            .query_local_token_transfers(&filter(ADDRESS))
+            .unwrap();
+        let token_ids: Vec<_> = stored
+            .iter()
+            .map(|transfer| transfer.token_id.as_str())
+            .collect();
+        assert_eq!(token_ids, ["1", "2"]);
+    }
+
+    #[test]
+    fn test_next_page() {
+        // a page ending in a later block: from that block again
+        assert_eq!(next_page(10, 1, 12), (12, 1));
+        // a page of one block: the next page of that block
+        assert_eq!(next_page(12, 1, 12), (12, 2));
+        assert_eq!(next_page(12, 2, 15), (15, 1));
+    }
+}
@@ -0,0 +1,215 @@
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+
+use crate::devwallet::{DevWallet, DEFAULT_MNEMONIC};
+use crate::ffi::LocalNodeConfig;
+
+const DEFAULT_ANVIL_PATH: &str = "anvil";
+const DEFAULT_CHAIN_ID: u64 = crate::environment::LOCAL_CHAIN_ID;
+const DEFAULT_ACCOUNTS: u32 = 10;
+const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// the interval of the checks whether the node is listening
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// a free local port (the OS's choice)
+fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+        .local_addr()?
+        .port())
+}
+
+/// the number of the prefunded accounts
+fn accounts(config: &LocalNodeConfig) -> u32 {
+    if config.accounts == 0 {
+        DEFAULT_ACCOUNTS
+    } else {
+        config.accounts
+    }
+}
+
+/// the arguments of Anvil
+fn anvil_args(config: &LocalNodeConfig, port: u16, chain_id: u64, mnemonic: &str) -> Vec<String> {
+    let mut args = vec![
+        "--port".to_owned(),
+        port.to_string(),
+        "--chain-id".to_owned(),
+        chain_id.to_string(),
+        "--accounts".to_owned(),
+        accounts(config).to_string(),
+        "--mnemonic".to_owned(),
+        mnemonic.to_owned(),
+    ];
+    if config.block_time_secs > 0 {
+        args.push("--block-time".to_owned());
+        args.push(config.block_time_secs.to_string());
+    }
+    args
+}
+
+/// A local Anvil node with prefunded accounts, for the end-to-end transaction tests
+/// (only with the `test-utils` feature); it's killed when dropped
+pub struct LocalNode {
+    child: Option<Child>,
+    port: u16,
+    chain_id: u64,
+    wallet: DevWallet,
+}
+
+impl LocalNode {
+    /// spawns Anvil and waits until it's listening
+    pub(crate) fn spawn(config: &LocalNodeConfig) -> Result<Self> {
+        let port = if config.port == 0 {
+            free_port()?
+        } else {
+            config.port
+        };
+        let chain_id = if config.chain_id == 0 {
+            DEFAULT_CHAIN_ID
+        } else {
+            config.chain_id
+        };
+        let mnemonic = if config.mnemonic.is_empty() {
+            DEFAULT_MNEMONIC
+        } else {
+            config.mnemonic.as_str()
+        };
+        let args = anvil_args(config, port, chain_id, mnemonic);
+        let wallet = DevWallet::new(mnemonic, accounts(config), chain_id)?;
+        let path = if config.anvil_path.is_empty() {
+            DEFAULT_ANVIL_PATH
+        } else {
+            config.anvil_path.as_str()
+        };
+        let child = Command::new(path)
+            .args(&args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("couldn't start {path} (is Anvil installed?): {e}"))?;
+        let mut node = Self {
+            child: Some(child),
+            port,
+            chain_id,
+            wallet,
+        };
+        let timeout = if config.startup_timeout_ms == 0 {
+            DEFAULT_STARTUP_TIMEOUT
+        } else {
+            Duration::from_millis(config.startup_timeout_ms)
+        };
+        node.wait_listening(timeout)?;
+        Ok(node)
+    }
+
+    fn wait_listening(&mut self, timeout: Duration) -> Result<()> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, self.port));
+        let start = Instant::now();
+        loop {
+            if TcpStream::connect_timeout(&address, POLL_INTERVAL).is_ok() {
+                return Ok(());
+            }
+            if let Some(status) = self.child.as_mut().and_then(|c| c.try_wait().ok()?) {
+                self.child = None;
+                return Err(anyhow!("anvil exited ({status}) on the port {}", self.port));
+            }
+            if start.elapsed() > timeout {
+                self.stop();
+                return Err(anyhow!(
+                    "timed out waiting for anvil on the port {}",
+                    self.port
+                ));
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// the JSON-RPC url
+    pub fn endpoint(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// the addresses of the prefunded accounts
+    pub fn addresses(&self) -> Vec<String> {
+        self.wallet.addresses()
+    }
+
+    /// the private keys (hexadecimal, 0x-prefixed) of the prefunded accounts
+    pub fn private_keys(&self) -> Vec<String> {
+        (0..self.addresses().len() as u32)
+            .filter_map(|index| self.wallet.private_key(index).ok())
+            .map(|key| format!("0x{}", hex::encode(key)))
+            .collect()
+    }
+
+    /// kills the node
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+
+    /// whether the node is running
+    pub fn is_running(&mut self) -> bool {
+        match self.child.as_mut().map(|child| child.try_wait()) {
+            Some(Ok(None)) => true,
+            Some(_) => {
+                self.child = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for LocalNode {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_anvil_args() {
+        let config = LocalNodeConfig {
+            block_time_secs: 2,
+            ..Default::default()
+        };
+        let port = free_port().unwrap();
+        assert_ne!(port, 0);
+        let args = anvil_args(&config, port, DEFAULT_CHAIN_ID, DEFAULT_MNEMONIC);
+        assert_eq!(
+            args[..6],
+            [
+                "--port",
+                &port.to_string(),
+                "--chain-id",
+                "31337",
+                "--accounts",
+                "10"
+            ]
+        );
+        assert_eq!(args[8..], ["--block-time", "2"]);
+
+        let config = LocalNodeConfig {
+            anvil_path: "/nonexistent/anvil".into(),
+            ..Default::default()
+        };
+        let error = LocalNode::spawn(&config).err().unwrap().to_string();
+        assert!(error.contains("is Anvil installed?"));
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use ethers::providers::{Middleware, ProviderError, RpcError};
+use ethers::types::{Address, Filter, Log, H256};
+use serde::{Deserialize, Serialize};
+
+use crate::abi::EventDecoder;
+use crate::ffi::{DecodedEvent, LogScannerCallback};
+use crate::{chainstate, parse_address, rpc};
+
+/// the initial (and the largest) number of blocks queried at once
+pub(crate) const MAX_RANGE: u64 = 2000;
+
+/// The position of the scanner (persisted after each scanned range)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    /// the next block to be scanned
+    pub(crate) next_block: u64,
+    /// the current size of the queried block ranges
+    pub(crate) range: u64,
+}
+
+impl Checkpoint {
+    /// parses a persisted checkpoint (its range must be between 1 and `MAX_RANGE`,
+    /// or the scanning would never advance or be rejected)
+    pub(crate) fn from_json(json: &str) -> Result<Self> {
+        let checkpoint: Self = serde_json::from_str(json)?;
+        if !(1..=MAX_RANGE).contains(&checkpoint.range) {
+            anyhow::bail!("invalid checkpoint range: {}", checkpoint.range);
+        }
+        Ok(checkpoint)
+    }
+
+    /// the size after a successful query: it grows back towards `MAX_RANGE`
+    pub(crate) fn on_success(&mut self, scanned: u64) {
+        self.next_block += scanned;
+        self.range = (self.range * 2).min(MAX_RANGE);
+    }
+
+    /// the size after the provider rejected the range (e.g. too many results),
+    /// returns false if it can't be reduced
+    pub(crate) fn on_limit(&mut self) -> bool {
+        if self.range <= 1 {
+            return false;
+        }
+        self.range /= 2;
+        true
+    }
+}
+
+/// whether the provider rejected the query, so that it may succeed with a smaller range
+pub(crate) fn is_limit_error(error: &ProviderError) -> bool {
+    error.is_error_response()
+}
+
+impl DecodedEvent {
+    fn new(log: Log, decoder: &EventDecoder) -> Self {
+        let (name, params) = decoder.decode(&log.topics, &log.data).unwrap_or_default();
+        Self {
+            address: format!("{:?}", log.address),
+            block_no: log.block_number.unwrap_or_default().as_u64(),
+            tx_hash: format!("{:?}", log.transaction_hash.unwrap_or_default()),
+            log_index: log.log_index.unwrap_or_default().as_u64(),
+            topics: log
+                .topics
+                .iter()
+                .map(|topic| format!("{topic:?}"))
+                .collect(),
+            data: format!("0x{}", hex::encode(&log.data)),
+            name,
+            params_json: params.to_string(),
+        }
+    }
+}
+
+/// Scans the event logs of contracts (via `eth_getLogs`) in block ranges,
+/// reduces the ranges rejected by the provider, and keeps a checkpoint,
+/// so that the scanning resumes where it stopped
+pub struct LogScanner {
+    provider: rpc::RpcProvider,
+    addresses: Vec<Address>,
+    decoder: EventDecoder,
+    checkpoint: Checkpoint,
+    /// where the checkpoint is persisted (none if empty)
+    checkpoint_path: Option<PathBuf>,
+    rt: tokio::runtime::Runtime,
+}
+
+impl LogScanner {
+    pub(crate) fn new(
+        rpc_url: &str,
+        abi_json: &str,
+        contract_addresses: &[String],
+        start_block: u64,
+        checkpoint_path: String,
+    ) -> Result<Self> {
+        let checkpoint_path = (!checkpoint_path.is_empty()).then(|| PathBuf::from(checkpoint_path));
+        let checkpoint = match &checkpoint_path {
+            Some(path) if path.exists() => Checkpoint::from_json(&std::fs::read_to_string(path)?)?,
+            _ => Checkpoint {
+                next_block: start_block,
+                range: MAX_RANGE,
+            },
+        };
+        Ok(Self {
+            provider: rpc::provider(rpc_url)?,
+            addresses: contract_addresses
+                .iter()
+                .map(|address| parse_address(address))
+                .collect::<Result<_, _>>()?,
+            decoder: EventDecoder::new(abi_json)?,
+            checkpoint,
+            checkpoint_path,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// the next block to be scanned
+    pub fn next_block(&self) -> u64 {
+        self.checkpoint.next_block
+    }
+
+    /// writes the checkpoint (via a temporary file, so that it's never partially written)
+    fn save_checkpoint(&self) -> Result<()> {
+        if let Some(path) = &self.checkpoint_path {
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_string(&self.checkpoint)?)?;
+            std::fs::rename(tmp, path)?;
+        }
+        Ok(())
+    }
+
+    fn filter(&self, from_block: u64, to_block: u64) -> Filter {
+        let mut filter = Filter::new().from_block(from_block).to_block(to_block);
+        if !self.addresses.is_empty() {
+            filter = filter.address(self.addresses.clone());
+        }
+        let signatures: Vec<H256> = self.decoder.signatures();
+        if !signatures.is_empty() {
+            filter = filter.topic0(signatures);
+        }
+        filter
+    }
+
+    /// scans the logs up to the latest block and calls the callback for each of them
+    /// (in the chain order), returns the number of the scanned logs
+    pub fn scan_blocking(&mut self, callback: &LogScannerCallback) -> Result<u64> {
+        let latest = self.rt.block_on(self.provider.get_block_number())?.as_u64();
+        chainstate::observe_block(&self.provider, latest);
+        let mut count = 0;
+        while self.checkpoint.next_block <= latest {
+            let from_block = self.checkpoint.next_block;
+            let to_block = (from_block + self.checkpoint.range - 1).min(latest);
+            let logs = match self
+                .rt
+                .block_on(self.provider.get_logs(&self.filter(from_block, to_block)))
+            {
+                Ok(logs) => logs,
+                Err(e) if is_limit_error(&e) && self.checkpoint.on_limit() => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for log in logs.into_iter().filter(|log| log.removed != Some(true)) {
+                callback.onEvent(&DecodedEvent::new(log, &self.decoder));
+                count += 1;
+            }
+            self.checkpoint.on_success(to_block - from_block + 1);
+            self.save_checkpoint()?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_range() {
+        let mut checkpoint = Checkpoint {
+            next_block: 100,
+            range: MAX_RANGE,
+        };
+        assert!(checkpoint.on_limit());
+        assert!(checkpoint.on_limit());
+        assert_eq!(checkpoint.range, MAX_RANGE / 4);
+        checkpoint.on_success(MAX_RANGE / 4);
+        assert_eq!(checkpoint.next_block, 100 + MAX_RANGE / 4);
+        assert_eq!(checkpoint.range, MAX_RANGE / 2);
+        checkpoint.on_success(MAX_RANGE / 2);
+        checkpoint.on_success(MAX_RANGE);
+        assert_eq!(checkpoint.range, MAX_RANGE);
+
+        checkpoint.range = 1;
+        assert!(!checkpoint.on_limit());
+    }
+
+    #[test]
+    fn test_checkpoint_from_json() {
+        assert_eq!(
+            Checkpoint::from_json(r#"{"next_block":5,"range":1}"#).unwrap(),
+            Checkpoint {
+                next_block: 5,
+                range: 1
+            }
+        );
+        for range in [0, MAX_RANGE + 1] {
+            let json = format!(r#"{{"next_block":5,"range":{range}}}"#);
+            assert!(Checkpoint::from_json(&json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_persisted() {
+        let path = std::env::temp_dir().join(format!("logscanner-{}.json", uuid::Uuid::new_v4()));
+        let open = || {
+            LogScanner::new(
+                "http://127.0.0.1:1",
+                "",
+                &["0x841a15d12aec9c6039fd132c2fbff112ed355700".to_owned()],
+                42,
+                path.to_string_lossy().into_owned(),
+            )
+            .unwrap()
+        };
+        let mut scanner = open();
+        assert_eq!(scanner.next_block(), 42);
+        scanner.checkpoint.on_success(10);
+        scanner.save_checkpoint().unwrap();
+        // resumes from the saved checkpoint (not from the start block)
+        assert_eq!(open().next_block(), 52);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+use std::io::Read;
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+
+use crate::error::GameSdkError;
+use crate::ffi::{MediaPolicy, VerifiedMedia};
+use crate::grpcweb::ProtoWriter;
+use crate::hashing::sha256;
+use crate::{http, ipfs, netusage};
+
+/// the multicodecs of the CIDs: raw bytes and dag-pb (UnixFS) nodes
+const RAW_CODEC: u64 = 0x55;
+const DAG_PB_CODEC: u64 = 0x70;
+/// the multihash code of SHA-256 and its digest length
+const SHA2_256: u64 = 0x12;
+/// the UnixFS type of the file nodes
+const UNIXFS_FILE: u64 = 2;
+/// the default chunk size of `ipfs add`: the larger files are chunked into a DAG
+const UNIXFS_CHUNK_SIZE: usize = 256 * 1024;
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE32_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+fn base58_decode(text: &str) -> Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![];
+    for c in text.bytes() {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or_else(|| anyhow!("invalid base58 character: {}", c as char))?
+            as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+    Ok(decoded)
+}
+
+/// decodes the lowercase, unpadded RFC 4648 base32
+fn base32_decode(text: &str) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_lowercase())
+            .ok_or_else(|| anyhow!("invalid base32 character: {}", c as char))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| anyhow!("truncated CID"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("invalid varint in CID"))
+}
+
+/// the error of the hashes (or CIDs) the media can't be verified against, distinct
+/// from the mismatch of the media
+fn unsupported(reason: String) -> anyhow::Error {
+    GameSdkError::UnsupportedMediaHash(reason).into()
+}
+
+/// the codec and the SHA-256 digest of the CID (CIDv0 "Qm..." or base32 CIDv1 "b...")
+fn parse_cid(cid: &str) -> Result<(u64, Vec<u8>)> {
+    let (codec, multihash) = if cid.starts_with("Qm") {
+        (DAG_PB_CODEC, base58_decode(cid)?)
+    } else if let Some(encoded) = cid.strip_prefix('b') {
+        let bytes = base32_decode(encoded)?;
+        let mut rest = bytes.as_slice();
+        if read_varint(&mut rest)? != 1 {
+            return Err(unsupported(format!("CID version: {cid}")));
+        }
+        let codec = read_varint(&mut rest)?;
+        (codec, rest.to_vec())
+    } else {
+        return Err(unsupported(format!("CID encoding: {cid}")));
+    };
+    let mut rest = multihash.as_slice();
+    let (code, len) = (read_varint(&mut rest)?, read_varint(&mut rest)?);
+    if code != SHA2_256 || len != 32 || rest.len() != 32 {
+        return Err(unsupported(format!("CID hash: {cid}")));
+    }
+    Ok((codec, rest.to_vec()))
+}
+
+/// the dag-pb node of the file in one UnixFS block (the files up to the 256 KiB chunk)
+fn unixfs_file_node(data: &[u8]) -> Vec<u8> {
+    let mut file = ProtoWriter::default().uint(1, UNIXFS_FILE);
+    if !data.is_empty() {
+        file = file.bytes(2, data);
+    }
+    let file = file.uint(3, data.len() as u64).finish();
+    ProtoWriter::default().bytes(1, &file).finish()
+}
+
+/// checks the data against the expected hash: the hexadecimal SHA-256 digest (optionally
+/// "sha256:" or 0x-prefixed) or the IPFS CID of a raw block or of a single-block UnixFS file;
+/// the CIDs of the chunked UnixFS files (over 256 KiB) are unsupported (not a mismatch)
+pub fn verify_media_hash(data: Vec<u8>, expected_hash: String) -> Result<()> {
+    let expected = expected_hash.trim();
+    let hex_digest = expected
+        .strip_prefix("sha256:")
+        .or_else(|| expected.strip_prefix("0x"))
+        .unwrap_or(expected);
+    let matches = if hex_digest.len() == 64 && hex_digest.bytes().all(|c| c.is_ascii_hexdigit()) {
+        hex::decode(hex_digest)? == sha256(data)
+    } else {
+        let (codec, digest) = parse_cid(expected)?;
+        match codec {
+            RAW_CODEC => digest == sha256(data),
+            DAG_PB_CODEC if data.len() > UNIXFS_CHUNK_SIZE => {
+                return Err(unsupported(format!(
+                    "the CID of a chunked UnixFS file (over 256 KiB) can't be verified: {expected}"
+                )));
+            }
+            DAG_PB_CODEC => digest == sha256(unixfs_file_node(&data)),
+            _ => return Err(unsupported(format!("CID codec: {expected}"))),
+        }
+    };
+    if matches {
+        Ok(())
+    } else {
+        Err(anyhow!("the media doesn't match the hash {expected}"))
+    }
+}
+
+/// whether the content type is allowed (any if the allowed ones are empty)
+fn content_type_allowed(content_type: &str, allowed: &[String]) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    allowed.is_empty()
+        || allowed
+            .iter()
+            .any(|allowed| essence.starts_with(&allowed.to_lowercase()))
+}
+
+/// reads the body of the response up to the maximum size (0 for no limit),
+/// it's recorded in the network usage
+pub(crate) fn read_limited(
+    response: reqwest::blocking::Response,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    let declared_size = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<u64>().ok());
+    if max_size > 0 && declared_size.is_some_and(|size| size > max_size) {
+        return Err(anyhow!("the media is over {max_size} bytes"));
+    }
+    let mut data = vec![];
+    let limit = if max_size > 0 {
+        // one more byte to detect the larger ones without the content length
+        max_size + 1
+    } else {
+        u64::MAX
+    };
+    let read = response.take(limit).read_to_end(&mut data);
+    netusage::record_received(data.len() as u64);
+    read?;
+    if max_size > 0 && data.len() as u64 > max_size {
+        return Err(anyhow!("the media is over {max_size} bytes"));
+    }
+    Ok(data)
+}
+
+/// downloads the media of the url (the `ipfs://` uris via the gateway of the policy)
+/// within the limits of the policy, and verifies it against the expected hash
+/// (the CID of the `ipfs://` uri if it's empty)
+pub(crate) fn download_verified_media(
+    url: &str,
+    expected_hash: &str,
+    policy: &MediaPolicy,
+) -> Result<VerifiedMedia> {
+    let expected_hash = match (expected_hash.is_empty(), url.strip_prefix("ipfs://")) {
+        (true, Some(path)) => path
+            .trim_start_matches("ipfs/")
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_owned(),
+        (true, None) => return Err(anyhow!("no expected hash of the media {url}")),
+        (false, _) => expected_hash.to_owned(),
+    };
+    let download_url = ipfs::ipfs_uri_to_gateway_url(url.to_owned(), policy.gateway_url.clone());
+    let client = http::blocking_client()?;
+    let response = http::send(&client, client.get(&download_url))?.error_for_status()?;
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_owned();
+    if !content_type_allowed(&content_type, &policy.allowed_content_types) {
+        return Err(anyhow!("the content type {content_type} is not allowed"));
+    }
+    let data = read_limited(response, policy.max_size)?;
+    verify_media_hash(data.clone(), expected_hash)?;
+    Ok(VerifiedMedia {
+        sha256: hex::encode(sha256(data.clone())),
+        content_type,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn base32_encode(bytes: &[u8]) -> String {
+        let bits: Vec<bool> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+            .collect();
+        bits.chunks(5)
+            .map(|chunk| {
+                let index = (0..5).fold(0, |index, i| {
+                    index << 1 | usize::from(chunk.get(i).copied().unwrap_or(false))
+                });
+                BASE32_ALPHABET[index] as char
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_media_hash() {
+        let data = b"hello world\n".to_vec();
+        // `ipfs add` of the file (CIDv0)
+        verify_media_hash(
+            data.clone(),
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o".into(),
+        )
+        .unwrap();
+        assert!(verify_media_hash(
+            b"hello world".to_vec(),
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o".into()
+        )
+        .is_err());
+        let digest = hex::encode(sha256(data.clone()));
+        verify_media_hash(data.clone(), format!("sha256:{digest}")).unwrap();
+        verify_media_hash(data.clone(), digest.to_uppercase()).unwrap();
+        assert!(verify_media_hash(data, "not a hash".into()).is_err());
+
+        // the chunked files are unsupported, not mismatched
+        let error = verify_media_hash(
+            vec![0; UNIXFS_CHUNK_SIZE + 1],
+            "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o".into(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::UnsupportedMediaHash(_))
+        ));
+        // the raw blocks of any size are hashed as a whole
+        let large = vec![0; UNIXFS_CHUNK_SIZE + 1];
+        let cid = format!(
+            "b{}",
+            base32_encode(&[&[1, 0x55, 0x12, 32][..], &sha256(large.clone())].concat())
+        );
+        verify_media_hash(large, cid).unwrap();
+    }
+
+    #[test]
+    fn test_content_type_allowed() {
+        let allowed = vec!["image/".to_owned(), "video/mp4".to_owned()];
+        assert!(content_type_allowed("image/png", &allowed));
+        assert!(content_type_allowed("Video/MP4; codecs=avc1", &allowed));
+        assert!(!content_type_allowed("text/html", &allowed));
+        assert!(content_type_allowed("text/html", &[]));
+        assert_eq!(base58_decode("1112").unwrap(), vec![0, 0, 0, 1]);
+        assert_eq!(base32_decode("mzxw6ytb").unwrap(), b"fooba");
+    }
+}
@@ -0,0 +1,388 @@
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use anyhow::{anyhow, Result};
+
+use crate::ffi::{MemoryUsage, SubsystemMemory};
+
+/// the allocations outside of the scopes of the SDK modules
+pub(crate) const OTHER: u8 = 0;
+/// the SDK's HTTP requests (the explorer APIs, the faucets...) and their responses
+pub(crate) const HTTP: u8 = 1;
+/// the JSON-RPC requests
+pub(crate) const JSONRPC: u8 = 2;
+/// the WalletConnect 1.0 clients
+pub(crate) const WALLETCONNECT: u8 = 3;
+/// the WalletConnect 2.0 clients
+pub(crate) const WALLETCONNECT2: u8 = 4;
+const SUBSYSTEMS: [&str; 5] = [
+    "other",
+    "http",
+    "jsonrpc",
+    "walletconnect",
+    "walletconnect2",
+];
+
+thread_local! {
+    /// the subsystem of the allocations on this thread
+    static SUBSYSTEM: Cell<u8> = const { Cell::new(OTHER) };
+}
+
+/// the subsystem of the allocations on this thread
+pub(crate) fn current_subsystem() -> u8 {
+    // the thread-local may be destroyed while the thread exits
+    SUBSYSTEM.try_with(Cell::get).unwrap_or(OTHER)
+}
+
+/// sets the subsystem of the allocations on this thread, returns the previous one
+fn set_subsystem(subsystem: u8) -> u8 {
+    SUBSYSTEM
+        .try_with(|current| current.replace(subsystem))
+        .unwrap_or(OTHER)
+}
+
+/// the allocations on this thread are counted for the subsystem (e.g. on the threads
+/// of a runtime, outside of the scopes)
+pub(crate) fn tag_thread(subsystem: u8) {
+    set_subsystem(subsystem);
+}
+
+/// The scope of a subsystem: the allocations on this thread are counted for it
+/// until the scope is dropped (then for the enclosing one)
+pub(crate) struct Scope {
+    previous: u8,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        set_subsystem(self.previous);
+    }
+}
+
+/// the allocations on this thread are counted for the subsystem while the scope lives
+/// (e.g. the SDK's runtimes created in it count theirs for it too)
+pub(crate) fn scope(subsystem: u8) -> Scope {
+    Scope {
+        previous: set_subsystem(subsystem),
+    }
+}
+
+/// The future whose allocations are counted for the subsystem
+/// (its polls may run on any thread of the runtime, so a `Scope` can't be held across them)
+pub(crate) struct Scoped<F> {
+    subsystem: u8,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let _scope = scope(self.subsystem);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// the allocations of the future are counted for the subsystem
+pub(crate) fn scoped<F: Future>(subsystem: u8, future: F) -> Scoped<F> {
+    Scoped {
+        subsystem,
+        future: Box::pin(future),
+    }
+}
+
+/// The counters of the allocations (they don't allocate)
+struct Stats {
+    current_bytes: AtomicUsize,
+    peak_bytes: AtomicUsize,
+    allocations: AtomicU64,
+    deallocations: AtomicU64,
+    /// by subsystem: the allocated bytes (the freed ones included)
+    allocated_bytes: [AtomicU64; SUBSYSTEMS.len()],
+    subsystem_allocations: [AtomicU64; SUBSYSTEMS.len()],
+}
+
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            current_bytes: AtomicUsize::new(0),
+            peak_bytes: AtomicUsize::new(0),
+            allocations: AtomicU64::new(0),
+            deallocations: AtomicU64::new(0),
+            allocated_bytes: [const { AtomicU64::new(0) }; SUBSYSTEMS.len()],
+            subsystem_allocations: [const { AtomicU64::new(0) }; SUBSYSTEMS.len()],
+        }
+    }
+
+    #[cfg_attr(not(feature = "memory-stats"), allow(dead_code))]
+    fn record_alloc(&self, subsystem: u8, size: usize) {
+        let current = self.current_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_bytes.fetch_max(current, Ordering::Relaxed);
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        let subsystem = subsystem as usize;
+        self.allocated_bytes[subsystem].fetch_add(size as u64, Ordering::Relaxed);
+        self.subsystem_allocations[subsystem].fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "memory-stats"), allow(dead_code))]
+    fn record_dealloc(&self, size: usize) {
+        self.current_bytes.fetch_sub(size, Ordering::Relaxed);
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            current_bytes: self.current_bytes.load(Ordering::Relaxed) as u64,
+            peak_bytes: self.peak_bytes.load(Ordering::Relaxed) as u64,
+            allocations: self.allocations.load(Ordering::Relaxed),
+            deallocations: self.deallocations.load(Ordering::Relaxed),
+            subsystems: SUBSYSTEMS
+                .iter()
+                .enumerate()
+                .map(|(i, subsystem)| SubsystemMemory {
+                    subsystem: subsystem.to_string(),
+                    allocated_bytes: self.allocated_bytes[i].load(Ordering::Relaxed),
+                    allocations: self.subsystem_allocations[i].load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+}
+
+static STATS: Stats = Stats::new();
+
+/// the platform allocator: `alloc(size, align, user_data)`
+pub type AllocFn = unsafe extern "C" fn(usize, usize, *mut c_void) -> *mut c_void;
+/// the platform allocator: `free(ptr, size, align, user_data)`
+pub type FreeFn = unsafe extern "C" fn(*mut c_void, usize, usize, *mut c_void);
+
+/// nothing was allocated yet
+const UNUSED: u8 = 0;
+/// the system allocator is used
+const SYSTEM: u8 = 1;
+/// the hooks are used
+const HOOKED: u8 = 2;
+
+/// The platform allocator hooks (they can only be set before the first allocation,
+/// so that the memory is freed by the allocator that allocated it)
+struct Hooks {
+    state: AtomicU8,
+    alloc: AtomicUsize,
+    free: AtomicUsize,
+    user_data: AtomicPtr<c_void>,
+}
+
+static HOOKS: Hooks = Hooks {
+    state: AtomicU8::new(UNUSED),
+    alloc: AtomicUsize::new(0),
+    free: AtomicUsize::new(0),
+    user_data: AtomicPtr::new(std::ptr::null_mut()),
+};
+
+impl Hooks {
+    fn set(&self, alloc: AllocFn, free: FreeFn, user_data: *mut c_void) -> Result<()> {
+        if self.state.load(Ordering::SeqCst) != UNUSED {
+            return Err(anyhow!(
+                "the allocator hooks must be set before the SDK allocates"
+            ));
+        }
+        self.alloc.store(alloc as usize, Ordering::SeqCst);
+        self.free.store(free as usize, Ordering::SeqCst);
+        self.user_data.store(user_data, Ordering::SeqCst);
+        self.state
+            .compare_exchange(UNUSED, HOOKED, Ordering::SeqCst, Ordering::SeqCst)
+            .map_err(|_| anyhow!("the allocator hooks must be set before the SDK allocates"))?;
+        Ok(())
+    }
+
+    /// the hooks if they're set (otherwise the system allocator is used from now on)
+    #[cfg_attr(not(feature = "memory-stats"), allow(dead_code))]
+    fn active(&self) -> Option<(AllocFn, FreeFn, *mut c_void)> {
+        // a plain load once the allocator is chosen (this is on every allocation)
+        let mut state = self.state.load(Ordering::SeqCst);
+        if state == UNUSED {
+            state = match self.state.compare_exchange(
+                UNUSED,
+                SYSTEM,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => SYSTEM,
+                Err(state) => state,
+            };
+        }
+        if state != HOOKED {
+            return None;
+        }
+        // safety: they were set from the function pointers
+        unsafe {
+            Some((
+                std::mem::transmute::<usize, AllocFn>(self.alloc.load(Ordering::SeqCst)),
+                std::mem::transmute::<usize, FreeFn>(self.free.load(Ordering::SeqCst)),
+                self.user_data.load(Ordering::SeqCst),
+            ))
+        }
+    }
+}
+
+/// forwards the allocations of the SDK to the platform allocator (see `Hooks`)
+pub(crate) fn set_allocator_hooks(
+    alloc: AllocFn,
+    free: FreeFn,
+    user_data: *mut c_void,
+) -> Result<()> {
+    if !cfg!(feature = "memory-stats") {
+        return Err(anyhow!("the SDK is built without the memory-stats feature"));
+    }
+    HOOKS.set(alloc, free, user_data)
+}
+
+/// the memory allocated by the SDK
+pub(crate) fn usage() -> Result<MemoryUsage> {
+    if !cfg!(feature = "memory-stats") {
+        return Err(anyhow!("the SDK is built without the memory-stats feature"));
+    }
+    Ok(STATS.usage())
+}
+
+#[cfg(feature = "memory-stats")]
+mod allocator {
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    use super::{current_subsystem, HOOKS, STATS};
+
+    /// The global allocator counting the allocations, with the platform allocator hooks
+    struct SdkAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: SdkAllocator = SdkAllocator;
+
+    unsafe impl GlobalAlloc for SdkAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = match HOOKS.active() {
+                Some((alloc, _, user_data)) => {
+                    alloc(layout.size(), layout.align(), user_data) as *mut u8
+                }
+                None => System.alloc(layout),
+            };
+            if !ptr.is_null() {
+                STATS.record_alloc(current_subsystem(), layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            if HOOKS.active().is_some() {
+                let ptr = self.alloc(layout);
+                if !ptr.is_null() {
+                    std::ptr::write_bytes(ptr, 0, layout.size());
+                }
+                return ptr;
+            }
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                STATS.record_alloc(current_subsystem(), layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            match HOOKS.active() {
+                Some((_, free, user_data)) => {
+                    free(ptr as _, layout.size(), layout.align(), user_data)
+                }
+                None => System.dealloc(ptr, layout),
+            }
+            STATS.record_dealloc(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            if HOOKS.active().is_some() {
+                let new_ptr = self.alloc(new_layout);
+                if !new_ptr.is_null() {
+                    std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                    self.dealloc(ptr, layout);
+                }
+                return new_ptr;
+            }
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                STATS.record_dealloc(layout.size());
+                STATS.record_alloc(current_subsystem(), new_size);
+            }
+            new_ptr
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    unsafe extern "C" fn alloc(_: usize, _: usize, _: *mut c_void) -> *mut c_void {
+        std::ptr::null_mut()
+    }
+
+    unsafe extern "C" fn free(_: *mut c_void, _: usize, _: usize, _: *mut c_void) {}
+
+    #[test]
+    fn test_memory_stats() {
+        let stats = Stats::new();
+        stats.record_alloc(OTHER, 100);
+        stats.record_alloc(HTTP, 50);
+        stats.record_dealloc(100);
+        stats.record_alloc(HTTP, 10);
+        let usage = stats.usage();
+        assert_eq!(usage.current_bytes, 60);
+        assert_eq!(usage.peak_bytes, 150);
+        assert_eq!(usage.allocations, 3);
+        assert_eq!(usage.deallocations, 1);
+        assert_eq!(usage.subsystems[0].allocated_bytes, 100);
+        assert_eq!(usage.subsystems[1].subsystem, "http");
+        assert_eq!(usage.subsystems[1].allocated_bytes, 60);
+        assert_eq!(usage.subsystems[1].allocations, 2);
+
+        // the hooks can't replace the allocator that allocated the memory
+        let hooks = Hooks {
+            state: AtomicU8::new(UNUSED),
+            alloc: AtomicUsize::new(0),
+            free: AtomicUsize::new(0),
+            user_data: AtomicPtr::new(std::ptr::null_mut()),
+        };
+        assert!(hooks.active().is_none());
+        assert!(hooks.set(alloc, free, std::ptr::null_mut()).is_err());
+        let hooks = Hooks {
+            state: AtomicU8::new(UNUSED),
+            ..hooks
+        };
+        hooks.set(alloc, free, std::ptr::null_mut()).unwrap();
+        assert!(hooks.active().is_some());
+    }
+
+    #[test]
+    fn test_memory_scopes() {
+        assert_eq!(current_subsystem(), OTHER);
+        {
+            let _walletconnect = scope(WALLETCONNECT);
+            {
+                let _http = scope(HTTP);
+                assert_eq!(current_subsystem(), HTTP);
+            }
+            assert_eq!(current_subsystem(), WALLETCONNECT);
+        }
+        assert_eq!(current_subsystem(), OTHER);
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let subsystem = rt.block_on(scoped(JSONRPC, async {
+            tokio::task::yield_now().await;
+            current_subsystem()
+        }));
+        assert_eq!(subsystem, JSONRPC);
+        assert_eq!(current_subsystem(), OTHER);
+    }
+}
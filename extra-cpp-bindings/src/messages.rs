@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+use crate::ffi::LocalizedMessage;
+
+/// the English templates of the SDK-surfaced messages by their stable ids;
+/// the `{name}` placeholders are the parameters of the messages
+const CATALOG: &[(&str, &str)] = &[
+    ("error.crypto_pay", "Crypto Pay Error: {error}"),
+    ("error.http", "{message}"),
+    ("error.json", "{message}"),
+    ("error.io", "{message}"),
+    ("error.invalid_wallet_id", "Invalid wallet id"),
+    ("error.invalid_address", "Invalid address: {address}"),
+    ("error.invalid_http_header", "Invalid HTTP header: {header}"),
+    ("error.missing_fixture", "No recorded HTTP fixture: {path}"),
+    (
+        "error.screening_denied",
+        "Address screening denied {address}: {reason}",
+    ),
+    ("error.policy_violation", "Policy violation: {reason}"),
+    (
+        "error.invalid_webhook_signature",
+        "Invalid webhook signature: {reason}",
+    ),
+    (
+        "error.not_signable",
+        "Not signable: {address} is a watch-only wallet",
+    ),
+    (
+        "error.unsupported_method",
+        "Unsupported by the wallet: {method}",
+    ),
+    ("error.fee_too_high", "Fee too high: {reason}"),
+    (
+        "error.unsupported_media_hash",
+        "Unsupported media hash: {reason}",
+    ),
+    ("error.unknown", "{message}"),
+    (
+        "fee.max_fee_per_gas",
+        "the max fee per gas {fee} is over the cap {max}",
+    ),
+    (
+        "fee.max_total_fee",
+        "the total fee {fee} is over the cap {max}",
+    ),
+    (
+        "fee.fee_unset",
+        "the max fee per gas is unset, the fee is chosen by the wallet",
+    ),
+    (
+        "fee.gas_limit_unset",
+        "the gas limit is unset, the total fee is not capped",
+    ),
+    (
+        "fee.over_threshold",
+        "the max fee per gas {fee} is over the warning threshold {threshold}",
+    ),
+    (
+        "fee.priority_over_max",
+        "the max priority fee per gas {priority} is over the max fee per gas {fee}",
+    ),
+    (
+        "policy.max_value_per_tx",
+        "the value {value} is over the limit {max} per transaction",
+    ),
+    (
+        "policy.max_value_per_day",
+        "the value {value} is over the daily limit {max} ({spent} sent today)",
+    ),
+    ("policy.contract_not_allowed", "the contract is not allowed"),
+    (
+        "policy.recipient_unchecked",
+        "the recipient {to} is not allowed (its contract code can't be checked without the JSON-RPC url of the SDK environment)",
+    ),
+    (
+        "policy.method_not_allowed",
+        "the method {selector} is not allowed",
+    ),
+    (
+        "policy.message_signing_not_allowed",
+        "the message signing is not allowed",
+    ),
+    (
+        "policy.digest_signing_not_allowed",
+        "the signing of the raw digests is not allowed",
+    ),
+    ("preview.native_transfer", "Send {value} to {to}"),
+    ("preview.contract_creation", "Deploy a contract"),
+    (
+        "preview.transfer",
+        "Transfer {amount} of {contract} to {to}",
+    ),
+    (
+        "preview.transfer_from",
+        "Transfer {amount} of {contract} from {from} to {to}",
+    ),
+    (
+        "preview.transfer_from_unknown",
+        "Transfer {value} (an amount or a token id) of {contract} from {from} to {to}",
+    ),
+    (
+        "preview.transfer_token",
+        "Transfer token {token_id} of {contract} from {from} to {to}",
+    ),
+    (
+        "preview.transfer_token_amount",
+        "Transfer {amount} of token {token_id} of {contract} from {from} to {to}",
+    ),
+    (
+        "preview.transfer_batch",
+        "Transfer tokens {token_ids} ({amounts}) of {contract} from {from} to {to}",
+    ),
+    (
+        "preview.approve",
+        "Approve {amount} of {contract} to {spender}",
+    ),
+    (
+        "preview.approve_unlimited",
+        "Approve an unlimited amount of {contract} to {spender}",
+    ),
+    (
+        "preview.approve_for_all",
+        "Allow {operator} to manage all tokens of {contract}",
+    ),
+    (
+        "preview.revoke_approval_for_all",
+        "Revoke the approval of {operator} for all tokens of {contract}",
+    ),
+    ("preview.mint", "Mint tokens of {contract}"),
+    ("preview.mint_to", "Mint tokens of {contract} to {to}"),
+    ("preview.mint_amount", "Mint {amount} of {contract} to {to}"),
+    (
+        "preview.mint_token",
+        "Mint token {token_id} of {contract} to {to}",
+    ),
+    ("preview.contract_call", "Call {method} on {contract}"),
+    ("preview.unknown_call", "Call {selector} on {contract}"),
+    ("preview.with_value", "{summary} (sending {value})"),
+];
+
+/// the English template of the message (empty if the id isn't in the catalog)
+fn template(id: &str) -> &'static str {
+    CATALOG
+        .iter()
+        .find(|(catalog_id, _)| *catalog_id == id)
+        .map(|(_, template)| *template)
+        .unwrap_or_default()
+}
+
+/// A piece of a template: the literal text or the name of a parameter
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Param(&'a str),
+}
+
+fn segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Param(&rest[start + 1..start + len]));
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    segments
+}
+
+/// fills the placeholders of the template with the parameters
+/// (the missing ones are left as they are)
+fn render(template: &str, params: &BTreeMap<String, String>) -> String {
+    segments(template)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => text.to_owned(),
+            Segment::Param(name) => params
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| format!("{{{name}}}")),
+        })
+        .collect()
+}
+
+/// A message of the catalog with its parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub id: &'static str,
+    pub params: BTreeMap<String, String>,
+}
+
+impl Message {
+    pub fn new(id: &'static str, params: &[(&str, String)]) -> Self {
+        debug_assert!(!template(id).is_empty(), "{id} is not in the catalog");
+        Self {
+            id,
+            params: params
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// the English text
+    pub fn text(&self) -> String {
+        render(template(self.id), &self.params)
+    }
+
+    /// the JSON object of the parameters
+    pub fn params_json(&self) -> String {
+        serde_json::to_string(&self.params).unwrap_or_default()
+    }
+
+    /// the id and the parameters at the end of an error text,
+    /// e.g. `[error.invalid_address {"address":"0x12"}]`
+    pub fn tag(&self) -> String {
+        format!("[{} {}]", self.id, self.params_json())
+    }
+
+    /// the message of the tag at the start of the text (see `tag`)
+    fn from_tag(text: &str) -> Option<Self> {
+        let (id, rest) = text.strip_prefix('[')?.split_once(' ')?;
+        let (id, _) = *CATALOG.iter().find(|(catalog_id, _)| *catalog_id == id)?;
+        let mut params =
+            serde_json::Deserializer::from_str(rest).into_iter::<BTreeMap<String, String>>();
+        let Some(Ok(parsed)) = params.next() else {
+            return None;
+        };
+        rest[params.byte_offset()..]
+            .starts_with(']')
+            .then_some(Self { id, params: parsed })
+    }
+}
+
+impl From<Message> for LocalizedMessage {
+    fn from(message: Message) -> Self {
+        Self {
+            id: message.id.to_owned(),
+            params_json: message.params_json(),
+            text: message.text(),
+        }
+    }
+}
+
+/// the message of the error text returned by an SDK function: the message of the last tag
+/// of an SDK error in it (the errors may be wrapped by the text around them, e.g. the failed
+/// request), with the policy violations and the too high fees as the violated rules (or caps);
+/// the other errors are "error.unknown" with the `message` parameter
+pub(crate) fn localize_error(error: &str) -> LocalizedMessage {
+    error
+        .rmatch_indices('[')
+        .find_map(|(start, _)| Message::from_tag(&error[start..]))
+        .unwrap_or_else(|| Message::new("error.unknown", &[("message", error.to_owned())]))
+        .into()
+}
+
+/// the JSON object of the English templates by their message ids
+pub(crate) fn catalog_json() -> String {
+    let catalog: Map<String, Value> = CATALOG
+        .iter()
+        .map(|(id, template)| (id.to_string(), Value::String(template.to_string())))
+        .collect();
+    Value::Object(catalog).to_string()
+}
+
+/// fills the placeholders of the (localized) template with the parameters
+/// of the JSON object (e.g. `params_json` of a localized message)
+pub(crate) fn render_message(template: &str, params_json: &str) -> Result<String> {
+    let params: Map<String, Value> = serde_json::from_str(params_json)?;
+    let params = params
+        .into_iter()
+        .map(|(name, value)| match value {
+            Value::String(value) => Ok((name, value)),
+            Value::Number(value) => Ok((name, value.to_string())),
+            Value::Bool(value) => Ok((name, value.to_string())),
+            value => Err(anyhow!("the parameter {name} is not a scalar: {value}")),
+        })
+        .collect::<Result<_>>()?;
+    Ok(render(template, &params))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::GameSdkError;
+
+    #[test]
+    fn test_catalog_matches_errors() {
+        let errors = [
+            (GameSdkError::InvalidWalletId, "error.invalid_wallet_id"),
+            (
+                GameSdkError::InvalidAddress("0x12".into()),
+                "error.invalid_address",
+            ),
+            (
+                GameSdkError::MissingFixture("a/b.json".into()),
+                "error.missing_fixture",
+            ),
+            (
+                GameSdkError::ScreeningDenied {
+                    address: "0x12".into(),
+                    reason: "phishing: reported".into(),
+                },
+                "error.screening_denied",
+            ),
+            (
+                GameSdkError::PolicyViolation(Message::new("policy.contract_not_allowed", &[])),
+                "policy.contract_not_allowed",
+            ),
+            (
+                GameSdkError::InvalidWebhookSignature("expired timestamp".into()),
+                "error.invalid_webhook_signature",
+            ),
+            (
+                GameSdkError::NotSignable("0x12".into()),
+                "error.not_signable",
+            ),
+            (
+                GameSdkError::UnsupportedMethod("eth_signTransaction".into()),
+                "error.unsupported_method",
+            ),
+            (
+                GameSdkError::FeeTooHigh(Message::new(
+                    "fee.max_total_fee",
+                    &[("fee", "2".into()), ("max", "1".into())],
+                )),
+                "fee.max_total_fee",
+            ),
+            (
+                GameSdkError::UnsupportedMediaHash("unsupported CID codec".into()),
+                "error.unsupported_media_hash",
+            ),
+        ];
+        for (error, id) in errors {
+            let message = localize_error(&error.to_string());
+            assert_eq!(message.id, id);
+            // the policy violations and the too high fees are localized as the violated rules
+            if !id.starts_with("policy.") && !id.starts_with("fee.") {
+                assert_eq!(message.text, error.message().text());
+            }
+        }
+        assert_eq!(
+            GameSdkError::PolicyViolation(Message::new("policy.contract_not_allowed", &[]))
+                .to_string(),
+            "Policy violation: the contract is not allowed [policy.contract_not_allowed {}]"
+        );
+
+        // the parameters are read from the tag, not from the text (the reason contains ": ")
+        let error: anyhow::Error = GameSdkError::ScreeningDenied {
+            address: "0x12".into(),
+            reason: "phishing: reported [1]".into(),
+        }
+        .into();
+        // the wrapped errors keep their messages
+        let error = anyhow!("send_typed_transaction error {error} [retried]");
+        let message = localize_error(&error.to_string());
+        assert_eq!(message.id, "error.screening_denied");
+        let params: BTreeMap<String, String> = serde_json::from_str(&message.params_json).unwrap();
+        assert_eq!(params["address"], "0x12");
+        assert_eq!(params["reason"], "phishing: reported [1]");
+        // the texts are localized without the errors (e.g. on another thread)
+        let message =
+            localize_error(r#"Invalid address: 0x34 [error.invalid_address {"address":"0x34"}]"#);
+        assert_eq!(message.id, "error.invalid_address");
+        assert_eq!(message.text, "Invalid address: 0x34");
+        let message = localize_error("Invalid address: 0x34 [error.not_in_catalog {}]");
+        assert_eq!(message.id, "error.unknown");
+
+        let message = localize_error("connection refused");
+        assert_eq!(message.id, "error.unknown");
+        assert_eq!(message.params_json, r#"{"message":"connection refused"}"#);
+    }
+
+    #[test]
+    fn test_render_message() {
+        let message = Message::new(
+            "preview.native_transfer",
+            &[("value", "1000".into()), ("to", "0x12".into())],
+        );
+        assert_eq!(message.text(), "Send 1000 to 0x12");
+        assert_eq!(
+            render_message("Envoyer {value} à {to} {missing}", &message.params_json()).unwrap(),
+            "Envoyer 1000 à 0x12 {missing}"
+        );
+        assert!(render_message("{a}", r#"{"a": [1]}"#).is_err());
+        let catalog: BTreeMap<String, String> = serde_json::from_str(&catalog_json()).unwrap();
+        assert_eq!(catalog.len(), CATALOG.len());
+        assert_eq!(catalog["preview.contract_creation"], "Deploy a contract");
+    }
+}
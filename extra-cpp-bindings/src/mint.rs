@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::Token;
+use serde_json::Value;
+
+use crate::ffi::{IpfsConfig, IpfsUpload, NftMint, NftMintRequest, WalletConnectTxEip155};
+use crate::{ipfs, parse_address};
+
+/// the mint function and its arguments if they're not set
+const DEFAULT_MINT_FUNCTION: &str = "safeMint(address,string)";
+const DEFAULT_MINT_ARGS: &[&str] = &["{to}", "{token_uri}"];
+
+/// the calldata of the mint function (e.g. "mint(address,uint256,uint256,bytes)")
+/// with the arguments (decimal integers, 0x-prefixed addresses and bytes, etc.)
+fn mint_calldata(mint_function: &str, args: &[String]) -> Result<Vec<u8>> {
+    let signature = mint_function.trim();
+    let signature = signature.strip_prefix("function ").unwrap_or(signature);
+    let abi = ethers::abi::parse_abi(&[&format!("function {signature}")])?;
+    let function = abi
+        .functions()
+        .next()
+        .ok_or_else(|| anyhow!("invalid mint function: {mint_function}"))?;
+    if function.inputs.len() != args.len() {
+        return Err(anyhow!(
+            "{} needs {} arguments, got {}",
+            function.name,
+            function.inputs.len(),
+            args.len()
+        ));
+    }
+    let tokens = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(input, arg)| LenientTokenizer::tokenize(&input.kind, arg))
+        .collect::<Result<Vec<Token>, _>>()?;
+    Ok(function.encode_input(&tokens)?)
+}
+
+/// the arguments of the request with the placeholders replaced
+fn mint_args(request: &NftMintRequest, token_uri: &str) -> Vec<String> {
+    let args: Vec<String> = if request.args.is_empty() {
+        DEFAULT_MINT_ARGS
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect()
+    } else {
+        request.args.clone()
+    };
+    args.into_iter()
+        .map(|arg| {
+            arg.replace("{to}", &request.to)
+                .replace("{token_uri}", token_uri)
+                .replace("{token_id}", &request.token_id)
+                .replace("{amount}", &request.amount)
+        })
+        .collect()
+}
+
+/// the metadata JSON object with the `image` of the uploaded image (if any)
+fn metadata_with_image(metadata_json: &str, image: &IpfsUpload) -> Result<String> {
+    let mut metadata: Value = serde_json::from_str(metadata_json)?;
+    let object = metadata
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("the NFT metadata is not a JSON object"))?;
+    if !image.uri.is_empty() {
+        object.insert("image".to_owned(), Value::String(image.uri.clone()));
+    }
+    Ok(metadata.to_string())
+}
+
+/// uploads the image and the metadata JSON (referencing the image) to IPFS
+/// and builds the mint transaction of the NFT with the metadata uri
+pub(crate) fn mint_nft_with_metadata(
+    config: &IpfsConfig,
+    request: &NftMintRequest,
+) -> Result<NftMint> {
+    let contract = parse_address(&request.contract)?;
+    parse_address(&request.to)?;
+    let image = if request.image_path.is_empty() {
+        IpfsUpload::default()
+    } else {
+        ipfs::upload_file(config, &request.image_path)?
+    };
+    let metadata = metadata_with_image(&request.metadata_json, &image)?;
+    let name = if request.token_id.is_empty() {
+        "metadata.json".to_owned()
+    } else {
+        format!("{}.json", request.token_id)
+    };
+    let metadata = ipfs::upload_json(config, &metadata, &name)?;
+    let mint_function = if request.mint_function.is_empty() {
+        DEFAULT_MINT_FUNCTION
+    } else {
+        request.mint_function.as_str()
+    };
+    let data = mint_calldata(mint_function, &mint_args(request, &metadata.uri))?;
+    Ok(NftMint {
+        image,
+        metadata,
+        transaction: WalletConnectTxEip155 {
+            to: format!("{contract:?}"),
+            data,
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mint_calldata() {
+        let request = NftMintRequest {
+            contract: "0x939d7350c54228e4958e05b65512c4a5bb6a2acc".into(),
+            to: "0x0101010101010101010101010101010101010101".into(),
+            image_path: String::new(),
+            metadata_json: r#"{"name": "Sword"}"#.into(),
+            mint_function: String::new(),
+            args: vec![],
+            token_id: "7".into(),
+            amount: "2".into(),
+        };
+        let data =
+            mint_calldata(DEFAULT_MINT_FUNCTION, &mint_args(&request, "ipfs://bafy1")).unwrap();
+        let abi = ethers::abi::parse_abi(&["function safeMint(address to, string uri)"]).unwrap();
+        let function = abi.function("safeMint").unwrap();
+        assert_eq!(data[..4], function.short_signature());
+        let args = function.decode_input(&data[4..]).unwrap();
+        assert_eq!(args[1], Token::String("ipfs://bafy1".into()));
+
+        let args = vec![
+            "{to}".to_owned(),
+            "{token_id}".to_owned(),
+            "{amount}".to_owned(),
+            "0x".to_owned(),
+        ];
+        let request = NftMintRequest { args, ..request };
+        let data = mint_calldata(
+            "function mint(address,uint256,uint256,bytes)",
+            &mint_args(&request, ""),
+        )
+        .unwrap();
+        assert_eq!(data.len(), 4 + 32 * 5);
+        assert!(mint_calldata("mint(address)", &mint_args(&request, "")).is_err());
+
+        let image = IpfsUpload {
+            uri: "ipfs://bafyimage".into(),
+            ..Default::default()
+        };
+        let metadata = metadata_with_image(&request.metadata_json, &image).unwrap();
+        let metadata: Value = serde_json::from_str(&metadata).unwrap();
+        assert_eq!(metadata["image"], "ipfs://bafyimage");
+        assert!(metadata_with_image("[]", &image).is_err());
+    }
+}
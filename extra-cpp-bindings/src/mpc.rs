@@ -0,0 +1,246 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use cxx::UniquePtr;
+use ethers::types::{Address, Signature, H256};
+
+use crate::ffi::{MpcMessage, MpcPartyCallback, MpcRoundResult, MpcTransportCallback};
+use crate::signer::{normalize_signature, Signer};
+
+/// the most rounds of a signing session (a protocol that doesn't end is aborted)
+const MAX_ROUNDS: u32 = 32;
+
+/// the sessions started by this process (so that the session ids are unique)
+static SESSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// The local party of a round-based threshold signing protocol (e.g. of an MPC wallet vendor)
+pub(crate) trait MpcParty: Send + Sync {
+    /// starts signing the digest in the session: the messages of the first round
+    fn start(&self, session_id: &str, digest: H256) -> Result<Vec<MpcMessage>>;
+    /// handles the messages of the other parties in a round: the messages of the next round,
+    /// or the signature (64 or 65 bytes) when it's done
+    fn handle(&self, session_id: &str, incoming: Vec<MpcMessage>) -> Result<MpcRoundResult>;
+}
+
+/// The opaque transport of the messages between the parties
+pub(crate) trait MpcTransport: Send + Sync {
+    /// sends the messages of the local party in a round and returns the ones of the other
+    /// parties in that round (it blocks until they're received)
+    fn exchange(&self, session_id: &str, outgoing: Vec<MpcMessage>) -> Result<Vec<MpcMessage>>;
+}
+
+/// the MPC party implemented in C++
+struct CallbackParty(UniquePtr<MpcPartyCallback>);
+
+impl MpcParty for CallbackParty {
+    fn start(&self, session_id: &str, digest: H256) -> Result<Vec<MpcMessage>> {
+        Ok(self.0.start(session_id, digest.as_bytes())?)
+    }
+
+    fn handle(&self, session_id: &str, incoming: Vec<MpcMessage>) -> Result<MpcRoundResult> {
+        Ok(self.0.handle(session_id, &incoming)?)
+    }
+}
+
+/// the MPC transport implemented in C++
+struct CallbackTransport(UniquePtr<MpcTransportCallback>);
+
+impl MpcTransport for CallbackTransport {
+    fn exchange(&self, session_id: &str, outgoing: Vec<MpcMessage>) -> Result<Vec<MpcMessage>> {
+        Ok(self.0.exchange(session_id, &outgoing)?)
+    }
+}
+
+/// a new session id
+fn session_id(digest: H256) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let session = SESSIONS.fetch_add(1, Ordering::Relaxed);
+    format!("{}-{nanos:x}-{session}", hex::encode(&digest[..8]))
+}
+
+/// A signer whose key is shared between the parties of a threshold (MPC) protocol:
+/// the SDK runs the rounds of the local party and exchanges their messages via the transport
+pub(crate) struct MpcSigner {
+    /// the address of the shared key
+    address: Address,
+    party: Box<dyn MpcParty>,
+    transport: Box<dyn MpcTransport>,
+}
+
+impl MpcSigner {
+    pub(crate) fn new(
+        address: &str,
+        party: UniquePtr<MpcPartyCallback>,
+        transport: UniquePtr<MpcTransportCallback>,
+    ) -> Result<Self> {
+        if party.is_null() || transport.is_null() {
+            anyhow::bail!("no callback");
+        }
+        Ok(Self::with_party(
+            crate::parse_address(address)?,
+            Box::new(CallbackParty(party)),
+            Box::new(CallbackTransport(transport)),
+        ))
+    }
+
+    fn with_party(
+        address: Address,
+        party: Box<dyn MpcParty>,
+        transport: Box<dyn MpcTransport>,
+    ) -> Self {
+        Self {
+            address,
+            party,
+            transport,
+        }
+    }
+}
+
+impl Signer for MpcSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let session_id = session_id(digest);
+        let mut outgoing = self.party.start(&session_id, digest)?;
+        for _ in 0..MAX_ROUNDS {
+            let incoming = self.transport.exchange(&session_id, outgoing)?;
+            let result = self.party.handle(&session_id, incoming)?;
+            if !result.signature.is_empty() {
+                return normalize_signature(&result.signature, digest, self.address);
+            }
+            outgoing = result.messages;
+        }
+        Err(anyhow!(
+            "the MPC session {session_id} didn't end in {MAX_ROUNDS} rounds"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::core::k256::elliptic_curve::ops::Reduce;
+    use ethers::core::k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+    use ethers::core::k256::{AffinePoint, EncodedPoint, ProjectivePoint, Scalar, U256};
+    use ethers::signers::LocalWallet;
+    use std::sync::Mutex;
+
+    use crate::hashing::sha256;
+
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn scalar(bytes: &[u8]) -> Scalar {
+        <Scalar as Reduce<U256>>::reduce_bytes(bytes.into())
+    }
+
+    /// The 2-of-2 reference party: additive shares of the key and of the nonce.
+    /// Round 1: the nonce points; round 2: the shares, combined into the signature.
+    /// NOT SECURE (the shares are revealed to the other party): it only exercises
+    /// the round-based pipeline
+    struct ReferenceParty {
+        id: u32,
+        key_share: Scalar,
+        /// the nonce share, the digest and the combined nonce point of the session
+        state: Mutex<Option<(Scalar, H256, ProjectivePoint)>>,
+    }
+
+    impl ReferenceParty {
+        fn message(&self, round: u32, payload: Vec<u8>) -> MpcMessage {
+            MpcMessage {
+                from: self.id,
+                to: 1 - self.id,
+                round,
+                payload,
+            }
+        }
+    }
+
+    impl MpcParty for ReferenceParty {
+        fn start(&self, session_id: &str, digest: H256) -> Result<Vec<MpcMessage>> {
+            let seed = [session_id.as_bytes(), &self.id.to_be_bytes()].concat();
+            let nonce_share = scalar(&sha256(seed));
+            let point = ProjectivePoint::GENERATOR * nonce_share;
+            *self.state.lock().unwrap() = Some((nonce_share, digest, point));
+            let encoded = point.to_affine().to_encoded_point(true);
+            Ok(vec![self.message(1, encoded.as_bytes().to_vec())])
+        }
+
+        fn handle(&self, _session_id: &str, incoming: Vec<MpcMessage>) -> Result<MpcRoundResult> {
+            let mut state = self.state.lock().unwrap();
+            let (nonce_share, digest, point) = state.ok_or_else(|| anyhow!("not started"))?;
+            let message = incoming.first().ok_or_else(|| anyhow!("no message"))?;
+            if message.round == 1 {
+                let other = EncodedPoint::from_bytes(&message.payload)?;
+                let other = Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&other))
+                    .ok_or_else(|| anyhow!("invalid point"))?;
+                *state = Some((nonce_share, digest, point + ProjectivePoint::from(other)));
+                let shares = [nonce_share.to_bytes(), self.key_share.to_bytes()].concat();
+                return Ok(MpcRoundResult {
+                    messages: vec![self.message(2, shares)],
+                    signature: vec![],
+                });
+            }
+            let nonce = nonce_share + scalar(&message.payload[..32]);
+            let key = self.key_share + scalar(&message.payload[32..]);
+            assert_eq!(ProjectivePoint::GENERATOR * nonce, point);
+            let r = scalar(&point.to_affine().to_encoded_point(false).x().unwrap()[..]);
+            let s = Option::<Scalar>::from(nonce.invert()).unwrap()
+                * (scalar(digest.as_bytes()) + r * key);
+            Ok(MpcRoundResult {
+                messages: vec![],
+                signature: [r.to_bytes(), s.to_bytes()].concat(),
+            })
+        }
+    }
+
+    /// the transport running the other party in process
+    struct LocalTransport {
+        other: ReferenceParty,
+        /// the messages of the other party for the next round
+        next: Mutex<Vec<MpcMessage>>,
+    }
+
+    impl MpcTransport for LocalTransport {
+        fn exchange(&self, session_id: &str, outgoing: Vec<MpcMessage>) -> Result<Vec<MpcMessage>> {
+            let mut next = self.next.lock().unwrap();
+            let incoming = match outgoing.first().map(|message| message.round) {
+                // the other party only needs the digest to output the signature
+                Some(1) => self.other.start(session_id, H256::zero())?,
+                _ => std::mem::take(&mut *next),
+            };
+            *next = self.other.handle(session_id, outgoing)?.messages;
+            Ok(incoming)
+        }
+    }
+
+    #[test]
+    fn test_mpc_reference_signing() {
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let address = Signer::address(&wallet);
+        let key = scalar(&hex::decode(PRIVATE_KEY).unwrap());
+        let share = scalar(&sha256(b"share".to_vec()));
+        let party = |id, key_share| ReferenceParty {
+            id,
+            key_share,
+            state: Mutex::new(None),
+        };
+        let signer = MpcSigner::with_party(
+            address,
+            Box::new(party(0, key - share)),
+            Box::new(LocalTransport {
+                other: party(1, share),
+                next: Mutex::new(vec![]),
+            }),
+        );
+        let digest = H256::repeat_byte(3);
+        let signature = signer.sign_digest(digest).unwrap();
+        assert_eq!(signature.recover(digest).unwrap(), address);
+        assert!(session_id(digest) != session_id(digest));
+    }
+}
@@ -0,0 +1,192 @@
+use std::sync::{Arc, Mutex};
+
+use cxx::UniquePtr;
+use defi_wallet_connect::usage as bridge_usage;
+
+use crate::ffi::{NetworkBudget, NetworkBudgetCallback, NetworkUsage, SubsystemUsage};
+
+/// the subsystem of the SDK's HTTP requests (the explorer APIs, the faucets, the JSON-RPC nodes...)
+const HTTP: &str = "http";
+/// the subsystem of the WalletConnect 1.0 bridge server messages
+const WALLETCONNECT: &str = "walletconnect";
+/// the subsystem of the WalletConnect 2.0 relay messages
+const WALLETCONNECT2: &str = "walletconnect2";
+
+/// The usage of the HTTP requests
+struct Counters {
+    requests: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+/// The budget and whether it was exceeded (the callback is called once)
+struct Budget {
+    budget: NetworkBudget,
+    exceeded: bool,
+}
+
+static HTTP_USAGE: Mutex<Counters> = Mutex::new(Counters {
+    requests: 0,
+    bytes_sent: 0,
+    bytes_received: 0,
+});
+/// the budget (none if it's not set)
+static BUDGET: Mutex<Option<Budget>> = Mutex::new(None);
+/// called when the budget is exceeded (none if it's not set)
+static BUDGET_CALLBACK: Mutex<Option<Arc<UniquePtr<NetworkBudgetCallback>>>> = Mutex::new(None);
+
+pub(crate) fn record_http(bytes_sent: u64, bytes_received: u64) {
+    {
+        let mut usage = HTTP_USAGE.lock().expect("network usage lock");
+        usage.requests += 1;
+        usage.bytes_sent += bytes_sent;
+        usage.bytes_received += bytes_received;
+    }
+    check_budget();
+}
+
+/// records the body of the response of a request already recorded (e.g. read by the caller
+/// of `http::send` after checking its headers)
+pub(crate) fn record_received(bytes_received: u64) {
+    HTTP_USAGE
+        .lock()
+        .expect("network usage lock")
+        .bytes_received += bytes_received;
+    check_budget();
+}
+
+/// the usage by subsystem and its totals
+fn report(subsystems: Vec<SubsystemUsage>) -> NetworkUsage {
+    NetworkUsage {
+        requests: subsystems.iter().map(|s| s.requests).sum(),
+        bytes_sent: subsystems.iter().map(|s| s.bytes_sent).sum(),
+        bytes_received: subsystems.iter().map(|s| s.bytes_received).sum(),
+        subsystems,
+    }
+}
+
+/// the network usage since the start or the last reset
+pub(crate) fn usage() -> NetworkUsage {
+    let http = {
+        let usage = HTTP_USAGE.lock().expect("network usage lock");
+        SubsystemUsage {
+            subsystem: HTTP.to_owned(),
+            requests: usage.requests,
+            bytes_sent: usage.bytes_sent,
+            bytes_received: usage.bytes_received,
+        }
+    };
+    let websocket = |subsystem: &str, usage: bridge_usage::Usage| SubsystemUsage {
+        subsystem: subsystem.to_owned(),
+        requests: usage.messages_sent,
+        bytes_sent: usage.bytes_sent,
+        bytes_received: usage.bytes_received,
+    };
+    report(vec![
+        http,
+        websocket(WALLETCONNECT, bridge_usage::usage()),
+        websocket(WALLETCONNECT2, bridge_usage::relay_usage()),
+    ])
+}
+
+/// resets the usage (the budget can be exceeded again)
+pub(crate) fn reset_usage() {
+    {
+        let mut usage = HTTP_USAGE.lock().expect("network usage lock");
+        usage.requests = 0;
+        usage.bytes_sent = 0;
+        usage.bytes_received = 0;
+    }
+    bridge_usage::reset();
+    if let Some(budget) = BUDGET.lock().expect("network budget lock").as_mut() {
+        budget.exceeded = false;
+    }
+}
+
+pub(crate) fn set_budget(budget: &NetworkBudget, callback: UniquePtr<NetworkBudgetCallback>) {
+    *BUDGET_CALLBACK
+        .lock()
+        .expect("network budget callback lock") = (!callback.is_null()).then(|| Arc::new(callback));
+    *BUDGET.lock().expect("network budget lock") = Some(Budget {
+        budget: budget.clone(),
+        exceeded: false,
+    });
+    bridge_usage::set_observer(Some(check_budget));
+    check_budget();
+}
+
+pub(crate) fn clear_budget() {
+    bridge_usage::set_observer(None);
+    *BUDGET.lock().expect("network budget lock") = None;
+    *BUDGET_CALLBACK
+        .lock()
+        .expect("network budget callback lock") = None;
+}
+
+/// whether the usage is over the budget (its zero limits are unset)
+fn is_over(budget: &NetworkBudget, usage: &NetworkUsage) -> bool {
+    let bytes = usage.bytes_sent + usage.bytes_received;
+    (budget.max_bytes != 0 && bytes > budget.max_bytes)
+        || (budget.max_requests != 0 && usage.requests > budget.max_requests)
+}
+
+/// calls the callback the first time the usage is over the budget: on a thread of its own,
+/// as it's checked on the thread of the request or on the task of a WalletConnect connection
+fn check_budget() {
+    let exceeded = {
+        let mut budget = BUDGET.lock().expect("network budget lock");
+        match budget.as_mut() {
+            Some(budget) if !budget.exceeded => {
+                let usage = usage();
+                budget.exceeded = is_over(&budget.budget, &usage);
+                budget.exceeded.then_some(usage)
+            }
+            _ => None,
+        }
+    };
+    let Some(usage) = exceeded else {
+        return;
+    };
+    let callback = BUDGET_CALLBACK
+        .lock()
+        .expect("network budget callback lock")
+        .clone();
+    if let Some(callback) = callback {
+        std::thread::spawn(move || callback.onBudgetExceeded(&usage));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_network_budget() {
+        let usage = report(vec![
+            SubsystemUsage {
+                subsystem: HTTP.to_owned(),
+                requests: 3,
+                bytes_sent: 300,
+                bytes_received: 2000,
+            },
+            SubsystemUsage {
+                subsystem: WALLETCONNECT.to_owned(),
+                requests: 2,
+                bytes_sent: 200,
+                bytes_received: 500,
+            },
+        ]);
+        assert_eq!(usage.requests, 5);
+        assert_eq!(usage.bytes_sent, 500);
+        assert_eq!(usage.bytes_received, 2500);
+
+        let budget = |max_bytes, max_requests| NetworkBudget {
+            max_bytes,
+            max_requests,
+        };
+        assert!(!is_over(&budget(0, 0), &usage));
+        assert!(!is_over(&budget(3000, 5), &usage));
+        assert!(is_over(&budget(2999, 0), &usage));
+        assert!(is_over(&budget(0, 4), &usage));
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::ffi::{NftCollectionStats, NftMarketplace, NftSale, NftStatsSource};
+use crate::http;
+
+/// the GraphQL API of the Crypto.com NFT marketplace
+const CRYPTO_COM_NFT_URL: &str = "https://crypto.com/nft-api/graphql";
+/// the REST API of the Minted marketplace
+const MINTED_URL: &str = "https://api.minted.network/v1";
+
+const CRYPTO_COM_NFT_QUERY: &str = "query GetCollectionStats($collectionId: ID!) {
+  public {
+    collection(id: $collectionId) {
+      id
+      metrics { items owners minSaleListingPriceDecimal totalSalesDecimal }
+    }
+  }
+}";
+
+/// A marketplace API of the NFT collection stats
+trait CollectionStatsApi {
+    /// the request of the stats of the collection
+    fn request(
+        &self,
+        client: &reqwest::blocking::Client,
+        collection: &str,
+    ) -> reqwest::blocking::RequestBuilder;
+    /// the stats of the collection in the JSON response
+    fn stats(&self, collection: &str, response: Value) -> Result<NftCollectionStats>;
+}
+
+/// the number or the string at the JSON pointer as a string (empty if it's missing)
+pub(crate) fn json_text(value: &Value, pointer: &str) -> String {
+    match value.pointer(pointer) {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Number(number)) => number.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// the number or the numeric string at the JSON pointer (0 if it's missing)
+fn json_u64(value: &Value, pointer: &str) -> u64 {
+    json_text(value, pointer).parse().unwrap_or_default()
+}
+
+struct CryptoComNft {
+    url: String,
+}
+
+impl CollectionStatsApi for CryptoComNft {
+    fn request(
+        &self,
+        client: &reqwest::blocking::Client,
+        collection: &str,
+    ) -> reqwest::blocking::RequestBuilder {
+        client.post(&self.url).json(&json!({
+            "operationName": "GetCollectionStats",
+            "query": CRYPTO_COM_NFT_QUERY,
+            "variables": { "collectionId": collection },
+        }))
+    }
+
+    fn stats(&self, collection: &str, response: Value) -> Result<NftCollectionStats> {
+        let metrics = response
+            .pointer("/data/public/collection/metrics")
+            .ok_or_else(|| anyhow!("no collection {collection} on Crypto.com NFT"))?;
+        Ok(NftCollectionStats {
+            collection: collection.to_owned(),
+            floor_price: json_text(metrics, "/minSaleListingPriceDecimal"),
+            currency: "USD".to_owned(),
+            total_volume: json_text(metrics, "/totalSalesDecimal"),
+            owners: json_u64(metrics, "/owners"),
+            items: json_u64(metrics, "/items"),
+            ..Default::default()
+        })
+    }
+}
+
+struct Minted {
+    url: String,
+}
+
+impl CollectionStatsApi for Minted {
+    fn request(
+        &self,
+        client: &reqwest::blocking::Client,
+        collection: &str,
+    ) -> reqwest::blocking::RequestBuilder {
+        client.get(format!(
+            "{}/collections/{collection}/stats",
+            self.url.trim_end_matches('/')
+        ))
+    }
+
+    fn stats(&self, collection: &str, response: Value) -> Result<NftCollectionStats> {
+        let stats = response
+            .get("data")
+            .ok_or_else(|| anyhow!("no collection {collection} on Minted"))?;
+        let recent_sales = stats
+            .get("recentSales")
+            .and_then(Value::as_array)
+            .map(|sales| {
+                sales
+                    .iter()
+                    .map(|sale| NftSale {
+                        token_id: json_text(sale, "/tokenId"),
+                        price: json_text(sale, "/price"),
+                        timestamp: json_u64(sale, "/timestamp"),
+                        tx_hash: json_text(sale, "/txHash"),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(NftCollectionStats {
+            collection: collection.to_owned(),
+            floor_price: json_text(stats, "/floorPrice"),
+            currency: json_text(stats, "/currency"),
+            volume_24h: json_text(stats, "/volume24h"),
+            total_volume: json_text(stats, "/totalVolume"),
+            sales_24h: json_u64(stats, "/sales24h"),
+            owners: json_u64(stats, "/owners"),
+            items: json_u64(stats, "/items"),
+            recent_sales,
+        })
+    }
+}
+
+/// the marketplace API of the url with the `{collection}` placeholder, returning
+/// the JSON of `NftCollectionStats`
+struct Custom {
+    url: String,
+}
+
+impl CollectionStatsApi for Custom {
+    fn request(
+        &self,
+        client: &reqwest::blocking::Client,
+        collection: &str,
+    ) -> reqwest::blocking::RequestBuilder {
+        client.get(self.url.replace("{collection}", collection))
+    }
+
+    fn stats(&self, collection: &str, response: Value) -> Result<NftCollectionStats> {
+        let mut stats: NftCollectionStats = serde_json::from_value(response)?;
+        if stats.collection.is_empty() {
+            stats.collection = collection.to_owned();
+        }
+        Ok(stats)
+    }
+}
+
+fn stats_api(source: &NftStatsSource) -> Result<Box<dyn CollectionStatsApi>> {
+    let url = |default: &str| {
+        if source.url.is_empty() {
+            default.to_owned()
+        } else {
+            source.url.clone()
+        }
+    };
+    match source.marketplace {
+        NftMarketplace::CryptoComNft => Ok(Box::new(CryptoComNft {
+            url: url(CRYPTO_COM_NFT_URL),
+        })),
+        NftMarketplace::Minted => Ok(Box::new(Minted {
+            url: url(MINTED_URL),
+        })),
+        NftMarketplace::Custom if !source.url.is_empty() => Ok(Box::new(Custom {
+            url: source.url.clone(),
+        })),
+        NftMarketplace::Custom => Err(anyhow!("the custom marketplace needs a url")),
+        _ => Err(anyhow!("unknown NFT marketplace")),
+    }
+}
+
+/// fetches the stats of the collection (its id or contract address) from the marketplace
+pub(crate) fn get_collection_stats(
+    source: &NftStatsSource,
+    collection: &str,
+) -> Result<NftCollectionStats> {
+    let api = stats_api(source)?;
+    let client = http::blocking_client()?;
+    let response: Value = http::send_json(&client, api.request(&client, collection))?;
+    api.stats(collection, response)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn source(marketplace: NftMarketplace, url: &str) -> NftStatsSource {
+        NftStatsSource {
+            marketplace,
+            url: url.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_marketplace_stats() {
+        let api = stats_api(&source(NftMarketplace::CryptoComNft, "")).unwrap();
+        let response = json!({"data": {"public": {"collection": {"id": "abc", "metrics": {
+            "items": 5000, "owners": "1200", "minSaleListingPriceDecimal": "12.5",
+            "totalSalesDecimal": "250000"
+        }}}}});
+        let stats = api.stats("abc", response).unwrap();
+        assert_eq!(stats.floor_price, "12.5");
+        assert_eq!((stats.items, stats.owners), (5000, 1200));
+        assert!(api
+            .stats("abc", json!({"data": {"public": {"collection": null}}}))
+            .is_err());
+
+        let api = stats_api(&source(NftMarketplace::Minted, "")).unwrap();
+        let response = json!({"data": {"floorPrice": 99.5, "currency": "CRO", "volume24h": "1000",
+            "sales24h": 3, "recentSales": [{"tokenId": "7", "price": "120", "timestamp": 1680000000,
+            "txHash": "0x01"}]}});
+        let stats = api.stats("0x12", response).unwrap();
+        assert_eq!(stats.floor_price, "99.5");
+        assert_eq!(stats.sales_24h, 3);
+        assert_eq!(stats.recent_sales[0].token_id, "7");
+        assert_eq!(stats.recent_sales[0].timestamp, 1680000000);
+
+        let api = stats_api(&source(
+            NftMarketplace::Custom,
+            "https://example.com/{collection}",
+        ))
+        .unwrap();
+        let stats = api
+            .stats("0x12", json!({"floor_price": "1", "items": 10}))
+            .unwrap();
+        assert_eq!(stats.collection, "0x12");
+        assert_eq!(stats.items, 10);
+        assert!(stats_api(&source(NftMarketplace::Custom, "")).is_err());
+    }
+}
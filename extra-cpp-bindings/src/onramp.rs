@@ -0,0 +1,367 @@
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Result};
+use bindings_common::value::normalize_value;
+use ethers::types::U256;
+use ethers::utils::parse_units;
+use serde_json::Value;
+
+use crate::ffi::{OnRampProvider, OnRampProviderKind, OnRampQuote};
+use crate::http;
+use crate::nftstats::json_text;
+
+const TRANSAK_API_URL: &str = "https://api.transak.com";
+const TRANSAK_WIDGET_URL: &str = "https://global.transak.com";
+const MOONPAY_API_URL: &str = "https://api.moonpay.com";
+const MOONPAY_WIDGET_URL: &str = "https://buy.moonpay.com";
+/// the network of the purchased coins
+const TRANSAK_NETWORK: &str = "cronos";
+/// the decimals the fiat fees are summed in
+const FEE_DECIMALS: u32 = 18;
+
+/// the providers set via `set_onramp_providers`
+static PROVIDERS: RwLock<Vec<OnRampProvider>> = RwLock::new(Vec::new());
+
+/// replaces the providers of the quotes
+pub(crate) fn set_providers(providers: Vec<OnRampProvider>) -> Result<()> {
+    if let Some(provider) = providers.iter().find(|p| p.api_key.is_empty()) {
+        return Err(anyhow!("no API key of the on-ramp {:?}", provider.kind));
+    }
+    *PROVIDERS.write().expect("onramp providers lock") = providers;
+    Ok(())
+}
+
+/// The request of a quote
+struct QuoteRequest<'a> {
+    fiat: &'a str,
+    crypto: &'a str,
+    /// the decimal amount of the fiat currency
+    amount: &'a str,
+    /// the ISO 3166-1 alpha-2 country code (empty if unknown)
+    region: &'a str,
+}
+
+/// A fiat on-ramp API
+trait OnRampApi {
+    /// the request of the quote
+    fn quote_request(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &QuoteRequest,
+    ) -> reqwest::blocking::RequestBuilder;
+    /// checks that the provider sells in the region of the request (if any)
+    fn check_region(
+        &self,
+        _client: &reqwest::blocking::Client,
+        _request: &QuoteRequest,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// the quote in the JSON response (without the redirect url)
+    fn quote(&self, request: &QuoteRequest, response: Value) -> Result<OnRampQuote>;
+    /// the url of the provider's widget buying the quote to the wallet address (if not empty)
+    fn redirect_url(&self, quote: &OnRampQuote, wallet_address: &str) -> Result<String>;
+}
+
+/// the url or its default if it's empty
+fn or_default(url: &str, default: &str) -> String {
+    if url.is_empty() {
+        default.to_owned()
+    } else {
+        url.trim_end_matches('/').to_owned()
+    }
+}
+
+/// the url with the query parameters (the empty ones are skipped)
+fn url_with_params(url: &str, params: &[(&str, &str)]) -> Result<String> {
+    let params = params.iter().filter(|(_, value)| !value.is_empty());
+    Ok(url::Url::parse_with_params(url, params)?.to_string())
+}
+
+struct Transak<'a> {
+    provider: &'a OnRampProvider,
+}
+
+impl OnRampApi for Transak<'_> {
+    fn quote_request(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &QuoteRequest,
+    ) -> reqwest::blocking::RequestBuilder {
+        let url = or_default(&self.provider.api_url, TRANSAK_API_URL);
+        client
+            .get(format!("{url}/api/v2/currencies/price"))
+            .query(&[
+                ("partnerApiKey", self.provider.api_key.as_str()),
+                ("fiatCurrency", request.fiat),
+                ("cryptoCurrency", request.crypto),
+                ("fiatAmount", request.amount),
+                ("network", TRANSAK_NETWORK),
+                ("isBuyOrSell", "BUY"),
+                ("paymentMethod", "credit_debit_card"),
+                ("quoteCountryCode", request.region),
+            ])
+    }
+
+    fn quote(&self, request: &QuoteRequest, response: Value) -> Result<OnRampQuote> {
+        let quote = response
+            .get("response")
+            .ok_or_else(|| anyhow!("no Transak quote: {response}"))?;
+        Ok(OnRampQuote {
+            provider: OnRampProviderKind::Transak,
+            fiat_currency: request.fiat.to_owned(),
+            crypto_currency: request.crypto.to_owned(),
+            fiat_amount: json_text(quote, "/fiatAmount"),
+            crypto_amount: json_text(quote, "/cryptoAmount"),
+            fee_amount: json_text(quote, "/totalFee"),
+            price: json_text(quote, "/marketConversionPrice"),
+            region: request.region.to_owned(),
+            redirect_url: String::new(),
+        })
+    }
+
+    fn redirect_url(&self, quote: &OnRampQuote, wallet_address: &str) -> Result<String> {
+        url_with_params(
+            &or_default(&self.provider.widget_url, TRANSAK_WIDGET_URL),
+            &[
+                ("apiKey", &self.provider.api_key),
+                ("fiatCurrency", &quote.fiat_currency),
+                ("cryptoCurrencyCode", &quote.crypto_currency),
+                ("fiatAmount", &quote.fiat_amount),
+                ("network", TRANSAK_NETWORK),
+                ("countryCode", &quote.region),
+                ("walletAddress", wallet_address),
+            ],
+        )
+    }
+}
+
+struct MoonPay<'a> {
+    provider: &'a OnRampProvider,
+}
+
+/// the sum of the decimal fiat amounts (the missing ones are 0)
+fn sum_fees(fees: &[String]) -> Result<String> {
+    let mut total = U256::zero();
+    for fee in fees.iter().filter(|fee| !fee.is_empty()) {
+        let fee: U256 = parse_units(fee, FEE_DECIMALS)
+            .map_err(|e| anyhow!("invalid fee {fee}: {e}"))?
+            .into();
+        total = total
+            .checked_add(fee)
+            .ok_or_else(|| anyhow!("the fees overflow"))?;
+    }
+    Ok(normalize_value(&total.to_string(), Some(FEE_DECIMALS)).1)
+}
+
+impl OnRampApi for MoonPay<'_> {
+    /// the quotes of MoonPay don't take the region, so the region is checked
+    /// against the countries where MoonPay sells
+    fn check_region(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &QuoteRequest,
+    ) -> Result<()> {
+        if request.region.is_empty() {
+            return Ok(());
+        }
+        let url = or_default(&self.provider.api_url, MOONPAY_API_URL);
+        let countries: Value = http::send_json(
+            client,
+            client
+                .get(format!("{url}/v3/countries"))
+                .query(&[("apiKey", self.provider.api_key.as_str())]),
+        )?;
+        if buy_allowed(&countries, request.region) {
+            Ok(())
+        } else {
+            Err(anyhow!("MoonPay doesn't sell in {}", request.region))
+        }
+    }
+
+    fn quote_request(
+        &self,
+        client: &reqwest::blocking::Client,
+        request: &QuoteRequest,
+    ) -> reqwest::blocking::RequestBuilder {
+        let url = or_default(&self.provider.api_url, MOONPAY_API_URL);
+        client
+            .get(format!(
+                "{url}/v3/currencies/{}/buy_quote",
+                request.crypto.to_lowercase()
+            ))
+            .query(&[
+                ("apiKey", self.provider.api_key.as_str()),
+                ("baseCurrencyCode", &request.fiat.to_lowercase()),
+                ("baseCurrencyAmount", request.amount),
+            ])
+    }
+
+    fn quote(&self, request: &QuoteRequest, response: Value) -> Result<OnRampQuote> {
+        let crypto_amount = json_text(&response, "/quoteCurrencyAmount");
+        if crypto_amount.is_empty() {
+            return Err(anyhow!("no MoonPay quote: {response}"));
+        }
+        // the fees are the MoonPay and the network ones
+        let fee_amount = sum_fees(&[
+            json_text(&response, "/feeAmount"),
+            json_text(&response, "/networkFeeAmount"),
+        ])?;
+        Ok(OnRampQuote {
+            provider: OnRampProviderKind::MoonPay,
+            fiat_currency: request.fiat.to_owned(),
+            crypto_currency: request.crypto.to_owned(),
+            fiat_amount: json_text(&response, "/baseCurrencyAmount"),
+            crypto_amount,
+            fee_amount,
+            price: json_text(&response, "/quoteCurrencyPrice"),
+            region: request.region.to_owned(),
+            redirect_url: String::new(),
+        })
+    }
+
+    fn redirect_url(&self, quote: &OnRampQuote, wallet_address: &str) -> Result<String> {
+        url_with_params(
+            &or_default(&self.provider.widget_url, MOONPAY_WIDGET_URL),
+            &[
+                ("apiKey", &self.provider.api_key),
+                ("currencyCode", &quote.crypto_currency.to_lowercase()),
+                ("baseCurrencyCode", &quote.fiat_currency.to_lowercase()),
+                ("baseCurrencyAmount", &quote.fiat_amount),
+                ("walletAddress", wallet_address),
+            ],
+        )
+    }
+}
+
+/// whether the country (ISO 3166-1 alpha-2) is buy-allowed in the MoonPay countries
+fn buy_allowed(countries: &Value, region: &str) -> bool {
+    countries.as_array().is_some_and(|countries| {
+        countries.iter().any(|country| {
+            json_text(country, "/alpha2").eq_ignore_ascii_case(region)
+                && country.get("isBuyAllowed") == Some(&Value::Bool(true))
+        })
+    })
+}
+
+fn onramp_api(provider: &OnRampProvider) -> Result<Box<dyn OnRampApi + '_>> {
+    match provider.kind {
+        OnRampProviderKind::Transak => Ok(Box::new(Transak { provider })),
+        OnRampProviderKind::MoonPay => Ok(Box::new(MoonPay { provider })),
+        _ => Err(anyhow!("unknown on-ramp provider")),
+    }
+}
+
+/// the quotes of the providers set via `set_onramp_providers` (the most crypto first;
+/// the failing providers are skipped, unless all of them fail)
+pub(crate) fn get_onramp_quotes(
+    fiat: &str,
+    crypto: &str,
+    amount: &str,
+    region: &str,
+) -> Result<Vec<OnRampQuote>> {
+    let providers = PROVIDERS.read().expect("onramp providers lock").clone();
+    if providers.is_empty() {
+        return Err(anyhow!("no on-ramp providers (see `set_onramp_providers`)"));
+    }
+    let request = QuoteRequest {
+        fiat,
+        crypto,
+        amount,
+        region,
+    };
+    let client = http::blocking_client()?;
+    let mut quotes = vec![];
+    let mut last_error = None;
+    for provider in providers.iter() {
+        let quote = onramp_api(provider).and_then(|api| {
+            api.check_region(&client, &request)?;
+            let response: Value = http::send_json(&client, api.quote_request(&client, &request))?;
+            let mut quote = api.quote(&request, response)?;
+            quote.redirect_url = api.redirect_url(&quote, "")?;
+            Ok(quote)
+        });
+        match quote {
+            Ok(quote) => quotes.push(quote),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    if let (true, Some(e)) = (quotes.is_empty(), last_error) {
+        return Err(e);
+    }
+    let crypto_amount =
+        |quote: &OnRampQuote| quote.crypto_amount.parse::<f64>().unwrap_or_default();
+    quotes.sort_by(|a, b| crypto_amount(b).total_cmp(&crypto_amount(a)));
+    Ok(quotes)
+}
+
+/// the redirect url of the quote buying to the wallet address
+pub(crate) fn onramp_redirect_url(quote: &OnRampQuote, wallet_address: &str) -> Result<String> {
+    let provider = PROVIDERS
+        .read()
+        .expect("onramp providers lock")
+        .iter()
+        .find(|provider| provider.kind == quote.provider)
+        .cloned()
+        .ok_or_else(|| anyhow!("the on-ramp {:?} is not set", quote.provider))?;
+    let api = onramp_api(&provider)?;
+    api.redirect_url(quote, wallet_address)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(kind: OnRampProviderKind) -> OnRampProvider {
+        OnRampProvider {
+            kind,
+            api_key: "pk_test".into(),
+            api_url: String::new(),
+            widget_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_onramp_quotes() {
+        let request = QuoteRequest {
+            fiat: "USD",
+            crypto: "CRO",
+            amount: "100",
+            region: "FR",
+        };
+        let transak = provider(OnRampProviderKind::Transak);
+        let api = onramp_api(&transak).unwrap();
+        let response = json!({"response": {"fiatAmount": 100, "cryptoAmount": 1150.25,
+            "totalFee": 3.99, "marketConversionPrice": 0.0833}});
+        let quote = api.quote(&request, response).unwrap();
+        assert_eq!(quote.crypto_amount, "1150.25");
+        assert_eq!(quote.fee_amount, "3.99");
+        let url = api.redirect_url(&quote, "0x01").unwrap();
+        assert!(url.starts_with("https://global.transak.com/?apiKey=pk_test&fiatCurrency=USD"));
+        assert!(url.ends_with("&countryCode=FR&walletAddress=0x01"));
+        assert!(api.quote(&request, json!({"error": "invalid"})).is_err());
+
+        let moonpay = provider(OnRampProviderKind::MoonPay);
+        let api = onramp_api(&moonpay).unwrap();
+        let response = json!({"baseCurrencyAmount": 100, "quoteCurrencyAmount": 1140.5,
+            "feeAmount": 3.5, "networkFeeAmount": 0.5, "quoteCurrencyPrice": 0.0839});
+        let quote = api.quote(&request, response).unwrap();
+        assert_eq!(quote.fee_amount, "4");
+        assert_eq!(
+            sum_fees(&["0.1".into(), "0.2".into(), String::new()]).unwrap(),
+            "0.3"
+        );
+        assert!(sum_fees(&["free".into()]).is_err());
+        let countries = json!([{"alpha2": "FR", "isBuyAllowed": true},
+            {"alpha2": "KP", "isBuyAllowed": false}]);
+        assert!(buy_allowed(&countries, "fr"));
+        assert!(!buy_allowed(&countries, "KP"));
+        assert!(!buy_allowed(&countries, "US"));
+        assert_eq!(
+            api.redirect_url(&quote, "").unwrap(),
+            "https://buy.moonpay.com/?apiKey=pk_test&currencyCode=cro&baseCurrencyCode=usd\
+             &baseCurrencyAmount=100"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use super::error::GameSdkError;
-use super::ffi::OptionalArguments;
+use super::ffi::{CryptoComPaymentResponse, CryptoPayWebhookEvent, OptionalArguments};
+use super::hashing::verify_hmac_sha256;
+use super::http;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -199,13 +201,14 @@ pub(crate) fn create_payment(
         data.push(("expired_at", &expired_at));
     }
 
-    let client = reqwest::blocking::Client::new();
-    let resp: ResponseData = client
-        .post(URL)
-        .basic_auth(secret_or_publishable_api_key, Some(""))
-        .form(&data)
-        .send()?
-        .json()?;
+    let client = http::blocking_client()?;
+    let resp: ResponseData = http::send_json(
+        &client,
+        client
+            .post(URL)
+            .basic_auth(secret_or_publishable_api_key, Some(""))
+            .form(&data),
+    )?;
 
     match resp {
         ResponseData::Error { error: err } => Err(GameSdkError::CryptoPayError(err)),
@@ -218,12 +221,13 @@ pub(crate) fn get_payment(
     payment_id: &str,
 ) -> Result<CryptoPayObject, GameSdkError> {
     let url: String = format!("https://pay.crypto.com/api/payments/{payment_id}");
-    let client = reqwest::blocking::Client::new();
-    let resp: ResponseData = client
-        .get(url)
-        .basic_auth(secret_or_publishable_api_key, Some(""))
-        .send()?
-        .json()?;
+    let client = http::blocking_client()?;
+    let resp: ResponseData = http::send_json(
+        &client,
+        client
+            .get(url)
+            .basic_auth(secret_or_publishable_api_key, Some("")),
+    )?;
 
     match resp {
         ResponseData::Error { error: err } => Err(GameSdkError::CryptoPayError(err)),
@@ -231,9 +235,119 @@ pub(crate) fn get_payment(
     }
 }
 
+/// the tolerance of the webhook timestamps (5 minutes) if none is given
+const DEFAULT_WEBHOOK_TOLERANCE_SECS: u64 = 300;
+
+/// the event of a webhook callback
+/// https://pay-docs.crypto.com/#api-reference-webhooks
+#[derive(Deserialize)]
+struct WebhookEvent {
+    id: String,
+    /// e.g. "payment.captured"
+    #[serde(rename = "type")]
+    event_type: String,
+    /// timestamp Measured in seconds since the Unix epoch.
+    #[serde(default)]
+    created: u64,
+    data: WebhookEventData,
+}
+
+#[derive(Deserialize)]
+struct WebhookEventData {
+    object: serde_json::Value,
+}
+
+/// the payment fields of the event object (empty for the other objects, e.g. refunds)
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WebhookPayment {
+    id: String,
+    amount: Option<serde_json::Number>,
+    currency: String,
+    status: String,
+    deposit_address: Option<String>,
+    qr_code: Option<String>,
+    expired_at: Option<u64>,
+}
+
+/// verifies the `Pay-Signature` header ("t=<timestamp>,v1=<signature>") of the webhook payload:
+/// the HMAC-SHA256 of "<timestamp>.<payload>" with the webhook signature secret,
+/// and the timestamp within the tolerance (5 minutes if 0) of `now`
+pub(crate) fn verify_webhook_signature(
+    payload: &str,
+    signature_header: &str,
+    webhook_secret: &str,
+    tolerance_secs: u64,
+    now: u64,
+) -> Result<(), GameSdkError> {
+    let invalid = |reason: &str| GameSdkError::InvalidWebhookSignature(reason.to_owned());
+    let mut timestamp = None;
+    let mut signatures = vec![];
+    for item in signature_header.split(',') {
+        match item.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<u64>().ok(),
+            Some(("v1", value)) => signatures.push(value.to_lowercase()),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or_else(|| invalid("no timestamp"))?;
+    if signatures.is_empty() {
+        return Err(invalid("no v1 signature"));
+    }
+    let tolerance_secs = if tolerance_secs == 0 {
+        DEFAULT_WEBHOOK_TOLERANCE_SECS
+    } else {
+        tolerance_secs
+    };
+    if now.abs_diff(timestamp) > tolerance_secs {
+        return Err(invalid("the timestamp is outside of the tolerance"));
+    }
+    let signed = format!("{timestamp}.{payload}");
+    if signatures.iter().any(|signature| {
+        hex::decode(signature).is_ok_and(|signature| {
+            verify_hmac_sha256(webhook_secret.as_bytes(), signed.as_bytes(), &signature)
+        })
+    }) {
+        Ok(())
+    } else {
+        Err(invalid("the signature doesn't match"))
+    }
+}
+
+/// parses the payload of the webhook callback
+pub(crate) fn parse_webhook_event(payload: &str) -> Result<CryptoPayWebhookEvent, GameSdkError> {
+    let event: WebhookEvent = serde_json::from_str(payload)?;
+    let is_payment = event.event_type.starts_with("payment.")
+        && !event.event_type.starts_with("payment.refund_");
+    let payment = if is_payment {
+        serde_json::from_value(event.data.object.clone()).unwrap_or_default()
+    } else {
+        WebhookPayment::default()
+    };
+    Ok(CryptoPayWebhookEvent {
+        id: event.id,
+        event_type: event.event_type,
+        created: event.created,
+        payment: CryptoComPaymentResponse {
+            id: payment.id,
+            main_app_qr_code: payment.qr_code.unwrap_or_default(),
+            onchain_deposit_address: payment.deposit_address.unwrap_or_default(),
+            base_amount: payment
+                .amount
+                .map(|amount| amount.to_string())
+                .unwrap_or_default(),
+            currency: payment.currency,
+            expiration: payment.expired_at.unwrap_or_default(),
+            status: payment.status,
+        },
+        object_json: event.data.object.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::hashing::hmac_sha256;
 
     #[test]
     pub fn test_parse_payment_object() {
@@ -281,4 +395,35 @@ mod test {
         "pay_method":"Other Wallets","allow_pay_later":false,"amount_in_usd":"0.0","amount_in_usdc":"0.0"}"#;
         let _po: CryptoPayObject = serde_json::from_str(sample).expect("parse");
     }
+
+    #[test]
+    fn test_verify_webhook() {
+        let payload = r#"{"id":"evt_1","object_type":"event","type":"payment.captured","created":1646670287,
+        "data":{"object":{"id":"a8608fef-05bf-4a43-9d16-6c0f2235ece7","amount":2500,"currency":"USD",
+        "status":"succeeded","deposit_address":"0xD3B8bD9855FFC40cC9E8a8Ea322BAB1bE481565d"}}}"#;
+        let secret = "whsec_test";
+        let signature = hex::encode(hmac_sha256(
+            secret.as_bytes().to_vec(),
+            format!("1646670287.{payload}").into_bytes(),
+        ));
+        let header = format!("t=1646670287,v1=deadbeef,v1={signature}");
+        verify_webhook_signature(payload, &header, secret, 0, 1646670300).unwrap();
+        assert!(verify_webhook_signature(payload, &header, "other", 0, 1646670300).is_err());
+        assert!(verify_webhook_signature(payload, &header, secret, 0, 1646680000).is_err());
+        assert!(verify_webhook_signature(payload, "v1=00", secret, 0, 1646670300).is_err());
+        let tampered = payload.replace("2500", "25000");
+        assert!(verify_webhook_signature(&tampered, &header, secret, 0, 1646670300).is_err());
+
+        let event = parse_webhook_event(payload).unwrap();
+        assert_eq!(event.event_type, "payment.captured");
+        assert_eq!(event.created, 1646670287);
+        assert_eq!(event.payment.id, "a8608fef-05bf-4a43-9d16-6c0f2235ece7");
+        assert_eq!(event.payment.base_amount, "2500");
+        assert_eq!(event.payment.status, "succeeded");
+        let refund = r#"{"id":"evt_2","type":"payment.refund_transferred","created":1,
+        "data":{"object":{"id":"r1","payment_id":"p1","amount":100}}}"#;
+        let event = parse_webhook_event(refund).unwrap();
+        assert!(event.payment.id.is_empty());
+        assert!(event.object_json.contains("\"payment_id\":\"p1\""));
+    }
 }
@@ -0,0 +1,579 @@
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use cxx::UniquePtr;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, U256};
+use ethers::utils::keccak256;
+
+use crate::error::GameSdkError;
+use crate::ffi::{PolicyCallback, PolicyRule, PolicyViolation, TransactionPolicy};
+use crate::messages::Message;
+use crate::{chainstate, parse_address, rpc};
+
+/// The parsed transaction policy
+#[derive(Debug, Default)]
+struct Policy {
+    max_value_per_tx: Option<U256>,
+    max_value_per_day: Option<U256>,
+    allowed_contracts: Vec<Address>,
+    allowed_methods: Vec<[u8; 4]>,
+    block_message_signing: bool,
+}
+
+/// the 4-byte selector of the method: the 0x-prefixed selector or the method signature,
+/// e.g. "0xa9059cbb" or "transfer(address,uint256)"
+fn method_selector(method: &str) -> Result<[u8; 4]> {
+    let method = method.trim();
+    if let Some(selector) = method.strip_prefix("0x") {
+        let bytes = hex::decode(selector)?;
+        return <[u8; 4]>::try_from(bytes.as_slice())
+            .map_err(|_| anyhow::anyhow!("invalid method selector: {method}"));
+    }
+    let signature: String = method.chars().filter(|c| !c.is_whitespace()).collect();
+    let hash = keccak256(signature.as_bytes());
+    Ok([hash[0], hash[1], hash[2], hash[3]])
+}
+
+impl TryFrom<&TransactionPolicy> for Policy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: &TransactionPolicy) -> Result<Self> {
+        let limit = |value: &str| -> Result<Option<U256>> {
+            if value.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(U256::from_dec_str(value)?))
+            }
+        };
+        Ok(Self {
+            max_value_per_tx: limit(&policy.max_value_per_tx)?,
+            max_value_per_day: limit(&policy.max_value_per_day)?,
+            allowed_contracts: policy
+                .allowed_contracts
+                .iter()
+                .map(|address| parse_address(address))
+                .collect::<Result<_, _>>()?,
+            allowed_methods: policy
+                .allowed_methods
+                .iter()
+                .map(|method| method_selector(method))
+                .collect::<Result<_>>()?,
+            block_message_signing: policy.block_message_signing,
+        })
+    }
+}
+
+/// The policy and the value sent today
+struct PolicyState {
+    policy: Option<Policy>,
+    /// the day (since the Unix epoch, UTC) of `spent`
+    day: u64,
+    spent: U256,
+}
+
+static POLICY: Mutex<PolicyState> = Mutex::new(PolicyState {
+    policy: None,
+    day: 0,
+    spent: U256([0; 4]),
+});
+/// called with the violations (none if it's not set)
+static POLICY_CALLBACK: Mutex<Option<Arc<UniquePtr<PolicyCallback>>>> = Mutex::new(None);
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86400
+}
+
+impl PolicyState {
+    /// the value sent today (it's reset on a new day)
+    fn spent_on(&mut self, day: u64) -> U256 {
+        if self.day != day {
+            self.day = day;
+            self.spent = U256::zero();
+        }
+        self.spent
+    }
+}
+
+pub(crate) fn set_policy(policy: &TransactionPolicy) -> Result<()> {
+    let policy = Policy::try_from(policy)?;
+    POLICY.lock().expect("policy lock").policy = Some(policy);
+    Ok(())
+}
+
+pub(crate) fn clear_policy() {
+    POLICY.lock().expect("policy lock").policy = None;
+}
+
+pub(crate) fn set_callback(callback: UniquePtr<PolicyCallback>) {
+    *POLICY_CALLBACK.lock().expect("policy callback lock") =
+        (!callback.is_null()).then(|| Arc::new(callback));
+}
+
+/// the value (in the base units of the native coin) sent today
+pub(crate) fn spent_today() -> String {
+    POLICY
+        .lock()
+        .expect("policy lock")
+        .spent_on(today())
+        .to_string()
+}
+
+/// A violation of the policy with the message of the violated rule
+struct Violation {
+    violation: PolicyViolation,
+    message: Message,
+}
+
+impl Violation {
+    fn new(rule: PolicyRule, message: Message, to: Option<&Address>, value: U256) -> Self {
+        Self {
+            violation: PolicyViolation {
+                rule,
+                message: message.text(),
+                to: to.map(|to| format!("{to:?}")).unwrap_or_default(),
+                value: value.to_string(),
+            },
+            message,
+        }
+    }
+}
+
+/// the violated rule of the policy (if any); `to_contract` is whether the recipient
+/// has a contract code (a plain transfer to it calls the contract), none if it can't be
+/// checked (the plain transfer is then restricted like a contract call)
+fn evaluate(
+    policy: &Policy,
+    spent_today: U256,
+    tx: &TypedTransaction,
+    to_contract: Option<bool>,
+) -> Option<Violation> {
+    let value = tx.value().copied().unwrap_or_default();
+    let to = tx.to_addr();
+    if let Some(max) = policy.max_value_per_tx {
+        if value > max {
+            return Some(Violation::new(
+                PolicyRule::MaxValuePerTx,
+                Message::new(
+                    "policy.max_value_per_tx",
+                    &[("value", value.to_string()), ("max", max.to_string())],
+                ),
+                to,
+                value,
+            ));
+        }
+    }
+    if let Some(max) = policy.max_value_per_day {
+        if spent_today.saturating_add(value) > max {
+            return Some(Violation::new(
+                PolicyRule::MaxValuePerDay,
+                Message::new(
+                    "policy.max_value_per_day",
+                    &[
+                        ("value", value.to_string()),
+                        ("max", max.to_string()),
+                        ("spent", spent_today.to_string()),
+                    ],
+                ),
+                to,
+                value,
+            ));
+        }
+    }
+    // the plain transfers to the externally owned accounts are not contract calls
+    let data: &[u8] = tx.data().map(|data| data.as_ref()).unwrap_or_default();
+    if data.is_empty() && to_contract == Some(false) {
+        return None;
+    }
+    if !policy.allowed_contracts.is_empty()
+        && !to.is_some_and(|to| policy.allowed_contracts.contains(to))
+    {
+        let message = match to {
+            Some(to) if data.is_empty() && to_contract.is_none() => {
+                Message::new("policy.recipient_unchecked", &[("to", format!("{to:?}"))])
+            }
+            _ => Message::new("policy.contract_not_allowed", &[]),
+        };
+        return Some(Violation::new(
+            PolicyRule::ContractNotAllowed,
+            message,
+            to,
+            value,
+        ));
+    }
+    if !policy.allowed_methods.is_empty()
+        && !data.get(..4).is_some_and(|selector| {
+            policy
+                .allowed_methods
+                .iter()
+                .any(|method| method == selector)
+        })
+    {
+        return Some(Violation::new(
+            PolicyRule::MethodNotAllowed,
+            Message::new(
+                "policy.method_not_allowed",
+                &[(
+                    "selector",
+                    format!("0x{}", hex::encode(data.get(..4).unwrap_or(data))),
+                )],
+            ),
+            to,
+            value,
+        ));
+    }
+    None
+}
+
+/// The value of a transaction being signed or sent, added to the value sent today
+/// when the policy is checked (so that the concurrent transactions can't exceed
+/// the daily limit together): it's released if it's dropped without `commit`
+/// (the transaction failed)
+#[must_use]
+pub(crate) struct Reservation {
+    /// the policy state of the value sent today
+    state: &'static Mutex<PolicyState>,
+    day: u64,
+    value: U256,
+    committed: bool,
+}
+
+impl Reservation {
+    /// keeps the value in the value sent today (the transaction was signed or sent;
+    /// the dry runs aren't kept)
+    pub(crate) fn commit(mut self) {
+        self.committed = !crate::dryrun::is_enabled();
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        let mut state = self.state.lock().expect("policy lock");
+        // the value sent on the previous days is already reset
+        if state.day == self.day {
+            state.spent = state.spent.saturating_sub(self.value);
+        }
+    }
+}
+
+/// reports the violation to the callback (outside of the callback lock,
+/// so that the callback can replace itself) and returns it as `PolicyViolation` error
+fn blocked(violation: Violation) -> anyhow::Error {
+    let callback = POLICY_CALLBACK
+        .lock()
+        .expect("policy callback lock")
+        .clone();
+    if let Some(callback) = callback {
+        callback.onPolicyBlocked(&violation.violation);
+    }
+    GameSdkError::PolicyViolation(violation.message).into()
+}
+
+/// whether the recipient of the plain transfer has a contract code, if the policy of the state
+/// restricts the contracts (via the cache of the account states and the JSON-RPC url;
+/// none without it, so that the transfer isn't allowed unchecked)
+async fn is_contract_transfer(
+    state: &Mutex<PolicyState>,
+    tx: &TypedTransaction,
+    rpc_url: &str,
+) -> Result<Option<bool>> {
+    let restricted = state
+        .lock()
+        .expect("policy lock")
+        .policy
+        .as_ref()
+        .is_some_and(|policy| !policy.allowed_contracts.is_empty());
+    let plain = tx.data().is_none_or(|data| data.is_empty());
+    match tx.to_addr() {
+        Some(_) if restricted && plain && rpc_url.is_empty() => Ok(None),
+        Some(to) if restricted && plain => Ok(Some(
+            chainstate::has_code(&rpc::provider(rpc_url)?, *to).await?,
+        )),
+        _ => Ok(Some(false)),
+    }
+}
+
+/// evaluates the policy of the state on the day, and reserves the value of the transaction
+/// (or returns the violated rule)
+fn reserve(
+    state: &'static Mutex<PolicyState>,
+    day: u64,
+    tx: &TypedTransaction,
+    to_contract: Option<bool>,
+) -> Result<Reservation, Violation> {
+    let mut locked = state.lock().expect("policy lock");
+    let spent = locked.spent_on(day);
+    if let Some(violation) = locked
+        .policy
+        .as_ref()
+        .and_then(|policy| evaluate(policy, spent, tx, to_contract))
+    {
+        return Err(violation);
+    }
+    let value = tx.value().copied().unwrap_or_default();
+    locked.spent = spent.saturating_add(value);
+    Ok(Reservation {
+        state,
+        day,
+        value,
+        committed: false,
+    })
+}
+
+/// evaluates the policy before the transaction is signed or sent, and reserves its value;
+/// the violation is reported to the callback and returned as `PolicyViolation` error
+pub(crate) async fn check_transaction(tx: &TypedTransaction) -> Result<Reservation> {
+    // the JSON-RPC url of the SDK environment
+    let rpc_url = crate::environment::config()
+        .map(|config| config.rpc_url)
+        .unwrap_or_default();
+    let to_contract = is_contract_transfer(&POLICY, tx, &rpc_url).await?;
+    // the violation is reported outside of the policy lock
+    let reserved = reserve(&POLICY, today(), tx, to_contract);
+    reserved.map_err(blocked)
+}
+
+/// the violated rule of the policy for the message signing request (if any):
+/// the raw digests (`eth_sign`) can be the hashes of the transactions,
+/// so they're blocked by any policy
+fn evaluate_message(policy: &Policy, eip191_prefix: bool) -> Option<Violation> {
+    let message = if !eip191_prefix {
+        Message::new("policy.digest_signing_not_allowed", &[])
+    } else if policy.block_message_signing {
+        Message::new("policy.message_signing_not_allowed", &[])
+    } else {
+        return None;
+    };
+    Some(Violation::new(
+        PolicyRule::MessageSigningNotAllowed,
+        message,
+        None,
+        U256::zero(),
+    ))
+}
+
+/// evaluates the policy before a message is signed with the EIP-191 prefix (`personal_sign`)
+/// or a raw digest is signed (`eth_sign`); the violation is reported like the transactions'
+pub(crate) fn check_message(eip191_prefix: bool) -> Result<()> {
+    let violation = POLICY
+        .lock()
+        .expect("policy lock")
+        .policy
+        .as_ref()
+        .and_then(|policy| evaluate_message(policy, eip191_prefix));
+    match violation {
+        Some(violation) => Err(blocked(violation)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::types::Eip1559TransactionRequest;
+
+    #[test]
+    fn test_policy_rules() {
+        let contract = "0x939d7350c54228e4958e05b65512c4a5bb6a2acc";
+        let policy = Policy::try_from(&TransactionPolicy {
+            max_value_per_tx: "100".into(),
+            max_value_per_day: "150".into(),
+            allowed_contracts: vec![contract.into()],
+            allowed_methods: vec!["transfer(address, uint256)".into(), "0x095ea7b3".into()],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(policy.allowed_methods[0], [0xa9, 0x05, 0x9c, 0xbb]);
+
+        let transfer = |value: u64| {
+            TypedTransaction::Eip1559(
+                Eip1559TransactionRequest::new()
+                    .to(Address::repeat_byte(1))
+                    .value(value),
+            )
+        };
+        assert!(evaluate(&policy, U256::zero(), &transfer(100), Some(false)).is_none());
+        let violation = evaluate(&policy, U256::zero(), &transfer(101), Some(false)).unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::MaxValuePerTx);
+        assert_eq!(violation.violation.value, "101");
+        let violation = evaluate(&policy, U256::from(60), &transfer(100), Some(false)).unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::MaxValuePerDay);
+        // a plain transfer to another contract calls it
+        let violation = evaluate(&policy, U256::zero(), &transfer(100), Some(true)).unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::ContractNotAllowed);
+
+        let call = |to: &str, data: Vec<u8>| {
+            TypedTransaction::Eip1559(
+                Eip1559TransactionRequest::new()
+                    .to(parse_address(to).unwrap())
+                    .data(data),
+            )
+        };
+        assert!(evaluate(
+            &policy,
+            U256::zero(),
+            &call(contract, vec![0x09, 0x5e, 0xa7, 0xb3]),
+            Some(true)
+        )
+        .is_none());
+        let violation = evaluate(
+            &policy,
+            U256::zero(),
+            &call(
+                "0x0202020202020202020202020202020202020202",
+                vec![0xa9, 0x05, 0x9c, 0xbb],
+            ),
+            Some(true),
+        )
+        .unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::ContractNotAllowed);
+        let violation = evaluate(
+            &policy,
+            U256::zero(),
+            &call(contract, vec![0xde, 0xad, 0xbe, 0xef]),
+            Some(true),
+        )
+        .unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::MethodNotAllowed);
+        assert_eq!(
+            violation.violation.message,
+            "the method 0xdeadbeef is not allowed"
+        );
+        assert_eq!(violation.message.id, "policy.method_not_allowed");
+
+        assert!(Policy::try_from(&TransactionPolicy {
+            allowed_methods: vec!["0x1234".into()],
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_message_rules() {
+        let policy = Policy::default();
+        assert!(evaluate_message(&policy, true).is_none());
+        let violation = evaluate_message(&policy, false).unwrap();
+        assert_eq!(
+            violation.violation.rule,
+            PolicyRule::MessageSigningNotAllowed
+        );
+        let policy = Policy {
+            block_message_signing: true,
+            ..Default::default()
+        };
+        let violation = evaluate_message(&policy, true).unwrap();
+        assert_eq!(
+            violation.violation.message,
+            "the message signing is not allowed"
+        );
+    }
+
+    #[test]
+    fn test_reservation() {
+        // a state of the test (not the one of the SDK, shared by the other tests)
+        static STATE: Mutex<PolicyState> = Mutex::new(PolicyState {
+            policy: None,
+            day: 0,
+            spent: U256([0; 4]),
+        });
+        STATE.lock().unwrap().policy = Some(
+            Policy::try_from(&TransactionPolicy {
+                max_value_per_day: "150".into(),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let transfer = TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .to(Address::repeat_byte(1))
+                .value(100),
+        );
+        let check = |day| reserve(&STATE, day, &transfer, Some(false));
+        let spent = || STATE.lock().unwrap().spent;
+        // the pending transaction counts towards the daily limit
+        let pending = check(1).ok().unwrap();
+        assert_eq!(spent(), U256::from(100));
+        let violation = check(1).err().unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::MaxValuePerDay);
+        // the failed transaction is released
+        drop(pending);
+        assert_eq!(spent(), U256::zero());
+        check(1).ok().unwrap().commit();
+        assert_eq!(spent(), U256::from(100));
+        // the value sent is reset on a new day
+        let next = check(2).ok().unwrap();
+        assert_eq!(spent(), U256::from(100));
+        drop(next);
+        assert_eq!(spent(), U256::zero());
+    }
+
+    #[test]
+    fn test_unchecked_recipient() {
+        // a state of the test (not the one of the SDK, shared by the other tests)
+        static STATE: Mutex<PolicyState> = Mutex::new(PolicyState {
+            policy: None,
+            day: 0,
+            spent: U256([0; 4]),
+        });
+        let contract = "0x939d7350c54228e4958e05b65512c4a5bb6a2acc";
+        STATE.lock().unwrap().policy = Some(
+            Policy::try_from(&TransactionPolicy {
+                allowed_contracts: vec![contract.into()],
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+        let transfer = |to: Address| {
+            TypedTransaction::Eip1559(Eip1559TransactionRequest::new().to(to).value(100))
+        };
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        // without the JSON-RPC url, the plain transfer is restricted like a contract call
+        let other = transfer(Address::repeat_byte(1));
+        let to_contract = rt
+            .block_on(is_contract_transfer(&STATE, &other, ""))
+            .unwrap();
+        assert_eq!(to_contract, None);
+        let violation = reserve(&STATE, 1, &other, to_contract).err().unwrap();
+        assert_eq!(violation.violation.rule, PolicyRule::ContractNotAllowed);
+        assert_eq!(violation.message.id, "policy.recipient_unchecked");
+        assert_eq!(
+            violation.violation.message,
+            "the recipient 0x0101010101010101010101010101010101010101 is not allowed \
+             (its contract code can't be checked without the JSON-RPC url of the SDK environment)"
+        );
+        // the allowed contracts are still paid
+        let allowed = transfer(parse_address(contract).unwrap());
+        assert!(reserve(&STATE, 1, &allowed, to_contract).is_ok());
+
+        // the plain transfers aren't checked if the contracts aren't restricted
+        STATE.lock().unwrap().policy = Some(Policy::default());
+        let to_contract = rt
+            .block_on(is_contract_transfer(&STATE, &other, ""))
+            .unwrap();
+        assert_eq!(to_contract, Some(false));
+        assert!(reserve(&STATE, 1, &other, to_contract).is_ok());
+    }
+
+    #[test]
+    fn test_spent_today() {
+        let mut state = PolicyState {
+            policy: None,
+            day: 10,
+            spent: U256::from(5),
+        };
+        assert_eq!(state.spent_on(10), U256::from(5));
+        assert_eq!(state.spent_on(11), U256::zero());
+        assert_eq!(state.day, 11);
+    }
+}
@@ -0,0 +1,457 @@
+use anyhow::Result;
+use ethers::abi::{Abi, Function};
+use ethers::types::{NameOrAddress, U256};
+use serde_json::{json, Map, Value};
+
+use crate::abi::token_to_json;
+use crate::ffi::TransactionDescription;
+use crate::messages::Message;
+
+/// the standard token methods described without an ABI
+pub(crate) const STANDARD_METHODS: &[&str] = &[
+    "function transfer(address to, uint256 amount)",
+    "function approve(address spender, uint256 amount)",
+    "function transferFrom(address from, address to, uint256 amount)",
+    "function safeTransferFrom(address from, address to, uint256 tokenId)",
+    "function safeTransferFrom(address from, address to, uint256 tokenId, bytes data)",
+    "function safeTransferFrom(address from, address to, uint256 id, uint256 amount, bytes data)",
+    "function safeBatchTransferFrom(address from, address to, uint256[] ids, uint256[] amounts, bytes data)",
+    "function setApprovalForAll(address operator, bool approved)",
+    "function mint(address to, uint256 amount)",
+    "function safeMint(address to, uint256 tokenId)",
+    "function mint(address to)",
+];
+
+/// the parameter value as a string (the arrays are comma-separated)
+fn param_text(params: &Map<String, Value>, name: &str) -> String {
+    match params.get(name) {
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| {
+                value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), ToOwned::to_owned)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    }
+}
+
+/// the action and the message of the decoded call of the contract;
+/// `erc721` is whether the ABI is of an ERC-721 contract (none without the ABI):
+/// the ERC-20 and ERC-721 `transferFrom` have the same selector
+fn describe_call(
+    function: &Function,
+    params: &Map<String, Value>,
+    contract: &str,
+    erc721: Option<bool>,
+) -> (&'static str, Message) {
+    let p = |name: &str| param_text(params, name);
+    let contract = ("contract", contract.to_owned());
+    let inputs: Vec<&str> = function
+        .inputs
+        .iter()
+        .map(|input| input.name.as_str())
+        .collect();
+    match (function.name.as_str(), inputs.as_slice()) {
+        ("transfer", ["to", "amount"]) => (
+            "transfer",
+            Message::new(
+                "preview.transfer",
+                &[("amount", p("amount")), contract, ("to", p("to"))],
+            ),
+        ),
+        ("approve", ["spender", "amount"]) if p("amount") == U256::MAX.to_string() => (
+            "approve",
+            Message::new(
+                "preview.approve_unlimited",
+                &[contract, ("spender", p("spender"))],
+            ),
+        ),
+        ("approve", ["spender", "amount"]) => (
+            "approve",
+            Message::new(
+                "preview.approve",
+                &[("amount", p("amount")), contract, ("spender", p("spender"))],
+            ),
+        ),
+        ("transferFrom", ["from", "to", "tokenId"]) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_token",
+                &[
+                    ("token_id", p("tokenId")),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("transferFrom", ["from", "to", value]) if erc721 == Some(true) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_token",
+                &[
+                    ("token_id", p(value)),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("transferFrom", ["from", "to", value]) if erc721 == Some(false) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_from",
+                &[
+                    ("amount", p(value)),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("transferFrom", ["from", "to", value]) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_from_unknown",
+                &[
+                    ("value", p(value)),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("safeTransferFrom", ["from", "to", "tokenId", ..]) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_token",
+                &[
+                    ("token_id", p("tokenId")),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("safeTransferFrom", ["from", "to", "id", "amount", ..]) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_token_amount",
+                &[
+                    ("amount", p("amount")),
+                    ("token_id", p("id")),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("safeBatchTransferFrom", _) => (
+            "transfer",
+            Message::new(
+                "preview.transfer_batch",
+                &[
+                    ("token_ids", p("ids")),
+                    ("amounts", p("amounts")),
+                    contract,
+                    ("from", p("from")),
+                    ("to", p("to")),
+                ],
+            ),
+        ),
+        ("setApprovalForAll", _) if params.get("approved") == Some(&Value::Bool(false)) => (
+            "revoke_approval_for_all",
+            Message::new(
+                "preview.revoke_approval_for_all",
+                &[("operator", p("operator")), contract],
+            ),
+        ),
+        ("setApprovalForAll", _) => (
+            "approve_for_all",
+            Message::new(
+                "preview.approve_for_all",
+                &[("operator", p("operator")), contract],
+            ),
+        ),
+        (name, _) if name.to_lowercase().contains("mint") => {
+            let message = match inputs.as_slice() {
+                [_, "tokenId"] => Message::new(
+                    "preview.mint_token",
+                    &[("token_id", p("tokenId")), contract, ("to", p("to"))],
+                ),
+                [_, "amount"] => Message::new(
+                    "preview.mint_amount",
+                    &[("amount", p("amount")), contract, ("to", p("to"))],
+                ),
+                _ if params.contains_key("to") => {
+                    Message::new("preview.mint_to", &[contract, ("to", p("to"))])
+                }
+                _ => Message::new("preview.mint", &[contract]),
+            };
+            ("mint", message)
+        }
+        (name, _) => (
+            "contract_call",
+            Message::new(
+                "preview.contract_call",
+                &[("method", name.to_owned()), contract],
+            ),
+        ),
+    }
+}
+
+impl TransactionDescription {
+    /// the summary is the English text of the message
+    fn new(action: &str, message: Message, method: String, params: Map<String, Value>) -> Self {
+        Self {
+            action: action.to_owned(),
+            summary: message.text(),
+            method,
+            params_json: Value::Object(params).to_string(),
+            message_id: message.id.to_owned(),
+            message_params_json: message.params_json(),
+        }
+    }
+}
+
+/// the message of the decoded call sending the value: the call's message is rendered
+/// with the same parameters (its id is the `summary_id` parameter)
+fn with_value(message: Message, value: U256) -> Message {
+    let mut params: Vec<(&str, String)> = message
+        .params
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    params.push(("summary_id", message.id.to_owned()));
+    params.push(("summary", message.text()));
+    params.push(("value", value.to_string()));
+    Message::new("preview.with_value", &params)
+}
+
+/// the function of the ABI (or of the standard token methods) with the selector
+pub(crate) fn find_function(abi: &Abi, selector: &[u8]) -> Option<Function> {
+    abi.functions()
+        .find(|function| function.short_signature() == selector)
+        .cloned()
+}
+
+/// describes the transaction (the JSON of an EIP-1559 transaction request): the calldata is
+/// decoded with the ABI JSON (the standard token methods if it's empty or the method
+/// isn't in it)
+pub(crate) fn describe_transaction(
+    tx_json: &str,
+    abi_json: &str,
+) -> Result<TransactionDescription> {
+    let tx = crate::txjson::parse_transaction_json(tx_json)?;
+    let value = tx.value.unwrap_or_default();
+    let to = match &tx.to {
+        Some(NameOrAddress::Address(address)) => format!("{address:?}"),
+        Some(NameOrAddress::Name(name)) => name.clone(),
+        None => String::new(),
+    };
+    let mut params = Map::new();
+    params.insert("to".to_owned(), json!(to));
+    params.insert("value".to_owned(), json!(value.to_string()));
+    let data = tx
+        .data
+        .as_ref()
+        .map(|data| data.as_ref())
+        .unwrap_or_default();
+    if to.is_empty() {
+        return Ok(TransactionDescription::new(
+            "contract_creation",
+            Message::new("preview.contract_creation", &[]),
+            String::new(),
+            params,
+        ));
+    }
+    if data.is_empty() {
+        return Ok(TransactionDescription::new(
+            "native_transfer",
+            Message::new(
+                "preview.native_transfer",
+                &[("value", value.to_string()), ("to", to)],
+            ),
+            String::new(),
+            params,
+        ));
+    }
+    let selector = &data[..data.len().min(4)];
+    let abi: Option<Abi> = if abi_json.trim().is_empty() {
+        None
+    } else {
+        Some(serde_json::from_str(abi_json)?)
+    };
+    // the ERC-721 contracts have `ownerOf`
+    let erc721 = abi
+        .as_ref()
+        .map(|abi| abi.functions_by_name("ownerOf").is_ok());
+    let function = abi
+        .as_ref()
+        .and_then(|abi| find_function(abi, selector))
+        .or_else(|| find_function(&ethers::abi::parse_abi(STANDARD_METHODS).ok()?, selector));
+    let decoded = function.and_then(|function| {
+        let tokens = function.decode_input(data.get(4..)?).ok()?;
+        Some((function, tokens))
+    });
+    let Some((function, tokens)) = decoded else {
+        return Ok(TransactionDescription::new(
+            "contract_call",
+            Message::new(
+                "preview.unknown_call",
+                &[
+                    ("selector", format!("0x{}", hex::encode(selector))),
+                    ("contract", to),
+                ],
+            ),
+            String::new(),
+            params,
+        ));
+    };
+    let mut args = Map::new();
+    for (index, (input, token)) in function.inputs.iter().zip(tokens).enumerate() {
+        let name = if input.name.is_empty() {
+            format!("arg{index}")
+        } else {
+            input.name.clone()
+        };
+        args.insert(name, token_to_json(token));
+    }
+    let (action, message) = describe_call(&function, &args, &to, erc721);
+    let message = if value.is_zero() {
+        message
+    } else {
+        with_value(message, value)
+    };
+    params.insert("contract".to_owned(), json!(to));
+    params.insert("args".to_owned(), Value::Object(args));
+    let description = TransactionDescription::new(action, message, function.signature(), params);
+    Ok(description)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::abi::Token;
+    use ethers::types::Address;
+
+    const CONTRACT: &str = "0x939d7350c54228e4958e05b65512c4a5bb6a2acc";
+
+    /// the ABI-encoded call of the method
+    fn encode_call(signature: &str, tokens: &[Token]) -> String {
+        let abi = ethers::abi::parse_abi(&[signature]).unwrap();
+        let function = abi.functions().next().unwrap();
+        format!("0x{}", hex::encode(function.encode_input(tokens).unwrap()))
+    }
+
+    fn tx(data: &str) -> String {
+        json!({ "to": CONTRACT, "data": data }).to_string()
+    }
+
+    #[test]
+    fn test_describe_token_calls() {
+        let spender = Address::repeat_byte(2);
+        let approve = encode_call(
+            "function approve(address spender, uint256 amount)",
+            &[Token::Address(spender), Token::Uint(1000.into())],
+        );
+        let description = describe_transaction(&tx(&approve), "").unwrap();
+        assert_eq!(description.action, "approve");
+        assert_eq!(
+            description.summary,
+            format!("Approve 1000 of {CONTRACT} to {spender:?}")
+        );
+        assert_eq!(description.method, "approve(address,uint256)");
+        let params: Value = serde_json::from_str(&description.params_json).unwrap();
+        assert_eq!(params["args"]["amount"], "1000");
+        assert_eq!(description.message_id, "preview.approve");
+        let message_params: Value = serde_json::from_str(&description.message_params_json).unwrap();
+        assert_eq!(message_params["spender"], format!("{spender:?}"));
+
+        let unlimited = encode_call(
+            "function approve(address spender, uint256 amount)",
+            &[Token::Address(spender), Token::Uint(U256::MAX)],
+        );
+        assert!(describe_transaction(&tx(&unlimited), "")
+            .unwrap()
+            .summary
+            .starts_with("Approve an unlimited amount"));
+
+        let transfer_from = encode_call(
+            "function transferFrom(address from, address to, uint256 amount)",
+            &[
+                Token::Address(spender),
+                Token::Address(spender),
+                Token::Uint(7.into()),
+            ],
+        );
+        let erc721 = r#"[{"type": "function", "name": "ownerOf", "stateMutability": "view",
+            "inputs": [{"name": "tokenId", "type": "uint256"}],
+            "outputs": [{"name": "", "type": "address"}]}]"#;
+        let description = describe_transaction(&tx(&transfer_from), erc721).unwrap();
+        assert_eq!(description.message_id, "preview.transfer_token");
+        assert_eq!(
+            description.summary,
+            format!("Transfer token 7 of {CONTRACT} from {spender:?} to {spender:?}")
+        );
+        let erc20 = r#"[{"type": "function", "name": "transferFrom", "stateMutability": "nonpayable",
+            "inputs": [{"name": "from", "type": "address"}, {"name": "to", "type": "address"},
+            {"name": "value", "type": "uint256"}], "outputs": [{"name": "", "type": "bool"}]}]"#;
+        let description = describe_transaction(&tx(&transfer_from), erc20).unwrap();
+        assert_eq!(description.message_id, "preview.transfer_from");
+        assert!(description.summary.starts_with("Transfer 7 of"));
+        // the standard method is either of them
+        let description = describe_transaction(&tx(&transfer_from), "").unwrap();
+        assert_eq!(description.message_id, "preview.transfer_from_unknown");
+
+        let mint = encode_call(
+            "function safeMint(address to, uint256 tokenId)",
+            &[Token::Address(spender), Token::Uint(1.into())],
+        );
+        assert_eq!(
+            describe_transaction(&tx(&mint), "").unwrap().summary,
+            format!("Mint token 1 of {CONTRACT} to {spender:?}")
+        );
+    }
+
+    #[test]
+    fn test_describe_other_transactions() {
+        let abi = r#"[{"type": "function", "name": "craft", "stateMutability": "payable",
+            "inputs": [{"name": "recipe", "type": "uint256"}], "outputs": []}]"#;
+        let craft = encode_call("function craft(uint256 recipe)", &[Token::Uint(7.into())]);
+        let request = json!({ "to": CONTRACT, "data": craft, "value": "0x64" }).to_string();
+        let description = describe_transaction(&request, abi).unwrap();
+        assert_eq!(description.action, "contract_call");
+        assert_eq!(
+            description.summary,
+            format!("Call craft on {CONTRACT} (sending 100)")
+        );
+        assert_eq!(description.message_id, "preview.with_value");
+        let message_params: Value = serde_json::from_str(&description.message_params_json).unwrap();
+        assert_eq!(message_params["summary_id"], "preview.contract_call");
+        assert_eq!(message_params["method"], "craft");
+        assert_eq!(message_params["value"], "100");
+        // not in the ABI nor a standard method
+        let description = describe_transaction(&request, "").unwrap();
+        assert_eq!(
+            description.summary,
+            format!("Call 0x{} on {CONTRACT}", &craft[2..10])
+        );
+
+        let send = json!({ "to": CONTRACT, "value": "0x3e8" }).to_string();
+        let description = describe_transaction(&send, "").unwrap();
+        assert_eq!(description.action, "native_transfer");
+        assert_eq!(description.summary, format!("Send 1000 to {CONTRACT}"));
+        let deploy = json!({ "data": "0x6080" }).to_string();
+        assert_eq!(
+            describe_transaction(&deploy, "").unwrap().action,
+            "contract_creation"
+        );
+        assert!(describe_transaction("not a transaction", "").is_err());
+    }
+}
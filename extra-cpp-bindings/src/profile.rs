@@ -0,0 +1,526 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use bech32::{FromBase32, ToBase32, Variant};
+use bindings_common::timestamp;
+use bindings_common::value::normalize_value;
+use ethers::core::k256::ecdsa::signature::hazmat::PrehashVerifier;
+use ethers::core::k256::ecdsa::{Signature as EcdsaSignature, VerifyingKey};
+use ethers::core::rand::{thread_rng, Rng};
+use ethers::types::Signature;
+use ripemd::{Digest, Ripemd160};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cosmos::{get_all_pages, Page, Pagination};
+use crate::ffi::{
+    AssetAccount, CosmosCoin, ProfileAddress, ProfileAddressKind, ProfileAsset, ProfileEndpoints,
+    RawTxDetail, ValueKind,
+};
+use crate::hashing::sha256;
+
+/// An address linked to a profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LinkedAddress {
+    /// hexadecimal (lowercase) or bech32
+    address: String,
+    /// the message to be signed with the key of the address
+    challenge: String,
+    /// whether the challenge was signed
+    verified: bool,
+}
+
+impl LinkedAddress {
+    fn kind(&self) -> ProfileAddressKind {
+        if self.address.starts_with("0x") {
+            ProfileAddressKind::Evm
+        } else {
+            ProfileAddressKind::Cosmos
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileState {
+    profile_id: String,
+    addresses: Vec<LinkedAddress>,
+}
+
+/// the bech32 address of the compressed secp256k1 public key with the prefix
+/// (e.g. "cro"): ripemd160(sha256(public key))
+fn cosmos_address(prefix: &str, public_key: &[u8]) -> Result<String> {
+    let hash = Ripemd160::digest(sha256(public_key.to_vec()));
+    Ok(bech32::encode(prefix, hash.to_base32(), Variant::Bech32)?)
+}
+
+/// the ADR-036 sign doc of the arbitrary data signed by the Cosmos address
+/// (the amino JSON with the sorted keys, as signed by Keplr's `signArbitrary`)
+fn adr036_sign_doc(signer: &str, data: &[u8]) -> String {
+    serde_json::json!({
+        "account_number": "0",
+        "chain_id": "",
+        "fee": { "amount": [], "gas": "0" },
+        "memo": "",
+        "msgs": [{
+            "type": "sign/MsgSignData",
+            "value": {
+                "data": base64::engine::general_purpose::STANDARD.encode(data),
+                "signer": signer,
+            },
+        }],
+        "sequence": "0",
+    })
+    .to_string()
+}
+
+/// verifies the EIP-191 signature (like `personal_sign`) of the message by the address
+fn verify_evm(address: &str, message: &str, signature: &[u8]) -> Result<()> {
+    let signature = Signature::try_from(signature)?;
+    let recovered = signature.recover(message)?;
+    if !format!("{recovered:?}").eq_ignore_ascii_case(address) {
+        return Err(anyhow!("the signature is not of {address}"));
+    }
+    Ok(())
+}
+
+/// verifies the ADR-036 signature (64 bytes, r || s) of the message by the address
+/// and its compressed public key
+fn verify_cosmos(address: &str, message: &str, signature: &[u8], public_key: &[u8]) -> Result<()> {
+    let (prefix, _, _) = bech32::decode(address)?;
+    if cosmos_address(&prefix, public_key)? != address {
+        return Err(anyhow!("the public key is not of {address}"));
+    }
+    let key = VerifyingKey::from_sec1_bytes(public_key)?;
+    let signature = EcdsaSignature::from_slice(signature)?;
+    let digest = sha256(adr036_sign_doc(address, message.as_bytes()).into_bytes());
+    key.verify_prehash(&digest, &signature)
+        .map_err(|_| anyhow!("the signature is not of {address}"))
+}
+
+/// the lowercase hexadecimal address or the bech32 one
+fn normalize(address: &str) -> Result<String> {
+    if address.starts_with("0x") {
+        return Ok(crate::normalize_address(address)?);
+    }
+    let (prefix, data, variant) = bech32::decode(address)?;
+    if variant != Variant::Bech32 || Vec::<u8>::from_base32(&data)?.len() != 20 {
+        return Err(anyhow!("invalid Cosmos address: {address}"));
+    }
+    Ok(bech32::encode(&prefix, data, Variant::Bech32)?)
+}
+
+/// A transaction of the LCD `GetTxsEvent` response
+#[derive(Deserialize)]
+struct CosmosTxResponse {
+    txhash: String,
+    height: String,
+    timestamp: String,
+    tx: Value,
+}
+
+impl CosmosTxResponse {
+    /// the transaction as the history entry: the sender, the receiver and the first coin
+    /// of its first message (e.g. `MsgSend`)
+    fn into_detail(self) -> RawTxDetail {
+        let message = &self.tx["body"]["messages"][0];
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| message[*name].as_str())
+                .unwrap_or_default()
+                .to_owned()
+        };
+        let coin: Option<CosmosCoin> = match &message["amount"] {
+            Value::Array(coins) => coins.first(),
+            amount => Some(amount),
+        }
+        .and_then(|coin| serde_json::from_value(coin.clone()).ok());
+        let (value, raw_value, formatted_value, value_kind) = match coin {
+            Some(coin) => {
+                let decimals = crate::asset::cosmos_denom_info(&coin.denom).map(|(_, d)| d);
+                let (raw_value, formatted_value) = normalize_value(&coin.amount, decimals);
+                let kind = if decimals.is_some() {
+                    ValueKind::Native
+                } else {
+                    ValueKind::Unknown
+                };
+                (coin.amount, raw_value, formatted_value, kind)
+            }
+            None => (
+                "0".to_owned(),
+                "0".to_owned(),
+                "0".to_owned(),
+                ValueKind::Unknown,
+            ),
+        };
+        let (timestamp_epoch, timestamp_iso) = timestamp::normalize(&self.timestamp);
+        RawTxDetail {
+            hash: self.txhash,
+            to_address: field(&["to_address", "receiver", "validator_address"]),
+            from_address: field(&["from_address", "sender", "delegator_address"]),
+            value,
+            raw_value,
+            formatted_value,
+            value_kind,
+            block_no: self.height.parse().unwrap_or_default(),
+            timestamp: self.timestamp,
+            timestamp_epoch,
+            timestamp_iso,
+            contract_address: String::new(),
+            display_name: String::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TxsPage {
+    #[serde(default)]
+    tx_responses: Vec<CosmosTxResponse>,
+    pagination: Option<Pagination>,
+}
+
+impl Page for TxsPage {
+    type Item = CosmosTxResponse;
+
+    fn pagination(&self) -> Option<&Pagination> {
+        self.pagination.as_ref()
+    }
+
+    fn into_items(self) -> Vec<CosmosTxResponse> {
+        self.tx_responses
+    }
+}
+
+/// the transactions sent and received by the Cosmos address (via the LCD url)
+fn get_cosmos_history(lcd_url: &str, address: &str) -> Result<Vec<RawTxDetail>> {
+    let mut history = vec![];
+    for event in ["message.sender", "transfer.recipient"] {
+        let mut url = url::Url::parse(&format!(
+            "{}/cosmos/tx/v1beta1/txs",
+            lcd_url.trim_end_matches('/')
+        ))?;
+        url.query_pairs_mut()
+            .append_pair("events", &format!("{event}='{address}'"))
+            .append_pair("order_by", "ORDER_BY_DESC");
+        history.extend(
+            get_all_pages::<TxsPage>(url.as_str())?
+                .into_iter()
+                .map(CosmosTxResponse::into_detail),
+        );
+    }
+    Ok(history)
+}
+
+/// The identity of a player across the addresses (EVM and Cosmos) proven to be theirs by
+/// a signed challenge, so that the holdings and the history are queried across all of them;
+/// persisted as a JSON file (if the path is set)
+#[derive(Debug)]
+pub struct PlayerProfile {
+    state: ProfileState,
+    path: Option<PathBuf>,
+}
+
+impl PlayerProfile {
+    pub(crate) fn open(profile_id: String, path: String) -> Result<Self> {
+        if profile_id.is_empty() {
+            return Err(anyhow!("empty profile id"));
+        }
+        let path = (!path.is_empty()).then(|| PathBuf::from(path));
+        let state = match &path {
+            Some(path) if path.exists() => {
+                let state: ProfileState = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                if state.profile_id != profile_id {
+                    return Err(anyhow!(
+                        "the profile file is of the profile {}",
+                        state.profile_id
+                    ));
+                }
+                state
+            }
+            _ => ProfileState {
+                profile_id,
+                addresses: vec![],
+            },
+        };
+        Ok(Self { state, path })
+    }
+
+    /// writes the profile (via a temporary file, so that it's never partially written)
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, serde_json::to_string(&self.state)?)?;
+            std::fs::rename(tmp, path)?;
+        }
+        Ok(())
+    }
+
+    fn linked(&self, address: &str) -> Option<&LinkedAddress> {
+        self.state
+            .addresses
+            .iter()
+            .find(|linked| linked.address == address)
+    }
+
+    pub fn profile_id(&self) -> String {
+        self.state.profile_id.clone()
+    }
+
+    /// adds the address (unverified) and returns the challenge to be signed with its key
+    /// (the same one until it's verified)
+    pub fn add_address(&mut self, address: String) -> Result<String> {
+        let address = normalize(&address)?;
+        if let Some(linked) = self.linked(&address) {
+            if linked.verified {
+                return Err(anyhow!("{address} is already verified"));
+            }
+            return Ok(linked.challenge.clone());
+        }
+        let nonce: [u8; 16] = thread_rng().gen();
+        let challenge = format!(
+            "Link {address} to the player profile {}\nNonce: {}",
+            self.state.profile_id,
+            hex::encode(nonce)
+        );
+        self.state.addresses.push(LinkedAddress {
+            address,
+            challenge: challenge.clone(),
+            verified: false,
+        });
+        self.save()?;
+        Ok(challenge)
+    }
+
+    /// verifies the signature of the challenge of the address: the EIP-191 one (65 bytes)
+    /// of an EVM address, or the ADR-036 one (64 bytes) of a Cosmos address with its
+    /// compressed public key (33 bytes)
+    pub fn verify_address(
+        &mut self,
+        address: String,
+        signature: Vec<u8>,
+        public_key: Vec<u8>,
+    ) -> Result<()> {
+        let address = normalize(&address)?;
+        let linked = self
+            .linked(&address)
+            .ok_or_else(|| anyhow!("{address} is not in the profile"))?;
+        match linked.kind() {
+            ProfileAddressKind::Evm => verify_evm(&address, &linked.challenge, &signature)?,
+            _ => verify_cosmos(&address, &linked.challenge, &signature, &public_key)?,
+        }
+        if let Some(linked) = self
+            .state
+            .addresses
+            .iter_mut()
+            .find(|linked| linked.address == address)
+        {
+            linked.verified = true;
+        }
+        self.save()
+    }
+
+    /// removes the address, returns false if it wasn't in the profile
+    pub fn remove_address(&mut self, address: String) -> Result<bool> {
+        let address = normalize(&address)?;
+        let count = self.state.addresses.len();
+        self.state
+            .addresses
+            .retain(|linked| linked.address != address);
+        if self.state.addresses.len() == count {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    pub fn addresses(&self) -> Vec<ProfileAddress> {
+        self.state
+            .addresses
+            .iter()
+            .map(|linked| ProfileAddress {
+                address: linked.address.clone(),
+                kind: linked.kind(),
+                verified: linked.verified,
+            })
+            .collect()
+    }
+
+    fn verified(&self) -> impl Iterator<Item = &LinkedAddress> {
+        self.state.addresses.iter().filter(|linked| linked.verified)
+    }
+
+    /// the assets of all the verified addresses (the EVM ones via the JSON-RPC and
+    /// BlockScout urls, the Cosmos ones via the LCD url; skipped if the url is empty)
+    pub fn get_holdings_blocking(&self, endpoints: &ProfileEndpoints) -> Result<Vec<ProfileAsset>> {
+        let rt = crate::runtime::new_runtime()?;
+        let mut holdings = vec![];
+        for linked in self.verified() {
+            let mut account = AssetAccount::default();
+            match linked.kind() {
+                ProfileAddressKind::Evm if !endpoints.rpc_url.is_empty() => {
+                    account.evm_address = linked.address.clone();
+                    account.rpc_url = endpoints.rpc_url.clone();
+                    account.blockscout_base_url = endpoints.blockscout_base_url.clone();
+                }
+                ProfileAddressKind::Cosmos if !endpoints.lcd_url.is_empty() => {
+                    account.cosmos_address = linked.address.clone();
+                    account.lcd_url = endpoints.lcd_url.clone();
+                }
+                _ => continue,
+            }
+            let assets = rt.block_on(crate::asset::get_all_assets(&account))?;
+            holdings.extend(assets.into_iter().map(|asset| ProfileAsset {
+                address: linked.address.clone(),
+                asset,
+            }));
+        }
+        Ok(holdings)
+    }
+
+    /// the transactions of all the verified addresses (newest first; a transaction
+    /// between two of them is returned once): the EVM ones via Cronoscan (the API key
+    /// can be obtained from https://cronoscan.com), the Cosmos ones via the LCD url
+    /// (skipped if it's empty)
+    pub fn get_history_blocking(
+        &self,
+        api_key: String,
+        lcd_url: String,
+    ) -> Result<Vec<RawTxDetail>> {
+        let mut history: Vec<RawTxDetail> = vec![];
+        for linked in self.verified() {
+            let txs = match linked.kind() {
+                ProfileAddressKind::Evm => crate::get_transaction_history_blocking(
+                    linked.address.clone(),
+                    api_key.clone(),
+                )?,
+                ProfileAddressKind::Cosmos if !lcd_url.is_empty() => {
+                    get_cosmos_history(&lcd_url, &linked.address)?
+                }
+                _ => continue,
+            };
+            for tx in txs {
+                if !history.iter().any(|existing| existing.hash == tx.hash) {
+                    history.push(tx);
+                }
+            }
+        }
+        // the block numbers of the chains aren't comparable, so the times are
+        history.sort_by_key(|tx| std::cmp::Reverse((tx.timestamp_epoch, tx.block_no)));
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::core::k256::ecdsa::signature::hazmat::PrehashSigner;
+    use ethers::core::k256::ecdsa::SigningKey;
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::utils::hash_message;
+
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_cosmos_address() {
+        // the public key of the private key 1 (the generator point), as in BIP-173
+        let public_key =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let address = cosmos_address("cro", &public_key).unwrap();
+        assert!(address.starts_with("cro1w508d6qejxtdg4y5r3zarvary0c5xw7k"));
+        assert_eq!(normalize(&address.to_uppercase()).unwrap(), address);
+        assert!(normalize("cro1invalid").is_err());
+    }
+
+    #[test]
+    fn test_adr036_vector() {
+        // the sign doc of Keplr's `signArbitrary("cro1...", "hello")`
+        let address = "cro15428vq2uzwhm3taey9sr9x5vm6tk78ewp0axjg";
+        assert_eq!(
+            adr036_sign_doc(address, b"hello"),
+            r#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"sign/MsgSignData","value":{"data":"aGVsbG8=","signer":"cro15428vq2uzwhm3taey9sr9x5vm6tk78ewp0axjg"}}],"sequence":"0"}"#
+        );
+        // its (RFC 6979) signature by the key of `PRIVATE_KEY`
+        let key = SigningKey::from_slice(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let public_key = key.verifying_key().to_encoded_point(true);
+        let signature = hex::decode("04087373e2c9a7bfe4ea00e08bfc1d0f4503319d7367f1ca3d6e28c1bf12e4eb515102a365910bbf0fff68ed1cd6ccb6c64dd8b69897a98cf49e571081bc6e01").unwrap();
+        verify_cosmos(address, "hello", &signature, public_key.as_bytes()).unwrap();
+        assert!(verify_cosmos(address, "hello!", &signature, public_key.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_cosmos_history() {
+        let page: TxsPage = serde_json::from_str(
+            r#"{"tx_responses":[{"height":"5123","txhash":"ABCD","timestamp":"2022-03-03T14:35:56Z","tx":{"body":{"messages":[{"@type":"/cosmos.bank.v1beta1.MsgSend","from_address":"cro1a","to_address":"cro1b","amount":[{"denom":"basecro","amount":"150000000"}]}]}}},{"height":"5000","txhash":"EF01","timestamp":"2022-03-02T00:00:00Z","tx":{"body":{"messages":[{"@type":"/cosmos.staking.v1beta1.MsgDelegate","delegator_address":"cro1a","validator_address":"crocncl1v","amount":{"denom":"uatom","amount":"7"}}]}}}],"pagination":{"next_key":null,"total":"2"}}"#,
+        )
+        .unwrap();
+        assert!(page.pagination().unwrap().next_key.is_none());
+        let history: Vec<RawTxDetail> = page
+            .into_items()
+            .into_iter()
+            .map(CosmosTxResponse::into_detail)
+            .collect();
+        assert_eq!(history[0].hash, "ABCD");
+        assert_eq!(history[0].from_address, "cro1a");
+        assert_eq!(history[0].to_address, "cro1b");
+        assert_eq!(history[0].block_no, 5123);
+        assert_eq!(history[0].formatted_value, "1.5");
+        assert_eq!(history[0].value_kind, ValueKind::Native);
+        assert_eq!(history[0].timestamp_epoch, 1646318156);
+        assert_eq!(history[1].to_address, "crocncl1v");
+        assert_eq!(history[1].value, "7");
+        assert_eq!(history[1].value_kind, ValueKind::Unknown);
+    }
+
+    #[test]
+    fn test_player_profile_verification() {
+        let path = std::env::temp_dir().join(format!("profile-{}.json", uuid::Uuid::new_v4()));
+        let path_text = path.to_string_lossy().to_string();
+        let mut profile = PlayerProfile::open("player-1".into(), path_text.clone()).unwrap();
+
+        // EVM: personal_sign of the challenge
+        let wallet: LocalWallet = PRIVATE_KEY.parse().unwrap();
+        let evm_address = format!("{:?}", wallet.address());
+        let challenge = profile
+            .add_address(evm_address.to_uppercase().replace("0X", "0x"))
+            .unwrap();
+        assert_eq!(profile.add_address(evm_address.clone()).unwrap(), challenge);
+        let other = LocalWallet::from_bytes(&[1u8; 32]).unwrap();
+        let forged = other.sign_hash(hash_message(&challenge)).unwrap();
+        assert!(profile
+            .verify_address(evm_address.clone(), forged.to_vec(), vec![])
+            .is_err());
+        let signature = wallet.sign_hash(hash_message(&challenge)).unwrap();
+        profile
+            .verify_address(evm_address.clone(), signature.to_vec(), vec![])
+            .unwrap();
+
+        // Cosmos: ADR-036 of the challenge with the public key
+        let key = SigningKey::from_slice(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let public_key = key
+            .verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec();
+        let cosmos_address = cosmos_address("cro", &public_key).unwrap();
+        let challenge = profile.add_address(cosmos_address.clone()).unwrap();
+        let digest = sha256(adr036_sign_doc(&cosmos_address, challenge.as_bytes()).into_bytes());
+        let signature: EcdsaSignature = key.sign_prehash(&digest).unwrap();
+        assert!(profile
+            .verify_address(cosmos_address.clone(), signature.to_vec(), vec![2; 33])
+            .is_err());
+        profile
+            .verify_address(cosmos_address.clone(), signature.to_vec(), public_key)
+            .unwrap();
+
+        // reopened from the file
+        let profile = PlayerProfile::open("player-1".into(), path_text.clone()).unwrap();
+        let addresses = profile.addresses();
+        assert_eq!(addresses.len(), 2);
+        assert!(addresses.iter().all(|address| address.verified));
+        assert_eq!(addresses[1].kind, ProfileAddressKind::Cosmos);
+        assert!(PlayerProfile::open("player-2".into(), path_text).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use bindings_common::timestamp::parse_epoch;
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+
+use crate::ffi::{QueryFilter, RawTokenTxDetail, RawTxDetail, TransferDirection, TxListOptions};
+use crate::{http, parse_address};
+
+/// The fields of the transactions and the transfers that are filtered
+pub(crate) trait Filtered {
+    fn hash(&self) -> &str;
+    fn sender(&self) -> &str;
+    fn receiver(&self) -> &str;
+    fn raw_value(&self) -> &str;
+    fn timestamp(&self) -> &str;
+    /// the contracts of the record (the receiver or the created contract of a transaction)
+    fn contracts(&self) -> Vec<&str>;
+}
+
+impl Filtered for RawTxDetail {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn sender(&self) -> &str {
+        &self.from_address
+    }
+
+    fn receiver(&self) -> &str {
+        &self.to_address
+    }
+
+    fn raw_value(&self) -> &str {
+        &self.raw_value
+    }
+
+    fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    fn contracts(&self) -> Vec<&str> {
+        vec![&self.to_address, &self.contract_address]
+    }
+}
+
+impl Filtered for RawTokenTxDetail {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    fn sender(&self) -> &str {
+        &self.from_address
+    }
+
+    fn receiver(&self) -> &str {
+        &self.to_address
+    }
+
+    fn raw_value(&self) -> &str {
+        &self.raw_value
+    }
+
+    fn timestamp(&self) -> &str {
+        &self.timestamp
+    }
+
+    fn contracts(&self) -> Vec<&str> {
+        vec![&self.contract_address]
+    }
+}
+
+/// The explorer of the records, which looks up the blocks of the time range
+pub(crate) enum Explorer<'a> {
+    /// the Cronoscan API (of the SDK environment) with the API key
+    Cronoscan(&'a str),
+    /// the base url of the BlockScout API
+    BlockScout(&'a str),
+}
+
+/// the block of `getblocknobytime`: a string on Cronoscan, an object on BlockScout
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BlockNumber {
+    Number(String),
+    Object {
+        #[serde(rename = "blockNumber")]
+        block_number: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct BlockNumberResponse {
+    status: String,
+    result: BlockNumber,
+}
+
+impl BlockNumberResponse {
+    fn block(&self) -> Result<u64> {
+        let block = match &self.result {
+            BlockNumber::Number(block)
+            | BlockNumber::Object {
+                block_number: block,
+            } => block,
+        };
+        if self.status != "1" {
+            anyhow::bail!("no block at the time: {block}");
+        }
+        Ok(block.parse()?)
+    }
+}
+
+impl Explorer<'_> {
+    /// the first block after the time or the last one before it (`closest`)
+    async fn block_at(&self, timestamp: u64, closest: &str) -> Result<u64> {
+        let mut url: url::Url = match self {
+            Self::Cronoscan(api_key) => crate::explorer_url(api_key)?,
+            Self::BlockScout(base_url) => base_url.parse()?,
+        };
+        url.query_pairs_mut()
+            .append_pair("module", "block")
+            .append_pair("action", "getblocknobytime")
+            .append_pair("timestamp", &timestamp.to_string())
+            .append_pair("closest", closest);
+        if let Self::Cronoscan(api_key) = self {
+            url.query_pairs_mut().append_pair("apikey", api_key);
+        }
+        let response: BlockNumberResponse = http::get_json_async(url.as_str()).await?;
+        response.block()
+    }
+}
+
+/// the block range of the options within the blocks of the time range
+/// (none if they don't overlap)
+fn narrow(
+    options: &TxListOptions,
+    first_block: Option<u64>,
+    last_block: Option<u64>,
+) -> Option<TxListOptions> {
+    let start_block = options.start_block.max(first_block.unwrap_or_default());
+    let end_block = match (options.end_block, last_block) {
+        (0, last_block) => last_block.unwrap_or_default(),
+        (end_block, Some(last_block)) => end_block.min(last_block),
+        (end_block, None) => end_block,
+    };
+    if end_block != 0 && start_block > end_block {
+        return None;
+    }
+    Some(TxListOptions {
+        start_block,
+        end_block,
+        page: options.page,
+        offset: options.offset,
+        sort: options.sort,
+    })
+}
+
+/// The parsed `QueryFilter` of the queried address
+pub(crate) struct Filter {
+    address: Address,
+    direction: TransferDirection,
+    min_value: Option<U256>,
+    contracts: Vec<Address>,
+    from_timestamp: u64,
+    to_timestamp: u64,
+    dedup_by_hash: bool,
+}
+
+/// whether the address (hexadecimal) is the expected one
+fn is_address(address: &str, expected: &Address) -> bool {
+    parse_address(address).ok().as_ref() == Some(expected)
+}
+
+impl Filter {
+    pub(crate) fn new(address: &str, filter: &QueryFilter) -> Result<Self> {
+        let min_value = match filter.min_value.trim() {
+            "" => None,
+            value => Some(
+                U256::from_dec_str(value).map_err(|_| anyhow!("invalid minimum value: {value}"))?,
+            ),
+        };
+        Ok(Self {
+            address: parse_address(address)?,
+            direction: filter.direction,
+            min_value,
+            contracts: filter
+                .contract_addresses
+                .iter()
+                .map(|contract| Ok(parse_address(contract)?))
+                .collect::<Result<_>>()?,
+            from_timestamp: filter.from_timestamp,
+            to_timestamp: filter.to_timestamp,
+            dedup_by_hash: filter.dedup_by_hash,
+        })
+    }
+
+    /// the contract that the explorer filters by (if there's only one)
+    pub(crate) fn single_contract(&self) -> Option<String> {
+        match self.contracts.as_slice() {
+            [contract] => Some(format!("{contract:?}")),
+            _ => None,
+        }
+    }
+
+    /// the options with the block range narrowed to the time range, so that the explorer
+    /// filters by time too (the bounds the explorer can't look up aren't narrowed, the records
+    /// are filtered anyway); none if no block is in both ranges
+    pub(crate) async fn block_range(
+        &self,
+        options: &TxListOptions,
+        explorer: Explorer<'_>,
+    ) -> Option<TxListOptions> {
+        let first_block = match self.from_timestamp {
+            0 => None,
+            time => explorer.block_at(time, "after").await.ok(),
+        };
+        let last_block = match self.to_timestamp {
+            0 => None,
+            time => explorer.block_at(time, "before").await.ok(),
+        };
+        narrow(options, first_block, last_block)
+    }
+
+    fn matches(&self, record: &impl Filtered) -> bool {
+        let direction = match self.direction {
+            TransferDirection::Incoming => is_address(record.receiver(), &self.address),
+            TransferDirection::Outgoing => is_address(record.sender(), &self.address),
+            _ => true,
+        };
+        let value = match self.min_value {
+            Some(min_value) => U256::from_dec_str(record.raw_value()).is_ok_and(|v| v >= min_value),
+            None => true,
+        };
+        let contract = self.contracts.is_empty()
+            || record
+                .contracts()
+                .iter()
+                .any(|address| self.contracts.iter().any(|c| is_address(address, c)));
+        // the records without a known time aren't in a time range
+        let time = (self.from_timestamp == 0 && self.to_timestamp == 0)
+            || parse_epoch(record.timestamp()).is_some_and(|time| {
+                time >= self.from_timestamp && (self.to_timestamp == 0 || time <= self.to_timestamp)
+            });
+        direction && value && contract && time
+    }
+
+    /// the matching records in the same order (the first one of each hash if deduplicated)
+    pub(crate) fn apply<R: Filtered>(&self, records: Vec<R>) -> Vec<R> {
+        let mut hashes = BTreeSet::new();
+        records
+            .into_iter()
+            .filter(|record| self.matches(record))
+            .filter(|record| !self.dedup_by_hash || hashes.insert(record.hash().to_lowercase()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::SortOrder;
+
+    const PLAYER: &str = "0x841a15d12aec9c6039fd132c2fbff112ed355700";
+    const OTHER: &str = "0x9ad08de843158b0a4f8efdae6ea49caf77bbf13f";
+    const TOKEN: &str = "0x715b4d660148c477e03358f8b0315ed4088fe89a";
+
+    fn transfer(hash: &str, from: &str, to: &str, value: &str, time: &str) -> RawTokenTxDetail {
+        RawTokenTxDetail {
+            to_address: to.to_owned(),
+            from_address: from.to_owned(),
+            value: value.to_owned(),
+            raw_value: value.to_owned(),
+            timestamp: time.to_owned(),
+            hash: hash.to_owned(),
+            contract_address: TOKEN.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn filter() -> QueryFilter {
+        QueryFilter {
+            direction: TransferDirection::Any,
+            min_value: String::default(),
+            contract_addresses: vec![],
+            from_timestamp: 0,
+            to_timestamp: 0,
+            dedup_by_hash: false,
+        }
+    }
+
+    fn hashes(filter: &QueryFilter, records: &[RawTokenTxDetail]) -> Vec<String> {
+        Filter::new(PLAYER, filter)
+            .unwrap()
+            .apply(records.to_vec())
+            .into_iter()
+            .map(|record| record.hash)
+            .collect()
+    }
+
+    #[test]
+    fn test_query_filter() {
+        let records = vec![
+            transfer("0x01", OTHER, PLAYER, "100", "1000"),
+            transfer("0x02", PLAYER, OTHER, "5", "2000"),
+            transfer("0x02", PLAYER, OTHER, "7", "2000"),
+            transfer(
+                "0x03",
+                OTHER,
+                "0x9AD08DE843158B0A4F8EFDAE6EA49CAF77BBF13F",
+                "1",
+                "x",
+            ),
+        ];
+        assert_eq!(hashes(&filter(), &records).len(), 4);
+
+        let incoming = QueryFilter {
+            direction: TransferDirection::Incoming,
+            ..filter()
+        };
+        assert_eq!(hashes(&incoming, &records), vec!["0x01"]);
+        let outgoing = QueryFilter {
+            direction: TransferDirection::Outgoing,
+            dedup_by_hash: true,
+            ..filter()
+        };
+        assert_eq!(hashes(&outgoing, &records), vec!["0x02"]);
+        let min_value = QueryFilter {
+            min_value: "7".to_owned(),
+            ..filter()
+        };
+        assert_eq!(hashes(&min_value, &records), vec!["0x01", "0x02"]);
+        let time_range = QueryFilter {
+            from_timestamp: 1500,
+            to_timestamp: 2000,
+            ..filter()
+        };
+        assert_eq!(hashes(&time_range, &records), vec!["0x02", "0x02"]);
+        // the ISO-8601 timestamps are in the time range too
+        let iso = vec![transfer("0x04", OTHER, PLAYER, "1", "1970-01-01T00:25:00Z")];
+        assert_eq!(hashes(&time_range, &iso), vec!["0x04"]);
+        let other_contract = QueryFilter {
+            contract_addresses: vec![OTHER.to_owned()],
+            ..filter()
+        };
+        assert!(hashes(&other_contract, &records).is_empty());
+        let token = QueryFilter {
+            contract_addresses: vec![TOKEN.to_uppercase().replace("0X", "0x")],
+            ..filter()
+        };
+        let parsed = Filter::new(PLAYER, &token).unwrap();
+        assert_eq!(parsed.single_contract().as_deref(), Some(TOKEN));
+        assert_eq!(hashes(&token, &records).len(), 4);
+
+        let invalid = QueryFilter {
+            min_value: "1.5".to_owned(),
+            ..filter()
+        };
+        assert!(Filter::new(PLAYER, &invalid).is_err());
+    }
+
+    #[test]
+    fn test_block_range() {
+        let block = |json: &str| serde_json::from_str::<BlockNumberResponse>(json)?.block();
+        // Cronoscan and BlockScout
+        let cronoscan = r#"{"status":"1","message":"OK","result":"7000"}"#;
+        assert_eq!(block(cronoscan).unwrap(), 7000);
+        let blockscout = r#"{"status":"1","message":"OK","result":{"blockNumber":"7001"}}"#;
+        assert_eq!(block(blockscout).unwrap(), 7001);
+        let error = r#"{"status":"0","message":"NOTOK","result":"Error! No closest block found"}"#;
+        assert!(block(error).is_err());
+
+        let options = TxListOptions {
+            start_block: 100,
+            end_block: 0,
+            page: 2,
+            offset: 10,
+            sort: SortOrder::Descending,
+        };
+        let range = |options: &TxListOptions, first, last| {
+            narrow(options, first, last).map(|options| (options.start_block, options.end_block))
+        };
+        assert_eq!(range(&options, None, None), Some((100, 0)));
+        assert_eq!(range(&options, Some(50), Some(500)), Some((100, 500)));
+        assert_eq!(range(&options, Some(200), None), Some((200, 0)));
+        let narrowed = narrow(&options, Some(200), Some(500)).unwrap();
+        assert_eq!((narrowed.page, narrowed.offset), (2, 10));
+        assert!(narrowed.sort == SortOrder::Descending);
+        let options = TxListOptions {
+            end_block: 300,
+            ..options
+        };
+        assert_eq!(range(&options, Some(200), Some(500)), Some((200, 300)));
+        assert_eq!(range(&options, Some(400), None), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use cxx::UniquePtr;
+use serde_json::Value;
+
+use crate::ffi::{ExchangeRate, RateServiceCallback};
+use crate::http;
+
+/// the CoinGecko API (the free one; rate-limited)
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3";
+/// the shortest refresh interval
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+/// the CoinGecko ids of the common symbols (the other ones are used as ids)
+const COINGECKO_IDS: &[(&str, &str)] = &[
+    ("CRO", "crypto-com-chain"),
+    ("WCRO", "wrapped-cro"),
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("USDC", "usd-coin"),
+    ("USDT", "tether"),
+    ("DAI", "dai"),
+    ("VVS", "vvs-finance"),
+];
+
+/// A pair "BASE/QUOTE", e.g. "CRO/USD"
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Pair {
+    base: String,
+    quote: String,
+}
+
+impl Pair {
+    fn parse(pair: &str) -> Result<Self> {
+        match pair.split_once('/') {
+            Some((base, quote)) if !base.trim().is_empty() && !quote.trim().is_empty() => {
+                Ok(Self {
+                    base: base.trim().to_uppercase(),
+                    quote: quote.trim().to_uppercase(),
+                })
+            }
+            _ => Err(anyhow!("invalid pair (e.g. \"CRO/USD\"): {pair}")),
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("{}/{}", self.base, self.quote)
+    }
+
+    fn coingecko_id(&self) -> String {
+        COINGECKO_IDS
+            .iter()
+            .find(|(symbol, _)| *symbol == self.base)
+            .map(|(_, id)| id.to_string())
+            .unwrap_or_else(|| self.base.to_lowercase())
+    }
+}
+
+/// the url of the prices of the pairs (in one request)
+fn prices_url(api_url: &str, pairs: &[Pair]) -> String {
+    let ids: BTreeSet<String> = pairs.iter().map(Pair::coingecko_id).collect();
+    let quotes: BTreeSet<String> = pairs.iter().map(|p| p.quote.to_lowercase()).collect();
+    format!(
+        "{}/simple/price?ids={}&vs_currencies={}",
+        api_url.trim_end_matches('/'),
+        ids.into_iter().collect::<Vec<_>>().join(","),
+        quotes.into_iter().collect::<Vec<_>>().join(",")
+    )
+}
+
+/// the rates of the pairs in the response (the missing ones are skipped)
+fn parse_rates(pairs: &[Pair], response: &Value, updated_at: u64) -> Vec<ExchangeRate> {
+    pairs
+        .iter()
+        .filter_map(|pair| {
+            let rate = response
+                .get(pair.coingecko_id())?
+                .get(pair.quote.to_lowercase())?;
+            Some(ExchangeRate {
+                pair: pair.name(),
+                base: pair.base.clone(),
+                quote: pair.quote.clone(),
+                rate: match rate {
+                    Value::Number(number) => number.to_string(),
+                    Value::String(text) => text.clone(),
+                    _ => return None,
+                },
+                updated_at,
+            })
+        })
+        .collect()
+}
+
+/// the cached rates by pair
+type Rates = Arc<RwLock<BTreeMap<String, ExchangeRate>>>;
+
+/// caches the fetched rates and returns the changed ones
+fn update_rates(rates: &Rates, fetched: Vec<ExchangeRate>) -> Vec<ExchangeRate> {
+    let mut rates = rates.write().expect("rates lock");
+    fetched
+        .into_iter()
+        .filter(|rate| {
+            let changed = rates
+                .get(&rate.pair)
+                .map(|cached| cached.rate != rate.rate)
+                .unwrap_or(true);
+            rates.insert(rate.pair.clone(), rate.clone());
+            changed
+        })
+        .collect()
+}
+
+/// Refreshes the exchange rates of a set of pairs from CoinGecko at an interval,
+/// caches them and calls the callback for each changed rate
+pub struct RateService {
+    pairs: Vec<Pair>,
+    api_url: String,
+    interval: Duration,
+    rates: Rates,
+    task: Option<tokio::task::JoinHandle<()>>,
+    rt: tokio::runtime::Runtime,
+}
+
+impl RateService {
+    pub(crate) fn new(pairs: &[String], interval_ms: u64, api_url: String) -> Result<Self> {
+        if pairs.is_empty() {
+            return Err(anyhow!("no pairs"));
+        }
+        Ok(Self {
+            pairs: pairs
+                .iter()
+                .map(|pair| Pair::parse(pair))
+                .collect::<Result<_>>()?,
+            api_url: if api_url.is_empty() {
+                COINGECKO_URL.to_owned()
+            } else {
+                api_url
+            },
+            interval: Duration::from_millis(interval_ms).max(MIN_INTERVAL),
+            rates: Default::default(),
+            task: None,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// starts refreshing (it replaces the previous callback)
+    pub fn start(&mut self, callback: UniquePtr<RateServiceCallback>) -> Result<()> {
+        if callback.is_null() {
+            anyhow::bail!("no callback");
+        }
+        self.stop();
+        let url = prices_url(&self.api_url, &self.pairs);
+        let pairs = self.pairs.clone();
+        let rates = self.rates.clone();
+        let interval = self.interval;
+        self.task = Some(self.rt.spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                match http::get_json_async::<Value>(&url).await {
+                    Ok(response) => {
+                        let now = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_secs();
+                        for rate in update_rates(&rates, parse_rates(&pairs, &response, now)) {
+                            callback.onRateUpdated(&rate);
+                        }
+                    }
+                    Err(e) => callback.onError(&e.to_string()),
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// stops refreshing (the cached rates are kept)
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// whether the service is refreshing
+    pub fn is_running(&self) -> bool {
+        self.task
+            .as_ref()
+            .map(|task| !task.is_finished())
+            .unwrap_or_default()
+    }
+
+    /// the cached rate of the pair (without any request)
+    pub fn get_rate(&self, pair: String) -> Result<ExchangeRate> {
+        let pair = Pair::parse(&pair)?.name();
+        self.rates
+            .read()
+            .expect("rates lock")
+            .get(&pair)
+            .cloned()
+            .ok_or_else(|| anyhow!("no rate of {pair} yet"))
+    }
+
+    /// the cached rates
+    pub fn get_rates(&self) -> Vec<ExchangeRate> {
+        self.rates
+            .read()
+            .expect("rates lock")
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for RateService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rates() {
+        let pairs: Vec<Pair> = ["cro/usd", "CRO/EUR", "ETH/USD", "ABC/USD"]
+            .iter()
+            .map(|pair| Pair::parse(pair).unwrap())
+            .collect();
+        assert!(Pair::parse("CRO").is_err());
+        assert_eq!(
+            prices_url(COINGECKO_URL, &pairs),
+            "https://api.coingecko.com/api/v3/simple/price\
+             ?ids=abc,crypto-com-chain,ethereum&vs_currencies=eur,usd"
+        );
+        let response: Value = serde_json::from_str(
+            r#"{"crypto-com-chain": {"usd": 0.0837, "eur": 0.0771}, "ethereum": {"usd": 1850.12}}"#,
+        )
+        .unwrap();
+        let fetched = parse_rates(&pairs, &response, 1680000000);
+        assert_eq!(fetched.len(), 3);
+        assert_eq!(fetched[0].pair, "CRO/USD");
+        assert_eq!(fetched[0].rate, "0.0837");
+
+        let rates = Rates::default();
+        assert_eq!(update_rates(&rates, fetched.clone()).len(), 3);
+        assert!(update_rates(&rates, fetched).is_empty());
+        let response = json!({"crypto-com-chain": {"usd": "0.0840"}});
+        let changed = update_rates(&rates, parse_rates(&pairs, &response, 1680000060));
+        assert_eq!(changed.len(), 1);
+        assert_eq!(rates.read().unwrap()["CRO/USD"].updated_at, 1680000060);
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::providers::{HttpClientError, JsonRpcClient, JsonRpcError, Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Bytes, U256};
+use ethers::utils::rlp::Rlp;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{chainstate, http, memory, parse_address};
+
+/// The JSON-RPC transport of the providers: HTTP requests sent via `http::execute_async`,
+/// so they are recorded in the health of the endpoints and in the network usage
+#[derive(Debug, Clone)]
+pub(crate) struct RpcTransport {
+    client: reqwest::Client,
+    url: url::Url,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcTransport {
+    /// the url of the JSON-RPC endpoint
+    pub(crate) fn url(&self) -> &url::Url {
+        &self.url
+    }
+}
+
+/// A JSON-RPC request
+#[derive(Serialize)]
+struct RpcRequest<'a, T> {
+    id: u64,
+    jsonrpc: &'static str,
+    method: &'a str,
+    params: T,
+}
+
+/// A JSON-RPC response: its result or its error
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+/// the deserialization error of the text (as reported by the ethers HTTP transport)
+fn serde_error(err: serde_json::Error, text: &str) -> HttpClientError {
+    HttpClientError::SerdeJson {
+        err,
+        text: text.to_owned(),
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcTransport {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, HttpClientError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let request = async move {
+            let payload = RpcRequest {
+                id: self.next_id.fetch_add(1, Ordering::SeqCst),
+                jsonrpc: "2.0",
+                method,
+                params,
+            };
+            let request = self.client.post(self.url.clone()).json(&payload).build()?;
+            // a JSON-RPC error (e.g. a reverted call) is answered by a healthy node
+            let (_, body) = http::execute_async(&self.client, request).await?;
+            let response: RpcResponse =
+                serde_json::from_str(&body).map_err(|e| serde_error(e, &body))?;
+            if let Some(error) = response.error {
+                return Err(HttpClientError::JsonRpcError(error));
+            }
+            // no result is null (e.g. `eth_getTransactionReceipt` of a pending transaction)
+            serde_json::from_value(response.result.unwrap_or_default())
+                .map_err(|e| serde_error(e, &body))
+        };
+        memory::scoped(memory::JSONRPC, request).await
+    }
+}
+
+/// the JSON-RPC provider of the SDK
+pub(crate) type RpcProvider = Provider<RpcTransport>;
+
+/// the JSON-RPC provider of the url (with the SDK's HTTP settings, e.g. the default headers)
+pub(crate) fn provider(rpc_url: &str) -> Result<RpcProvider> {
+    let url = url::Url::parse(rpc_url)?;
+    Ok(Provider::new(RpcTransport {
+        client: http::async_client()?,
+        url,
+        next_id: Arc::new(AtomicU64::new(1)),
+    }))
+}
+
+/// whether there is a contract code at the address (i.e. it's not an externally owned account)
+pub(crate) async fn is_contract(rpc_url: &str, address: &str) -> Result<bool> {
+    let address = parse_address(address)?;
+    chainstate::has_code(&provider(rpc_url)?, address).await
+}
+
+/// the number of transactions sent from the address (0 for a fresh account)
+pub(crate) async fn get_tx_count(rpc_url: &str, address: &str) -> Result<u64> {
+    let address = parse_address(address)?;
+    let count = chainstate::nonce(&provider(rpc_url)?, address).await?;
+    count
+        .try_into()
+        .map_err(|_| anyhow!("the transaction count is too large: {count}"))
+}
+
+/// the balance of the native coin of the address (in wei)
+pub(crate) async fn get_balance(rpc_url: &str, address: &str) -> Result<U256> {
+    let address = parse_address(address)?;
+    chainstate::balance(&provider(rpc_url)?, address).await
+}
+
+/// broadcasts the signed raw transaction (via `eth_sendRawTransaction`), returns its hash
+pub(crate) async fn send_raw_transaction(rpc_url: &str, raw_tx: Vec<u8>) -> Result<String> {
+    let provider = provider(rpc_url)?;
+    let pending = provider
+        .send_raw_transaction(Bytes::from(raw_tx.clone()))
+        .await?;
+    // the nonce and the balance of the sender change
+    if let Ok((tx, signature)) = TypedTransaction::decode_signed(&Rlp::new(&raw_tx)) {
+        if let (Some(chain_id), Ok(sender)) = (tx.chain_id(), signature.recover(tx.sighash())) {
+            chainstate::invalidate(chain_id.as_u64(), sender);
+        }
+    }
+    Ok(format!("{:?}", pending.tx_hash()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::GameSdkError;
+
+    #[test]
+    fn test_invalid_rpc_inputs() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // fails before sending the request to the (unreachable) url
+        let err = rt
+            .block_on(is_contract("http://127.0.0.1:1", "not an address"))
+            .expect_err("invalid address");
+        assert!(matches!(
+            err.downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::InvalidAddress(_))
+        ));
+        assert!(rt
+            .block_on(get_tx_count(
+                "not a url",
+                "0x841a15d12aec9c6039fd132c2fbff112ed355700"
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn test_rpc_endpoint_health() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider = provider("http://127.0.0.2:1").unwrap();
+        assert!(rt.block_on(provider.get_block_number()).is_err());
+        let health = http::endpoint_health()
+            .into_iter()
+            .find(|health| health.host == "127.0.0.2")
+            .expect("the JSON-RPC endpoint's health");
+        assert!(health.failure_count >= 1);
+        assert!(!health.last_error.is_empty());
+    }
+
+    #[test]
+    fn test_rpc_network_usage() {
+        use std::io::{Read, Write};
+
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":"0x10"}"#;
+        // a node answering one request
+        let node = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", node.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = node.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]).unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        let http_usage = || {
+            crate::netusage::usage()
+                .subsystems
+                .into_iter()
+                .find(|usage| usage.subsystem == "http")
+                .unwrap()
+        };
+        let before = http_usage();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let block = rt
+            .block_on(provider(&url).unwrap().get_block_number())
+            .unwrap();
+        assert_eq!(block, 16.into());
+        let after = http_usage();
+        assert!(after.requests > before.requests);
+        assert!(after.bytes_sent > before.bytes_sent);
+        assert!(after.bytes_received >= before.bytes_received + body.len() as u64);
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use cxx::UniquePtr;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::ffi::{RuntimeConfig, RuntimeThreadCallback};
+use crate::memory;
+
+/// the options of the SDK's runtimes (none if it's not set: the tokio defaults are used)
+static CONFIG: Mutex<Option<RuntimeConfig>> = Mutex::new(None);
+/// called on each new thread of the runtimes (none if it's not set)
+static THREAD_CALLBACK: Mutex<Option<UniquePtr<RuntimeThreadCallback>>> = Mutex::new(None);
+
+pub(crate) fn set_config(config: &RuntimeConfig, callback: UniquePtr<RuntimeThreadCallback>) {
+    *THREAD_CALLBACK.lock().expect("runtime callback lock") =
+        (!callback.is_null()).then_some(callback);
+    *CONFIG.lock().expect("runtime config lock") = Some(config.clone());
+}
+
+pub(crate) fn clear_config() {
+    *CONFIG.lock().expect("runtime config lock") = None;
+    *THREAD_CALLBACK.lock().expect("runtime callback lock") = None;
+}
+
+fn on_thread_start() {
+    if let Some(callback) = THREAD_CALLBACK
+        .lock()
+        .expect("runtime callback lock")
+        .as_ref()
+    {
+        let thread = std::thread::current();
+        callback.onThreadStart(thread.name().unwrap_or_default());
+    }
+}
+
+/// the builder of a multi-threaded runtime with the options (the allocations
+/// of its threads are counted for the memory subsystem of the caller's scope)
+fn builder(config: &RuntimeConfig) -> Builder {
+    let subsystem = memory::current_subsystem();
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all().on_thread_start(move || {
+        memory::tag_thread(subsystem);
+        on_thread_start();
+    });
+    if config.worker_threads != 0 {
+        builder.worker_threads(config.worker_threads as usize);
+    }
+    if config.max_blocking_threads != 0 {
+        builder.max_blocking_threads(config.max_blocking_threads as usize);
+    }
+    if !config.thread_name_prefix.is_empty() {
+        let prefix = config.thread_name_prefix.clone();
+        let next_id = AtomicUsize::new(0);
+        builder
+            .thread_name_fn(move || format!("{prefix}-{}", next_id.fetch_add(1, Ordering::SeqCst)));
+    }
+    builder
+}
+
+/// a new runtime of the SDK (with the options of `set_runtime_config`)
+pub(crate) fn new_runtime() -> std::io::Result<Runtime> {
+    let config = CONFIG.lock().expect("runtime config lock").clone();
+    // the defaults are the same as `Runtime::new`
+    builder(&config.unwrap_or_default()).build()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_runtime_config() {
+        let config = RuntimeConfig {
+            worker_threads: 1,
+            max_blocking_threads: 1,
+            thread_name_prefix: "game-sdk-net".to_owned(),
+        };
+        let rt = builder(&config).build().unwrap();
+        // the spawned tasks run on the worker threads
+        let task = rt.spawn(async { std::thread::current().name().map(str::to_owned) });
+        let name = rt.block_on(task).unwrap().unwrap();
+        assert!(name.starts_with("game-sdk-net-"), "{name}");
+
+        let rt = {
+            let _scope = memory::scope(memory::WALLETCONNECT);
+            builder(&config).build().unwrap()
+        };
+        let task = rt.spawn(async { memory::current_subsystem() });
+        assert_eq!(rt.block_on(task).unwrap(), memory::WALLETCONNECT);
+    }
+}
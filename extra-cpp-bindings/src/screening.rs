@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::Result;
+use ethers::abi::{decode, ParamType, Token};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::Address;
+use serde::Deserialize;
+
+use crate::error::GameSdkError;
+use crate::ffi::{RiskLevel, ScreeningVerdict};
+use crate::http;
+
+/// the denied addresses (lowercase) and the reasons
+static DENYLIST: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+/// the remote screening endpoint (none if empty)
+static SCREENING_URL: RwLock<String> = RwLock::new(String::new());
+
+/// An entry of the denylist JSON (the reason is optional)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DenylistEntry {
+    Address(String),
+    WithReason {
+        address: String,
+        #[serde(default)]
+        reason: String,
+    },
+}
+
+impl DenylistEntry {
+    fn into_pair(self) -> (String, String) {
+        match self {
+            DenylistEntry::Address(address) => (address.to_lowercase(), String::new()),
+            DenylistEntry::WithReason { address, reason } => (address.to_lowercase(), reason),
+        }
+    }
+}
+
+/// replaces the denylist with the JSON array, returns the number of the denied addresses
+pub(crate) fn load_denylist(json: &str) -> Result<u32> {
+    let entries: Vec<DenylistEntry> = serde_json::from_str(json)?;
+    let denylist: BTreeMap<String, String> =
+        entries.into_iter().map(DenylistEntry::into_pair).collect();
+    let count = denylist.len() as u32;
+    *DENYLIST.write().expect("denylist lock") = denylist;
+    Ok(count)
+}
+
+/// replaces the denylist with the JSON array downloaded from the url
+pub(crate) fn update_denylist(url: &str) -> Result<u32> {
+    let entries: serde_json::Value = http::get_json(url)?;
+    load_denylist(&entries.to_string())
+}
+
+pub(crate) fn add_denied_address(address: &str, reason: String) {
+    DENYLIST
+        .write()
+        .expect("denylist lock")
+        .insert(address.to_lowercase(), reason);
+}
+
+/// returns false if the address wasn't denied
+pub(crate) fn remove_denied_address(address: &str) -> bool {
+    DENYLIST
+        .write()
+        .expect("denylist lock")
+        .remove(&address.to_lowercase())
+        .is_some()
+}
+
+pub(crate) fn set_screening_endpoint(url: String) {
+    *SCREENING_URL.write().expect("screening url lock") = url;
+}
+
+/// The response of the remote screening endpoint
+#[derive(Deserialize)]
+struct RemoteVerdict {
+    /// "clean", "suspicious" or "denied"
+    risk: String,
+    #[serde(default)]
+    reason: String,
+}
+
+impl ScreeningVerdict {
+    fn new(address: &str, risk: RiskLevel, reason: String, source: &str) -> Self {
+        Self {
+            address: address.to_owned(),
+            risk,
+            reason,
+            source: source.to_owned(),
+        }
+    }
+}
+
+/// the verdict of the local denylist (none if the address isn't on it)
+fn denylist_verdict(address: &str) -> Option<ScreeningVerdict> {
+    let denylist = DENYLIST.read().expect("denylist lock");
+    let reason = denylist.get(&address.to_lowercase())?;
+    Some(ScreeningVerdict::new(
+        address,
+        RiskLevel::Denied,
+        reason.clone(),
+        "denylist",
+    ))
+}
+
+/// screens the address against the denylist and the remote endpoint (if it's set);
+/// a failed remote screening is `Suspicious`, so that it doesn't block the transactions
+pub(crate) async fn screen_address(address: &str) -> ScreeningVerdict {
+    if let Some(verdict) = denylist_verdict(address) {
+        return verdict;
+    }
+    let endpoint = SCREENING_URL.read().expect("screening url lock").clone();
+    if endpoint.is_empty() {
+        return ScreeningVerdict::new(address, RiskLevel::Clean, String::new(), "denylist");
+    }
+    let url = match url::Url::parse_with_params(&endpoint, [("address", address)]) {
+        Ok(url) => url,
+        Err(e) => {
+            return ScreeningVerdict::new(
+                address,
+                RiskLevel::Suspicious,
+                format!("screening unavailable: {e}"),
+                "endpoint",
+            )
+        }
+    };
+    match http::get_json_async::<RemoteVerdict>(url.as_str()).await {
+        Ok(remote) => {
+            let risk = match remote.risk.to_lowercase().as_str() {
+                "clean" => RiskLevel::Clean,
+                "denied" => RiskLevel::Denied,
+                _ => RiskLevel::Suspicious,
+            };
+            ScreeningVerdict::new(address, risk, remote.reason, "endpoint")
+        }
+        Err(e) => ScreeningVerdict::new(
+            address,
+            RiskLevel::Suspicious,
+            format!("screening unavailable: {e}"),
+            "endpoint",
+        ),
+    }
+}
+
+/// the recipient (or the approved operator) of the token calls:
+/// ERC-20/721/1155 transfers, approvals and `setApprovalForAll`
+fn calldata_recipient(data: &[u8]) -> Option<Address> {
+    let (selector, args) = (data.get(..4)?, data.get(4..)?);
+    let index = match selector {
+        // transfer(address,uint256), approve(address,uint256), setApprovalForAll(address,bool)
+        [0xa9, 0x05, 0x9c, 0xbb] | [0x09, 0x5e, 0xa7, 0xb3] | [0xa2, 0x2c, 0xb4, 0x65] => 0,
+        // transferFrom(address,address,uint256) and the safeTransferFrom variants
+        [0x23, 0xb8, 0x72, 0xdd]
+        | [0x42, 0x84, 0x2e, 0x0e]
+        | [0xb8, 0x8d, 0x4f, 0xde]
+        | [0xf2, 0x42, 0x43, 0x2a]
+        | [0x2e, 0xb2, 0xc2, 0xd6] => 1,
+        _ => return None,
+    };
+    let words = decode(
+        &vec![ParamType::Address; index + 1],
+        args.get(..32 * (index + 1))?,
+    )
+    .ok()?;
+    match words.get(index)? {
+        Token::Address(address) => Some(*address),
+        _ => None,
+    }
+}
+
+/// screens the destination and the token recipient of the transaction before it's signed
+/// or sent; it fails if any of them is denied
+pub(crate) async fn check_transaction(tx: &TypedTransaction) -> Result<()> {
+    let mut addresses = vec![];
+    if let Some(to) = tx.to_addr() {
+        addresses.push(*to);
+    }
+    if let Some(recipient) = tx.data().and_then(|data| calldata_recipient(data)) {
+        addresses.push(recipient);
+    }
+    for address in addresses {
+        let verdict = screen_address(&format!("{address:?}")).await;
+        if verdict.risk == RiskLevel::Denied {
+            return Err(GameSdkError::ScreeningDenied {
+                address: verdict.address,
+                reason: verdict.reason,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::types::Eip1559TransactionRequest;
+
+    #[test]
+    fn test_calldata_recipient() {
+        let recipient = Address::repeat_byte(7);
+        let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        data.extend(ethers::abi::encode(&[
+            Token::Address(recipient),
+            Token::Uint(1.into()),
+        ]));
+        assert_eq!(calldata_recipient(&data), Some(recipient));
+        let mut data = vec![0x42, 0x84, 0x2e, 0x0e];
+        data.extend(ethers::abi::encode(&[
+            Token::Address(Address::repeat_byte(1)),
+            Token::Address(recipient),
+            Token::Uint(1.into()),
+        ]));
+        assert_eq!(calldata_recipient(&data), Some(recipient));
+        assert_eq!(calldata_recipient(&data[..40]), None);
+        assert_eq!(calldata_recipient(&[0xde, 0xad, 0xbe, 0xef]), None);
+    }
+
+    #[test]
+    fn test_denylist_screening() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let denied = Address::repeat_byte(0x5c);
+        let token = Address::repeat_byte(0x5d);
+        let mut data = vec![0xa9, 0x05, 0x9c, 0xbb];
+        data.extend(ethers::abi::encode(&[
+            Token::Address(denied),
+            Token::Uint(1.into()),
+        ]));
+        let tx = TypedTransaction::Eip1559(Eip1559TransactionRequest::new().to(token).data(data));
+        rt.block_on(check_transaction(&tx)).unwrap();
+
+        add_denied_address(&format!("{denied:?}").to_uppercase(), "phishing".into());
+        let verdict = rt.block_on(screen_address(&format!("{denied:?}")));
+        assert_eq!(verdict.risk, RiskLevel::Denied);
+        assert_eq!(verdict.reason, "phishing");
+        let err = rt.block_on(check_transaction(&tx)).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<GameSdkError>(),
+            Some(GameSdkError::ScreeningDenied { .. })
+        ));
+        assert!(remove_denied_address(&format!("{denied:?}")));
+        assert!(!remove_denied_address(&format!("{denied:?}")));
+        rt.block_on(check_transaction(&tx)).unwrap();
+    }
+}
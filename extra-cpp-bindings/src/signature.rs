@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Result};
+use ethers::types::{Signature, U256};
+
+use crate::ffi::SignatureParts;
+
+impl From<&Signature> for SignatureParts {
+    fn from(sig: &Signature) -> Self {
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        sig.r.to_big_endian(&mut r);
+        sig.s.to_big_endian(&mut s);
+        SignatureParts {
+            r: r.to_vec(),
+            s: s.to_vec(),
+            v: sig.v,
+        }
+    }
+}
+
+impl SignatureParts {
+    /// the recovery id (0 or 1) from v (0/1, 27/28 or EIP-155, i.e. 35 or more)
+    fn y_parity(&self) -> Result<u8> {
+        match self.v {
+            0 | 1 => Ok(self.v as u8),
+            27 | 28 => Ok((self.v - 27) as u8),
+            v if v >= 35 => Ok(((v - 35) % 2) as u8),
+            v => Err(anyhow!("invalid v: {v}")),
+        }
+    }
+
+    fn check_lengths(&self) -> Result<()> {
+        if self.r.len() != 32 || self.s.len() != 32 {
+            return Err(anyhow!("r and s must be 32 bytes"));
+        }
+        Ok(())
+    }
+}
+
+/// splits a 65-byte signature (r || s || v) into its parts
+pub fn signature_from_rsv(signature: Vec<u8>) -> Result<SignatureParts> {
+    if signature.len() != 65 {
+        return Err(anyhow!("the signature must be 65 bytes (r || s || v)"));
+    }
+    Ok(SignatureParts {
+        r: signature[..32].to_vec(),
+        s: signature[32..64].to_vec(),
+        v: signature[64] as u64,
+    })
+}
+
+/// joins the parts into a 65-byte signature (r || s || v)
+pub fn signature_to_rsv(signature: &SignatureParts) -> Result<Vec<u8>> {
+    signature.check_lengths()?;
+    let v: u8 = signature
+        .v
+        .try_into()
+        .map_err(|_| anyhow!("v does not fit in a byte: {}", signature.v))?;
+    let mut bytes = Vec::with_capacity(65);
+    bytes.extend_from_slice(&signature.r);
+    bytes.extend_from_slice(&signature.s);
+    bytes.push(v);
+    Ok(bytes)
+}
+
+/// encodes the parts into a 64-byte EIP-2098 compact signature (r || yParity and s)
+pub fn signature_to_eip2098(signature: &SignatureParts) -> Result<Vec<u8>> {
+    signature.check_lengths()?;
+    if signature.s[0] & 0x80 != 0 {
+        return Err(anyhow!("s is not in the lower half of the curve order"));
+    }
+    let mut bytes = Vec::with_capacity(64);
+    bytes.extend_from_slice(&signature.r);
+    bytes.extend_from_slice(&signature.s);
+    bytes[32] |= signature.y_parity()? << 7;
+    Ok(bytes)
+}
+
+/// decodes a 64-byte EIP-2098 compact signature (v is 27 or 28)
+pub fn signature_from_eip2098(compact: Vec<u8>) -> Result<SignatureParts> {
+    if compact.len() != 64 {
+        return Err(anyhow!("the compact signature must be 64 bytes"));
+    }
+    let mut s = compact[32..].to_vec();
+    let y_parity = s[0] >> 7;
+    s[0] &= 0x7f;
+    Ok(SignatureParts {
+        r: compact[..32].to_vec(),
+        s,
+        v: 27 + y_parity as u64,
+    })
+}
+
+impl TryFrom<&SignatureParts> for Signature {
+    type Error = anyhow::Error;
+
+    fn try_from(signature: &SignatureParts) -> Result<Self> {
+        signature.check_lengths()?;
+        Ok(Signature {
+            r: U256::from_big_endian(&signature.r),
+            s: U256::from_big_endian(&signature.s),
+            v: signature.v,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn test_eip2098_vectors() {
+        // https://eips.ethereum.org/EIPS/eip-2098#test-cases
+        let parts = SignatureParts {
+            r: hex!("68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b90").to_vec(),
+            s: hex!("7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064").to_vec(),
+            v: 27,
+        };
+        let compact = signature_to_eip2098(&parts).expect("compact");
+        assert_eq!(compact[32..], parts.s[..]);
+        assert_eq!(signature_from_eip2098(compact).expect("parts"), parts);
+
+        let parts = SignatureParts {
+            r: hex!("9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76").to_vec(),
+            s: hex!("139c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793").to_vec(),
+            v: 28,
+        };
+        let compact = signature_to_eip2098(&parts).expect("compact");
+        assert_eq!(
+            compact[32..],
+            hex!("939c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793")
+        );
+        assert_eq!(signature_from_eip2098(compact).expect("parts"), parts);
+
+        let rsv = signature_to_rsv(&parts).expect("rsv");
+        assert_eq!(rsv.len(), 65);
+        assert_eq!(signature_from_rsv(rsv).expect("parts"), parts);
+        let sig = Signature::try_from(&parts).expect("signature");
+        assert_eq!(SignatureParts::from(&sig), parts);
+    }
+
+    #[test]
+    fn test_invalid_signatures() {
+        assert!(signature_from_rsv(vec![0; 64]).is_err());
+        assert!(signature_from_eip2098(vec![0; 65]).is_err());
+        let parts = SignatureParts {
+            r: vec![0; 31],
+            s: vec![0; 32],
+            v: 27,
+        };
+        assert!(signature_to_rsv(&parts).is_err());
+        for v in [2, 26, 29, 34] {
+            let parts = SignatureParts {
+                r: vec![0; 32],
+                s: vec![0; 32],
+                v,
+            };
+            assert!(signature_to_eip2098(&parts).is_err());
+        }
+        let eip155 = SignatureParts {
+            r: vec![0; 32],
+            s: vec![0; 32],
+            v: 25 * 2 + 36,
+        };
+        assert_eq!(signature_to_eip2098(&eip155).expect("compact")[32], 0x80);
+    }
+}
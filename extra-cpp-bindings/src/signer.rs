@@ -0,0 +1,170 @@
+use anyhow::{anyhow, Result};
+use cxx::UniquePtr;
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::signers::{to_eip155_v, LocalWallet};
+use ethers::types::{Address, RecoveryMessage, Signature, H256, U256};
+
+use crate::ffi::ExternalSignerCallback;
+use crate::policy::Reservation;
+
+/// the order of the secp256k1 curve
+const SECP256K1_N: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+/// A signer of the 32-byte digests: the in-process keys or an external one
+/// (e.g. an HSM, a KMS or a remote signing service)
+pub(crate) trait Signer: Send + Sync {
+    fn address(&self) -> Address;
+    /// signs the digest as it is (v is 27 or 28)
+    fn sign_digest(&self, digest: H256) -> Result<Signature>;
+}
+
+impl Signer for LocalWallet {
+    fn address(&self) -> Address {
+        ethers::signers::Signer::address(self)
+    }
+
+    fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        Ok(self.sign_hash(digest)?)
+    }
+}
+
+/// the in-process wallet of the 32-byte private key
+pub(crate) fn local_wallet(private_key: &[u8]) -> Result<LocalWallet> {
+    LocalWallet::from_bytes(private_key).map_err(|e| anyhow!("invalid private key: {e}"))
+}
+
+/// the checks before any signer signs or sends the transaction: the chain of the SDK
+/// environment, the fee caps (or the override), the policy and the screening;
+/// the value reserved by the policy is committed once it's signed (or maybe sent)
+pub(crate) async fn check_transaction(
+    tx: &TypedTransaction,
+    fee_cap: Option<U256>,
+) -> Result<Reservation> {
+    if let Some(chain_id) = tx.chain_id() {
+        crate::environment::chain_id(chain_id.as_u64())?;
+    }
+    crate::feecap::check_transaction(tx, fee_cap)?;
+    let reservation = crate::policy::check_transaction(tx).await?;
+    crate::screening::check_transaction(tx).await?;
+    Ok(reservation)
+}
+
+/// `check_transaction` of the blocking signers (e.g. the dev wallets and Ledger)
+pub(crate) fn check_transaction_blocking(
+    tx: &TypedTransaction,
+    fee_cap: Option<U256>,
+) -> Result<Reservation> {
+    crate::runtime::new_runtime()?.block_on(check_transaction(tx, fee_cap))
+}
+
+/// signs the transaction on its chain (v is EIP-155)
+pub(crate) fn sign_transaction(signer: &dyn Signer, tx: &TypedTransaction) -> Result<Signature> {
+    let chain_id = tx
+        .chain_id()
+        .ok_or_else(|| anyhow!("the transaction has no chain id"))?
+        .as_u64();
+    let mut signature = signer.sign_digest(tx.sighash())?;
+    signature.v = to_eip155_v((signature.v - 27) as u8, chain_id);
+    // the nonce and the balance change once it's sent
+    crate::chainstate::invalidate(chain_id, signer.address());
+    Ok(signature)
+}
+
+/// the signature of the digest by the address from the 64 (r || s) or 65 (r || s || v) bytes
+/// of an external signer: s is normalized to the lower half of the curve order (EIP-2)
+/// and v is the one recovering the address (27 or 28), as the HSMs don't always tell it
+pub(crate) fn normalize_signature(
+    bytes: &[u8],
+    digest: H256,
+    address: Address,
+) -> Result<Signature> {
+    if bytes.len() != 64 && bytes.len() != 65 {
+        return Err(anyhow!(
+            "the external signature must be 64 or 65 bytes, not {}",
+            bytes.len()
+        ));
+    }
+    let n = U256::from_str_radix(SECP256K1_N, 16).expect("curve order");
+    let r = U256::from_big_endian(&bytes[..32]);
+    let mut s = U256::from_big_endian(&bytes[32..64]);
+    if s > n / 2 {
+        s = n - s;
+    }
+    [27, 28]
+        .into_iter()
+        .map(|v| Signature { r, s, v })
+        .find(|signature| {
+            signature
+                .recover(RecoveryMessage::Hash(digest))
+                .is_ok_and(|recovered| recovered == address)
+        })
+        .ok_or_else(|| anyhow!("the external signature is not of {address:?}"))
+}
+
+/// A signer delegating the digests to the C++ callback (e.g. of an HSM)
+pub(crate) struct ExternalSigner {
+    address: Address,
+    callback: UniquePtr<ExternalSignerCallback>,
+}
+
+impl ExternalSigner {
+    pub(crate) fn new(address: &str, callback: UniquePtr<ExternalSignerCallback>) -> Result<Self> {
+        if callback.is_null() {
+            anyhow::bail!("no callback");
+        }
+        Ok(Self {
+            address: crate::parse_address(address)?,
+            callback,
+        })
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_digest(&self, digest: H256) -> Result<Signature> {
+        let bytes = self
+            .callback
+            .signDigest(&format!("{:?}", self.address), digest.as_bytes())
+            .map_err(|e| anyhow!("the external signer failed: {e}"))?;
+        normalize_signature(&bytes, digest, self.address)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_signature() {
+        let wallet: LocalWallet =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let address = Signer::address(&wallet);
+        let digest = H256::repeat_byte(7);
+        let signature = wallet.sign_digest(digest).unwrap();
+        let bytes = signature.to_vec();
+        // r || s without v
+        assert_eq!(
+            normalize_signature(&bytes[..64], digest, address).unwrap(),
+            signature
+        );
+        // a high s (e.g. of a KMS) is normalized
+        let n = U256::from_str_radix(SECP256K1_N, 16).unwrap();
+        let mut high = Signature {
+            s: n - signature.s,
+            ..signature
+        }
+        .to_vec();
+        high[64] = 0;
+        assert_eq!(
+            normalize_signature(&high, digest, address).unwrap(),
+            signature
+        );
+        assert!(normalize_signature(&bytes, H256::zero(), address).is_err());
+        assert!(normalize_signature(&bytes[..63], digest, address).is_err());
+    }
+}
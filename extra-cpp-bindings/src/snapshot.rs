@@ -0,0 +1,297 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+use ethers::abi::ParamType;
+use ethers::providers::Middleware;
+use ethers::types::{Address, Filter, Log, H256, U256};
+use ethers::utils::keccak256;
+
+use crate::ffi::SnapshotHolder;
+use crate::logscanner::{is_limit_error, Checkpoint, MAX_RANGE};
+use crate::{chainstate, parse_address, rpc};
+
+/// `Transfer(address,address,uint256)` of ERC-20 (the value in the data)
+/// and ERC-721 (the indexed token id)
+fn transfer_topic() -> H256 {
+    H256(keccak256("Transfer(address,address,uint256)"))
+}
+
+fn transfer_single_topic() -> H256 {
+    H256(keccak256(
+        "TransferSingle(address,address,address,uint256,uint256)",
+    ))
+}
+
+fn transfer_batch_topic() -> H256 {
+    H256(keccak256(
+        "TransferBatch(address,address,address,uint256[],uint256[])",
+    ))
+}
+
+fn topic_address(topic: &H256) -> Address {
+    Address::from_slice(&topic[12..])
+}
+
+/// The holdings of a token contract replayed from its transfers
+#[derive(Default)]
+struct Holdings {
+    balances: HashMap<Address, U256>,
+    /// the owners of the ERC-721 tokens, by token id
+    owners: BTreeMap<U256, Address>,
+}
+
+impl Holdings {
+    fn transfer(&mut self, from: Address, to: Address, amount: U256) {
+        // the zero address is the source of the mints and the sink of the burns
+        if !from.is_zero() {
+            let balance = self.balances.entry(from).or_default();
+            *balance = balance.saturating_sub(amount);
+            if balance.is_zero() {
+                self.balances.remove(&from);
+            }
+        }
+        if !to.is_zero() && !amount.is_zero() {
+            let balance = self.balances.entry(to).or_default();
+            *balance = balance.saturating_add(amount);
+        }
+    }
+
+    /// replays the transfer log (the other logs are ignored)
+    fn apply(&mut self, log: &Log) -> Result<()> {
+        let topics = &log.topics;
+        let invalid = || anyhow!("invalid transfer log in {:?}", log.transaction_hash);
+        match topics.first() {
+            // ERC-721: the token id is indexed
+            Some(topic) if *topic == transfer_topic() && topics.len() == 4 => {
+                let to = topic_address(&topics[2]);
+                let token_id = U256::from_big_endian(topics[3].as_bytes());
+                self.transfer(topic_address(&topics[1]), to, U256::one());
+                if to.is_zero() {
+                    self.owners.remove(&token_id);
+                } else {
+                    self.owners.insert(token_id, to);
+                }
+            }
+            Some(topic) if *topic == transfer_topic() && topics.len() == 3 => {
+                if log.data.len() < 32 {
+                    return Err(invalid());
+                }
+                let amount = U256::from_big_endian(&log.data[..32]);
+                self.transfer(topic_address(&topics[1]), topic_address(&topics[2]), amount);
+            }
+            Some(topic) if *topic == transfer_single_topic() && topics.len() == 4 => {
+                if log.data.len() < 64 {
+                    return Err(invalid());
+                }
+                let amount = U256::from_big_endian(&log.data[32..64]);
+                self.transfer(topic_address(&topics[2]), topic_address(&topics[3]), amount);
+            }
+            Some(topic) if *topic == transfer_batch_topic() && topics.len() == 4 => {
+                let amounts = ParamType::Array(Box::new(ParamType::Uint(256)));
+                let decoded = ethers::abi::decode(&[amounts.clone(), amounts], &log.data)?;
+                let values = decoded
+                    .into_iter()
+                    .nth(1)
+                    .and_then(|values| values.into_array())
+                    .ok_or_else(invalid)?;
+                let amount = values
+                    .into_iter()
+                    .filter_map(|value| value.into_uint())
+                    .fold(U256::zero(), |total, value| total.saturating_add(value));
+                self.transfer(topic_address(&topics[2]), topic_address(&topics[3]), amount);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// the holders with a positive balance, the largest first
+    fn holders(&self) -> Vec<SnapshotHolder> {
+        let mut token_ids: HashMap<Address, Vec<String>> = HashMap::new();
+        for (token_id, owner) in &self.owners {
+            token_ids
+                .entry(*owner)
+                .or_default()
+                .push(token_id.to_string());
+        }
+        let mut holders: Vec<(Address, U256)> = self
+            .balances
+            .iter()
+            .filter(|(_, balance)| !balance.is_zero())
+            .map(|(address, balance)| (*address, *balance))
+            .collect();
+        holders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        holders
+            .into_iter()
+            .map(|(address, balance)| SnapshotHolder {
+                address: format!("{address:?}"),
+                balance: balance.to_string(),
+                token_ids: token_ids.remove(&address).unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// reconstructs the holders of the token contract (ERC-20, ERC-721 or ERC-1155) at the block
+/// by replaying its transfer logs from `from_block` (e.g. the block of its deployment);
+/// the progress is called after each block range with the last replayed block
+/// and the number of the holders so far
+pub async fn snapshot_holders(
+    rpc_url: &str,
+    contract: &str,
+    from_block: u64,
+    block_number: u64,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<Vec<SnapshotHolder>> {
+    let contract = parse_address(contract)?;
+    if from_block > block_number {
+        return Err(anyhow!(
+            "the first block {from_block} is after the block {block_number}"
+        ));
+    }
+    let provider = rpc::provider(rpc_url)?;
+    let latest = provider.get_block_number().await?.as_u64();
+    chainstate::observe_block(&provider, latest);
+    if block_number > latest {
+        return Err(anyhow!(
+            "the block {block_number} is after the latest block {latest}"
+        ));
+    }
+    let topics = vec![
+        transfer_topic(),
+        transfer_single_topic(),
+        transfer_batch_topic(),
+    ];
+    let mut holdings = Holdings::default();
+    let mut checkpoint = Checkpoint {
+        next_block: from_block,
+        range: MAX_RANGE,
+    };
+    while checkpoint.next_block <= block_number {
+        let from = checkpoint.next_block;
+        let to = (from + checkpoint.range - 1).min(block_number);
+        let filter = Filter::new()
+            .address(contract)
+            .topic0(topics.clone())
+            .from_block(from)
+            .to_block(to);
+        let mut logs = match provider.get_logs(&filter).await {
+            Ok(logs) => logs,
+            Err(e) if is_limit_error(&e) && checkpoint.on_limit() => continue,
+            Err(e) => return Err(e.into()),
+        };
+        logs.retain(|log| log.removed != Some(true));
+        logs.sort_by_key(|log| (log.block_number, log.log_index));
+        for log in &logs {
+            holdings.apply(log)?;
+        }
+        checkpoint.on_success(to - from + 1);
+        progress(to, holdings.balances.len() as u64);
+    }
+    Ok(holdings.holders())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn topic(address: Address) -> H256 {
+        H256::from(address)
+    }
+
+    fn log(topics: Vec<H256>, data: Vec<u8>) -> Log {
+        Log {
+            topics,
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_snapshot_replay() {
+        let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let zero = Address::zero();
+
+        // ERC-721: mints, a transfer and a burn
+        let mut holdings = Holdings::default();
+        let nft = |from, to, id: u64| {
+            let id = H256::from_low_u64_be(id);
+            log(vec![transfer_topic(), topic(from), topic(to), id], vec![])
+        };
+        for transfer in [
+            nft(zero, alice, 1),
+            nft(zero, alice, 2),
+            nft(zero, alice, 3),
+            nft(alice, bob, 2),
+            nft(alice, zero, 3),
+        ] {
+            holdings.apply(&transfer).unwrap();
+        }
+        let holders = holdings.holders();
+        assert_eq!(holders.len(), 2);
+        assert_eq!(holders[0].address, format!("{alice:?}"));
+        assert_eq!(holders[0].balance, "1");
+        assert_eq!(holders[0].token_ids, vec!["1"]);
+        assert_eq!(holders[1].token_ids, vec!["2"]);
+
+        // ERC-20: the largest balance first, the emptied ones are dropped
+        let mut holdings = Holdings::default();
+        let erc20 = |from, to, amount: u64| {
+            log(
+                vec![transfer_topic(), topic(from), topic(to)],
+                H256::from_low_u64_be(amount).as_bytes().to_vec(),
+            )
+        };
+        for transfer in [
+            erc20(zero, alice, 100),
+            erc20(alice, bob, 70),
+            erc20(zero, alice, 5),
+        ] {
+            holdings.apply(&transfer).unwrap();
+        }
+        let holders = holdings.holders();
+        assert_eq!(holders[0].address, format!("{bob:?}"));
+        assert_eq!(
+            (holders[0].balance.as_str(), holders[1].balance.as_str()),
+            ("70", "35")
+        );
+        assert!(holders[0].token_ids.is_empty());
+        holdings.apply(&erc20(bob, alice, 70)).unwrap();
+        assert_eq!(holdings.holders().len(), 1);
+        assert!(holdings
+            .apply(&log(
+                vec![transfer_topic(), topic(alice), topic(bob)],
+                vec![]
+            ))
+            .is_err());
+
+        // ERC-1155: the amounts of all the ids
+        let mut holdings = Holdings::default();
+        let operator = topic(Address::repeat_byte(9));
+        // the id 7, the amount 3
+        let (id, amount) = (H256::from_low_u64_be(7), H256::from_low_u64_be(3));
+        holdings
+            .apply(&log(
+                vec![transfer_single_topic(), operator, topic(zero), topic(alice)],
+                [id.as_bytes(), amount.as_bytes()].concat(),
+            ))
+            .unwrap();
+        let batch = ethers::abi::encode(&[
+            ethers::abi::Token::Array(vec![
+                ethers::abi::Token::Uint(1.into()),
+                ethers::abi::Token::Uint(2.into()),
+            ]),
+            ethers::abi::Token::Array(vec![
+                ethers::abi::Token::Uint(10.into()),
+                ethers::abi::Token::Uint(20.into()),
+            ]),
+        ]);
+        holdings
+            .apply(&log(
+                vec![transfer_batch_topic(), operator, topic(zero), topic(alice)],
+                batch,
+            ))
+            .unwrap();
+        assert_eq!(holdings.holders()[0].balance, "33");
+    }
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::cosmos::{get_all_pages, Page, Pagination};
+use crate::ffi::{CosmosCoin, CosmosDelegation, CosmosReward, CosmosRewards, CosmosUnbondingEntry};
+use crate::http;
+
+#[derive(Deserialize)]
+struct RawDelegation {
+    validator_address: String,
+    shares: String,
+}
+
+#[derive(Deserialize)]
+struct RawDelegationResponse {
+    delegation: RawDelegation,
+    balance: CosmosCoin,
+}
+
+#[derive(Deserialize)]
+struct DelegationsPage {
+    delegation_responses: Vec<RawDelegationResponse>,
+    pagination: Option<Pagination>,
+}
+
+impl Page for DelegationsPage {
+    type Item = CosmosDelegation;
+
+    fn pagination(&self) -> Option<&Pagination> {
+        self.pagination.as_ref()
+    }
+
+    fn into_items(self) -> Vec<CosmosDelegation> {
+        self.delegation_responses
+            .into_iter()
+            .map(|response| CosmosDelegation {
+                validator_address: response.delegation.validator_address,
+                shares: response.delegation.shares,
+                balance: response.balance,
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawUnbondingEntry {
+    creation_height: String,
+    completion_time: String,
+    initial_balance: String,
+    balance: String,
+}
+
+#[derive(Deserialize)]
+struct RawUnbondingDelegation {
+    validator_address: String,
+    entries: Vec<RawUnbondingEntry>,
+}
+
+#[derive(Deserialize)]
+struct UnbondingPage {
+    unbonding_responses: Vec<RawUnbondingDelegation>,
+    pagination: Option<Pagination>,
+}
+
+impl Page for UnbondingPage {
+    type Item = CosmosUnbondingEntry;
+
+    fn pagination(&self) -> Option<&Pagination> {
+        self.pagination.as_ref()
+    }
+
+    fn into_items(self) -> Vec<CosmosUnbondingEntry> {
+        self.unbonding_responses
+            .into_iter()
+            .flat_map(|unbonding| {
+                let validator_address = unbonding.validator_address;
+                unbonding
+                    .entries
+                    .into_iter()
+                    .map(move |entry| CosmosUnbondingEntry {
+                        validator_address: validator_address.clone(),
+                        creation_height: entry.creation_height.parse().unwrap_or_default(),
+                        completion_time: entry.completion_time,
+                        initial_balance: entry.initial_balance,
+                        balance: entry.balance,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawRewards {
+    rewards: Vec<CosmosReward>,
+    total: Vec<CosmosCoin>,
+}
+
+/// the delegations of the delegator (with the validator, the shares and the balance)
+pub(crate) fn get_delegations(lcd_url: &str, address: &str) -> Result<Vec<CosmosDelegation>> {
+    let url = format!(
+        "{}/cosmos/staking/v1beta1/delegations/{address}",
+        lcd_url.trim_end_matches('/')
+    );
+    get_all_pages::<DelegationsPage>(&url)
+}
+
+/// the unbonding entries of the delegator (one for each undelegation being completed)
+pub(crate) fn get_unbonding(lcd_url: &str, address: &str) -> Result<Vec<CosmosUnbondingEntry>> {
+    let url = format!(
+        "{}/cosmos/staking/v1beta1/delegators/{address}/unbonding_delegations",
+        lcd_url.trim_end_matches('/')
+    );
+    get_all_pages::<UnbondingPage>(&url)
+}
+
+/// the pending rewards of the delegator by the validator, and their total
+pub(crate) fn get_rewards(lcd_url: &str, address: &str) -> Result<CosmosRewards> {
+    let url = format!(
+        "{}/cosmos/distribution/v1beta1/delegators/{address}/rewards",
+        lcd_url.trim_end_matches('/')
+    );
+    let rewards: RawRewards = http::get_json(&url)?;
+    Ok(CosmosRewards {
+        rewards: rewards.rewards,
+        total: rewards.total,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_staking_pages() {
+        let page: DelegationsPage = serde_json::from_str(
+            r#"{
+                "delegation_responses": [{
+                    "delegation": {
+                        "delegator_address": "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy",
+                        "validator_address": "crocncl1pk9eajj4zs3fsjnuyqlaspqs8f9ey3nd5wc3hd",
+                        "shares": "1000000000.000000000000000000"
+                    },
+                    "balance": {"denom": "basecro", "amount": "1000000000"}
+                }],
+                "pagination": {"next_key": "FPTi8Tr", "total": "2"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            page.pagination().unwrap().next_key.as_deref(),
+            Some("FPTi8Tr")
+        );
+        assert_eq!(
+            page.into_items(),
+            vec![CosmosDelegation {
+                validator_address: "crocncl1pk9eajj4zs3fsjnuyqlaspqs8f9ey3nd5wc3hd".into(),
+                shares: "1000000000.000000000000000000".into(),
+                balance: CosmosCoin {
+                    denom: "basecro".into(),
+                    amount: "1000000000".into()
+                },
+            }]
+        );
+
+        let page: UnbondingPage = serde_json::from_str(
+            r#"{
+                "unbonding_responses": [{
+                    "delegator_address": "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy",
+                    "validator_address": "crocncl1pk9eajj4zs3fsjnuyqlaspqs8f9ey3nd5wc3hd",
+                    "entries": [
+                        {"creation_height": "100", "completion_time": "2023-06-01T00:00:00Z",
+                         "initial_balance": "500", "balance": "500"},
+                        {"creation_height": "120", "completion_time": "2023-06-02T00:00:00Z",
+                         "initial_balance": "300", "balance": "290"}
+                    ]
+                }],
+                "pagination": {"next_key": null, "total": "1"}
+            }"#,
+        )
+        .unwrap();
+        assert!(page.pagination().unwrap().next_key.is_none());
+        let entries = page.into_items();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].creation_height, 120);
+        assert_eq!(entries[1].balance, "290");
+        assert_eq!(
+            entries[1].validator_address,
+            "crocncl1pk9eajj4zs3fsjnuyqlaspqs8f9ey3nd5wc3hd"
+        );
+
+        let rewards: RawRewards = serde_json::from_str(
+            r#"{
+                "rewards": [{
+                    "validator_address": "crocncl1pk9eajj4zs3fsjnuyqlaspqs8f9ey3nd5wc3hd",
+                    "reward": [{"denom": "basecro", "amount": "1234.567800000000000000"}]
+                }],
+                "total": [{"denom": "basecro", "amount": "1234.567800000000000000"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(rewards.rewards[0].reward, rewards.total);
+    }
+}
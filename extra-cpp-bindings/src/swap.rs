@@ -0,0 +1,421 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use ethers::abi::{Abi, Token};
+use ethers::providers::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Eip1559TransactionRequest, U256};
+
+use crate::amount::format_amount;
+use crate::ffi::{
+    AmountFormat, DexRouter, LiquidityPosition, RoundingMode, SwapOptions, SwapQuote,
+    WalletConnectTxCommon, WalletConnectTxEip155,
+};
+use crate::{chainstate, parse_address, rpc};
+
+/// the methods of the UniswapV2-style routers, factories and pairs
+const DEX_METHODS: &[&str] = &[
+    "function factory() view returns (address)",
+    "function getAmountsOut(uint256 amountIn, address[] path) view returns (uint256[] amounts)",
+    "function getPair(address tokenA, address tokenB) view returns (address pair)",
+    "function getReserves() view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)",
+    "function token0() view returns (address)",
+    "function token1() view returns (address)",
+    "function totalSupply() view returns (uint256)",
+    "function balanceOf(address owner) view returns (uint256)",
+    "function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) returns (uint256[] amounts)",
+    "function swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline) payable returns (uint256[] amounts)",
+    "function swapExactTokensForETH(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) returns (uint256[] amounts)",
+];
+
+/// the basis points of 100%
+const MAX_BPS: u32 = 10_000;
+
+fn dex_abi() -> Result<Abi> {
+    Ok(ethers::abi::parse_abi(DEX_METHODS)?)
+}
+
+/// the router address of the DEX on Cronos mainnet
+pub fn dex_router_address(router: DexRouter) -> Result<String> {
+    match router {
+        DexRouter::VvsFinance => Ok("0x145863Eb42Cf62847A6Ca784e6416C1682b1b2Ae".to_owned()),
+        DexRouter::MmFinance => Ok("0x145677FC4d9b8F19B5D56d1820c48e0443049a30".to_owned()),
+        _ => Err(anyhow!("unknown DEX router")),
+    }
+}
+
+/// calls the view method of the contract, returns the decoded outputs
+pub(crate) async fn call(
+    provider: &rpc::RpcProvider,
+    contract: Address,
+    abi: &Abi,
+    method: &str,
+    args: &[Token],
+) -> Result<Vec<Token>> {
+    let function = abi.function(method)?;
+    let tx = TypedTransaction::Eip1559(
+        Eip1559TransactionRequest::new()
+            .to(contract)
+            .data(function.encode_input(args)?),
+    );
+    let output = provider.call(&tx, None).await?;
+    Ok(function.decode_output(&output)?)
+}
+
+fn to_u256(token: &Token) -> Result<U256> {
+    token
+        .clone()
+        .into_uint()
+        .ok_or_else(|| anyhow!("unexpected output: {token}"))
+}
+
+fn to_address(token: &Token) -> Result<Address> {
+    token
+        .clone()
+        .into_address()
+        .ok_or_else(|| anyhow!("unexpected output: {token}"))
+}
+
+/// the approximate floating-point value of the integer
+fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// the price impact (in basis points, including the LP fees) of the swap of `amount_in`
+/// for `amount_out` through the pairs of the path: their (input, output) reserves
+fn price_impact_bps(amount_in: U256, amount_out: U256, reserves: &[(U256, U256)]) -> u32 {
+    // the output at the mid price of the route (i.e. without moving the reserves)
+    let ideal = reserves
+        .iter()
+        .fold(to_f64(amount_in), |amount, (reserve_in, reserve_out)| {
+            amount * to_f64(*reserve_out) / to_f64(*reserve_in)
+        });
+    if !ideal.is_finite() || ideal <= 0.0 {
+        return 0;
+    }
+    let impact = (1.0 - to_f64(amount_out) / ideal) * MAX_BPS as f64;
+    impact.round().clamp(0.0, MAX_BPS as f64) as u32
+}
+
+/// the minimum output of the swap with the tolerated slippage (in basis points)
+pub fn swap_amount_out_min(amount_out: String, slippage_bps: u32) -> Result<String> {
+    if slippage_bps > MAX_BPS {
+        return Err(anyhow!("the slippage is over 100%: {slippage_bps} bps"));
+    }
+    let amount_out = U256::from_dec_str(&amount_out)?;
+    // at most the output, so it doesn't overflow
+    let amount_out_min = amount_out.full_mul(U256::from(MAX_BPS - slippage_bps)) / MAX_BPS;
+    Ok(U256::try_from(amount_out_min)
+        .map_err(|_| anyhow!("the minimum output overflows"))?
+        .to_string())
+}
+
+/// quotes the swap of `amount_in` (in the base units of the first token of the path)
+/// with `getAmountsOut` of the router; the price impact is computed from the reserves
+/// of the pairs of the path
+pub(crate) async fn get_swap_quote(
+    rpc_url: &str,
+    router: &str,
+    path: &[String],
+    amount_in: &str,
+) -> Result<SwapQuote> {
+    if path.len() < 2 {
+        return Err(anyhow!("the swap path needs at least two tokens"));
+    }
+    let provider = rpc::provider(rpc_url)?;
+    let abi = dex_abi()?;
+    let router = parse_address(router)?;
+    let tokens = path
+        .iter()
+        .map(|token| parse_address(token))
+        .collect::<Result<Vec<_>, _>>()?;
+    let amount_in = U256::from_dec_str(amount_in)?;
+
+    let outputs = call(
+        &provider,
+        router,
+        &abi,
+        "getAmountsOut",
+        &[
+            Token::Uint(amount_in),
+            Token::Array(tokens.iter().copied().map(Token::Address).collect()),
+        ],
+    )
+    .await?;
+    let amounts = outputs
+        .first()
+        .and_then(|amounts| amounts.clone().into_array())
+        .ok_or_else(|| anyhow!("unexpected getAmountsOut output"))?
+        .iter()
+        .map(to_u256)
+        .collect::<Result<Vec<_>>>()?;
+    let amount_out = *amounts
+        .last()
+        .ok_or_else(|| anyhow!("unexpected getAmountsOut output"))?;
+
+    let factory = to_address(&call(&provider, router, &abi, "factory", &[]).await?[0])?;
+    let mut reserves = Vec::with_capacity(tokens.len() - 1);
+    for pair_tokens in tokens.windows(2) {
+        let (token_in, token_out) = (pair_tokens[0], pair_tokens[1]);
+        let pair = to_address(
+            &call(
+                &provider,
+                factory,
+                &abi,
+                "getPair",
+                &[Token::Address(token_in), Token::Address(token_out)],
+            )
+            .await?[0],
+        )?;
+        let outputs = call(&provider, pair, &abi, "getReserves", &[]).await?;
+        let (reserve0, reserve1) = (to_u256(&outputs[0])?, to_u256(&outputs[1])?);
+        // the reserves are in the order of the sorted token addresses
+        reserves.push(if token_in < token_out {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        });
+    }
+
+    Ok(SwapQuote {
+        chain_id: chainstate::chain_id(&provider).await?,
+        path: tokens.iter().map(|token| format!("{token:?}")).collect(),
+        amounts: amounts.iter().map(ToString::to_string).collect(),
+        amount_in: amount_in.to_string(),
+        amount_out: amount_out.to_string(),
+        price_impact_bps: price_impact_bps(amount_in, amount_out, &reserves),
+    })
+}
+
+/// the fraction digits of the pool share percentage
+const SHARE_DECIMALS: u32 = 8;
+
+/// the underlying amounts of the tokens of the LP tokens and their share of the pool
+/// (the percentage of the total supply)
+fn position_amounts(
+    lp_balance: U256,
+    total_supply: U256,
+    reserve0: U256,
+    reserve1: U256,
+) -> Result<(U256, U256, String)> {
+    if total_supply.is_zero() {
+        return Ok((U256::zero(), U256::zero(), "0".to_owned()));
+    }
+    let share = |amount: U256| -> Result<U256> {
+        U256::try_from(amount.full_mul(lp_balance) / total_supply)
+            .map_err(|_| anyhow!("the LP balance is over the total supply"))
+    };
+    let percent = share(U256::exp10(2 + SHARE_DECIMALS as usize))?;
+    let percent = format_amount(
+        percent.to_string(),
+        &AmountFormat {
+            decimals: SHARE_DECIMALS,
+            max_fraction_digits: SHARE_DECIMALS,
+            significant_digits: 0,
+            rounding: RoundingMode::Floor,
+            trim_zeros: true,
+            thousands_separator: String::new(),
+            decimal_mark: ".".to_owned(),
+        },
+    )?;
+    Ok((share(reserve0)?, share(reserve1)?, percent))
+}
+
+/// the liquidity position of the account in the UniswapV2-style pair (the LP token contract):
+/// its LP tokens, the underlying amounts of the tokens and its share of the pool
+pub(crate) async fn get_liquidity_position(
+    rpc_url: &str,
+    pair: &str,
+    account: &str,
+) -> Result<LiquidityPosition> {
+    let provider = rpc::provider(rpc_url)?;
+    let abi = dex_abi()?;
+    let pair = parse_address(pair)?;
+    let account = parse_address(account)?;
+
+    let token0 = to_address(&call(&provider, pair, &abi, "token0", &[]).await?[0])?;
+    let token1 = to_address(&call(&provider, pair, &abi, "token1", &[]).await?[0])?;
+    let reserves = call(&provider, pair, &abi, "getReserves", &[]).await?;
+    let (reserve0, reserve1) = (to_u256(&reserves[0])?, to_u256(&reserves[1])?);
+    let total_supply = to_u256(&call(&provider, pair, &abi, "totalSupply", &[]).await?[0])?;
+    let lp_balance = to_u256(
+        &call(
+            &provider,
+            pair,
+            &abi,
+            "balanceOf",
+            &[Token::Address(account)],
+        )
+        .await?[0],
+    )?;
+    let (amount0, amount1, share_percent) =
+        position_amounts(lp_balance, total_supply, reserve0, reserve1)?;
+
+    Ok(LiquidityPosition {
+        pair: format!("{pair:?}"),
+        token0: format!("{token0:?}"),
+        token1: format!("{token1:?}"),
+        lp_balance: lp_balance.to_string(),
+        lp_total_supply: total_supply.to_string(),
+        reserve0: reserve0.to_string(),
+        reserve1: reserve1.to_string(),
+        amount0: amount0.to_string(),
+        amount1: amount1.to_string(),
+        share_percent,
+    })
+}
+
+/// builds the swap transaction of the quote: `swapExactTokensForTokens` (or the native coin
+/// variants) to the router with the minimum output of the slippage and the deadline;
+/// the chain id of the quote is set, the other transaction fields of `common` are left
+/// to be set by the caller
+pub fn build_swap_transaction(
+    quote: &SwapQuote,
+    options: &SwapOptions,
+) -> Result<WalletConnectTxEip155> {
+    if options.native_in && options.native_out {
+        return Err(anyhow!("both sides of the swap can't be the native coin"));
+    }
+    let abi = dex_abi()?;
+    let amount_in = U256::from_dec_str(&quote.amount_in)?;
+    let amount_out_min = U256::from_dec_str(&swap_amount_out_min(
+        quote.amount_out.clone(),
+        options.slippage_bps,
+    )?)?;
+    let path = Token::Array(
+        quote
+            .path
+            .iter()
+            .map(|token| Ok(Token::Address(parse_address(token)?)))
+            .collect::<Result<Vec<_>>>()?,
+    );
+    let recipient = Token::Address(parse_address(&options.recipient)?);
+    let deadline = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .checked_add(options.deadline_secs)
+        .ok_or_else(|| anyhow!("the deadline overflows: {}", options.deadline_secs))?;
+    let deadline = Token::Uint(deadline.into());
+
+    let (method, args) = if options.native_in {
+        (
+            "swapExactETHForTokens",
+            vec![Token::Uint(amount_out_min), path, recipient, deadline],
+        )
+    } else {
+        let method = if options.native_out {
+            "swapExactTokensForETH"
+        } else {
+            "swapExactTokensForTokens"
+        };
+        (
+            method,
+            vec![
+                Token::Uint(amount_in),
+                Token::Uint(amount_out_min),
+                path,
+                recipient,
+                deadline,
+            ],
+        )
+    };
+    Ok(WalletConnectTxEip155 {
+        to: format!("{:?}", parse_address(&options.router)?),
+        value: if options.native_in {
+            amount_in.to_string()
+        } else {
+            String::new()
+        },
+        data: abi.function(method)?.encode_input(&args)?,
+        common: WalletConnectTxCommon {
+            chainid: quote.chain_id,
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_price_impact_and_slippage() {
+        let reserves = [(U256::from(1_000_000u64), U256::from(2_000_000u64))];
+        // 1% of the reserves: ~1% impact, plus the 0.3% fee
+        let amount_out = U256::from(19_740u64);
+        assert_eq!(
+            price_impact_bps(U256::from(10_000u64), amount_out, &reserves),
+            130
+        );
+        assert_eq!(
+            price_impact_bps(U256::from(10_000u64), U256::from(30_000u64), &reserves),
+            0
+        );
+        assert_eq!(swap_amount_out_min("19740".into(), 50).unwrap(), "19641");
+        assert_eq!(swap_amount_out_min("19740".into(), 0).unwrap(), "19740");
+        assert!(swap_amount_out_min("19740".into(), 10_001).is_err());
+    }
+
+    #[test]
+    fn test_position_amounts() {
+        let (amount0, amount1, percent) = position_amounts(
+            U256::from(125u64),
+            U256::from(1000u64),
+            U256::from(8000u64),
+            U256::from(333u64),
+        )
+        .unwrap();
+        assert_eq!((amount0, amount1), (U256::from(1000u64), U256::from(41u64)));
+        assert_eq!(percent, "12.5");
+        let (_, _, percent) =
+            position_amounts(U256::one(), U256::from(3u64), U256::one(), U256::one()).unwrap();
+        assert_eq!(percent, "33.33333333");
+        let (amount0, _, percent) =
+            position_amounts(U256::zero(), U256::zero(), U256::one(), U256::one()).unwrap();
+        assert_eq!((amount0, percent.as_str()), (U256::zero(), "0"));
+        assert!(position_amounts(U256::MAX, U256::one(), U256::MAX, U256::one()).is_err());
+    }
+
+    #[test]
+    fn test_build_swap_transaction() {
+        let quote = SwapQuote {
+            chain_id: 25,
+            path: vec![
+                "0x5C7F8A570d578ED84E63fdFA7b1eE72dEae1AE23".into(),
+                "0xc21223249CA28397B4B6541dfFaEcC539BfF0c59".into(),
+            ],
+            amounts: vec!["1000".into(), "2000".into()],
+            amount_in: "1000".into(),
+            amount_out: "2000".into(),
+            price_impact_bps: 10,
+        };
+        let mut options = SwapOptions {
+            router: dex_router_address(DexRouter::VvsFinance).unwrap(),
+            recipient: "0x0101010101010101010101010101010101010101".into(),
+            slippage_bps: 100,
+            deadline_secs: 600,
+            native_in: true,
+            native_out: false,
+        };
+        let tx = build_swap_transaction(&quote, &options).unwrap();
+        assert_eq!(tx.value, "1000");
+        assert_eq!(tx.common.chainid, 25);
+        let function = dex_abi().unwrap();
+        let function = function.function("swapExactETHForTokens").unwrap();
+        assert_eq!(tx.data[..4], function.short_signature());
+        let args = function.decode_input(&tx.data[4..]).unwrap();
+        assert_eq!(args[0], Token::Uint(1980.into()));
+
+        options.native_in = false;
+        let tx = build_swap_transaction(&quote, &options).unwrap();
+        assert!(tx.value.is_empty());
+        assert_eq!(tx.to, "0x145863eb42cf62847a6ca784e6416c1682b1b2ae");
+        options.deadline_secs = u64::MAX;
+        assert!(build_swap_transaction(&quote, &options).is_err());
+        options.deadline_secs = 600;
+        options.native_out = true;
+        options.native_in = true;
+        assert!(build_swap_transaction(&quote, &options).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use anyhow::{anyhow, Result};
+use ethers::types::transaction::eip2930::AccessList;
+use ethers::types::{Address, Bytes, Eip1559TransactionRequest, NameOrAddress, U256};
+use serde::Deserialize;
+
+/// the JSON Schema (draft 2020-12) of the transaction JSON, e.g. for the C++ codegen
+const TRANSACTION_JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Eip1559TransactionRequest",
+  "description": "An EIP-1559 transaction request: the quantities are JSON numbers, decimal strings or 0x-prefixed hexadecimal strings; `to` or `data` is required",
+  "type": "object",
+  "additionalProperties": false,
+  "properties": {
+    "type": { "$ref": "#/$defs/quantity", "description": "the transaction type, 2 (the default) only" },
+    "from": { "$ref": "#/$defs/address" },
+    "to": { "type": "string", "description": "the receiver or the called contract (an address or an ENS name); none to deploy a contract" },
+    "data": { "$ref": "#/$defs/bytes", "description": "the calldata or the contract code (alias: `input`)" },
+    "input": { "$ref": "#/$defs/bytes" },
+    "value": { "$ref": "#/$defs/quantity", "description": "in wei, 0 by default" },
+    "gas": { "$ref": "#/$defs/quantity", "description": "the gas limit (alias: `gasLimit`), estimated by the wallet if unset" },
+    "gasLimit": { "$ref": "#/$defs/quantity" },
+    "maxFeePerGas": { "$ref": "#/$defs/quantity" },
+    "maxPriorityFeePerGas": { "$ref": "#/$defs/quantity" },
+    "nonce": { "$ref": "#/$defs/quantity" },
+    "chainId": { "$ref": "#/$defs/quantity" },
+    "accessList": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "additionalProperties": false,
+        "required": ["address", "storageKeys"],
+        "properties": {
+          "address": { "$ref": "#/$defs/address" },
+          "storageKeys": { "type": "array", "items": { "type": "string", "pattern": "^0x[0-9a-fA-F]{64}$" } }
+        }
+      }
+    }
+  },
+  "$defs": {
+    "address": { "type": "string", "pattern": "^0x[0-9a-fA-F]{40}$" },
+    "bytes": { "type": "string", "pattern": "^(0x)?([0-9a-fA-F]{2})*$" },
+    "quantity": {
+      "oneOf": [
+        { "type": "integer", "minimum": 0 },
+        { "type": "string", "pattern": "^(0x[0-9a-fA-F]+|[0-9]+)$" }
+      ]
+    }
+  }
+}"##;
+
+/// a quantity: a JSON number, a decimal string or a 0x-prefixed hexadecimal string
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Quantity {
+    Number(u64),
+    Text(String),
+}
+
+/// The transaction JSON as written by the games (see `TRANSACTION_JSON_SCHEMA`)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+struct TransactionJson {
+    #[serde(rename = "type")]
+    tx_type: Option<Quantity>,
+    from: Option<String>,
+    to: Option<String>,
+    data: Option<String>,
+    input: Option<String>,
+    value: Option<Quantity>,
+    gas: Option<Quantity>,
+    gas_limit: Option<Quantity>,
+    max_fee_per_gas: Option<Quantity>,
+    max_priority_fee_per_gas: Option<Quantity>,
+    nonce: Option<Quantity>,
+    chain_id: Option<Quantity>,
+    access_list: Option<AccessList>,
+}
+
+fn quantity(field: &str, value: &Option<Quantity>) -> Result<Option<U256>> {
+    let invalid = |text: &str| {
+        anyhow!(
+            "`{field}`: invalid quantity {text:?} (a decimal or 0x-prefixed hexadecimal number)"
+        )
+    };
+    match value {
+        None => Ok(None),
+        Some(Quantity::Number(number)) => Ok(Some((*number).into())),
+        Some(Quantity::Text(text)) => match text.strip_prefix("0x") {
+            Some(hex) if !hex.is_empty() => U256::from_str_radix(hex, 16)
+                .map(Some)
+                .map_err(|_| invalid(text)),
+            Some(_) => Err(invalid(text)),
+            None => U256::from_dec_str(text)
+                .map(Some)
+                .map_err(|_| invalid(text)),
+        },
+    }
+}
+
+fn address(field: &str, value: &str) -> Result<Address> {
+    crate::parse_address(value).map_err(|_| anyhow!("`{field}`: invalid address {value:?}"))
+}
+
+/// parses the transaction JSON into the EIP-1559 transaction request, with the errors
+/// naming the invalid field (e.g. an unknown field, a bad hexadecimal string or a missing `to`)
+pub(crate) fn parse_transaction_json(json: &str) -> Result<Eip1559TransactionRequest> {
+    let tx: TransactionJson =
+        serde_json::from_str(json).map_err(|e| anyhow!("invalid transaction JSON: {e}"))?;
+    if let Some(tx_type) = quantity("type", &tx.tx_type)? {
+        if tx_type != U256::from(2) {
+            return Err(anyhow!(
+                "`type`: only EIP-1559 transactions (type 2) are supported"
+            ));
+        }
+    }
+    let data = match (&tx.data, &tx.input) {
+        (Some(_), Some(_)) => return Err(anyhow!("`data` and `input` are the same field")),
+        (Some(data), None) | (None, Some(data)) => {
+            let field = if tx.data.is_some() { "data" } else { "input" };
+            let digits = data.strip_prefix("0x").unwrap_or(data);
+            hex::decode(digits)
+                .map_err(|e| anyhow!("`{field}`: invalid hexadecimal string ({e})"))?
+        }
+        (None, None) => vec![],
+    };
+    if tx.to.is_none() && data.is_empty() {
+        return Err(anyhow!(
+            "the transaction needs `to` (or `data` to deploy a contract)"
+        ));
+    }
+    let gas = match (&tx.gas, &tx.gas_limit) {
+        (Some(_), Some(_)) => return Err(anyhow!("`gas` and `gasLimit` are the same field")),
+        (Some(_), None) => quantity("gas", &tx.gas)?,
+        (None, _) => quantity("gasLimit", &tx.gas_limit)?,
+    };
+
+    let mut request =
+        Eip1559TransactionRequest::new().value(quantity("value", &tx.value)?.unwrap_or_default());
+    if let Some(from) = &tx.from {
+        request = request.from(address("from", from)?);
+    }
+    if let Some(to) = &tx.to {
+        // an ENS name, e.g. "game.eth"
+        request = request.to(if to.contains('.') && !to.starts_with("0x") {
+            NameOrAddress::Name(to.clone())
+        } else {
+            NameOrAddress::Address(address("to", to)?)
+        });
+    }
+    if !data.is_empty() {
+        request = request.data(Bytes::from(data));
+    }
+    if let Some(gas) = gas {
+        request = request.gas(gas);
+    }
+    if let Some(fee) = quantity("maxFeePerGas", &tx.max_fee_per_gas)? {
+        request = request.max_fee_per_gas(fee);
+    }
+    if let Some(fee) = quantity("maxPriorityFeePerGas", &tx.max_priority_fee_per_gas)? {
+        request = request.max_priority_fee_per_gas(fee);
+    }
+    if let Some(nonce) = quantity("nonce", &tx.nonce)? {
+        request = request.nonce(nonce);
+    }
+    if let Some(chain_id) = quantity("chainId", &tx.chain_id)? {
+        if chain_id > U256::from(u64::MAX) {
+            return Err(anyhow!("`chainId`: {chain_id} is too large"));
+        }
+        request = request.chain_id(chain_id.as_u64());
+    }
+    if let Some(access_list) = tx.access_list {
+        request = request.access_list(access_list);
+    }
+    Ok(request)
+}
+
+/// the JSON Schema of the transaction JSON
+pub fn transaction_json_schema() -> String {
+    TRANSACTION_JSON_SCHEMA.to_owned()
+}
+
+/// validates the transaction JSON, returns its canonical form
+/// (the quantities as 0x-prefixed hexadecimal strings)
+pub fn validate_transaction_json(json: String) -> Result<String> {
+    Ok(serde_json::to_string(&parse_transaction_json(&json)?)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const CONTRACT: &str = "0x5c7f8a570d578ed84e63fdfa7b1ee72deae1ae23";
+
+    fn error(json: Value) -> String {
+        parse_transaction_json(&json.to_string())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_transaction_json() {
+        let tx = parse_transaction_json(
+            &json!({
+                "to": CONTRACT,
+                "input": "a9059cbb",
+                "value": "1000",
+                "gasLimit": 21000,
+                "maxFeePerGas": "0x12a05f200",
+                "nonce": "0x1",
+                "chainId": 25
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            tx.to,
+            Some(NameOrAddress::Address(CONTRACT.parse().unwrap()))
+        );
+        assert_eq!(tx.data.unwrap().to_vec(), hex::decode("a9059cbb").unwrap());
+        assert_eq!(tx.value, Some(1000.into()));
+        assert_eq!(tx.gas, Some(21000.into()));
+        assert_eq!(tx.max_fee_per_gas, Some(5_000_000_000u64.into()));
+        assert_eq!(tx.nonce, Some(1.into()));
+        assert_eq!(tx.chain_id, Some(25.into()));
+        // the defaults
+        let tx = parse_transaction_json(&json!({ "data": "0x6080" }).to_string()).unwrap();
+        assert_eq!((tx.to, tx.value), (None, Some(U256::zero())));
+
+        assert!(
+            error(json!({ "to": CONTRACT, "gasPrice": "1" })).contains("unknown field `gasPrice`")
+        );
+        assert!(error(json!({ "to": CONTRACT, "data": "0xzz" }))
+            .starts_with("`data`: invalid hexadecimal"));
+        assert!(error(json!({ "to": "0x1234" })).starts_with("`to`: invalid address"));
+        assert!(error(json!({ "to": CONTRACT, "value": "1.5" }))
+            .starts_with("`value`: invalid quantity"));
+        assert!(error(json!({ "to": CONTRACT, "nonce": "0x" }))
+            .starts_with("`nonce`: invalid quantity"));
+        assert!(error(json!({ "value": "1" })).contains("needs `to`"));
+        assert!(error(json!({ "to": CONTRACT, "type": 0 })).starts_with("`type`"));
+        assert!(error(json!({ "to": CONTRACT, "gas": 1, "gasLimit": 1 })).contains("same field"));
+
+        let canonical =
+            validate_transaction_json(json!({ "to": CONTRACT, "value": 100 }).to_string()).unwrap();
+        let canonical: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(canonical["value"], "0x64");
+    }
+
+    #[test]
+    fn test_transaction_json_schema() {
+        let schema: Value = serde_json::from_str(&transaction_json_schema()).unwrap();
+        // every property of the schema is accepted
+        let mut tx = serde_json::Map::new();
+        for property in schema["properties"].as_object().unwrap().keys() {
+            let value = match property.as_str() {
+                "from" | "to" => json!(CONTRACT),
+                "data" => json!("0x6080"),
+                "input" | "gasLimit" => continue,
+                "type" => json!(2),
+                "accessList" => json!([{ "address": CONTRACT, "storageKeys": [] }]),
+                _ => json!("0x1"),
+            };
+            tx.insert(property.clone(), value);
+        }
+        parse_transaction_json(&Value::Object(tx).to_string()).unwrap();
+    }
+}
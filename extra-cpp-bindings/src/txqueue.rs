@@ -0,0 +1,610 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::providers::{Middleware, ProviderError, RpcError};
+use ethers::types::{BlockNumber, Bytes, Eip1559TransactionRequest, Signature, TxHash, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::ffi::{TxJob, TxJobStatus, WalletConnectTxEip155};
+use crate::policy::Reservation;
+use crate::signer::{self, Signer};
+use crate::walletconnect::eip1559_request;
+use crate::{chainstate, environment, rpc};
+
+/// the jobs by id (big-endian, so that they're iterated in the enqueue order)
+const JOBS_TREE: &str = "jobs";
+/// the next nonce of the queue (it's ahead of the chain while the transactions are pending)
+const NEXT_NONCE_KEY: &[u8] = b"next_nonce";
+
+/// the lifecycle of a job (see `TxJobStatus`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Status {
+    Queued,
+    Signed,
+    Sent,
+    Confirmed,
+    Failed,
+    Cancelled,
+}
+
+impl From<Status> for TxJobStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Queued => TxJobStatus::Queued,
+            Status::Signed => TxJobStatus::Signed,
+            Status::Sent => TxJobStatus::Sent,
+            Status::Confirmed => TxJobStatus::Confirmed,
+            Status::Failed => TxJobStatus::Failed,
+            Status::Cancelled => TxJobStatus::Cancelled,
+        }
+    }
+}
+
+/// a persisted job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Job {
+    id: u64,
+    status: Status,
+    /// the transaction (its nonce is set when it's signed)
+    request: Eip1559TransactionRequest,
+    /// the cap of the total fee overriding the configured fee caps (`max_total_fee`)
+    #[serde(default)]
+    fee_cap: Option<U256>,
+    /// the signed transaction, kept until it's mined so that it can be broadcast again
+    raw_tx: Option<Bytes>,
+    tx_hash: Option<TxHash>,
+    error: String,
+    created_at: u64,
+    updated_at: u64,
+}
+
+impl From<&Job> for TxJob {
+    fn from(job: &Job) -> Self {
+        Self {
+            id: job.id,
+            status: job.status.into(),
+            to: job
+                .request
+                .to
+                .as_ref()
+                .and_then(|to| to.as_address())
+                .map(|to| format!("{to:?}"))
+                .unwrap_or_default(),
+            value: job.request.value.unwrap_or_default().to_string(),
+            data: job.request.data.clone().unwrap_or_default().to_vec(),
+            nonce: job
+                .request
+                .nonce
+                .map(|nonce| nonce.to_string())
+                .unwrap_or_default(),
+            tx_hash: job
+                .tx_hash
+                .map(|hash| format!("{hash:?}"))
+                .unwrap_or_default(),
+            error: job.error.clone(),
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// whether the node already has the broadcast transaction (or it was mined),
+/// so that broadcasting it again is not a failure
+fn is_already_sent(error: &ProviderError) -> bool {
+    let message = error.to_string().to_lowercase();
+    ["already known", "known transaction", "nonce too low"]
+        .iter()
+        .any(|known| message.contains(known))
+}
+
+/// Durable queue of the transactions of a custodial wallet (e.g. of a game server):
+/// the jobs are persisted in an embedded database, signed with consecutive nonces and
+/// broadcast by `process_blocking`, which resumes the unfinished ones after a restart
+pub struct TxQueue {
+    db: sled::Db,
+    provider: rpc::RpcProvider,
+    /// the in-process key or an external signer
+    signer: Box<dyn Signer>,
+    chain_id: u64,
+    rt: tokio::runtime::Runtime,
+}
+
+impl TxQueue {
+    /// opens the queue of the chain of the SDK environment (its JSON-RPC url and chain id
+    /// if they're empty or 0; the other chains are refused)
+    pub(crate) fn open(
+        path: impl AsRef<Path>,
+        rpc_url: &str,
+        signer: Box<dyn Signer>,
+        chain_id: u64,
+    ) -> Result<Self> {
+        let rpc_url = environment::rpc_url(rpc_url)?;
+        let chain_id = environment::chain_id(chain_id)?;
+        Self::with_db(sled::open(path)?, &rpc_url, signer, chain_id)
+    }
+
+    fn with_db(
+        db: sled::Db,
+        rpc_url: &str,
+        signer: Box<dyn Signer>,
+        chain_id: u64,
+    ) -> Result<Self> {
+        Ok(Self {
+            db,
+            provider: rpc::provider(rpc_url)?,
+            signer,
+            chain_id,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    fn jobs(&self) -> Result<Vec<Job>> {
+        self.db
+            .open_tree(JOBS_TREE)?
+            .iter()
+            .values()
+            .map(|job| Ok(serde_json::from_slice(&job?)?))
+            .collect()
+    }
+
+    fn job(&self, id: u64) -> Result<Job> {
+        let job = self
+            .db
+            .open_tree(JOBS_TREE)?
+            .get(id.to_be_bytes())?
+            .ok_or_else(|| anyhow!("no transaction job {id}"))?;
+        Ok(serde_json::from_slice(&job)?)
+    }
+
+    fn save(&self, job: &mut Job) -> Result<()> {
+        job.updated_at = now();
+        self.db
+            .open_tree(JOBS_TREE)?
+            .insert(job.id.to_be_bytes(), serde_json::to_vec(job)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn stored_next_nonce(&self) -> Result<Option<U256>> {
+        Ok(self
+            .db
+            .get(NEXT_NONCE_KEY)?
+            .map(|nonce| U256::from_big_endian(&nonce)))
+    }
+
+    fn set_next_nonce(&self, nonce: U256) -> Result<()> {
+        let mut bytes = [0u8; 32];
+        nonce.to_big_endian(&mut bytes);
+        self.db.insert(NEXT_NONCE_KEY, &bytes)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// the address of the wallet
+    pub fn address(&self) -> String {
+        format!("{:?}", self.signer.address())
+    }
+
+    /// enqueues the transaction (its nonce is ignored: the queue sets it)
+    /// and returns the id of its job
+    pub fn enqueue(&self, tx: &WalletConnectTxEip155) -> Result<u64> {
+        let fee_cap = crate::feecap::fee_cap_override(&tx.common)?;
+        let mut request = eip1559_request(tx)?;
+        request.nonce = None;
+        request.from = Some(self.signer.address());
+        request.chain_id = Some(self.chain_id.into());
+        let created_at = now();
+        let mut job = Job {
+            id: self.db.generate_id()?,
+            status: Status::Queued,
+            request,
+            fee_cap,
+            raw_tx: None,
+            tx_hash: None,
+            error: String::new(),
+            created_at,
+            updated_at: created_at,
+        };
+        self.save(&mut job)?;
+        Ok(job.id)
+    }
+
+    /// the jobs (in the enqueue order)
+    pub fn list_jobs(&self) -> Result<Vec<TxJob>> {
+        Ok(self.jobs()?.iter().map(TxJob::from).collect())
+    }
+
+    pub fn get_job(&self, id: u64) -> Result<TxJob> {
+        Ok(TxJob::from(&self.job(id)?))
+    }
+
+    /// cancels the job if it's not signed yet
+    pub fn cancel(&self, id: u64) -> Result<()> {
+        let mut job = self.job(id)?;
+        if job.status != Status::Queued {
+            return Err(anyhow!(
+                "the transaction job {id} can't be cancelled ({:?})",
+                job.status
+            ));
+        }
+        job.status = Status::Cancelled;
+        self.save(&mut job)
+    }
+
+    /// removes the confirmed, failed and cancelled jobs and returns how many
+    pub fn prune(&self) -> Result<u32> {
+        let jobs = self.db.open_tree(JOBS_TREE)?;
+        let mut pruned = 0;
+        for job in self.jobs()? {
+            if matches!(
+                job.status,
+                Status::Confirmed | Status::Failed | Status::Cancelled
+            ) {
+                jobs.remove(job.id.to_be_bytes())?;
+                pruned += 1;
+            }
+        }
+        self.db.flush()?;
+        Ok(pruned)
+    }
+
+    /// broadcasts the signed transaction of the job (again after a restart)
+    async fn broadcast(&self, job: &mut Job) -> Result<()> {
+        let raw_tx = job
+            .raw_tx
+            .clone()
+            .ok_or_else(|| anyhow!("the transaction job {} is not signed", job.id))?;
+        match self.provider.send_raw_transaction(raw_tx).await {
+            Ok(_) => chainstate::invalidate(self.chain_id, self.signer.address()),
+            Err(e) if is_already_sent(&e) => {}
+            Err(e) if e.is_error_response() => {
+                // rejected by the node (e.g. insufficient funds): its nonce is reused
+                job.status = Status::Failed;
+                job.error = e.to_string();
+                job.raw_tx = None;
+                if let Some(nonce) = job.request.nonce {
+                    self.set_next_nonce(nonce)?;
+                }
+                self.save(job)?;
+                return Err(anyhow!("the transaction job {} was rejected: {e}", job.id));
+            }
+            // e.g. the node is unreachable: it's broadcast again by the next call
+            Err(e) => return Err(e.into()),
+        }
+        job.status = Status::Sent;
+        self.save(job)
+    }
+
+    /// the nonce of the next signed job (the stored one, unless the pending transactions
+    /// of the address are ahead of it)
+    async fn next_nonce(&self) -> Result<U256> {
+        let pending = self
+            .provider
+            .get_transaction_count(self.signer.address(), Some(BlockNumber::Pending.into()))
+            .await?;
+        Ok(self
+            .stored_next_nonce()?
+            .map_or(pending, |stored| stored.max(pending)))
+    }
+
+    /// the transaction of the job with the nonce and the missing fees and gas,
+    /// and its signature once it passes the checks of the WalletConnect transactions
+    /// (the value reserved by the policy is committed once the job is persisted)
+    async fn sign_request(
+        &self,
+        job: &Job,
+        nonce: U256,
+    ) -> Result<(TypedTransaction, Signature, Reservation)> {
+        let mut tx = TypedTransaction::Eip1559(job.request.clone());
+        // the chain id of the request isn't persisted
+        tx.set_chain_id(self.chain_id);
+        tx.set_nonce(nonce);
+        self.provider.fill_transaction(&mut tx, None).await?;
+        let reservation = signer::check_transaction(&tx, job.fee_cap).await?;
+        // the external signers may block (e.g. the KMS requests)
+        let signature =
+            tokio::task::block_in_place(|| signer::sign_transaction(self.signer.as_ref(), &tx))?;
+        Ok((tx, signature, reservation))
+    }
+
+    /// sets the nonce and the missing fees and gas of the job, signs it and persists it
+    /// before it's broadcast
+    async fn sign(&self, job: &mut Job) -> Result<()> {
+        let nonce = self.next_nonce().await?;
+        let (tx, signature, reservation) = self.sign_request(job, nonce).await?;
+        job.raw_tx = Some(tx.rlp_signed(&signature));
+        job.tx_hash = Some(tx.hash(&signature));
+        job.request = match tx {
+            TypedTransaction::Eip1559(request) => request,
+            _ => return Err(anyhow!("not an EIP-1559 transaction")),
+        };
+        job.status = Status::Signed;
+        self.save(job)?;
+        reservation.commit();
+        self.set_next_nonce(nonce + 1)
+    }
+
+    /// checks the receipt of the sent job
+    async fn check_receipt(&self, job: &mut Job, mined_nonce: U256) -> Result<()> {
+        let tx_hash = job
+            .tx_hash
+            .ok_or_else(|| anyhow!("the transaction job {} has no hash", job.id))?;
+        match self.provider.get_transaction_receipt(tx_hash).await? {
+            Some(receipt) => {
+                if receipt.status == Some(1u64.into()) {
+                    job.status = Status::Confirmed;
+                } else {
+                    job.status = Status::Failed;
+                    job.error = "reverted".to_owned();
+                }
+            }
+            None if job.request.nonce.is_some_and(|nonce| nonce < mined_nonce) => {
+                job.status = Status::Failed;
+                job.error = "the nonce was used by another transaction".to_owned();
+            }
+            // pending: broadcast again, in case the node dropped it (e.g. from its mempool)
+            None => return self.rebroadcast(job).await,
+        }
+        job.raw_tx = None;
+        self.save(job)
+    }
+
+    /// broadcasts the signed transaction of the pending job again (the node already
+    /// having it is not a failure); a rejection is kept as the error of the job, which
+    /// stays sent until its nonce is mined
+    async fn rebroadcast(&self, job: &mut Job) -> Result<()> {
+        let Some(raw_tx) = job.raw_tx.clone() else {
+            return Ok(());
+        };
+        match self.provider.send_raw_transaction(raw_tx).await {
+            Ok(_) => Ok(()),
+            Err(e) if is_already_sent(&e) => Ok(()),
+            Err(e) if e.is_error_response() => {
+                job.error = e.to_string();
+                self.save(job)
+            }
+            // e.g. the node is unreachable: it's broadcast again by the next call
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// the dry run of the jobs: the signed ones and the queued ones (signed with the next
+    /// nonces) are simulated and recorded (see `dry_run_results`) instead of being broadcast;
+    /// the jobs and the nonce of the queue don't change, so no job is changed
+    async fn dry_run(&self) -> Result<u32> {
+        let mut nonce = None;
+        for job in self.jobs()? {
+            let raw_tx = match job.status {
+                Status::Signed => job.raw_tx,
+                Status::Queued => {
+                    let next = match nonce {
+                        Some(next) => next,
+                        None => self.next_nonce().await?,
+                    };
+                    let (tx, signature, _) = self.sign_request(&job, next).await?;
+                    nonce = Some(next + 1);
+                    Some(tx.rlp_signed(&signature))
+                }
+                _ => None,
+            };
+            if let Some(raw_tx) = raw_tx {
+                crate::dryrun::dry_run_raw_transaction(&raw_tx).await?;
+            }
+        }
+        Ok(0)
+    }
+
+    /// records the error of the job: the queued one (e.g. refused by the checks or the signer)
+    /// fails, the others (e.g. the node is unreachable) are retried by the next call
+    fn record_error(&self, job: &mut Job, error: anyhow::Error) -> Result<()> {
+        let unreachable = error
+            .downcast_ref::<ProviderError>()
+            .is_some_and(|e| !e.is_error_response());
+        match job.status {
+            Status::Queued if !unreachable => job.status = Status::Failed,
+            // rejected by the node: already recorded
+            Status::Failed => return Ok(()),
+            _ => {}
+        }
+        job.error = error.to_string();
+        self.save(job)
+    }
+
+    /// advances the jobs (or dry runs them if the dry-run mode is enabled): the sent ones are checked (and broadcast again while their nonce
+    /// is not mined), the signed ones are broadcast (again, after a restart) and the queued ones are signed and broadcast in order;
+    /// the error of a job is recorded and the others are advanced (but no job is signed after a signed one that's not broadcast);
+    /// returns how many changed
+    pub fn process_blocking(&mut self) -> Result<u32> {
+        if crate::dryrun::is_enabled() {
+            return self.rt.block_on(self.dry_run());
+        }
+        self.rt.block_on(async {
+            let mined_nonce = chainstate::nonce(&self.provider, self.signer.address()).await?;
+            let mut changed = 0;
+            // the nonces of the jobs signed after it would leave a gap
+            let mut unsent = false;
+            for mut job in self.jobs()? {
+                let status = job.status;
+                let result = match status {
+                    Status::Sent => self.check_receipt(&mut job, mined_nonce).await,
+                    Status::Signed => self.broadcast(&mut job).await,
+                    Status::Queued if !unsent => match self.sign(&mut job).await {
+                        Ok(()) => self.broadcast(&mut job).await,
+                        Err(e) => Err(e),
+                    },
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.record_error(&mut job, e)?;
+                }
+                unsent |= job.status == Status::Signed;
+                if job.status != status {
+                    changed += 1;
+                }
+            }
+            Ok(changed)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ffi::WalletConnectTxCommon;
+
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// a node answering the JSON-RPC requests with the results (or the errors) of the handler
+    fn node(
+        handler: impl Fn(&str) -> Result<serde_json::Value, &'static str> + Send + 'static,
+    ) -> String {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let node = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", node.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in node.incoming() {
+                let mut reader = BufReader::new(stream.unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                let response = match handler(request["method"].as_str().unwrap()) {
+                    Ok(result) => serde_json::json!({
+                        "jsonrpc": "2.0", "id": request["id"], "result": result
+                    }),
+                    Err(message) => serde_json::json!({
+                        "jsonrpc": "2.0", "id": request["id"],
+                        "error": {"code": -32000, "message": message}
+                    }),
+                };
+                let response = response.to_string();
+                let _ = reader.get_mut().write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{response}",
+                        response.len()
+                    )
+                    .as_bytes(),
+                );
+            }
+        });
+        url
+    }
+
+    /// a transfer with the gas and the fees (nothing is estimated)
+    fn transfer(max_total_fee: &str) -> WalletConnectTxEip155 {
+        WalletConnectTxEip155 {
+            to: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8".into(),
+            value: "1000".into(),
+            common: WalletConnectTxCommon {
+                gas_limit: "21000".into(),
+                max_fee_per_gas: "1000".into(),
+                max_priority_fee_per_gas: "1".into(),
+                max_total_fee: max_total_fee.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tx_queue_jobs() {
+        let db = sled::Config::new().temporary(true).open().expect("db");
+        let wallet = || Box::new(signer::local_wallet(&hex::decode(PRIVATE_KEY).unwrap()).unwrap());
+        let queue =
+            TxQueue::with_db(db.clone(), "http://127.0.0.1:8545", wallet(), 338).expect("queue");
+        assert_eq!(
+            queue.address(),
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        let tx = WalletConnectTxEip155 {
+            to: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8".into(),
+            value: "1000".into(),
+            ..Default::default()
+        };
+        let first = queue.enqueue(&tx).unwrap();
+        let second = queue.enqueue(&tx).unwrap();
+        assert!(second > first);
+        queue.cancel(second).unwrap();
+
+        // the jobs are persisted
+        let queue = TxQueue::with_db(db, "http://127.0.0.1:8545", wallet(), 338).unwrap();
+        let jobs = queue.list_jobs().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].status, TxJobStatus::Queued);
+        assert_eq!(jobs[0].to, tx.to);
+        assert_eq!(jobs[0].value, "1000");
+        assert!(jobs[0].nonce.is_empty());
+        assert_eq!(jobs[1].status, TxJobStatus::Cancelled);
+        assert!(queue.cancel(second).is_err());
+        assert!(queue.get_job(second + 100).is_err());
+
+        // a signed job is broadcast again, not signed again
+        let mut job = queue.job(first).unwrap();
+        job.status = Status::Signed;
+        job.request.nonce = Some(5.into());
+        queue.save(&mut job).unwrap();
+        assert!(queue.cancel(first).is_err());
+        assert_eq!(queue.get_job(first).unwrap().nonce, "5");
+        assert_eq!(queue.prune().unwrap(), 1);
+        assert_eq!(queue.list_jobs().unwrap().len(), 1);
+
+        // the fee cap of the transaction is checked when it's signed
+        assert_eq!(job.fee_cap, None);
+        let capped = queue
+            .enqueue(&WalletConnectTxEip155 {
+                common: WalletConnectTxCommon {
+                    max_total_fee: "21000".into(),
+                    ..Default::default()
+                },
+                ..tx
+            })
+            .unwrap();
+        assert_eq!(queue.job(capped).unwrap().fee_cap, Some(21000.into()));
+    }
+
+    #[test]
+    fn test_tx_queue_failed_job() {
+        let url = node(|method| match method {
+            "eth_chainId" => Ok("0x152".into()),
+            "eth_getTransactionCount" => Ok("0x0".into()),
+            "eth_sendRawTransaction" => Ok(format!("{:?}", TxHash::zero()).into()),
+            _ => Err("unsupported"),
+        });
+        let db = sled::Config::new().temporary(true).open().expect("db");
+        let wallet = Box::new(signer::local_wallet(&hex::decode(PRIVATE_KEY).unwrap()).unwrap());
+        let mut queue = TxQueue::with_db(db, &url, wallet, 338).unwrap();
+        // over its fee cap
+        let refused = queue.enqueue(&transfer("1")).unwrap();
+        let sent = queue.enqueue(&transfer("")).unwrap();
+
+        // the refused job fails, the next one is sent with the nonce it didn't use
+        assert_eq!(queue.process_blocking().unwrap(), 2);
+        let refused = queue.get_job(refused).unwrap();
+        assert_eq!(refused.status, TxJobStatus::Failed);
+        assert!(refused.error.contains("Fee too high"));
+        assert!(refused.nonce.is_empty());
+        let sent = queue.get_job(sent).unwrap();
+        assert_eq!(sent.status, TxJobStatus::Sent);
+        assert_eq!(sent.nonce, "0");
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use cxx::UniquePtr;
+use ethers::providers::Middleware;
+use ethers::types::{TransactionReceipt, TxHash, H256};
+
+use crate::ffi::{TrackedTxReceipt, TxTrackerCallback};
+use crate::{chainstate, rpc};
+
+/// the shortest polling interval
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the block of a mined transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MinedAt {
+    block_number: u64,
+    block_hash: H256,
+}
+
+impl MinedAt {
+    fn of(receipt: &TransactionReceipt) -> Option<Self> {
+        Some(Self {
+            block_number: receipt.block_number?.as_u64(),
+            block_hash: receipt.block_hash?,
+        })
+    }
+}
+
+/// an event of a tracked transaction
+#[derive(Debug, PartialEq)]
+enum TxEvent {
+    /// the confirmations changed (fewer than required)
+    Confirmations(TrackedTxReceipt),
+    /// the required confirmations are reached (the transaction is not tracked anymore)
+    Confirmed(TrackedTxReceipt),
+    /// the block of the transaction is not in the chain anymore
+    Reorged(MinedAt),
+}
+
+/// a tracked transaction
+#[derive(Debug, Default)]
+struct TrackedTx {
+    /// the block of the last receipt (`None` if pending)
+    mined: Option<MinedAt>,
+    /// the last reported confirmations
+    confirmations: u64,
+}
+
+impl TrackedTx {
+    /// the heights whose canonical block hashes are needed by `update`
+    fn heights(&self, receipt: Option<&TransactionReceipt>) -> Vec<u64> {
+        let mut heights: Vec<u64> = self
+            .mined
+            .iter()
+            .copied()
+            .chain(receipt.and_then(MinedAt::of))
+            .map(|mined| mined.block_number)
+            .collect();
+        heights.dedup();
+        heights
+    }
+
+    /// updates the transaction from its receipt (if any), the canonical block hashes of
+    /// `heights` (the unknown ones are missing) and the latest block, and returns its events;
+    /// only a different canonical block is a reorg: the missing receipts or blocks
+    /// (e.g. of a lagging node) keep the transaction as it is
+    fn update(
+        &mut self,
+        tx_hash: TxHash,
+        receipt: Option<&TransactionReceipt>,
+        canonical: &BTreeMap<u64, H256>,
+        latest: u64,
+        required: u64,
+    ) -> Vec<TxEvent> {
+        let mut events = vec![];
+        let replaced = |mined: &MinedAt| {
+            canonical
+                .get(&mined.block_number)
+                .is_some_and(|hash| *hash != mined.block_hash)
+        };
+        let current = receipt
+            .and_then(MinedAt::of)
+            .filter(|mined| canonical.get(&mined.block_number) == Some(&mined.block_hash));
+        if let Some(mined) = self.mined {
+            // the recorded block was replaced, or the transaction was mined in another block
+            if replaced(&mined) || current.is_some_and(|current| current != mined) {
+                events.push(TxEvent::Reorged(mined));
+                self.mined = None;
+                self.confirmations = 0;
+            }
+        }
+        if let (Some(mined), Some(receipt)) = (current, receipt) {
+            self.mined = Some(mined);
+            let confirmations = latest.saturating_sub(mined.block_number) + 1;
+            if confirmations != self.confirmations {
+                self.confirmations = confirmations;
+                let receipt = TrackedTxReceipt {
+                    tx_hash: format!("{tx_hash:?}"),
+                    block_number: mined.block_number,
+                    block_hash: format!("{:?}", mined.block_hash),
+                    success: receipt.status.map(|status| status.as_u64() == 1) == Some(true),
+                    gas_used: receipt.gas_used.unwrap_or_default().to_string(),
+                    confirmations,
+                };
+                events.push(if confirmations >= required {
+                    TxEvent::Confirmed(receipt)
+                } else {
+                    TxEvent::Confirmations(receipt)
+                });
+            }
+        }
+        events
+    }
+}
+
+/// the tracked transactions by hash
+type Tracked = Arc<Mutex<BTreeMap<TxHash, TrackedTx>>>;
+
+/// polls the receipt of the tracked transaction and calls the callback
+async fn poll_tx(
+    provider: &rpc::RpcProvider,
+    tracked: &Tracked,
+    tx_hash: TxHash,
+    latest: u64,
+    required: u64,
+    callback: &TxTrackerCallback,
+) -> Result<()> {
+    let receipt = provider.get_transaction_receipt(tx_hash).await?;
+    let heights = match tracked
+        .lock()
+        .expect("tracked transactions lock")
+        .get(&tx_hash)
+    {
+        Some(tx) => tx.heights(receipt.as_ref()),
+        // untracked meanwhile
+        None => return Ok(()),
+    };
+    let mut canonical = BTreeMap::new();
+    for height in heights {
+        if let Some(hash) = provider.get_block(height).await?.and_then(|b| b.hash) {
+            canonical.insert(height, hash);
+        }
+    }
+    let events = {
+        let mut tracked = tracked.lock().expect("tracked transactions lock");
+        let Some(tx) = tracked.get_mut(&tx_hash) else {
+            return Ok(());
+        };
+        let events = tx.update(tx_hash, receipt.as_ref(), &canonical, latest, required);
+        if events
+            .iter()
+            .any(|event| matches!(event, TxEvent::Confirmed(_)))
+        {
+            tracked.remove(&tx_hash);
+        }
+        events
+    };
+    for event in events {
+        match event {
+            TxEvent::Confirmations(receipt) => callback.onConfirmations(&receipt),
+            TxEvent::Confirmed(receipt) => callback.onConfirmed(&receipt),
+            TxEvent::Reorged(mined) => callback.onReorged(
+                &format!("{tx_hash:?}"),
+                mined.block_number,
+                &format!("{:?}", mined.block_hash),
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// polls the receipts of the tracked transactions once and calls the callback
+/// (the error of a transaction is reported, and the others are still polled)
+async fn poll(
+    provider: &rpc::RpcProvider,
+    tracked: &Tracked,
+    required: u64,
+    callback: &TxTrackerCallback,
+) -> Result<()> {
+    let latest = provider.get_block_number().await?.as_u64();
+    chainstate::observe_block(provider, latest);
+    let hashes: Vec<TxHash> = tracked
+        .lock()
+        .expect("tracked transactions lock")
+        .keys()
+        .copied()
+        .collect();
+    for tx_hash in hashes {
+        if let Err(e) = poll_tx(provider, tracked, tx_hash, latest, required, callback).await {
+            callback.onError(&format!("{tx_hash:?}: {e}"));
+        }
+    }
+    Ok(())
+}
+
+/// Polls the receipts of the sent transactions via JSON-RPC until they have enough
+/// confirmations, and detects the reorgs: if the block of a mined transaction is replaced,
+/// it's reported and the transaction is tracked again as pending
+pub struct TxTracker {
+    provider: rpc::RpcProvider,
+    confirmations: u64,
+    interval: Duration,
+    tracked: Tracked,
+    task: Option<tokio::task::JoinHandle<()>>,
+    rt: tokio::runtime::Runtime,
+}
+
+impl TxTracker {
+    pub(crate) fn new(rpc_url: &str, confirmations: u64, interval_ms: u64) -> Result<Self> {
+        Ok(Self {
+            provider: rpc::provider(rpc_url)?,
+            confirmations: confirmations.max(1),
+            interval: Duration::from_millis(interval_ms).max(MIN_INTERVAL),
+            tracked: Default::default(),
+            task: None,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// tracks the transaction until it's confirmed
+    pub fn track(&self, tx_hash: String) -> Result<()> {
+        let tx_hash: TxHash = tx_hash
+            .parse()
+            .map_err(|_| anyhow!("invalid transaction hash: {tx_hash}"))?;
+        self.tracked
+            .lock()
+            .expect("tracked transactions lock")
+            .entry(tx_hash)
+            .or_default();
+        Ok(())
+    }
+
+    /// stops tracking the transaction
+    pub fn untrack(&self, tx_hash: String) {
+        if let Ok(tx_hash) = tx_hash.parse::<TxHash>() {
+            self.tracked
+                .lock()
+                .expect("tracked transactions lock")
+                .remove(&tx_hash);
+        }
+    }
+
+    /// the hashes of the tracked (not yet confirmed) transactions
+    pub fn tracked_transactions(&self) -> Vec<String> {
+        self.tracked
+            .lock()
+            .expect("tracked transactions lock")
+            .keys()
+            .map(|tx_hash| format!("{tx_hash:?}"))
+            .collect()
+    }
+
+    /// starts polling (it replaces the previous callback)
+    pub fn start(&mut self, callback: UniquePtr<TxTrackerCallback>) -> Result<()> {
+        if callback.is_null() {
+            anyhow::bail!("no callback");
+        }
+        self.stop();
+        let provider = self.provider.clone();
+        let tracked = self.tracked.clone();
+        let required = self.confirmations;
+        let interval = self.interval;
+        self.task = Some(self.rt.spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                if let Err(e) = poll(&provider, &tracked, required, &callback).await {
+                    callback.onError(&e.to_string());
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// stops polling (the transactions stay tracked)
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// whether the tracker is polling
+    pub fn is_running(&self) -> bool {
+        self.task
+            .as_ref()
+            .map(|task| !task.is_finished())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for TxTracker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn receipt(block_number: u64, block_hash: H256) -> TransactionReceipt {
+        TransactionReceipt {
+            block_number: Some(block_number.into()),
+            block_hash: Some(block_hash),
+            status: Some(1u64.into()),
+            gas_used: Some(21000u64.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tracked_tx_reorg() {
+        let tx_hash = TxHash::repeat_byte(1);
+        let (a, b) = (H256::repeat_byte(0xa), H256::repeat_byte(0xb));
+        let mut tx = TrackedTx::default();
+        // pending
+        assert!(tx
+            .update(tx_hash, None, &BTreeMap::new(), 100, 3)
+            .is_empty());
+        // mined in the block 100 (a)
+        let mined = receipt(100, a);
+        assert_eq!(tx.heights(Some(&mined)), vec![100]);
+        let canonical = BTreeMap::from([(100, a)]);
+        let events = tx.update(tx_hash, Some(&mined), &canonical, 101, 3);
+        assert!(matches!(&events[..], [TxEvent::Confirmations(r)] if r.confirmations == 2));
+        // the block 100 is replaced (b) and the transaction is pending again
+        let canonical = BTreeMap::from([(100, b)]);
+        let events = tx.update(tx_hash, None, &canonical, 102, 3);
+        assert_eq!(
+            events,
+            vec![TxEvent::Reorged(MinedAt {
+                block_number: 100,
+                block_hash: a
+            })]
+        );
+        assert_eq!(tx.mined, None);
+        // a stale receipt of the replaced block is not a confirmation
+        assert!(tx
+            .update(tx_hash, Some(&mined), &canonical, 102, 3)
+            .is_empty());
+        // mined again in the block 101, then confirmed
+        let remined = receipt(101, b);
+        let canonical = BTreeMap::from([(101, b)]);
+        let events = tx.update(tx_hash, Some(&remined), &canonical, 103, 3);
+        assert!(matches!(&events[..], [TxEvent::Confirmed(r)] if r.block_number == 101));
+
+        // the missing receipt and block (e.g. of a lagging node) are not a reorg
+        let mut tx = TrackedTx::default();
+        let canonical = BTreeMap::from([(100, a)]);
+        tx.update(tx_hash, Some(&mined), &canonical, 100, 3);
+        assert!(tx.update(tx_hash, None, &canonical, 101, 3).is_empty());
+        assert!(tx
+            .update(tx_hash, None, &BTreeMap::new(), 101, 3)
+            .is_empty());
+        assert!(tx
+            .update(tx_hash, Some(&mined), &BTreeMap::new(), 101, 3)
+            .is_empty());
+        assert_eq!(tx.mined.map(|mined| mined.block_hash), Some(a));
+    }
+}
@@ -0,0 +1,442 @@
+//! flat exports with only blittable arguments for Unity (C# P/Invoke), enabled by the `unity` feature
+//! (see `include/play_sdk_unity.cs`).
+//!
+//! conventions (on top of the ones of `capi`):
+//! - strings are passed as UTF-8 bytes and a length (e.g. `Encoding.UTF8.GetBytes`)
+//! - a call with a result stores it on the calling thread and writes its length to `out_len`,
+//!   the caller then allocates the buffer and copies the result with `play_unity_read_result`
+//! - the WalletConnect client is an opaque handle (`IntPtr`) released with `play_unity_walletconnect_free`
+use std::cell::RefCell;
+use std::ptr;
+
+use anyhow::Result;
+
+use crate::capi::{
+    arg_address, arg_client, check_out, last_error, query_option, run, write_out, write_tx_hash,
+    InvalidArgument, PlaySdkStatus,
+};
+use crate::walletconnect::WalletconnectClient;
+
+thread_local! {
+    /// the result of the last call on this thread (until it is read)
+    static RESULT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// a UTF-8 string argument (null is empty if the length is 0)
+unsafe fn utf8_arg(ptr: *const u8, len: usize, name: &'static str) -> Result<String> {
+    if len == 0 {
+        return Ok(String::default());
+    }
+    if ptr.is_null() {
+        return Err(InvalidArgument(name).into());
+    }
+    let bytes = std::slice::from_raw_parts(ptr, len);
+    Ok(String::from_utf8(bytes.to_vec()).map_err(|_| InvalidArgument(name))?)
+}
+
+/// stores the result to be read by `play_unity_read_result`
+unsafe fn set_result(result: Vec<u8>, out_len: *mut usize) {
+    write_out(out_len, result.len());
+    RESULT.with(|r| *r.borrow_mut() = result);
+}
+
+/// copies the message of the last failed call on the calling thread to the buffer (truncated
+/// if the capacity is not enough), returns the length of the whole message (0 if none)
+///
+/// # Safety
+/// `buffer` must be null or `capacity` writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_last_error(buffer: *mut u8, capacity: usize) -> usize {
+    let message = last_error();
+    if !buffer.is_null() {
+        ptr::copy_nonoverlapping(message.as_ptr(), buffer, message.len().min(capacity));
+    }
+    message.len()
+}
+
+/// copies the result of the last call on the calling thread to the buffer
+/// (its capacity must be at least the returned `out_len`) and clears it
+///
+/// # Safety
+/// `buffer` must be `capacity` writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_read_result(buffer: *mut u8, capacity: usize) -> PlaySdkStatus {
+    run(|| {
+        let result = RESULT.with(|r| r.take());
+        if result.is_empty() {
+            return Ok(());
+        }
+        if buffer.is_null() || capacity < result.len() {
+            RESULT.with(|r| *r.borrow_mut() = result);
+            return Err(InvalidArgument("buffer").into());
+        }
+        ptr::copy_nonoverlapping(result.as_ptr(), buffer, result.len());
+        Ok(())
+    })
+}
+
+/// the transactions of the address (a JSON array of `RawTxDetail`)
+///
+/// # Safety
+/// the strings must be `*_len` readable bytes, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_get_transaction_history(
+    address: *const u8,
+    address_len: usize,
+    api_key: *const u8,
+    api_key_len: usize,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let result = crate::get_transaction_history_blocking(
+            utf8_arg(address, address_len, "address")?,
+            utf8_arg(api_key, api_key_len, "api_key")?,
+        )?;
+        set_result(serde_json::to_vec(&result)?, out_len);
+        Ok(())
+    })
+}
+
+/// the ERC20 transfers (a JSON array of `RawTokenTxDetail`;
+/// option: 0 = by contract, 1 = by address and contract, 2 = by address)
+///
+/// # Safety
+/// the strings must be `*_len` readable bytes, `out_len` a valid pointer
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_get_erc20_transfer_history(
+    address: *const u8,
+    address_len: usize,
+    contract_address: *const u8,
+    contract_address_len: usize,
+    option: u32,
+    api_key: *const u8,
+    api_key_len: usize,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let result = crate::get_erc20_transfer_history_blocking(
+            utf8_arg(address, address_len, "address")?,
+            utf8_arg(contract_address, contract_address_len, "contract_address")?,
+            query_option(option)?,
+            utf8_arg(api_key, api_key_len, "api_key")?,
+        )?;
+        set_result(serde_json::to_vec(&result)?, out_len);
+        Ok(())
+    })
+}
+
+/// the ERC721 transfers (a JSON array of `RawTokenTxDetail`;
+/// option: 0 = by contract, 1 = by address and contract, 2 = by address)
+///
+/// # Safety
+/// the strings must be `*_len` readable bytes, `out_len` a valid pointer
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_get_erc721_transfer_history(
+    address: *const u8,
+    address_len: usize,
+    contract_address: *const u8,
+    contract_address_len: usize,
+    option: u32,
+    api_key: *const u8,
+    api_key_len: usize,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let result = crate::get_erc721_transfer_history_blocking(
+            utf8_arg(address, address_len, "address")?,
+            utf8_arg(contract_address, contract_address_len, "contract_address")?,
+            query_option(option)?,
+            utf8_arg(api_key, api_key_len, "api_key")?,
+        )?;
+        set_result(serde_json::to_vec(&result)?, out_len);
+        Ok(())
+    })
+}
+
+/// the owned tokens (a JSON array of `RawTokenResult`)
+///
+/// # Safety
+/// the strings must be `*_len` readable bytes, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_get_tokens(
+    blockscout_base_url: *const u8,
+    blockscout_base_url_len: usize,
+    account_address: *const u8,
+    account_address_len: usize,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let result = crate::get_tokens_blocking(
+            utf8_arg(
+                blockscout_base_url,
+                blockscout_base_url_len,
+                "blockscout_base_url",
+            )?,
+            utf8_arg(account_address, account_address_len, "account_address")?,
+        )?;
+        set_result(serde_json::to_vec(&result)?, out_len);
+        Ok(())
+    })
+}
+
+/// creates a WalletConnect client (icon_url can be empty)
+///
+/// # Safety
+/// the strings must be `*_len` readable bytes, `out_client` a valid pointer
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_new(
+    description: *const u8,
+    description_len: usize,
+    url: *const u8,
+    url_len: usize,
+    icon_url: *const u8,
+    icon_url_len: usize,
+    name: *const u8,
+    name_len: usize,
+    chain_id: u64,
+    out_client: *mut *mut WalletconnectClient,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_client, "out_client")?;
+        let icon_url = utf8_arg(icon_url, icon_url_len, "icon_url")?;
+        let icon_urls = if icon_url.is_empty() {
+            vec![]
+        } else {
+            vec![icon_url]
+        };
+        let client = crate::walletconnect_new_client(
+            utf8_arg(description, description_len, "description")?,
+            utf8_arg(url, url_len, "url")?,
+            icon_urls,
+            utf8_arg(name, name_len, "name")?,
+            chain_id,
+        )?;
+        write_out(out_client, Box::into_raw(client));
+        Ok(())
+    })
+}
+
+/// restores a WalletConnect client from the result of `play_unity_walletconnect_save`
+///
+/// # Safety
+/// `session_info` must be `session_info_len` readable bytes, `out_client` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_restore(
+    session_info: *const u8,
+    session_info_len: usize,
+    out_client: *mut *mut WalletconnectClient,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_client, "out_client")?;
+        let client = crate::walletconnect_restore_client(utf8_arg(
+            session_info,
+            session_info_len,
+            "session_info",
+        )?)?;
+        write_out(out_client, Box::into_raw(client));
+        Ok(())
+    })
+}
+
+/// releases a WalletConnect client
+///
+/// # Safety
+/// `client` must be null or a client returned by the SDK that was not released yet
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_free(client: *mut WalletconnectClient) {
+    crate::capi::play_sdk_walletconnect_free(client)
+}
+
+/// the session as a string (UTF-8), which can be stored and restored later
+///
+/// # Safety
+/// `client` must be a valid client, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_save(
+    client: *mut WalletconnectClient,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let session_info = arg_client(client)?.save_client()?;
+        set_result(session_info.into_bytes(), out_len);
+        Ok(())
+    })
+}
+
+/// the connection string (UTF-8) to be displayed as a QR code
+///
+/// # Safety
+/// `client` must be a valid client, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_connection_string(
+    client: *mut WalletconnectClient,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        let uri = arg_client(client)?.get_connection_string()?;
+        set_result(uri.into_bytes(), out_len);
+        Ok(())
+    })
+}
+
+/// creates or restores the session (blocks until the wallet approves it),
+/// the result is the concatenated 20-byte addresses
+///
+/// # Safety
+/// `client` must be a valid client, the out-parameters valid pointers
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_ensure_session(
+    client: *mut WalletconnectClient,
+    out_chain_id: *mut u64,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_chain_id, "out_chain_id")?;
+        check_out(out_len, "out_len")?;
+        let result = arg_client(client)?.ensure_session_blocking()?;
+        let addresses = result
+            .addresses
+            .iter()
+            .flat_map(|address| address.address)
+            .collect();
+        write_out(out_chain_id, result.chain_id);
+        set_result(addresses, out_len);
+        Ok(())
+    })
+}
+
+/// signs the message (`personal_sign`), the result is the 65-byte signature (r || s || v)
+///
+/// # Safety
+/// `client` must be a valid client, `message` `message_len` readable bytes,
+/// `address` 20 bytes, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_sign_personal(
+    client: *mut WalletconnectClient,
+    message: *const u8,
+    message_len: usize,
+    address: *const u8,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_len, "out_len")?;
+        if message.is_null() && message_len > 0 {
+            return Err(InvalidArgument("message").into());
+        }
+        let message = if message_len == 0 {
+            vec![]
+        } else {
+            std::slice::from_raw_parts(message, message_len).to_vec()
+        };
+        let signature = arg_client(client)?.sign_personal_bytes_blocking(
+            message,
+            true,
+            arg_address(address)?,
+        )?;
+        set_result(signature, out_len);
+        Ok(())
+    })
+}
+
+/// signs the transaction (a JSON string of `Eip1559TransactionRequest`),
+/// the result is the signed transaction bytes
+///
+/// # Safety
+/// `client` must be a valid client, `transaction_request` `transaction_request_len` readable
+/// bytes, `address` 20 bytes, `out_tx_hash` 32 writable bytes, `out_len` a valid pointer
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_sign_transaction(
+    client: *mut WalletconnectClient,
+    transaction_request: *const u8,
+    transaction_request_len: usize,
+    address: *const u8,
+    out_tx_hash: *mut u8,
+    out_len: *mut usize,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_tx_hash, "out_tx_hash")?;
+        check_out(out_len, "out_len")?;
+        let result = arg_client(client)?.sign_transaction(
+            utf8_arg(
+                transaction_request,
+                transaction_request_len,
+                "transaction_request",
+            )?,
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &result.tx_hash)?;
+        set_result(result.raw_tx, out_len);
+        Ok(())
+    })
+}
+
+/// sends the transaction (a JSON string of `Eip1559TransactionRequest`) via the wallet
+///
+/// # Safety
+/// `client` must be a valid client, `transaction_request` `transaction_request_len` readable
+/// bytes, `address` 20 bytes, `out_tx_hash` 32 writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn play_unity_walletconnect_send_transaction(
+    client: *mut WalletconnectClient,
+    transaction_request: *const u8,
+    transaction_request_len: usize,
+    address: *const u8,
+    out_tx_hash: *mut u8,
+) -> PlaySdkStatus {
+    run(|| {
+        check_out(out_tx_hash, "out_tx_hash")?;
+        let tx_hash = arg_client(client)?.send_transaction(
+            utf8_arg(
+                transaction_request,
+                transaction_request_len,
+                "transaction_request",
+            )?,
+            arg_address(address)?,
+        )?;
+        write_tx_hash(out_tx_hash, &tx_hash)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unity_result_and_error() {
+        let mut len = 0;
+        unsafe { set_result(b"result".to_vec(), &mut len) };
+        assert_eq!(len, 6);
+        let mut small = [0u8; 3];
+        let status = unsafe { play_unity_read_result(small.as_mut_ptr(), small.len()) };
+        assert_eq!(status, PlaySdkStatus::InvalidArgument);
+        let mut buffer = vec![0u8; len];
+        let status = unsafe { play_unity_read_result(buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(status, PlaySdkStatus::Ok);
+        assert_eq!(buffer, b"result");
+
+        let address = b"0xnot-an-address";
+        let status = unsafe {
+            play_unity_get_transaction_history(
+                address.as_ptr(),
+                address.len(),
+                ptr::null(),
+                0,
+                &mut len,
+            )
+        };
+        assert_eq!(status, PlaySdkStatus::Error);
+        let needed = unsafe { play_unity_last_error(ptr::null_mut(), 0) };
+        let mut message = vec![0u8; needed];
+        unsafe { play_unity_last_error(message.as_mut_ptr(), message.len()) };
+        assert!(message.starts_with(b"Invalid address"));
+    }
+}
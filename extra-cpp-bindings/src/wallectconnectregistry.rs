@@ -3,6 +3,7 @@ use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use crate::error::GameSdkError;
+use crate::http;
 use crate::{ImageUrl, Platform, WalletEntry};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -15,8 +16,7 @@ pub(crate) struct Registry {
 impl Registry {
     pub(crate) fn fetch_new(cache: Option<PathBuf>) -> Result<Self, GameSdkError> {
         const URL: &str = "https://registry.walletconnect.com/api/v2/wallets";
-        let client = reqwest::blocking::Client::new();
-        let resp: Registry = client.get(URL).send()?.json()?;
+        let resp: Registry = http::get_json(URL)?;
         if let Some(cache) = cache {
             std::fs::write(cache, serde_json::to_string(&resp)?)?;
         }
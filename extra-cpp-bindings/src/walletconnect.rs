@@ -1,24 +1,64 @@
-use crate::ffi::{WalletConnectCallback, WalletConnectTxCommon};
+use crate::ffi::{
+    Erc1155TransferAction, Erc20ApprovalAction, Erc20TransferAction, Erc721TransferAction,
+    SignedTxResult, WalletConnectCallback, WalletConnectTxCommon,
+};
 use anyhow::{anyhow, Result};
 use defi_wallet_connect::session::SessionInfo;
-use defi_wallet_connect::{Client, Metadata, WCMiddleware};
+use defi_wallet_connect::{Client, Metadata, RequestMetadata, WCMiddleware};
 use defi_wallet_connect::{ClientChannelMessage, ClientChannelMessageType};
 
 use ethers::core::types::transaction::eip2718::TypedTransaction;
 use url::Url;
 
+use crate::audit::AuditLog;
+use crate::error::GameSdkError;
 use crate::ffi::WalletConnectSessionInfo;
+use crate::idempotency::{is_rejection, IdempotencyStore, MaybeSent};
 use cxx::UniquePtr;
 use ethers::prelude::{Address, Eip1559TransactionRequest, NameOrAddress, U256};
-use ethers::prelude::{Middleware, Signature, TxHash};
+use ethers::prelude::{Bytes, Middleware, Signature, TxHash};
 use ethers::types::H160;
+use ethers::utils::{hash_message, keccak256};
 use eyre::eyre;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::sync::Arc;
 
 pub struct WalletconnectClient {
     pub client: Option<defi_wallet_connect::Client>,
     pub rt: tokio::runtime::Runtime, // need to use the same runtime, otherwise c++ side crash
+    pub callback_task: Option<CallbackTask>,
+    pub polled_callback: Option<PolledCallback>,
+    /// the signing requests of the session
+    pub audit_log: AuditLog,
+    /// the sends by idempotency key
+    pub idempotency: IdempotencyStore,
+}
+
+/// the callback of the manual pump mode:
+/// the session events are queued and delivered by `poll_events`
+pub struct PolledCallback {
+    receiver: tokio::sync::mpsc::UnboundedReceiver<ClientChannelMessage>,
+    callback: UniquePtr<WalletConnectCallback>,
+}
+
+/// the task delivering the session events to the C++ callback
+pub struct CallbackTask {
+    /// the task calling the C++ callback
+    events: tokio::task::AbortHandle,
+    /// the task waiting for the end of `events` to report its error (or panic)
+    supervisor: tokio::task::JoinHandle<()>,
+}
+
+impl CallbackTask {
+    fn stop(&self) {
+        self.events.abort();
+        self.supervisor.abort();
+    }
+
+    fn is_running(&self) -> bool {
+        !self.supervisor.is_finished()
+    }
 }
 
 async fn restore_client(contents: String) -> Result<Client> {
@@ -53,20 +93,21 @@ async fn new_client(
     for icon in icon_urls {
         icons.push(icon.parse()?);
     }
-    let chain_id = match chain_id {
+    let chain_id = match crate::environment::chain_id(chain_id)? {
         0 => None,
-        _ => Some(chain_id),
+        chain_id => Some(chain_id),
+    };
+    let metadata = Metadata {
+        description,
+        url: url.parse()?,
+        icons,
+        name,
+    };
+    // the bridge server of the SDK environment
+    let client = match crate::environment::relay_url()? {
+        Some(bridge) => Client::with_bridge(metadata, chain_id, bridge).await?,
+        None => Client::new(metadata, chain_id).await?,
     };
-    let client = Client::new(
-        Metadata {
-            description,
-            url: url.parse()?,
-            icons,
-            name,
-        },
-        chain_id,
-    )
-    .await?;
     Ok(client)
 }
 
@@ -159,96 +200,313 @@ fn convert_session_info(
     Ok(cppsessioninfo)
 }
 
+/// call the C++ callback of the session event
+fn dispatch_event(
+    cppcallback: &WalletConnectCallback,
+    message: ClientChannelMessage,
+) -> eyre::Result<()> {
+    let info = message.session.ok_or_else(|| eyre!("no session info"))?;
+    let sessioninfo = convert_session_info(&info)?;
+    let myref = sessioninfo
+        .as_ref()
+        .ok_or_else(|| eyre!("no session info"))?;
+    match message.state {
+        ClientChannelMessageType::Connected => cppcallback.onConnected(myref),
+        ClientChannelMessageType::Disconnected => cppcallback.onDisconnected(myref),
+        ClientChannelMessageType::Connecting => cppcallback.onConnecting(myref),
+        ClientChannelMessageType::Updated => cppcallback.onUpdated(myref),
+    }
+    Ok(())
+}
+
 async fn setup_callback(
     client: &mut Client,
-    cppcallback: UniquePtr<WalletConnectCallback>,
+    cppcallback: Arc<UniquePtr<WalletConnectCallback>>,
 ) -> anyhow::Result<tokio::task::JoinHandle<eyre::Result<()>>> {
     client
         .run_callback(Box::new(
             move |message: ClientChannelMessage| -> eyre::Result<()> {
-                match message.state {
-                    ClientChannelMessageType::Connected => {
-                        if let Some(info) = message.session {
-                            let sessioninfo = convert_session_info(&info)?;
-                            if let Some(myref) = sessioninfo.as_ref() {
-                                cppcallback.onConnected(myref);
-                                Ok(())
-                            } else {
-                                Err(eyre!("no session info"))
-                            }
-                        } else {
-                            Err(eyre!("no session info"))
-                        }
-                    }
-                    ClientChannelMessageType::Disconnected => {
-                        if let Some(info) = message.session {
-                            let sessioninfo = convert_session_info(&info)?;
-                            if let Some(myref) = sessioninfo.as_ref() {
-                                cppcallback.onDisconnected(myref);
-                                Ok(())
-                            } else {
-                                Err(eyre!("no session info"))
-                            }
-                        } else {
-                            Err(eyre!("no session info"))
-                        }
-                    }
-                    ClientChannelMessageType::Connecting => {
-                        if let Some(info) = &message.session {
-                            let sessioninfo = convert_session_info(info)?;
-                            if let Some(myref) = sessioninfo.as_ref() {
-                                cppcallback.onConnecting(myref);
-                                Ok(())
-                            } else {
-                                Err(eyre!("no session info"))
-                            }
-                        } else {
-                            Err(eyre!("no session info"))
-                        }
-                    }
-                    ClientChannelMessageType::Updated => {
-                        if let Some(info) = &message.session {
-                            let sessioninfo = convert_session_info(info)?;
-                            if let Some(myref) = sessioninfo.as_ref() {
-                                cppcallback.onUpdated(myref);
-                                Ok(())
-                            } else {
-                                Err(eyre!("no session info"))
-                            }
-                        } else {
-                            Err(eyre!("no session info"))
-                        }
-                    }
-                } // end of match
+                dispatch_event(&cppcallback, message)
             },
         ))
         .await
         .map_err(|e| anyhow!("{:?}", e))
 }
 
+/// drops the cached state of the sender, as its nonce and balance change
+/// (on the chain of the transaction, or of the session if it doesn't set it)
+async fn invalidate_sender(client: &Client, tx: &TypedTransaction, address: Address) {
+    let chain_id = match tx.chain_id() {
+        Some(chain_id) => Some(chain_id.as_u64()),
+        None => client
+            .get_session_info()
+            .await
+            .ok()
+            .and_then(|info| info.chain_id),
+    };
+    if let Some(chain_id) = chain_id {
+        crate::chainstate::invalidate(chain_id, address);
+    }
+}
+
 async fn sign_typed_tx(
     client: Client,
     tx: &TypedTransaction,
     address: Address,
-) -> Result<Signature> {
-    let middleware = WCMiddleware::new(client);
-    let signature = middleware.sign_transaction(tx, address).await?;
-    Ok(signature)
+    fee_cap: Option<U256>,
+) -> Result<(Bytes, Signature)> {
+    let reservation = crate::signer::check_transaction(tx, fee_cap).await?;
+    let middleware = WCMiddleware::new(client.clone());
+    let signed = middleware.sign_transaction_raw(tx, address).await?;
+    invalidate_sender(&client, tx, address).await;
+    reservation.commit();
+    Ok(signed)
 }
 
-async fn send_typed_tx(client: Client, tx: TypedTransaction, address: Address) -> Result<TxHash> {
-    let middleware = WCMiddleware::new(client).with_sender(address);
-    let receipt = middleware.send_transaction(tx, None).await?.tx_hash();
+async fn send_typed_tx(
+    client: Client,
+    tx: TypedTransaction,
+    address: Address,
+    fee_cap: Option<U256>,
+) -> Result<TxHash> {
+    let reservation = crate::signer::check_transaction(&tx, fee_cap).await?;
+    let middleware = WCMiddleware::new(client.clone()).with_sender(address);
+    let sent = middleware.send_transaction(tx.clone(), None).await;
+    // a transaction maybe sent counts too (only the rejected ones are released)
+    if !sent.as_ref().is_err_and(|e| is_rejection(&e.to_string())) {
+        invalidate_sender(&client, &tx, address).await;
+        reservation.commit();
+    }
+    let receipt = sent
+        .map_err(|e| {
+            let message = e.to_string();
+            if is_rejection(&message) {
+                anyhow!(message)
+            } else {
+                // e.g. a timeout once the wallet got the request
+                MaybeSent(message).into()
+            }
+        })?
+        .tx_hash();
     Ok(receipt)
 }
 
-#[derive(Serialize, Deserialize)]
-enum ContractAction {
+/// the result of the request recorded in the audit log (or its error)
+fn audit_result<T>(
+    result: &Result<T>,
+    summary: impl FnOnce(&T) -> String,
+) -> Result<String, String> {
+    result.as_ref().map(summary).map_err(|e| e.to_string())
+}
+
+/// whether the wallet rejected the request because it doesn't support the method:
+/// JSON-RPC "Method not found" (-32601), or the messages of the wallets without it
+/// naming the method (not e.g. an unsupported chain or token of a supported method)
+fn is_unsupported_method_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    let code = message
+        .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .any(|code| code == "-32601");
+    code || message.contains("method not found")
+        || (message.contains("eth_signtransaction")
+            && ["not supported", "unsupported", "not implemented"]
+                .iter()
+                .any(|reason| message.contains(reason)))
+}
+
+/// the `UnsupportedMethod` error of the wallets without `eth_signTransaction`
+fn sign_transaction_error(e: anyhow::Error) -> anyhow::Error {
+    if is_unsupported_method_error(&e.to_string()) {
+        GameSdkError::UnsupportedMethod("eth_signTransaction".to_owned()).into()
+    } else {
+        e
+    }
+}
+
+fn signature_hex(sig: &Signature) -> String {
+    format!("0x{}", hex::encode(sig.to_vec()))
+}
+
+/// the signed transaction with its hash and signature components
+pub(crate) fn signed_tx_result(typedtx: &TypedTransaction, sig: &Signature) -> SignedTxResult {
+    raw_signed_tx_result(&typedtx.rlp_signed(sig), sig)
+}
+
+/// the signed raw transaction (e.g. as returned by the wallet) with its hash
+/// and signature components
+fn raw_signed_tx_result(raw_tx: &[u8], sig: &Signature) -> SignedTxResult {
+    SignedTxResult {
+        tx_hash: keccak256(raw_tx).to_vec(),
+        raw_tx: raw_tx.to_vec(),
+        signature: sig.into(),
+    }
+}
+
+/// the EIP-1559 transaction request of the fields that are set
+pub(crate) fn eip1559_request(
+    userinfo: &crate::ffi::WalletConnectTxEip155,
+) -> Result<Eip1559TransactionRequest> {
+    let mut tx = Eip1559TransactionRequest::new();
+
+    if !userinfo.to.is_empty() {
+        tx = tx.to(NameOrAddress::Address(Address::from_str(&userinfo.to)?));
+    }
+    if !userinfo.data.is_empty() {
+        tx = tx.data(userinfo.data.as_slice().to_vec());
+    }
+    if !userinfo.common.gas_limit.is_empty() {
+        tx = tx.gas(U256::from_dec_str(&userinfo.common.gas_limit)?);
+    }
+    let (max_fee_per_gas, max_priority_fee_per_gas) = eip1559_fees(&userinfo.common)?;
+    if let Some(max_fee_per_gas) = max_fee_per_gas {
+        tx = tx.max_fee_per_gas(max_fee_per_gas);
+    }
+    if let Some(max_priority_fee_per_gas) = max_priority_fee_per_gas {
+        tx = tx.max_priority_fee_per_gas(max_priority_fee_per_gas);
+    }
+    if !userinfo.common.nonce.is_empty() {
+        tx = tx.nonce(U256::from_dec_str(&userinfo.common.nonce)?);
+    }
+    if !userinfo.common.chainid == 0 {
+        tx = tx.chain_id(userinfo.common.chainid);
+    }
+    if !userinfo.value.is_empty() {
+        tx = tx.value(U256::from_dec_str(&userinfo.value)?);
+    }
+    Ok(tx)
+}
+
+/// the EIP-1559 fees (max fee per gas, max priority fee per gas),
+/// each of them falls back to `gas_price` if not set
+fn eip1559_fees(common: &WalletConnectTxCommon) -> Result<(Option<U256>, Option<U256>)> {
+    bindings_common::tx::eip1559_fees(
+        &common.gas_price,
+        &common.max_fee_per_gas,
+        &common.max_priority_fee_per_gas,
+    )
+}
+
+/// set the fees of the transaction (the max fee is used as the gas price of legacy transactions)
+fn set_fees(typedtx: &mut TypedTransaction, common: &WalletConnectTxCommon) -> Result<()> {
+    bindings_common::tx::set_fees(typedtx, eip1559_fees(common)?);
+    Ok(())
+}
+
+/// The contract action given as a JSON string to `sign_contract_transaction`
+/// and `send_contract_transaction`, e.g.:
+/// `{"ContractTransfer": {"Erc20Transfer": {"contract_address": "0x..", "to_address": "0x..", "amount": "1"}}}`
+/// or
+/// `{"ContractBatchTransfer": {"Erc1155": {"contract_address": "0x..", "from_address": "0x..",
+/// "to_address": "0x..", "token_ids": ["1", "2"], "amounts": ["10", "20"], "additional_data": []}}}`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ContractAction {
+    /// ERC20/ERC721/ERC1155 approvals
     ContractApproval(defi_wallet_core_common::ContractApproval),
+    /// ERC20/ERC721/ERC1155 single transfers
     ContractTransfer(defi_wallet_core_common::ContractTransfer),
+    /// ERC1155 batch transfers
+    ContractBatchTransfer(defi_wallet_core_common::ContractBatchTransfer),
+}
+
+impl From<&Erc20ApprovalAction> for ContractAction {
+    fn from(action: &Erc20ApprovalAction) -> Self {
+        ContractAction::ContractApproval(defi_wallet_core_common::ContractApproval::Erc20 {
+            contract_address: action.contract_address.clone(),
+            approved_address: action.approved_address.clone(),
+            amount: action.amount.clone(),
+        })
+    }
+}
+
+impl From<&Erc20TransferAction> for ContractAction {
+    fn from(action: &Erc20TransferAction) -> Self {
+        let transfer = if action.from_address.is_empty() {
+            defi_wallet_core_common::ContractTransfer::Erc20Transfer {
+                contract_address: action.contract_address.clone(),
+                to_address: action.to_address.clone(),
+                amount: action.amount.clone(),
+            }
+        } else {
+            defi_wallet_core_common::ContractTransfer::Erc20TransferFrom {
+                contract_address: action.contract_address.clone(),
+                from_address: action.from_address.clone(),
+                to_address: action.to_address.clone(),
+                amount: action.amount.clone(),
+            }
+        };
+        ContractAction::ContractTransfer(transfer)
+    }
+}
+
+impl From<&Erc721TransferAction> for ContractAction {
+    fn from(action: &Erc721TransferAction) -> Self {
+        let contract_address = action.contract_address.clone();
+        let from_address = action.from_address.clone();
+        let to_address = action.to_address.clone();
+        let token_id = action.token_id.clone();
+        let transfer = if !action.safe {
+            defi_wallet_core_common::ContractTransfer::Erc721TransferFrom {
+                contract_address,
+                from_address,
+                to_address,
+                token_id,
+            }
+        } else if action.additional_data.is_empty() {
+            defi_wallet_core_common::ContractTransfer::Erc721SafeTransferFrom {
+                contract_address,
+                from_address,
+                to_address,
+                token_id,
+            }
+        } else {
+            defi_wallet_core_common::ContractTransfer::Erc721SafeTransferFromWithAdditionalData {
+                contract_address,
+                from_address,
+                to_address,
+                token_id,
+                additional_data: action.additional_data.clone(),
+            }
+        };
+        ContractAction::ContractTransfer(transfer)
+    }
+}
+
+impl From<&Erc1155TransferAction> for ContractAction {
+    fn from(action: &Erc1155TransferAction) -> Self {
+        ContractAction::ContractTransfer(
+            defi_wallet_core_common::ContractTransfer::Erc1155SafeTransferFrom {
+                contract_address: action.contract_address.clone(),
+                from_address: action.from_address.clone(),
+                to_address: action.to_address.clone(),
+                token_id: action.token_id.clone(),
+                amount: action.amount.clone(),
+                additional_data: action.additional_data.clone(),
+            },
+        )
+    }
 }
 
 impl WalletconnectClient {
+    pub fn set_request_metadata(
+        &mut self,
+        metadata: &crate::ffi::WalletConnectRequestMetadata,
+    ) -> Result<()> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_owned());
+        let metadata = RequestMetadata {
+            name: non_empty(&metadata.name),
+            description: non_empty(&metadata.description),
+            icon: match metadata.icon_url.as_str() {
+                "" => None,
+                url => Some(url.parse()?),
+            },
+            message: non_empty(&metadata.message),
+        };
+        let metadata = (metadata != RequestMetadata::default()).then_some(metadata);
+        self.rt.block_on(client.set_request_metadata(metadata));
+        Ok(())
+    }
+
     /// sign a message
     pub fn sign_personal_blocking(
         &mut self,
@@ -256,34 +514,232 @@ impl WalletconnectClient {
         address: [u8; 20],
     ) -> Result<Vec<u8>> {
         if let Some(client) = self.client.as_mut() {
+            crate::policy::check_message(true)?;
             let signeraddress = Address::from_slice(&address);
 
             let result = self
                 .rt
                 .block_on(client.personal_sign(&message, &signeraddress))
-                .map_err(|e| anyhow!("sign_personal error {}", e.to_string()))?;
-
-            Ok(result.to_vec())
+                .map_err(|e| anyhow!("sign_personal error {}", e.to_string()));
+            self.audit_log.record(
+                "personal_sign",
+                hash_message(&message).as_bytes(),
+                audit_result(&result, signature_hex),
+            );
+
+            Ok(result?.to_vec())
         } else {
             anyhow::bail!("no client");
         }
     }
 
+    /// sign the challenge of an `AuthServer` (`personal_sign`) after checking
+    /// that it's of the expected domain and the address, and not expired
+    pub fn sign_auth_challenge_blocking(
+        &mut self,
+        challenge: String,
+        domain: String,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        crate::auth::check_challenge(&challenge, &domain, Address::from_slice(&address), now)?;
+        self.sign_personal_blocking(challenge, address)
+    }
+
+    /// sign arbitrary bytes: with the EIP-191 prefix applied by the wallet (`personal_sign`),
+    /// or a pre-hashed 32-byte digest as it is (`eth_sign`)
+    pub fn sign_personal_bytes_blocking(
+        &mut self,
+        message: Vec<u8>,
+        eip191_prefix: bool,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let client = self.client.as_mut().ok_or_else(|| anyhow!("no client"))?;
+        crate::policy::check_message(eip191_prefix)?;
+        let signeraddress = Address::from_slice(&address);
+        let result = if eip191_prefix {
+            let result = self
+                .rt
+                .block_on(client.personal_sign(&message, &signeraddress))
+                .map_err(|e| anyhow!("sign_personal error {e}"));
+            self.audit_log.record(
+                "personal_sign",
+                hash_message(&message).as_bytes(),
+                audit_result(&result, signature_hex),
+            );
+            result?
+        } else {
+            let digest: [u8; 32] = message
+                .as_slice()
+                .try_into()
+                .map_err(|_| anyhow!("the digest must be 32 bytes"))?;
+            let result = self
+                .rt
+                .block_on(client.eth_sign(&digest, &signeraddress))
+                .map_err(|e| anyhow!("eth_sign error {e}"));
+            self.audit_log
+                .record("eth_sign", &digest, audit_result(&result, signature_hex));
+            result?
+        };
+        Ok(result.to_vec())
+    }
+
     pub fn setup_callback_blocking(
         &mut self,
         usercallback: UniquePtr<WalletConnectCallback>,
     ) -> Result<()> {
         if let Some(client) = self.client.as_mut() {
-            self.rt.block_on(async move {
-                // FIXME handle the join_handle, or pass to c++ side
-                let _join_handle = setup_callback(client, usercallback).await?;
-                Ok(())
-            })
+            // the previous callback (if any) is replaced
+            if let Some(task) = self.callback_task.take() {
+                task.stop();
+            }
+            self.polled_callback = None;
+            let usercallback = Arc::new(usercallback);
+            let task = self.rt.block_on(async move {
+                let events = setup_callback(client, usercallback.clone()).await?;
+                let abort_handle = events.abort_handle();
+                let supervisor = tokio::spawn(async move {
+                    let error = match events.await {
+                        Ok(Ok(())) => return,
+                        Ok(Err(e)) => format!("callback error: {e}"),
+                        Err(e) if e.is_cancelled() => return,
+                        Err(e) => format!("callback task failed: {e}"),
+                    };
+                    usercallback.onError(&error);
+                });
+                anyhow::Ok(CallbackTask {
+                    events: abort_handle,
+                    supervisor,
+                })
+            })?;
+            self.callback_task = Some(task);
+            Ok(())
         } else {
             anyhow::bail!("no client");
         }
     }
 
+    /// setup the callback in the manual pump mode
+    /// (it replaces the previous one)
+    pub fn setup_polled_callback_blocking(
+        &mut self,
+        usercallback: UniquePtr<WalletConnectCallback>,
+    ) -> Result<()> {
+        self.stop_callback();
+        let client = self.client.as_mut().ok_or_else(|| anyhow!("no client"))?;
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let task = self.rt.block_on(async move {
+            let events = client
+                .run_callback(Box::new(move |message: ClientChannelMessage| {
+                    sender
+                        .send(message)
+                        .map_err(|_| eyre!("the event queue is closed"))
+                }))
+                .await
+                .map_err(|e| anyhow!("{:?}", e))?;
+            let abort_handle = events.abort_handle();
+            // the queued events and their errors are delivered by `poll_events`
+            let supervisor = tokio::spawn(async move {
+                let _ = events.await;
+            });
+            anyhow::Ok(CallbackTask {
+                events: abort_handle,
+                supervisor,
+            })
+        })?;
+        self.callback_task = Some(task);
+        self.polled_callback = Some(PolledCallback {
+            receiver,
+            callback: usercallback,
+        });
+        Ok(())
+    }
+
+    /// deliver up to `max_events` queued session events on the caller's thread,
+    /// returns the number of delivered events
+    pub fn poll_events(&mut self, max_events: u32) -> Result<u32> {
+        let polled = self
+            .polled_callback
+            .as_mut()
+            .ok_or_else(|| anyhow!("no polled callback"))?;
+        let mut delivered = 0;
+        while delivered < max_events {
+            match polled.receiver.try_recv() {
+                Ok(message) => {
+                    dispatch_event(&polled.callback, message).map_err(|e| anyhow!("{e}"))?;
+                    delivered += 1;
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// stop delivering the session events to the callback
+    pub fn stop_callback(&mut self) {
+        if let Some(task) = self.callback_task.take() {
+            task.stop();
+        }
+        self.polled_callback = None;
+    }
+
+    /// whether the callback task is running
+    /// (false if it was not set up, stopped or ended with an error)
+    pub fn is_callback_running(&self) -> bool {
+        self.callback_task
+            .as_ref()
+            .map(CallbackTask::is_running)
+            .unwrap_or_default()
+    }
+
+    /// pause the keepalives while the app is in the background
+    pub fn notify_app_background(&mut self) -> Result<()> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        self.rt.block_on(client.notify_app_background());
+        Ok(())
+    }
+
+    /// reconnect and resume the keepalives when the app returns to the foreground
+    pub fn notify_app_foreground(&mut self) -> Result<()> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        self.rt
+            .block_on(client.notify_app_foreground())
+            .map_err(|e| anyhow!("notify_app_foreground error {e}"))
+    }
+
+    /// the state of the bridge server connection and the health of the HTTP endpoints
+    pub fn get_connection_status(&mut self) -> Result<crate::ffi::ConnectionStatus> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let status = self.rt.block_on(client.connection_status());
+        Ok(crate::ffi::ConnectionStatus {
+            bridge_connected: status.connected,
+            last_ping_rtt_ms: status
+                .last_ping_rtt
+                .map(|rtt| rtt.as_millis() as u64)
+                .unwrap_or_default(),
+            reconnect_count: status.reconnect_count,
+            endpoints: crate::http::endpoint_health(),
+        })
+    }
+
+    /// enables or disables the capture of the messages exchanged with the wallet
+    pub fn set_replay_capture(&mut self, enabled: bool) -> Result<()> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        self.rt.block_on(client.set_replay_capture(enabled));
+        Ok(())
+    }
+
+    /// the captured messages as JSON Lines
+    pub fn export_replay_log(&mut self) -> Result<String> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let entries = self.rt.block_on(client.replay_entries());
+        defi_wallet_connect::replay::to_json_lines(&entries)
+            .map_err(|e| anyhow!("export_replay_log error {e}"))
+    }
+
     /// ensure session, if session does not exist, create a new session
     pub fn ensure_session_blocking(
         self: &mut WalletconnectClient,
@@ -335,6 +791,16 @@ impl WalletconnectClient {
         }
     }
 
+    /// session info without the key and with the topics hashed, as JSON
+    pub fn get_session_info_redacted(&mut self) -> Result<String> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let info = self
+            .rt
+            .block_on(client.get_session_info_redacted())
+            .map_err(|e| anyhow!("get_session_info_redacted error {e}"))?;
+        Ok(serde_json::to_string(&info)?)
+    }
+
     /// print uri(qrcode) for debugging
     pub fn print_uri(&mut self) -> Result<String> {
         if let Some(client) = self.client.as_ref() {
@@ -349,12 +815,139 @@ impl WalletconnectClient {
         }
     }
 
+    /// signs the transaction via the wallet (recorded in the audit log);
+    /// the fee cap overrides the configured fee caps
+    fn sign_tx(
+        &self,
+        client: Client,
+        tx: &TypedTransaction,
+        address: Address,
+        fee_cap: Option<U256>,
+    ) -> Result<(Bytes, Signature)> {
+        let result = self
+            .rt
+            .block_on(sign_typed_tx(client, tx, address, fee_cap))
+            .map_err(|e| anyhow!("sign_typed_transaction error {}", e.to_string()));
+        self.audit_log.record(
+            "eth_signTransaction",
+            tx.sighash().as_bytes(),
+            audit_result(&result, |(raw_tx, _)| {
+                format!("{:?}", TxHash::from(keccak256(raw_tx)))
+            }),
+        );
+        result
+    }
+
+    /// sends the transaction via the wallet (recorded in the audit log);
+    /// the fee cap overrides the configured fee caps
+    fn send_tx(
+        &self,
+        client: Client,
+        tx: TypedTransaction,
+        address: Address,
+        fee_cap: Option<U256>,
+    ) -> Result<TxHash> {
+        if crate::dryrun::is_enabled() {
+            return self.dry_run_tx(client, tx, address, fee_cap);
+        }
+        let sighash = tx.sighash();
+        let result = self
+            .rt
+            .block_on(send_typed_tx(client, tx, address, fee_cap))
+            .map_err(|e| {
+                let message = format!("send_typed_transaction error {e}");
+                if e.is::<MaybeSent>() {
+                    MaybeSent(message).into()
+                } else {
+                    anyhow!(message)
+                }
+            });
+        self.audit_log.record(
+            "eth_sendTransaction",
+            sighash.as_bytes(),
+            audit_result(&result, |hash| format!("{hash:?}")),
+        );
+        result
+    }
+
+    /// the dry run of the send: the transaction is simulated and signed via the wallet,
+    /// but it's recorded (see `dry_run_results`) instead of being broadcast
+    fn dry_run_tx(
+        &self,
+        client: Client,
+        mut tx: TypedTransaction,
+        address: Address,
+        fee_cap: Option<U256>,
+    ) -> Result<TxHash> {
+        tx.set_from(address);
+        let gas_estimate = self.rt.block_on(crate::dryrun::simulate(&tx))?;
+        let (raw_tx, _) = self
+            .sign_tx(client, &tx, address, fee_cap)
+            .map_err(sign_transaction_error)?;
+        Ok(crate::dryrun::record(&tx, &raw_tx, gas_estimate))
+    }
+
+    /// sends the transaction once per idempotency key: a repeated send returns the hash
+    /// of the first transaction instead of broadcasting again
+    fn send_tx_with_key(
+        &self,
+        client: Client,
+        tx: TypedTransaction,
+        address: Address,
+        idempotency_key: &str,
+        fee_cap: Option<U256>,
+    ) -> Result<TxHash> {
+        // the dry runs aren't sends of the key
+        if crate::dryrun::is_enabled() {
+            return self.send_tx(client, tx, address, fee_cap);
+        }
+        let nonce = tx.nonce().map(ToString::to_string).unwrap_or_default();
+        if let Some(tx_hash) = self.idempotency.begin(idempotency_key, &nonce)? {
+            return Ok(tx_hash);
+        }
+        let result = self.send_tx(client, tx, address, fee_cap);
+        self.idempotency.finish(idempotency_key, &result)?;
+        result
+    }
+
+    /// persists the sends by idempotency key to the JSON file (none if the path is empty),
+    /// loading the ones already in it
+    pub fn set_idempotency_store_path(&mut self, path: String) -> Result<()> {
+        self.idempotency.set_path(path)
+    }
+
+    /// forgets the idempotency key, so that a send with it broadcasts again
+    pub fn forget_idempotency_key(&mut self, idempotency_key: String) -> Result<()> {
+        self.idempotency.forget(&idempotency_key)
+    }
+
+    /// the recorded signing requests of the session as a JSON array
+    pub fn export_audit_log(&self) -> Result<String> {
+        self.audit_log.export_json()
+    }
+
+    /// the recorded signing requests of the session (oldest first)
+    pub fn audit_entries(&self) -> Vec<crate::ffi::AuditEntry> {
+        self.audit_log.entries()
+    }
+
+    /// appends the signing requests recorded from now on to the JSON Lines file
+    /// (none if the path is empty)
+    pub fn set_audit_log_path(&mut self, path: String) {
+        self.audit_log.set_path(path)
+    }
+
+    /// forgets the recorded signing requests (the file is kept)
+    pub fn clear_audit_log(&mut self) {
+        self.audit_log.clear()
+    }
+
     /// build cronos(eth) eip155 transaction
     pub fn sign_eip155_transaction_blocking(
         &mut self,
         userinfo: &crate::ffi::WalletConnectTxEip155,
         address: [u8; 20],
-    ) -> Result<Vec<u8>> {
+    ) -> Result<SignedTxResult> {
         if self.client.is_none() {
             anyhow::bail!("no client");
         }
@@ -365,41 +958,38 @@ impl WalletconnectClient {
             .ok_or_else(|| anyhow!("get walllet-connect client error"))?;
         let signeraddress = Address::from_slice(&address);
 
-        let mut tx = Eip1559TransactionRequest::new();
-
-        if !userinfo.to.is_empty() {
-            tx = tx.to(NameOrAddress::Address(Address::from_str(&userinfo.to)?));
-        }
-        if !userinfo.data.is_empty() {
-            tx = tx.data(userinfo.data.as_slice().to_vec());
-        }
-        if !userinfo.common.gas_limit.is_empty() {
-            tx = tx.gas(U256::from_dec_str(&userinfo.common.gas_limit)?);
-        }
-        if !userinfo.common.gas_price.is_empty() {
-            tx = tx
-                .max_priority_fee_per_gas(U256::from_dec_str(&userinfo.common.gas_price)?)
-                .max_fee_per_gas(U256::from_dec_str(&userinfo.common.gas_price)?);
-        }
-        if !userinfo.common.nonce.is_empty() {
-            tx = tx.nonce(U256::from_dec_str(&userinfo.common.nonce)?);
-        }
-        if !userinfo.common.chainid == 0 {
-            tx = tx.chain_id(userinfo.common.chainid);
-        }
-        if !userinfo.value.is_empty() {
-            tx = tx.value(U256::from_dec_str(&userinfo.value)?);
-        }
+        let tx = eip1559_request(userinfo)?;
         let newclient = client.clone();
         let typedtx = TypedTransaction::Eip1559(tx);
 
-        let sig = self
-            .rt
-            .block_on(sign_typed_tx(newclient, &typedtx, signeraddress))
-            .map_err(|e| anyhow!("sign_typed_transaction error {}", e.to_string()))?;
+        let (raw_tx, sig) = self.sign_tx(
+            newclient,
+            &typedtx,
+            signeraddress,
+            crate::feecap::fee_cap_override(&userinfo.common)?,
+        )?;
+
+        Ok(raw_signed_tx_result(&raw_tx, &sig))
+    }
 
-        let signed_tx = &typedtx.rlp_signed(&sig);
-        Ok(signed_tx.to_vec())
+    /// signs the eip155 transaction via the wallet (`eth_signTransaction`) without
+    /// broadcasting it, returns the signed raw transaction (as returned by the wallet)
+    pub fn wallet_sign_transaction_blocking(
+        &mut self,
+        userinfo: &crate::ffi::WalletConnectTxEip155,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let typedtx = TypedTransaction::Eip1559(eip1559_request(userinfo)?);
+        let (raw_tx, _) = self
+            .sign_tx(
+                client.clone(),
+                &typedtx,
+                Address::from_slice(&address),
+                crate::feecap::fee_cap_override(&userinfo.common)?,
+            )
+            .map_err(sign_transaction_error)?;
+        Ok(raw_tx.to_vec())
     }
 
     /// send cronos(eth) eip155 transaction
@@ -418,50 +1008,50 @@ impl WalletconnectClient {
             .ok_or_else(|| anyhow!("get walllet-connect client error"))?;
         let signeraddress = Address::from_slice(&address);
 
-        let mut tx = Eip1559TransactionRequest::new();
-
-        if !userinfo.to.is_empty() {
-            tx = tx.to(NameOrAddress::Address(Address::from_str(&userinfo.to)?));
-        }
-        if !userinfo.data.is_empty() {
-            tx = tx.data(userinfo.data.as_slice().to_vec());
-        }
-        if !userinfo.common.gas_limit.is_empty() {
-            tx = tx.gas(U256::from_dec_str(&userinfo.common.gas_limit)?);
-        }
-        if !userinfo.common.gas_price.is_empty() {
-            tx = tx
-                .max_priority_fee_per_gas(U256::from_dec_str(&userinfo.common.gas_price)?)
-                .max_fee_per_gas(U256::from_dec_str(&userinfo.common.gas_price)?);
-        }
-        if !userinfo.common.nonce.is_empty() {
-            tx = tx.nonce(U256::from_dec_str(&userinfo.common.nonce)?);
-        }
-        if !userinfo.common.chainid == 0 {
-            tx = tx.chain_id(userinfo.common.chainid);
-        }
-        if !userinfo.value.is_empty() {
-            tx = tx.value(U256::from_dec_str(&userinfo.value)?);
-        }
+        let tx = eip1559_request(userinfo)?;
 
         let newclient = client.clone();
         let typedtx = TypedTransaction::Eip1559(tx);
 
-        let tx_bytes = self
-            .rt
-            .block_on(send_typed_tx(newclient, typedtx, signeraddress))
-            .map_err(|e| anyhow!("send_typed_transaction error {}", e.to_string()))?;
+        let tx_bytes = self.send_tx(
+            newclient,
+            typedtx,
+            signeraddress,
+            crate::feecap::fee_cap_override(&userinfo.common)?,
+        )?;
 
         Ok(tx_bytes.0.to_vec())
     }
 
-    fn get_signed_tx_raw_bytes(
+    /// send cronos(eth) eip155 transaction once per idempotency key
+    pub fn send_eip155_transaction_with_key_blocking(
+        &mut self,
+        userinfo: &crate::ffi::WalletConnectTxEip155,
+        address: [u8; 20],
+        idempotency_key: String,
+    ) -> Result<Vec<u8>> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let signeraddress = Address::from_slice(&address);
+        let typedtx = TypedTransaction::Eip1559(eip1559_request(userinfo)?);
+
+        let tx_hash = self.send_tx_with_key(
+            client.clone(),
+            typedtx,
+            signeraddress,
+            &idempotency_key,
+            crate::feecap::fee_cap_override(&userinfo.common)?,
+        )?;
+
+        Ok(tx_hash.0.to_vec())
+    }
+
+    fn get_signed_tx_result(
         &self,
         newclient: Client,
         signeraddress: H160,
         typedtx: &mut TypedTransaction,
         common: &WalletConnectTxCommon,
-    ) -> Result<Vec<u8>> {
+    ) -> Result<SignedTxResult> {
         let mynonce = U256::from_dec_str(&common.nonce)?;
         if !mynonce.is_zero() {
             typedtx.set_nonce(mynonce);
@@ -473,17 +1063,16 @@ impl WalletconnectClient {
         if !common.gas_limit.is_empty() {
             typedtx.set_gas(U256::from_dec_str(&common.gas_limit)?);
         }
-        if !common.gas_price.is_empty() {
-            typedtx.set_gas_price(U256::from_dec_str(&common.gas_price)?);
-        }
+        set_fees(typedtx, common)?;
 
-        let sig = self
-            .rt
-            .block_on(sign_typed_tx(newclient, typedtx, signeraddress))
-            .map_err(|e| anyhow!("sign_typed_transaction error {}", e.to_string()))?;
+        let (raw_tx, sig) = self.sign_tx(
+            newclient,
+            typedtx,
+            signeraddress,
+            crate::feecap::fee_cap_override(common)?,
+        )?;
 
-        let signed_tx = &typedtx.rlp_signed(&sig);
-        Ok(signed_tx.to_vec())
+        Ok(raw_signed_tx_result(&raw_tx, &sig))
     }
 
     fn get_sent_tx_raw_bytes(
@@ -504,14 +1093,14 @@ impl WalletconnectClient {
         if !common.gas_limit.is_empty() {
             typedtx.set_gas(U256::from_dec_str(&common.gas_limit)?);
         }
-        if !common.gas_price.is_empty() {
-            typedtx.set_gas_price(U256::from_dec_str(&common.gas_price)?);
-        }
+        set_fees(typedtx, common)?;
 
-        let tx_bytes = self
-            .rt
-            .block_on(send_typed_tx(newclient, typedtx.clone(), signeraddress))
-            .map_err(|e| anyhow!("send_typed_transaction error {}", e.to_string()))?;
+        let tx_bytes = self.send_tx(
+            newclient,
+            typedtx.clone(),
+            signeraddress,
+            crate::feecap::fee_cap_override(common)?,
+        )?;
 
         Ok(tx_bytes.0.to_vec())
     }
@@ -520,7 +1109,7 @@ impl WalletconnectClient {
         &mut self,
         eip1559_transaction_request: String,
         address: [u8; 20],
-    ) -> Result<Vec<u8>> {
+    ) -> Result<SignedTxResult> {
         if self.client.is_none() {
             anyhow::bail!("no client");
         }
@@ -532,17 +1121,13 @@ impl WalletconnectClient {
         let signeraddress = Address::from_slice(&address);
 
         // parse json string transaction_info to TransactionRequest
-        let tx: Eip1559TransactionRequest = serde_json::from_str(&eip1559_transaction_request)?;
+        let tx = crate::txjson::parse_transaction_json(&eip1559_transaction_request)?;
         let typedtx = TypedTransaction::Eip1559(tx);
 
         let newclient = client.clone();
-        let sig = self
-            .rt
-            .block_on(sign_typed_tx(newclient, &typedtx, signeraddress))
-            .map_err(|e| anyhow!("sign_typed_transaction error {}", e.to_string()))?;
+        let (raw_tx, sig) = self.sign_tx(newclient, &typedtx, signeraddress, None)?;
 
-        let signed_tx = &typedtx.rlp_signed(&sig);
-        Ok(signed_tx.to_vec())
+        Ok(raw_signed_tx_result(&raw_tx, &sig))
     }
 
     pub fn send_transaction(
@@ -561,24 +1146,54 @@ impl WalletconnectClient {
         let signeraddress = Address::from_slice(&address);
 
         // parse json string transaction_info to TransactionRequest
-        let tx: Eip1559TransactionRequest = serde_json::from_str(&eip1559_transaction_request)?;
+        let tx = crate::txjson::parse_transaction_json(&eip1559_transaction_request)?;
         let typedtx = TypedTransaction::Eip1559(tx);
 
         let newclient = client.clone();
-        let tx_bytes = self
-            .rt
-            .block_on(send_typed_tx(newclient, typedtx, signeraddress))
-            .map_err(|e| anyhow!("send_typed_transaction error {}", e.to_string()))?;
+        let tx_bytes = self.send_tx(newclient, typedtx, signeraddress, None)?;
 
         Ok(tx_bytes.0.to_vec())
     }
 
+    /// `send_transaction` once per idempotency key
+    pub fn send_transaction_with_key(
+        &mut self,
+        eip1559_transaction_request: String,
+        address: [u8; 20],
+        idempotency_key: String,
+    ) -> Result<Vec<u8>> {
+        let client = self.client.as_ref().ok_or_else(|| anyhow!("no client"))?;
+        let signeraddress = Address::from_slice(&address);
+        let tx = crate::txjson::parse_transaction_json(&eip1559_transaction_request)?;
+        let typedtx = TypedTransaction::Eip1559(tx);
+
+        let tx_hash = self.send_tx_with_key(
+            client.clone(),
+            typedtx,
+            signeraddress,
+            &idempotency_key,
+            None,
+        )?;
+
+        Ok(tx_hash.0.to_vec())
+    }
+
     pub fn sign_contract_transaction(
         &mut self,
         contract_action: String,
         common: &WalletConnectTxCommon,
         address: [u8; 20],
-    ) -> Result<Vec<u8>> {
+    ) -> Result<SignedTxResult> {
+        let action: ContractAction = serde_json::from_str(&contract_action)?;
+        self.sign_contract_action(action, common, address)
+    }
+
+    fn sign_contract_action(
+        &mut self,
+        action: ContractAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<SignedTxResult> {
         if self.client.is_none() {
             anyhow::bail!("no client");
         }
@@ -589,38 +1204,9 @@ impl WalletconnectClient {
             .ok_or_else(|| anyhow!("get walllet-connect client error"))?;
         let newclient = client.clone();
 
-        let action: ContractAction = serde_json::from_str(&contract_action)?;
-        // parse json string transaction_info to TransactionRequest
-        // let tx: ContractTransfer = serde_json::from_str(&contract_transaction_info)?;
-
-        let mut typedtx = match action {
-            ContractAction::ContractApproval(approval) => {
-                self.rt
-                    .block_on(defi_wallet_core_common::construct_contract_approval_tx(
-                        approval,
-                        defi_wallet_core_common::EthNetwork::Custom {
-                            chain_id: common.chainid,
-                            legacy: false,
-                        },
-                        common.web3api_url.as_str(),
-                    ))?
-            }
-            ContractAction::ContractTransfer(transfer) => {
-                self.rt
-                    .block_on(defi_wallet_core_common::construct_contract_transfer_tx(
-                        transfer,
-                        defi_wallet_core_common::EthNetwork::Custom {
-                            chain_id: common.chainid,
-                            legacy: false,
-                        },
-                        // TODO unnessary for walletconnect
-                        common.web3api_url.as_str(),
-                    ))?
-            }
-        };
+        let mut typedtx = self.construct_contract_tx(action, common)?;
 
-        let tx = self.get_signed_tx_raw_bytes(newclient, signeraddress, &mut typedtx, common)?;
-        Ok(tx.to_vec())
+        self.get_signed_tx_result(newclient, signeraddress, &mut typedtx, common)
     }
 
     pub fn send_contract_transaction(
@@ -628,6 +1214,16 @@ impl WalletconnectClient {
         contract_action: String,
         common: &WalletConnectTxCommon,
         address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        let action: ContractAction = serde_json::from_str(&contract_action)?;
+        self.send_contract_action(action, common, address)
+    }
+
+    fn send_contract_action(
+        &mut self,
+        action: ContractAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
     ) -> Result<Vec<u8>> {
         if self.client.is_none() {
             anyhow::bail!("no client");
@@ -639,37 +1235,243 @@ impl WalletconnectClient {
             .ok_or_else(|| anyhow!("get walllet-connect client error"))?;
         let newclient = client.clone();
 
-        let action: ContractAction = serde_json::from_str(&contract_action)?;
-        // parse json string transaction_info to TransactionRequest
-        // let tx: ContractTransfer = serde_json::from_str(&contract_transaction_info)?;
+        let mut typedtx = self.construct_contract_tx(action, common)?;
+
+        let tx = self.get_sent_tx_raw_bytes(newclient, signeraddress, &mut typedtx, common)?;
+        Ok(tx.to_vec())
+    }
+
+    pub fn sign_erc20_approval(
+        &mut self,
+        action: &Erc20ApprovalAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<SignedTxResult> {
+        self.sign_contract_action(action.into(), common, address)
+    }
+
+    pub fn send_erc20_approval(
+        &mut self,
+        action: &Erc20ApprovalAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        self.send_contract_action(action.into(), common, address)
+    }
+
+    pub fn sign_erc20_transfer(
+        &mut self,
+        action: &Erc20TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<SignedTxResult> {
+        self.sign_contract_action(action.into(), common, address)
+    }
+
+    pub fn send_erc20_transfer(
+        &mut self,
+        action: &Erc20TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        self.send_contract_action(action.into(), common, address)
+    }
+
+    pub fn sign_erc721_transfer(
+        &mut self,
+        action: &Erc721TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<SignedTxResult> {
+        self.sign_contract_action(action.into(), common, address)
+    }
+
+    pub fn send_erc721_transfer(
+        &mut self,
+        action: &Erc721TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        self.send_contract_action(action.into(), common, address)
+    }
+
+    pub fn sign_erc1155_transfer(
+        &mut self,
+        action: &Erc1155TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<SignedTxResult> {
+        self.sign_contract_action(action.into(), common, address)
+    }
 
-        let mut typedtx = match action {
+    pub fn send_erc1155_transfer(
+        &mut self,
+        action: &Erc1155TransferAction,
+        common: &WalletConnectTxCommon,
+        address: [u8; 20],
+    ) -> Result<Vec<u8>> {
+        self.send_contract_action(action.into(), common, address)
+    }
+
+    /// construct the contract transaction of the action
+    fn construct_contract_tx(
+        &self,
+        action: ContractAction,
+        common: &WalletConnectTxCommon,
+    ) -> Result<TypedTransaction> {
+        let network = defi_wallet_core_common::EthNetwork::Custom {
+            chain_id: common.chainid,
+            legacy: false,
+        };
+        // TODO unnessary for walletconnect
+        let web3api_url = common.web3api_url.as_str();
+        let typedtx = match action {
             ContractAction::ContractApproval(approval) => {
                 self.rt
                     .block_on(defi_wallet_core_common::construct_contract_approval_tx(
                         approval,
-                        defi_wallet_core_common::EthNetwork::Custom {
-                            chain_id: common.chainid,
-                            legacy: false,
-                        },
-                        common.web3api_url.as_str(),
+                        network,
+                        web3api_url,
                     ))?
             }
             ContractAction::ContractTransfer(transfer) => {
                 self.rt
                     .block_on(defi_wallet_core_common::construct_contract_transfer_tx(
                         transfer,
-                        defi_wallet_core_common::EthNetwork::Custom {
-                            chain_id: common.chainid,
-                            legacy: false,
-                        },
-                        // TODO unnessary for walletconnect
-                        common.web3api_url.as_str(),
+                        network,
+                        web3api_url,
                     ))?
             }
+            ContractAction::ContractBatchTransfer(batch_transfer) => self.rt.block_on(
+                defi_wallet_core_common::construct_contract_batch_transfer_tx(
+                    batch_transfer,
+                    network,
+                    web3api_url,
+                ),
+            )?,
+        };
+        Ok(typedtx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_contract_batch_transfer_json() {
+        let action: ContractAction = serde_json::from_str(
+            r#"{
+                "ContractBatchTransfer": {
+                    "Erc1155": {
+                        "contract_address": "0x939D7350c54228e4958e05b65512C4a5BB6A2ACc",
+                        "from_address": "0x714e0aa8e6b0fd1b4b5d8d0a0a1d3c8e1b8dbd56",
+                        "to_address": "0xa9b34a4b568e640d5e5d1e6e13101025e1c0b5ee",
+                        "token_ids": ["1", "2"],
+                        "amounts": ["10", "20"],
+                        "additional_data": []
+                    }
+                }
+            }"#,
+        )
+        .expect("parse batch transfer");
+        assert!(matches!(action, ContractAction::ContractBatchTransfer(_)));
+    }
+
+    #[test]
+    fn test_signed_tx_result() {
+        let typedtx = TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .chain_id(25)
+                .nonce(1)
+                .value(1000),
+        );
+        let sig = Signature {
+            r: U256::from(1),
+            s: U256::from(2),
+            v: 1,
         };
+        let result = signed_tx_result(&typedtx, &sig);
+        assert_eq!(result.raw_tx, typedtx.rlp_signed(&sig).to_vec());
+        assert_eq!(result.tx_hash, typedtx.hash(&sig).as_bytes());
+        assert_eq!(result.signature.r.len(), 32);
+        assert_eq!(result.signature.r[31], 1);
+        assert_eq!(result.signature.s[31], 2);
+        assert_eq!(result.signature.v, 1);
+    }
 
-        let tx = self.get_sent_tx_raw_bytes(newclient, signeraddress, &mut typedtx, common)?;
-        Ok(tx.to_vec())
+    #[test]
+    fn test_unsupported_method_error() {
+        for message in [
+            "sign_typed_transaction error Method not found",
+            r#"sign_typed_transaction error {"code":-32601,"message":"the method does not exist"}"#,
+            "sign_typed_transaction error eth_signTransaction is not supported",
+            "sign_typed_transaction error Unsupported method: eth_signTransaction",
+        ] {
+            assert!(is_unsupported_method_error(message), "{message}");
+        }
+        for message in [
+            "sign_typed_transaction error User rejected the request",
+            "sign_typed_transaction error unsupported chain id 338",
+            "sign_typed_transaction error the token is not supported",
+            r#"sign_typed_transaction error {"code":-326010,"message":"failed"}"#,
+        ] {
+            assert!(!is_unsupported_method_error(message), "{message}");
+        }
+    }
+
+    #[test]
+    fn test_eip1559_fees() {
+        let mut common = WalletConnectTxCommon {
+            gas_price: "100".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            eip1559_fees(&common).expect("fees"),
+            (Some(U256::from(100)), Some(U256::from(100)))
+        );
+        common.max_priority_fee_per_gas = "2".to_owned();
+        let mut typedtx = TypedTransaction::Eip1559(Eip1559TransactionRequest::new());
+        set_fees(&mut typedtx, &common).expect("fees");
+        match typedtx {
+            TypedTransaction::Eip1559(tx) => {
+                assert_eq!(tx.max_fee_per_gas, Some(U256::from(100)));
+                assert_eq!(tx.max_priority_fee_per_gas, Some(U256::from(2)));
+            }
+            _ => panic!("not an EIP-1559 transaction"),
+        }
+        common.gas_price = String::default();
+        common.max_priority_fee_per_gas = String::default();
+        assert_eq!(eip1559_fees(&common).expect("fees"), (None, None));
+    }
+
+    #[test]
+    fn test_contract_action_structs() {
+        let erc20 = Erc20TransferAction {
+            contract_address: "0x939D7350c54228e4958e05b65512C4a5BB6A2ACc".to_owned(),
+            from_address: String::default(),
+            to_address: "0xa9b34a4b568e640d5e5d1e6e13101025e1c0b5ee".to_owned(),
+            amount: "1000".to_owned(),
+        };
+        assert!(matches!(
+            ContractAction::from(&erc20),
+            ContractAction::ContractTransfer(
+                defi_wallet_core_common::ContractTransfer::Erc20Transfer { .. }
+            )
+        ));
+        let erc721 = Erc721TransferAction {
+            contract_address: "0x939D7350c54228e4958e05b65512C4a5BB6A2ACc".to_owned(),
+            from_address: "0x714e0aa8e6b0fd1b4b5d8d0a0a1d3c8e1b8dbd56".to_owned(),
+            to_address: "0xa9b34a4b568e640d5e5d1e6e13101025e1c0b5ee".to_owned(),
+            token_id: "1".to_owned(),
+            safe: true,
+            additional_data: vec![],
+        };
+        assert!(matches!(
+            ContractAction::from(&erc721),
+            ContractAction::ContractTransfer(
+                defi_wallet_core_common::ContractTransfer::Erc721SafeTransferFrom { .. }
+            )
+        ));
     }
 }
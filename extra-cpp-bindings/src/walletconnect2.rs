@@ -0,0 +1,321 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use cxx::UniquePtr;
+use defi_wallet_connect::migration;
+use defi_wallet_connect::v2::{
+    Client, ClientOptions, CosmosSignDirectDoc, CosmosSignResponse, Metadata, Namespaces,
+    RequiredNamespaces, SessionInfo,
+};
+use serde::Serialize;
+
+use crate::ffi::{
+    ConnectionStatus, MigrationOutcome, RePairing, WalletConnect2CosmosSignature,
+    WalletConnect2Namespaces, WalletConnect2SessionProposalCallback,
+};
+use crate::memory;
+
+/// the WalletConnect 2.0 client
+pub struct Walletconnect2Client {
+    pub client: Client,
+    pub rt: tokio::runtime::Runtime, // need to use the same runtime, otherwise c++ side crash
+}
+
+impl From<&Namespaces> for WalletConnect2Namespaces {
+    fn from(namespaces: &Namespaces) -> Self {
+        Self {
+            accounts: namespaces
+                .get_ethereum_addresses()
+                .iter()
+                .map(|account| account.to_string())
+                .collect(),
+            chain_ids: namespaces.get_chain_ids(),
+            methods: namespaces.get_methods(),
+            events: namespaces.get_events(),
+            cosmos_accounts: namespaces.get_cosmos_accounts(),
+        }
+    }
+}
+
+impl<D: Serialize> TryFrom<CosmosSignResponse<D>> for WalletConnect2CosmosSignature {
+    type Error = anyhow::Error;
+
+    fn try_from(response: CosmosSignResponse<D>) -> Result<Self> {
+        Ok(Self {
+            pub_key_type: response.signature.pub_key.key_type,
+            pub_key: STANDARD.decode(response.signature.pub_key.value)?,
+            signature: STANDARD.decode(response.signature.signature)?,
+            signed_doc: serde_json::to_string(&response.signed)?,
+        })
+    }
+}
+
+/// relay_server: e.g. "wss://relay.walletconnect.com" (without the trailing `/`)
+/// required_namespaces: json of `RequiredNamespaces`
+/// client_meta: json of `Metadata`
+pub fn walletconnect2_client_new(
+    relay_server: String,
+    project_id: String,
+    required_namespaces: String,
+    client_meta: String,
+) -> Result<Box<Walletconnect2Client>> {
+    // the threads of the client's runtime count their allocations for it
+    let _scope = memory::scope(memory::WALLETCONNECT2);
+    let required_namespaces: RequiredNamespaces = serde_json::from_str(&required_namespaces)?;
+    // the chains of the SDK environment
+    for chain_id in required_namespaces.get_chain_ids() {
+        crate::environment::chain_id(chain_id)?;
+    }
+    let client_meta: Metadata = serde_json::from_str(&client_meta)?;
+    let opts = ClientOptions {
+        relay_server: relay_server.parse()?,
+        project_id,
+        required_namespaces,
+        client_meta,
+        callback_sender: None,
+    };
+    let rt = crate::runtime::new_runtime()?;
+    let client = rt
+        .block_on(Client::new(opts))
+        .map_err(|e| anyhow!("walletconnect2_client_new error {e}"))?;
+    Ok(Box::new(Walletconnect2Client { client, rt }))
+}
+
+/// restore the client from the session info (as written by `save_client`)
+pub fn walletconnect2_restore_client(session_info: String) -> Result<Box<Walletconnect2Client>> {
+    if session_info.is_empty() {
+        anyhow::bail!("session info is empty");
+    }
+    let _scope = memory::scope(memory::WALLETCONNECT2);
+    let session: SessionInfo = serde_json::from_str(&session_info)?;
+    let rt = crate::runtime::new_runtime()?;
+    let client = rt
+        .block_on(Client::restore(session, None))
+        .map_err(|e| anyhow!("walletconnect2_restore_client error {e}"))?;
+    Ok(Box::new(Walletconnect2Client { client, rt }))
+}
+
+/// detects the version of the saved session and migrates it
+/// (see `defi_wallet_connect::migration::migrate_session`)
+pub fn walletconnect2_migrate_session(
+    session_json: String,
+    default_chain_id: u64,
+) -> Result<MigrationOutcome> {
+    let outcome = migration::migrate_session(&session_json)
+        .map_err(|e| anyhow!("walletconnect2_migrate_session error {e}"))?;
+    Ok(match outcome {
+        migration::MigrationOutcome::Current(session) => MigrationOutcome {
+            current: true,
+            session_info: serde_json::to_string(&session)?,
+            re_pairing: RePairing::default(),
+        },
+        migration::MigrationOutcome::RePair(re_pairing) => MigrationOutcome {
+            current: false,
+            session_info: String::new(),
+            re_pairing: RePairing {
+                was_connected: re_pairing.was_connected,
+                accounts: re_pairing
+                    .accounts
+                    .iter()
+                    .map(|account| format!("{account:?}"))
+                    .collect(),
+                chain_id: re_pairing.chain_id.unwrap_or(default_chain_id),
+                wallet_name: re_pairing.wallet_name.clone().unwrap_or_default(),
+                client_meta: serde_json::to_string(&re_pairing.client_meta)?,
+                required_namespaces: serde_json::to_string(
+                    &re_pairing.required_namespaces(default_chain_id),
+                )?,
+            },
+        },
+    })
+}
+
+impl Walletconnect2Client {
+    /// create or restore a session, returns the namespaces settled by the wallet
+    pub fn ensure_session_blocking(&mut self) -> Result<WalletConnect2Namespaces> {
+        let namespaces = self
+            .rt
+            .block_on(self.client.ensure_session())
+            .map_err(|e| anyhow!("ensure_session error {e}"))?;
+        Ok((&namespaces).into())
+    }
+
+    pub fn get_connection_string(&mut self) -> String {
+        self.rt.block_on(self.client.get_connection_string())
+    }
+
+    /// save session to string which can be written to file
+    pub fn save_client(&mut self) -> Result<String> {
+        let session = self.rt.block_on(self.client.get_session_info());
+        Ok(serde_json::to_string(&session)?)
+    }
+
+    /// session info without the keys and with the topics hashed, as JSON
+    pub fn get_session_info_redacted(&mut self) -> Result<String> {
+        let session = self.rt.block_on(self.client.get_session_info_redacted());
+        Ok(serde_json::to_string(&session)?)
+    }
+
+    /// sign the protobuf-encoded Cosmos transaction (`cosmos_signDirect`)
+    pub fn cosmos_sign_direct_blocking(
+        &mut self,
+        chain_id: String,
+        signer_address: String,
+        account_number: u64,
+        auth_info_bytes: Vec<u8>,
+        body_bytes: Vec<u8>,
+    ) -> Result<WalletConnect2CosmosSignature> {
+        let sign_doc = CosmosSignDirectDoc {
+            chain_id: chain_id.clone(),
+            account_number: account_number.to_string(),
+            auth_info_bytes: STANDARD.encode(auth_info_bytes),
+            body_bytes: STANDARD.encode(body_bytes),
+        };
+        self.rt
+            .block_on(
+                self.client
+                    .cosmos_sign_direct(&chain_id, &signer_address, sign_doc),
+            )
+            .map_err(|e| anyhow!("cosmos_sign_direct error {e}"))?
+            .try_into()
+    }
+
+    /// sign the Amino JSON `StdSignDoc` (`cosmos_signAmino`)
+    pub fn cosmos_sign_amino_blocking(
+        &mut self,
+        chain_id: String,
+        signer_address: String,
+        sign_doc: String,
+    ) -> Result<WalletConnect2CosmosSignature> {
+        let sign_doc: serde_json::Value = serde_json::from_str(&sign_doc)?;
+        self.rt
+            .block_on(
+                self.client
+                    .cosmos_sign_amino(&chain_id, &signer_address, sign_doc),
+            )
+            .map_err(|e| anyhow!("cosmos_sign_amino error {e}"))?
+            .try_into()
+    }
+
+    /// the state of the relay connection and the health of the HTTP endpoints
+    pub fn get_connection_status(&mut self) -> ConnectionStatus {
+        let status = self.rt.block_on(self.client.connection_status());
+        ConnectionStatus {
+            bridge_connected: status.connected,
+            last_ping_rtt_ms: status
+                .last_ping_rtt
+                .map(|rtt| rtt.as_millis() as u64)
+                .unwrap_or_default(),
+            reconnect_count: status.reconnect_count,
+            endpoints: crate::http::endpoint_health(),
+        }
+    }
+
+    /// enables or disables the capture of the messages exchanged with the wallet
+    pub fn set_replay_capture(&mut self, enabled: bool) {
+        self.rt.block_on(self.client.set_replay_capture(enabled))
+    }
+
+    /// the captured messages as JSON Lines
+    pub fn export_replay_log(&mut self) -> Result<String> {
+        let entries = self.rt.block_on(self.client.replay_entries());
+        defi_wallet_connect::replay::to_json_lines(&entries)
+            .map_err(|e| anyhow!("export_replay_log error {e}"))
+    }
+
+    /// the callback previews the namespaces settled by the wallet before the session
+    /// is established (a null callback removes it)
+    pub fn set_session_proposal_callback(
+        &mut self,
+        usercallback: UniquePtr<WalletConnect2SessionProposalCallback>,
+    ) {
+        if usercallback.is_null() {
+            self.rt
+                .block_on(self.client.clear_session_proposal_handler());
+            return;
+        }
+        let usercallback = Arc::new(usercallback);
+        self.rt
+            .block_on(self.client.set_session_proposal_handler(move |namespaces| {
+                usercallback.onSessionProposal(&namespaces.into())
+            }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_namespaces() {
+        let namespaces: Namespaces = serde_json::from_str(
+            r#"{"eip155":{"accounts":["eip155:25:0xcE915a3b937261853EE2C60B8010c22c295200B0"],"methods":["personal_sign"],"events":["chainChanged"]}}"#,
+        )
+        .unwrap();
+        let namespaces = WalletConnect2Namespaces::from(&namespaces);
+        assert_eq!(
+            namespaces.accounts,
+            vec!["eip155:25:0xce915a3b937261853ee2c60b8010c22c295200b0"]
+        );
+        assert_eq!(namespaces.chain_ids, vec![25]);
+        assert_eq!(namespaces.methods, vec!["personal_sign"]);
+        assert_eq!(namespaces.events, vec!["chainChanged"]);
+        assert!(namespaces.cosmos_accounts.is_empty());
+    }
+
+    /// a saved WalletConnect 1.0 session (without the chain id)
+    const V1_SESSION: &str = r#"{"connected":true,"accounts":["0x0101010101010101010101010101010101010101"],"chainId":null,"bridge":"https://l.bridge.walletconnect.org/","key":"0101010101010101010101010101010101010101010101010101010101010101","clientId":"4e6234f1-02ae-412d-a6cf-d1e709054943","clientMeta":{"description":"the game","url":"https://game.example.test/","icons":[],"name":"Game"},"peerId":"12fd5172-1d81-4d75-bbb1-eea4c57cc8de","peerMeta":{"name":"DeFi Wallet"},"handshakeTopic":"21913778-59f3-4082-b496-f8511f54ec15"}"#;
+
+    #[test]
+    fn test_migrate_session() {
+        let outcome = walletconnect2_migrate_session(V1_SESSION.to_owned(), 338).unwrap();
+        assert!(!outcome.current);
+        assert!(outcome.session_info.is_empty());
+        let re_pairing = outcome.re_pairing;
+        assert!(re_pairing.was_connected);
+        assert_eq!(
+            re_pairing.accounts,
+            vec!["0x0101010101010101010101010101010101010101"]
+        );
+        assert_eq!(re_pairing.chain_id, 338);
+        assert_eq!(re_pairing.wallet_name, "DeFi Wallet");
+        let client_meta: Metadata = serde_json::from_str(&re_pairing.client_meta).unwrap();
+        assert_eq!(client_meta.name, "Game");
+        let namespaces: RequiredNamespaces =
+            serde_json::from_str(&re_pairing.required_namespaces).unwrap();
+        assert_eq!(namespaces.get_chain_ids(), vec![338]);
+
+        let session = SessionInfo::new(
+            "wss://relay.walletconnect.com".parse().unwrap(),
+            "project".to_owned(),
+            namespaces,
+            client_meta,
+        );
+        let outcome =
+            walletconnect2_migrate_session(serde_json::to_string(&session).unwrap(), 338).unwrap();
+        assert!(outcome.current);
+        let restored: SessionInfo = serde_json::from_str(&outcome.session_info).unwrap();
+        assert_eq!(
+            restored.session_proposal_topic,
+            session.session_proposal_topic
+        );
+        assert!(outcome.re_pairing.accounts.is_empty());
+
+        assert!(walletconnect2_migrate_session("{}".to_owned(), 338).is_err());
+    }
+
+    #[test]
+    fn test_cosmos_signature() {
+        let response: CosmosSignResponse<CosmosSignDirectDoc> = serde_json::from_str(
+            r#"{"signature":{"pub_key":{"type":"tendermint/PubKeySecp256k1","value":"AgEC"},"signature":"AQID"},"signed":{"chainId":"crypto-org-chain-mainnet-1","accountNumber":"1","authInfoBytes":"CgoKCA==","bodyBytes":"CgQKAgoA"}}"#,
+        )
+        .unwrap();
+        let signature = WalletConnect2CosmosSignature::try_from(response).unwrap();
+        assert_eq!(signature.pub_key_type, "tendermint/PubKeySecp256k1");
+        assert_eq!(signature.pub_key, vec![2, 1, 2]);
+        assert_eq!(signature.signature, vec![1, 2, 3]);
+        assert!(signature.signed_doc.contains("\"accountNumber\":\"1\""));
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use anyhow::Result;
+use cxx::UniquePtr;
+use ethers::etherscan::account::{
+    ERC20TokenTransferEvent, ERC721TokenTransferEvent, NormalTransaction, Sort, TokenQueryOption,
+    TxListParams,
+};
+use ethers::types::Address;
+use serde::de::DeserializeOwned;
+
+use crate::ffi::{AddressWatcherCallback, RawTokenTxDetail, RawTxDetail};
+use crate::{get_explorer_result, parse_address, tx_list_params};
+
+/// the last block number accepted by the explorer API
+const END_BLOCK: u64 = 99999999;
+/// how many records are requested in one poll
+const PAGE_SIZE: u64 = 100;
+/// the shortest polling interval (the explorer API is rate-limited)
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// a record that can be watched
+trait Watched {
+    fn block_no(&self) -> u64;
+    /// identifies the record (a transaction may have several token transfers)
+    fn key(&self) -> String;
+}
+
+impl Watched for RawTxDetail {
+    fn block_no(&self) -> u64 {
+        self.block_no
+    }
+
+    fn key(&self) -> String {
+        self.hash.to_lowercase()
+    }
+}
+
+impl Watched for RawTokenTxDetail {
+    fn block_no(&self) -> u64 {
+        self.block_no
+    }
+
+    fn key(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}",
+            self.hash,
+            self.contract_address,
+            self.from_address,
+            self.to_address,
+            self.value,
+            self.token_id
+        )
+        .to_lowercase()
+    }
+}
+
+/// the position of one kind of records of a watched address
+#[derive(Debug, Default)]
+struct WatchState {
+    /// whether the first poll was done (its records are not new)
+    started: bool,
+    /// the block the next poll starts from (it may not have been complete)
+    start_block: u64,
+    /// the keys of the already seen records in `start_block`
+    seen: HashSet<String>,
+    /// the page (from 0) of the records since `start_block` the next poll requests
+    /// (the full pages of `start_block` are skipped, as it may have more records than a page)
+    page: u64,
+}
+
+impl WatchState {
+    /// the explorer query parameters of the next poll: the latest records in the first one,
+    /// then the records since `start_block`
+    fn list_params(&self) -> TxListParams {
+        if self.started {
+            TxListParams::new(
+                self.start_block,
+                END_BLOCK,
+                self.page + 1,
+                PAGE_SIZE,
+                Sort::Asc,
+            )
+        } else {
+            TxListParams::new(0, END_BLOCK, 1, PAGE_SIZE, Sort::Desc)
+        }
+    }
+
+    /// updates the position from the fetched records and returns the new ones (oldest first)
+    fn advance<D: Watched>(&mut self, fetched: Vec<D>) -> Vec<D> {
+        let full_page = fetched.len() as u64 >= PAGE_SIZE;
+        let mut records: BTreeMap<(u64, String), D> = fetched
+            .into_iter()
+            .map(|record| ((record.block_no(), record.key()), record))
+            .collect();
+        records.retain(|(block_no, key), _| {
+            *block_no > self.start_block
+                || (*block_no == self.start_block && !self.seen.contains(key))
+        });
+        match records.keys().next_back() {
+            Some((last_block, _)) if *last_block > self.start_block => {
+                self.start_block = *last_block;
+                self.seen.clear();
+                self.page = 0;
+            }
+            // a full page of `start_block`: the next poll requests the next page
+            _ if self.started && full_page => self.page += 1,
+            _ => {}
+        }
+        for (block_no, key) in records.keys() {
+            if *block_no == self.start_block {
+                self.seen.insert(key.clone());
+            }
+        }
+        if std::mem::replace(&mut self.started, true) {
+            records.into_values().collect()
+        } else {
+            vec![]
+        }
+    }
+}
+
+/// a watched address
+#[derive(Debug)]
+struct WatchedAddress {
+    address: Address,
+    transactions: WatchState,
+    erc20_transfers: WatchState,
+    erc721_transfers: WatchState,
+}
+
+/// the explorer query parameters of the action
+fn query_params(
+    action: &str,
+    address: Address,
+    state: &WatchState,
+) -> BTreeMap<&'static str, String> {
+    if action == "txlist" {
+        let mut params = tx_list_params(state.list_params());
+        params.insert("address", format!("{address:?}"));
+        params
+    } else {
+        TokenQueryOption::ByAddress(address)
+            .into_params(state.list_params())
+            .into_iter()
+            .collect()
+    }
+}
+
+/// polls the records of the action and returns the new ones
+async fn poll<E, D>(
+    action: &str,
+    address: Address,
+    state: &mut WatchState,
+    api_key: &str,
+) -> Result<Vec<D>>
+where
+    E: DeserializeOwned,
+    D: Watched + for<'a> From<&'a E>,
+{
+    let params = query_params(action, address, state);
+    let events: Vec<E> = get_explorer_result(action, params, api_key.to_owned()).await?;
+    Ok(state.advance(events.iter().map(D::from).collect()))
+}
+
+impl WatchedAddress {
+    /// polls the explorer once and calls the callback for the new records
+    async fn poll(&mut self, api_key: &str, callback: &AddressWatcherCallback) -> Result<()> {
+        let address = format!("{:?}", self.address);
+        let transactions = poll::<NormalTransaction, RawTxDetail>(
+            "txlist",
+            self.address,
+            &mut self.transactions,
+            api_key,
+        )
+        .await?;
+        for tx in transactions {
+            callback.onNewTransaction(&address, &tx);
+        }
+        let mut transfers = poll::<ERC20TokenTransferEvent, RawTokenTxDetail>(
+            "tokentx",
+            self.address,
+            &mut self.erc20_transfers,
+            api_key,
+        )
+        .await?;
+        transfers.extend(
+            poll::<ERC721TokenTransferEvent, RawTokenTxDetail>(
+                "tokennfttx",
+                self.address,
+                &mut self.erc721_transfers,
+                api_key,
+            )
+            .await?,
+        );
+        for transfer in transfers {
+            callback.onNewTokenTransfer(&address, &transfer);
+        }
+        Ok(())
+    }
+}
+
+/// Polls Cronoscan at an interval for the activity of a set of addresses
+/// and calls the callback once for each new transaction or token transfer
+/// (the activity before the start is not reported)
+pub struct AddressWatcher {
+    addresses: Vec<Address>,
+    api_key: String,
+    interval: Duration,
+    task: Option<tokio::task::JoinHandle<()>>,
+    rt: tokio::runtime::Runtime,
+}
+
+impl AddressWatcher {
+    pub(crate) fn new(addresses: &[String], api_key: String, interval_ms: u64) -> Result<Self> {
+        Ok(Self {
+            addresses: addresses
+                .iter()
+                .map(|address| parse_address(address))
+                .collect::<Result<_, _>>()?,
+            api_key,
+            interval: Duration::from_millis(interval_ms).max(MIN_INTERVAL),
+            task: None,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    /// starts polling (it replaces the previous callback; the watched addresses start again
+    /// from their latest activity)
+    pub fn start(&mut self, callback: UniquePtr<AddressWatcherCallback>) -> Result<()> {
+        if callback.is_null() {
+            anyhow::bail!("no callback");
+        }
+        self.stop();
+        let mut watched: Vec<WatchedAddress> = self
+            .addresses
+            .iter()
+            .map(|address| WatchedAddress {
+                address: *address,
+                transactions: WatchState::default(),
+                erc20_transfers: WatchState::default(),
+                erc721_transfers: WatchState::default(),
+            })
+            .collect();
+        let api_key = self.api_key.clone();
+        let interval = self.interval;
+        self.task = Some(self.rt.spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                for address in watched.iter_mut() {
+                    if let Err(e) = address.poll(&api_key, &callback).await {
+                        callback.onError(&format!("{:?}: {e}", address.address));
+                    }
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// stops polling
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    /// whether the watcher is polling
+    pub fn is_running(&self) -> bool {
+        self.task
+            .as_ref()
+            .map(|task| !task.is_finished())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for AddressWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// the watch state only looks at the blocks and the hashes
+    fn tx(hash: &str, block_no: u64) -> RawTxDetail {
+        RawTxDetail {
+            hash: hash.to_owned(),
+            block_no,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_watch_state_advance() {
+        let mut state = WatchState::default();
+        // the first poll (latest first) is not reported
+        assert!(state.advance(vec![tx("b", 11), tx("a", 10)]).is_empty());
+        assert_eq!(state.start_block, 11);
+        assert_eq!(state.list_params().start_block, 11);
+        // the block 11 is fetched again: only its new transaction is reported
+        let new = state.advance(vec![tx("b", 11), tx("c", 11), tx("d", 12)]);
+        assert_eq!(new, vec![tx("c", 11), tx("d", 12)]);
+        assert_eq!(state.start_block, 12);
+        // nothing new
+        assert!(state.advance(vec![tx("d", 12)]).is_empty());
+        assert!(state.advance(Vec::<RawTxDetail>::new()).is_empty());
+        assert_eq!(
+            state.advance(vec![tx("D", 12), tx("e", 12)]),
+            vec![tx("e", 12)]
+        );
+    }
+
+    #[test]
+    fn test_watch_state_full_block() {
+        let mut state = WatchState::default();
+        assert!(state.advance(vec![tx("a", 11)]).is_empty());
+        // a full page of the block 11: the next poll requests the next page
+        let page: Vec<_> = (0..PAGE_SIZE).map(|i| tx(&format!("b{i}"), 11)).collect();
+        assert_eq!(state.advance(page).len() as u64, PAGE_SIZE);
+        assert_eq!(state.list_params().page, 2);
+        let new = state.advance(vec![tx("c", 11), tx("d", 12)]);
+        assert_eq!(new, vec![tx("c", 11), tx("d", 12)]);
+        assert_eq!((state.start_block, state.page), (12, 0));
+        assert_eq!(state.list_params().page, 1);
+    }
+}
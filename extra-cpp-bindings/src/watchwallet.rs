@@ -0,0 +1,111 @@
+use anyhow::Result;
+
+use crate::error::GameSdkError;
+use crate::ffi::{
+    QueryOption, RawTokenResult, RawTokenTxDetail, RawTxDetail, SignedTxResult,
+    WalletConnectTxEip155,
+};
+use crate::watcher::AddressWatcher;
+
+/// A read-only wallet of an address (e.g. of another player or of a customer):
+/// its history, balances and tokens can be queried and its activity watched,
+/// but every signing fails with `NotSignable`
+pub struct WatchWallet {
+    /// hexadecimal, 0x-prefixed
+    address: String,
+    /// the runtime of the JSON-RPC requests (created once)
+    rt: tokio::runtime::Runtime,
+}
+
+impl WatchWallet {
+    pub(crate) fn new(address: &str) -> Result<Self> {
+        Ok(Self {
+            address: crate::normalize_address(address)?,
+            rt: crate::runtime::new_runtime()?,
+        })
+    }
+
+    fn not_signable<T>(&self) -> Result<T> {
+        Err(GameSdkError::NotSignable(self.address.clone()).into())
+    }
+
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    /// the transactions of the address (see `get_transaction_history_blocking`)
+    pub fn get_transaction_history_blocking(&self, api_key: String) -> Result<Vec<RawTxDetail>> {
+        crate::get_transaction_history_blocking(self.address.clone(), api_key)
+    }
+
+    /// the token transfers of the address (see `get_token_transfers_blocking`)
+    pub fn get_token_transfers_blocking(
+        &self,
+        blockscout_base_url: String,
+    ) -> Result<Vec<RawTokenTxDetail>> {
+        crate::get_token_transfers_blocking(
+            blockscout_base_url,
+            self.address.clone(),
+            String::new(),
+            QueryOption::ByAddress,
+        )
+    }
+
+    /// the tokens owned by the address (see `get_tokens_blocking`)
+    pub fn get_tokens_blocking(&self, blockscout_base_url: String) -> Result<Vec<RawTokenResult>> {
+        crate::get_tokens_blocking(blockscout_base_url, self.address.clone())
+    }
+
+    /// the balance of the native coin (decimal, in wei) via the JSON-RPC url
+    pub fn get_balance_blocking(&self, rpc_url: String) -> Result<String> {
+        let balance = self
+            .rt
+            .block_on(crate::rpc::get_balance(&rpc_url, &self.address))?;
+        Ok(balance.to_string())
+    }
+
+    /// a watcher of the activity of the address (see `new_address_watcher`)
+    pub fn watch_activity(&self, api_key: String, interval_ms: u64) -> Result<Box<AddressWatcher>> {
+        crate::new_address_watcher(vec![self.address.clone()], api_key, interval_ms)
+    }
+
+    pub fn sign_personal_message(&self, _message: Vec<u8>) -> Result<Vec<u8>> {
+        self.not_signable()
+    }
+
+    pub fn sign_hash(&self, _hash: Vec<u8>) -> Result<Vec<u8>> {
+        self.not_signable()
+    }
+
+    pub fn sign_eip155_transaction(&self, _tx: &WalletConnectTxEip155) -> Result<SignedTxResult> {
+        self.not_signable()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_watch_wallet_not_signable() {
+        assert!(WatchWallet::new("not an address").is_err());
+        let wallet = WatchWallet::new("0x841A15D12aEc9c6039FD132c2FbFF112eD355700").unwrap();
+        assert_eq!(
+            wallet.address(),
+            "0x841a15d12aec9c6039fd132c2fbff112ed355700"
+        );
+        let errors = [
+            wallet.sign_personal_message(b"hello".to_vec()).unwrap_err(),
+            wallet.sign_hash(vec![0; 32]).unwrap_err(),
+            wallet
+                .sign_eip155_transaction(&WalletConnectTxEip155::default())
+                .unwrap_err(),
+        ];
+        for error in errors {
+            assert!(matches!(
+                error.downcast_ref::<GameSdkError>(),
+                Some(GameSdkError::NotSignable(address)) if *address == wallet.address()
+            ));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use ethers::types::U256;
+
+use crate::ffi::WalletConnectTxEip155;
+
+/// the methods of the wrapped native coin (WETH9-style)
+const WRAPPED_METHODS: &[&str] = &[
+    "function deposit() payable",
+    "function withdraw(uint256 wad)",
+];
+
+/// the address of the canonical WCRO contract of the chain
+pub fn wcro_address(chain_id: u64) -> Result<String> {
+    match chain_id {
+        25 => Ok("0x5C7F8A570d578ED84E63fdFA7b1eE72dEae1AE23".to_owned()),
+        _ => Err(anyhow!("no known WCRO contract on the chain {chain_id}")),
+    }
+}
+
+/// the transaction calling the method of the WCRO contract of the chain
+fn wcro_transaction(
+    chain_id: u64,
+    method: &str,
+    args: &[ethers::abi::Token],
+    value: String,
+) -> Result<WalletConnectTxEip155> {
+    let abi = ethers::abi::parse_abi(WRAPPED_METHODS)?;
+    let mut tx = WalletConnectTxEip155 {
+        to: wcro_address(chain_id)?,
+        value,
+        data: abi.function(method)?.encode_input(args)?,
+        common: Default::default(),
+    };
+    tx.common.chainid = chain_id;
+    Ok(tx)
+}
+
+/// wraps the amount of CRO (in the base units): `deposit` with the amount as the value
+pub fn build_wrap_transaction(chain_id: u64, amount: String) -> Result<WalletConnectTxEip155> {
+    let amount = U256::from_dec_str(&amount)?;
+    wcro_transaction(chain_id, "deposit", &[], amount.to_string())
+}
+
+/// unwraps the amount of WCRO (in the base units): `withdraw` without a value
+pub fn build_unwrap_transaction(chain_id: u64, amount: String) -> Result<WalletConnectTxEip155> {
+    let amount = U256::from_dec_str(&amount)?;
+    wcro_transaction(
+        chain_id,
+        "withdraw",
+        &[ethers::abi::Token::Uint(amount)],
+        "0".to_owned(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wrap_transactions() {
+        let wrap = build_wrap_transaction(25, "1000".into()).unwrap();
+        assert_eq!(wrap.value, "1000");
+        // deposit()
+        assert_eq!(wrap.data, hex::decode("d0e30db0").unwrap());
+        assert_eq!(wrap.common.chainid, 25);
+
+        let unwrap = build_unwrap_transaction(25, "1000".into()).unwrap();
+        assert_eq!(unwrap.value, "0");
+        assert_eq!(unwrap.to, wrap.to);
+        // withdraw(uint256)
+        assert_eq!(unwrap.data[..4], hex::decode("2e1a7d4d").unwrap());
+        assert_eq!(U256::from_big_endian(&unwrap.data[4..]), U256::from(1000));
+
+        assert!(build_wrap_transaction(25, "-1".into()).is_err());
+        assert!(build_unwrap_transaction(12345, "1".into()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+use ethers::core::types::{Address, Chain};
+use ethers::etherscan::{
+    account::{TokenQueryOption, TxListParams},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+pub use bindings_common::explorer::{RawBlockScoutTransfer, RawTokenTxDetail, RawTxDetail};
+pub use bindings_common::value::ValueKind;
+
+/// the same as `QueryOption` of the C++ bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryOption {
+    ByContract,
+    ByAddressAndContract,
+    ByAddress,
+}
+
+impl TryFrom<u8> for QueryOption {
+    type Error = anyhow::Error;
+
+    fn try_from(option: u8) -> Result<Self> {
+        match option {
+            0 => Ok(QueryOption::ByContract),
+            1 => Ok(QueryOption::ByAddressAndContract),
+            2 => Ok(QueryOption::ByAddress),
+            _ => Err(anyhow!("invalid query option: {option}")),
+        }
+    }
+}
+
+/// Token ownership result detail from BlockScout API
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RawTokenResult {
+    pub balance: String,
+    #[serde(rename = "contractAddress")]
+    pub contract_address: String,
+    pub decimals: String,
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    #[serde(rename = "type")]
+    pub token_type: String,
+}
+
+/// Token holder detail from BlockScout API
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct TokenHolderDetail {
+    pub address: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RawResponse<R> {
+    message: String,
+    pub result: Vec<R>,
+    status: String,
+}
+
+/// Etherscan-compatible API response (on errors, `result` is a string)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExplorerResponse<R> {
+    Success(RawResponse<R>),
+    Error {
+        message: String,
+        result: Option<String>,
+    },
+}
+
+/// parses a hexadecimal address (with or without the 0x prefix)
+pub fn parse_address(address: &str) -> Result<Address> {
+    address
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Invalid address: {address}"))
+}
+
+/// returns the normalized (0x-prefixed, lowercase) form of a hexadecimal address
+pub fn normalize_address(address: &str) -> Result<String> {
+    Ok(format!("{:?}", parse_address(address)?))
+}
+
+/// the Cronoscan API url of an account module action
+pub fn explorer_url(action: &str, params: BTreeMap<&str, String>, api_key: &str) -> Result<Url> {
+    let client = Client::new(Chain::Cronos, api_key)?;
+    let mut url = client.etherscan_api_url().clone();
+    url.query_pairs_mut()
+        .append_pair("module", "account")
+        .append_pair("action", action)
+        .extend_pairs(params)
+        .append_pair("apikey", api_key);
+    Ok(url)
+}
+
+/// the result of an Etherscan-compatible API response
+pub fn explorer_result<R: serde::de::DeserializeOwned>(body: &str) -> Result<Vec<R>> {
+    match serde_json::from_str::<ExplorerResponse<R>>(body)? {
+        ExplorerResponse::Success(resp) => Ok(resp.result),
+        ExplorerResponse::Error { message, result } => {
+            Err(anyhow!("{message}: {}", result.unwrap_or_default()))
+        }
+    }
+}
+
+/// the parameters of the `txlist` action
+pub fn transaction_history_params(address: &str) -> Result<BTreeMap<&'static str, String>> {
+    let mut params: BTreeMap<_, _> = HashMap::from(TxListParams::default()).into_iter().collect();
+    params.insert("address", normalize_address(address)?);
+    Ok(params)
+}
+
+/// the parameters of the `tokentx` and `tokennfttx` actions
+pub fn token_query_params(
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+) -> Result<BTreeMap<&'static str, String>> {
+    let option = match option {
+        QueryOption::ByContract => TokenQueryOption::ByContract(parse_address(contract_address)?),
+        QueryOption::ByAddressAndContract => TokenQueryOption::ByAddressAndContract(
+            parse_address(address)?,
+            parse_address(contract_address)?,
+        ),
+        QueryOption::ByAddress => TokenQueryOption::ByAddress(parse_address(address)?),
+    };
+    Ok(option
+        .into_params(TxListParams::default())
+        .into_iter()
+        .collect())
+}
+
+/// the BlockScout url of the owned tokens
+pub fn tokens_url(blockscout_base_url: &str, account_address: &str) -> Result<String> {
+    let account_address = normalize_address(account_address)?;
+    Ok(format!(
+        "{blockscout_base_url}?module=account&action=tokenlist&address={account_address}"
+    ))
+}
+
+/// the BlockScout url of the token transfers (QueryOption::ByContract is not supported)
+pub fn token_transfers_url(
+    blockscout_base_url: &str,
+    address: &str,
+    contract_address: &str,
+    option: QueryOption,
+) -> Result<String> {
+    match option {
+        QueryOption::ByAddress => {
+            let address = normalize_address(address)?;
+            Ok(format!(
+                "{blockscout_base_url}?module=account&action=tokentx&address={address}"
+            ))
+        }
+        QueryOption::ByAddressAndContract => {
+            let address = normalize_address(address)?;
+            let contract_address = normalize_address(contract_address)?;
+            Ok(format!(
+                "{blockscout_base_url}?module=account&action=tokentx&address={address}&contractaddress={contract_address}"
+            ))
+        }
+        QueryOption::ByContract => Err(anyhow!("unsupported option")),
+    }
+}
+
+/// the BlockScout url of the token holders
+pub fn token_holders_url(
+    blockscout_base_url: &str,
+    contract_address: &str,
+    page: u64,
+    offset: u64,
+) -> Result<String> {
+    let contract_address = normalize_address(contract_address)?;
+    Ok(format!(
+        "{blockscout_base_url}?module=token&action=getTokenHolders&contractaddress={contract_address}&page={page}&offset={offset}"
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_explorer_url() {
+        let params =
+            transaction_history_params("0x841A15D12aEc9c6039FD132c2FbFF112eD355700").unwrap();
+        let url = explorer_url("txlist", params, "KEY").unwrap();
+        let query: BTreeMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["module"], "account");
+        assert_eq!(query["action"], "txlist");
+        assert_eq!(query["apikey"], "KEY");
+        assert_eq!(
+            query["address"],
+            "0x841a15d12aec9c6039fd132c2fbff112ed355700"
+        );
+        assert!(transaction_history_params("0x1234").is_err());
+        assert!(token_query_params("", "", QueryOption::ByAddress).is_err());
+    }
+
+    #[test]
+    fn test_explorer_result() {
+        let error = r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#;
+        let err = explorer_result::<RawTokenResult>(error).unwrap_err();
+        assert_eq!(err.to_string(), "NOTOK: Invalid API Key");
+
+        let body = r#"{"message":"OK","status":"1","result":[{"blockHash":"0xbb","blockNumber":"10","confirmations":"1","contractAddress":"0x0000000000000000000000000000000000000001","cumulativeGasUsed":"21000","from":"0x0000000000000000000000000000000000000002","gas":"21000","gasPrice":"1","gasUsed":"21000","hash":"0xaa","input":"0x","logIndex":"0","nonce":"0","timeStamp":"1650000000","to":"0x0000000000000000000000000000000000000003","tokenDecimal":"","tokenID":"7","tokenName":"NFT","tokenSymbol":"N","transactionIndex":"0","value":"1"}]}"#;
+        let transfers: RawResponse<RawBlockScoutTransfer> = serde_json::from_str(body).unwrap();
+        let detail = RawTokenTxDetail::try_from(&transfers.result[0]).unwrap();
+        assert_eq!(detail.block_no, 10);
+        assert_eq!(detail.token_id, "7");
+        assert_eq!(detail.value_kind, ValueKind::NonFungible);
+        assert_eq!(detail.timestamp_iso, "2022-04-15T05:20:00Z");
+    }
+}
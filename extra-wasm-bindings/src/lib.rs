@@ -0,0 +1,12 @@
+//! WebAssembly (wasm32-unknown-unknown) bindings of the explorer queries
+//! and the transaction building, for the web builds of games.
+//! The exports are only compiled for `target_arch = "wasm32"`, e.g.:
+//! `wasm-pack build extra-wasm-bindings --target web`
+
+/// explorer (Cronoscan/BlockScout) urls, responses and their conversions
+pub mod explorer;
+/// unsigned transaction building
+pub mod tx;
+/// the wasm-bindgen exports (the HTTP requests use the fetch API)
+#[cfg(target_arch = "wasm32")]
+mod wasm;
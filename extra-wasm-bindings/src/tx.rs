@@ -0,0 +1,156 @@
+use anyhow::Result;
+use bindings_common::tx::{eip1559_fees, set_fees};
+use ethers::core::types::transaction::eip2718::TypedTransaction;
+use ethers::core::types::{Address, Eip1559TransactionRequest, U256};
+use ethers::core::utils::keccak256;
+use serde::{Deserialize, Serialize};
+
+/// the common transaction fields (the same as `WalletConnectTxCommon` of the C++ bindings;
+/// the decimal strings can be empty)
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct TxCommon {
+    pub gas_limit: String,
+    pub gas_price: String,
+    pub nonce: String,
+    pub chainid: u64,
+    pub web3api_url: String,
+    pub max_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+}
+
+/// The contract action (the same JSON as in `sign_contract_transaction` of the C++ bindings)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ContractAction {
+    /// ERC20/ERC721/ERC1155 approvals
+    ContractApproval(defi_wallet_core_common::ContractApproval),
+    /// ERC20/ERC721/ERC1155 single transfers
+    ContractTransfer(defi_wallet_core_common::ContractTransfer),
+    /// ERC1155 batch transfers
+    ContractBatchTransfer(defi_wallet_core_common::ContractBatchTransfer),
+}
+
+/// An unsigned transaction to be signed by a wallet (e.g. an injected browser wallet)
+#[derive(Serialize, Debug)]
+pub struct UnsignedTx {
+    /// the transaction (the JSON of ethers' `TypedTransaction`)
+    pub transaction: TypedTransaction,
+    /// the RLP-encoded unsigned transaction (0x-prefixed hexadecimal)
+    pub rlp: String,
+    /// the hash to be signed (0x-prefixed hexadecimal)
+    pub sighash: String,
+}
+
+impl From<TypedTransaction> for UnsignedTx {
+    fn from(transaction: TypedTransaction) -> Self {
+        let rlp = transaction.rlp();
+        let sighash = keccak256(&rlp);
+        UnsignedTx {
+            transaction,
+            rlp: format!("0x{}", hex::encode(rlp)),
+            sighash: format!("0x{}", hex::encode(sighash)),
+        }
+    }
+}
+
+/// sets the sender and the common fields (the same way as the C++ bindings before signing)
+fn apply_common(typedtx: &mut TypedTransaction, from: Address, common: &TxCommon) -> Result<()> {
+    if !common.nonce.is_empty() {
+        let nonce = U256::from_dec_str(&common.nonce)?;
+        if !nonce.is_zero() {
+            typedtx.set_nonce(nonce);
+        }
+    }
+    typedtx.set_from(from);
+    if common.chainid != 0 {
+        typedtx.set_chain_id(common.chainid);
+    }
+    if !common.gas_limit.is_empty() {
+        typedtx.set_gas(U256::from_dec_str(&common.gas_limit)?);
+    }
+    let fees = eip1559_fees(
+        &common.gas_price,
+        &common.max_fee_per_gas,
+        &common.max_priority_fee_per_gas,
+    )?;
+    set_fees(typedtx, fees);
+    Ok(())
+}
+
+/// builds the transaction from a JSON string of `Eip1559TransactionRequest`
+pub fn build_transaction(
+    eip1559_transaction_request: &str,
+    from: Address,
+    common: &TxCommon,
+) -> Result<UnsignedTx> {
+    let request: Eip1559TransactionRequest = serde_json::from_str(eip1559_transaction_request)?;
+    let mut typedtx = TypedTransaction::Eip1559(request);
+    apply_common(&mut typedtx, from, common)?;
+    Ok(typedtx.into())
+}
+
+/// builds the contract transaction of the action
+/// (`common.web3api_url` is queried for the missing fields, e.g. the nonce)
+pub async fn build_contract_transaction(
+    action: ContractAction,
+    from: Address,
+    common: &TxCommon,
+) -> Result<UnsignedTx> {
+    let network = defi_wallet_core_common::EthNetwork::Custom {
+        chain_id: common.chainid,
+        legacy: false,
+    };
+    let web3api_url = common.web3api_url.as_str();
+    let mut typedtx = match action {
+        ContractAction::ContractApproval(approval) => {
+            defi_wallet_core_common::construct_contract_approval_tx(approval, network, web3api_url)
+                .await?
+        }
+        ContractAction::ContractTransfer(transfer) => {
+            defi_wallet_core_common::construct_contract_transfer_tx(transfer, network, web3api_url)
+                .await?
+        }
+        ContractAction::ContractBatchTransfer(batch_transfer) => {
+            defi_wallet_core_common::construct_contract_batch_transfer_tx(
+                batch_transfer,
+                network,
+                web3api_url,
+            )
+            .await?
+        }
+    };
+    apply_common(&mut typedtx, from, common)?;
+    Ok(typedtx.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_build_transaction() {
+        let common = TxCommon {
+            gas_limit: "21000".into(),
+            gas_price: "5000000000000".into(),
+            nonce: "1".into(),
+            chainid: 338,
+            ..Default::default()
+        };
+        let request = r#"{"to": "0x841a15d12aec9c6039fd132c2fbff112ed355700", "value": "0x1"}"#;
+        let unsigned = build_transaction(request, Address::repeat_byte(1), &common).unwrap();
+        assert!(unsigned.rlp.starts_with("0x02"));
+        let rlp = hex::decode(&unsigned.rlp[2..]).unwrap();
+        assert_eq!(
+            unsigned.sighash,
+            format!("0x{}", hex::encode(keccak256(rlp)))
+        );
+        match unsigned.transaction {
+            TypedTransaction::Eip1559(tx) => {
+                assert_eq!(tx.chain_id, Some(338.into()));
+                assert_eq!(tx.max_fee_per_gas, Some(5_000_000_000_000u64.into()));
+                assert_eq!(tx.nonce, Some(1.into()));
+            }
+            _ => panic!("not an EIP-1559 transaction"),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::explorer::{self, RawResponse, RawTokenResult, RawTokenTxDetail, TokenHolderDetail};
+use crate::tx::{self, ContractAction, TxCommon};
+
+fn to_js<T: Serialize>(result: Result<T>) -> Result<JsValue, JsValue> {
+    let value = result.map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(serde_wasm_bindgen::to_value(&value)?)
+}
+
+/// GET the url (via the fetch API)
+async fn get_text(url: &str) -> Result<String> {
+    Ok(reqwest::get(url).await?.text().await?)
+}
+
+/// GET the url and deserialize the JSON response
+async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
+    Ok(serde_json::from_str(&get_text(url).await?)?)
+}
+
+/// queries the account module of the Cronoscan API
+async fn get_explorer_result<R: DeserializeOwned>(
+    action: &str,
+    params: std::collections::BTreeMap<&str, String>,
+    api_key: &str,
+) -> Result<Vec<R>> {
+    let url = explorer::explorer_url(action, params, api_key)?;
+    explorer::explorer_result(&get_text(url.as_str()).await?)
+}
+
+async fn token_history<R>(
+    action: &str,
+    address: &str,
+    contract_address: &str,
+    option: u8,
+    api_key: &str,
+) -> Result<Vec<RawTokenTxDetail>>
+where
+    R: DeserializeOwned,
+    for<'a> &'a R: Into<RawTokenTxDetail>,
+{
+    let params = explorer::token_query_params(address, contract_address, option.try_into()?)?;
+    let transfers: Vec<R> = get_explorer_result(action, params, api_key).await?;
+    Ok(transfers.iter().map(Into::into).collect())
+}
+
+/// returns the transactions of a given address (an array of `RawTxDetail`).
+/// The API key can be obtained from https://cronoscan.com
+#[wasm_bindgen(js_name = getTransactionHistory)]
+pub async fn get_transaction_history(address: String, api_key: String) -> Result<JsValue, JsValue> {
+    to_js(
+        async {
+            let params = explorer::transaction_history_params(&address)?;
+            let transactions: Vec<ethers::etherscan::account::NormalTransaction> =
+                get_explorer_result("txlist", params, &api_key).await?;
+            Ok(transactions
+                .iter()
+                .map(explorer::RawTxDetail::from)
+                .collect::<Vec<_>>())
+        }
+        .await,
+    )
+}
+
+/// returns the ERC20 transfers (an array of `RawTokenTxDetail`;
+/// option: 0 = by contract, 1 = by address and contract, 2 = by address)
+#[wasm_bindgen(js_name = getErc20TransferHistory)]
+pub async fn get_erc20_transfer_history(
+    address: String,
+    contract_address: String,
+    option: u8,
+    api_key: String,
+) -> Result<JsValue, JsValue> {
+    to_js(
+        token_history::<ethers::etherscan::account::ERC20TokenTransferEvent>(
+            "tokentx",
+            &address,
+            &contract_address,
+            option,
+            &api_key,
+        )
+        .await,
+    )
+}
+
+/// returns the ERC721 transfers (an array of `RawTokenTxDetail`;
+/// option: 0 = by contract, 1 = by address and contract, 2 = by address)
+#[wasm_bindgen(js_name = getErc721TransferHistory)]
+pub async fn get_erc721_transfer_history(
+    address: String,
+    contract_address: String,
+    option: u8,
+    api_key: String,
+) -> Result<JsValue, JsValue> {
+    to_js(
+        token_history::<ethers::etherscan::account::ERC721TokenTransferEvent>(
+            "tokennfttx",
+            &address,
+            &contract_address,
+            option,
+            &api_key,
+        )
+        .await,
+    )
+}
+
+/// given the BlockScout REST API base url and the account address (hexadecimal),
+/// it will return the list of all owned tokens (an array of `RawTokenResult`)
+#[wasm_bindgen(js_name = getTokens)]
+pub async fn get_tokens(
+    blockscout_base_url: String,
+    account_address: String,
+) -> Result<JsValue, JsValue> {
+    to_js(
+        async {
+            let url = explorer::tokens_url(&blockscout_base_url, &account_address)?;
+            let resp: RawResponse<RawTokenResult> = get_json(&url).await?;
+            Ok(resp.result)
+        }
+        .await,
+    )
+}
+
+/// given the BlockScout REST API base url, the account address and the optional contract address,
+/// it will return all the token transfers (an array of `RawTokenTxDetail`;
+/// option: 1 = by address and contract, 2 = by address)
+#[wasm_bindgen(js_name = getTokenTransfers)]
+pub async fn get_token_transfers(
+    blockscout_base_url: String,
+    address: String,
+    contract_address: String,
+    option: u8,
+) -> Result<JsValue, JsValue> {
+    to_js(
+        async {
+            let url = explorer::token_transfers_url(
+                &blockscout_base_url,
+                &address,
+                &contract_address,
+                option.try_into()?,
+            )?;
+            let resp: RawResponse<explorer::RawBlockScoutTransfer> = get_json(&url).await?;
+            Ok(resp
+                .result
+                .iter()
+                .flat_map(RawTokenTxDetail::try_from)
+                .collect::<Vec<_>>())
+        }
+        .await,
+    )
+}
+
+/// given the BlockScout REST API base url and the contract address,
+/// it will return the list of owners and balances (an array of `TokenHolderDetail`)
+#[wasm_bindgen(js_name = getTokenHolders)]
+pub async fn get_token_holders(
+    blockscout_base_url: String,
+    contract_address: String,
+    page: u64,
+    offset: u64,
+) -> Result<JsValue, JsValue> {
+    to_js(
+        async {
+            let url =
+                explorer::token_holders_url(&blockscout_base_url, &contract_address, page, offset)?;
+            let resp: RawResponse<TokenHolderDetail> = get_json(&url).await?;
+            Ok(resp.result)
+        }
+        .await,
+    )
+}
+
+/// builds a transaction from the JSON string of `Eip1559TransactionRequest` and the common fields
+/// (an object with the fields of `WalletConnectTxCommon`), returns an `UnsignedTx`
+/// (`{ transaction, rlp, sighash }`) to be signed by the wallet
+#[wasm_bindgen(js_name = buildTransaction)]
+pub fn build_transaction(
+    eip1559_transaction_request: String,
+    from_address: String,
+    common: JsValue,
+) -> Result<JsValue, JsValue> {
+    let common: TxCommon = serde_wasm_bindgen::from_value(common)?;
+    to_js(
+        explorer::parse_address(&from_address)
+            .and_then(|from| tx::build_transaction(&eip1559_transaction_request, from, &common)),
+    )
+}
+
+/// builds a contract transaction from the JSON string of `ContractAction`
+/// (the same as in `sign_contract_transaction` of the C++ bindings) and the common fields,
+/// returns an `UnsignedTx` (`{ transaction, rlp, sighash }`) to be signed by the wallet
+#[wasm_bindgen(js_name = buildContractTransaction)]
+pub async fn build_contract_transaction(
+    contract_action: String,
+    from_address: String,
+    common: JsValue,
+) -> Result<JsValue, JsValue> {
+    let common: TxCommon = serde_wasm_bindgen::from_value(common)?;
+    to_js(
+        async {
+            let action: ContractAction = serde_json::from_str(&contract_action)?;
+            let from = explorer::parse_address(&from_address)?;
+            tx::build_contract_transaction(action, from, &common).await
+        }
+        .await,
+    )
+}
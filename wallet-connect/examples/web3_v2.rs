@@ -3,7 +3,7 @@ use std::error::Error;
 use std::io::BufRead;
 async fn make_client(
     callback_sender: Option<tokio::sync::mpsc::UnboundedSender<String>>,
-) -> Result<Client, relay_client::Error> {
+) -> eyre::Result<Client> {
     let opts = ClientOptions {
         relay_server: "wss://relay.walletconnect.com".parse().expect("url"),
         project_id: std::env::args().skip(1).next().expect("project_id"),
@@ -0,0 +1,31 @@
+//! runs the WalletConnect 1.0 and 2.0 conformance scenarios against the reference wallets:
+//! `cargo run -p defi-wallet-connect --features interop --bin wc-interop`
+use defi_wallet_connect::interop::{v2, Fixture, MockBridge};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> eyre::Result<()> {
+    let bridge = MockBridge::start().await?;
+    let relay = v2::MockRelay::start().await?;
+    let mut results = vec![];
+    for (name, result) in Fixture::default().run_all(&bridge).await {
+        results.push((format!("1.0 {name}"), result));
+    }
+    for (name, result) in v2::Fixture::default().run_all(&relay).await {
+        results.push((format!("2.0 {name}"), result));
+    }
+    let mut failed = 0;
+    for (name, result) in &results {
+        match result {
+            Ok(()) => println!("ok      {name}"),
+            Err(e) => {
+                failed += 1;
+                println!("FAILED  {name}: {e:#}");
+            }
+        }
+    }
+    println!("{} passed, {failed} failed", results.len() - failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
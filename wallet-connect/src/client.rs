@@ -2,6 +2,8 @@
 mod core;
 /// The external options to create a client
 mod options;
+/// The capture of the decrypted messages and their replay (for debugging)
+pub mod replay;
 /// The wallet-connect session management
 pub mod session;
 /// The websocket connection management
@@ -12,10 +14,13 @@ use std::str::FromStr;
 
 use self::{
     core::{Connector, ConnectorError},
-    options::Options,
-    session::SessionInfo,
+    options::{Connection, Options},
+    session::{RedactedSessionInfo, SessionInfo},
+};
+use crate::{
+    hex,
+    protocol::{Metadata, RequestMetadata},
 };
-use crate::{hex, protocol::Metadata};
 use async_trait::async_trait;
 use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::{
@@ -56,6 +61,17 @@ impl Default for ClientChannelMessage {
     }
 }
 
+/// The health of the connection to the bridge server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStatus {
+    /// whether the websocket connection is open
+    pub connected: bool,
+    /// the round-trip time of the last answered websocket ping (none before the first one)
+    pub last_ping_rtt: Option<std::time::Duration>,
+    /// the number of the reconnections (e.g. when the app returned to the foreground)
+    pub reconnect_count: u64,
+}
+
 /// The WalletConnect 1.0 client
 /// (holds the middleware trait implementations for ethers)
 /// this is persistent and can be recovered from session-info-string.
@@ -82,6 +98,18 @@ impl Client {
         Client::with_options(Options::new(meta.into(), chain_id)).await
     }
 
+    /// Creates a new client from the provided metadata and chain id
+    /// connecting to the bridge server (instead of the default one)
+    pub async fn with_bridge(
+        meta: impl Into<Metadata>,
+        chain_id: Option<u64>,
+        bridge: url::Url,
+    ) -> Result<Self, ConnectorError> {
+        let mut options = Options::new(meta.into(), chain_id);
+        options.connection = Connection::Bridge(bridge);
+        Client::with_options(options).await
+    }
+
     /// Restore a new client from the provided options
     pub async fn restore(session_info: SessionInfo) -> Result<Self, ConnectorError> {
         Ok(Client {
@@ -96,12 +124,26 @@ impl Client {
         connection.get_session_info().await
     }
 
+    /// get current session info without the key and with the topics hashed
+    /// (for the logs or bug reports)
+    pub async fn get_session_info_redacted(&self) -> Result<RedactedSessionInfo, ConnectorError> {
+        Ok(self.get_session_info().await?.redacted())
+    }
+
     /// create qrcode from this string
     pub async fn get_connection_string(&self) -> Result<String, ConnectorError> {
         let connection = self.connection.read().await;
         Ok(connection.get_uri().await?.as_url().as_str().to_string())
     }
 
+    /// attach the metadata (e.g. an explanatory message) to the next request
+    /// that the wallet asks the user to approve (signing or sending);
+    /// the wallets that do not support it ignore it
+    pub async fn set_request_metadata(&self, metadata: Option<RequestMetadata>) {
+        let connection = self.connection.read().await;
+        connection.set_request_metadata(metadata);
+    }
+
     /// manual polling for session
     /// receive client state messages directly though channel
     /// refer to run_callback to create channel
@@ -120,13 +162,11 @@ impl Client {
         self.set_callback(sender);
 
         let join_handle = tokio::spawn(async move {
-            loop {
-                let message = receiver.recv().await;
-
-                if let Some(message) = message {
-                    mycallback(message)?;
-                }
+            // it ends when the client (the sender) is dropped
+            while let Some(message) = receiver.recv().await {
+                mycallback(message)?;
             }
+            Ok(())
         });
 
         Ok(join_handle)
@@ -144,7 +184,9 @@ impl Client {
     /// If successful, the returned value is the wallet's addresses and the chain ID.
     /// TODO: more specific error types than eyre
     pub async fn ensure_session(&mut self) -> Result<(Vec<Address>, u64), eyre::Error> {
-        let mut connection = self.connection.write().await;
+        // not exclusive, so that the app can reconnect while the session is pending
+        // (e.g. the user switched to the wallet app to approve it)
+        let connection = self.connection.read().await;
         if let Some(v) = &self.callback_channel {
             connection.set_callback(v.clone()).await;
         }
@@ -152,10 +194,60 @@ impl Client {
         connection.ensure_session().await
     }
 
+    /// to be called when the app goes to the background
+    /// (mobile OSes suspend its sockets): the keepalives are paused
+    pub async fn notify_app_background(&self) {
+        let connection = self.connection.read().await;
+        connection.pause_keepalive();
+    }
+
+    /// to be called when the app returns to the foreground:
+    /// it reconnects to the bridge server, receives the messages queued in the meantime
+    /// and resumes the keepalives
+    /// (the current session is sent to the callback as `Updated` if it is connected)
+    pub async fn notify_app_foreground(&self) -> Result<(), ConnectorError> {
+        let connection = self.connection.read().await;
+        if let Some(v) = &self.callback_channel {
+            connection.set_callback(v.clone()).await;
+        }
+        connection.reconnect().await
+    }
+
+    /// whether the connection to the bridge server was closed
+    pub async fn is_connection_closed(&self) -> bool {
+        let connection = self.connection.read().await;
+        connection.is_closed()
+    }
+
+    /// the health of the connection to the bridge server
+    pub async fn connection_status(&self) -> ConnectionStatus {
+        let connection = self.connection.read().await;
+        let (last_ping_rtt, reconnect_count) = connection.connection_stats();
+        ConnectionStatus {
+            connected: !connection.is_closed(),
+            last_ping_rtt,
+            reconnect_count,
+        }
+    }
+
+    /// enables (with an empty log) or disables the capture of the decrypted messages
+    /// exchanged with the wallet (the secrets are redacted)
+    pub async fn set_replay_capture(&self, enabled: bool) {
+        let connection = self.connection.read().await;
+        connection.set_replay_capture(enabled);
+    }
+
+    /// the captured messages (see `replay::replay`)
+    pub async fn replay_entries(&self) -> Vec<replay::ReplayEntry> {
+        let connection = self.connection.read().await;
+        connection.replay_entries()
+    }
+
     /// Send a request to sign a message as per https://eips.ethereum.org/EIPS/eip-1271
+    /// (the message can be a text or arbitrary bytes, the wallet applies the EIP-191 prefix)
     pub async fn personal_sign(
         &mut self,
-        message: &str,
+        message: impl AsRef<[u8]>,
         address: &Address,
     ) -> Result<Signature, ClientError> {
         let sig_str: String = self
@@ -173,6 +265,25 @@ impl Client {
             .context("failed to parse signature")
             .map_err(ClientError::Eyre)
     }
+
+    /// Send a request to sign a 32-byte digest without the EIP-191 prefix (`eth_sign`)
+    /// NOTE: some wallets do not support it or still apply the prefix
+    pub async fn eth_sign(
+        &mut self,
+        digest: &[u8; 32],
+        address: &Address,
+    ) -> Result<Signature, ClientError> {
+        let sig_str: String = self
+            .request(
+                "eth_sign",
+                vec![format!("{address:?}"), format!("0x{}", hex::encode(digest))],
+            )
+            .await?;
+
+        Signature::from_str(&sig_str)
+            .context("failed to parse signature")
+            .map_err(ClientError::Eyre)
+    }
 }
 
 /// Error thrown when sending an HTTP request
@@ -245,53 +356,26 @@ impl WCMiddleware<Provider<Client>> {
     pub fn with_sender(self, address: impl Into<Address>) -> Self {
         WCMiddleware(self.0.with_sender(address))
     }
-}
-
-/// The wrapper error type for `ethers` middleware-related issues
-#[derive(Error, Debug)]
-pub enum WCError<M: Middleware> {
-    #[error("{0}")]
-    MiddlewareError(M::Error),
-    #[error("client error: {0}")]
-    ClientError(ClientError),
-}
-
-impl<M: Middleware> MiddlewareError for WCError<M> {
-    type Inner = M::Error;
-    fn from_err(src: M::Error) -> Self {
-        WCError::MiddlewareError(src)
-    }
-    fn as_inner(&self) -> Option<&Self::Inner> {
-        match self {
-            WCError::MiddlewareError(e) => Some(e),
-            _ => None,
-        }
-    }
-}
-
-#[async_trait]
-impl Middleware for WCMiddleware<Provider<Client>> {
-    type Error = WCError<Provider<Client>>;
-    type Provider = Client;
-    type Inner = Provider<Client>;
-
-    fn inner(&self) -> &Provider<Client> {
-        &self.0
-    }
 
-    async fn sign_transaction(
+    /// signs the transaction via the wallet (`eth_signTransaction`), returns the signed
+    /// raw transaction as returned by the wallet (so that it's broadcast as the wallet signed it)
+    /// and its signature; the wallet's transaction must have the requested receiver,
+    /// value and data
+    pub async fn sign_transaction_raw(
         &self,
         tx: &TypedTransaction,
         from: Address,
-    ) -> Result<Signature, Self::Error> {
+    ) -> Result<(Bytes, Signature), WCError<Provider<Client>>> {
         let mut tx_obj = HashMap::new();
         tx_obj.insert("from", format!("{from:?}"));
+        let mut requested_to = None;
         if let Some(to) = tx.to() {
             let addr = match to {
                 NameOrAddress::Address(addr) => *addr,
                 NameOrAddress::Name(n) => self.resolve_name(n).await?,
             };
             tx_obj.insert("to", format!("{addr:?}"));
+            requested_to = Some(addr);
         }
         if let Some(data) = tx.data() {
             tx_obj.insert("data", format!("0x{}", hex::encode(data)));
@@ -327,23 +411,68 @@ impl Middleware for WCMiddleware<Provider<Client>> {
         }
 
         let first_byte = tx_rlp.as_raw()[0];
-        // TODO: check that the decoded request matches the typed transaction content here? or a new `sign_transaction` function that returns both request+signature?
-        if first_byte <= 0x7f {
-            let decoded_request = TypedTransaction::decode_signed(&tx_rlp);
-            decoded_request
-                .map(|x| x.1)
-                .map_err(|e| WCError::ClientError(ClientError::Eyre(eyre!(e))))
+        let decoded = if first_byte <= 0x7f {
+            TypedTransaction::decode_signed(&tx_rlp).map_err(|e| eyre!(e))
         } else if (0xc0..=0xfe).contains(&first_byte) {
-            let decoded_request = TransactionRequest::decode_signed_rlp(&tx_rlp);
-            decoded_request
-                .map(|x| x.1)
-                .map_err(|e| WCError::ClientError(ClientError::Eyre(eyre!(e))))
+            TransactionRequest::decode_signed_rlp(&tx_rlp)
+                .map(|(request, signature)| (TypedTransaction::Legacy(request), signature))
+                .map_err(|e| eyre!(e))
         } else {
-            Err(WCError::ClientError(ClientError::Eyre(eyre!(
-                "failed to decode transaction"
-            ))))
+            Err(eyre!("failed to decode transaction"))
+        };
+        let (signed, signature) =
+            decoded.map_err(|e| WCError::ClientError(ClientError::Eyre(e)))?;
+        let to = |tx: &TypedTransaction| tx.to().and_then(NameOrAddress::as_address).copied();
+        let data = |tx: &TypedTransaction| tx.data().cloned().unwrap_or_default();
+        let value = |tx: &TypedTransaction| tx.value().copied().unwrap_or_default();
+        if to(&signed) != requested_to || data(&signed) != data(tx) || value(&signed) != value(tx) {
+            return Err(WCError::ClientError(ClientError::Eyre(eyre!(
+                "the wallet signed a different transaction"
+            ))));
+        }
+        Ok((tx_bytes, signature))
+    }
+}
+
+/// The wrapper error type for `ethers` middleware-related issues
+#[derive(Error, Debug)]
+pub enum WCError<M: Middleware> {
+    #[error("{0}")]
+    MiddlewareError(M::Error),
+    #[error("client error: {0}")]
+    ClientError(ClientError),
+}
+
+impl<M: Middleware> MiddlewareError for WCError<M> {
+    type Inner = M::Error;
+    fn from_err(src: M::Error) -> Self {
+        WCError::MiddlewareError(src)
+    }
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            WCError::MiddlewareError(e) => Some(e),
+            _ => None,
         }
     }
+}
+
+#[async_trait]
+impl Middleware for WCMiddleware<Provider<Client>> {
+    type Error = WCError<Provider<Client>>;
+    type Provider = Client;
+    type Inner = Provider<Client>;
+
+    fn inner(&self) -> &Provider<Client> {
+        &self.0
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+        from: Address,
+    ) -> Result<Signature, Self::Error> {
+        Ok(self.sign_transaction_raw(tx, from).await?.1)
+    }
 
     async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
         &self,
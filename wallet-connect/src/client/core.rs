@@ -2,12 +2,13 @@
 //! Modifications Copyright (c) 2022, Cronos Labs (licensed under the Apache License, Version 2.0)
 use super::{
     options::{Connection, Options},
+    replay::{Direction, ReplayEntry, ReplayLog},
     session::Session,
     session::SessionInfo,
     socket::{MessageHandler, Socket},
 };
 use crate::client::ClientChannelMessage;
-use crate::protocol::Topic;
+use crate::protocol::{RequestMetadata, Topic};
 use crate::uri::Uri;
 use crate::ClientError;
 use async_trait::async_trait;
@@ -15,11 +16,15 @@ use dashmap::DashMap;
 use ethers::prelude::{Address, JsonRpcClient};
 use rand::Rng;
 use serde::{de::DeserializeOwned, Serialize};
-use std::sync::{atomic::AtomicBool, Arc};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
 use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::{oneshot, Mutex};
+use url::Url;
 
 /// This `Context` holds the wallet-connect client state
 #[derive(Debug)]
@@ -40,6 +45,25 @@ pub struct Context {
     /// When the response is received, the request is removed
     /// and the response is sent to the receiver via the one-shot channel.
     pub pending_requests: DashMap<u64, oneshot::Sender<serde_json::Value>>,
+    /// the metadata attached to the next request that needs the user approval
+    pub request_metadata: std::sync::Mutex<Option<RequestMetadata>>,
+    /// whether the keepalives are paused (the app is in the background)
+    pub keepalive_paused: AtomicBool,
+    /// the round-trip time of the last answered websocket ping
+    pub last_ping_rtt: std::sync::Mutex<Option<Duration>>,
+    /// the number of the reconnections to the bridge server
+    pub reconnect_count: AtomicU64,
+    /// the captured messages (none if the capture is disabled)
+    pub replay_log: std::sync::Mutex<Option<ReplayLog>>,
+}
+
+impl Context {
+    /// records the decrypted message if the capture is enabled
+    pub(crate) fn capture(&self, direction: Direction, topic: &Topic, payload: &[u8]) {
+        if let Some(log) = self.replay_log.lock().expect("replay log lock").as_mut() {
+            log.record(direction, &topic.to_string(), payload);
+        }
+    }
 }
 
 /// `SharedContext` holds the thread-safe reference to the wallet-connect client state
@@ -56,6 +80,11 @@ impl SharedContext {
             pending_requests_timeout: Duration::from_millis(60000),
             pending_requests_limit: 2,
             pending_requests: DashMap::new(),
+            request_metadata: std::sync::Mutex::new(None),
+            keepalive_paused: AtomicBool::new(false),
+            last_ping_rtt: std::sync::Mutex::new(None),
+            reconnect_count: AtomicU64::new(0),
+            replay_log: std::sync::Mutex::new(None),
         }))
     }
 }
@@ -67,6 +96,28 @@ pub struct Connector {
     socket: Socket,
     /// the client state
     context: SharedContext,
+    /// the handshake topic to subscribe to (when the connection is created from a URI
+    /// or restored)
+    handshake_topic: Option<Topic>,
+}
+
+/// how many times the connection is attempted when the app returns to the foreground
+const RECONNECT_ATTEMPTS: u32 = 3;
+/// the delay before the first repeated attempt (doubled after each attempt)
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(200);
+
+/// the websocket URL of the bridge server
+/// (WalletConnect bridge URLs are expected to be automatically converted
+/// from a `http(s)` to `ws(s)` protocol for the WebSocket connection)
+fn bridge_url(info: &SessionInfo) -> Result<Url, ConnectorError> {
+    let mut url = info.bridge.clone();
+    match url.scheme() {
+        "http" => url.set_scheme("ws").unwrap(),
+        "https" => url.set_scheme("wss").unwrap(),
+        "ws" | "wss" => {}
+        scheme => return Err(ConnectorError::BadScheme(scheme.into())),
+    }
+    Ok(url)
 }
 
 /// maximum is 9007199254740991 , 2^53 -1
@@ -88,14 +139,25 @@ impl Connector {
         Ok(session.info.clone())
     }
 
-    pub async fn set_callback(&mut self, myfunc: UnboundedSender<ClientChannelMessage>) {
+    /// sets the metadata attached to the next request that needs the user approval
+    /// (`None` clears it)
+    pub fn set_request_metadata(&self, metadata: Option<RequestMetadata>) {
+        *self
+            .context
+            .0
+            .request_metadata
+            .lock()
+            .expect("request metadata lock") = metadata;
+    }
+
+    pub async fn set_callback(&self, myfunc: UnboundedSender<ClientChannelMessage>) {
         self.context.0.session.lock().await.set_callback(myfunc);
     }
 
     /// This will return an existing session or create a new session.
     /// If successful, the returned value is the wallet's addresses and the chain ID.
     /// TODO: more specific error types than eyre
-    pub async fn ensure_session(&mut self) -> Result<(Vec<Address>, u64), eyre::Error> {
+    pub async fn ensure_session(&self) -> Result<(Vec<Address>, u64), eyre::Error> {
         let session = self.context.0.session.lock().await;
         if session.info.connected {
             Ok((
@@ -107,37 +169,128 @@ impl Connector {
             // no need to hold the session lock, hence this explicit drop
             drop(session);
             self.socket
-                .create_session(get_safe_random(), &mut self.context)
+                .create_session(get_safe_random(), &self.context)
                 .await
         }
     }
 
+    /// pauses the keepalives (when the app goes to the background,
+    /// as mobile OSes suspend its sockets)
+    pub fn pause_keepalive(&self) {
+        self.context
+            .0
+            .keepalive_paused
+            .store(true, Ordering::SeqCst);
+    }
+
+    /// reconnects to the bridge server (when the app returns to the foreground,
+    /// the previous connection is likely dead even if it was not closed yet),
+    /// subscribes to the topics again (so that the bridge server delivers
+    /// the messages queued in the meantime) and resumes the keepalives;
+    /// the current session is then sent to the callback (as `Updated`) if it is connected
+    pub async fn reconnect(&self) -> Result<(), ConnectorError> {
+        let (url, key, client_id) = {
+            let session = self.context.0.session.lock().await;
+            (
+                bridge_url(&session.info)?,
+                session.info.key.clone(),
+                session.info.client_id.clone(),
+            )
+        };
+        let mut backoff = RECONNECT_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            let handler = MessageHandler {
+                context: self.context.clone(),
+            };
+            match self
+                .socket
+                .reconnect(url.clone(), key.clone(), handler)
+                .await
+            {
+                Ok(()) => {
+                    self.context
+                        .0
+                        .reconnect_count
+                        .fetch_add(1, Ordering::SeqCst);
+                    break;
+                }
+                Err(e) if attempt >= RECONNECT_ATTEMPTS => return Err(e.into()),
+                Err(_) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+        self.socket.subscribe(client_id).await?;
+        if let Some(topic) = &self.handshake_topic {
+            self.socket.subscribe(topic.clone()).await?;
+        }
+        self.context
+            .0
+            .keepalive_paused
+            .store(false, Ordering::SeqCst);
+        let session = self.context.0.session.lock().await;
+        if session.info.connected {
+            session.event_updated();
+        }
+        Ok(())
+    }
+
+    /// whether the websocket connection was closed
+    pub fn is_closed(&self) -> bool {
+        self.socket.is_closed()
+    }
+
+    /// enables (with an empty log) or disables the capture of the messages
+    pub fn set_replay_capture(&self, enabled: bool) {
+        *self.context.0.replay_log.lock().expect("replay log lock") =
+            enabled.then(ReplayLog::default);
+    }
+
+    /// the captured messages (empty if the capture is disabled)
+    pub fn replay_entries(&self) -> Vec<ReplayEntry> {
+        self.context
+            .0
+            .replay_log
+            .lock()
+            .expect("replay log lock")
+            .as_ref()
+            .map(ReplayLog::entries)
+            .unwrap_or_default()
+    }
+
+    /// the round-trip time of the last answered ping and the number of the reconnections
+    pub fn connection_stats(&self) -> (Option<Duration>, u64) {
+        let context = &self.context.0;
+        (
+            *context.last_ping_rtt.lock().expect("ping rtt lock"),
+            context.reconnect_count.load(Ordering::SeqCst),
+        )
+    }
+
     pub async fn new_client(
         handshake_topic: Option<Topic>,
         session: Session,
     ) -> Result<Self, ConnectorError> {
         let client_id = session.info.client_id.clone();
-        // NOTE: WalletConnect bridge URLs are expected to be automatically
-        // converted from a `http(s)` to `ws(s)` protocol for the WebSocket
-        // connection.
-        let mut url = session.info.bridge.clone();
-        match url.scheme() {
-            "http" => url.set_scheme("ws").unwrap(),
-            "https" => url.set_scheme("wss").unwrap(),
-            "ws" | "wss" => {}
-            scheme => return Err(ConnectorError::BadScheme(scheme.into())),
-        }
+        let url = bridge_url(&session.info)?;
         let key = session.info.key.clone();
         let context = SharedContext::new(session);
         let handler = MessageHandler {
             context: context.clone(),
         };
-        let mut socket = Socket::connect(url, key, handler).await?;
+        let socket = Socket::connect(url, key, handler).await?;
         socket.subscribe(client_id.clone()).await?;
-        if let Some(topic) = handshake_topic {
-            socket.subscribe(topic).await?;
+        if let Some(topic) = &handshake_topic {
+            socket.subscribe(topic.clone()).await?;
         }
-        Ok(Self { socket, context })
+        Ok(Self {
+            socket,
+            context,
+            handshake_topic,
+        })
     }
 
     pub async fn restore(session_info: SessionInfo) -> Result<Self, ConnectorError> {
@@ -195,8 +348,27 @@ pub enum ConnectorError {
 
 #[cfg(test)]
 mod test {
+    use super::bridge_url;
+    use crate::client::options::Options;
     use crate::{uri::Uri, SocketMessage};
 
+    #[test]
+    pub fn test_bridge_url() {
+        let meta = crate::Metadata {
+            description: "test".into(),
+            url: "http://localhost:8080/".parse().unwrap(),
+            icons: vec![],
+            name: "test".into(),
+        };
+        let mut session = Options::new(meta, None).create_session();
+        assert_eq!(
+            bridge_url(&session.info).unwrap().as_str(),
+            "wss://l.bridge.walletconnect.org/"
+        );
+        session.info.bridge = "ftp://localhost".parse().unwrap();
+        assert!(bridge_url(&session.info).is_err());
+    }
+
     #[test]
     pub fn test_payloads() {
         let u = "wc:c0254d9e-b523-4b7e-845a-e457abe05df4@1?bridge=https%3A%2F%2Fl.bridge.walletconnect.org&key=f674df12094c46f96f41fd6a6ec7702eadb41f706480369d5be9729716147807";
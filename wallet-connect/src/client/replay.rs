@@ -0,0 +1,282 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use super::core::SharedContext;
+use super::session::Session;
+use super::socket::MessageHandler;
+use crate::protocol::Topic;
+
+/// the most captured messages (the oldest are dropped)
+const MAX_ENTRIES: usize = 1000;
+/// the fields whose values are replaced by `REDACTED`: the ones ending with "key"
+/// (e.g. `symKey`, `sessionKey`, `privateKey`) or containing one of the words
+/// (compared in lowercase)
+const SECRET_WORDS: &[&str] = &[
+    "secret",
+    "password",
+    "mnemonic",
+    "seed",
+    "token",
+    "authorization",
+];
+const REDACTED: &str = "[redacted]";
+
+/// whether the value of the field is a secret
+fn is_secret(field: &str) -> bool {
+    let field = field.to_lowercase();
+    field.ends_with("key") || SECRET_WORDS.iter().any(|word| field.contains(word))
+}
+
+/// Whether the message was sent to or received from the wallet
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+/// A decrypted message exchanged with the wallet via the bridge server or the relay
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReplayEntry {
+    /// the order of the message in the capture
+    pub seq: u64,
+    pub direction: Direction,
+    /// the hash of the topic (see `Topic::hashed`): the messages of the same topic
+    /// are recognizable, but the topic can't be subscribed to
+    pub topic: Topic,
+    /// the JSON-RPC request or response (the secrets are redacted)
+    pub message: Value,
+}
+
+/// The captured messages (in the order they were sent or received)
+#[derive(Debug, Default)]
+pub struct ReplayLog {
+    entries: VecDeque<ReplayEntry>,
+    next_seq: u64,
+}
+
+/// replaces the values of the secret fields in the message
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (field, value) in map.iter_mut() {
+                if is_secret(field) {
+                    *value = Value::String(REDACTED.to_owned());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+impl ReplayLog {
+    /// records the decrypted payload of the topic (a WalletConnect 1.0 or 2.0 one);
+    /// the payload is kept as a string if it isn't JSON
+    pub(crate) fn record(&mut self, direction: Direction, topic: &str, payload: &[u8]) {
+        let mut message = serde_json::from_slice(payload)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(payload).into_owned()));
+        redact(&mut message);
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(ReplayEntry {
+            seq: self.next_seq,
+            direction,
+            topic: Topic::hash_of(topic),
+            message,
+        });
+        self.next_seq += 1;
+    }
+
+    /// the captured messages (oldest first)
+    pub fn entries(&self) -> Vec<ReplayEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
+
+/// the entries as JSON Lines (one entry per line)
+pub fn to_json_lines(entries: &[ReplayEntry]) -> eyre::Result<String> {
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+/// loads the entries of a replay log in JSON Lines (the empty lines are skipped)
+pub fn from_json_lines(lines: &str) -> eyre::Result<Vec<ReplayEntry>> {
+    lines
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+/// The client state after a replay
+#[derive(Debug)]
+pub struct Replay {
+    /// the responses to the outgoing requests, by request id
+    /// (the requests without a response aren't included)
+    pub responses: Vec<(u64, Value)>,
+    /// the session updated by the incoming messages
+    pub session: Session,
+}
+
+/// replays the captured messages against the client state machine (e.g. in the tests
+/// of the wallet interoperability bugs): the outgoing requests are awaiting a response,
+/// and the incoming messages are processed as if they were received from the wallet
+pub async fn replay(entries: &[ReplayEntry], session: Session) -> Replay {
+    let context = SharedContext::new(session);
+    let handler = MessageHandler {
+        context: context.clone(),
+    };
+    let mut pending = vec![];
+    for entry in entries {
+        match entry.direction {
+            Direction::Outgoing => {
+                if let Some(id) = entry.message["id"].as_u64() {
+                    let (tx, rx) = oneshot::channel();
+                    context.0.pending_requests.insert(id, tx);
+                    pending.push((id, rx));
+                }
+            }
+            Direction::Incoming => {
+                if let Ok(payload) = serde_json::to_vec(&entry.message) {
+                    handler.handle(entry.topic.clone(), payload).await;
+                }
+            }
+        }
+    }
+    let responses = pending
+        .into_iter()
+        .filter_map(|(id, mut rx)| rx.try_recv().ok().map(|response| (id, response)))
+        .collect();
+    let session = context.0.session.lock().await.clone();
+    Replay { responses, session }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::session::SessionInfo;
+    use crate::crypto::Key;
+    use crate::protocol::Metadata;
+    use serde_json::json;
+
+    fn session() -> Session {
+        Session {
+            info: SessionInfo {
+                connected: true,
+                accounts: vec![],
+                chain_id: Some(338),
+                bridge: "https://l.bridge.walletconnect.org".parse().unwrap(),
+                key: Key::random(),
+                client_id: Topic::new(),
+                client_meta: Metadata {
+                    description: "replay".to_owned(),
+                    url: "https://example.test".parse().unwrap(),
+                    icons: vec![],
+                    name: "replay".to_owned(),
+                },
+                peer_id: None,
+                peer_meta: None,
+                handshake_topic: Topic::new(),
+            },
+            callback_channel: None,
+        }
+    }
+
+    #[test]
+    fn replay_log_redacts_secrets() {
+        let mut log = ReplayLog::default();
+        let topic = Topic::new();
+        let request = json!({
+            "id": 1,
+            "method": "custom",
+            "params": [{
+                "key": "abc",
+                "nested": {"privateKey": "0x01", "to": "0x02"},
+                "relay": {"symKey": "0a0b", "sessionKey": "0c0d", "accessToken": "t"},
+            }],
+        });
+        log.record(
+            Direction::Outgoing,
+            &topic.to_string(),
+            request.to_string().as_bytes(),
+        );
+        log.record(Direction::Incoming, &topic.to_string(), b"not json");
+        let entries = log.entries();
+        let params = &entries[0].message["params"][0];
+        assert_eq!(params["key"], REDACTED);
+        assert_eq!(params["nested"]["privateKey"], REDACTED);
+        assert_eq!(params["nested"]["to"], "0x02");
+        assert_eq!(params["relay"]["symKey"], REDACTED);
+        assert_eq!(params["relay"]["sessionKey"], REDACTED);
+        assert_eq!(params["relay"]["accessToken"], REDACTED);
+        assert_eq!(entries[1].seq, 1);
+        assert_eq!(entries[1].message, "not json");
+        // the topic isn't written, only its hash
+        assert_eq!(entries[0].topic, topic.hashed());
+        assert_ne!(entries[0].topic, topic);
+
+        let lines = to_json_lines(&entries).unwrap();
+        assert!(!lines.contains(&topic.to_string()));
+        assert_eq!(from_json_lines(&lines).unwrap(), entries);
+
+        // the oldest messages are dropped
+        for _ in 0..MAX_ENTRIES {
+            log.record(Direction::Incoming, &topic.to_string(), b"{}");
+        }
+        let entries = log.entries();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].seq, 2);
+    }
+
+    #[tokio::test]
+    async fn replay_messages() {
+        let mut log = ReplayLog::default();
+        let topic = Topic::new();
+        let messages = [
+            (
+                Direction::Outgoing,
+                json!({"id": 7, "jsonrpc": "2.0", "method": "personal_sign", "params": ["0x00"]}),
+            ),
+            (
+                Direction::Incoming,
+                json!({"id": 7, "jsonrpc": "2.0", "result": "0x1234"}),
+            ),
+            (
+                Direction::Incoming,
+                json!({
+                    "id": 8,
+                    "jsonrpc": "2.0",
+                    "method": "wc_sessionUpdate",
+                    "params": [{
+                        "approved": true,
+                        "accounts": ["0x0000000000000000000000000000000000000001"],
+                        "chainId": 25,
+                    }],
+                }),
+            ),
+        ];
+        for (direction, message) in messages {
+            log.record(
+                direction,
+                &topic.to_string(),
+                message.to_string().as_bytes(),
+            );
+        }
+        let entries = from_json_lines(&to_json_lines(&log.entries()).unwrap()).unwrap();
+        let replay = replay(&entries, session()).await;
+        assert_eq!(replay.responses, vec![(7, json!("0x1234"))]);
+        assert_eq!(replay.session.info.chain_id, Some(25));
+        assert_eq!(replay.session.info.accounts.len(), 1);
+    }
+}
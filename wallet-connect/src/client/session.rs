@@ -2,6 +2,7 @@
 //! Modifications Copyright (c) 2022, Cronos Labs (licensed under the Apache License, Version 2.0)
 use crate::client::{ClientChannelMessage, ClientChannelMessageType};
 use crate::crypto::Key;
+use crate::hex;
 use crate::protocol::{
     Metadata, PeerMetadata, SessionParams, SessionRequest, SessionUpdate, Topic,
 };
@@ -9,6 +10,7 @@ use crate::uri::Uri;
 use ethers::prelude::Address;
 use secrecy::ExposeSecret;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::sync::mpsc::UnboundedSender;
 use url::form_urlencoded::Serializer;
 use url::Url;
@@ -53,6 +55,44 @@ impl SessionInfo {
         ))
         .expect("WalletConnect URIs from sessions are always valid")
     }
+
+    /// the session info without the key and with the topics replaced by their hashes
+    /// (e.g. to be attached to the bug reports)
+    pub fn redacted(&self) -> RedactedSessionInfo {
+        RedactedSessionInfo {
+            connected: self.connected,
+            accounts: self.accounts.clone(),
+            chain_id: self.chain_id,
+            bridge: self.bridge.clone(),
+            key_hash: hex::encode(Sha256::digest(self.key.as_ref())),
+            client_id: self.client_id.hashed(),
+            client_meta: self.client_meta.clone(),
+            peer_id: self.peer_id.as_ref().map(Topic::hashed),
+            peer_meta: self.peer_meta.clone(),
+            handshake_topic: self.handshake_topic.hashed(),
+        }
+    }
+}
+
+/// The WalletConnect 1.0 session information without its secrets
+/// (it can't be restored: the key is left out)
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactedSessionInfo {
+    pub connected: bool,
+    pub accounts: Vec<Address>,
+    pub chain_id: Option<u64>,
+    pub bridge: Url,
+    /// the hash of the key (the same sessions are still recognizable)
+    pub key_hash: String,
+    /// the hash of the client's ID
+    pub client_id: Topic,
+    pub client_meta: Metadata,
+    /// the hash of the wallet's ID
+    pub peer_id: Option<Topic>,
+    pub peer_meta: Option<PeerMetadata>,
+    /// the hash of the one-time request ID
+    pub handshake_topic: Topic,
 }
 
 #[derive(Debug, Clone)]
@@ -188,4 +228,38 @@ mod tests {
         let deserialized = serde_json::from_str(&serialized).unwrap();
         assert_eq!(topic, deserialized);
     }
+
+    #[test]
+    fn session_info_redacted() {
+        let info = SessionInfo {
+            connected: true,
+            accounts: vec![Address::repeat_byte(1)],
+            chain_id: Some(25),
+            bridge: "https://l.bridge.walletconnect.org".parse().unwrap(),
+            key: Key::random(),
+            client_id: Topic::new(),
+            client_meta: Metadata {
+                description: "the game".to_owned(),
+                url: "https://game.example.test".parse().unwrap(),
+                icons: vec![],
+                name: "Game".to_owned(),
+            },
+            peer_id: Some(Topic::new()),
+            peer_meta: None,
+            handshake_topic: Topic::new(),
+        };
+        let redacted = info.redacted();
+        assert_ne!(redacted.client_id, info.client_id);
+        assert_ne!(redacted.peer_id, info.peer_id);
+        assert_ne!(redacted.handshake_topic, info.handshake_topic);
+        assert_eq!(redacted.accounts, info.accounts);
+        // the hashes are the same for the same session
+        assert_eq!(redacted.client_id, info.redacted().client_id);
+        assert_eq!(redacted.key_hash, info.redacted().key_hash);
+        // and it can't be restored
+        let json = serde_json::to_string(&redacted).unwrap();
+        assert!(serde_json::from_str::<SessionInfo>(&json).is_err());
+        assert!(!json.contains(info.key.display().expose_secret()));
+        assert!(!json.contains(&info.client_id.to_string()));
+    }
 }
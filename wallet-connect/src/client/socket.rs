@@ -1,6 +1,8 @@
 //! Copyright (c) 2021 HIHAHEHO Studio (licensed under the Apache License, Version 2.0)
 //! Modifications Copyright (c) 2022, Cronos Labs (licensed under the Apache License, Version 2.0)
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use ethers::prelude::Address;
 use futures::{future, SinkExt, TryStreamExt};
@@ -21,22 +23,54 @@ use url::Url;
 pub use wasm::*;
 
 use super::core::SharedContext;
+use super::replay::Direction;
 use crate::{
     crypto::Key,
     protocol::{SocketMessage, SocketMessageKind, Topic},
-    BridgeServerMsg, Request,
+    BridgeServerMsg, Request, USER_APPROVAL_METHODS,
 };
 use eyre::{eyre, Context};
 
+/// how often the subscription is repeated to keep the connection alive
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A websocket frame of the bridge server connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// the data of a message
+    Data(Vec<u8>),
+    /// a ping (it measures the round-trip time)
+    Ping(Vec<u8>),
+    /// the answer of a ping
+    Pong(Vec<u8>),
+}
+
 /// This structure holds the websocket connection
+/// (it can be replaced by `reconnect`)
 #[derive(Debug)]
 pub struct Socket {
+    connection: std::sync::RwLock<SocketConnection>,
+}
+
+/// the tasks of one websocket connection
+#[derive(Debug)]
+struct SocketConnection {
     /// queue for messages to be sent to the bridge server
-    sender: UnboundedSender<(Option<u64>, Vec<u8>)>,
+    sender: UnboundedSender<(Option<u64>, Frame)>,
     /// the handle of the task that writes on the websocket connection
-    _write_handle: JoinHandle<()>,
+    write_handle: JoinHandle<()>,
     /// the handle of the task that reads on the websocket connection
-    _read_handle: JoinHandle<()>,
+    read_handle: JoinHandle<()>,
+    /// the handle of the task that keeps the connection alive
+    keepalive_handle: JoinHandle<()>,
+}
+
+impl SocketConnection {
+    fn close(&self) {
+        self.keepalive_handle.abort();
+        self.read_handle.abort();
+        self.write_handle.abort();
+    }
 }
 
 /// A helper wrapper for processing the received messages
@@ -71,6 +105,15 @@ impl MessageHandler {
     }
 }
 
+/// sends a websocket ping (its pong measures the round-trip time)
+fn ping(
+    sender: &UnboundedSender<(Option<u64>, Frame)>,
+    sent_at: &std::sync::Mutex<Option<Instant>>,
+) -> bool {
+    *sent_at.lock().expect("ping lock") = Some(Instant::now());
+    sender.send((None, Frame::Ping(vec![]))).is_ok()
+}
+
 /// returns a topic and the decrypted payload
 fn check_socket_msg(mmsg: Vec<u8>, key: &Key) -> Option<(Topic, Vec<u8>)> {
     match serde_json::from_slice::<SocketMessage>(&mmsg) {
@@ -89,13 +132,24 @@ fn check_socket_msg(mmsg: Vec<u8>, key: &Key) -> Option<(Topic, Vec<u8>)> {
 }
 
 impl Socket {
+    fn sender(&self) -> UnboundedSender<(Option<u64>, Frame)> {
+        self.connection
+            .read()
+            .expect("socket connection lock")
+            .sender
+            .clone()
+    }
+
     fn send_socket_msg(
         &self,
         context: &SharedContext,
         id: u64,
         msg: SocketMessage,
     ) -> eyre::Result<()> {
-        if let Err(_e) = self.sender.send((Some(id), serde_json::to_vec(&msg)?)) {
+        if let Err(_e) = self
+            .sender()
+            .send((Some(id), Frame::Data(serde_json::to_vec(&msg)?)))
+        {
             // not to let the requester to wait forever
             const ERROR_MSG: &str = "\"Failed to send message to the queue\"";
             if let Some((_id, sender)) = context.0.pending_requests.remove(&id) {
@@ -130,10 +184,25 @@ impl Socket {
             .clone()
             .unwrap_or_else(|| session.info.handshake_topic.clone());
         let key = &session.info.key;
+        let metadata = if USER_APPROVAL_METHODS.contains(&method) {
+            context
+                .0
+                .request_metadata
+                .lock()
+                .expect("request metadata lock")
+                .take()
+        } else {
+            None
+        };
+        let request =
+            serde_json::to_string(&Request::new(id, method, params).with_metadata(metadata))?;
+        context
+            .0
+            .capture(Direction::Outgoing, &topic, request.as_bytes());
         let message = SocketMessage {
             kind: SocketMessageKind::Pub,
             topic,
-            payload: Some(key.seal(serde_json::to_string(&Request::new(id, method, params))?)),
+            payload: Some(key.seal(request)),
             silent: true,
         };
         drop(session);
@@ -181,9 +250,9 @@ impl Socket {
     /// attempts to create a session with the external wallet,
     /// and returns the wallet's addresses and chain ID (if successful)
     pub async fn create_session(
-        &mut self,
+        &self,
         id: u64,
-        context: &mut SharedContext,
+        context: &SharedContext,
     ) -> eyre::Result<(Vec<Address>, u64)> {
         let session = context.0.session.lock().await;
         if session.info.connected {
@@ -202,14 +271,15 @@ impl Socket {
         let topic = session.info.handshake_topic.clone();
         let key = &session.info.key;
         let session_req = session.request();
+        let request =
+            serde_json::to_string(&Request::new(id, "wc_sessionRequest", vec![session_req]))?;
+        context
+            .0
+            .capture(Direction::Outgoing, &topic, request.as_bytes());
         let message = SocketMessage {
             kind: SocketMessageKind::Pub,
             topic,
-            payload: Some(key.seal(serde_json::to_string(&Request::new(
-                id,
-                "wc_sessionRequest",
-                vec![session_req],
-            ))?)),
+            payload: Some(key.seal(request)),
             silent: true,
         };
         drop(session);
@@ -232,35 +302,112 @@ impl Socket {
     }
 
     /// sends a subscription for the given topic
-    pub async fn subscribe(&mut self, topic: Topic) -> eyre::Result<()> {
-        let msg = SocketMessage {
-            kind: SocketMessageKind::Sub,
-            topic,
-            payload: None,
-            silent: true,
-        };
-        let payload = serde_json::to_vec(&msg)?;
-        self.sender.send((None, payload))?;
+    pub async fn subscribe(&self, topic: Topic) -> eyre::Result<()> {
+        self.sender()
+            .send((None, Frame::Data(subscription(topic)?)))?;
         Ok(())
     }
 
     /// connects to the bridge server via a websocket
     /// and starts the send/receive tasks
-    /// TODO: handle reconnections?
     pub async fn connect(url: Url, key: Key, handler: MessageHandler) -> eyre::Result<Self> {
+        Ok(Self {
+            connection: std::sync::RwLock::new(SocketConnection::open(url, key, handler).await?),
+        })
+    }
+
+    /// replaces the websocket connection with a new one
+    /// (the previous one is closed; the topics need to be subscribed again)
+    pub async fn reconnect(&self, url: Url, key: Key, handler: MessageHandler) -> eyre::Result<()> {
+        let connection = SocketConnection::open(url, key, handler).await?;
+        let previous = std::mem::replace(
+            &mut *self.connection.write().expect("socket connection lock"),
+            connection,
+        );
+        previous.close();
+        Ok(())
+    }
+
+    /// whether the websocket connection was closed (e.g. by the bridge server)
+    pub fn is_closed(&self) -> bool {
+        self.connection
+            .read()
+            .expect("socket connection lock")
+            .read_handle
+            .is_finished()
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        if let Ok(connection) = self.connection.get_mut() {
+            connection.close();
+        }
+    }
+}
+
+/// the serialized subscription for the given topic
+fn subscription(topic: Topic) -> eyre::Result<Vec<u8>> {
+    let msg = SocketMessage {
+        kind: SocketMessageKind::Sub,
+        topic,
+        payload: None,
+        silent: true,
+    };
+    Ok(serde_json::to_vec(&msg)?)
+}
+
+impl SocketConnection {
+    async fn open(url: Url, key: Key, handler: MessageHandler) -> eyre::Result<Self> {
         let (mut tx, rx) = connect(url).await?.split();
-        let (sender, mut receiver) = unbounded_channel::<(Option<u64>, Vec<u8>)>();
+        let (sender, mut receiver) = unbounded_channel::<(Option<u64>, Frame)>();
         let sender_out = sender.clone();
+        let keepalive_sender = sender.clone();
         let context = handler.context.clone();
+        let keepalive_context = handler.context.clone();
+        let ping_context = handler.context.clone();
+        let response_key = key.clone();
+        // when the last ping was sent
+        let ping_sent_at = Arc::new(std::sync::Mutex::new(None::<Instant>));
+        let keepalive_ping_sent_at = ping_sent_at.clone();
 
         // a task for reading from the websocket connection, decrypting the data
         // and sending them as responses to the previous requests by the message handler
         let reader = tokio::spawn(async move {
             let _ = rx
-                .try_filter_map(|mmsg| future::ok(check_socket_msg(mmsg, &key)))
+                .try_filter_map(|frame| {
+                    future::ok(match frame {
+                        Frame::Data(mmsg) => {
+                            crate::usage::record_received(mmsg.len());
+                            check_socket_msg(mmsg, &key)
+                        }
+                        Frame::Pong(_) => {
+                            let sent_at = ping_sent_at.lock().expect("ping lock").take();
+                            if let Some(sent_at) = sent_at {
+                                *ping_context.0.last_ping_rtt.lock().expect("ping rtt lock") =
+                                    Some(sent_at.elapsed());
+                            }
+                            None
+                        }
+                        Frame::Ping(_) => None,
+                    })
+                })
                 .try_for_each(|(topic, decrypted)| async {
+                    handler
+                        .context
+                        .0
+                        .capture(Direction::Incoming, &topic, &decrypted);
                     if let Some(resp) = handler.handle(topic, decrypted).await {
-                        let _ = sender.send((None, resp));
+                        // the response is captured as it's decrypted by the wallet
+                        if let Some((topic, decrypted)) =
+                            check_socket_msg(resp.clone(), &response_key)
+                        {
+                            handler
+                                .context
+                                .0
+                                .capture(Direction::Outgoing, &topic, &decrypted);
+                        }
+                        let _ = sender.send((None, Frame::Data(resp)));
                     }
                     Ok(())
                 })
@@ -269,6 +416,9 @@ impl Socket {
         // a task for sending the messages to the bridge server
         let writer = tokio::spawn(async move {
             while let Some((mid, x)) = receiver.recv().await {
+                if let Frame::Data(data) = &x {
+                    crate::usage::record_sent(data.len());
+                }
                 if let (Err(_), Some(id)) = (tx.send(x).await, mid) {
                     // not to let the requester to wait forever
                     const ERROR_MSG: &str = "\"Failed to send message to the bridge server\"";
@@ -278,10 +428,42 @@ impl Socket {
                 }
             }
         });
+        // a task for keeping the connection alive (the bridge server has no ping message,
+        // so the client's subscription is repeated); it's paused when the app is in the background;
+        // the websocket pings measure the round-trip time
+        let keepalive = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(KEEPALIVE_INTERVAL);
+            // the first tick completes immediately
+            interval.tick().await;
+            if !ping(&keepalive_sender, &keepalive_ping_sent_at) {
+                return;
+            }
+            loop {
+                interval.tick().await;
+                if keepalive_context.0.keepalive_paused.load(Ordering::SeqCst) {
+                    continue;
+                }
+                let client_id = keepalive_context
+                    .0
+                    .session
+                    .lock()
+                    .await
+                    .info
+                    .client_id
+                    .clone();
+                let sent = subscription(client_id)
+                    .map(|payload| keepalive_sender.send((None, Frame::Data(payload))).is_ok())
+                    .unwrap_or_default();
+                if !sent || !ping(&keepalive_sender, &keepalive_ping_sent_at) {
+                    break;
+                }
+            }
+        });
         Ok(Self {
             sender: sender_out,
-            _write_handle: writer,
-            _read_handle: reader,
+            write_handle: writer,
+            read_handle: reader,
+            keepalive_handle: keepalive,
         })
     }
 }
@@ -312,35 +494,53 @@ pub enum SinkError {
 #[cfg(not(target_arch = "wasm32"))]
 mod native {
 
-    use tokio_tungstenite::connect_async;
+    use tokio::net::TcpStream;
     use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::{
+        client_async_tls_with_config, connect_async_tls_with_config, Connector,
+    };
     use url::Url;
 
-    use super::{SinkError, WebSocketClient};
+    use super::{Frame, SinkError, WebSocketClient};
 
     use eyre::Result;
     use futures::future::{ready, Ready};
     use futures::{prelude::*, Sink, Stream};
 
-    type Bytes = Vec<u8>;
-
     /// connects using `tokio_tungstenite` to the bridge server
     /// and returns the writer and reader streams wrapped in a struct
     pub async fn connect<'a>(
         addr: Url,
     ) -> Result<
         WebSocketClient<
-            impl Sink<Bytes, Error = SinkError> + Send + Sync + Unpin,
-            impl Stream<Item = Result<Bytes>> + Send + Sync + Unpin,
+            impl Sink<Frame, Error = SinkError> + Send + Sync + Unpin,
+            impl Stream<Item = Result<Frame>> + Send + Sync + Unpin,
         >,
     > {
-        let (stream, _response) = connect_async(addr.as_ref()).await?;
+        // custom root certificates or pinning if set via `tls::set_tls_options`
+        let connector = crate::tls::tls_config().map(Connector::Rustls);
+        let (stream, _response) = match crate::dns::resolve_override(&addr) {
+            Some(socket_addrs) => {
+                let tcp = TcpStream::connect(&socket_addrs[..]).await?;
+                client_async_tls_with_config(addr.as_ref(), tcp, None, connector).await?
+            }
+            None => connect_async_tls_with_config(addr.as_ref(), None, connector).await?,
+        };
         let (tx, rx) = stream.split();
-        let rx = rx.map(|message| -> Result<Bytes> { Ok(message?.into_data()) });
+        let rx = rx.map(|message| -> Result<Frame> {
+            Ok(match message? {
+                Message::Pong(payload) => Frame::Pong(payload),
+                message => Frame::Data(message.into_data()),
+            })
+        });
         let tx = tx
             .sink_map_err(|err| SinkError::Send(err.to_string()))
-            .with(|bytes: Bytes| -> Ready<Result<Message, SinkError>> {
-                ready(Ok(Message::binary(bytes)))
+            .with(|frame: Frame| -> Ready<Result<Message, SinkError>> {
+                ready(Ok(match frame {
+                    Frame::Data(bytes) => Message::binary(bytes),
+                    Frame::Ping(payload) => Message::Ping(payload),
+                    Frame::Pong(payload) => Message::Pong(payload),
+                }))
             });
 
         Ok(WebSocketClient { tx, rx })
@@ -356,28 +556,30 @@ mod wasm {
     use url::Url;
     use ws_stream_wasm::{WsMessage, WsMeta};
 
-    use crate::{SinkError, WebSocketClient};
+    use crate::{Frame, SinkError, WebSocketClient};
     use eyre::Result;
-    use futures::future::{ready, Ready};
     use futures::{prelude::*, Sink, Stream};
 
-    type Bytes = Vec<u8>;
-
     pub async fn connect<'a>(
         addr: Url,
     ) -> Result<
         WebSocketClient<
-            impl Sink<Bytes, Error = SinkError> + Send + Sync + Unpin,
-            impl Stream<Item = Result<Bytes>> + Send + Sync + Unpin,
+            impl Sink<Frame, Error = SinkError> + Send + Sync + Unpin,
+            impl Stream<Item = Result<Frame>> + Send + Sync + Unpin,
         >,
     > {
         let (_, wsio) = WsMeta::connect(addr.as_ref(), None).await?;
         let (tx, rx) = wsio.split();
-        let rx = rx.map(|message| -> Result<Bytes> { Ok(message.into()) });
+        let rx = rx.map(|message| -> Result<Frame> { Ok(Frame::Data(message.into())) });
+        // the browsers don't send the pings of the pages
         let tx = tx
             .sink_map_err(|err| SinkError::Send(err.to_string()))
-            .with(|bytes: Bytes| -> Ready<Result<WsMessage, SinkError>> {
-                ready(Ok(WsMessage::Binary(bytes)))
+            .with_flat_map(|frame: Frame| {
+                let message = match frame {
+                    Frame::Data(bytes) => Some(Ok(WsMessage::Binary(bytes))),
+                    Frame::Ping(_) | Frame::Pong(_) => None,
+                };
+                stream::iter(message)
             });
 
         Ok(WebSocketClient { tx, rx })
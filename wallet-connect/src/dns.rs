@@ -0,0 +1,71 @@
+//! Static host overrides for the platforms (consoles, CI) with restricted resolvers
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::RwLock;
+
+use url::Url;
+
+/// the overrides set via `set_host_overrides`
+static HOST_OVERRIDES: RwLock<BTreeMap<String, Vec<IpAddr>>> = RwLock::new(BTreeMap::new());
+
+/// replaces the host overrides: the connections to these hosts will use
+/// the given IP addresses (IPv4 or IPv6) instead of the system resolver
+pub fn set_host_overrides(overrides: BTreeMap<String, Vec<IpAddr>>) {
+    let overrides = overrides
+        .into_iter()
+        .filter(|(_, addresses)| !addresses.is_empty())
+        .map(|(host, addresses)| (host.to_lowercase(), addresses))
+        .collect();
+    *HOST_OVERRIDES.write().expect("host overrides lock") = overrides;
+}
+
+/// returns the current host overrides
+pub fn host_overrides() -> BTreeMap<String, Vec<IpAddr>> {
+    HOST_OVERRIDES.read().expect("host overrides lock").clone()
+}
+
+/// returns the socket addresses for the url's host if it is overridden
+pub(crate) fn resolve_override(url: &Url) -> Option<Vec<SocketAddr>> {
+    let host = url.host_str()?.to_lowercase();
+    let port = url.port_or_known_default()?;
+    HOST_OVERRIDES
+        .read()
+        .expect("host overrides lock")
+        .get(&host)
+        .map(|addresses| {
+            addresses
+                .iter()
+                .map(|ip| SocketAddr::new(*ip, port))
+                .collect()
+        })
+}
+
+/// serializes the tests setting the host overrides
+#[cfg(test)]
+pub(crate) static TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_override() {
+        let _lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut overrides = BTreeMap::new();
+        overrides.insert(
+            "Bridge.Example.Test".to_owned(),
+            vec!["::1".parse().unwrap(), "127.0.0.1".parse().unwrap()],
+        );
+        set_host_overrides(overrides);
+        let addrs = resolve_override(&"wss://bridge.example.test/".parse().unwrap())
+            .expect("overridden host");
+        assert_eq!(
+            addrs,
+            vec![
+                "[::1]:443".parse::<SocketAddr>().unwrap(),
+                "127.0.0.1:443".parse().unwrap()
+            ]
+        );
+        assert!(resolve_override(&"wss://other.example.test/".parse().unwrap()).is_none());
+    }
+}
@@ -0,0 +1,578 @@
+//! The conformance scenarios of the WalletConnect 1.0 client: scripted sessions
+//! against a headless reference wallet via a local bridge server
+//! (the downstream projects can run them with their own metadata or chain id)
+/// the scenarios of the WalletConnect 2.0 client via a local relay server
+pub mod v2;
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ethers::prelude::Address;
+use ethers::signers::{LocalWallet, Signer};
+use eyre::{eyre, Context};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+use crate::crypto::Key;
+use crate::uri::Uri;
+use crate::{
+    Client, JsonRpcError, Metadata, PeerMetadata, Response, SessionParams, SessionUpdate,
+    SocketMessage, SocketMessageKind, Topic,
+};
+
+/// how long a scenario can take (the session requests don't time out)
+const SCENARIO_TIMEOUT: Duration = Duration::from_secs(10);
+/// how long the client takes to process the wallet's session update
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(2);
+/// the error code of the rejections (the client turns it into an error)
+const REJECTED: i64 = -32000;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// how many messages are queued per topic until there's a subscriber
+/// (the oldest ones are dropped first)
+const MAX_QUEUED: usize = 100;
+/// how long the queued messages are kept
+const QUEUE_TTL: Duration = Duration::from_secs(60);
+
+/// The subscribers and the messages not delivered yet, by topic
+struct Topics<M> {
+    subscribers: HashMap<String, Vec<UnboundedSender<M>>>,
+    queued: HashMap<String, VecDeque<(Instant, M)>>,
+    ttl: Duration,
+}
+
+impl<M: Clone> Default for Topics<M> {
+    fn default() -> Self {
+        Self::new(QUEUE_TTL)
+    }
+}
+
+impl<M: Clone> Topics<M> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            subscribers: HashMap::new(),
+            queued: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// the subscriber receives the queued messages of the topic and the next ones
+    fn subscribe(&mut self, topic: &str, subscriber: UnboundedSender<M>) {
+        if let Some(queued) = self.queued.remove(topic) {
+            for (published, message) in queued {
+                if published.elapsed() < self.ttl {
+                    let _ = subscriber.send(message);
+                }
+            }
+        }
+        self.subscribers
+            .entry(topic.to_owned())
+            .or_default()
+            .push(subscriber);
+    }
+
+    fn unsubscribe(&mut self, topic: &str, subscriber: &UnboundedSender<M>) {
+        if let Some(subscribers) = self.subscribers.get_mut(topic) {
+            subscribers.retain(|s| !s.same_channel(subscriber));
+        }
+    }
+
+    /// delivers the message to the subscribers of the topic other than its publisher
+    /// (or queues it until there's one)
+    fn publish(&mut self, topic: &str, message: M, publisher: &UnboundedSender<M>) {
+        let ttl = self.ttl;
+        self.queued.retain(|_, queued| {
+            queued.retain(|(published, _)| published.elapsed() < ttl);
+            !queued.is_empty()
+        });
+        self.subscribers.retain(|_, subscribers| {
+            subscribers.retain(|subscriber| !subscriber.is_closed());
+            !subscribers.is_empty()
+        });
+        let subscribers: Vec<_> = self
+            .subscribers
+            .get(topic)
+            .into_iter()
+            .flatten()
+            .filter(|subscriber| !subscriber.same_channel(publisher))
+            .collect();
+        if subscribers.is_empty() {
+            let queued = self.queued.entry(topic.to_owned()).or_default();
+            if queued.len() == MAX_QUEUED {
+                queued.pop_front();
+            }
+            queued.push_back((Instant::now(), message));
+        } else {
+            for subscriber in subscribers {
+                let _ = subscriber.send(message.clone());
+            }
+        }
+    }
+}
+
+/// A local bridge server: it relays the published messages to the subscribers
+/// of their topics (and queues them for a while until there's a subscriber)
+pub struct MockBridge {
+    url: Url,
+    handle: JoinHandle<()>,
+}
+
+impl MockBridge {
+    /// listens on a free local port
+    pub async fn start() -> eyre::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?).parse()?;
+        let topics = Arc::new(Mutex::new(Topics::<String>::default()));
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(relay(stream, topics.clone()));
+            }
+        });
+        Ok(Self { url, handle })
+    }
+
+    /// the URL of the bridge server for the client (`Client::with_bridge`)
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl Drop for MockBridge {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// relays the messages of one websocket connection
+async fn relay(stream: TcpStream, topics: Arc<Mutex<Topics<String>>>) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut writer, mut reader) = ws.split();
+    let (sender, mut receiver) = unbounded_channel::<String>();
+    let write_handle = tokio::spawn(async move {
+        while let Some(text) = receiver.recv().await {
+            if writer.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+    while let Some(Ok(message)) = reader.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(socket_message) = serde_json::from_str::<SocketMessage>(&text) else {
+            continue;
+        };
+        let mut topics = topics.lock().expect("bridge topics lock");
+        let topic = socket_message.topic.to_string();
+        match socket_message.kind {
+            SocketMessageKind::Sub => topics.subscribe(&topic, sender.clone()),
+            SocketMessageKind::Pub => topics.publish(&topic, text, &sender),
+        }
+    }
+    write_handle.abort();
+}
+
+/// A headless wallet: it answers the session requests and `personal_sign`
+/// (the other methods are rejected as not supported)
+pub struct ReferenceWallet {
+    signer: LocalWallet,
+    chain_id: u64,
+    approve_sessions: bool,
+    approve_requests: bool,
+}
+
+impl ReferenceWallet {
+    /// a wallet approving the sessions and the requests
+    pub fn new(signer: LocalWallet, chain_id: u64) -> Self {
+        Self {
+            signer,
+            chain_id,
+            approve_sessions: true,
+            approve_requests: true,
+        }
+    }
+
+    pub fn rejecting_sessions(mut self) -> Self {
+        self.approve_sessions = false;
+        self
+    }
+
+    pub fn rejecting_requests(mut self) -> Self {
+        self.approve_requests = false;
+        self
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// connects to the bridge server of the connection URI (`wc:...`)
+    /// and answers the messages until the session is dropped
+    pub async fn connect(self, uri: &str) -> eyre::Result<WalletSession> {
+        let (handshake_topic, mut bridge, key) = Uri::parse(uri)?.into_parts();
+        match bridge.scheme() {
+            "http" => bridge.set_scheme("ws"),
+            "https" => bridge.set_scheme("wss"),
+            _ => Ok(()),
+        }
+        .map_err(|_| eyre!("invalid bridge URL: {bridge}"))?;
+        let (ws, _) = tokio_tungstenite::connect_async(bridge.as_str()).await?;
+        let (mut writer, mut reader) = ws.split();
+
+        let wallet_id = Topic::new();
+        let (sender, mut receiver) = unbounded_channel::<String>();
+        for topic in [&handshake_topic, &wallet_id] {
+            sender.send(serde_json::to_string(&SocketMessage {
+                topic: topic.clone(),
+                kind: SocketMessageKind::Sub,
+                payload: None,
+                silent: true,
+            })?)?;
+        }
+        let write_handle = tokio::spawn(async move {
+            while let Some(text) = receiver.recv().await {
+                if writer.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let peer_id = Arc::new(Mutex::new(None));
+        let read_handle = {
+            let (key, sender, peer_id) = (key.clone(), sender.clone(), peer_id.clone());
+            tokio::spawn(async move {
+                while let Some(Ok(message)) = reader.next().await {
+                    let Some(request) =
+                        serde_json::from_slice::<SocketMessage>(&message.into_data())
+                            .ok()
+                            .and_then(|message| message.payload)
+                            .and_then(|payload| key.open(&payload).ok())
+                            .and_then(|payload| serde_json::from_slice::<Value>(&payload).ok())
+                    else {
+                        continue;
+                    };
+                    if request["method"] == "wc_sessionRequest" {
+                        if let Ok(dapp_id) =
+                            serde_json::from_value(request["params"][0]["peerId"].clone())
+                        {
+                            *peer_id.lock().expect("wallet peer lock") = Some(dapp_id);
+                        }
+                    }
+                    let topic = peer_id.lock().expect("wallet peer lock").clone();
+                    if let (Some(topic), Some(response)) =
+                        (topic, self.respond(&request, &wallet_id).await)
+                    {
+                        let _ = sender.send(publish(&key, topic, &response));
+                    }
+                }
+            })
+        };
+        Ok(WalletSession {
+            key,
+            sender,
+            peer_id,
+            handles: [read_handle, write_handle],
+        })
+    }
+
+    /// the response to the dapp's request (none if it's not a request)
+    async fn respond(&self, request: &Value, wallet_id: &Topic) -> Option<Value> {
+        let id = request["id"].as_u64()?;
+        let method = request["method"].as_str()?;
+        let response = match method {
+            "wc_sessionRequest" if self.approve_sessions => json!(Response::new(
+                id,
+                SessionParams {
+                    approved: true,
+                    accounts: vec![self.address()],
+                    chain_id: self.chain_id,
+                    peer_id: wallet_id.clone(),
+                    peer_meta: PeerMetadata::Strict(reference_metadata()),
+                },
+            )),
+            "wc_sessionRequest" => rejection(id, "Session Rejected"),
+            "personal_sign" if self.approve_requests => {
+                let message = request["params"][0].as_str()?;
+                let message = hex::decode(message.trim_start_matches("0x")).ok()?;
+                let signature = self.signer.sign_message(message).await.ok()?;
+                json!(Response::new(id, format!("0x{signature}")))
+            }
+            "personal_sign" => rejection(id, "User rejected the request"),
+            _ => json!(Response::<Value>::error(
+                id,
+                JsonRpcError {
+                    code: METHOD_NOT_FOUND,
+                    message: format!("Method not supported: {method}"),
+                    data: None,
+                },
+            )),
+        };
+        Some(response)
+    }
+}
+
+fn rejection(id: u64, message: &str) -> Value {
+    json!(Response::<Value>::error(
+        id,
+        JsonRpcError {
+            code: REJECTED,
+            message: message.to_owned(),
+            data: None,
+        },
+    ))
+}
+
+fn reference_metadata() -> Metadata {
+    Metadata {
+        description: "WalletConnect reference wallet".to_owned(),
+        url: "https://example.test/wallet".parse().expect("metadata url"),
+        icons: vec![],
+        name: "Reference Wallet".to_owned(),
+    }
+}
+
+/// the serialized message publishing the encrypted payload
+fn publish(key: &Key, topic: Topic, payload: &Value) -> String {
+    serde_json::to_string(&SocketMessage {
+        topic,
+        kind: SocketMessageKind::Pub,
+        payload: Some(key.seal(payload.to_string())),
+        silent: true,
+    })
+    .expect("socket message serialization")
+}
+
+/// The wallet's side of a session (the connection is closed when it's dropped)
+pub struct WalletSession {
+    key: Key,
+    sender: UnboundedSender<String>,
+    /// the dapp's client id (after its session request)
+    peer_id: Arc<Mutex<Option<Topic>>>,
+    handles: [JoinHandle<()>; 2],
+}
+
+impl WalletSession {
+    /// disconnects as the wallet user would (a session update that isn't approved)
+    pub fn disconnect(&self) -> eyre::Result<()> {
+        let topic = self
+            .peer_id
+            .lock()
+            .expect("wallet peer lock")
+            .clone()
+            .ok_or_else(|| eyre!("no session request was received"))?;
+        let update = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "wc_sessionUpdate",
+            "params": [SessionUpdate {
+                approved: false,
+                accounts: None,
+                chain_id: None,
+            }],
+        });
+        self.sender.send(publish(&self.key, topic, &update))?;
+        Ok(())
+    }
+}
+
+impl Drop for WalletSession {
+    fn drop(&mut self) {
+        self.handles.iter().for_each(JoinHandle::abort);
+    }
+}
+
+/// The options of the scenarios
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    /// the client's metadata
+    pub metadata: Metadata,
+    /// the chain id of the reference wallet
+    pub chain_id: u64,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            metadata: Metadata {
+                description: "WalletConnect interoperability harness".to_owned(),
+                url: "https://example.test/dapp".parse().expect("metadata url"),
+                icons: vec![],
+                name: "Interop Harness".to_owned(),
+            },
+            chain_id: 338,
+        }
+    }
+}
+
+impl Fixture {
+    /// a client and the reference wallet connected via the bridge server
+    /// (the session is not requested yet)
+    async fn connect(
+        &self,
+        bridge: &MockBridge,
+        wallet: ReferenceWallet,
+    ) -> eyre::Result<(Client, WalletSession)> {
+        let client = Client::with_bridge(
+            self.metadata.clone(),
+            Some(self.chain_id),
+            bridge.url().clone(),
+        )
+        .await?;
+        let uri = client.get_connection_string().await?;
+        let session = wallet.connect(&uri).await?;
+        Ok((client, session))
+    }
+
+    fn wallet(&self) -> ReferenceWallet {
+        ReferenceWallet::new(LocalWallet::new(&mut rand::thread_rng()), self.chain_id)
+    }
+
+    /// the wallet approves the session: the client gets its account and chain id
+    pub async fn session_settle(&self, bridge: &MockBridge) -> eyre::Result<()> {
+        let wallet = self.wallet();
+        let address = wallet.address();
+        let (mut client, _session) = self.connect(bridge, wallet).await?;
+        let (accounts, chain_id) = client.ensure_session().await?;
+        if accounts != vec![address] || chain_id != self.chain_id {
+            return Err(eyre!("unexpected session: {accounts:?} on {chain_id}"));
+        }
+        let info = client.get_session_info().await?;
+        if !info.connected || info.peer_meta.is_none() {
+            return Err(eyre!("the session info is not updated"));
+        }
+        Ok(())
+    }
+
+    /// the wallet rejects the session: the client gets an error
+    pub async fn session_rejected(&self, bridge: &MockBridge) -> eyre::Result<()> {
+        let (mut client, _session) = self
+            .connect(bridge, self.wallet().rejecting_sessions())
+            .await?;
+        match client.ensure_session().await {
+            Ok(_) => Err(eyre!("the rejected session was settled")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// the wallet signs a message: the signature is from its account
+    pub async fn personal_sign(&self, bridge: &MockBridge) -> eyre::Result<()> {
+        let wallet = self.wallet();
+        let address = wallet.address();
+        let (mut client, _session) = self.connect(bridge, wallet).await?;
+        client.ensure_session().await?;
+        let message = "interop personal_sign";
+        let signature = client.personal_sign(message, &address).await?;
+        signature
+            .verify(message, address)
+            .wrap_err("the signature is not from the wallet's account")
+    }
+
+    /// the wallet rejects a request: the client gets an error
+    pub async fn request_rejected(&self, bridge: &MockBridge) -> eyre::Result<()> {
+        let wallet = self.wallet().rejecting_requests();
+        let address = wallet.address();
+        let (mut client, _session) = self.connect(bridge, wallet).await?;
+        client.ensure_session().await?;
+        match client.personal_sign("interop rejected", &address).await {
+            Ok(_) => Err(eyre!("the rejected request was signed")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// the wallet disconnects: the session is no longer connected
+    pub async fn wallet_disconnect(&self, bridge: &MockBridge) -> eyre::Result<()> {
+        let (mut client, session) = self.connect(bridge, self.wallet()).await?;
+        client.ensure_session().await?;
+        session.disconnect()?;
+        tokio::time::timeout(UPDATE_TIMEOUT, async {
+            while client.get_session_info().await?.connected {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            Ok::<_, eyre::Report>(())
+        })
+        .await
+        .map_err(|_| eyre!("the session is still connected after the wallet disconnected"))?
+    }
+
+    /// runs all the scenarios (each with a new client and wallet) and returns their results
+    pub async fn run_all(&self, bridge: &MockBridge) -> Vec<(&'static str, eyre::Result<()>)> {
+        vec![
+            ("session_settle", run(self.session_settle(bridge)).await),
+            ("session_rejected", run(self.session_rejected(bridge)).await),
+            ("personal_sign", run(self.personal_sign(bridge)).await),
+            ("request_rejected", run(self.request_rejected(bridge)).await),
+            (
+                "wallet_disconnect",
+                run(self.wallet_disconnect(bridge)).await,
+            ),
+        ]
+    }
+}
+
+/// runs a scenario (it fails if it doesn't finish in time)
+async fn run(scenario: impl Future<Output = eyre::Result<()>>) -> eyre::Result<()> {
+    tokio::time::timeout(SCENARIO_TIMEOUT, scenario)
+        .await
+        .map_err(|_| eyre!("timed out"))?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_queued_messages() {
+        let (publisher, _) = unbounded_channel();
+        let mut topics = Topics::default();
+        for i in 0..MAX_QUEUED + 5 {
+            topics.publish("topic", i, &publisher);
+        }
+        topics.publish("other", 0, &publisher);
+        let (sender, mut receiver) = unbounded_channel();
+        topics.subscribe("topic", sender.clone());
+        let mut received = vec![];
+        while let Ok(message) = receiver.try_recv() {
+            received.push(message);
+        }
+        // the oldest ones are dropped
+        assert_eq!(received, (5..MAX_QUEUED + 5).collect::<Vec<_>>());
+        // the subscribers receive the messages, except their publisher
+        topics.publish("topic", 1000, &publisher);
+        topics.publish("topic", 1001, &sender);
+        assert_eq!(receiver.try_recv(), Ok(1000));
+        assert!(receiver.try_recv().is_err());
+        // (it's queued for the other subscribers)
+        assert_eq!(topics.queued["topic"].len(), 1);
+
+        let mut topics = Topics::new(Duration::ZERO);
+        topics.publish("topic", 1, &publisher);
+        let (sender, mut receiver) = unbounded_channel();
+        topics.subscribe("topic", sender);
+        // the expired messages aren't delivered
+        assert!(receiver.try_recv().is_err());
+        topics.publish("other", 2, &publisher);
+        topics.publish("another", 3, &publisher);
+        // and they are swept
+        assert_eq!(topics.queued.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_interop_scenarios() {
+        let bridge = MockBridge::start().await.unwrap();
+        for (name, result) in Fixture::default().run_all(&bridge).await {
+            assert!(result.is_ok(), "{name}: {result:?}");
+        }
+    }
+}
@@ -0,0 +1,699 @@
+//! The conformance scenarios of the WalletConnect 2.0 client: scripted sessions
+//! against a headless reference wallet via a local relay server
+//! (it speaks the `irn_*` JSON-RPC of the relay, the wallet is attached in-process)
+use std::collections::HashMap;
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ethers::prelude::Address;
+use ethers::signers::{LocalWallet, Signer};
+use eyre::{eyre, Context};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use super::Topics;
+use crate::crypto::Key;
+use crate::hex;
+use crate::v2::crypto::{decode_decrypt, derive_symkey_topic, encrypt_and_encode};
+use crate::v2::{Client, ClientOptions, Metadata, Namespaces, RequiredNamespaces};
+
+/// how long a scenario can take
+const SCENARIO_TIMEOUT: Duration = Duration::from_secs(10);
+/// how long the client takes to process the wallet's session deletion
+const DELETE_TIMEOUT: Duration = Duration::from_secs(2);
+/// the error code of the rejections
+/// (ref: https://docs.walletconnect.com/2.0/specs/clients/sign/error-codes)
+const USER_REJECTED: i64 = 5000;
+const USER_DISCONNECTED: i64 = 6000;
+const METHOD_NOT_FOUND: i64 = -32601;
+/// the tags of the wallet's messages
+/// (ref: https://docs.walletconnect.com/2.0/specs/clients/sign/rpc-methods)
+const SESSION_PROPOSE_RESPONSE_TAG: u32 = 1101;
+const SESSION_SETTLE_REQUEST_TAG: u32 = 1102;
+const SESSION_REQUEST_RESPONSE_TAG: u32 = 1109;
+const SESSION_DELETE_REQUEST_TAG: u32 = 1112;
+/// how long the settled sessions last
+const SESSION_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A message published on the relay server
+#[derive(Clone, Debug)]
+pub struct Published {
+    pub topic: String,
+    /// the encrypted envelope (base64)
+    pub message: String,
+    pub tag: u32,
+}
+
+/// A local relay server: it relays the published messages to the subscribers
+/// of their topics (and queues them for a while until there's a subscriber)
+pub struct MockRelay {
+    url: Url,
+    topics: Arc<Mutex<Topics<Published>>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockRelay {
+    /// listens on a free local port
+    pub async fn start() -> eyre::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", listener.local_addr()?).parse()?;
+        let topics = Arc::new(Mutex::new(Topics::<Published>::default()));
+        let handle = {
+            let topics = topics.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(relay(stream, topics.clone()));
+                }
+            })
+        };
+        Ok(Self {
+            url,
+            topics,
+            handle,
+        })
+    }
+
+    /// the URL of the relay server for the client (`ClientOptions::relay_server`)
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// an in-process connection to the relay server and the messages of its subscriptions
+    pub fn peer(&self) -> (RelayPeer, UnboundedReceiver<Published>) {
+        let (sender, receiver) = unbounded_channel();
+        let peer = RelayPeer {
+            topics: self.topics.clone(),
+            sender,
+        };
+        (peer, receiver)
+    }
+}
+
+impl Drop for MockRelay {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// An in-process connection to the relay server
+/// (it doesn't receive the messages it publishes, as the websocket connections)
+#[derive(Clone)]
+pub struct RelayPeer {
+    topics: Arc<Mutex<Topics<Published>>>,
+    sender: UnboundedSender<Published>,
+}
+
+impl RelayPeer {
+    /// the messages of the topic are received (the queued ones first)
+    pub fn subscribe(&self, topic: &str) {
+        self.topics
+            .lock()
+            .expect("relay topics lock")
+            .subscribe(topic, self.sender.clone());
+    }
+
+    pub fn publish(&self, message: Published) {
+        self.topics.lock().expect("relay topics lock").publish(
+            &message.topic.clone(),
+            message,
+            &self.sender,
+        );
+    }
+}
+
+/// the JSON-RPC response of the relay server
+fn relay_response(id: &Value, result: Value) -> String {
+    json!({"id": id, "jsonrpc": "2.0", "result": result}).to_string()
+}
+
+/// relays the messages of one websocket connection
+async fn relay(stream: TcpStream, topics: Arc<Mutex<Topics<Published>>>) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let (mut writer, mut reader) = ws.split();
+    let (sender, mut receiver) = unbounded_channel::<String>();
+    let write_handle = tokio::spawn(async move {
+        while let Some(text) = receiver.recv().await {
+            if writer.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+    // the subscription ids of the connection, by topic
+    let subscriptions = Arc::new(Mutex::new(HashMap::<String, String>::new()));
+    let (deliveries, mut delivered) = unbounded_channel::<Published>();
+    let delivery_handle = {
+        let (sender, subscriptions) = (sender.clone(), subscriptions.clone());
+        let next_id = AtomicU64::new(1);
+        tokio::spawn(async move {
+            while let Some(published) = delivered.recv().await {
+                let Some(id) = subscriptions
+                    .lock()
+                    .expect("relay subscriptions lock")
+                    .get(&published.topic)
+                    .cloned()
+                else {
+                    continue;
+                };
+                let published_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                let request = json!({
+                    "id": next_id.fetch_add(1, Ordering::Relaxed),
+                    "jsonrpc": "2.0",
+                    "method": "irn_subscription",
+                    "params": {
+                        "id": id,
+                        "data": {
+                            "topic": published.topic,
+                            "message": published.message,
+                            "publishedAt": published_at,
+                            "tag": published.tag,
+                        },
+                    },
+                });
+                let _ = sender.send(request.to_string());
+            }
+        })
+    };
+    while let Some(Ok(message)) = reader.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Binary(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(request) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+        // the responses of the client (to the deliveries) aren't tracked
+        let (Some(method), Some(topic)) = (
+            request["method"].as_str(),
+            request["params"]["topic"].as_str(),
+        ) else {
+            continue;
+        };
+        let id = &request["id"];
+        let mut topics = topics.lock().expect("relay topics lock");
+        let response = match method {
+            "irn_subscribe" => {
+                let subscription_id = hex::encode(Key::random().as_ref());
+                subscriptions
+                    .lock()
+                    .expect("relay subscriptions lock")
+                    .insert(topic.to_owned(), subscription_id.clone());
+                topics.subscribe(topic, deliveries.clone());
+                relay_response(id, json!(subscription_id))
+            }
+            "irn_unsubscribe" => {
+                subscriptions
+                    .lock()
+                    .expect("relay subscriptions lock")
+                    .remove(topic);
+                topics.unsubscribe(topic, &deliveries);
+                relay_response(id, json!(true))
+            }
+            "irn_publish" => {
+                topics.publish(
+                    topic,
+                    Published {
+                        topic: topic.to_owned(),
+                        message: request["params"]["message"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_owned(),
+                        tag: request["params"]["tag"].as_u64().unwrap_or_default() as u32,
+                    },
+                    &deliveries,
+                );
+                relay_response(id, json!(true))
+            }
+            _ => json!({
+                "id": id,
+                "jsonrpc": "2.0",
+                "error": {"code": METHOD_NOT_FOUND, "message": format!("Method not found: {method}")},
+            })
+            .to_string(),
+        };
+        let _ = sender.send(response);
+    }
+    write_handle.abort();
+    delivery_handle.abort();
+}
+
+/// A headless wallet: it answers the session proposal and `personal_sign`
+/// (the other methods are rejected as not supported)
+pub struct ReferenceWallet {
+    signer: LocalWallet,
+    chain_id: u64,
+    approve_sessions: bool,
+    approve_requests: bool,
+}
+
+impl ReferenceWallet {
+    /// a wallet approving the sessions and the requests
+    pub fn new(signer: LocalWallet, chain_id: u64) -> Self {
+        Self {
+            signer,
+            chain_id,
+            approve_sessions: true,
+            approve_requests: true,
+        }
+    }
+
+    pub fn rejecting_sessions(mut self) -> Self {
+        self.approve_sessions = false;
+        self
+    }
+
+    pub fn rejecting_requests(mut self) -> Self {
+        self.approve_requests = false;
+        self
+    }
+
+    pub fn address(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// pairs via the connection URI (`wc:...@2?symKey=...`) on the relay server
+    /// and answers the messages until the session is dropped
+    pub async fn connect(self, relay: &MockRelay, uri: &str) -> eyre::Result<WalletSession> {
+        let (proposal_topic, proposal_key) = parse_uri(uri)?;
+        let (peer, mut received) = relay.peer();
+        peer.subscribe(&proposal_topic);
+        let published = received
+            .recv()
+            .await
+            .ok_or_else(|| eyre!("no session proposal was received"))?;
+        let proposal: Value =
+            serde_json::from_slice(&decode_decrypt(&proposal_key, &published.message)?)?;
+        let id = &proposal["id"];
+
+        if !self.approve_sessions {
+            let response = rejection(id, USER_REJECTED, "User rejected.");
+            peer.publish(seal(
+                &proposal_topic,
+                &proposal_key,
+                &response,
+                SESSION_PROPOSE_RESPONSE_TAG,
+            ));
+            return Ok(WalletSession::default());
+        }
+
+        let mut secret = StaticSecret::new(relay_rpc::auth::rand::thread_rng());
+        let public_key = hex::encode(PublicKey::from(&secret).as_bytes());
+        let secret_key = Key::from_raw(secret.to_bytes());
+        secret.zeroize();
+        let proposer_key = proposal["params"]["proposer"]["publicKey"]
+            .as_str()
+            .ok_or_else(|| eyre!("no proposer's public key"))?;
+        let (topic, key) = derive_symkey_topic(proposer_key, &secret_key)
+            .ok_or_else(|| eyre!("invalid proposer's public key: {proposer_key}"))?;
+        let topic = topic.to_string();
+        peer.subscribe(&topic);
+
+        let response = json!({
+            "id": id,
+            "jsonrpc": "2.0",
+            "result": {"relay": {"protocol": "irn"}, "responderPublicKey": public_key},
+        });
+        peer.publish(seal(
+            &proposal_topic,
+            &proposal_key,
+            &response,
+            SESSION_PROPOSE_RESPONSE_TAG,
+        ));
+        let expiry = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            + SESSION_EXPIRY;
+        let settle = json!({
+            "id": request_id(),
+            "jsonrpc": "2.0",
+            "method": "wc_sessionSettle",
+            "params": {
+                "relay": {"protocol": "irn"},
+                "namespaces": {
+                    "eip155": {
+                        "accounts": [format!("eip155:{}:{:?}", self.chain_id, self.address())],
+                        "methods": ["personal_sign"],
+                        "events": ["chainChanged", "accountsChanged"],
+                    },
+                },
+                "requiredNamespaces": proposal["params"]["requiredNamespaces"],
+                "controller": {"publicKey": public_key, "metadata": reference_metadata()},
+                "expiry": expiry.as_secs(),
+            },
+        });
+        peer.publish(seal(&topic, &key, &settle, SESSION_SETTLE_REQUEST_TAG));
+
+        let handle = {
+            let (peer, topic, key) = (peer.clone(), topic.clone(), key.clone());
+            tokio::spawn(async move {
+                while let Some(published) = received.recv().await {
+                    let Some(request) = decode_decrypt(&key, &published.message)
+                        .ok()
+                        .and_then(|plain| serde_json::from_slice::<Value>(&plain).ok())
+                    else {
+                        continue;
+                    };
+                    if let Some(response) = self.respond(&request).await {
+                        peer.publish(seal(&topic, &key, &response, SESSION_REQUEST_RESPONSE_TAG));
+                    }
+                }
+            })
+        };
+        Ok(WalletSession {
+            session: Some((peer, topic, key)),
+            handle: Some(handle),
+        })
+    }
+
+    /// the response to the dapp's session request (none if it's not one)
+    async fn respond(&self, request: &Value) -> Option<Value> {
+        if request["method"] != "wc_sessionRequest" {
+            return None;
+        }
+        let id = &request["id"];
+        let method = request["params"]["request"]["method"].as_str()?;
+        let response = match method {
+            "personal_sign" if self.approve_requests => {
+                let message = request["params"]["request"]["params"][0].as_str()?;
+                let message = hex::decode(message.trim_start_matches("0x")).ok()?;
+                let signature = self.signer.sign_message(message).await.ok()?;
+                json!({"id": id, "jsonrpc": "2.0", "result": format!("0x{signature}")})
+            }
+            "personal_sign" => rejection(id, USER_REJECTED, "User rejected."),
+            _ => rejection(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Method not supported: {method}"),
+            ),
+        };
+        Some(response)
+    }
+}
+
+/// the topic and the key of the session proposal in the connection URI
+fn parse_uri(uri: &str) -> eyre::Result<(String, Key)> {
+    let (topic, params) = uri
+        .strip_prefix("wc:")
+        .and_then(|uri| uri.split_once('?'))
+        .ok_or_else(|| eyre!("invalid connection URI: {uri}"))?;
+    let (topic, version) = topic
+        .split_once('@')
+        .ok_or_else(|| eyre!("no version in the connection URI: {uri}"))?;
+    if version != "2" {
+        return Err(eyre!("unsupported version: {version}"));
+    }
+    let key = url::form_urlencoded::parse(params.as_bytes())
+        .find(|(name, _)| name == "symKey")
+        .ok_or_else(|| eyre!("no symKey in the connection URI: {uri}"))?
+        .1;
+    let key = Key::from_str(&key).map_err(|e| eyre!("invalid symKey: {e}"))?;
+    Ok((topic.to_owned(), key))
+}
+
+fn request_id() -> u64 {
+    rand::random::<u64>() % 9007199254740990 + 1
+}
+
+fn rejection(id: &Value, code: i64, message: &str) -> Value {
+    json!({"id": id, "jsonrpc": "2.0", "error": {"code": code, "message": message}})
+}
+
+fn reference_metadata() -> Metadata {
+    Metadata {
+        description: "WalletConnect reference wallet".to_owned(),
+        url: "https://example.test/wallet".to_owned(),
+        icons: vec![],
+        name: "Reference Wallet".to_owned(),
+    }
+}
+
+/// the message publishing the encrypted payload
+fn seal(topic: &str, key: &Key, payload: &Value, tag: u32) -> Published {
+    Published {
+        topic: topic.to_owned(),
+        message: encrypt_and_encode(key, payload.to_string().as_bytes()),
+        tag,
+    }
+}
+
+/// The wallet's side of a session (it stops answering when it's dropped)
+#[derive(Default)]
+pub struct WalletSession {
+    /// the wallet's connection, the pairing topic and key (none if the session was rejected)
+    session: Option<(RelayPeer, String, Key)>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl WalletSession {
+    /// disconnects as the wallet user would (`wc_sessionDelete`)
+    pub fn disconnect(&self) -> eyre::Result<()> {
+        let (peer, topic, key) = self
+            .session
+            .as_ref()
+            .ok_or_else(|| eyre!("no session was settled"))?;
+        let delete = json!({
+            "id": request_id(),
+            "jsonrpc": "2.0",
+            "method": "wc_sessionDelete",
+            "params": {"code": USER_DISCONNECTED, "message": "User disconnected."},
+        });
+        peer.publish(seal(topic, key, &delete, SESSION_DELETE_REQUEST_TAG));
+        Ok(())
+    }
+}
+
+impl Drop for WalletSession {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.abort();
+        }
+    }
+}
+
+/// The options of the scenarios
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    /// the client's metadata
+    pub metadata: Metadata,
+    /// the chain id of the reference wallet
+    pub chain_id: u64,
+    /// the project id of the client (the mock relay server doesn't check it)
+    pub project_id: String,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            metadata: Metadata {
+                description: "WalletConnect interoperability harness".to_owned(),
+                url: "https://example.test/dapp".to_owned(),
+                icons: vec![],
+                name: "Interop Harness".to_owned(),
+            },
+            chain_id: 338,
+            project_id: "interop".to_owned(),
+        }
+    }
+}
+
+impl Fixture {
+    /// a client and the reference wallet paired via the relay server
+    /// (the wallet answers the session proposal of `ensure_session`)
+    async fn pair(
+        &self,
+        relay: &MockRelay,
+        wallet: ReferenceWallet,
+    ) -> eyre::Result<(Client, eyre::Result<Namespaces>, WalletSession)> {
+        let mut client = Client::new(ClientOptions {
+            relay_server: relay.url().clone(),
+            project_id: self.project_id.clone(),
+            required_namespaces: RequiredNamespaces::new(
+                vec!["personal_sign".to_owned()],
+                vec![format!("eip155:{}", self.chain_id)],
+                vec!["chainChanged".to_owned(), "accountsChanged".to_owned()],
+            ),
+            client_meta: self.metadata.clone(),
+            callback_sender: None,
+        })
+        .await?;
+        let uri = client.get_connection_string().await;
+        let (namespaces, session) =
+            tokio::join!(client.ensure_session(), wallet.connect(relay, &uri));
+        Ok((client, namespaces, session?))
+    }
+
+    fn wallet(&self) -> ReferenceWallet {
+        ReferenceWallet::new(LocalWallet::new(&mut rand::thread_rng()), self.chain_id)
+    }
+
+    /// the wallet settles the session: the client gets its account and chain id
+    pub async fn session_settle(&self, relay: &MockRelay) -> eyre::Result<()> {
+        let wallet = self.wallet();
+        let address = wallet.address();
+        let (client, namespaces, _session) = self.pair(relay, wallet).await?;
+        let namespaces = namespaces?;
+        let accounts: Vec<Address> = namespaces
+            .get_ethereum_addresses()
+            .iter()
+            .map(|account| account.address)
+            .collect();
+        if accounts != vec![address] || namespaces.get_chain_ids() != vec![self.chain_id] {
+            return Err(eyre!("unexpected session: {namespaces:?}"));
+        }
+        let info = client.get_session_info().await;
+        if !info.connected || info.pairing_peer_meta.is_none() {
+            return Err(eyre!("the session info is not updated"));
+        }
+        Ok(())
+    }
+
+    /// the wallet rejects the session proposal: the client gets an error
+    pub async fn session_rejected(&self, relay: &MockRelay) -> eyre::Result<()> {
+        let (_client, namespaces, _session) =
+            self.pair(relay, self.wallet().rejecting_sessions()).await?;
+        match namespaces {
+            Ok(_) => Err(eyre!("the rejected session was settled")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// the wallet signs a message: the signature is from its account
+    pub async fn personal_sign(&self, relay: &MockRelay) -> eyre::Result<()> {
+        let wallet = self.wallet();
+        let address = wallet.address();
+        let (mut client, namespaces, _session) = self.pair(relay, wallet).await?;
+        namespaces?;
+        let message = "interop personal_sign";
+        let signature = client.personal_sign(message, &address).await?;
+        signature
+            .verify(message, address)
+            .wrap_err("the signature is not from the wallet's account")
+    }
+
+    /// the wallet rejects a request: the client gets an error
+    pub async fn request_rejected(&self, relay: &MockRelay) -> eyre::Result<()> {
+        let wallet = self.wallet().rejecting_requests();
+        let address = wallet.address();
+        let (mut client, namespaces, _session) = self.pair(relay, wallet).await?;
+        namespaces?;
+        match client.personal_sign("interop rejected", &address).await {
+            Ok(_) => Err(eyre!("the rejected request was signed")),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// the wallet deletes the session: the session is no longer connected
+    pub async fn wallet_disconnect(&self, relay: &MockRelay) -> eyre::Result<()> {
+        let (client, namespaces, session) = self.pair(relay, self.wallet()).await?;
+        namespaces?;
+        session.disconnect()?;
+        tokio::time::timeout(DELETE_TIMEOUT, async {
+            while client.get_session_info().await.connected {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .map_err(|_| eyre!("the session is still connected after the wallet disconnected"))
+    }
+
+    /// runs all the scenarios (each with a new client and wallet) and returns their results
+    pub async fn run_all(&self, relay: &MockRelay) -> Vec<(&'static str, eyre::Result<()>)> {
+        vec![
+            ("session_settle", run(self.session_settle(relay)).await),
+            ("session_rejected", run(self.session_rejected(relay)).await),
+            ("personal_sign", run(self.personal_sign(relay)).await),
+            ("request_rejected", run(self.request_rejected(relay)).await),
+            (
+                "wallet_disconnect",
+                run(self.wallet_disconnect(relay)).await,
+            ),
+        ]
+    }
+}
+
+/// runs a scenario (it fails if it doesn't finish in time)
+async fn run(scenario: impl Future<Output = eyre::Result<()>>) -> eyre::Result<()> {
+    tokio::time::timeout(SCENARIO_TIMEOUT, scenario)
+        .await
+        .map_err(|_| eyre!("timed out"))?
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_uri() {
+        let key = Key::random();
+        let uri = format!(
+            "wc:{}@2?symKey={}&relay-protocol=irn",
+            "ab".repeat(32),
+            hex::encode(key.as_ref())
+        );
+        let (topic, parsed) = parse_uri(&uri).unwrap();
+        assert_eq!(topic, "ab".repeat(32));
+        assert_eq!(parsed.as_ref(), key.as_ref());
+        assert!(parse_uri("wc:abc@1?key=00").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relay_protocol() {
+        let relay = MockRelay::start().await.unwrap();
+        let (mut ws, _) = tokio_tungstenite::connect_async(relay.url().as_str())
+            .await
+            .unwrap();
+        let (peer, mut messages) = relay.peer();
+        // published before the subscription: it's queued
+        peer.publish(Published {
+            topic: "t1".to_owned(),
+            message: "m1".to_owned(),
+            tag: 1100,
+        });
+        let subscribe = json!({"id": 1, "jsonrpc": "2.0", "method": "irn_subscribe", "params": {"topic": "t1"}});
+        ws.send(Message::Text(subscribe.to_string())).await.unwrap();
+        let mut received = vec![];
+        while received.len() < 2 {
+            let message = ws.next().await.unwrap().unwrap();
+            received.push(serde_json::from_slice::<Value>(&message.into_data()).unwrap());
+        }
+        let response = received.iter().find(|m| m["id"] == 1).unwrap();
+        let subscription_id = response["result"].as_str().unwrap();
+        let delivery = received
+            .iter()
+            .find(|m| m["method"] == "irn_subscription")
+            .unwrap();
+        assert_eq!(delivery["params"]["id"], subscription_id);
+        assert_eq!(delivery["params"]["data"]["message"], "m1");
+        assert_eq!(delivery["params"]["data"]["tag"], 1100);
+
+        peer.subscribe("t2");
+        let publish = json!({"id": 2, "jsonrpc": "2.0", "method": "irn_publish", "params": {"topic": "t2", "message": "m2", "ttl": 300, "tag": 1108}});
+        ws.send(Message::Text(publish.to_string())).await.unwrap();
+        let published = messages.recv().await.unwrap();
+        assert_eq!((published.message.as_str(), published.tag), ("m2", 1108));
+    }
+
+    #[tokio::test]
+    async fn test_interop_scenarios() {
+        let relay = MockRelay::start().await.unwrap();
+        for (name, result) in Fixture::default().run_all(&relay).await {
+            assert!(result.is_ok(), "{name}: {result:?}");
+        }
+    }
+}
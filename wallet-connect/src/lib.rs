@@ -2,14 +2,27 @@
 mod client;
 /// the cryptography helpers for WalletConnect 1.0
 mod crypto;
+/// static host overrides for the outgoing connections
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dns;
 /// small utilities for hexadecimal operations
 mod hex;
+/// the interoperability scenarios against a headless reference wallet
+#[cfg(all(feature = "interop", not(target_arch = "wasm32")))]
+pub mod interop;
+/// the migration of the saved WalletConnect 1.0 sessions to WalletConnect 2.0
+pub mod migration;
 /// the WalletConnect 1.0 relevant payload definitions: https://docs.walletconnect.com/tech-spec#events--payloads
 mod protocol;
 /// helpers for serde
 mod serialization;
+/// custom root certificates and certificate pinning for the outgoing connections
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tls;
 /// utilities for the connection URI: https://docs.walletconnect.com/tech-spec#requesting-connection
 mod uri;
+/// the data usage of the bridge server connections
+pub mod usage;
 pub mod v2;
 pub use client::*;
 pub use protocol::*;
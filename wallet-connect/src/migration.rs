@@ -0,0 +1,177 @@
+//! The migration of the saved WalletConnect 1.0 sessions (e.g. in the player saves)
+//! to WalletConnect 2.0: the wallet has to pair again, as the 1.0 bridge servers
+//! aren't compatible with the 2.0 relay servers, but the account and the chain are kept
+use ethers::prelude::Address;
+use eyre::eyre;
+use serde_json::Value;
+
+use crate::client::session::SessionInfo as V1SessionInfo;
+use crate::v2::{Metadata, Namespaces, RequiredNamespaces, SessionInfo};
+use crate::{PeerMetadata, USER_APPROVAL_METHODS};
+
+/// the events of the 1.0 sessions (the wallet's session updates)
+const EVENTS: &[&str] = &["chainChanged", "accountsChanged"];
+
+/// The saved session after the migration
+#[derive(Debug, Clone)]
+pub enum MigrationOutcome {
+    /// a WalletConnect 2.0 session: it's restored as is (`v2::Client::restore`)
+    Current(Box<SessionInfo>),
+    /// a WalletConnect 1.0 session: the wallet has to pair again
+    RePair(RePairing),
+}
+
+/// What's kept from the WalletConnect 1.0 session to pair the wallet again
+#[derive(Debug, Clone)]
+pub struct RePairing {
+    /// if the wallet had approved the 1.0 session
+    pub was_connected: bool,
+    /// the accounts of the 1.0 session (empty if it wasn't approved)
+    pub accounts: Vec<Address>,
+    /// the chain id of the 1.0 session (or the one the client requested)
+    pub chain_id: Option<u64>,
+    /// the client metadata of the 1.0 session (for `v2::ClientOptions`)
+    pub client_meta: Metadata,
+    /// the name of the wallet (e.g. to tell the player which wallet to open)
+    pub wallet_name: Option<String>,
+}
+
+impl RePairing {
+    /// the namespaces of the proposal: the chain of the 1.0 session
+    /// (or the default one if it's unknown) with the 1.0 signing methods
+    pub fn required_namespaces(&self, default_chain_id: u64) -> RequiredNamespaces {
+        RequiredNamespaces::new(
+            USER_APPROVAL_METHODS
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            vec![format!(
+                "eip155:{}",
+                self.chain_id.unwrap_or(default_chain_id)
+            )],
+            EVENTS.iter().map(|e| e.to_string()).collect(),
+        )
+    }
+
+    /// whether the wallet approved the new session with an account of the 1.0 session
+    /// (if not, the player may have paired a different wallet or account);
+    /// it's true if the 1.0 session had no accounts
+    pub fn keeps_account(&self, namespaces: &Namespaces) -> bool {
+        self.accounts.is_empty()
+            || namespaces
+                .get_ethereum_addresses()
+                .iter()
+                .any(|account| self.accounts.contains(&account.address))
+    }
+}
+
+impl From<V1SessionInfo> for RePairing {
+    fn from(info: V1SessionInfo) -> Self {
+        let wallet_name = match info.peer_meta {
+            Some(PeerMetadata::Strict(meta)) => Some(meta.name),
+            Some(PeerMetadata::Malformed(meta)) => meta["name"].as_str().map(str::to_owned),
+            None => None,
+        };
+        Self {
+            was_connected: info.connected,
+            accounts: if info.connected {
+                info.accounts
+            } else {
+                vec![]
+            },
+            chain_id: info.chain_id,
+            client_meta: Metadata {
+                description: info.client_meta.description,
+                url: info.client_meta.url.to_string(),
+                icons: info
+                    .client_meta
+                    .icons
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                name: info.client_meta.name,
+            },
+            wallet_name,
+        }
+    }
+}
+
+/// detects the version of the saved session (its JSON) and migrates it
+pub fn migrate_session(session_json: &str) -> eyre::Result<MigrationOutcome> {
+    let value: Value = serde_json::from_str(session_json)?;
+    if value.get("relayServer").is_some() {
+        Ok(MigrationOutcome::Current(Box::new(serde_json::from_value(
+            value,
+        )?)))
+    } else if value.get("bridge").is_some() && value.get("handshakeTopic").is_some() {
+        let info: V1SessionInfo = serde_json::from_value(value)?;
+        Ok(MigrationOutcome::RePair(info.into()))
+    } else {
+        Err(eyre!("not a WalletConnect session"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::Key;
+    use crate::protocol::Topic;
+
+    fn v1_session(connected: bool) -> V1SessionInfo {
+        V1SessionInfo {
+            connected,
+            accounts: vec![Address::repeat_byte(1)],
+            chain_id: Some(25),
+            bridge: "https://l.bridge.walletconnect.org".parse().unwrap(),
+            key: Key::random(),
+            client_id: Topic::new(),
+            client_meta: crate::Metadata {
+                description: "the game".to_owned(),
+                url: "https://game.example.test".parse().unwrap(),
+                icons: vec!["https://game.example.test/icon.png".parse().unwrap()],
+                name: "Game".to_owned(),
+            },
+            peer_id: Some(Topic::new()),
+            peer_meta: Some(PeerMetadata::Malformed(
+                serde_json::json!({"name": "DeFi Wallet"}),
+            )),
+            handshake_topic: Topic::new(),
+        }
+    }
+
+    #[test]
+    fn test_migrate_v1_session() {
+        let json = serde_json::to_string(&v1_session(true)).unwrap();
+        let MigrationOutcome::RePair(repairing) = migrate_session(&json).unwrap() else {
+            panic!("a 1.0 session must be paired again");
+        };
+        assert!(repairing.was_connected);
+        assert_eq!(repairing.accounts, vec![Address::repeat_byte(1)]);
+        assert_eq!(repairing.chain_id, Some(25));
+        assert_eq!(repairing.wallet_name.as_deref(), Some("DeFi Wallet"));
+        assert_eq!(repairing.client_meta.url, "https://game.example.test/");
+        let namespaces = repairing.required_namespaces(338);
+        assert_eq!(namespaces.eip155.chains, vec!["eip155:25"]);
+
+        let namespaces: Namespaces = serde_json::from_value(serde_json::json!({
+            "eip155": {
+                "accounts": [format!("eip155:25:{:?}", Address::repeat_byte(1))],
+                "methods": [],
+                "events": [],
+            }
+        }))
+        .unwrap();
+        assert!(repairing.keeps_account(&namespaces));
+
+        // the accounts of a session that wasn't approved aren't kept
+        let json = serde_json::to_string(&v1_session(false)).unwrap();
+        let MigrationOutcome::RePair(repairing) = migrate_session(&json).unwrap() else {
+            panic!("a 1.0 session must be paired again");
+        };
+        assert!(repairing.accounts.is_empty());
+        assert!(repairing.keeps_account(&namespaces));
+
+        assert!(migrate_session(r#"{"cursor": 1}"#).is_err());
+        assert!(migrate_session("not json").is_err());
+    }
+}
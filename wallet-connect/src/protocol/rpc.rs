@@ -95,6 +95,9 @@ pub struct Request<'a, T> {
     method: &'a str,
     #[serde(skip_serializing_if = "is_zst")]
     pub params: T,
+    /// the optional information for the wallet's prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RequestMetadata>,
 }
 
 impl<'a, T> Request<'a, T> {
@@ -105,8 +108,44 @@ impl<'a, T> Request<'a, T> {
             jsonrpc: "2.0",
             method,
             params,
+            metadata: None,
         }
     }
+
+    /// Attaches the information for the wallet's prompt
+    pub fn with_metadata(mut self, metadata: Option<RequestMetadata>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+/// the methods that the wallet asks the user to approve
+pub const USER_APPROVAL_METHODS: &[&str] = &[
+    "personal_sign",
+    "eth_sign",
+    "eth_signTypedData",
+    "eth_signTransaction",
+    "eth_sendTransaction",
+];
+
+/// the information attached to a signing request, so that the wallets
+/// that support it can display a richer prompt.
+/// It is not a part of the JSON-RPC request specification,
+/// so the wallets that do not support it ignore it.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RequestMetadata {
+    /// name of the dApp/game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// description of the dApp/game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// a link to the icon to display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Url>,
+    /// explanation of the request (e.g. "buy the sword for 10 CRO")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
 }
 
 /// the request coming in from the wallet
@@ -164,6 +203,14 @@ impl<T> Response<T> {
             data: ResponseData::Success { result },
         }
     }
+
+    pub fn error(id: u64, error: JsonRpcError) -> Self {
+        Self {
+            id,
+            jsonrpc: "2.0".into(),
+            data: ResponseData::Error { error },
+        }
+    }
 }
 
 /// the result of the request
@@ -217,3 +264,25 @@ impl fmt::Display for JsonRpcError {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_request_metadata_serialization() {
+        let request = Request::new(1, "personal_sign", vec!["0x00"]);
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"id":1,"jsonrpc":"2.0","method":"personal_sign","params":["0x00"]}"#
+        );
+        let request = request.with_metadata(Some(RequestMetadata {
+            message: Some("buy the sword".to_owned()),
+            ..Default::default()
+        }));
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"id":1,"jsonrpc":"2.0","method":"personal_sign","params":["0x00"],"metadata":{"message":"buy the sword"}}"#
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Copyright (c) 2020 Nicholas Rodrigues Lordello (licensed under the Apache License, Version 2.0)
 //! Modifications Copyright (c) 2022, Cronos Labs (licensed under the Apache License, Version 2.0)
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 use uuid::{self, Uuid};
@@ -20,6 +21,20 @@ impl Topic {
     pub fn zero() -> Self {
         Topic(Uuid::nil().to_string())
     }
+
+    /// a topic derived from the hash of this one
+    /// (the same topics are still recognizable in the redacted logs)
+    pub fn hashed(&self) -> Self {
+        Self::hash_of(&self.0)
+    }
+
+    /// a topic derived from the hash of the text (e.g. a WalletConnect 2.0 topic)
+    pub(crate) fn hash_of(topic: &str) -> Self {
+        let hash = Sha256::digest(topic.as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash[..16]);
+        Topic(Uuid::from_bytes(bytes).to_string())
+    }
 }
 
 impl Default for Topic {
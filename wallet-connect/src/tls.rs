@@ -0,0 +1,225 @@
+//! TLS customization for the outgoing connections
+//! (the WalletConnect 1.0 bridge and 2.0 relay WebSockets, and it can be shared
+//! with other HTTPS clients that accept a `rustls::ClientConfig`)
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use base64::{engine::general_purpose, Engine as _};
+use eyre::{eyre, Result};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+
+/// the TLS configuration set via `set_tls_options`
+static TLS_CONFIG: RwLock<Option<Arc<ClientConfig>>> = RwLock::new(None);
+
+/// The TLS options for the outgoing connections
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    /// additional trusted root certificates (DER-encoded),
+    /// they are added to the default webpki roots
+    pub root_certificates: Vec<Vec<u8>>,
+    /// SHA-256 hashes of the allowed SubjectPublicKeyInfo:
+    /// if not empty, one of the certificates in the server's chain
+    /// needs to match one of them
+    pub spki_sha256_pins: Vec<[u8; 32]>,
+}
+
+impl TlsOptions {
+    /// adds the root certificates from a PEM string (it can contain several certificates)
+    pub fn add_root_certificates_pem(&mut self, pem: &str) -> Result<()> {
+        let certs = rustls_pemfile::certs(&mut pem.as_bytes())?;
+        if certs.is_empty() && !pem.trim().is_empty() {
+            return Err(eyre!("no certificate found in PEM"));
+        }
+        self.root_certificates.extend(certs);
+        Ok(())
+    }
+
+    /// adds a base64-encoded SHA-256 SPKI pin (with an optional "sha256/" prefix),
+    /// e.g. the output of:
+    /// `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64`
+    pub fn add_spki_pin(&mut self, pin: &str) -> Result<()> {
+        let pin = pin.trim();
+        let encoded = pin.strip_prefix("sha256/").unwrap_or(pin);
+        let hash: [u8; 32] = general_purpose::STANDARD
+            .decode(encoded)?
+            .try_into()
+            .map_err(|_| eyre!("invalid SPKI pin length: {pin}"))?;
+        self.spki_sha256_pins.push(hash);
+        Ok(())
+    }
+
+    /// builds the rustls client configuration
+    pub fn client_config(&self) -> Result<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        for cert in self.root_certificates.iter() {
+            roots.add(&Certificate(cert.clone()))?;
+        }
+        let builder = ClientConfig::builder().with_safe_defaults();
+        let config = if self.spki_sha256_pins.is_empty() {
+            builder.with_root_certificates(roots).with_no_client_auth()
+        } else {
+            builder
+                .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier {
+                    inner: WebPkiVerifier::new(roots, None),
+                    pins: self.spki_sha256_pins.clone(),
+                }))
+                .with_no_client_auth()
+        };
+        Ok(config)
+    }
+}
+
+/// sets the TLS options used by the subsequent connections
+/// (`None` restores the defaults)
+pub fn set_tls_options(options: Option<TlsOptions>) -> Result<()> {
+    let config = match options {
+        Some(options) => Some(Arc::new(options.client_config()?)),
+        None => None,
+    };
+    *TLS_CONFIG.write().expect("tls config lock") = config;
+    Ok(())
+}
+
+/// returns the custom TLS configuration (if it was set)
+pub fn tls_config() -> Option<Arc<ClientConfig>> {
+    TLS_CONFIG.read().expect("tls config lock").clone()
+}
+
+/// the default webpki verification + the SPKI pin check
+struct PinnedCertVerifier {
+    inner: WebPkiVerifier,
+    pins: Vec<[u8; 32]>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+        let pinned = std::iter::once(end_entity)
+            .chain(intermediates.iter())
+            .filter_map(|cert| subject_public_key_info(&cert.0))
+            .any(|spki| {
+                let hash: [u8; 32] = Sha256::digest(spki).into();
+                self.pins.contains(&hash)
+            });
+        if pinned {
+            Ok(verified)
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match the pinned keys".to_owned(),
+            ))
+        }
+    }
+}
+
+/// returns the header length and the content length of a DER element
+fn der_header(data: &[u8]) -> Option<(usize, usize)> {
+    let first = *data.get(1)?;
+    if first < 0x80 {
+        return Some((2, first as usize));
+    }
+    let count = (first & 0x7f) as usize;
+    if count == 0 || count > 4 {
+        return None;
+    }
+    let len = data
+        .get(2..2 + count)?
+        .iter()
+        .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    Some((2 + count, len))
+}
+
+/// returns the content of the DER element (if it has the expected tag)
+fn der_enter(data: &[u8], tag: u8) -> Option<&[u8]> {
+    if *data.first()? != tag {
+        return None;
+    }
+    let (header, len) = der_header(data)?;
+    data.get(header..header + len)
+}
+
+/// returns the data after the first DER element
+fn der_skip(data: &[u8]) -> Option<&[u8]> {
+    let (header, len) = der_header(data)?;
+    data.get(header + len..)
+}
+
+/// returns the DER-encoded SubjectPublicKeyInfo of a X.509 certificate
+fn subject_public_key_info(cert: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const VERSION: u8 = 0xa0;
+    let tbs = der_enter(der_enter(cert, SEQUENCE)?, SEQUENCE)?;
+    let mut rest = tbs;
+    if *rest.first()? == VERSION {
+        rest = der_skip(rest)?;
+    }
+    // serialNumber, signature, issuer, validity, subject
+    for _ in 0..5 {
+        rest = der_skip(rest)?;
+    }
+    let (header, len) = der_header(rest)?;
+    rest.get(..header + len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBjzCCATWgAwIBAgIUf0blkSwGsl9vnae54o0pU8sxtfUwCgYIKoZIzj0EAwIw
+HDEaMBgGA1UEAwwRcGxheS1jcHAtc2RrLnRlc3QwIBcNMjYxMDE2MTQ1MzA5WhgP
+MjEyNjA5MjIxNDUzMDlaMBwxGjAYBgNVBAMMEXBsYXktY3BwLXNkay50ZXN0MFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEjJ4AI6uXDMgyRliDCDboLQADvL5Rqpbh
+ZCfVpMFl+VPm4fym7VAwt7xAHAUZAvxmmagQ0ie54Ycmh1rpl/LvnKNTMFEwHQYD
+VR0OBBYEFNOItpeP89sNWhkau014tLgvkJPLMB8GA1UdIwQYMBaAFNOItpeP89sN
+Whkau014tLgvkJPLMA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIg
+fnzLK4Ko9IE6N2y5TgIXwztlL3H+DYL4kZcI74IvdWUCIQCH5/J0DceyK3W55XMi
+jrVLNcs+Lco5VMp2YKXpk1Mw5g==
+-----END CERTIFICATE-----
+";
+    // openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64
+    const TEST_PIN: &str = "sha256/6S4hqYRnmJYnNfM77CVk3DNqI0nkfcLI82R4+910wd8=";
+
+    #[test]
+    fn test_spki_pin_matches_openssl() {
+        let mut options = TlsOptions::default();
+        options
+            .add_root_certificates_pem(TEST_CERT)
+            .expect("parse pem");
+        options.add_spki_pin(TEST_PIN).expect("parse pin");
+        let spki = subject_public_key_info(&options.root_certificates[0]).expect("spki");
+        let hash: [u8; 32] = Sha256::digest(spki).into();
+        assert_eq!(hash, options.spki_sha256_pins[0]);
+        assert!(options.client_config().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_tls_options() {
+        let mut options = TlsOptions::default();
+        assert!(options.add_spki_pin("c2hvcnQ=").is_err());
+        assert!(options.add_root_certificates_pem("not a pem").is_err());
+    }
+}
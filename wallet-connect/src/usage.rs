@@ -0,0 +1,117 @@
+//! The data usage of the bridge server connections (WalletConnect 1.0)
+//! and of the relay connections (WalletConnect 2.0), of all the clients
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// The counters of the messages of one kind of connection
+struct Counters {
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            messages_sent: AtomicU64::new(0),
+            messages_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+        }
+    }
+
+    fn usage(&self) -> Usage {
+        Usage {
+            messages_sent: self.messages_sent.load(Ordering::SeqCst),
+            messages_received: self.messages_received.load(Ordering::SeqCst),
+            bytes_sent: self.bytes_sent.load(Ordering::SeqCst),
+            bytes_received: self.bytes_received.load(Ordering::SeqCst),
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.messages_sent,
+            &self.messages_received,
+            &self.bytes_sent,
+            &self.bytes_received,
+        ] {
+            counter.store(0, Ordering::SeqCst);
+        }
+    }
+
+    fn record_sent(&self, bytes: usize) {
+        self.messages_sent.fetch_add(1, Ordering::SeqCst);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::SeqCst);
+        notify();
+    }
+
+    fn record_received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::SeqCst);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::SeqCst);
+        notify();
+    }
+}
+
+/// the messages of the bridge server connections
+static BRIDGE: Counters = Counters::new();
+/// the messages of the relay connections
+static RELAY: Counters = Counters::new();
+/// called after the usage changed (none if it's not set)
+static OBSERVER: RwLock<Option<fn()>> = RwLock::new(None);
+
+/// The data usage of the websocket messages (the pings aren't included)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// returns the usage of the bridge server connections since the start or the last `reset`
+pub fn usage() -> Usage {
+    BRIDGE.usage()
+}
+
+/// returns the usage of the relay connections since the start or the last `reset`
+pub fn relay_usage() -> Usage {
+    RELAY.usage()
+}
+
+/// resets the usage of the bridge server and the relay connections
+pub fn reset() {
+    BRIDGE.reset();
+    RELAY.reset();
+}
+
+/// sets the function called after each message (e.g. to check a budget);
+/// it's called on the task of the connection, so it shouldn't block
+pub fn set_observer(observer: Option<fn()>) {
+    *OBSERVER.write().expect("usage observer lock") = observer;
+}
+
+fn notify() {
+    let observer = *OBSERVER.read().expect("usage observer lock");
+    if let Some(observer) = observer {
+        observer();
+    }
+}
+
+pub(crate) fn record_sent(bytes: usize) {
+    BRIDGE.record_sent(bytes);
+}
+
+pub(crate) fn record_received(bytes: usize) {
+    BRIDGE.record_received(bytes);
+}
+
+pub(crate) fn record_relay_sent(bytes: usize) {
+    RELAY.record_sent(bytes);
+}
+
+pub(crate) fn record_relay_received(bytes: usize) {
+    RELAY.record_received(bytes);
+}
@@ -20,11 +20,13 @@ use url::Url;
 
 use crate::{hex, ClientError};
 
-use super::core::Connector;
-use super::protocol::{Namespaces, RequiredNamespaces};
-use super::session::SessionInfo;
+use super::core::{Connector, SessionProposalHandler};
+use super::protocol::{
+    CosmosSignDirectDoc, CosmosSignRequest, CosmosSignResponse, Namespaces, RequiredNamespaces,
+    COSMOS_SIGN_AMINO_METHOD, COSMOS_SIGN_DIRECT_METHOD,
+};
+use super::session::{RedactedSessionInfo, SessionInfo};
 use super::Metadata;
-use relay_client::Error;
 
 /// The WalletConnect 2.0 basic client options
 pub struct ClientOptions {
@@ -58,7 +60,7 @@ pub struct Client {
 
 impl Client {
     /// Creates a new client from the provided metadata
-    pub async fn new(opts: ClientOptions) -> Result<Self, Error> {
+    pub async fn new(opts: ClientOptions) -> eyre::Result<Self> {
         let session = SessionInfo::new(
             opts.relay_server,
             opts.project_id,
@@ -90,6 +92,12 @@ impl Client {
         connection.get_session_info().await
     }
 
+    /// get current session info without the keys and with the topics hashed
+    /// (for the logs or bug reports)
+    pub async fn get_session_info_redacted(&self) -> RedactedSessionInfo {
+        self.get_session_info().await.redacted()
+    }
+
     /// create qrcode from this string
     pub async fn get_connection_string(&self) -> String {
         let connection = self.connection.read().await;
@@ -110,15 +118,110 @@ impl Client {
             .ok_or_else(|| eyre!("No namespaces in session info"))
     }
 
+    /// Returns the last error of decrypting a message received from the relay
+    /// (a corrupted message or a message tampered with / encrypted with another key)
+    pub async fn last_crypto_error(&self) -> Option<super::CryptoError> {
+        self.connection.read().await.last_crypto_error()
+    }
+
+    /// Sets the largest accepted size of a relayed message (the encrypted envelope;
+    /// `DEFAULT_MAX_MESSAGE_SIZE` by default): the relay connections made after it
+    /// (e.g. on reconnecting) are closed when a larger message is received,
+    /// and sending the larger requests fails
+    pub async fn set_max_message_size(&self, max_message_size: usize) {
+        self.connection
+            .read()
+            .await
+            .set_max_message_size(max_message_size)
+    }
+
+    /// Returns the number of the relay connections closed for a message exceeding the size limit
+    pub async fn oversized_messages(&self) -> u64 {
+        self.connection.read().await.oversized_messages()
+    }
+
+    /// Returns the health of the relay connection (whether it's open, the round-trip time
+    /// of the last ping and the number of the reconnections)
+    pub async fn connection_status(&self) -> crate::ConnectionStatus {
+        self.connection.read().await.connection_status()
+    }
+
+    /// Returns whether the connected wallet granted the method on the chain
+    /// (false if the session is not established), e.g. to disable the features
+    /// the wallet doesn't support instead of failing at the request time
+    pub async fn session_supports(&self, method: String, chain_id: u64) -> bool {
+        self.get_session_info()
+            .await
+            .namespaces
+            .map(|namespaces| namespaces.supports(&method, chain_id))
+            .unwrap_or_default()
+    }
+
+    /// Sets the handler previewing the namespaces (accounts, methods and events)
+    /// settled by the wallet before `ensure_session` completes: if it returns false,
+    /// the session is rejected and `ensure_session` fails
+    /// (e.g. to require the Cronos mainnet account before any request is made)
+    pub async fn set_session_proposal_handler(
+        &self,
+        handler: impl Fn(&Namespaces) -> bool + Send + Sync + 'static,
+    ) {
+        self.connection
+            .read()
+            .await
+            .set_session_proposal_handler(Some(SessionProposalHandler::new(handler)))
+    }
+
+    /// Removes the session proposal handler (all the sessions are accepted)
+    pub async fn clear_session_proposal_handler(&self) {
+        self.connection
+            .read()
+            .await
+            .set_session_proposal_handler(None)
+    }
+
+    /// Enables (with an empty log) or disables the capture of the decrypted messages
+    /// exchanged with the wallet (the secrets are redacted)
+    pub async fn set_replay_capture(&self, enabled: bool) {
+        self.connection.read().await.set_replay_capture(enabled)
+    }
+
+    /// Returns the captured messages (see `crate::replay::to_json_lines`)
+    pub async fn replay_entries(&self) -> Vec<crate::replay::ReplayEntry> {
+        self.connection.read().await.replay_entries()
+    }
+
+    /// Sets how long the messages that couldn't be published
+    /// (e.g. while the relay connection was down) are queued; 60 seconds by default
+    pub async fn set_outbox_ttl(&self, ttl: std::time::Duration) {
+        self.connection.read().await.set_outbox_ttl(ttl)
+    }
+
+    /// Returns the messages queued while they couldn't be published (oldest first)
+    pub async fn queued_messages(&self) -> Vec<super::OutboundMessage> {
+        self.connection.read().await.queued_messages()
+    }
+
+    /// Removes the queued messages (their requests fail), returns their number
+    pub async fn clear_queued_messages(&self) -> usize {
+        self.connection.read().await.clear_queued_messages()
+    }
+
+    /// Reconnects to the relay server (e.g. after the connection was lost),
+    /// renews the subscriptions and publishes the queued messages
+    pub async fn reconnect(&self) -> eyre::Result<()> {
+        self.connection.read().await.reconnect().await
+    }
+
     pub async fn send_ping(&mut self) -> Result<String, eyre::Error> {
         let mut connection = self.connection.write().await;
         connection.send_ping().await
     }
 
     /// Send a request to sign a message as per https://eips.ethereum.org/EIPS/eip-1271
+    /// (the message can be a text or arbitrary bytes, the wallet applies the EIP-191 prefix)
     pub async fn personal_sign(
         &mut self,
-        message: &str,
+        message: impl AsRef<[u8]>,
         address: &Address,
     ) -> Result<Signature, ClientError> {
         let sig_str: String = self
@@ -136,6 +239,77 @@ impl Client {
             .context("failed to parse signature")
             .map_err(ClientError::Eyre)
     }
+
+    /// Send a request to sign a 32-byte digest without the EIP-191 prefix (`eth_sign`)
+    /// NOTE: some wallets do not support it or still apply the prefix
+    pub async fn eth_sign(
+        &mut self,
+        digest: &[u8; 32],
+        address: &Address,
+    ) -> Result<Signature, ClientError> {
+        let sig_str: String = self
+            .request(
+                "eth_sign",
+                vec![format!("{address:?}"), format!("0x{}", hex::encode(digest))],
+            )
+            .await?;
+
+        Signature::from_str(&sig_str)
+            .context("failed to parse signature")
+            .map_err(ClientError::Eyre)
+    }
+}
+
+impl Client {
+    /// Send a request to sign the protobuf-encoded Cosmos transaction (`cosmos_signDirect`)
+    /// with the account of the signer address on the chain
+    /// (e.g. "crypto-org-chain-mainnet-1"; the Cosmos namespace has to be required)
+    pub async fn cosmos_sign_direct(
+        &self,
+        chain_id: &str,
+        signer_address: &str,
+        sign_doc: CosmosSignDirectDoc,
+    ) -> Result<CosmosSignResponse<CosmosSignDirectDoc>, ClientError> {
+        self.cosmos_sign(
+            chain_id,
+            COSMOS_SIGN_DIRECT_METHOD,
+            signer_address,
+            sign_doc,
+        )
+        .await
+    }
+
+    /// Send a request to sign the Amino JSON `StdSignDoc` (`cosmos_signAmino`)
+    /// with the account of the signer address on the chain
+    pub async fn cosmos_sign_amino(
+        &self,
+        chain_id: &str,
+        signer_address: &str,
+        sign_doc: serde_json::Value,
+    ) -> Result<CosmosSignResponse<serde_json::Value>, ClientError> {
+        self.cosmos_sign(chain_id, COSMOS_SIGN_AMINO_METHOD, signer_address, sign_doc)
+            .await
+    }
+
+    async fn cosmos_sign<D: Serialize + DeserializeOwned + Send + Sync>(
+        &self,
+        chain_id: &str,
+        method: &str,
+        signer_address: &str,
+        sign_doc: D,
+    ) -> Result<CosmosSignResponse<D>, ClientError> {
+        let chain_id = if chain_id.starts_with("cosmos:") {
+            chain_id.to_owned()
+        } else {
+            format!("cosmos:{chain_id}")
+        };
+        let params = CosmosSignRequest {
+            signer_address: signer_address.to_owned(),
+            sign_doc,
+        };
+        let connection = self.connection.read().await;
+        connection.session_request(chain_id, method, params).await
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
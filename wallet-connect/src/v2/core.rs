@@ -1,7 +1,14 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use super::{
-    crypto::{decode_decrypt, encrypt_and_encode},
+    crypto::{decode_decrypt, encrypt_and_encode, CryptoError},
     protocol::{
         WcSessionDelete, WcSessionExtend, WcSessionPing, WcSessionProposeResponse,
         WcSessionRequest, WcSessionSettle, WcSessionUpdate, WC_SESSION_DELETE_RESPONSE_TAG,
@@ -9,13 +16,15 @@ use super::{
         WC_SESSION_PING_REQUEST_METHOD, WC_SESSION_PING_REQUEST_TAG, WC_SESSION_PING_RESPONSE_TAG,
         WC_SESSION_PROPOSE_REQUEST_METHOD, WC_SESSION_PROPOSE_REQUEST_TAG,
         WC_SESSION_REQUEST_METHOD, WC_SESSION_REQUEST_TAG, WC_SESSION_SETTLE_RESPONSE_TAG,
-        WC_SESSION_UPDATE_RESPONSE_TAG,
+        WC_SESSION_UPDATE_RESPONSE_TAG, WC_USER_REJECTED_CODE,
     },
     session::SessionInfo,
+    tunnel::RelayTunnel,
 };
 use crate::crypto::Key;
-use crate::v2::WcSessionPropose;
-use crate::{v2::WcSessionEvent, ClientError, Request, Response};
+use crate::replay::{Direction, ReplayEntry, ReplayLog};
+use crate::v2::{Namespaces, WcSessionPropose};
+use crate::{v2::WcSessionEvent, ClientError, JsonRpcError, Request, Response};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use ethers::providers::JsonRpcClient;
@@ -29,6 +38,55 @@ use relay_rpc::{
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::{mpsc, oneshot, Mutex, Notify};
 
+/// the default limit of the relayed message size (the encrypted and encoded envelope):
+/// large enough for the session proposals / responses with many chains and accounts
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// A message published to the relay
+/// (it's queued while it can't be published, e.g. when the relay connection is down)
+#[derive(Debug, Clone)]
+pub struct OutboundMessage {
+    /// the topic it's published on
+    pub topic: Topic,
+    /// the encrypted and encoded message
+    pub message: String,
+    /// the relay tag of the message type
+    pub tag: u32,
+    /// the id of the request awaiting its response (none for the responses)
+    pub request_id: Option<u64>,
+    /// when it was first sent
+    pub created: Instant,
+}
+
+impl OutboundMessage {
+    fn new(topic: Topic, message: String, tag: u32, request_id: Option<u64>) -> Self {
+        Self {
+            topic,
+            message,
+            tag,
+            request_id,
+            created: Instant::now(),
+        }
+    }
+}
+
+/// Decides whether the session settled by the wallet is accepted
+/// (e.g. to require some chains or methods); the rejected sessions are not established
+#[derive(Clone)]
+pub struct SessionProposalHandler(Arc<dyn Fn(&Namespaces) -> bool + Send + Sync>);
+
+impl SessionProposalHandler {
+    pub fn new(handler: impl Fn(&Namespaces) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl std::fmt::Debug for SessionProposalHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SessionProposalHandler")
+    }
+}
+
 /// This `Context` holds the wallet-connect client state
 #[derive(Debug)]
 pub struct Context {
@@ -50,8 +108,35 @@ pub struct Context {
     /// and the response is sent to the receiver via the one-shot channel.
     pub pending_requests: DashMap<u64, oneshot::Sender<serde_json::Value>>,
     /// the map of existing subscriptions
-    /// (currently unused; but may be used for deleting subscriptions etc.)
+    /// (used for unsubscribing from the topics no longer used after the key rotations)
     pub subscriptions: DashMap<Topic, SubscriptionId>,
+    /// the last error of decrypting a received message
+    /// (e.g. for telling the corrupted messages from the tampered ones)
+    pub last_crypto_error: std::sync::Mutex<Option<CryptoError>>,
+    /// the largest accepted size of a relayed message: the relay connections
+    /// (made after it's set) are limited to it, so that the connection receiving
+    /// a larger message is closed before the message is buffered and decoded,
+    /// and the larger outgoing ones are rejected
+    pub max_message_size: AtomicUsize,
+    /// the number of the relay connections closed for exceeding `max_message_size`
+    pub oversized_messages: AtomicU64,
+    /// the number of the open relay connections
+    pub relay_connections: AtomicU64,
+    /// the number of the relay connections made (the ones after the first are reconnections)
+    pub relay_connects: AtomicU64,
+    /// the round-trip time (in microseconds) of the last ping answered by the relay
+    /// (0 if none was answered)
+    pub relay_ping_rtt_us: AtomicU64,
+    /// the messages that couldn't be published (e.g. while the relay connection was down);
+    /// they're published again after reconnecting
+    pub outbox: std::sync::Mutex<VecDeque<OutboundMessage>>,
+    /// how long the messages are kept in the outbox
+    /// (the requests of the expired ones fail)
+    pub outbox_ttl: std::sync::Mutex<Duration>,
+    /// previews the wallet's namespaces before the session is settled (all accepted if none)
+    pub session_proposal_handler: std::sync::Mutex<Option<SessionProposalHandler>>,
+    /// the captured messages (none if the capture is disabled)
+    pub replay_log: std::sync::Mutex<Option<ReplayLog>>,
 }
 
 /// `SharedContext` holds the thread-safe reference to the wallet-connect client state
@@ -65,9 +150,101 @@ impl Context {
             session: Mutex::new(session),
             session_pending_notify: Notify::new(),
             pending_requests_timeout: Duration::from_millis(60000),
+            outbox: std::sync::Mutex::new(VecDeque::new()),
+            outbox_ttl: std::sync::Mutex::new(Duration::from_millis(60000)),
             pending_requests_limit: 2,
             pending_requests: DashMap::new(),
             subscriptions: DashMap::new(),
+            last_crypto_error: std::sync::Mutex::new(None),
+            max_message_size: AtomicUsize::new(DEFAULT_MAX_MESSAGE_SIZE),
+            oversized_messages: AtomicU64::new(0),
+            relay_connections: AtomicU64::new(0),
+            relay_connects: AtomicU64::new(0),
+            relay_ping_rtt_us: AtomicU64::new(0),
+            session_proposal_handler: std::sync::Mutex::new(None),
+            replay_log: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// records the decrypted message if the capture is enabled
+    fn capture(&self, direction: Direction, topic: &Topic, payload: &[u8]) {
+        if let Some(log) = self.replay_log.lock().expect("replay log lock").as_mut() {
+            log.record(direction, topic.as_ref(), payload);
+        }
+    }
+
+    /// locks the outbox after dropping the expired messages
+    /// (their pending requests are removed, so they fail)
+    fn lock_outbox(&self) -> std::sync::MutexGuard<'_, VecDeque<OutboundMessage>> {
+        let ttl = *self.outbox_ttl.lock().expect("outbox ttl lock");
+        let mut outbox = self.outbox.lock().expect("outbox lock");
+        outbox.retain(|queued| {
+            let expired = queued.created.elapsed() >= ttl;
+            if let (true, Some(request_id)) = (expired, queued.request_id) {
+                self.pending_requests.remove(&request_id);
+            }
+            !expired
+        });
+        outbox
+    }
+
+    /// the queued messages (oldest first)
+    pub fn queued_messages(&self) -> Vec<OutboundMessage> {
+        self.lock_outbox().iter().cloned().collect()
+    }
+
+    /// removes the queued messages (their pending requests fail),
+    /// returns the number of the removed messages
+    pub fn clear_queued_messages(&self) -> usize {
+        let removed: Vec<OutboundMessage> = self.lock_outbox().drain(..).collect();
+        for request_id in removed.iter().filter_map(|queued| queued.request_id) {
+            self.pending_requests.remove(&request_id);
+        }
+        removed.len()
+    }
+
+    /// publishes the message or, if it fails, queues it
+    async fn publish(&self, client: &Client, outbound: OutboundMessage) {
+        let published = client
+            .publish(
+                outbound.topic.clone(),
+                outbound.message.as_str(),
+                outbound.tag,
+                self.pending_requests_timeout,
+            )
+            .await;
+        if published.is_err() {
+            self.lock_outbox().push_back(outbound);
+        }
+    }
+
+    /// encrypts and encodes the outgoing message of the topic, fails if it exceeds
+    /// the size limit (it's captured if the capture is enabled)
+    fn seal(&self, topic: &Topic, key: &Key, plain: &[u8]) -> eyre::Result<String> {
+        let message = encrypt_and_encode(key, plain);
+        let max_message_size = self.max_message_size.load(Ordering::Relaxed);
+        if message.len() > max_message_size {
+            eyre::bail!(
+                "message too large: {} bytes (max {max_message_size})",
+                message.len()
+            );
+        }
+        self.capture(Direction::Outgoing, topic, plain);
+        Ok(message)
+    }
+
+    /// decrypts the message received on the topic and records the error if it fails
+    /// (it's captured if the capture is enabled)
+    fn decrypt(&self, topic: &Topic, key: &Key, message: &str) -> Option<Vec<u8>> {
+        match decode_decrypt(key, message) {
+            Ok(plain) => {
+                self.capture(Direction::Incoming, topic, &plain);
+                Some(plain)
+            }
+            Err(e) => {
+                *self.last_crypto_error.lock().expect("crypto error lock") = Some(e);
+                None
+            }
         }
     }
 
@@ -80,9 +257,14 @@ impl Context {
         let response_str = serde_json::to_string(&argresponse)?;
         let session = self.session.lock().await;
         if let Some((t, key)) = &session.pairing_topic_symkey {
-            let message = encrypt_and_encode(key, response_str.as_bytes());
+            let message = self.seal(t, key, response_str.as_bytes())?;
             let _ = sender
-                .send(ConnectorMessage::Publish(t.clone(), message, tag))
+                .send(ConnectorMessage::Publish(OutboundMessage::new(
+                    t.clone(),
+                    message,
+                    tag,
+                    None,
+                )))
                 .await;
         }
         Ok(())
@@ -126,6 +308,28 @@ impl Context {
         sender: &mpsc::Sender<ConnectorMessage>,
     ) -> eyre::Result<()> {
         let request = serde_json::from_slice::<Request<WcSessionSettle>>(plain)?;
+        let handler = self
+            .session_proposal_handler
+            .lock()
+            .expect("session proposal handler lock")
+            .clone();
+        if let Some(SessionProposalHandler(accepts)) = handler {
+            if !accepts(&request.params.namespaces) {
+                let response = Response::<()>::error(
+                    request.id,
+                    JsonRpcError {
+                        code: WC_USER_REJECTED_CODE,
+                        message: "User rejected.".to_owned(),
+                        data: None,
+                    },
+                );
+                self.send_response(response, sender, WC_SESSION_SETTLE_RESPONSE_TAG)
+                    .await?;
+                // `ensure_session` fails as the session is not connected
+                self.session_pending_notify.notify_waiters();
+                return Ok(());
+            }
+        }
         {
             let response = Response::new(request.id, true);
 
@@ -185,9 +389,21 @@ impl Context {
             session.session_update(request.params.clone());
         }
 
+        // the response is still sent with the key of the request
         let response = Response::new(request.id, true);
         self.send_response(response, sender, WC_SESSION_UPDATE_RESPONSE_TAG)
             .await?;
+        if let Some(public_key) = &request.params.public_key {
+            // the subscriptions are queued under the lock,
+            // so that no message is published with the new key before them
+            let mut session = self.session.lock().await;
+            if let Some((topic, unused)) = session.rekey(public_key) {
+                let _ = sender.send(ConnectorMessage::Subscribe(topic)).await;
+                if let Some(unused) = unused {
+                    let _ = sender.send(ConnectorMessage::Unsubscribe(unused)).await;
+                }
+            }
+        }
         self.send_callback(request, callback_sender).await?;
         Ok(())
     }
@@ -244,7 +460,11 @@ impl Context {
         sender: &mpsc::Sender<ConnectorMessage>,
     ) -> eyre::Result<()> {
         let session = self.session.lock().await;
-        if let Some((t, _key)) = &session.pairing_topic_symkey {
+        let topics = [
+            &session.pairing_topic_symkey,
+            &session.previous_pairing_topic_symkey,
+        ];
+        for (t, _key) in topics.into_iter().flatten() {
             let _ = sender.send(ConnectorMessage::Subscribe(t.clone())).await;
         }
 
@@ -306,12 +526,12 @@ impl ConnectionHandler for MessageHandler {
 
         tokio::spawn(async move {
             let session = context.session.lock().await;
-            match (&message.topic, &session.pairing_topic_symkey) {
+            match (&message.topic, session.pairing_key(&message.topic)) {
                 // this case is for the session proposal
                 // so expecting the session proposal response there
                 (t, _) if t == &session.session_proposal_topic => {
-                    if let Ok(plain) =
-                        decode_decrypt(&session.session_proposal_symkey, &message.message)
+                    if let Some(plain) =
+                        context.decrypt(t, &session.session_proposal_symkey, &message.message)
                     {
                         drop(session);
                         let _ = context
@@ -321,8 +541,9 @@ impl ConnectionHandler for MessageHandler {
                 }
                 // this case is for the session settlement and normal requests
                 // (and events? TODO: check if session updates are sent here)
-                (t1, Some((t2, key))) if t1 == t2 => {
-                    if let Ok(plain) = decode_decrypt(key, &message.message) {
+                // on the current pairing topic or the previous one (before the key rotation)
+                (_, Some(key)) => {
+                    if let Some(plain) = context.decrypt(&message.topic, &key, &message.message) {
                         drop(session);
                         let plain = plain.as_slice();
                         let plainjson = serde_json::from_slice::<serde_json::Value>(plain).unwrap();
@@ -432,8 +653,10 @@ pub struct Connector {
 /// messages processed in the task loop
 #[derive(Debug)]
 enum ConnectorMessage {
-    Publish(Topic, String, u32),
+    Publish(OutboundMessage),
     Subscribe(Topic),
+    Unsubscribe(Topic),
+    Reconnect(oneshot::Sender<eyre::Result<()>>),
 }
 
 impl Connector {
@@ -449,6 +672,94 @@ impl Connector {
         session.clone()
     }
 
+    /// the last error of decrypting a received message (if any)
+    pub fn last_crypto_error(&self) -> Option<CryptoError> {
+        self.context
+            .last_crypto_error
+            .lock()
+            .expect("crypto error lock")
+            .clone()
+    }
+
+    /// sets the largest accepted size of a relayed message
+    pub fn set_max_message_size(&self, max_message_size: usize) {
+        self.context
+            .max_message_size
+            .store(max_message_size, Ordering::Relaxed);
+    }
+
+    /// the number of the relay connections closed for a message exceeding the size limit
+    pub fn oversized_messages(&self) -> u64 {
+        self.context.oversized_messages.load(Ordering::Relaxed)
+    }
+
+    /// the health of the relay connection
+    pub fn connection_status(&self) -> crate::ConnectionStatus {
+        let rtt = self.context.relay_ping_rtt_us.load(Ordering::Relaxed);
+        crate::ConnectionStatus {
+            connected: self.context.relay_connections.load(Ordering::Relaxed) > 0,
+            last_ping_rtt: (rtt > 0).then(|| Duration::from_micros(rtt)),
+            reconnect_count: self
+                .context
+                .relay_connects
+                .load(Ordering::Relaxed)
+                .saturating_sub(1),
+        }
+    }
+
+    /// sets the handler previewing the wallet's namespaces before the session is settled
+    pub fn set_session_proposal_handler(&self, handler: Option<SessionProposalHandler>) {
+        *self
+            .context
+            .session_proposal_handler
+            .lock()
+            .expect("session proposal handler lock") = handler;
+    }
+
+    /// enables (with an empty log) or disables the capture of the messages
+    pub fn set_replay_capture(&self, enabled: bool) {
+        *self.context.replay_log.lock().expect("replay log lock") =
+            enabled.then(ReplayLog::default);
+    }
+
+    /// the captured messages (empty if the capture is disabled)
+    pub fn replay_entries(&self) -> Vec<ReplayEntry> {
+        self.context
+            .replay_log
+            .lock()
+            .expect("replay log lock")
+            .as_ref()
+            .map(ReplayLog::entries)
+            .unwrap_or_default()
+    }
+
+    /// sets how long the messages that couldn't be published are queued
+    pub fn set_outbox_ttl(&self, ttl: Duration) {
+        *self.context.outbox_ttl.lock().expect("outbox ttl lock") = ttl;
+    }
+
+    /// the messages queued while they couldn't be published (oldest first)
+    pub fn queued_messages(&self) -> Vec<OutboundMessage> {
+        self.context.queued_messages()
+    }
+
+    /// removes the queued messages (their requests fail), returns their number
+    pub fn clear_queued_messages(&self) -> usize {
+        self.context.clear_queued_messages()
+    }
+
+    /// reconnects to the relay server (e.g. after the connection was lost),
+    /// renews the subscriptions and publishes the queued messages
+    pub async fn reconnect(&self) -> eyre::Result<()> {
+        let (result_sender, result_receiver) = oneshot::channel();
+        self.sender
+            .send(ConnectorMessage::Reconnect(result_sender))
+            .await
+            .map_err(|e| eyre::eyre!("{e}"))?;
+        result_receiver.await??;
+        Ok(())
+    }
+
     pub async fn do_request<T: Serialize>(
         &self,
         topic: Topic,
@@ -461,21 +772,48 @@ impl Connector {
         let req = Request::new(request_id, method, params);
         use eyre::Context;
         let request_str = serde_json::to_string(&req).wrap_err("serialize request")?;
-        let message = encrypt_and_encode(key, request_str.as_bytes());
+        let message = self.context.seal(&topic, key, request_str.as_bytes())?;
 
         let (msgsender, msgreceiver) = oneshot::channel();
         self.context.pending_requests.insert(request_id, msgsender);
 
         self.sender
-            .send(ConnectorMessage::Publish(
+            .send(ConnectorMessage::Publish(OutboundMessage::new(
                 topic.clone(),
                 message.clone(),
                 tag,
-            ))
+                Some(request_id),
+            )))
             .await
             .map_err(|e| ClientError::Eyre(eyre::eyre!(e)))?;
-        let receivedpacket = msgreceiver.await?;
-        Ok(receivedpacket)
+        self.wait_response(request_id, msgreceiver).await
+    }
+
+    /// waits for the response of the request, or fails (and removes the pending
+    /// request) after the same timeout as the 1.0 requests
+    /// (the queued ones may expire earlier)
+    async fn wait_response(
+        &self,
+        request_id: u64,
+        receiver: oneshot::Receiver<serde_json::Value>,
+    ) -> eyre::Result<serde_json::Value> {
+        let timeout = self.context.pending_requests_timeout;
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(receivedpacket) => Ok(receivedpacket?),
+            Err(_) => {
+                self.context.pending_requests.remove(&request_id);
+                Err(eyre::eyre!(
+                    "{}",
+                    serde_json::json!({
+                        "code": -32000,
+                        "payload": {
+                            "reason": "Request is dropped because of timeout",
+                            "timeout": timeout.as_millis() as u64,
+                        }
+                    })
+                ))
+            }
+        }
     }
 
     pub async fn send_ping(&mut self) -> eyre::Result<String> {
@@ -527,6 +865,10 @@ impl Connector {
         let key: Key = session.session_proposal_symkey.clone();
         drop(session);
 
+        // the wallet may settle the session right after its response to the proposal
+        let settled = self.context.session_pending_notify.notified();
+        tokio::pin!(settled);
+        settled.as_mut().enable();
         let response = self
             .do_request(
                 topic,
@@ -544,7 +886,12 @@ impl Connector {
         }
 
         // wait for the session settle request
-        self.context.session_pending_notify.notified().await;
+        settled.await;
+        if !self.context.session.lock().await.connected {
+            return Err(eyre::eyre!(
+                "EnsureSessionFail the session was rejected by the proposal handler"
+            ));
+        }
         Ok(())
     }
 
@@ -552,7 +899,7 @@ impl Connector {
     pub async fn new_client(
         session: SessionInfo,
         callback_sender: Option<tokio::sync::mpsc::UnboundedSender<String>>,
-    ) -> Result<Self, Error> {
+    ) -> eyre::Result<Self> {
         let mut relay_address = session.relay_server.clone().to_string();
         // remove "/"
         relay_address.pop();
@@ -562,8 +909,24 @@ impl Connector {
         let handler = MessageHandler::new(context.clone(), sender.clone(), callback_sender);
         let client = Client::new(handler);
         let auth = session.auth_jwt.clone();
-        let opts = ConnectionOptions::new(project_id, auth).with_address(relay_address);
-        client.connect(opts).await?;
+        // a tunnel accepts one connection, so each connection has its own
+        let connect = {
+            let context = context.clone();
+            let client = client.clone();
+            move || {
+                let (relay_address, project_id, auth) =
+                    (relay_address.clone(), project_id.clone(), auth.clone());
+                let (context, client) = (context.clone(), client.clone());
+                async move {
+                    let tunnel = RelayTunnel::start(&relay_address, context).await?;
+                    let opts =
+                        ConnectionOptions::new(project_id, auth).with_address(tunnel.address());
+                    client.connect(opts).await?;
+                    eyre::Ok(tunnel)
+                }
+            }
+        };
+        let mut tunnel = connect().await?;
 
         let task_context = context.clone();
         // a task loop to handle messages
@@ -571,10 +934,8 @@ impl Connector {
         let _task_handler = tokio::spawn(async move {
             loop {
                 match receiver.recv().await {
-                    Some(ConnectorMessage::Publish(topic, message, tag)) => {
-                        let _ = client
-                            .publish(topic, message, tag, task_context.pending_requests_timeout)
-                            .await;
+                    Some(ConnectorMessage::Publish(outbound)) => {
+                        task_context.publish(&client, outbound).await;
                     }
                     Some(ConnectorMessage::Subscribe(topic)) => {
                         let sid = client.subscribe(topic.clone()).await;
@@ -582,6 +943,36 @@ impl Connector {
                             task_context.subscriptions.insert(topic, id);
                         }
                     }
+                    Some(ConnectorMessage::Unsubscribe(topic)) => {
+                        if let Some((topic, id)) = task_context.subscriptions.remove(&topic) {
+                            let _ = client.unsubscribe(topic, id).await;
+                        }
+                    }
+                    Some(ConnectorMessage::Reconnect(result_sender)) => {
+                        let result = connect().await.map(|reconnected| {
+                            // the relay connection goes through it while the client is used
+                            tunnel = reconnected;
+                        });
+                        if result.is_ok() {
+                            // the subscriptions don't survive the connection
+                            let topics: Vec<Topic> = task_context
+                                .subscriptions
+                                .iter()
+                                .map(|subscription| subscription.key().clone())
+                                .collect();
+                            for topic in topics {
+                                if let Ok(id) = client.subscribe(topic.clone()).await {
+                                    task_context.subscriptions.insert(topic, id);
+                                }
+                            }
+                            let queued: Vec<OutboundMessage> =
+                                task_context.lock_outbox().drain(..).collect();
+                            for outbound in queued {
+                                task_context.publish(&client, outbound).await;
+                            }
+                        }
+                        let _ = result_sender.send(result);
+                    }
                     None => {
                         break;
                     }
@@ -609,11 +1000,6 @@ impl JsonRpcClient for Connector {
         params: T,
     ) -> Result<R, ClientError> {
         let session = self.context.session.lock().await;
-        let topickey = if let Some((topic, key)) = session.pairing_topic_symkey.as_ref() {
-            Some((topic.clone(), key.clone()))
-        } else {
-            None
-        };
         // get chain id or default (cronos mainnet)
         let chain_id = session
             .required_namespaces
@@ -624,6 +1010,26 @@ impl JsonRpcClient for Connector {
             .unwrap_or_else(|| "eip155:25".to_owned());
         // release the lock
         drop(session);
+        self.session_request(chain_id, method, params).await
+    }
+}
+
+impl Connector {
+    /// Sends the request of the method to the wallet (via `wc_sessionRequest`)
+    /// on the chain (e.g. "eip155:25" or "cosmos:crypto-org-chain-mainnet-1")
+    pub async fn session_request<T: Serialize + Send + Sync, R: DeserializeOwned>(
+        &self,
+        chain_id: String,
+        method: &str,
+        params: T,
+    ) -> Result<R, ClientError> {
+        let topickey = self
+            .context
+            .session
+            .lock()
+            .await
+            .pairing_topic_symkey
+            .clone();
         // if pairing was established, we should have a topic + symmetric key
         if let Some((topic, key)) = topickey {
             let request_id = get_safe_random();
@@ -631,20 +1037,19 @@ impl JsonRpcClient for Connector {
             let req = Request::new(request_id, WC_SESSION_REQUEST_METHOD, params);
             use eyre::Context;
             let request_str = serde_json::to_string(&req).wrap_err("serialize request")?;
-            let message = encrypt_and_encode(&key, request_str.as_bytes());
+            let message = self.context.seal(&topic, &key, request_str.as_bytes())?;
             let (sender, receiver) = oneshot::channel();
             self.context.pending_requests.insert(request_id, sender);
             self.sender
-                .send(ConnectorMessage::Publish(
+                .send(ConnectorMessage::Publish(OutboundMessage::new(
                     topic.clone(),
                     message,
                     WC_SESSION_REQUEST_TAG,
-                ))
-                .await
-                .map_err(|e| ClientError::Eyre(eyre::eyre!(e)))?;
-            let response = receiver
+                    Some(request_id),
+                )))
                 .await
                 .map_err(|e| ClientError::Eyre(eyre::eyre!(e)))?;
+            let response = self.wait_response(request_id, receiver).await?;
             let resp: R = serde_json::from_value(response).wrap_err("failed to parse response")?;
             Ok(resp)
         } else {
@@ -654,3 +1059,190 @@ impl JsonRpcClient for Connector {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::{Metadata, RequiredNamespaces};
+
+    fn test_context() -> Context {
+        Context::new(SessionInfo::new(
+            "wss://relay.walletconnect.com/".parse().unwrap(),
+            "project".to_owned(),
+            RequiredNamespaces::new(vec![], vec!["eip155:25".to_owned()], vec![]),
+            Metadata {
+                description: String::default(),
+                url: "https://example.com".to_owned(),
+                icons: vec![],
+                name: "test".to_owned(),
+            },
+        ))
+    }
+
+    #[test]
+    fn test_message_size_limit() {
+        let context = test_context();
+        let key = Key::from_raw([1u8; 32]);
+        let topic = Topic::generate();
+        assert!(context.seal(&topic, &key, &[0u8; 1000]).is_ok());
+
+        context.max_message_size.store(100, Ordering::Relaxed);
+        assert!(context
+            .seal(&topic, &key, &[0u8; 1000])
+            .unwrap_err()
+            .to_string()
+            .starts_with("message too large"));
+        assert!(context.seal(&topic, &key, b"{}").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_replay_capture() {
+        let context = test_context();
+        let key = Key::from_raw([3u8; 32]);
+        let topic = Topic::generate();
+        context.session.lock().await.pairing_topic_symkey = Some((topic.clone(), key.clone()));
+        // not captured while the capture is disabled
+        context.seal(&topic, &key, b"{}").unwrap();
+        *context.replay_log.lock().unwrap() = Some(ReplayLog::default());
+
+        let request = r#"{"id":1,"jsonrpc":"2.0","method":"wc_sessionPing","params":{}}"#;
+        let message = context.seal(&topic, &key, request.as_bytes()).unwrap();
+        let received = context.decrypt(&topic, &key, &message).unwrap();
+        // the handler's response
+        let (sender, mut receiver) = mpsc::channel(10);
+        context
+            .handle_session_ping_request(&received, &sender, None)
+            .await
+            .unwrap();
+        assert!(receiver.recv().await.is_some());
+
+        let entries = context
+            .replay_log
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .entries();
+        let directions: Vec<Direction> = entries.iter().map(|entry| entry.direction).collect();
+        assert_eq!(
+            directions,
+            vec![
+                Direction::Outgoing,
+                Direction::Incoming,
+                Direction::Outgoing
+            ]
+        );
+        assert_eq!(entries[1].message["method"], "wc_sessionPing");
+        assert_eq!(entries[2].message["id"], 1);
+        // the topics are hashed
+        assert_eq!(entries[0].topic, crate::Topic::hash_of(topic.as_ref()));
+    }
+
+    #[tokio::test]
+    async fn test_session_proposal_rejected() {
+        let settle = r#"{"id":1678415342621744,"jsonrpc":"2.0","method":"wc_sessionSettle","params":{"relay":{"protocol":"irn"},"namespaces":{"eip155":{"accounts":["eip155:5:0xcE915a3b937261853EE2C60B8010c22c295200B0"],"methods":["personal_sign"],"events":["chainChanged"]}},"requiredNamespaces":{"eip155":{"methods":["personal_sign"],"chains":["eip155:5"],"events":["chainChanged"]}},"controller":{"publicKey":"94f705551213e83822c9a0c29063bb79223eec36433ad411f2de7bbaa4ae496f","metadata":{"name":"React Wallet","description":"React Wallet for WalletConnect","url":"https://walletconnect.com/","icons":[]}},"expiry":1679020142}}"#;
+        let context = test_context();
+        let key = Key::from_raw([2u8; 32]);
+        context.session.lock().await.pairing_topic_symkey = Some((Topic::generate(), key.clone()));
+        // requires the Cronos mainnet
+        *context.session_proposal_handler.lock().unwrap() =
+            Some(SessionProposalHandler::new(|namespaces| {
+                namespaces.get_chain_ids().contains(&25)
+            }));
+        let (sender, mut receiver) = mpsc::channel(10);
+        context
+            .handle_session_settle_request(settle.as_bytes(), &sender)
+            .await
+            .unwrap();
+        assert!(!context.session.lock().await.connected);
+        let Some(ConnectorMessage::Publish(outbound)) = receiver.recv().await else {
+            panic!("no response");
+        };
+        let response: serde_json::Value =
+            serde_json::from_slice(&decode_decrypt(&key, &outbound.message).unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], WC_USER_REJECTED_CODE);
+
+        // accepted without the handler
+        *context.session_proposal_handler.lock().unwrap() = None;
+        context
+            .handle_session_settle_request(settle.as_bytes(), &sender)
+            .await
+            .unwrap();
+        assert!(context.session.lock().await.connected);
+    }
+
+    #[test]
+    fn test_outbox() {
+        let context = test_context();
+        let topic = Topic::generate();
+        let (sender, mut receiver) = oneshot::channel();
+        context.pending_requests.insert(1, sender);
+        context.lock_outbox().extend([
+            OutboundMessage::new(topic.clone(), "request".to_owned(), 1, Some(1)),
+            OutboundMessage::new(topic, "response".to_owned(), 2, None),
+        ]);
+        let queued = context.queued_messages();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].message, "request");
+        assert!(context.pending_requests.contains_key(&1));
+
+        // the expired messages are dropped and their requests fail
+        *context.outbox_ttl.lock().unwrap() = Duration::ZERO;
+        assert!(context.queued_messages().is_empty());
+        assert!(!context.pending_requests.contains_key(&1));
+        assert!(receiver.try_recv().is_err());
+
+        *context.outbox_ttl.lock().unwrap() = Duration::from_secs(60);
+        let (sender, _receiver) = oneshot::channel();
+        context.pending_requests.insert(2, sender);
+        context.lock_outbox().push_back(OutboundMessage::new(
+            Topic::generate(),
+            "request".to_owned(),
+            1,
+            Some(2),
+        ));
+        assert_eq!(context.clear_queued_messages(), 1);
+        assert!(context.queued_messages().is_empty());
+        assert!(context.pending_requests.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() {
+        let mut context = test_context();
+        context.pending_requests_timeout = Duration::from_millis(10);
+        let context = Arc::new(context);
+        // the messages are published, but never answered
+        let (sender, mut receiver) = mpsc::channel(10);
+        let connector = Connector {
+            context: context.clone(),
+            _task_handler: tokio::spawn(async move { while receiver.recv().await.is_some() {} }),
+            sender,
+        };
+        let error = connector
+            .do_request(
+                Topic::generate(),
+                &Key::from_raw([3u8; 32]),
+                WC_SESSION_PING_REQUEST_METHOD,
+                serde_json::json!({}),
+                WC_SESSION_PING_REQUEST_TAG,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("timeout"));
+        assert!(context.pending_requests.is_empty());
+
+        // the session requests too
+        context.session.lock().await.pairing_topic_symkey =
+            Some((Topic::generate(), Key::from_raw([3u8; 32])));
+        let error = connector
+            .session_request::<_, serde_json::Value>(
+                "eip155:25".to_owned(),
+                "personal_sign",
+                serde_json::json!([]),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("timeout"));
+        assert!(context.pending_requests.is_empty());
+    }
+}
@@ -6,6 +6,7 @@ use chacha20poly1305::{
 use hkdf::Hkdf;
 use relay_rpc::domain::Topic;
 use sha2::{Digest, Sha256};
+use thiserror::Error;
 use x25519_dalek::{PublicKey, StaticSecret};
 use zeroize::Zeroize;
 
@@ -46,23 +47,50 @@ pub fn derive_symkey_topic(responder_public_key: &str, secret: &Key) -> Option<(
 /// The first byte is a version byte, the next 12 bytes are the nonce
 /// (see https://docs.walletconnect.com/2.0/specs/clients/core/crypto/crypto-envelopes#type-0-envelope)
 pub fn encrypt_and_encode(key: &Key, data: &[u8]) -> String {
-    let cipher = ChaCha20Poly1305::new_from_slice(key.as_ref()).expect("correct key");
     let nonce = ChaCha20Poly1305::generate_nonce(OsRng {});
-    let ciphertext = cipher.encrypt(&nonce, data).expect("encryption");
+    seal_with_nonce(key, nonce.as_ref(), data)
+}
+
+/// Encrypt with the given nonce and encode the type 0 envelope
+/// (deterministic, so that it can be checked against the known envelopes)
+pub(crate) fn seal_with_nonce(key: &Key, nonce: &[u8; 12], data: &[u8]) -> String {
+    let cipher = ChaCha20Poly1305::new_from_slice(key.as_ref()).expect("correct key");
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(nonce), data)
+        .expect("encryption");
     let mut buf = vec![0];
-    buf.extend_from_slice(&nonce);
+    buf.extend_from_slice(nonce);
     buf.extend_from_slice(&ciphertext);
     general_purpose::STANDARD.encode(buf)
 }
 
+/// Errors that can occur when decoding and decrypting an envelope.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// the message is corrupted (not base64)
+    #[error("invalid base64 encoding: {0}")]
+    Base64(#[from] base64::DecodeError),
+    /// the message is corrupted (shorter than the version byte and the nonce)
+    #[error("envelope too short: {0} bytes")]
+    Truncated(usize),
+    /// the message was tampered with or encrypted with another key
+    #[error("unable to verify integrity of payload")]
+    Aead,
+}
+
 /// Decode using base64 and decrypt using ChaCha20Poly1305
 /// The first byte is a version byte, the next 12 bytes are the nonce
 /// (see https://docs.walletconnect.com/2.0/specs/clients/core/crypto/crypto-envelopes#type-0-envelope)
-pub fn decode_decrypt(key: &Key, data: &str) -> Result<Vec<u8>, ()> {
-    let decoded = general_purpose::STANDARD.decode(data).map_err(|_| ())?;
+pub fn decode_decrypt(key: &Key, data: &str) -> Result<Vec<u8>, CryptoError> {
+    let decoded = general_purpose::STANDARD.decode(data)?;
+    if decoded.len() < 13 {
+        return Err(CryptoError::Truncated(decoded.len()));
+    }
     let cipher = ChaCha20Poly1305::new_from_slice(key.as_ref()).expect("correct key");
     let nonce = Nonce::clone_from_slice(&decoded[1..13]);
-    cipher.decrypt(&nonce, &decoded[13..]).map_err(|_| ())
+    cipher
+        .decrypt(&nonce, &decoded[13..])
+        .map_err(|_| CryptoError::Aead)
 }
 
 #[cfg(test)]
@@ -71,7 +99,9 @@ mod test {
 
     use crate::crypto::Key;
 
-    use super::{decode_decrypt, derive_symkey_topic, encrypt_and_encode};
+    use super::{
+        decode_decrypt, derive_symkey_topic, encrypt_and_encode, seal_with_nonce, CryptoError,
+    };
 
     #[test]
     pub fn test_derive_topic() {
@@ -80,13 +110,80 @@ mod test {
             132, 149, 158, 189, 217, 78, 224, 11, 145, 159, 235, 198, 115,
         ];
         let key = Key::from_raw(dapp_secret);
-        let Some((topic, _)) = derive_symkey_topic("f22533e8a398c465569c04c14b853c86b63ad94ffa916861eb138819c8be475f", &key) else { panic!("can't derive topic") };
+        let Some((topic, _)) = derive_symkey_topic(
+            "f22533e8a398c465569c04c14b853c86b63ad94ffa916861eb138819c8be475f",
+            &key,
+        ) else {
+            panic!("can't derive topic")
+        };
         assert_eq!(
             topic.as_ref(),
             "1630ba5249b23659ee3d7e5f5561b784710bc50a0ef50869c774c831b68452d0"
         );
     }
 
+    #[test]
+    pub fn test_derive_symkey_known_answer() {
+        // the X25519 keys from RFC 7748 (section 6.1); the symmetric key is HKDF-SHA256
+        // of the shared secret (no salt and info) and the topic is its SHA-256 hash
+        let alice_secret =
+            hex::decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")
+                .unwrap();
+        let key = Key::from_raw(alice_secret.try_into().unwrap());
+        let (topic, sym_key) = derive_symkey_topic(
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f",
+            &key,
+        )
+        .expect("derive");
+        assert_eq!(
+            hex::encode(sym_key.as_ref()),
+            "ea1d8a20f476d1e1ec952ca42708b8f7161ce7c81eadf97e520e2b40333decd5"
+        );
+        assert_eq!(
+            topic.as_ref(),
+            "1ca1d70db64cab0f93de5934e27f7114e8e9fd7dd3c7145d81ce7f2dd2cd05c8"
+        );
+        assert!(derive_symkey_topic("de9edb7d", &key).is_none());
+    }
+
+    #[test]
+    pub fn test_seal_known_answer() {
+        let mut raw = [0u8; 32];
+        raw.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let key = Key::from_raw(raw);
+        let mut nonce = [0u8; 12];
+        nonce.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        let plaintext = br#"{"id":1,"jsonrpc":"2.0","method":"wc_sessionPing","params":{}}"#;
+        let envelope = seal_with_nonce(&key, &nonce, plaintext);
+        assert_eq!(
+            envelope,
+            "AAABAgMEBQYHCAkKC/LZYWQLLZRslelMnPZvfgDrSpDVf0SPlcT6SrEdr8Ie1qJbkLlt1OtEWxm2Nf3hRFyoiwScWtn2YZrjfO0y6nz7/sPCsNe70vIwxndFNw=="
+        );
+        assert_eq!(decode_decrypt(&key, &envelope).unwrap(), plaintext);
+        // only the tag for the empty payload
+        assert_eq!(
+            seal_with_nonce(&key, &nonce, b""),
+            "AAABAgMEBQYHCAkKCylaSYuIQaHF9V1NYG9zEVk="
+        );
+        // truncated or tampered envelopes
+        assert_eq!(
+            decode_decrypt(&key, "AAABAgME"),
+            Err(CryptoError::Truncated(6))
+        );
+        assert_eq!(
+            decode_decrypt(&key, "AAABAgMEBQYHCAkKCylaSYuIQaHF9V1NYG9zEVo="),
+            Err(CryptoError::Aead)
+        );
+        assert!(matches!(
+            decode_decrypt(&key, "not base64"),
+            Err(CryptoError::Base64(_))
+        ));
+        assert_eq!(
+            decode_decrypt(&Key::random(), &envelope),
+            Err(CryptoError::Aead)
+        );
+    }
+
     #[quickcheck]
     fn encode_decode_encrypt_decrypt(data: Vec<u8>) -> bool {
         let key = Key::random();
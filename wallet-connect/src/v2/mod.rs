@@ -1,9 +1,12 @@
 mod client;
 mod core;
-mod crypto;
+pub(crate) mod crypto;
 mod protocol;
 mod session;
+mod tunnel;
 
+pub use self::core::{OutboundMessage, SessionProposalHandler, DEFAULT_MAX_MESSAGE_SIZE};
 pub use client::*;
+pub use crypto::CryptoError;
 pub use protocol::*;
 pub use session::*;
@@ -51,7 +51,10 @@ pub struct Relay {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RequiredNamespaces {
     pub(crate) eip155: Eip155,
-    // FIXME: Cosmos, Solana, Stellar...
+    /// the Cosmos namespace (e.g. for signing the crypto.org chain transactions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cosmos: Option<Cosmos>,
+    // FIXME: Solana, Stellar...
 }
 
 impl RequiredNamespaces {
@@ -63,8 +66,34 @@ impl RequiredNamespaces {
                 chains,
                 events,
             },
+            cosmos: None,
         }
     }
+
+    /// Requires the Cosmos namespace too
+    /// (chains are the chain IDs prefixed with "cosmos:", e.g. "cosmos:crypto-org-chain-mainnet-1")
+    pub fn with_cosmos(
+        mut self,
+        methods: Vec<String>,
+        chains: Vec<String>,
+        events: Vec<String>,
+    ) -> Self {
+        self.cosmos = Some(Cosmos {
+            methods,
+            chains,
+            events,
+        });
+        self
+    }
+
+    /// the required EIP155 chain IDs (the chains that aren't "eip155:" and a number are skipped)
+    pub fn get_chain_ids(&self) -> Vec<u64> {
+        self.eip155
+            .chains
+            .iter()
+            .filter_map(|chain| chain.strip_prefix("eip155:")?.parse().ok())
+            .collect()
+    }
 }
 
 /// the required EIP155 namespace
@@ -78,6 +107,15 @@ pub struct Eip155 {
     events: Vec<String>,
 }
 
+/// the required Cosmos namespace
+/// chains are the chain IDs prefixed with "cosmos:"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Cosmos {
+    methods: Vec<String>,
+    pub(crate) chains: Vec<String>,
+    events: Vec<String>,
+}
+
 /// The response to the session proposal request.
 #[derive(Serialize, Deserialize)]
 pub struct WcSessionProposeResponse {
@@ -101,6 +139,9 @@ pub struct WcSessionSettle {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct WcSessionUpdate {
     pub namespaces: Namespaces,
+    /// the wallet's new public key (hexadecimal) if it rotated its key
+    #[serde(rename = "publicKey", default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -128,6 +169,9 @@ pub struct WcSessionDelete {
 
 /// https://docs.walletconnect.com/2.0/specs/clients/sign/rpc-methods#wc_sessionsettle
 pub const WC_SESSION_SETTLE_RESPONSE_TAG: u32 = 1103;
+/// the error code of the rejected requests
+/// ref: https://docs.walletconnect.com/2.0/specs/clients/sign/error-codes
+pub const WC_USER_REJECTED_CODE: i64 = 5000;
 pub const WC_SESSION_UPDATE_RESPONSE_TAG: u32 = 1105;
 pub const WC_SESSION_PING_RESPONSE_TAG: u32 = 1115;
 pub const WC_SESSION_DELETE_RESPONSE_TAG: u32 = 1113;
@@ -147,6 +191,8 @@ pub struct Peer {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Namespaces {
     pub(crate) eip155: NamespacesEip155,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) cosmos: Option<NamespacesCosmos>,
 }
 
 /// The address with EIP155 chain ID
@@ -190,6 +236,45 @@ impl Namespaces {
     pub fn get_ethereum_addresses(&self) -> Vec<Eip155AddressWithChainId> {
         self.eip155.accounts.clone()
     }
+
+    /// the chain IDs of the accounts (deduplicated, in the order of the accounts)
+    pub fn get_chain_ids(&self) -> Vec<u64> {
+        let mut chain_ids: Vec<u64> = vec![];
+        for account in self.eip155.accounts.iter() {
+            if !chain_ids.contains(&account.chain_id) {
+                chain_ids.push(account.chain_id);
+            }
+        }
+        chain_ids
+    }
+
+    /// the JSON-RPC methods granted by the wallet
+    pub fn get_methods(&self) -> Vec<String> {
+        self.eip155.methods.clone()
+    }
+
+    /// the events the wallet may emit
+    pub fn get_events(&self) -> Vec<String> {
+        self.eip155.events.clone()
+    }
+
+    /// the Cosmos accounts (e.g. "cosmos:crypto-org-chain-mainnet-1:cro1...")
+    pub fn get_cosmos_accounts(&self) -> Vec<String> {
+        self.cosmos
+            .as_ref()
+            .map(|cosmos| cosmos.accounts.clone())
+            .unwrap_or_default()
+    }
+
+    /// whether the wallet granted the method and an account on the chain
+    pub fn supports(&self, method: &str, chain_id: u64) -> bool {
+        self.eip155.methods.iter().any(|granted| granted == method)
+            && self
+                .eip155
+                .accounts
+                .iter()
+                .any(|account| account.chain_id == chain_id)
+    }
 }
 
 /// The EIP155 namespace
@@ -201,6 +286,15 @@ pub struct NamespacesEip155 {
     pub(crate) events: Vec<String>,
 }
 
+/// The Cosmos namespace
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NamespacesCosmos {
+    /// the accounts prefixed with "cosmos:" and the chain ID
+    pub(crate) accounts: Vec<String>,
+    pub(crate) methods: Vec<String>,
+    pub(crate) events: Vec<String>,
+}
+
 /// ref: https://docs.walletconnect.com/2.0/specs/clients/sign/rpc-methods#wc_sessionrequest
 pub const WC_SESSION_REQUEST_METHOD: &str = "wc_sessionRequest";
 /// ref: https://docs.walletconnect.com/2.0/specs/clients/sign/rpc-methods#wc_sessionrequest
@@ -232,11 +326,66 @@ pub struct WcSessionRequestData<T> {
     // expiry: Option<u64>,
 }
 
+/// ref: https://docs.walletconnect.com/2.0/advanced/rpc-reference/cosmos-rpc#cosmos_signdirect
+pub const COSMOS_SIGN_DIRECT_METHOD: &str = "cosmos_signDirect";
+/// ref: https://docs.walletconnect.com/2.0/advanced/rpc-reference/cosmos-rpc#cosmos_signamino
+pub const COSMOS_SIGN_AMINO_METHOD: &str = "cosmos_signAmino";
+
+/// The params of the Cosmos signing requests
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CosmosSignRequest<D> {
+    /// the bech32 address of the signer
+    pub signer_address: String,
+    pub sign_doc: D,
+}
+
+/// The protobuf sign doc of `cosmos_signDirect`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CosmosSignDirectDoc {
+    pub chain_id: String,
+    pub account_number: String,
+    /// base64-encoded `AuthInfo`
+    pub auth_info_bytes: String,
+    /// base64-encoded `TxBody`
+    pub body_bytes: String,
+}
+
+/// The public key of the Cosmos signer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CosmosPubKey {
+    /// e.g. "tendermint/PubKeySecp256k1"
+    #[serde(rename = "type")]
+    pub key_type: String,
+    /// base64-encoded
+    pub value: String,
+}
+
+/// The signature of the Cosmos signing requests
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CosmosSignature {
+    pub pub_key: CosmosPubKey,
+    /// base64-encoded
+    pub signature: String,
+}
+
+/// The response of the Cosmos signing requests:
+/// the signature and the sign doc signed by the wallet (it may differ, e.g. in the fee)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CosmosSignResponse<D> {
+    pub signature: CosmosSignature,
+    pub signed: D,
+}
+
 #[cfg(test)]
 mod test {
     use crate::Request;
 
-    use super::WcSessionSettle;
+    use super::{
+        CosmosSignDirectDoc, CosmosSignRequest, CosmosSignResponse, RequiredNamespaces,
+        WcSessionRequest, WcSessionSettle,
+    };
 
     #[test]
     pub fn test_deserialize_wc_settle() {
@@ -246,6 +395,12 @@ mod test {
         assert_eq!(data.namespaces.eip155.accounts.len(), 1);
         assert_eq!(data.namespaces.eip155.methods.len(), 5);
         assert_eq!(data.namespaces.eip155.events.len(), 2);
+        assert_eq!(data.namespaces.get_chain_ids(), vec![5]);
+        assert_eq!(data.namespaces.get_methods()[3], "personal_sign");
+        assert!(data.namespaces.supports("eth_signTypedData", 5));
+        assert!(!data.namespaces.supports("eth_signTypedData_v4", 5));
+        assert!(!data.namespaces.supports("eth_signTypedData", 25));
+        assert!(data.namespaces.get_cosmos_accounts().is_empty());
         assert_eq!(data.required_namespaces.eip155.methods.len(), 5);
         assert_eq!(data.required_namespaces.eip155.chains.len(), 1);
         assert_eq!(data.required_namespaces.eip155.events.len(), 2);
@@ -256,4 +411,77 @@ mod test {
                 .unwrap()
         );
     }
+
+    #[test]
+    pub fn test_cosmos_sign_direct() {
+        let required = RequiredNamespaces::new(vec![], vec!["eip155:25".to_owned()], vec![])
+            .with_cosmos(
+                vec!["cosmos_signDirect".to_owned()],
+                vec!["cosmos:crypto-org-chain-mainnet-1".to_owned()],
+                vec![],
+            );
+        assert_eq!(required.get_chain_ids(), vec![25]);
+        let required = serde_json::to_value(required).unwrap();
+        assert_eq!(
+            required["cosmos"]["chains"][0],
+            "cosmos:crypto-org-chain-mainnet-1"
+        );
+
+        let sign_doc = CosmosSignDirectDoc {
+            chain_id: "crypto-org-chain-mainnet-1".to_owned(),
+            account_number: "1".to_owned(),
+            auth_info_bytes: "CgoKCA==".to_owned(),
+            body_bytes: "CgQKAgoA".to_owned(),
+        };
+        let request = WcSessionRequest::new(
+            "cosmos_signDirect".to_owned(),
+            CosmosSignRequest {
+                signer_address: "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy".to_owned(),
+                sign_doc: sign_doc.clone(),
+            },
+            "cosmos:crypto-org-chain-mainnet-1".to_owned(),
+        );
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            serde_json::json!({
+                "request": {
+                    "method": "cosmos_signDirect",
+                    "params": {
+                        "signerAddress": "cro1u08u5dvtnpmlpdq333uj9tcj75yceggszxpnsy",
+                        "signDoc": {
+                            "chainId": "crypto-org-chain-mainnet-1",
+                            "accountNumber": "1",
+                            "authInfoBytes": "CgoKCA==",
+                            "bodyBytes": "CgQKAgoA"
+                        }
+                    }
+                },
+                "chainId": "cosmos:crypto-org-chain-mainnet-1"
+            })
+        );
+
+        let response: CosmosSignResponse<CosmosSignDirectDoc> = serde_json::from_str(
+            r#"{
+                "signature": {
+                    "pub_key": {
+                        "type": "tendermint/PubKeySecp256k1",
+                        "value": "AgSEjOuOr991QlHCORRmdE5ahVKeyBrmtgoYepCpQGOW"
+                    },
+                    "signature": "S7BJEbiXQ4vxvhA=="
+                },
+                "signed": {
+                    "chainId": "crypto-org-chain-mainnet-1",
+                    "accountNumber": "1",
+                    "authInfoBytes": "CgoKCA==",
+                    "bodyBytes": "CgQKAgoA"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.signed, sign_doc);
+        assert_eq!(
+            response.signature.pub_key.key_type,
+            "tendermint/PubKeySecp256k1"
+        );
+    }
 }
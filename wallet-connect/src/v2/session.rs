@@ -44,6 +44,10 @@ pub struct SessionInfo {
     pub pairing_topic_symkey: Option<(Topic, Key)>,
     /// the wallet's metadata
     pub pairing_peer_meta: Option<Peer>,
+    /// the pairing topic and key before the wallet rotated its key
+    /// (kept until the next rotation, so that the messages in flight are still handled)
+    #[serde(default)]
+    pub previous_pairing_topic_symkey: Option<(Topic, Key)>,
     /// the one-time request ID
     pub session_proposal_topic: Topic,
     /// hex (without 0x) of ed 25519 keypair
@@ -100,6 +104,7 @@ impl SessionInfo {
             client_meta,
             pairing_topic_symkey: None,
             pairing_peer_meta: None,
+            previous_pairing_topic_symkey: None,
             session_proposal_topic,
             pairing_keypair,
             auth_jwt,
@@ -158,13 +163,193 @@ impl SessionInfo {
         self.namespaces = Some(info.namespaces);
     }
 
+    /// Derive the pairing topic and key from the wallet's new public key
+    /// and return the new topic (to subscribe) and the no longer used topic (to unsubscribe)
+    /// if the key was rotated
+    pub fn rekey(&mut self, responder_public_key: &str) -> Option<(Topic, Option<Topic>)> {
+        let (topic, key) = derive_symkey_topic(responder_public_key, &self.client_secret_key)?;
+        if matches!(&self.pairing_topic_symkey, Some((current, _)) if current == &topic) {
+            return None;
+        }
+        let current = self.pairing_topic_symkey.replace((topic.clone(), key));
+        let unused = std::mem::replace(&mut self.previous_pairing_topic_symkey, current);
+        if let Some(peer) = self.pairing_peer_meta.as_mut() {
+            peer.public_key = responder_public_key.to_owned();
+        }
+        Some((topic, unused.map(|(topic, _)| topic)))
+    }
+
+    /// the key of the current or the previous (before the key rotation) pairing topic
+    pub fn pairing_key(&self, topic: &Topic) -> Option<Key> {
+        [
+            &self.pairing_topic_symkey,
+            &self.previous_pairing_topic_symkey,
+        ]
+        .into_iter()
+        .flatten()
+        .find(|(t, _)| t == topic)
+        .map(|(_, key)| key.clone())
+    }
+
     // FIXME: 7 days
     pub fn session_extend(&mut self) {}
 
     pub fn session_delete(&mut self) {
         self.connected = false;
         self.pairing_topic_symkey = None;
+        self.previous_pairing_topic_symkey = None;
         self.pairing_peer_meta = None;
         self.namespaces = None;
     }
+
+    /// the session info without the keys and the auth token and with the topics
+    /// replaced by their hashes (e.g. to be attached to the bug reports)
+    pub fn redacted(&self) -> RedactedSessionInfo {
+        RedactedSessionInfo {
+            connected: self.connected,
+            required_namespaces: self.required_namespaces.clone(),
+            namespaces: self.namespaces.clone(),
+            relay_server: self.relay_server.clone(),
+            project_id: self.project_id.clone(),
+            client_meta: self.client_meta.clone(),
+            pairing_topic: self
+                .pairing_topic_symkey
+                .as_ref()
+                .map(|(topic, _)| hashed(topic)),
+            pairing_peer_meta: self.pairing_peer_meta.clone(),
+            previous_pairing_topic: self
+                .previous_pairing_topic_symkey
+                .as_ref()
+                .map(|(topic, _)| hashed(topic)),
+            session_proposal_topic: hashed(&self.session_proposal_topic),
+        }
+    }
+}
+
+/// the hash of the topic (the same as in the replay log)
+fn hashed(topic: &Topic) -> String {
+    crate::Topic::hash_of(topic.as_ref()).to_string()
+}
+
+/// The WalletConnect 2.0 session information without its secrets
+/// (it can't be restored: the keys and the auth token are left out)
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactedSessionInfo {
+    pub connected: bool,
+    pub required_namespaces: RequiredNamespaces,
+    pub namespaces: Option<Namespaces>,
+    pub relay_server: Url,
+    pub project_id: String,
+    pub client_meta: Peer,
+    /// the hash of the pairing topic
+    pub pairing_topic: Option<String>,
+    pub pairing_peer_meta: Option<Peer>,
+    /// the hash of the pairing topic before the wallet rotated its key
+    pub previous_pairing_topic: Option<String>,
+    /// the hash of the session proposal topic
+    pub session_proposal_topic: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wallet_public_key() -> String {
+        let secret = StaticSecret::new(rand::thread_rng());
+        hex::encode(PublicKey::from(&secret).as_bytes())
+    }
+
+    #[test]
+    fn test_rekey() {
+        let mut session = SessionInfo::new(
+            "wss://relay.walletconnect.com/".parse().unwrap(),
+            "project".to_owned(),
+            RequiredNamespaces::new(vec![], vec!["eip155:25".to_owned()], vec![]),
+            Metadata {
+                description: String::default(),
+                url: "https://example.com".to_owned(),
+                icons: vec![],
+                name: "test".to_owned(),
+            },
+        );
+        let first_key = wallet_public_key();
+        let (first, unused) = session.rekey(&first_key).expect("derived");
+        assert!(unused.is_none());
+        // the same key is not a rotation
+        assert!(session.rekey(&first_key).is_none());
+        assert!(session.rekey("invalid").is_none());
+
+        let (second, unused) = session.rekey(&wallet_public_key()).expect("rotated");
+        assert_ne!(first, second);
+        // the first topic is kept for the messages in flight
+        assert!(unused.is_none());
+        assert!(session.pairing_key(&first).is_some());
+        assert_eq!(
+            session
+                .pairing_key(&second)
+                .map(|key| key.as_ref().to_vec()),
+            session
+                .pairing_topic_symkey
+                .as_ref()
+                .map(|(_, key)| key.as_ref().to_vec())
+        );
+
+        let (third, unused) = session.rekey(&wallet_public_key()).expect("rotated");
+        assert_eq!(unused, Some(first.clone()));
+        assert!(session.pairing_key(&first).is_none());
+        assert!(session.pairing_key(&second).is_some());
+        assert!(session.pairing_key(&third).is_some());
+
+        session.session_delete();
+        assert!(session.pairing_key(&second).is_none());
+    }
+
+    #[test]
+    fn test_redacted() {
+        let mut session = SessionInfo::new(
+            "wss://relay.walletconnect.com/".parse().unwrap(),
+            "project".to_owned(),
+            RequiredNamespaces::new(vec![], vec!["eip155:25".to_owned()], vec![]),
+            Metadata {
+                description: String::default(),
+                url: "https://example.com".to_owned(),
+                icons: vec![],
+                name: "test".to_owned(),
+            },
+        );
+        let (pairing_topic, _) = session.rekey(&wallet_public_key()).expect("derived");
+        let redacted = session.redacted();
+        assert_eq!(redacted.pairing_topic, Some(hashed(&pairing_topic)));
+        assert_eq!(
+            redacted.session_proposal_topic,
+            session.redacted().session_proposal_topic
+        );
+
+        let json = serde_json::to_string(&redacted).unwrap();
+        // it can't be restored
+        assert!(serde_json::from_str::<SessionInfo>(&json).is_err());
+        let secrets = [
+            session
+                .session_proposal_symkey
+                .display()
+                .expose_secret()
+                .clone(),
+            session.client_secret_key.display().expose_secret().clone(),
+            session
+                .pairing_topic_symkey
+                .as_ref()
+                .unwrap()
+                .1
+                .display()
+                .expose_secret()
+                .clone(),
+            session.session_proposal_topic.to_string(),
+            pairing_topic.to_string(),
+            hex::encode(&session.pairing_keypair),
+        ];
+        for secret in secrets {
+            assert!(!json.contains(&secret), "{secret}");
+        }
+    }
 }
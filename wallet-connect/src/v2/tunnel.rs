@@ -0,0 +1,454 @@
+//! A loopback tunnel to the relay server: `relay_client` connects with its own TLS
+//! and WebSocket configuration, so the relay connections go through this tunnel
+//! (a local WebSocket server forwarding the messages) with the TLS options
+//! (`tls::set_tls_options`), the host overrides of the relay (`dns::set_host_overrides`)
+//! and the size limit of the received messages (`Client::set_max_message_size`);
+//! it pings the relay to measure the health of the connection (`Client::connection_status`)
+//! and counts the relayed messages (`usage::relay_usage`).
+//! A tunnel accepts one connection, of its random secret path (the project id and the auth
+//! token of the relay aren't usable by the other local processes), so a new tunnel is started
+//! for each connection
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures::{SinkExt, StreamExt};
+use relay_rpc::auth::{rand, rand::Rng};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::{
+    client_async_tls_with_config, connect_async_tls_with_config, Connector, MaybeTlsStream,
+    WebSocketStream,
+};
+use url::Url;
+
+use super::core::SharedContext;
+
+/// the size of the relay's JSON-RPC request around a relayed message (its topic, id, tag etc.),
+/// so that the messages within `max_message_size` are received
+const ENVELOPE_OVERHEAD: usize = 1024;
+/// how often the relay is pinged by the tunnel
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// the payload of the tunnel's pings (their pongs aren't forwarded to `relay_client`)
+const PING_PAYLOAD: &[u8] = b"relay-tunnel";
+/// how long a local connection has to complete its WebSocket handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The local server forwarding one relay connection (it's stopped when dropped)
+pub(crate) struct RelayTunnel {
+    /// the address of the local server with the secret path (ws://127.0.0.1:port/secret)
+    address: String,
+    handle: JoinHandle<()>,
+}
+
+impl RelayTunnel {
+    /// starts the tunnel to the relay server (with the message size limit of the context)
+    pub(crate) async fn start(relay_address: &str, context: SharedContext) -> eyre::Result<Self> {
+        let relay: Url = relay_address.parse()?;
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let secret = crate::hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let address = format!("ws://{}/{secret}", listener.local_addr()?);
+        let handle = tokio::spawn(async move {
+            if let Some((local, url)) = accept(listener, &relay, &secret).await {
+                forward(local, url, context).await;
+            }
+        });
+        Ok(Self { address, handle })
+    }
+
+    /// the address `relay_client` connects to instead of the relay server
+    pub(crate) fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl Drop for RelayTunnel {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// the relay url with the path (after the secret) and the query (the project id
+/// and the auth token) of the local request; none if it isn't of the secret path
+fn upstream_url(relay: &Url, secret: &str, request: &Request) -> Option<Url> {
+    let path = request
+        .uri()
+        .path()
+        .strip_prefix('/')?
+        .strip_prefix(secret)?;
+    if !path.is_empty() && !path.starts_with('/') {
+        return None;
+    }
+    let mut url = relay.clone();
+    url.set_path(if path.is_empty() { "/" } else { path });
+    url.set_query(request.uri().query());
+    Some(url)
+}
+
+/// the WebSocket limits of the relay connection: the larger messages (and frames)
+/// are rejected while they're received, before they're buffered and decoded
+fn upstream_config(max_message_size: usize) -> WebSocketConfig {
+    let max_size = max_message_size.saturating_add(ENVELOPE_OVERHEAD);
+    WebSocketConfig {
+        max_message_size: Some(max_size),
+        max_frame_size: Some(max_size),
+        ..Default::default()
+    }
+}
+
+/// connects to the relay server with the TLS options, the host overrides
+/// and the WebSocket limits
+async fn connect_upstream(
+    url: &Url,
+    config: WebSocketConfig,
+) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, WsError> {
+    let connector = crate::tls::tls_config().map(Connector::Rustls);
+    let (stream, _response) = match crate::dns::resolve_override(url) {
+        Some(socket_addrs) => {
+            let tcp = TcpStream::connect(&socket_addrs[..]).await?;
+            client_async_tls_with_config(url.as_str(), tcp, Some(config), connector).await?
+        }
+        None => connect_async_tls_with_config(url.as_str(), Some(config), connector).await?,
+    };
+    Ok(stream)
+}
+
+/// The handshake callback keeping the upstream url of the local request
+/// (the requests of other paths than the secret one are refused)
+struct UpstreamUrl<'a> {
+    relay: &'a Url,
+    secret: &'a str,
+    url: &'a mut Option<Url>,
+}
+
+impl Callback for UpstreamUrl<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        *self.url = upstream_url(self.relay, self.secret, request);
+        if self.url.is_none() {
+            let mut forbidden = ErrorResponse::new(None);
+            *forbidden.status_mut() = StatusCode::FORBIDDEN;
+            return Err(forbidden);
+        }
+        Ok(response)
+    }
+}
+
+/// accepts the first local connection of the secret path; the listener is dropped then,
+/// so that no other connection is accepted
+async fn accept(
+    listener: TcpListener,
+    relay: &Url,
+    secret: &str,
+) -> Option<(WebSocketStream<TcpStream>, Url)> {
+    loop {
+        let (stream, _) = listener.accept().await.ok()?;
+        let mut url = None;
+        let callback = UpstreamUrl {
+            relay,
+            secret,
+            url: &mut url,
+        };
+        let handshake = tokio_tungstenite::accept_hdr_async(stream, callback);
+        let accepted = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await;
+        if let (Ok(Ok(local)), Some(url)) = (accepted, url) {
+            return Some((local, url));
+        }
+    }
+}
+
+/// The open relay connection in the statistics of the context (until it's dropped)
+struct OpenConnection<'a>(&'a SharedContext);
+
+impl<'a> OpenConnection<'a> {
+    fn new(context: &'a SharedContext) -> Self {
+        context.relay_connects.fetch_add(1, Ordering::Relaxed);
+        context.relay_connections.fetch_add(1, Ordering::Relaxed);
+        Self(context)
+    }
+}
+
+impl Drop for OpenConnection<'_> {
+    fn drop(&mut self) {
+        self.0.relay_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// forwards the messages of the connection of `relay_client` to the relay server
+/// (the connection is closed if the relay sends a message exceeding the size limit)
+async fn forward(local: WebSocketStream<TcpStream>, url: Url, context: SharedContext) {
+    let config = upstream_config(context.max_message_size.load(Ordering::Relaxed));
+    let Ok(upstream) = connect_upstream(&url, config).await else {
+        // the local connection is dropped, so `relay_client` sees the failure
+        return;
+    };
+    let _open = OpenConnection::new(&context);
+    let (mut local_tx, mut local_rx) = local.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+    // when the unanswered ping of the tunnel was sent
+    let ping_sent: Mutex<Option<Instant>> = Mutex::new(None);
+    let outgoing = async {
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        loop {
+            let message = tokio::select! {
+                received = local_rx.next() => match received {
+                    Some(Ok(message)) => {
+                        if message.is_text() || message.is_binary() {
+                            crate::usage::record_relay_sent(message.len());
+                        }
+                        message
+                    }
+                    _ => break,
+                },
+                _ = ping.tick() => {
+                    *ping_sent.lock().expect("ping lock") = Some(Instant::now());
+                    Message::Ping(PING_PAYLOAD.to_vec())
+                }
+            };
+            if upstream_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = upstream_tx.close().await;
+    };
+    let incoming = async {
+        while let Some(received) = upstream_rx.next().await {
+            let message = match received {
+                Ok(message) => message,
+                Err(WsError::Capacity(_)) => {
+                    context.oversized_messages.fetch_add(1, Ordering::Relaxed);
+                    break;
+                }
+                Err(_) => break,
+            };
+            if matches!(&message, Message::Pong(payload) if payload == PING_PAYLOAD) {
+                let sent = ping_sent.lock().expect("ping lock").take();
+                if let Some(sent) = sent {
+                    let rtt = sent.elapsed().as_micros().max(1) as u64;
+                    context.relay_ping_rtt_us.store(rtt, Ordering::Relaxed);
+                }
+                continue;
+            }
+            if message.is_text() || message.is_binary() {
+                crate::usage::record_relay_received(message.len());
+            }
+            if local_tx.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = local_tx.close().await;
+    };
+    tokio::select! {
+        _ = outgoing => {}
+        _ = incoming => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v2::core::Context;
+    use crate::v2::{Metadata, RequiredNamespaces, SessionInfo};
+
+    fn test_context() -> SharedContext {
+        std::sync::Arc::new(Context::new(SessionInfo::new(
+            "wss://relay.walletconnect.com/".parse().unwrap(),
+            "project".to_owned(),
+            RequiredNamespaces::new(vec![], vec!["eip155:25".to_owned()], vec![]),
+            Metadata {
+                description: String::default(),
+                url: "https://example.com".to_owned(),
+                icons: vec![],
+                name: "test".to_owned(),
+            },
+        )))
+    }
+
+    #[test]
+    fn test_upstream_url() {
+        let relay: Url = "wss://relay.walletconnect.com".parse().unwrap();
+        let url = |local: &str| {
+            let request = Request::get(local).body(()).unwrap();
+            upstream_url(&relay, "s3cret", &request).map(|url| url.to_string())
+        };
+        for local in [
+            "ws://127.0.0.1:4000/s3cret?projectId=abc&auth=token",
+            "ws://127.0.0.1:4000/s3cret/?projectId=abc&auth=token",
+        ] {
+            assert_eq!(
+                url(local).as_deref(),
+                Some("wss://relay.walletconnect.com/?projectId=abc&auth=token")
+            );
+        }
+        assert_eq!(url("ws://127.0.0.1:4000/?projectId=abc"), None);
+        assert_eq!(url("ws://127.0.0.1:4000/s3crets?projectId=abc"), None);
+        assert_eq!(url("ws://127.0.0.1:4000/other/s3cret"), None);
+    }
+
+    #[tokio::test]
+    // the host overrides are global, so the tests setting them are serialized
+    #[allow(clippy::await_holding_lock)]
+    async fn test_tunnel_host_override() {
+        // a relay echoing the messages with the path and query of its request
+        let relay = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = relay.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = relay.accept().await.unwrap();
+            let mut path = None;
+            let callback = UpstreamUrl {
+                relay: &"ws://relay.tunnel.test".parse().unwrap(),
+                secret: "",
+                url: &mut path,
+            };
+            let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback)
+                .await
+                .unwrap();
+            let query = path.unwrap().query().unwrap_or_default().to_owned();
+            while let Some(Ok(message)) = ws.next().await {
+                // the pings of the tunnel are answered by tungstenite
+                if !message.is_text() {
+                    continue;
+                }
+                let reply = format!("{query} {}", message.into_text().unwrap());
+                ws.send(reply.into()).await.unwrap();
+            }
+        });
+        let _lock = crate::dns::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut overrides = std::collections::BTreeMap::new();
+        overrides.insert(
+            "relay.tunnel.test".to_owned(),
+            vec!["127.0.0.1".parse().unwrap()],
+        );
+        crate::dns::set_host_overrides(overrides);
+
+        let tunnel = RelayTunnel::start(&format!("ws://relay.tunnel.test:{port}"), test_context())
+            .await
+            .unwrap();
+        let (mut client, _) =
+            tokio_tungstenite::connect_async(format!("{}/?projectId=abc", tunnel.address()))
+                .await
+                .unwrap();
+        client.send("ping".into()).await.unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply.into_text().unwrap(), "projectId=abc ping");
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_single_connection() {
+        // a relay echoing the messages
+        let relay = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = relay.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = relay.accept().await {
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(message)) = ws.next().await {
+                        if message.is_text() {
+                            ws.send(message).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        let tunnel = RelayTunnel::start(&format!("ws://127.0.0.1:{port}"), test_context())
+            .await
+            .unwrap();
+        let address: Url = tunnel.address().parse().unwrap();
+        assert_eq!(address.path().len(), 1 + 64);
+        // the other paths are refused
+        let root = format!("ws://127.0.0.1:{}/?projectId=abc", address.port().unwrap());
+        assert!(tokio_tungstenite::connect_async(root).await.is_err());
+
+        let (mut client, _) = tokio_tungstenite::connect_async(tunnel.address())
+            .await
+            .unwrap();
+        client.send("hello".into()).await.unwrap();
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply.into_text().unwrap(), "hello");
+        // no other connection is accepted
+        assert!(tokio_tungstenite::connect_async(tunnel.address())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_message_size_limit() {
+        // a relay sending a message larger than the limit
+        let relay = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = relay.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = relay.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let _ = ws.send("x".repeat(4096).into()).await;
+            let _ = ws.next().await;
+        });
+        let context = test_context();
+        context.max_message_size.store(100, Ordering::Relaxed);
+        let tunnel = RelayTunnel::start(&format!("ws://127.0.0.1:{port}"), context.clone())
+            .await
+            .unwrap();
+        let (mut client, _) = tokio_tungstenite::connect_async(tunnel.address())
+            .await
+            .unwrap();
+        // the connection is closed instead of receiving the message
+        while let Some(Ok(message)) = client.next().await {
+            assert!(message.is_close());
+        }
+        assert_eq!(context.oversized_messages.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_connection_status() {
+        // a relay echoing the messages (and answering the pings)
+        let relay = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = relay.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = relay.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(message)) = ws.next().await {
+                if message.is_text() {
+                    ws.send(message).await.unwrap();
+                }
+            }
+        });
+        let context = test_context();
+        let tunnel = RelayTunnel::start(&format!("ws://127.0.0.1:{port}"), context.clone())
+            .await
+            .unwrap();
+        let (mut client, _) = tokio_tungstenite::connect_async(tunnel.address())
+            .await
+            .unwrap();
+        client.send("hello".into()).await.unwrap();
+        // the pong of the tunnel's ping isn't forwarded
+        let reply = client.next().await.unwrap().unwrap();
+        assert_eq!(reply.into_text().unwrap(), "hello");
+        let usage = crate::usage::relay_usage();
+        assert!(usage.messages_sent >= 1 && usage.bytes_sent >= 5);
+        assert!(usage.messages_received >= 1 && usage.bytes_received >= 5);
+        assert_eq!(context.relay_connections.load(Ordering::Relaxed), 1);
+        assert_eq!(context.relay_connects.load(Ordering::Relaxed), 1);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while context.relay_ping_rtt_us.load(Ordering::Relaxed) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        client.close(None).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while context.relay_connections.load(Ordering::Relaxed) > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}